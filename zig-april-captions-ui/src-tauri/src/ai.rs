@@ -0,0 +1,848 @@
+// Gemini client and context assembly
+//
+// AI requests are made from the backend with the key in `AISettings`, so the
+// key never has to reach the webview. Context (meeting notes, knowledge,
+// transcript, compression snapshots, earlier answers) is put together here
+// from the same stores the rest of the app writes to. Knowledge is the notes
+// most similar to the question by their embeddings, plus the nominated ones.
+//
+// The `*_stream` commands return a request id at once and deliver the answer
+// as `ai-stream` events ({requestId, delta, done}); `cancel_ai_request` aborts
+// one mid-generation. Prompts the frontend still builds itself are sent
+// through `gemini_request`, which adds the key on the way out.
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{debug, warn};
+
+use crate::integrations::{self, SessionSummary};
+use crate::{tts, vector_index, AISettings, AppState, ContextSnapshot, KnowledgeEntry};
+
+/// Answers are phrased for the user to say out loud
+pub const ANSWER_SYSTEM_PROMPT: &str = "You are a personal meeting/interview assistant. Your job is to help the user speak confidently. \
+    IMPORTANT: Generate responses in FIRST PERSON that the user can READ ALOUD or say directly. \
+    Example: If asked 'introduce yourself', respond with 'I'm a fullstack developer at...' NOT 'You are a developer...'. \
+    Use the knowledge base context to personalize responses with the user's actual background, skills, and experience. \
+    Keep responses concise and natural-sounding (2-4 sentences). \
+    Write as if you ARE the user speaking to others in a meeting or interview.";
+
+const TRANSLATE_SYSTEM_PROMPT: &str = "You translate live meeting captions. \
+    Reply with the translation only, keeping names and technical terms as they are.";
+
+/// Transcript lines included in an answer's context
+const TRANSCRIPT_CONTEXT_LINES: usize = 20;
+
+/// Knowledge notes retrieved for a question by default
+const KNOWLEDGE_TOP_K: usize = 5;
+
+/// Retrieved notes less similar to the question than this are left out
+const MIN_KNOWLEDGE_SIMILARITY: f32 = 0.3;
+
+const COMPRESS_SYSTEM_PROMPT: &str = "You compress a conversation between a user and their meeting assistant. \
+    Write a dense summary that keeps every fact, decision, name, number and open question \
+    needed to continue the conversation. If an earlier summary is given, fold it in. \
+    Reply with the summary only.";
+
+/// Newest entries left out of a compression so the latest exchange stays verbatim
+const KEEP_RECENT_ENTRIES: usize = 4;
+
+/// Managed state: streamed generations still running, by request id
+#[derive(Default)]
+pub struct AiRequests {
+    running: Mutex<HashMap<String, JoinHandle<()>>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StreamEvent<'a> {
+    request_id: &'a str,
+    delta: &'a str,
+    done: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    cancelled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Which sources go into an answer's context
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextOptions {
+    #[serde(default = "crate::default_true")]
+    pub knowledge: bool,
+    #[serde(default = "crate::default_true")]
+    pub transcript: bool,
+    #[serde(default = "crate::default_true")]
+    pub snapshots: bool,
+    #[serde(default = "crate::default_true")]
+    pub semantic: bool, // Rank history by similarity to the question instead of recency
+    #[serde(default)]
+    pub history_limit: Option<usize>,
+    #[serde(default)]
+    pub knowledge_limit: Option<usize>, // Notes retrieved by similarity; 0 for nominated notes only
+}
+
+impl Default for ContextOptions {
+    fn default() -> Self {
+        Self {
+            knowledge: true,
+            transcript: true,
+            snapshots: true,
+            semantic: true,
+            history_limit: None,
+            knowledge_limit: None,
+        }
+    }
+}
+
+/// Context sections, each empty when there was nothing to include
+#[derive(Debug, Default)]
+pub struct AssembledContext {
+    pub meeting: String,
+    pub knowledge: String,
+    pub snapshot: String,
+    pub transcript: String,
+    pub pinned: String,
+    pub history: String,
+}
+
+impl AssembledContext {
+    pub fn text(&self) -> String {
+        format!(
+            "{}{}{}{}{}{}",
+            self.meeting, self.knowledge, self.snapshot, self.transcript, self.pinned, self.history
+        )
+    }
+}
+
+/// Configured AI settings, or an error when no key is set
+pub fn settings(state: &AppState) -> Result<AISettings, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings
+        .ai
+        .clone()
+        .filter(|ai| !ai.api_key.is_empty())
+        .ok_or_else(|| "No AI API key configured".to_string())
+}
+
+/// A titled context section, or nothing when there are no items
+fn section_text(title: &str, items: &[String], separator: &str) -> String {
+    if items.is_empty() {
+        String::new()
+    } else {
+        format!("=== {} ===\n{}\n", title, items.join(separator))
+    }
+}
+
+/// A knowledge note retrieved for a question, with its similarity to it
+#[derive(Debug, Clone, Serialize)]
+pub struct RetrievedKnowledge {
+    #[serde(flatten)]
+    pub entry: KnowledgeEntry,
+    pub score: f32,
+}
+
+/// The `k` notes most similar to `query`, best first. Only notes the
+/// embedding worker has indexed can be found.
+async fn retrieve(state: &AppState, query: &str, api_key: &str, k: usize) -> Result<Vec<RetrievedKnowledge>, String> {
+    let query_embedding = crate::generate_embedding(query, api_key).await?;
+    let mut scored = {
+        let conn = state.db()?;
+        vector_index::nearest(&conn, vector_index::Indexed::Knowledge, &query_embedding, k)
+            .map_err(|e| format!("Vector search failed: {}", e))?
+    };
+    scored.retain(|(_, score)| *score >= MIN_KNOWLEDGE_SIMILARITY);
+
+    let knowledge = state.knowledge.read()?;
+    Ok(scored
+        .into_iter()
+        .filter_map(|(id, score)| {
+            let entry = knowledge.iter().find(|e| e.id == id)?;
+            Some(RetrievedKnowledge { entry: entry.clone(), score })
+        })
+        .take(k)
+        .collect())
+}
+
+/// Knowledge for a question: the notes retrieved for it, then the nominated
+/// ones not among them. Without a query or key only nominated notes are used.
+async fn knowledge_items(
+    state: &AppState,
+    query: Option<&str>,
+    api_key: Option<&str>,
+    k: usize,
+) -> Result<Vec<String>, String> {
+    let retrieved = match (query, api_key) {
+        (Some(query), Some(key)) if k > 0 => retrieve(state, query, key, k).await.unwrap_or_else(|e| {
+            warn!("Knowledge retrieval failed, using nominated notes only: {}", e);
+            Vec::new()
+        }),
+        _ => Vec::new(),
+    };
+    let knowledge = state.knowledge.read()?;
+    let nominated = knowledge
+        .iter()
+        .filter(|e| e.nominated && !retrieved.iter().any(|r| r.entry.id == e.id));
+    Ok(retrieved
+        .iter()
+        .map(|r| &r.entry)
+        .chain(nominated)
+        .map(|e| format!("- {}", e.content))
+        .collect())
+}
+
+/// The latest compression snapshot of the current session
+fn latest_snapshot(state: &AppState, session_id: Option<&str>) -> Result<Option<String>, String> {
+    let snapshots = state.snapshots.read()?;
+    Ok(snapshots
+        .iter()
+        .filter(|s| session_id.is_none() || s.session_id.as_deref() == session_id)
+        .max_by_key(|s| s.created_at)
+        .map(|s| s.summary.clone()))
+}
+
+/// Entries the user pinned; included whatever the options and compression say
+fn pinned_items(state: &AppState) -> Result<Vec<String>, String> {
+    let conn = state.db()?;
+    let entries = crate::database::pinned_entries(&conn).map_err(|e| e.to_string())?;
+    Ok(entries.iter().map(|e| format!("[{}]: {}", e.entry_type, e.content)).collect())
+}
+
+/// Earlier answers, by similarity to `query` when it and a key are given and
+/// semantic history is on, else the most recent; returns the section title too
+async fn history_items(
+    state: &AppState,
+    query: Option<&str>,
+    api_key: Option<&str>,
+    semantic: bool,
+    limit: usize,
+) -> Result<(&'static str, Vec<String>), String> {
+    if let (true, Some(query), Some(key)) = (semantic, query, api_key) {
+        match crate::get_semantic_history_context(state, query, key, limit).await {
+            Ok(items) => return Ok(("Relevant Context (Semantic)", items)),
+            Err(e) => warn!("Semantic search failed, falling back to recent: {}", e),
+        }
+    }
+    Ok(("Recent AI Responses", crate::get_recent_history_context(state, limit)?))
+}
+
+const TRANSCRIPT_TITLE: &str = "Current Conversation Transcript (Recent Lines)";
+const SNAPSHOT_TITLE: &str = "Earlier In This Conversation (Summary)";
+
+/// Collect context for a question; semantic history and knowledge retrieval
+/// need `query` and `api_key`
+pub async fn build_context(
+    state: &AppState,
+    query: Option<&str>,
+    api_key: Option<&str>,
+    opts: &ContextOptions,
+) -> Result<AssembledContext, String> {
+    // Read everything behind a lock before the first await
+    let meeting = {
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        let calendar = state.meeting_context.lock().map_err(|e| e.to_string())?;
+        let template = crate::templates::current(state).and_then(|t| t.meeting_context);
+        let parts: Vec<&str> = [
+            settings.ai.as_ref().and_then(|ai| ai.meeting_context.as_deref()),
+            template.as_deref(),
+            calendar.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|part| !part.trim().is_empty())
+        .collect();
+        if parts.is_empty() {
+            String::new()
+        } else {
+            format!("=== Meeting Context ===\n{}\n", parts.join("\n\n"))
+        }
+    };
+    let mut context = AssembledContext {
+        meeting,
+        ..Default::default()
+    };
+    if opts.transcript {
+        let lines = state.transcript_lines.lock().map_err(|e| e.to_string())?.texts();
+        let recent = &lines[lines.len().saturating_sub(TRANSCRIPT_CONTEXT_LINES)..];
+        context.transcript = section_text(TRANSCRIPT_TITLE, recent, "\n");
+    }
+    if opts.knowledge {
+        let k = opts.knowledge_limit.unwrap_or(KNOWLEDGE_TOP_K);
+        let knowledge = knowledge_items(state, query, api_key, k).await?;
+        context.knowledge = section_text("User's Knowledge Base", &knowledge, "\n");
+    }
+    if opts.snapshots {
+        let snapshot = latest_snapshot(state, crate::current_session_id(state).as_deref())?;
+        context.snapshot = section_text(SNAPSHOT_TITLE, &snapshot.into_iter().collect::<Vec<_>>(), "");
+    }
+    context.pinned = section_text("Pinned", &pinned_items(state)?, "\n\n");
+
+    let history_limit = opts.history_limit.unwrap_or(10);
+    let (title, history) = history_items(state, query, api_key, opts.semantic, history_limit).await?;
+    context.history = section_text(title, &history, "\n\n");
+
+    Ok(context)
+}
+
+/// Context budget used by `build_ai_context` when none is given
+const DEFAULT_CONTEXT_BUDGET: i64 = 8000;
+
+/// One source of the planned context, its items most important first
+struct Section {
+    name: &'static str,
+    title: &'static str,
+    separator: &'static str,
+    items: Vec<String>,
+    newest_first: bool, // Printed in reverse so the text reads oldest first
+}
+
+/// What the planner took from one source
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SectionPlan {
+    pub name: &'static str,
+    pub included: usize,
+    pub excluded: usize,
+    pub tokens: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextPlan {
+    pub prompt: String,
+    pub max_tokens: i64,
+    pub used_tokens: i64,
+    pub sections: Vec<SectionPlan>,
+}
+
+/// Fill `budget` with sections in priority order. Within a section items are
+/// taken in order until the next one doesn't fit, so a section is never
+/// left with gaps; lower priority sections still get whatever room is left.
+fn plan_sections(sections: Vec<Section>, budget: i64) -> (String, Vec<SectionPlan>) {
+    let mut remaining = budget;
+    let mut text = String::new();
+    let mut plans = Vec::new();
+    for section in sections {
+        let header = estimate_tokens(&format!("=== {} ===\n\n", section.title));
+        let separator = estimate_tokens(section.separator);
+        let mut taken: Vec<String> = Vec::new();
+        let mut tokens = header;
+        for item in &section.items {
+            let cost = estimate_tokens(item) + if taken.is_empty() { 0 } else { separator };
+            if tokens + cost > remaining {
+                break;
+            }
+            tokens += cost;
+            taken.push(item.clone());
+        }
+        if taken.is_empty() {
+            tokens = 0;
+        }
+        if section.newest_first {
+            taken.reverse();
+        }
+        text.push_str(&section_text(section.title, &taken, section.separator));
+        remaining -= tokens;
+        plans.push(SectionPlan {
+            name: section.name,
+            included: taken.len(),
+            excluded: section.items.len() - taken.len(),
+            tokens,
+        });
+    }
+    (text, plans)
+}
+
+/// Assemble an answer prompt within `max_tokens` (estimated): the latest
+/// snapshot, then pinned entries, nominated knowledge, relevant history and as
+/// much of the recent transcript as still fits. Returns the prompt and what
+/// each source contributed.
+#[tauri::command]
+pub async fn build_ai_context(
+    state: tauri::State<'_, Arc<AppState>>,
+    question: String,
+    max_tokens: Option<i64>,
+) -> Result<ContextPlan, String> {
+    let max_tokens = max_tokens.unwrap_or(DEFAULT_CONTEXT_BUDGET).max(0);
+    let api_key = settings(&state).ok().map(|ai| ai.api_key);
+
+    let snapshot = latest_snapshot(&state, crate::current_session_id(&state).as_deref())?;
+    let pinned = pinned_items(&state)?;
+    let knowledge = knowledge_items(&state, Some(&question), api_key.as_deref(), KNOWLEDGE_TOP_K).await?;
+    let mut transcript = state.transcript_lines.lock().map_err(|e| e.to_string())?.texts();
+    transcript.reverse();
+    let (history_title, history) = history_items(&state, Some(&question), api_key.as_deref(), true, 10).await?;
+
+    let section = |name, title, separator, items| Section { name, title, separator, items, newest_first: false };
+    let sections = vec![
+        section("snapshot", SNAPSHOT_TITLE, "", snapshot.into_iter().collect()),
+        section("pinned", "Pinned", "\n\n", pinned),
+        section("knowledge", "User's Knowledge Base", "\n", knowledge),
+        section("history", history_title, "\n\n", history),
+        Section { newest_first: true, ..section("transcript", TRANSCRIPT_TITLE, "\n", transcript) },
+    ];
+    let budget = max_tokens - estimate_tokens(&answer_prompt("\n", &question));
+    let (context, sections) = plan_sections(sections, budget);
+    let prompt = answer_prompt(&context, &question);
+    Ok(ContextPlan { used_tokens: estimate_tokens(&prompt), prompt, max_tokens, sections })
+}
+
+/// Run a single (non-streaming) Gemini generation
+pub async fn generate_content(
+    ai: &AISettings,
+    system_instruction: &str,
+    prompt: &str,
+    json_output: bool,
+) -> Result<String, String> {
+    if ai.api_key.is_empty() {
+        return Err("No AI API key configured".to_string());
+    }
+    let url = format!(
+        "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent?key={}",
+        ai.model, ai.api_key
+    );
+
+    let mut generation_config = serde_json::json!({ "temperature": 0.3 });
+    if json_output {
+        generation_config["responseMimeType"] = serde_json::json!("application/json");
+    }
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post(&url)
+        .json(&serde_json::json!({
+            "system_instruction": {
+                "parts": [{"text": system_instruction}]
+            },
+            "contents": [{
+                "parts": [{"text": prompt}]
+            }],
+            "generationConfig": generation_config
+        }))
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("API error: {}", response.status()));
+    }
+
+    let data: serde_json::Value = response.json().await.map_err(|e| format!("Parse failed: {}", e))?;
+    data["candidates"][0]["content"]["parts"]
+        .as_array()
+        .map(|parts| parts.iter().filter_map(|p| p["text"].as_str()).collect::<String>())
+        .filter(|text| !text.is_empty())
+        .ok_or_else(|| "Empty response from AI".to_string())
+}
+
+/// Text parts of one Gemini response chunk
+fn response_text(data: &serde_json::Value) -> String {
+    data["candidates"][0]["content"]["parts"]
+        .as_array()
+        .map(|parts| parts.iter().filter_map(|p| p["text"].as_str()).collect())
+        .unwrap_or_default()
+}
+
+/// Run a streamed Gemini generation, handing each text delta to `on_delta`
+pub async fn stream_content(
+    ai: &AISettings,
+    system_instruction: &str,
+    prompt: &str,
+    mut on_delta: impl FnMut(&str),
+) -> Result<String, String> {
+    let url = format!(
+        "https://generativelanguage.googleapis.com/v1beta/models/{}:streamGenerateContent?alt=sse&key={}",
+        ai.model, ai.api_key
+    );
+    let mut response = reqwest::Client::new()
+        .post(&url)
+        .json(&serde_json::json!({
+            "system_instruction": { "parts": [{"text": system_instruction}] },
+            "contents": [{ "parts": [{"text": prompt}] }],
+            "generationConfig": { "temperature": 0.7 }
+        }))
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!("API error {}: {}", status, error_text));
+    }
+
+    // SSE: one "data: {json}" line per event; chunks may split lines
+    let mut pending: Vec<u8> = Vec::new();
+    let mut full_text = String::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| format!("Stream failed: {}", e))? {
+        pending.extend_from_slice(&chunk);
+        while let Some(newline) = pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=newline).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(json) = line.trim().strip_prefix("data:") else {
+                continue;
+            };
+            match serde_json::from_str::<serde_json::Value>(json.trim()) {
+                Ok(data) => {
+                    let delta = response_text(&data);
+                    if !delta.is_empty() {
+                        full_text.push_str(&delta);
+                        on_delta(&delta);
+                    }
+                }
+                Err(e) => debug!("Skipping unparseable stream event: {}", e),
+            }
+        }
+    }
+    Ok(full_text)
+}
+
+/// Stream a generation as `ai-stream` events; returns the request id.
+/// `on_complete` gets the full text of a successful one.
+fn start_stream(
+    app_handle: &AppHandle,
+    ai: AISettings,
+    system_instruction: &'static str,
+    prompt: String,
+    on_complete: Option<fn(&AppHandle, &str)>,
+) -> String {
+    let request_id = uuid::Uuid::new_v4().to_string();
+    let requests = app_handle.state::<AiRequests>();
+    // Held across the spawn so the task can't finish and deregister first
+    let mut running = requests.running.lock().unwrap_or_else(|e| e.into_inner());
+
+    let (app_handle, id) = (app_handle.clone(), request_id.clone());
+    let task = tauri::async_runtime::spawn(async move {
+        let emit = |delta: &str, done: bool, error: Option<String>| {
+            let event = StreamEvent { request_id: &id, delta, done, cancelled: false, error };
+            let _ = app_handle.emit("ai-stream", event);
+        };
+        let result = stream_content(&ai, system_instruction, &prompt, |delta| emit(delta, false, None)).await;
+        match &result {
+            Ok(text) => {
+                if let Some(on_complete) = on_complete {
+                    on_complete(&app_handle, text);
+                }
+            }
+            Err(e) => warn!("AI stream {} failed: {}", id, e),
+        }
+        emit("", true, result.err());
+        if let Ok(mut running) = app_handle.state::<AiRequests>().running.lock() {
+            running.remove(&id);
+        }
+    });
+    running.insert(request_id.clone(), task);
+    request_id
+}
+
+/// Abort a streamed generation; false when it already finished
+#[tauri::command]
+pub async fn cancel_ai_request(
+    app_handle: AppHandle,
+    requests: tauri::State<'_, AiRequests>,
+    id: String,
+) -> Result<bool, String> {
+    let task = requests.running.lock().map_err(|e| e.to_string())?.remove(&id);
+    let Some(task) = task else {
+        return Ok(false);
+    };
+    task.abort();
+    let event = StreamEvent { request_id: &id, delta: "", done: true, cancelled: true, error: None };
+    let _ = app_handle.emit("ai-stream", event);
+    Ok(true)
+}
+
+fn answer_prompt(context: &str, question: &str) -> String {
+    if context.is_empty() {
+        question.to_string()
+    } else {
+        format!("{}\n\nUser question: {}", context, question)
+    }
+}
+
+/// Rough token estimate (~4 characters per token), used before asking the API
+fn estimate_tokens(text: &str) -> i64 {
+    (text.chars().count() as i64 + 3) / 4
+}
+
+/// Exact token count from the model, falling back to the estimate
+async fn count_tokens(ai: &AISettings, text: &str) -> i64 {
+    let url = format!(
+        "https://generativelanguage.googleapis.com/v1beta/models/{}:countTokens?key={}",
+        ai.model, ai.api_key
+    );
+    let response = reqwest::Client::new()
+        .post(&url)
+        .json(&serde_json::json!({ "contents": [{ "parts": [{"text": text}] }] }))
+        .send()
+        .await;
+    let total = match response {
+        Ok(response) if response.status().is_success() => {
+            response.json::<serde_json::Value>().await.ok().and_then(|data| data["totalTokens"].as_i64())
+        }
+        _ => None,
+    };
+    total.unwrap_or_else(|| estimate_tokens(text))
+}
+
+/// Fold chat entries newer than the last snapshot into a new snapshot once
+/// they exceed `threshold_tokens`. With `prune`, the summarized entries are
+/// marked compressed and no longer fed back as history. Pinned entries are
+/// left alone. Returns None when there is not enough to compress yet.
+#[tauri::command]
+pub async fn compress_context(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    threshold_tokens: Option<i64>,
+    prune: Option<bool>,
+) -> Result<Option<ContextSnapshot>, String> {
+    let ai = settings(&state)?;
+    let session_id = crate::current_session_id(&state);
+    let previous: Option<ContextSnapshot> = state
+        .snapshots
+        .read()?
+        .iter()
+        .filter(|s| s.session_id == session_id)
+        .max_by_key(|s| s.covered_until)
+        .cloned();
+    let covered_until = previous.as_ref().map(|s| s.covered_until).unwrap_or(i64::MIN);
+
+    let entries: Vec<(String, i64, String, String)> = {
+        let conn = state.db()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, timestamp, entry_type, content FROM chat_entries
+                 WHERE timestamp > ?1 AND compressed = 0 AND pinned = 0 AND session_id IS ?2
+                 ORDER BY timestamp ASC",
+            )
+            .map_err(|e| format!("Prepare failed: {}", e))?;
+        let rows = stmt
+            .query_map(params![covered_until, session_id], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .map_err(|e| format!("Query failed: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        rows
+    };
+    if entries.len() <= KEEP_RECENT_ENTRIES {
+        return Ok(None);
+    }
+    let to_compress = &entries[..entries.len() - KEEP_RECENT_ENTRIES];
+    let transcript = to_compress
+        .iter()
+        .map(|(_, _, entry_type, content)| format!("[{}]: {}", entry_type, content))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let threshold = threshold_tokens.unwrap_or(4000);
+    if estimate_tokens(&transcript) < threshold {
+        return Ok(None);
+    }
+
+    let prompt = match &previous {
+        Some(previous) => format!("Earlier summary:\n{}\n\nConversation since:\n{}", previous.summary, transcript),
+        None => format!("Conversation:\n{}", transcript),
+    };
+    let summary = generate_content(&ai, COMPRESS_SYSTEM_PROMPT, &prompt, false).await?;
+    let original_token_count = count_tokens(&ai, &prompt).await;
+    let compressed_token_count = count_tokens(&ai, &summary).await;
+
+    let last = to_compress.last().map(|(_, timestamp, _, _)| *timestamp).unwrap_or_default();
+    let snapshot = crate::store_snapshot(
+        &app_handle,
+        ContextSnapshot {
+            id: uuid::Uuid::new_v4().to_string(),
+            created_at: crate::now_millis(),
+            summary,
+            covered_until: last,
+            original_token_count,
+            compressed_token_count,
+            session_id,
+        },
+    )?;
+
+    if prune.unwrap_or(false) {
+        let mut conn = state.db()?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        for (id, _, _, _) in to_compress {
+            tx.execute("UPDATE chat_entries SET compressed = 1 WHERE id = ?1", params![id])
+                .map_err(|e| e.to_string())?;
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+    }
+    debug!(
+        "Compressed {} entries: {} -> {} tokens",
+        to_compress.len(),
+        snapshot.original_token_count,
+        snapshot.compressed_token_count
+    );
+    Ok(Some(snapshot))
+}
+
+/// Summarize a stored session, or the live transcript when none is given
+#[tauri::command]
+pub async fn ai_summarize(app_handle: AppHandle, session_id: Option<String>) -> Result<SessionSummary, String> {
+    let text = match session_id {
+        Some(id) => integrations::session_text(&app_handle, &id)?,
+        None => {
+            let state = app_handle.state::<Arc<AppState>>();
+            let lines = state.transcript_lines.lock().map_err(|e| e.to_string())?.texts();
+            lines.join("\n")
+        }
+    };
+    if text.trim().is_empty() {
+        return Err("Nothing to summarize yet".to_string());
+    }
+    integrations::summarize(&app_handle, &text).await
+}
+
+/// The knowledge notes an answer to `query` would draw on, most relevant first
+#[tauri::command]
+pub async fn retrieve_knowledge(
+    state: tauri::State<'_, Arc<AppState>>,
+    query: String,
+    k: Option<usize>,
+) -> Result<Vec<RetrievedKnowledge>, String> {
+    let ai = settings(&state)?;
+    retrieve(&state, &query, &ai.api_key, k.unwrap_or(KNOWLEDGE_TOP_K)).await
+}
+
+/// Answer a question with context assembled from the local stores
+#[tauri::command]
+pub async fn ai_answer(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    question: String,
+    context_opts: Option<ContextOptions>,
+) -> Result<String, String> {
+    let ai = settings(&state)?;
+    let opts = context_opts.unwrap_or_default();
+    let context = build_context(&state, Some(&question), Some(&ai.api_key), &opts).await?.text();
+    let answer = generate_content(&ai, ANSWER_SYSTEM_PROMPT, &answer_prompt(&context, &question), false).await?;
+    tts::speak_answer_if_enabled(&app_handle, &answer);
+    Ok(answer)
+}
+
+/// Streaming `ai_answer`: returns a request id, the answer arrives as `ai-stream` events
+#[tauri::command]
+pub async fn ai_answer_stream(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    question: String,
+    context_opts: Option<ContextOptions>,
+) -> Result<String, String> {
+    let ai = settings(&state)?;
+    let opts = context_opts.unwrap_or_default();
+    let context = build_context(&state, Some(&question), Some(&ai.api_key), &opts).await?.text();
+    Ok(start_stream(
+        &app_handle,
+        ai,
+        ANSWER_SYSTEM_PROMPT,
+        answer_prompt(&context, &question),
+        Some(tts::speak_answer_if_enabled),
+    ))
+}
+
+/// A `generateContent` request path: "<version>/models/<model>"
+fn valid_model_path(path: &str) -> bool {
+    let parts: Vec<&str> = path.split('/').collect();
+    matches!(parts.as_slice(), [version, "models", model]
+        if matches!(*version, "v1" | "v1beta")
+            && !model.is_empty()
+            && model.chars().all(|c| c.is_ascii_alphanumeric() || "-._".contains(c)))
+}
+
+/// Send a `generateContent` request body built by the frontend to Gemini
+/// with the configured key; returns the response body. `api_key` checks a key
+/// that hasn't been saved yet.
+#[tauri::command]
+pub async fn gemini_request(
+    state: tauri::State<'_, Arc<AppState>>,
+    path: String,
+    body: serde_json::Value,
+    api_key: Option<String>,
+) -> Result<serde_json::Value, String> {
+    if !valid_model_path(&path) {
+        return Err(format!("Invalid Gemini request path: {}", path));
+    }
+    let api_key = match api_key.filter(|k| !k.is_empty() && k != crate::settings_update::SECRET_MASK) {
+        Some(key) => key,
+        None => settings(&state)?.api_key,
+    };
+    let response = reqwest::Client::new()
+        .post(format!("https://generativelanguage.googleapis.com/{}:generateContent", path))
+        .header("x-goog-api-key", api_key)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+    let status = response.status();
+    let data: serde_json::Value = response.json().await.unwrap_or_default();
+    if !status.is_success() {
+        return Err(data["error"]["message"]
+            .as_str()
+            .map(String::from)
+            .unwrap_or_else(|| format!("API request failed: {}", status.as_u16())));
+    }
+    Ok(data)
+}
+
+/// Translate text, by default into the configured translation language
+#[tauri::command]
+pub async fn ai_translate(
+    state: tauri::State<'_, Arc<AppState>>,
+    text: String,
+    target_lang: Option<String>,
+) -> Result<String, String> {
+    let ai = settings(&state)?;
+    let prompt = translate_prompt(&ai, &text, target_lang)?;
+    generate_content(&ai, TRANSLATE_SYSTEM_PROMPT, &prompt, false).await
+}
+
+/// Streaming `ai_translate`
+#[tauri::command]
+pub async fn ai_translate_stream(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    text: String,
+    target_lang: Option<String>,
+) -> Result<String, String> {
+    let ai = settings(&state)?;
+    let prompt = translate_prompt(&ai, &text, target_lang)?;
+    Ok(start_stream(&app_handle, ai, TRANSLATE_SYSTEM_PROMPT, prompt, None))
+}
+
+fn translate_prompt(ai: &AISettings, text: &str, target_lang: Option<String>) -> Result<String, String> {
+    let target = target_lang
+        .or_else(|| ai.translation_language.clone())
+        .ok_or_else(|| "No target language given".to_string())?;
+    Ok(format!("Translate into {}:\n\n{}", target, text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_sections() {
+        let section = |name, items: &[&str], newest_first| Section {
+            name,
+            title: name,
+            separator: "\n",
+            items: items.iter().map(|s| s.to_string()).collect(),
+            newest_first,
+        };
+        let sections = vec![
+            section("pinned", &["[answer]: ship on friday"], false),
+            section("history", &["x".repeat(400).as_str()], false),
+            section("transcript", &["third line", "second line", "first line"], true),
+        ];
+        let (text, plans) = plan_sections(sections, 22);
+        assert_eq!(text, "=== pinned ===\n[answer]: ship on friday\n=== transcript ===\nsecond line\nthird line\n");
+        let counts: Vec<(usize, usize)> = plans.iter().map(|p| (p.included, p.excluded)).collect();
+        assert_eq!(counts, vec![(1, 0), (0, 1), (2, 1)]);
+        assert!(plans.iter().map(|p| p.tokens).sum::<i64>() <= 22);
+    }
+}
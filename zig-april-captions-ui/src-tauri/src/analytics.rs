@@ -0,0 +1,257 @@
+// Talk-time statistics for a session
+//
+// Computed from the stored final captions. A caption only carries the time it
+// was finalized, so the span it was spoken over is estimated: it starts where
+// the previous caption on the same channel ended, but no earlier than its
+// words allow at MIN_WORDS_PER_MINUTE. Continuous speech is therefore timed
+// exactly and a caption after a pause isn't credited with the pause.
+//
+// From those spans come each speaker's words, speaking time, share and pace,
+// the silences where nobody spoke for SILENCE_GAP_MS or longer, and
+// interruptions: a speaker starting while someone else's caption is still
+// being spoken, which only happens when both sides are captured.
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::{database, db_pool, AppState};
+
+/// Slowest speech a caption's span is stretched to
+const MIN_WORDS_PER_MINUTE: i64 = 100;
+
+/// Shortest pause reported as a silence gap
+const SILENCE_GAP_MS: i64 = 5_000;
+
+/// Label for captions without a speaker or source
+const UNKNOWN_SPEAKER: &str = "Unknown";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SpeakerStats {
+    pub speaker: String,
+    pub lines: usize,
+    pub words: usize,
+    pub speaking_ms: i64,
+    pub share: f64, // Fraction of all speaking time, 0..1
+    pub words_per_minute: f64,
+    pub interruptions: usize, // Times this speaker cut someone off
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SilenceGap {
+    pub start: i64,
+    pub end: i64,
+    pub duration_ms: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionAnalytics {
+    pub session_id: String,
+    pub duration_ms: i64,
+    pub speaking_ms: i64,
+    pub silence_ms: i64, // Total of `silence_gaps`
+    pub words: usize,
+    pub words_per_minute: f64,
+    pub interruptions: usize,
+    pub speakers: Vec<SpeakerStats>, // Most speaking time first
+    pub silence_gaps: Vec<SilenceGap>,
+}
+
+/// One caption and the span it is estimated to have been spoken over
+struct Utterance {
+    speaker: String,
+    words: usize,
+    start: i64,
+    end: i64,
+}
+
+fn words_per_minute(words: usize, ms: i64) -> f64 {
+    if ms > 0 {
+        words as f64 * 60_000.0 / ms as f64
+    } else {
+        0.0
+    }
+}
+
+/// Estimate spans for (timestamp, text, speaker, channel) rows, oldest first
+fn utterances(rows: Vec<(i64, String, String, String)>, started_at: i64) -> Vec<Utterance> {
+    let mut channel_ends: Vec<(String, i64)> = Vec::new();
+    rows.into_iter()
+        .map(|(end, text, speaker, channel)| {
+            let words = text.split_whitespace().count();
+            let longest = words as i64 * 60_000 / MIN_WORDS_PER_MINUTE;
+            let previous = match channel_ends.iter_mut().find(|(c, _)| *c == channel) {
+                Some((_, previous)) => std::mem::replace(previous, end),
+                None => {
+                    channel_ends.push((channel, end));
+                    started_at
+                }
+            };
+            Utterance {
+                speaker,
+                words,
+                start: previous.max(end - longest).min(end),
+                end,
+            }
+        })
+        .collect()
+}
+
+fn compute(session_id: &str, started_at: i64, ended_at: i64, utterances: &[Utterance]) -> SessionAnalytics {
+    let mut speakers: Vec<SpeakerStats> = Vec::new();
+    let mut interruptions = 0;
+    for (i, utterance) in utterances.iter().enumerate() {
+        // Someone else still talking when this caption began; captions are
+        // in finalized order, so the other one may come later
+        let interrupted = utterances.iter().enumerate().any(|(j, other)| {
+            j != i && other.speaker != utterance.speaker && other.start < utterance.start && other.end > utterance.start
+        });
+        let index = match speakers.iter().position(|s| s.speaker == utterance.speaker) {
+            Some(index) => index,
+            None => {
+                speakers.push(SpeakerStats {
+                    speaker: utterance.speaker.clone(),
+                    lines: 0,
+                    words: 0,
+                    speaking_ms: 0,
+                    share: 0.0,
+                    words_per_minute: 0.0,
+                    interruptions: 0,
+                });
+                speakers.len() - 1
+            }
+        };
+        let stats = &mut speakers[index];
+        stats.lines += 1;
+        stats.words += utterance.words;
+        stats.speaking_ms += utterance.end - utterance.start;
+        if interrupted {
+            stats.interruptions += 1;
+            interruptions += 1;
+        }
+    }
+
+    // Silences are the gaps between the merged spans of everyone's speech
+    let mut spans: Vec<(i64, i64)> = utterances.iter().map(|u| (u.start, u.end)).collect();
+    spans.sort();
+    let mut silence_gaps = Vec::new();
+    let mut speaking_ms = 0;
+    let mut covered_until = started_at;
+    for (start, end) in spans {
+        if start - covered_until >= SILENCE_GAP_MS {
+            silence_gaps.push(SilenceGap {
+                start: covered_until,
+                end: start,
+                duration_ms: start - covered_until,
+            });
+        }
+        speaking_ms += (end - start.max(covered_until)).max(0);
+        covered_until = covered_until.max(end);
+    }
+    if ended_at - covered_until >= SILENCE_GAP_MS {
+        silence_gaps.push(SilenceGap {
+            start: covered_until,
+            end: ended_at,
+            duration_ms: ended_at - covered_until,
+        });
+    }
+
+    let total_speaker_ms: i64 = speakers.iter().map(|s| s.speaking_ms).sum();
+    for stats in &mut speakers {
+        stats.share = if total_speaker_ms > 0 {
+            stats.speaking_ms as f64 / total_speaker_ms as f64
+        } else {
+            0.0
+        };
+        stats.words_per_minute = words_per_minute(stats.words, stats.speaking_ms);
+    }
+    speakers.sort_by_key(|s| std::cmp::Reverse(s.speaking_ms));
+    let words = speakers.iter().map(|s| s.words).sum();
+
+    SessionAnalytics {
+        session_id: session_id.to_string(),
+        duration_ms: (ended_at - started_at).max(0),
+        speaking_ms,
+        silence_ms: silence_gaps.iter().map(|g| g.duration_ms).sum(),
+        words,
+        words_per_minute: words_per_minute(words, speaking_ms),
+        interruptions,
+        speakers,
+        silence_gaps,
+    }
+}
+
+/// Talk-time statistics of a session; a running session is measured up to
+/// its last caption
+pub fn session_analytics(conn: &Connection, session_id: &str) -> Result<SessionAnalytics, String> {
+    let session = database::get_session(conn, session_id)
+        .map_err(|e| format!("Failed to load session: {}", e))?
+        .ok_or_else(|| format!("Session {} not found", session_id))?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT timestamp, text, speaker, source, channel FROM transcript_lines
+             WHERE session_id = ?1 ORDER BY timestamp ASC, id ASC",
+        )
+        .map_err(|e| format!("Prepare failed: {}", e))?;
+    let rows = stmt
+        .query_map(params![session_id], |row| {
+            let speaker: Option<String> = row.get(2)?;
+            let source: Option<String> = row.get(3)?;
+            let channel: Option<String> = row.get(4)?;
+            let speaker = speaker
+                .or(source)
+                .filter(|s| !s.is_empty())
+                .unwrap_or_else(|| UNKNOWN_SPEAKER.to_string());
+            Ok((row.get(0)?, row.get(1)?, speaker, channel.unwrap_or_default()))
+        })
+        .map_err(|e| format!("Query failed: {}", e))?
+        .collect::<Result<Vec<(i64, String, String, String)>, _>>()
+        .map_err(|e| format!("Failed to load transcript: {}", e))?;
+
+    let last_caption = rows.last().map(|row| row.0).unwrap_or(session.started_at);
+    let ended_at = session.ended_at.unwrap_or(last_caption).max(last_caption);
+    let utterances = utterances(rows, session.started_at);
+    Ok(compute(session_id, session.started_at, ended_at, &utterances))
+}
+
+/// Talk time, pace, speaker share, silences and interruptions of a session
+#[tauri::command]
+pub async fn get_session_analytics(
+    state: tauri::State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<SessionAnalytics, String> {
+    db_pool::read(&state, move |conn| session_analytics(conn, &session_id)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_talk_time_and_gaps() {
+        let conn = database::init_memory_db().unwrap();
+        let session = database::start_session(&conn, 0, "mic", "m").unwrap();
+        // "me" speaks for 6s straight; after a long pause "them" answers on
+        // the other channel and "me" cuts in before they're done
+        for (timestamp, text, source, channel) in [
+            (3_000, "one two three four five", "me", "mic"),
+            (6_000, "six seven eight nine ten", "me", "mic"),
+            (30_000, "a b c d e f g h i j", "them", "monitor"),
+            (28_000, "wait", "me", "mic"),
+        ] {
+            database::add_caption_line(&conn, &session.id, timestamp, text, Some(source), Some(channel), None)
+                .unwrap();
+        }
+        database::end_session(&conn, &session.id, 31_000).unwrap();
+
+        let analytics = session_analytics(&conn, &session.id).unwrap();
+        assert_eq!(analytics.duration_ms, 31_000);
+        assert_eq!(analytics.words, 21);
+        let me = analytics.speakers.iter().find(|s| s.speaker == "me").unwrap();
+        assert_eq!((me.lines, me.words, me.speaking_ms), (3, 11, 6_600));
+        assert_eq!(me.interruptions, 1);
+        let them = analytics.speakers.iter().find(|s| s.speaker == "them").unwrap();
+        assert_eq!(them.speaking_ms, 6_000);
+        assert_eq!(analytics.silence_gaps.len(), 1);
+        assert_eq!((analytics.silence_gaps[0].start, analytics.silence_gaps[0].end), (6_000, 24_000));
+    }
+}
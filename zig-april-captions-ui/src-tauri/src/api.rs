@@ -0,0 +1,284 @@
+// REST API served by the embedded server under `/api`
+//
+// Read endpoints for scripting and home automation, plus start/stop control.
+// All routes need the server token (`?token=` or `Authorization: Bearer`).
+// Responses are JSON; failures return `{ "error": "..." }` with a 4xx/5xx code.
+//
+//   GET  /api/status                  running/paused state, version, transcript size
+//   GET  /api/transcript              current transcript lines
+//   GET  /api/search?q=&limit=        case-insensitive match over transcript and chat
+//   GET  /api/sessions                captioning sessions with line and message counts
+//   GET  /api/sessions/{id}           transcript and chat messages of one session
+//   GET  /api/sessions/{id}/transcript timestamped transcript lines of one session;
+//                                     `?format=text` returns plain text instead
+//   GET  /api/captions/stream         live caption/status/session events as
+//                                     Server-Sent Events (see server.rs)
+//   GET  /api/summaries               context summaries, newest first
+//   POST /api/session/start           start captions; optional body
+//                                     { "model_path": "...", "audio_source": "mic" | "monitor" }
+//   POST /api/session/stop            stop captions
+use axum::extract::{Path, Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use rusqlite::params;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::info;
+
+use crate::database;
+use crate::server::ServerContext;
+use crate::AppState;
+
+const DEFAULT_SEARCH_LIMIT: usize = 50;
+
+pub struct ApiError(StatusCode, String);
+
+impl From<String> for ApiError {
+    fn from(message: String) -> Self {
+        ApiError(StatusCode::INTERNAL_SERVER_ERROR, message)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": self.1 }))).into_response()
+    }
+}
+
+type ApiResult = Result<Json<Value>, ApiError>;
+
+#[derive(Deserialize)]
+struct SearchQuery {
+    q: String,
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct TranscriptQuery {
+    format: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StartRequest {
+    model_path: Option<String>,
+    audio_source: Option<String>,
+}
+
+pub fn routes() -> Router<ServerContext> {
+    Router::new()
+        .route("/status", get(status))
+        .route("/transcript", get(transcript))
+        .route("/search", get(search))
+        .route("/sessions", get(sessions))
+        .route("/sessions/{id}", get(session_messages))
+        .route("/sessions/{id}/transcript", get(session_transcript))
+        .route("/captions/stream", get(crate::server::caption_stream))
+        .route("/summaries", get(summaries))
+        .route("/session/start", post(start_session))
+        .route("/session/stop", post(stop_session))
+}
+
+pub fn transcript_lines(app_handle: &AppHandle) -> Result<Vec<String>, String> {
+    let state = app_handle.state::<Arc<AppState>>();
+    let lines = state.transcript_lines.lock().map_err(|e| e.to_string())?;
+    Ok(lines.texts())
+}
+
+async fn status(State(ctx): State<ServerContext>) -> ApiResult {
+    let state = ctx.app_handle.state::<Arc<AppState>>();
+    let caption_state = crate::caption_state(&state);
+    Ok(Json(json!({
+        "running": caption_state != "stopped",
+        "state": caption_state,
+        "version": ctx.app_handle.package_info().version.to_string(),
+        "transcript_lines": transcript_lines(&ctx.app_handle)?.len(),
+    })))
+}
+
+async fn transcript(State(ctx): State<ServerContext>) -> ApiResult {
+    Ok(Json(json!({ "lines": transcript_lines(&ctx.app_handle)? })))
+}
+
+/// `%needle%` for LIKE ... ESCAPE '\', with wildcards in the needle matched literally
+fn like_pattern(needle: &str) -> String {
+    let mut pattern = String::from("%");
+    for c in needle.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
+
+/// Case-insensitive substring search over the live transcript and chat history
+pub fn search_all(app_handle: &AppHandle, query: &str, limit: Option<usize>) -> Result<Value, String> {
+    let needle = query.trim().to_lowercase();
+    let limit = limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+
+    let transcript: Vec<String> = transcript_lines(app_handle)?
+        .into_iter()
+        .filter(|line| line.to_lowercase().contains(&needle))
+        .take(limit)
+        .collect();
+
+    let state = app_handle.state::<Arc<AppState>>();
+    let conn = state.db()?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM chat_entries WHERE content LIKE ?1 ESCAPE '\\' ORDER BY timestamp DESC LIMIT ?2",
+            database::CHAT_ENTRY_COLUMNS
+        ))
+        .map_err(|e| format!("Prepare failed: {}", e))?;
+    let chat = stmt
+        .query_map(params![like_pattern(&needle), limit as i64], database::chat_entry_from_row)
+        .map_err(|e| format!("Query failed: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(json!({ "transcript": transcript, "chat": chat }))
+}
+
+async fn search(State(ctx): State<ServerContext>, Query(query): Query<SearchQuery>) -> ApiResult {
+    if query.q.trim().is_empty() {
+        return Err(ApiError(StatusCode::BAD_REQUEST, "Query parameter q is required".to_string()));
+    }
+    Ok(Json(search_all(&ctx.app_handle, &query.q, query.limit)?))
+}
+
+/// Captioning sessions with transcript and chat counts, most recent first
+pub fn list_sessions(app_handle: &AppHandle) -> Result<Vec<Value>, String> {
+    let state = app_handle.state::<Arc<AppState>>();
+    let conn = state.db()?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT s.id, s.title, s.started_at, s.ended_at, s.audio_source, s.model_path,
+                    (SELECT COUNT(*) FROM transcript_lines t WHERE t.session_id = s.id),
+                    (SELECT COUNT(*) FROM chat_entries c WHERE c.session_id = s.id),
+                    s.audio_path
+             FROM sessions s ORDER BY s.started_at DESC",
+        )
+        .map_err(|e| format!("Prepare failed: {}", e))?;
+    let sessions = stmt
+        .query_map([], |row| {
+            Ok(json!({
+                "id": row.get::<_, String>(0)?,
+                "title": row.get::<_, String>(1)?,
+                "started_at": row.get::<_, i64>(2)?,
+                "ended_at": row.get::<_, Option<i64>>(3)?,
+                "audio_source": row.get::<_, Option<String>>(4)?,
+                "model_path": row.get::<_, Option<String>>(5)?,
+                "line_count": row.get::<_, i64>(6)?,
+                "message_count": row.get::<_, i64>(7)?,
+                "audio_path": row.get::<_, Option<String>>(8)?,
+            }))
+        })
+        .map_err(|e| format!("Query failed: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(sessions)
+}
+
+async fn sessions(State(ctx): State<ServerContext>) -> ApiResult {
+    Ok(Json(json!({ "sessions": list_sessions(&ctx.app_handle)? })))
+}
+
+async fn session_messages(State(ctx): State<ServerContext>, Path(id): Path<String>) -> ApiResult {
+    let state = ctx.app_handle.state::<Arc<AppState>>();
+    let conn = state.db()?;
+    let session = database::get_session(&conn, &id).map_err(|e| e.to_string())?;
+    let transcript = database::session_transcript(&conn, &id).map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM chat_entries WHERE session_id = ?1 ORDER BY timestamp ASC",
+            database::CHAT_ENTRY_COLUMNS
+        ))
+        .map_err(|e| format!("Prepare failed: {}", e))?;
+    let messages = stmt
+        .query_map(params![id], database::chat_entry_from_row)
+        .map_err(|e| format!("Query failed: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    // Chat-only sessions from before sessions were recorded have no row
+    if session.is_none() && messages.is_empty() {
+        return Err(ApiError(StatusCode::NOT_FOUND, format!("Session {} not found", id)));
+    }
+    Ok(Json(json!({ "id": id, "session": session, "transcript": transcript, "messages": messages })))
+}
+
+async fn session_transcript(
+    State(ctx): State<ServerContext>,
+    Path(id): Path<String>,
+    Query(query): Query<TranscriptQuery>,
+) -> Result<Response, ApiError> {
+    let state = ctx.app_handle.state::<Arc<AppState>>();
+    let conn = state.db()?;
+    if database::get_session(&conn, &id).map_err(|e| e.to_string())?.is_none() {
+        return Err(ApiError(StatusCode::NOT_FOUND, format!("Session {} not found", id)));
+    }
+    match query.format.as_deref() {
+        Some("text") => {
+            let text: String = database::session_transcript(&conn, &id)
+                .map_err(|e| e.to_string())?
+                .iter()
+                .map(|line| format!("{}\n", line))
+                .collect();
+            Ok(([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], text).into_response())
+        }
+        None | Some("json") => {
+            let lines = database::session_transcript_lines(&conn, &id).map_err(|e| e.to_string())?;
+            Ok(Json(json!({ "id": id, "lines": lines })).into_response())
+        }
+        Some(other) => Err(ApiError(StatusCode::BAD_REQUEST, format!("Unknown format: {}", other))),
+    }
+}
+
+async fn summaries(State(ctx): State<ServerContext>) -> ApiResult {
+    let snapshots = crate::get_all_snapshots(ctx.app_handle.state()).await?;
+    Ok(Json(json!({ "summaries": snapshots })))
+}
+
+async fn start_session(State(ctx): State<ServerContext>, body: Option<Json<StartRequest>>) -> ApiResult {
+    let (model_path, audio_source) = {
+        let state = ctx.app_handle.state::<Arc<AppState>>();
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        let request = body.map(|Json(b)| b);
+        (
+            request
+                .as_ref()
+                .and_then(|r| r.model_path.clone())
+                .unwrap_or_else(|| settings.model_path.clone()),
+            request
+                .as_ref()
+                .and_then(|r| r.audio_source.clone())
+                .unwrap_or_else(|| settings.audio_source.clone()),
+        )
+    };
+    if model_path.is_empty() {
+        return Err(ApiError(StatusCode::BAD_REQUEST, "No model configured".to_string()));
+    }
+
+    let app_handle = ctx.app_handle.clone();
+    crate::start_captions(app_handle.clone(), app_handle.state(), model_path, audio_source.clone(), None, None)
+        .await?;
+
+    info!("Captions started through the REST API");
+    let _ = app_handle.emit("remote-session", json!({ "action": "started", "audioSource": audio_source }));
+    Ok(Json(json!({ "running": true })))
+}
+
+async fn stop_session(State(ctx): State<ServerContext>) -> ApiResult {
+    let app_handle = ctx.app_handle.clone();
+    crate::stop_captions(app_handle.clone(), app_handle.state()).await?;
+
+    info!("Captions stopped through the REST API");
+    let _ = app_handle.emit("remote-session", json!({ "action": "stopped" }));
+    Ok(Json(json!({ "running": false })))
+}
@@ -0,0 +1,104 @@
+// Audio input device enumeration
+//
+// Linux captures through PulseAudio, so devices are PulseAudio sources and the
+// ID is the source name the recognizer passes to pa_simple_new. Elsewhere the
+// recognizer matches devices by name, so the cpal device name doubles as ID.
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioDevice {
+    pub id: String,
+    pub name: String,
+    pub kind: String, // "input" or "monitor"
+    pub is_default: bool,
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn pactl(args: &[&str]) -> Result<String, String> {
+    let output = std::process::Command::new("pactl")
+        .args(args)
+        .env("LC_ALL", "C")
+        .output()
+        .map_err(|e| format!("Failed to run pactl: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("pactl failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Parse `pactl list sources` into (name, description) pairs
+#[cfg(target_os = "linux")]
+fn parse_sources(listing: &str) -> Vec<(String, String)> {
+    let mut sources = Vec::new();
+    let mut name: Option<String> = None;
+    for line in listing.lines().map(str::trim) {
+        if let Some(value) = line.strip_prefix("Name: ") {
+            name = Some(value.to_string());
+        } else if let Some(value) = line.strip_prefix("Description: ") {
+            if let Some(name) = name.take() {
+                sources.push((name, value.to_string()));
+            }
+        }
+    }
+    sources
+}
+
+#[cfg(target_os = "linux")]
+pub fn enumerate() -> Result<Vec<AudioDevice>, String> {
+    let default = pactl(&["get-default-source"]).map(|s| s.trim().to_string()).unwrap_or_default();
+    let devices = parse_sources(&pactl(&["list", "sources"])?)
+        .into_iter()
+        .map(|(id, name)| AudioDevice {
+            kind: if id.ends_with(".monitor") { "monitor" } else { "input" }.to_string(),
+            is_default: id == default,
+            id,
+            name,
+        })
+        .collect();
+    Ok(devices)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn enumerate() -> Result<Vec<AudioDevice>, String> {
+    use cpal::traits::{DeviceTrait, HostTrait};
+
+    let host = cpal::default_host();
+    let default = host.default_input_device().and_then(|d| d.name().ok());
+    let devices = host
+        .input_devices()
+        .map_err(|e| format!("Failed to list audio devices: {}", e))?
+        .filter_map(|device| device.name().ok())
+        .map(|name| AudioDevice {
+            id: name.clone(),
+            is_default: default.as_deref() == Some(name.as_str()),
+            kind: "input".to_string(),
+            name,
+        })
+        .collect();
+    Ok(devices)
+}
+
+#[tauri::command]
+pub async fn list_audio_devices() -> Result<Vec<AudioDevice>, String> {
+    tokio::task::spawn_blocking(enumerate).await.map_err(|e| e.to_string())?
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sources() {
+        let listing = "Source #0\n\tState: SUSPENDED\n\tName: alsa_output.pci.analog-stereo.monitor\n\
+                       \tDescription: Monitor of Built-in Audio\n\nSource #1\n\tName: alsa_input.pci.analog-stereo\n\
+                       \tDescription: Built-in Audio Analog Stereo\n";
+        assert_eq!(
+            parse_sources(listing),
+            vec![
+                ("alsa_output.pci.analog-stereo.monitor".to_string(), "Monitor of Built-in Audio".to_string()),
+                ("alsa_input.pci.analog-stereo".to_string(), "Built-in Audio Analog Stereo".to_string()),
+            ]
+        );
+    }
+}
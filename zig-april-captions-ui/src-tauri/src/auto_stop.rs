@@ -0,0 +1,119 @@
+// Automatic stop of a captioning run
+//
+// `start_captions` can be given a maximum duration and a silence limit. A
+// watcher then checks every CHECK_INTERVAL how long the run has lasted and
+// how long ago the last caption (partial or final) arrived, and stops
+// captions like the stop button would once either limit is reached, so a
+// forgotten session doesn't record hours of an empty room. The reason is
+// emitted as `captions-auto-stopped` just before the stop.
+//
+// Time spent paused doesn't count as silence. A manual stop or a new start
+// bumps `AppState.run`, which ends the watcher; model switches and automatic
+// restarts after a crash keep it running.
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+use crate::{current_session_id, now_millis, AppState};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AutoStopOptions {
+    #[serde(default)]
+    pub max_duration_minutes: Option<u32>,
+    #[serde(default)]
+    pub stop_on_silence_minutes: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    MaxDuration,
+    Silence,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct AutoStopped {
+    reason: StopReason,
+    session_id: Option<String>,
+    elapsed_ms: i64,
+    silent_ms: i64,
+}
+
+fn minutes_ms(minutes: Option<u32>) -> Option<i64> {
+    minutes.filter(|m| *m > 0).map(|m| m as i64 * 60_000)
+}
+
+/// Which limit, if any, a run `elapsed_ms` long and silent for `silent_ms` has reached
+fn reached(options: &AutoStopOptions, elapsed_ms: i64, silent_ms: i64) -> Option<StopReason> {
+    if minutes_ms(options.max_duration_minutes).map(|max| elapsed_ms >= max).unwrap_or(false) {
+        Some(StopReason::MaxDuration)
+    } else if minutes_ms(options.stop_on_silence_minutes).map(|max| silent_ms >= max).unwrap_or(false) {
+        Some(StopReason::Silence)
+    } else {
+        None
+    }
+}
+
+/// Note that a caption arrived
+pub fn on_caption(state: &AppState) {
+    state.last_caption_at.store(now_millis(), Ordering::SeqCst);
+}
+
+/// Watch the run started as `run` and stop it at the first limit reached
+pub fn watch(app_handle: &AppHandle, options: AutoStopOptions, run: u64) {
+    if minutes_ms(options.max_duration_minutes).is_none() && minutes_ms(options.stop_on_silence_minutes).is_none() {
+        return;
+    }
+    let app_handle = app_handle.clone();
+    let started_at = now_millis();
+    app_handle.state::<Arc<AppState>>().last_caption_at.store(started_at, Ordering::SeqCst);
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            let state = app_handle.state::<Arc<AppState>>();
+            if state.run.load(Ordering::SeqCst) != run {
+                return;
+            }
+            let now = now_millis();
+            if state.paused.load(Ordering::SeqCst) {
+                on_caption(&state);
+            }
+            let elapsed_ms = now - started_at;
+            let silent_ms = now - state.last_caption_at.load(Ordering::SeqCst);
+            let Some(reason) = reached(&options, elapsed_ms, silent_ms) else {
+                continue;
+            };
+
+            let session_id = current_session_id(&state);
+            info!("Stopping captions automatically ({:?})", reason);
+            let _ = app_handle.emit(
+                "captions-auto-stopped",
+                AutoStopped { reason, session_id, elapsed_ms, silent_ms },
+            );
+            if let Err(e) = crate::stop_captions(app_handle.clone(), app_handle.state()).await {
+                warn!("Automatic stop failed: {}", e);
+            }
+            return;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reached() {
+        let options = AutoStopOptions { max_duration_minutes: Some(60), stop_on_silence_minutes: Some(10) };
+        assert_eq!(reached(&options, 30 * 60_000, 60_000), None);
+        assert_eq!(reached(&options, 30 * 60_000, 10 * 60_000), Some(StopReason::Silence));
+        assert_eq!(reached(&options, 60 * 60_000, 0), Some(StopReason::MaxDuration));
+        let off = AutoStopOptions { max_duration_minutes: Some(0), stop_on_silence_minutes: None };
+        assert_eq!(reached(&off, i64::MAX, i64::MAX), None);
+    }
+}
@@ -0,0 +1,98 @@
+// Launch at login and captions on launch
+//
+// `Settings.autostart` holds two independent switches:
+//   - `launch_at_login` registers the app with the OS (a LaunchAgent on
+//     macOS, the registry on Windows, an XDG autostart entry on Linux)
+//     through tauri-plugin-autostart; the registration follows the setting
+//     at every launch and settings save. A portable install never registers
+//     itself, since the executable may be on a drive that's gone next login
+//   - `start_captions` starts captioning with the saved model and audio
+//     source as soon as the main window has loaded, so its listeners get the
+//     usual events; anything emitted before they subscribe is replayed
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_autostart::ManagerExt;
+use tracing::{info, warn};
+
+use crate::AppState;
+
+/// Argument the OS launches the app with at login
+pub const LOGIN_ARG: &str = "--autostart";
+
+/// Time for the UI to subscribe to events after its page has loaded
+const READY_DELAY: Duration = Duration::from_secs(1);
+
+/// Captions are started at most once per launch, not on every reload
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AutostartSettings {
+    #[serde(default)]
+    pub launch_at_login: bool,
+    #[serde(default)]
+    pub start_captions: bool, // Start with the saved model and audio source on launch
+}
+
+fn autostart_settings(app_handle: &AppHandle) -> AutostartSettings {
+    app_handle
+        .state::<Arc<AppState>>()
+        .settings
+        .lock()
+        .ok()
+        .and_then(|s| s.autostart.clone())
+        .unwrap_or_default()
+}
+
+/// Register or unregister the login item to match the settings
+pub fn apply_settings(app_handle: &AppHandle) -> Result<(), String> {
+    if crate::portable::is_portable() {
+        return Ok(());
+    }
+    let wanted = autostart_settings(app_handle).launch_at_login;
+    let launcher = app_handle.autolaunch();
+    let registered = launcher.is_enabled().map_err(|e| e.to_string())?;
+    if wanted == registered {
+        return Ok(());
+    }
+    if wanted {
+        launcher
+            .enable()
+            .map_err(|e| format!("Failed to enable launch at login: {}", e))?;
+    } else {
+        launcher
+            .disable()
+            .map_err(|e| format!("Failed to disable launch at login: {}", e))?;
+    }
+    info!(
+        "Launch at login {}",
+        if wanted { "enabled" } else { "disabled" }
+    );
+    Ok(())
+}
+
+/// Called when the main window has loaded; starts captions if configured
+pub fn on_main_window_ready(app_handle: &AppHandle) {
+    if !autostart_settings(app_handle).start_captions || STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(READY_DELAY).await;
+        let running = app_handle
+            .state::<Arc<AppState>>()
+            .process
+            .lock()
+            .map(|p| p.is_some())
+            .unwrap_or(false);
+        if running {
+            return;
+        }
+        match crate::start_with_saved_settings(&app_handle).await {
+            Ok(()) => info!("Captions started on launch"),
+            Err(e) => warn!("Could not start captions on launch: {}", e),
+        }
+    });
+}
@@ -0,0 +1,339 @@
+// Backup and restore
+//
+// `create_backup` zips the whole data directory (database, settings, stores,
+// recordings, transcript logs) with a manifest recording the backup format
+// and database schema version. A plain database is added as a consistent
+// snapshot; an encrypted one is saved first and copied sealed, so restoring
+// it needs the same passphrase. Secrets stay out of the zip: settings and
+// profiles are added with their tokens and passwords blanked, and
+// `redaction.key` is left behind, so kept unredacted originals can only be
+// read on the machine that wrote them.
+//
+// `restore_backup` checks the manifest and the database, unpacks the backup
+// next to the data directory and restarts the app. The swap happens at the
+// next launch, before anything opens the files: the current directory is
+// renamed to `zigy.before-restore` and the unpacked one takes its place, so a
+// failed restore never leaves a half-written directory behind. Secrets the
+// backup left blank are then copied over from the replaced settings and
+// profiles, so restoring on the same machine keeps keys and tokens.
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::AppHandle;
+use tracing::info;
+use zip::write::SimpleFileOptions;
+
+use crate::{database, encryption, get_config_dir, now_millis, settings_update, AppState};
+
+/// Bumped when the layout of the zip changes
+const FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_NAME: &str = "manifest.json";
+
+/// Never backed up: logs, locks, secrets and files of the running instance
+const SKIPPED_NAMES: [&str; 9] = [
+    "logs",
+    "backups",
+    "redaction.key",
+    "session.lock",
+    "recovery_transcript.json",
+    "zigy.db",
+    "zigy.db-wal",
+    "zigy.db-shm",
+    "zigy.db-journal",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format_version: u32,
+    pub schema_version: i64,
+    pub app_version: String,
+    pub created_at: i64,
+    pub encrypted: bool, // The database is sealed with the storage passphrase
+    pub data_dir: String, // Where the data lived, to fix up recording paths
+    pub files: usize,
+}
+
+fn staging_dir() -> PathBuf {
+    get_config_dir().with_file_name("zigy.restore")
+}
+
+fn previous_dir() -> PathBuf {
+    get_config_dir().with_file_name("zigy.before-restore")
+}
+
+/// Files under `dir` as (path, name in the zip)
+fn collect_files(dir: &Path, prefix: &str, files: &mut Vec<(PathBuf, String)>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let skipped = SKIPPED_NAMES.contains(&name.as_str()) || name.ends_with(".lock") || name.ends_with(".tmp");
+        if prefix.is_empty() && skipped {
+            continue;
+        }
+        let path = entry.path();
+        let zip_name = format!("{}{}", prefix, name);
+        if path.is_dir() {
+            collect_files(&path, &format!("{}/", zip_name), files)?;
+        } else {
+            files.push((path, zip_name));
+        }
+    }
+    Ok(())
+}
+
+/// Settings and profiles, which are copied with their secrets blanked
+fn holds_settings(zip_name: &str) -> bool {
+    zip_name == "settings.json" || (zip_name.starts_with("profiles/") && zip_name.ends_with(".json"))
+}
+
+/// Contents of a settings or profile file without its secrets
+fn without_secrets(path: &Path) -> Result<Vec<u8>, String> {
+    let json = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut value: serde_json::Value =
+        serde_json::from_slice(&json).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    // Profiles keep their settings under "settings"
+    match value.get_mut("settings") {
+        Some(settings) if settings.is_object() => settings_update::strip_secrets(settings),
+        _ => settings_update::strip_secrets(&mut value),
+    }
+    serde_json::to_vec_pretty(&value).map_err(|e| e.to_string())
+}
+
+/// Fill the blanked secrets of a restored settings or profile file from its
+/// replaced copy, if there is one
+fn refill_secrets(path: &Path, previous: &Path) -> Result<(), String> {
+    let read = |path: &Path| -> Result<serde_json::Value, String> {
+        let json = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        serde_json::from_slice(&json).map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+    };
+    if !previous.is_file() {
+        return Ok(());
+    }
+    let (mut value, old) = (read(path)?, read(previous)?);
+    // Profiles keep their settings under "settings"
+    let refilled = match (value.get_mut("settings"), old.get("settings")) {
+        (Some(settings), Some(old)) if settings.is_object() => settings_update::refill_secrets(old, settings),
+        _ => settings_update::refill_secrets(&old, &mut value),
+    };
+    if refilled {
+        let json = serde_json::to_vec_pretty(&value).map_err(|e| e.to_string())?;
+        std::fs::write(path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Write a backup of all app data to `dest_path`
+#[tauri::command]
+pub async fn create_backup(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    dest_path: String,
+) -> Result<BackupManifest, String> {
+    state.flush_stores();
+    let data_dir = get_config_dir();
+    let encrypted = encryption::is_enabled();
+
+    // Plain databases are snapshotted so writes during the backup don't tear it
+    let snapshot = std::env::temp_dir().join(format!("zigy-backup-{}.db", uuid::Uuid::new_v4()));
+    if !encrypted {
+        let conn = state.db()?;
+        conn.execute("VACUUM INTO ?1", [snapshot.to_string_lossy()])
+            .map_err(|e| format!("Failed to snapshot the database: {}", e))?;
+    }
+
+    let result = (|| -> Result<BackupManifest, String> {
+        let mut files = Vec::new();
+        collect_files(&data_dir, "", &mut files).map_err(|e| format!("Failed to list data files: {}", e))?;
+        if !encrypted {
+            files.push((snapshot.clone(), "zigy.db".to_string()));
+        }
+        let manifest = BackupManifest {
+            format_version: FORMAT_VERSION,
+            schema_version: database::SCHEMA_VERSION,
+            app_version: app_handle.package_info().version.to_string(),
+            created_at: now_millis(),
+            encrypted,
+            data_dir: data_dir.to_string_lossy().to_string(),
+            files: files.len(),
+        };
+
+        let file = std::fs::File::create(&dest_path).map_err(|e| format!("Failed to create {}: {}", dest_path, e))?;
+        let mut zip = zip::ZipWriter::new(file);
+        let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        zip.start_file(MANIFEST_NAME, options).map_err(|e| e.to_string())?;
+        zip.write_all(&serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?)
+            .map_err(|e| e.to_string())?;
+        for (path, name) in &files {
+            if holds_settings(name) {
+                let json = without_secrets(path)?;
+                zip.start_file(name.as_str(), options).map_err(|e| e.to_string())?;
+                zip.write_all(&json).map_err(|e| format!("Failed to add {}: {}", name, e))?;
+                continue;
+            }
+            let mut source = std::fs::File::open(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            zip.start_file(name.as_str(), options).map_err(|e| e.to_string())?;
+            std::io::copy(&mut source, &mut zip).map_err(|e| format!("Failed to add {}: {}", name, e))?;
+        }
+        zip.finish().map_err(|e| format!("Failed to write backup: {}", e))?;
+        Ok(manifest)
+    })();
+    let _ = std::fs::remove_file(&snapshot);
+
+    let manifest = result?;
+    info!("Backup of {} files written to {}", manifest.files, dest_path);
+    Ok(manifest)
+}
+
+fn read_manifest<R: Read + std::io::Seek>(archive: &mut zip::ZipArchive<R>) -> Result<BackupManifest, String> {
+    let mut file = archive
+        .by_name(MANIFEST_NAME)
+        .map_err(|_| "Not a backup: manifest.json is missing".to_string())?;
+    let mut json = String::new();
+    file.read_to_string(&mut json).map_err(|e| e.to_string())?;
+    serde_json::from_str(&json).map_err(|e| format!("Unreadable backup manifest: {}", e))
+}
+
+fn check_versions(manifest: &BackupManifest) -> Result<(), String> {
+    if manifest.format_version > FORMAT_VERSION {
+        return Err(format!(
+            "The backup was made by a newer version of the app ({}); update before restoring",
+            manifest.app_version
+        ));
+    }
+    if manifest.schema_version > database::SCHEMA_VERSION {
+        return Err(format!(
+            "The backup's database (schema {}) is newer than this app supports (schema {})",
+            manifest.schema_version,
+            database::SCHEMA_VERSION
+        ));
+    }
+    Ok(())
+}
+
+/// Check a backup and unpack it for the swap at the next launch, then restart
+#[tauri::command]
+pub async fn restore_backup(app_handle: AppHandle, path: String) -> Result<(), String> {
+    let file = std::fs::File::open(&path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("Not a valid backup: {}", e))?;
+    let manifest = read_manifest(&mut archive)?;
+    check_versions(&manifest)?;
+
+    let staging = staging_dir();
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging).map_err(|e| format!("Failed to clear {}: {}", staging.display(), e))?;
+    }
+    let unpacked = (|| -> Result<(), String> {
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i).map_err(|e| e.to_string())?;
+            let Some(name) = entry.enclosed_name() else {
+                return Err(format!("Unsafe path in backup: {}", entry.name()));
+            };
+            let target = staging.join(name);
+            if entry.is_dir() {
+                std::fs::create_dir_all(&target).map_err(|e| e.to_string())?;
+                continue;
+            }
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            let mut out = std::fs::File::create(&target).map_err(|e| format!("Failed to unpack {}: {}", target.display(), e))?;
+            std::io::copy(&mut entry, &mut out).map_err(|e| format!("Failed to unpack {}: {}", target.display(), e))?;
+        }
+
+        let db_name = if manifest.encrypted { "zigy.db.enc" } else { "zigy.db" };
+        if !staging.join(db_name).is_file() {
+            return Err(format!("The backup has no database ({})", db_name));
+        }
+        if !manifest.encrypted {
+            let conn = rusqlite::Connection::open(staging.join(db_name)).map_err(|e| e.to_string())?;
+            let integrity: String = conn
+                .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+                .map_err(|e| e.to_string())?;
+            if integrity != "ok" {
+                return Err(format!("The backup's database is damaged: {}", integrity));
+            }
+        }
+        Ok(())
+    })();
+    if let Err(e) = unpacked {
+        let _ = std::fs::remove_dir_all(&staging);
+        return Err(e);
+    }
+
+    info!("Backup from {} staged, restarting to restore it", path);
+    app_handle.restart();
+}
+
+/// Swap in a staged restore. Runs first thing at launch; returns whether a
+/// backup was restored.
+pub fn apply_pending_restore() -> Result<bool, String> {
+    let staging = staging_dir();
+    if !staging.is_dir() {
+        return Ok(false);
+    }
+    let data_dir = get_config_dir();
+    let previous = previous_dir();
+    if previous.exists() {
+        std::fs::remove_dir_all(&previous).map_err(|e| format!("Failed to remove {}: {}", previous.display(), e))?;
+    }
+    std::fs::rename(&data_dir, &previous).map_err(|e| format!("Failed to move the current data aside: {}", e))?;
+    if let Err(e) = std::fs::rename(&staging, &data_dir) {
+        let _ = std::fs::rename(&previous, &data_dir);
+        return Err(format!("Failed to restore the backup: {}", e));
+    }
+
+    let mut settings_files = vec!["settings.json".to_string()];
+    if let Ok(entries) = std::fs::read_dir(data_dir.join("profiles")) {
+        settings_files.extend(entries.flatten().map(|e| format!("profiles/{}", e.file_name().to_string_lossy())));
+    }
+    for name in settings_files.iter().filter(|name| holds_settings(name)) {
+        if let Err(e) = refill_secrets(&data_dir.join(name), &previous.join(name)) {
+            // Runs before logging is set up
+            eprintln!("Secrets not restored: {}", e);
+        }
+    }
+
+    // Recording paths point into the data directory the backup was made from
+    let manifest: Option<BackupManifest> = std::fs::read(data_dir.join(MANIFEST_NAME))
+        .ok()
+        .and_then(|json| serde_json::from_slice(&json).ok());
+    let _ = std::fs::remove_file(data_dir.join(MANIFEST_NAME));
+    if let Some(manifest) = manifest.filter(|m| !m.encrypted) {
+        let old_dir = manifest.data_dir;
+        let new_dir = data_dir.to_string_lossy().to_string();
+        if old_dir != new_dir {
+            let conn = rusqlite::Connection::open(database::get_db_path()).map_err(|e| e.to_string())?;
+            conn.execute(
+                "UPDATE sessions SET audio_path = ?2 || SUBSTR(audio_path, LENGTH(?1) + 1)
+                 WHERE SUBSTR(audio_path, 1, LENGTH(?1)) = ?1",
+                rusqlite::params![old_dir, new_dir],
+            )
+            .map_err(|e| format!("Failed to update recording paths: {}", e))?;
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_versions() {
+        let manifest = |format_version, schema_version| BackupManifest {
+            format_version,
+            schema_version,
+            app_version: "9.9.9".to_string(),
+            created_at: 0,
+            encrypted: false,
+            data_dir: String::new(),
+            files: 0,
+        };
+        assert!(check_versions(&manifest(FORMAT_VERSION, database::SCHEMA_VERSION)).is_ok());
+        assert!(check_versions(&manifest(FORMAT_VERSION, 0)).is_ok());
+        assert!(check_versions(&manifest(FORMAT_VERSION + 1, database::SCHEMA_VERSION)).is_err());
+        assert!(check_versions(&manifest(FORMAT_VERSION, database::SCHEMA_VERSION + 1)).is_err());
+    }
+}
@@ -0,0 +1,88 @@
+// Bookmarks: moments marked during a meeting
+//
+// `add_bookmark` (or the bookmark hotkey) stores the current time against the
+// running session, with an optional label such as "decision made here".
+// Bookmarks are listed in session exports next to the transcript line they
+// fall on, and emitted as `bookmark-added` for the UI.
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::info;
+
+use crate::{current_session_id, now_millis, AppState};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub id: String,
+    pub session_id: String,
+    pub timestamp: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+fn insert(conn: &Connection, bookmark: &Bookmark) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO bookmarks (id, session_id, timestamp, label) VALUES (?1, ?2, ?3, ?4)",
+        params![bookmark.id, bookmark.session_id, bookmark.timestamp, bookmark.label],
+    )?;
+    Ok(())
+}
+
+/// A session's bookmarks, oldest first
+pub fn session_bookmarks(conn: &Connection, session_id: &str) -> rusqlite::Result<Vec<Bookmark>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, session_id, timestamp, label FROM bookmarks WHERE session_id = ?1 ORDER BY timestamp ASC",
+    )?;
+    let bookmarks = stmt
+        .query_map(params![session_id], |row| {
+            Ok(Bookmark {
+                id: row.get(0)?,
+                session_id: row.get(1)?,
+                timestamp: row.get(2)?,
+                label: row.get(3)?,
+            })
+        })?
+        .collect();
+    bookmarks
+}
+
+/// Bookmark the current moment of the running session
+pub fn add(app_handle: &AppHandle, label: Option<String>) -> Result<Bookmark, String> {
+    let state = app_handle.state::<Arc<AppState>>();
+    let session_id = current_session_id(&state).ok_or("No session is running")?;
+    let bookmark = Bookmark {
+        id: uuid::Uuid::new_v4().to_string(),
+        session_id,
+        timestamp: now_millis(),
+        label: label.map(|l| l.trim().to_string()).filter(|l| !l.is_empty()),
+    };
+    let conn = state.db()?;
+    insert(&conn, &bookmark).map_err(|e| format!("Failed to save bookmark: {}", e))?;
+    drop(conn);
+    info!("Bookmarked session {} at {}", bookmark.session_id, bookmark.timestamp);
+    let _ = app_handle.emit("bookmark-added", &bookmark);
+    Ok(bookmark)
+}
+
+#[tauri::command]
+pub async fn add_bookmark(app_handle: AppHandle, label: Option<String>) -> Result<Bookmark, String> {
+    add(&app_handle, label)
+}
+
+#[tauri::command]
+pub async fn get_session_bookmarks(
+    state: tauri::State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<Vec<Bookmark>, String> {
+    let conn = state.db()?;
+    session_bookmarks(&conn, &session_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_bookmark(state: tauri::State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    let conn = state.db()?;
+    conn.execute("DELETE FROM bookmarks WHERE id = ?1", params![id])
+        .map_err(|e| format!("Failed to delete bookmark: {}", e))?;
+    Ok(())
+}
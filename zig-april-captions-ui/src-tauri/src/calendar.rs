@@ -0,0 +1,755 @@
+// Calendar integration
+//
+// Read-only: meetings come from iCalendar feeds listed in the calendar
+// settings: http(s) URLs such as Google Calendar's "secret address in iCal
+// format" or Outlook's published ICS link, or `webcal://` links. Feeds are
+// fetched at startup and every REFRESH_INTERVAL and kept in memory.
+//
+// When captions start during a meeting (or just before one), the session is
+// named after the event and its details (time, attendees, location,
+// description) become meeting context for the AI features until the session
+// ends. With `on_meeting_start` set, the frontend is asked whether to start
+// captions as a meeting begins ("prompt"), or captions start on their own
+// ("auto").
+//
+// Recurring events support DAILY, WEEKLY (with BYDAY) and MONTHLY (by day of
+// the month, or with BYDAY such as 2TU or -1FR) rules with INTERVAL, COUNT, UNTIL and EXDATE, and moved or cancelled instances. Other
+// rules only produce their first occurrence. Times without a known time zone
+// are taken as local time.
+use chrono::{Datelike, NaiveDate, NaiveDateTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+use crate::{database, now_millis, AppState};
+
+/// How often feeds are fetched again
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// How often the worker looks for meetings that are starting
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Captions started this long before a meeting still belong to it
+const EARLY_START_MS: i64 = 10 * 60_000;
+
+/// A meeting counts as starting for this long after its start time
+const START_GRACE_MS: i64 = 2 * 60_000;
+
+/// Occurrences are expanded from a day ago up to this far ahead
+const HORIZON_MS: i64 = 14 * 86_400_000;
+
+/// Length of timed events that have neither DTEND nor DURATION
+const DEFAULT_DURATION_MS: i64 = 30 * 60_000;
+
+/// Days walked per recurrence rule, so an old daily rule can't stall a refresh
+const MAX_RECURRENCE_DAYS: i64 = 20 * 366;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MeetingStart {
+    #[default]
+    Off,
+    Prompt, // Emit `meeting-starting` so the frontend can offer to start captions
+    Auto,   // Start captions with the saved model and audio source
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalendarSettings {
+    #[serde(default)]
+    pub feeds: Vec<String>, // ICS URLs
+    #[serde(default = "crate::default_true")]
+    pub auto_title: bool, // Name sessions after the current meeting
+    #[serde(default = "crate::default_true")]
+    pub auto_context: bool, // Use the meeting's details as AI meeting context
+    #[serde(default)]
+    pub on_meeting_start: MeetingStart,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Meeting {
+    pub uid: String,
+    pub title: String,
+    pub start: i64, // Unix ms
+    pub end: i64,
+    pub location: Option<String>,
+    pub description: Option<String>,
+    pub attendees: Vec<String>,
+}
+
+#[derive(Default)]
+struct Cache {
+    meetings: Vec<Meeting>,
+    fetched_at: Option<Instant>,
+    announced: HashSet<(String, i64)>, // Meetings already handled by the start check
+}
+
+/// Managed state: meetings from the last refresh
+#[derive(Default)]
+pub struct CalendarState {
+    cache: Mutex<Cache>,
+}
+
+fn calendar_settings(app_handle: &AppHandle) -> Option<CalendarSettings> {
+    app_handle
+        .state::<Arc<AppState>>()
+        .settings
+        .lock()
+        .ok()
+        .and_then(|s| s.calendar.clone())
+        .filter(|c| c.feeds.iter().any(|f| !f.trim().is_empty()))
+}
+
+// ---------------------------------------------------------------------------
+// iCalendar parsing
+
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+}
+
+/// Join folded lines (continuations start with a space or tab)
+fn unfold(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        match (line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+fn parse_property(line: &str) -> Option<Property> {
+    // The value starts at the first colon outside a quoted parameter
+    let mut quoted = false;
+    let colon = line.char_indices().find_map(|(i, c)| {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        (c == ':' && !quoted).then_some(i)
+    })?;
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+    let mut parts = head.split(';');
+    let name = parts.next()?.trim().to_uppercase();
+    let params = parts
+        .filter_map(|p| p.split_once('='))
+        .map(|(k, v)| (k.trim().to_uppercase(), v.trim_matches('"').to_string()))
+        .collect();
+    Some(Property { name, params, value: value.to_string() })
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
+
+#[derive(Clone, Copy)]
+enum Zone {
+    Utc,
+    Named(chrono_tz::Tz),
+    Local,
+}
+
+impl Zone {
+    fn to_millis(self, time: NaiveDateTime) -> Option<i64> {
+        // Times skipped by a DST change are moved past the gap
+        let shifted = time + chrono::Duration::hours(1);
+        match self {
+            Zone::Utc => Some(time.and_utc().timestamp_millis()),
+            Zone::Named(tz) => tz
+                .from_local_datetime(&time)
+                .earliest()
+                .or_else(|| tz.from_local_datetime(&shifted).earliest())
+                .map(|t| t.timestamp_millis()),
+            Zone::Local => chrono::Local
+                .from_local_datetime(&time)
+                .earliest()
+                .or_else(|| chrono::Local.from_local_datetime(&shifted).earliest())
+                .map(|t| t.timestamp_millis()),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct EventTime {
+    time: NaiveDateTime, // Wall-clock time in `zone`
+    zone: Zone,
+    all_day: bool,
+}
+
+impl EventTime {
+    fn millis(&self) -> Option<i64> {
+        self.zone.to_millis(self.time)
+    }
+}
+
+fn parse_time(property: &Property) -> Option<EventTime> {
+    let value = property.value.trim();
+    if property.param("VALUE") == Some("DATE") || value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return Some(EventTime { time: date.and_hms_opt(0, 0, 0)?, zone: Zone::Local, all_day: true });
+    }
+    let (value, zone) = match value.strip_suffix('Z') {
+        Some(utc) => (utc, Zone::Utc),
+        None => {
+            let zone = property
+                .param("TZID")
+                .and_then(|tz| tz.parse::<chrono_tz::Tz>().ok())
+                .map(Zone::Named)
+                .unwrap_or(Zone::Local);
+            (value, zone)
+        }
+    };
+    let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    Some(EventTime { time, zone, all_day: false })
+}
+
+/// `PT1H30M`, `P1D`, `-PT15M`, ... in milliseconds
+fn parse_duration(value: &str) -> Option<i64> {
+    let (sign, rest) = match value.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let rest = rest.strip_prefix('P')?;
+    let mut total = 0;
+    let mut number = String::new();
+    for c in rest.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => {}
+            _ => {
+                let n: i64 = number.parse().ok()?;
+                number.clear();
+                total += n * match c {
+                    'W' => 7 * 86_400_000,
+                    'D' => 86_400_000,
+                    'H' => 3_600_000,
+                    'M' => 60_000,
+                    'S' => 1000,
+                    _ => return None,
+                };
+            }
+        }
+    }
+    Some(sign * total)
+}
+
+#[derive(Default)]
+struct RawEvent {
+    uid: String,
+    summary: String,
+    description: Option<String>,
+    location: Option<String>,
+    start: Option<EventTime>,
+    end: Option<EventTime>,
+    duration: Option<i64>,
+    attendees: Vec<String>,
+    rrule: Option<String>,
+    exdates: HashSet<i64>,
+    recurrence_id: Option<i64>,
+    cancelled: bool,
+}
+
+fn parse_events(ics: &str) -> Vec<RawEvent> {
+    let mut events = Vec::new();
+    let mut current: Option<RawEvent> = None;
+    let mut nested = 0; // Depth of components inside the event (alarms)
+    for line in unfold(ics) {
+        let Some(property) = parse_property(&line) else {
+            continue;
+        };
+        match (property.name.as_str(), property.value.trim()) {
+            ("BEGIN", "VEVENT") => {
+                current = Some(RawEvent::default());
+                nested = 0;
+                continue;
+            }
+            ("END", "VEVENT") => {
+                events.extend(current.take());
+                continue;
+            }
+            ("BEGIN", _) if current.is_some() => nested += 1,
+            ("END", _) if current.is_some() => nested -= 1,
+            _ => {}
+        }
+        let Some(event) = current.as_mut().filter(|_| nested == 0) else {
+            continue;
+        };
+        match property.name.as_str() {
+            "UID" => event.uid = property.value.clone(),
+            "SUMMARY" => event.summary = unescape(&property.value),
+            "DESCRIPTION" => event.description = Some(unescape(&property.value)),
+            "LOCATION" => event.location = Some(unescape(&property.value)),
+            "DTSTART" => event.start = parse_time(&property),
+            "DTEND" => event.end = parse_time(&property),
+            "DURATION" => event.duration = parse_duration(property.value.trim()),
+            "RRULE" => event.rrule = Some(property.value.clone()),
+            "STATUS" => event.cancelled = property.value.trim().eq_ignore_ascii_case("CANCELLED"),
+            "RECURRENCE-ID" => event.recurrence_id = parse_time(&property).and_then(|t| t.millis()),
+            "EXDATE" => {
+                for value in property.value.split(',') {
+                    let single = Property {
+                        name: String::new(),
+                        params: property.params.clone(),
+                        value: value.to_string(),
+                    };
+                    if let Some(ms) = parse_time(&single).and_then(|t| t.millis()) {
+                        event.exdates.insert(ms);
+                    }
+                }
+            }
+            "ATTENDEE" => {
+                let name = property
+                    .param("CN")
+                    .map(String::from)
+                    .unwrap_or_else(|| property.value.trim_start_matches("mailto:").to_string());
+                if !name.is_empty() {
+                    event.attendees.push(name);
+                }
+            }
+            _ => {}
+        }
+    }
+    events
+}
+
+struct Rule {
+    freq: String,
+    interval: i64,
+    count: Option<usize>,
+    until: Option<i64>,
+    by_day: Vec<(Option<i64>, chrono::Weekday)>, // Ordinal within the month (MONTHLY), and the day
+}
+
+fn parse_rule(rrule: &str) -> Rule {
+    let mut rule = Rule { freq: String::new(), interval: 1, count: None, until: None, by_day: Vec::new() };
+    for (key, value) in rrule.split(';').filter_map(|p| p.split_once('=')) {
+        match key.to_uppercase().as_str() {
+            "FREQ" => rule.freq = value.to_uppercase(),
+            "INTERVAL" => rule.interval = value.parse().unwrap_or(1).max(1),
+            "COUNT" => rule.count = value.parse().ok(),
+            "UNTIL" => {
+                let until = Property { name: String::new(), params: Vec::new(), value: value.to_string() };
+                rule.until = parse_time(&until).and_then(|t| t.millis());
+            }
+            "BYDAY" => {
+                rule.by_day = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter_map(|d| {
+                        // An ordinal such as the 2 in 2TU or the -1 in -1FR
+                        let split = d.len().saturating_sub(2);
+                        let (ordinal, day) = d.split_at_checked(split)?;
+                        let ordinal = match ordinal {
+                            "" => None,
+                            n => Some(n.parse::<i64>().ok().filter(|n| *n != 0)?),
+                        };
+                        let day = match day.to_uppercase().as_str() {
+                            "MO" => chrono::Weekday::Mon,
+                            "TU" => chrono::Weekday::Tue,
+                            "WE" => chrono::Weekday::Wed,
+                            "TH" => chrono::Weekday::Thu,
+                            "FR" => chrono::Weekday::Fri,
+                            "SA" => chrono::Weekday::Sat,
+                            "SU" => chrono::Weekday::Sun,
+                            _ => return None,
+                        };
+                        Some((ordinal, day))
+                    })
+                    .collect()
+            }
+            _ => {}
+        }
+    }
+    rule
+}
+
+fn days_in_month(date: NaiveDate) -> u32 {
+    let (year, month) = if date.month() == 12 { (date.year() + 1, 1) } else { (date.year(), date.month() + 1) };
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|next| next.pred_opt())
+        .map(|last| last.day())
+        .unwrap_or(31)
+}
+
+/// Whether `date` is one of a MONTHLY rule's BYDAY days, e.g. the second
+/// Tuesday (2TU) or the last Friday (-1FR) of its month
+fn is_monthly_day(date: NaiveDate, by_day: &[(Option<i64>, chrono::Weekday)]) -> bool {
+    let from_start = (date.day() as i64 - 1) / 7 + 1;
+    let from_end = -((days_in_month(date) as i64 - date.day() as i64) / 7 + 1);
+    by_day.iter().any(|(ordinal, day)| {
+        *day == date.weekday() && ordinal.map(|n| n == from_start || n == from_end).unwrap_or(true)
+    })
+}
+
+/// Start times (wall clock) of a recurring event, up to `until_ms`
+fn occurrences(start: &EventTime, rule: &Rule, until_ms: i64) -> Vec<NaiveDateTime> {
+    if !matches!(rule.freq.as_str(), "DAILY" | "WEEKLY" | "MONTHLY") {
+        return vec![start.time];
+    }
+    let first = start.time.date();
+    let weekdays: Vec<chrono::Weekday> = if rule.by_day.is_empty() {
+        vec![first.weekday()]
+    } else {
+        rule.by_day.iter().map(|(_, day)| *day).collect()
+    };
+    let week_start = first - chrono::Duration::days(first.weekday().num_days_from_monday() as i64);
+    let mut times = Vec::new();
+    let mut seen = 0;
+    let mut date = first;
+    for _ in 0..MAX_RECURRENCE_DAYS {
+        let matches = match rule.freq.as_str() {
+            "DAILY" => (date - first).num_days() % rule.interval == 0,
+            "WEEKLY" => ((date - week_start).num_days() / 7) % rule.interval == 0 && weekdays.contains(&date.weekday()),
+            "MONTHLY" => {
+                let months = (date.year() - first.year()) as i64 * 12 + date.month() as i64 - first.month() as i64;
+                let day_matches = if rule.by_day.is_empty() {
+                    date.day() == first.day()
+                } else {
+                    is_monthly_day(date, &rule.by_day)
+                };
+                day_matches && months % rule.interval == 0
+            }
+            _ => false,
+        };
+        if matches {
+            let time = date.and_time(start.time.time());
+            let Some(ms) = start.zone.to_millis(time) else {
+                break;
+            };
+            if ms > until_ms || rule.until.map(|u| ms > u).unwrap_or(false) {
+                break;
+            }
+            times.push(time);
+            seen += 1;
+            if rule.count.map(|c| seen >= c).unwrap_or(false) {
+                break;
+            }
+        }
+        match date.succ_opt() {
+            Some(next) => date = next,
+            None => break,
+        }
+    }
+    times
+}
+
+/// Timed meetings overlapping [from, to], soonest first. All-day events are
+/// left out: they are rarely meetings.
+fn expand(events: Vec<RawEvent>, from: i64, to: i64) -> Vec<Meeting> {
+    // Instances moved or cancelled by an override, per event
+    let mut overridden: HashMap<String, HashSet<i64>> = HashMap::new();
+    for event in &events {
+        if let Some(recurrence_id) = event.recurrence_id {
+            overridden.entry(event.uid.clone()).or_default().insert(recurrence_id);
+        }
+    }
+
+    let mut meetings = Vec::new();
+    for event in events {
+        let Some(start) = event.start.filter(|s| !s.all_day) else {
+            continue;
+        };
+        if event.cancelled {
+            continue;
+        }
+        let Some(first_ms) = start.millis() else {
+            continue;
+        };
+        let duration = match (event.end.and_then(|e| e.millis()), event.duration) {
+            (Some(end), _) => (end - first_ms).max(0),
+            (None, Some(duration)) => duration.max(0),
+            (None, None) => DEFAULT_DURATION_MS,
+        };
+        let starts: Vec<i64> = match (&event.rrule, event.recurrence_id) {
+            (Some(rrule), None) => occurrences(&start, &parse_rule(rrule), to)
+                .into_iter()
+                .filter_map(|t| start.zone.to_millis(t))
+                .filter(|ms| !event.exdates.contains(ms))
+                .filter(|ms| !overridden.get(&event.uid).map(|o| o.contains(ms)).unwrap_or(false))
+                .collect(),
+            _ => vec![first_ms],
+        };
+        for start_ms in starts {
+            if start_ms + duration < from || start_ms > to {
+                continue;
+            }
+            meetings.push(Meeting {
+                uid: event.uid.clone(),
+                title: event.summary.trim().to_string(),
+                start: start_ms,
+                end: start_ms + duration,
+                location: event.location.clone().filter(|l| !l.trim().is_empty()),
+                description: event.description.clone().filter(|d| !d.trim().is_empty()),
+                attendees: event.attendees.clone(),
+            });
+        }
+    }
+    meetings.sort_by_key(|m| m.start);
+    meetings
+}
+
+// ---------------------------------------------------------------------------
+// Feeds
+
+async fn fetch_feed(feed: &str) -> Result<String, String> {
+    let feed = feed.trim();
+    let url = match feed.strip_prefix("webcal://") {
+        Some(rest) => format!("https://{}", rest),
+        None => feed.to_string(),
+    };
+    let scheme = url.split_once("://").map(|(scheme, _)| scheme.to_lowercase());
+    if !matches!(scheme.as_deref(), Some("https" | "http")) {
+        return Err(format!("Calendar feeds must be http(s) URLs: {}", feed));
+    }
+    let response = reqwest::Client::new()
+        .get(&url)
+        .timeout(Duration::from_secs(20))
+        .send()
+        .await
+        .map_err(|e| format!("Calendar request failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Calendar feed returned {}", response.status()));
+    }
+    response.text().await.map_err(|e| e.to_string())
+}
+
+/// Fetch every feed and replace the cached meetings
+async fn refresh(app_handle: &AppHandle, settings: &CalendarSettings) -> Result<usize, String> {
+    let now = now_millis();
+    let mut meetings = Vec::new();
+    let mut errors = Vec::new();
+    for feed in settings.feeds.iter().filter(|f| !f.trim().is_empty()) {
+        match fetch_feed(feed).await {
+            Ok(ics) => meetings.extend(expand(parse_events(&ics), now - 86_400_000, now + HORIZON_MS)),
+            Err(e) => errors.push(e),
+        }
+    }
+    // Keep the old meetings when no feed could be read, e.g. while offline
+    if meetings.is_empty() && !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+    for e in &errors {
+        warn!("{}", e);
+    }
+    meetings.sort_by_key(|m| m.start);
+    let count = meetings.len();
+    let state = app_handle.state::<CalendarState>();
+    let mut cache = state.cache.lock().map_err(|e| e.to_string())?;
+    cache.meetings = meetings;
+    cache.fetched_at = Some(Instant::now());
+    Ok(count)
+}
+
+fn is_stale(app_handle: &AppHandle) -> bool {
+    let state = app_handle.state::<CalendarState>();
+    let fetched_at = state.cache.lock().ok().and_then(|c| c.fetched_at);
+    fetched_at.map(|t| t.elapsed() >= REFRESH_INTERVAL).unwrap_or(true)
+}
+
+fn cached_meetings(app_handle: &AppHandle) -> Vec<Meeting> {
+    let state = app_handle.state::<CalendarState>();
+    let meetings = state.cache.lock().map(|c| c.meetings.clone());
+    meetings.unwrap_or_default()
+}
+
+/// The meeting going on at `now`, or about to start; the one that starts
+/// nearest to `now` when several overlap
+fn meeting_at(meetings: &[Meeting], now: i64) -> Option<&Meeting> {
+    meetings
+        .iter()
+        .filter(|m| m.start - EARLY_START_MS <= now && now < m.end)
+        .min_by_key(|m| (m.start - now).abs())
+}
+
+fn meeting_context(meeting: &Meeting) -> String {
+    let mut context = format!(
+        "Meeting: {}\nScheduled: {} ({} minutes)\n",
+        meeting.title,
+        crate::export::date_time(meeting.start),
+        (meeting.end - meeting.start) / 60_000
+    );
+    if !meeting.attendees.is_empty() {
+        context.push_str(&format!("Attendees: {}\n", meeting.attendees.join(", ")));
+    }
+    if let Some(location) = &meeting.location {
+        context.push_str(&format!("Location: {}\n", location));
+    }
+    if let Some(description) = &meeting.description {
+        context.push_str(&format!("\n{}\n", description.trim()));
+    }
+    context
+}
+
+/// Name a new session after the current meeting and use the meeting as AI
+/// context. Called when captions start.
+pub fn apply_to_session(app_handle: &AppHandle, session_id: &str) {
+    let Some(settings) = calendar_settings(app_handle) else {
+        return;
+    };
+    let meetings = cached_meetings(app_handle);
+    let Some(meeting) = meeting_at(&meetings, now_millis()) else {
+        return;
+    };
+    let state = app_handle.state::<Arc<AppState>>();
+    if settings.auto_title && !meeting.title.is_empty() {
+        let renamed = state
+            .db()
+            .and_then(|conn| database::rename_session(&conn, session_id, &meeting.title).map_err(|e| e.to_string()));
+        match renamed {
+            Ok(_) => info!("Session {} named after meeting \"{}\"", session_id, meeting.title),
+            Err(e) => warn!("Could not rename session {}: {}", session_id, e),
+        }
+    }
+    if settings.auto_context {
+        if let Ok(mut context) = state.meeting_context.lock() {
+            *context = Some(meeting_context(meeting));
+        }
+    }
+}
+
+/// Prompt for, or start, captions for a meeting that just began
+async fn check_meeting_start(app_handle: &AppHandle, settings: &CalendarSettings) {
+    if settings.on_meeting_start == MeetingStart::Off {
+        return;
+    }
+    let now = now_millis();
+    let starting = {
+        let state = app_handle.state::<CalendarState>();
+        let Ok(mut cache) = state.cache.lock() else {
+            return;
+        };
+        let meeting = cache
+            .meetings
+            .iter()
+            .find(|m| m.start <= now && now < m.start + START_GRACE_MS)
+            .cloned();
+        meeting.filter(|m| cache.announced.insert((m.uid.clone(), m.start)))
+    };
+    let Some(meeting) = starting else {
+        return;
+    };
+
+    let state = app_handle.state::<Arc<AppState>>();
+    let running = state.process.lock().map(|p| p.is_some()).unwrap_or(false);
+    if running {
+        return;
+    }
+    let mut auto_started = false;
+    if settings.on_meeting_start == MeetingStart::Auto {
+        match crate::start_with_saved_settings(app_handle).await {
+            Ok(()) => {
+                info!("Captions started for meeting \"{}\"", meeting.title);
+                auto_started = true;
+            }
+            Err(e) => warn!("Could not start captions for \"{}\": {}", meeting.title, e),
+        }
+    }
+    let _ = app_handle.emit(
+        "meeting-starting",
+        serde_json::json!({ "meeting": meeting, "autoStarted": auto_started }),
+    );
+}
+
+/// Keep feeds fresh and watch for meetings starting
+pub fn start(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            if let Some(settings) = calendar_settings(&app_handle) {
+                if is_stale(&app_handle) {
+                    if let Err(e) = refresh(&app_handle, &settings).await {
+                        warn!("Calendar refresh failed: {}", e);
+                    }
+                }
+                check_meeting_start(&app_handle, &settings).await;
+            }
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+/// Meetings in progress or starting within `hours` (default 24), soonest first
+#[tauri::command]
+pub async fn get_upcoming_meetings(app_handle: AppHandle, hours: Option<i64>) -> Result<Vec<Meeting>, String> {
+    let Some(settings) = calendar_settings(&app_handle) else {
+        return Ok(Vec::new());
+    };
+    if is_stale(&app_handle) {
+        refresh(&app_handle, &settings).await?;
+    }
+    let now = now_millis();
+    let until = now + hours.unwrap_or(24).clamp(1, HORIZON_MS / 3_600_000) * 3_600_000;
+    Ok(cached_meetings(&app_handle)
+        .into_iter()
+        .filter(|m| m.end > now && m.start <= until)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weekly_recurrence() {
+        let ics = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:standup\r\nSUMMARY:Stand\r\n up\\, daily\r\n\
+                   DTSTART:20260302T090000Z\r\nDTEND:20260302T091500Z\r\n\
+                   RRULE:FREQ=WEEKLY;BYDAY=MO,WE;COUNT=4\r\nEXDATE:20260304T090000Z\r\n\
+                   ATTENDEE;CN=\"Lan, PM\":mailto:lan@example.com\r\n\
+                   BEGIN:VALARM\r\nDESCRIPTION:Reminder\r\nEND:VALARM\r\nEND:VEVENT\r\n\
+                   BEGIN:VEVENT\r\nUID:standup\r\nRECURRENCE-ID:20260309T090000Z\r\nSUMMARY:Moved standup\r\n\
+                   DTSTART:20260309T100000Z\r\nDURATION:PT30M\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        let events = parse_events(ics);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].summary, "Standup, daily");
+        assert_eq!(events[0].description, None);
+        assert_eq!(events[0].attendees, vec!["Lan, PM"]);
+
+        let monday = 1_772_355_600_000; // 2026-03-02 09:00 UTC
+        let meetings = expand(events, 0, i64::MAX);
+        let starts: Vec<(i64, &str)> = meetings.iter().map(|m| (m.start - monday, m.title.as_str())).collect();
+        let hour = 3_600_000;
+        assert_eq!(
+            starts,
+            vec![(0, "Standup, daily"), (7 * 24 * hour + hour, "Moved standup"), (9 * 24 * hour, "Standup, daily")]
+        );
+        assert_eq!(meetings[1].end - meetings[1].start, 30 * 60_000);
+        assert_eq!(meeting_at(&meetings, monday + 5 * 60_000).map(|m| m.start), Some(monday));
+    }
+
+    #[test]
+    fn test_monthly_by_day() {
+        let start = EventTime {
+            time: NaiveDate::from_ymd_opt(2026, 3, 10).unwrap().and_hms_opt(9, 0, 0).unwrap(),
+            zone: Zone::Utc,
+            all_day: false,
+        };
+        let rule = parse_rule("FREQ=MONTHLY;BYDAY=2TU,-1FR;COUNT=4");
+        let days: Vec<(u32, u32)> = occurrences(&start, &rule, i64::MAX)
+            .iter()
+            .map(|t| (t.month(), t.day()))
+            .collect();
+        assert_eq!(days, vec![(3, 10), (3, 27), (4, 14), (4, 24)]);
+    }
+}
@@ -0,0 +1,98 @@
+// Batched writes of final captions
+//
+// A busy session produces several finals a second, from the engine and, when
+// both sides are captured, from the system audio recognizer too. Instead of a
+// transaction and a synced append to the session log for each of them, the
+// stdout readers queue finals here and a background task writes whatever is
+// queued every FLUSH_INTERVAL: the session logs are appended to and synced
+// once, and the lines (with their word timings) go into zigy.db in a single
+// transaction.
+//
+// Ending a session and shutting down flush what is left, so nothing queued is
+// lost to a clean stop; a crash loses at most one interval of captions. With
+// encryption on, the lines reach disk when the database is next sealed (every
+// autosave interval, at session end and at shutdown), never from here: sealing
+// rewrites the whole database image.
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tracing::warn;
+
+use crate::database::{self, NewCaptionLine};
+use crate::{encryption, recovery, AppState};
+
+/// How long finals wait before they are written
+const FLUSH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Finals waiting to be written, oldest first
+#[derive(Default)]
+pub struct CaptionBatch {
+    pending: Mutex<Vec<NewCaptionLine>>,
+}
+
+impl CaptionBatch {
+    pub fn push(&self, line: NewCaptionLine) {
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.push(line);
+        }
+    }
+
+    fn take(&self) -> Vec<NewCaptionLine> {
+        self.pending.lock().map(|mut pending| std::mem::take(&mut *pending)).unwrap_or_default()
+    }
+}
+
+/// Append lines to their sessions' logs, one synced write per session
+fn log_lines(lines: &[NewCaptionLine]) {
+    let mut start = 0;
+    while start < lines.len() {
+        let session_id = &lines[start].session_id;
+        let end = lines[start..]
+            .iter()
+            .position(|line| &line.session_id != session_id)
+            .map_or(lines.len(), |n| start + n);
+        let logged: Vec<recovery::LoggedLine> = lines[start..end]
+            .iter()
+            .map(|line| recovery::LoggedLine {
+                timestamp: line.timestamp,
+                text: line.text.clone(),
+                source: line.source.clone(),
+                speaker: line.speaker.clone(),
+            })
+            .collect();
+        if let Err(e) = recovery::append_transcript_lines(session_id, &logged) {
+            warn!("Failed to append to transcript log: {}", e);
+        }
+        start = end;
+    }
+}
+
+/// Write every queued final now
+pub fn flush(state: &AppState) {
+    let lines = state.finals.take();
+    if lines.is_empty() {
+        return;
+    }
+    if !encryption::is_enabled() {
+        log_lines(&lines);
+    }
+    let result = state
+        .db()
+        .and_then(|conn| database::add_caption_lines(&conn, &lines).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        warn!("Failed to save {} transcript lines: {}", lines.len(), e);
+    }
+}
+
+/// Flush queued finals every FLUSH_INTERVAL
+pub fn start(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(FLUSH_INTERVAL).await;
+            let state = app_handle.state::<Arc<AppState>>().inner().clone();
+            if let Err(e) = tauri::async_runtime::spawn_blocking(move || flush(&state)).await {
+                warn!("Caption flush failed: {}", e);
+            }
+        }
+    });
+}
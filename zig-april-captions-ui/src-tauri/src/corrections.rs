@@ -0,0 +1,263 @@
+// Caption correction rules
+//
+// User-defined substitutions applied to every final caption before it is
+// emitted or stored, for misrecognitions that keep coming back. A rule is
+// either literal text, matched as whole words, or a regular expression whose
+// replacement may refer to groups ("$1"). Rules run in list order, each on
+// the output of the previous one, after the custom vocabulary.
+//
+// The rules live in correction_rules.json; the compiled form is cached and
+// rebuilt after every change.
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+use tracing::warn;
+
+use crate::AppState;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorrectionRule {
+    pub id: String,
+    pub pattern: String,
+    pub replacement: String,
+    #[serde(default)]
+    pub is_regex: bool,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    #[serde(default = "crate::default_true")]
+    pub enabled: bool,
+}
+
+/// Fields to change on a rule
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CorrectionRuleUpdate {
+    #[serde(default)]
+    pub pattern: Option<String>,
+    #[serde(default)]
+    pub replacement: Option<String>,
+    #[serde(default)]
+    pub is_regex: Option<bool>,
+    #[serde(default)]
+    pub case_sensitive: Option<bool>,
+    #[serde(default)]
+    pub enabled: Option<bool>,
+}
+
+struct CompiledRule {
+    regex: Regex,
+    replacement: String,
+    expand: bool, // Whether "$1" in the replacement refers to a group
+}
+
+/// Enabled rules compiled, or None until the next caption rebuilds them
+#[derive(Default)]
+pub struct CompiledRules(Mutex<Option<Vec<CompiledRule>>>);
+
+impl CompiledRules {
+    fn invalidate(&self) {
+        if let Ok(mut compiled) = self.0.lock() {
+            *compiled = None;
+        }
+    }
+}
+
+fn compile(rule: &CorrectionRule) -> Result<CompiledRule, String> {
+    let pattern = if rule.is_regex {
+        rule.pattern.clone()
+    } else {
+        let escaped = regex::escape(rule.pattern.trim());
+        // Whole words only, unless the text starts or ends with punctuation
+        let starts_word = rule.pattern.trim().starts_with(|c: char| c.is_alphanumeric());
+        let ends_word = rule.pattern.trim().ends_with(|c: char| c.is_alphanumeric());
+        format!(
+            "{}{}{}",
+            if starts_word { r"\b" } else { "" },
+            escaped,
+            if ends_word { r"\b" } else { "" }
+        )
+    };
+    let regex = RegexBuilder::new(&pattern)
+        .case_insensitive(!rule.case_sensitive)
+        .build()
+        .map_err(|e| format!("Invalid pattern \"{}\": {}", rule.pattern, e))?;
+    Ok(CompiledRule {
+        regex,
+        replacement: rule.replacement.clone(),
+        expand: rule.is_regex,
+    })
+}
+
+fn validate(rule: &CorrectionRule) -> Result<(), String> {
+    if rule.pattern.trim().is_empty() {
+        return Err("Pattern cannot be empty".to_string());
+    }
+    let compiled = compile(rule)?;
+    if compiled.regex.is_match("") {
+        return Err(format!("Pattern \"{}\" matches empty text", rule.pattern));
+    }
+    Ok(())
+}
+
+fn apply_compiled(text: &str, rules: &[CompiledRule]) -> String {
+    let mut text = text.to_string();
+    for rule in rules {
+        let replaced = if rule.expand {
+            rule.regex.replace_all(&text, rule.replacement.as_str())
+        } else {
+            rule.regex.replace_all(&text, regex::NoExpand(&rule.replacement))
+        };
+        text = replaced.into_owned();
+    }
+    text
+}
+
+/// Apply the enabled rules to a final caption
+pub fn apply(app_handle: &AppHandle, text: &str) -> String {
+    let cache = app_handle.state::<CompiledRules>();
+    let Ok(mut compiled) = cache.0.lock() else {
+        return text.to_string();
+    };
+    if compiled.is_none() {
+        let state = app_handle.state::<Arc<AppState>>();
+        let rules = state.correction_rules.read().map(|r| r.clone()).unwrap_or_default();
+        let built = rules
+            .iter()
+            .filter(|r| r.enabled)
+            .filter_map(|r| {
+                compile(r)
+                    .map_err(|e| warn!("Skipping correction rule {}: {}", r.id, e))
+                    .ok()
+            })
+            .collect();
+        *compiled = Some(built);
+    }
+    match compiled.as_deref() {
+        Some(rules) if !rules.is_empty() => apply_compiled(text, rules),
+        _ => text.to_string(),
+    }
+}
+
+#[tauri::command]
+pub async fn get_correction_rules(state: tauri::State<'_, Arc<AppState>>) -> Result<Vec<CorrectionRule>, String> {
+    Ok(state.correction_rules.read()?.clone())
+}
+
+#[tauri::command]
+pub async fn add_correction_rule(
+    state: tauri::State<'_, Arc<AppState>>,
+    cache: tauri::State<'_, CompiledRules>,
+    pattern: String,
+    replacement: String,
+    is_regex: bool,
+    case_sensitive: bool,
+) -> Result<CorrectionRule, String> {
+    let rule = CorrectionRule {
+        id: uuid::Uuid::new_v4().to_string(),
+        pattern,
+        replacement,
+        is_regex,
+        case_sensitive,
+        enabled: true,
+    };
+    validate(&rule)?;
+    state.correction_rules.update(|rules| rules.push(rule.clone()))?;
+    cache.invalidate();
+    Ok(rule)
+}
+
+#[tauri::command]
+pub async fn update_correction_rule(
+    state: tauri::State<'_, Arc<AppState>>,
+    cache: tauri::State<'_, CompiledRules>,
+    id: String,
+    update: CorrectionRuleUpdate,
+) -> Result<CorrectionRule, String> {
+    let mut rule = state
+        .correction_rules
+        .read()?
+        .iter()
+        .find(|r| r.id == id)
+        .cloned()
+        .ok_or_else(|| "Correction rule not found".to_string())?;
+    if let Some(pattern) = update.pattern {
+        rule.pattern = pattern;
+    }
+    if let Some(replacement) = update.replacement {
+        rule.replacement = replacement;
+    }
+    if let Some(is_regex) = update.is_regex {
+        rule.is_regex = is_regex;
+    }
+    if let Some(case_sensitive) = update.case_sensitive {
+        rule.case_sensitive = case_sensitive;
+    }
+    if let Some(enabled) = update.enabled {
+        rule.enabled = enabled;
+    }
+    validate(&rule)?;
+    state.correction_rules.update(|rules| {
+        if let Some(existing) = rules.iter_mut().find(|r| r.id == id) {
+            *existing = rule.clone();
+        }
+    })?;
+    cache.invalidate();
+    Ok(rule)
+}
+
+#[tauri::command]
+pub async fn delete_correction_rule(
+    state: tauri::State<'_, Arc<AppState>>,
+    cache: tauri::State<'_, CompiledRules>,
+    id: String,
+) -> Result<(), String> {
+    state.correction_rules.update(|rules| rules.retain(|r| r.id != id))?;
+    cache.invalidate();
+    Ok(())
+}
+
+/// Replace the whole list, e.g. after reordering
+#[tauri::command]
+pub async fn save_correction_rules(
+    state: tauri::State<'_, Arc<AppState>>,
+    cache: tauri::State<'_, CompiledRules>,
+    rules: Vec<CorrectionRule>,
+) -> Result<(), String> {
+    for rule in &rules {
+        validate(rule)?;
+    }
+    state.correction_rules.update(|current| *current = rules)?;
+    cache.invalidate();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_rules() {
+        let rule = |pattern: &str, replacement: &str, is_regex, case_sensitive| CorrectionRule {
+            id: String::new(),
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+            is_regex,
+            case_sensitive,
+            enabled: true,
+        };
+        let rules: Vec<CompiledRule> = [
+            rule("jig", "Zig", false, false),
+            rule(r"(\d+) percent", "$1%", true, false),
+            rule("US", "U.S.", false, true),
+        ]
+        .iter()
+        .map(|r| compile(r).unwrap())
+        .collect();
+        assert_eq!(
+            apply_compiled("Jig is up 20 percent in the US, not a jigsaw for us", &rules),
+            "Zig is up 20% in the U.S., not a jigsaw for us"
+        );
+        assert!(validate(&rule("(", "", true, false)).is_err());
+        assert!(validate(&rule("a*", "", true, false)).is_err());
+    }
+}
@@ -0,0 +1,98 @@
+// Symmetric encryption helpers
+//
+// AES-256-GCM with a random 96-bit nonce per message, stored in front of the
+// ciphertext. Keys are raw 32-byte values; where they come from is up to the
+// caller.
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use sha2::Sha256;
+use std::path::Path;
+
+pub const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+
+pub type SecretKey = [u8; KEY_LEN];
+
+pub fn generate_key() -> SecretKey {
+    Aes256Gcm::generate_key(OsRng).into()
+}
+
+pub fn random_bytes(len: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; len];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
+/// PBKDF2-HMAC-SHA256 key for a passphrase
+pub fn derive_key(passphrase: &str, salt: &[u8], iterations: u32) -> SecretKey {
+    let mut key = [0u8; KEY_LEN];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, iterations, &mut key);
+    key
+}
+
+/// Nonce followed by the ciphertext and tag
+pub fn encrypt(key: &SecretKey, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| "Encryption failed".to_string())?;
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+pub fn decrypt(key: &SecretKey, sealed: &[u8]) -> Result<Vec<u8>, String> {
+    if sealed.len() < NONCE_LEN {
+        return Err("Encrypted data is truncated".to_string());
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Decryption failed: wrong key or corrupted data".to_string())
+}
+
+/// Read the key at `path`, creating it (readable by the owner only) if missing
+pub fn load_or_create_key(path: &Path) -> Result<SecretKey, String> {
+    match std::fs::read(path) {
+        Ok(bytes) => bytes
+            .try_into()
+            .map_err(|_| format!("Key file {} is corrupted", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let key = generate_key();
+            write_private(path, &key)?;
+            Ok(key)
+        }
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+/// Write a file only the current user can read
+pub fn write_private(path: &Path, contents: &[u8]) -> Result<(), String> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(path)
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    std::io::Write::write_all(&mut file, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let key = generate_key();
+        let sealed = encrypt(&key, b"the launch slips to May").unwrap();
+        assert_eq!(decrypt(&key, &sealed).unwrap(), b"the launch slips to May");
+        assert!(decrypt(&generate_key(), &sealed).is_err());
+    }
+}
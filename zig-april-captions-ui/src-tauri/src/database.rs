@@ -1,25 +1,186 @@
 // Database module for SQLite with vector support
-use rusqlite::{Connection, Result as SqliteResult, params};
+use rusqlite::serialize::OwnedData;
+use rusqlite::{ffi, params, Connection, DatabaseName, OptionalExtension, Result as SqliteResult};
 use std::path::PathBuf;
+use std::time::Duration;
 use serde::{Deserialize, Serialize};
 
+/// Version of the newest migration in MIGRATIONS, kept in `PRAGMA user_version`
+pub const SCHEMA_VERSION: i64 = 4;
+
+/// Statements each connection keeps prepared for `prepare_cached`
+const STATEMENT_CACHE_CAPACITY: usize = 64;
+
+/// How long a statement waits for another connection's lock before failing
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// Get the database path
 pub fn get_db_path() -> PathBuf {
-    let config_dir = dirs::config_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("zigy");
-    std::fs::create_dir_all(&config_dir).ok();
-    config_dir.join("zigy.db")
+    crate::get_config_dir().join("zigy.db")
 }
 
 /// Initialize the database with all required tables
 pub fn init_db() -> SqliteResult<Connection> {
+    crate::vector_index::register();
     let db_path = get_db_path();
     let conn = Connection::open(&db_path)?;
+    tune(&conn)?;
+    migrate(&conn, false)?;
+    Ok(conn)
+}
+
+/// Settings for writing captions while the UI reads. In WAL mode readers
+/// don't block the writer or each other, and synchronous=NORMAL only syncs at
+/// checkpoints: a power cut can lose the last commits but not corrupt the file.
+fn tune(conn: &Connection) -> SqliteResult<()> {
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
+    conn.pragma_update(None, "synchronous", "NORMAL")?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+    Ok(())
+}
+
+/// In-memory database with the same schema, used when the file can't be opened
+pub fn init_memory_db() -> SqliteResult<Connection> {
+    crate::vector_index::register();
+    let conn = Connection::open_in_memory()?;
+    migrate(&conn, false)?;
+    Ok(conn)
+}
+
+/// Copy of the whole database as one SQLite image
+pub fn serialize_db(conn: &Connection) -> SqliteResult<Vec<u8>> {
+    Ok(conn.serialize(DatabaseName::Main)?.to_vec())
+}
 
-    // Enable foreign keys
+/// In-memory database loaded from an image made by `serialize_db`
+pub fn load_db_image(image: &[u8]) -> SqliteResult<Connection> {
+    crate::vector_index::register();
+    let mut conn = Connection::open_in_memory()?;
+    let nomem = || rusqlite::Error::SqliteFailure(ffi::Error::new(ffi::SQLITE_NOMEM), None);
+    // SAFETY: the buffer is allocated by SQLite, filled completely and then
+    // owned by the connection, which frees it
+    let data = unsafe {
+        let ptr = ffi::sqlite3_malloc64(image.len().max(1) as u64) as *mut u8;
+        let ptr = std::ptr::NonNull::new(ptr).ok_or_else(nomem)?;
+        std::ptr::copy_nonoverlapping(image.as_ptr(), ptr.as_ptr(), image.len());
+        OwnedData::from_raw_nonnull(ptr, image.len())
+    };
+    conn.deserialize(DatabaseName::Main, data, false)?;
+    conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+    migrate(&conn, false)?;
+    Ok(conn)
+}
+
+/// A step of the schema, applied to databases below its version
+struct Migration {
+    version: i64,
+    description: &'static str,
+    up: fn(&Connection) -> SqliteResult<()>,
+}
+
+/// Every migration, oldest first. A schema change goes in a new migration at
+/// the end, with SCHEMA_VERSION bumped to its version; shipped migrations are
+/// never edited. The baseline grew in place before this list existed, so it
+/// is reapplied to every database (it only adds what is missing).
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "Baseline schema",
+        up: create_tables,
+    },
+    Migration {
+        version: 2,
+        description: "sqlite-vec index of chat and knowledge embeddings",
+        up: crate::vector_index::create,
+    },
+    Migration {
+        version: 3,
+        description: "Meeting template a session was started from",
+        up: add_session_template,
+    },
+    Migration {
+        version: 4,
+        description: "Caption id of transcript lines, for edits of the live transcript",
+        up: add_transcript_caption_id,
+    },
+];
+
+fn add_session_template(conn: &Connection) -> SqliteResult<()> {
+    conn.execute("ALTER TABLE sessions ADD COLUMN template_id TEXT", [])?;
+    Ok(())
+}
+
+fn add_transcript_caption_id(conn: &Connection) -> SqliteResult<()> {
+    conn.execute("ALTER TABLE transcript_lines ADD COLUMN caption_id TEXT", [])?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_transcript_lines_caption ON transcript_lines(caption_id)",
+        [],
+    )?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationReport {
+    pub from_version: i64,
+    pub to_version: i64,
+    pub applied: Vec<String>, // "<version>: <description>", oldest first
+    pub dry_run: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup: Option<String>, // Id of the snapshot taken before migrating
+}
+
+pub fn schema_version(conn: &Connection) -> SqliteResult<i64> {
+    conn.pragma_query_value(None, "user_version", |row| row.get(0))
+}
+
+fn has_tables(conn: &Connection) -> SqliteResult<bool> {
+    conn.query_row("SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table')", [], |row| row.get(0))
+}
+
+/// Bring the database up to SCHEMA_VERSION. The pending migrations run in one
+/// transaction, so a failing one leaves the database as it was; a dry run
+/// rolls them back once they have all applied. A database file that already
+/// has tables is snapshotted to backups/ before it is migrated. A database
+/// newer than this app is left at its version.
+pub fn migrate(conn: &Connection, dry_run: bool) -> SqliteResult<MigrationReport> {
     conn.execute("PRAGMA foreign_keys = ON", [])?;
+    let from_version = schema_version(conn)?;
+    let pending: Vec<&Migration> = MIGRATIONS.iter().filter(|m| m.version > from_version).collect();
+    let mut report = MigrationReport {
+        from_version,
+        to_version: from_version,
+        applied: Vec::new(),
+        dry_run,
+        backup: None,
+    };
 
+    let on_disk = conn.path().is_some_and(|path| !path.is_empty());
+    if !dry_run && !pending.is_empty() && on_disk && has_tables(conn)? {
+        let backup = crate::db_backups::backup_connection(conn)
+            .map_err(|e| rusqlite::Error::SqliteFailure(ffi::Error::new(ffi::SQLITE_IOERR), Some(e)))?;
+        report.backup = Some(backup.id);
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    if from_version >= 1 {
+        create_tables(&tx)?;
+    }
+    for migration in pending {
+        (migration.up)(&tx)?;
+        tx.pragma_update(None, "user_version", migration.version)?;
+        report.to_version = migration.version;
+        report.applied.push(format!("{}: {}", migration.version, migration.description));
+    }
+    if dry_run {
+        tx.rollback()?;
+    } else {
+        tx.commit()?;
+    }
+    Ok(report)
+}
+
+fn create_tables(conn: &Connection) -> SqliteResult<()> {
     // Create chat_entries table
     conn.execute(
         "CREATE TABLE IF NOT EXISTS chat_entries (
@@ -31,10 +192,21 @@ pub fn init_db() -> SqliteResult<Connection> {
             embedding BLOB,
             session_id TEXT,
             parent_id TEXT,
-            created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+            created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+            speaker TEXT,
+            compressed INTEGER NOT NULL DEFAULT 0,
+            pinned INTEGER NOT NULL DEFAULT 0
         )",
         [],
     )?;
+    add_column_if_missing(conn, "chat_entries", "speaker", "TEXT")?;
+    // Side ("me", "them" or an ingest source) and audio channel of transcript entries
+    add_column_if_missing(conn, "chat_entries", "source", "TEXT")?;
+    add_column_if_missing(conn, "chat_entries", "channel", "TEXT")?;
+    // Set once an entry is folded into a context snapshot
+    add_column_if_missing(conn, "chat_entries", "compressed", "INTEGER NOT NULL DEFAULT 0")?;
+    // Pinned entries are always part of the AI context and never compressed
+    add_column_if_missing(conn, "chat_entries", "pinned", "INTEGER NOT NULL DEFAULT 0")?;
 
     // Create indexes for common queries
     conn.execute(
@@ -57,10 +229,30 @@ pub fn init_db() -> SqliteResult<Connection> {
             content TEXT NOT NULL,
             created_at INTEGER NOT NULL,
             nominated INTEGER NOT NULL DEFAULT 1,
-            embedding BLOB
+            embedding BLOB,
+            folder TEXT
         )",
         [],
     )?;
+    add_column_if_missing(conn, "knowledge_entries", "folder", "TEXT")?;
+
+    // Knowledge folders and note tags (mirrored from the knowledge store)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS knowledge_folders (
+            name TEXT PRIMARY KEY,
+            created_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS knowledge_tags (
+            entry_id TEXT NOT NULL,
+            tag TEXT NOT NULL COLLATE NOCASE,
+            PRIMARY KEY (entry_id, tag)
+        )",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_knowledge_tags_tag ON knowledge_tags(tag)", [])?;
 
     // Create context_snapshots table
     conn.execute(
@@ -70,11 +262,253 @@ pub fn init_db() -> SqliteResult<Connection> {
             summary TEXT NOT NULL,
             covered_until INTEGER NOT NULL,
             original_token_count INTEGER NOT NULL,
-            compressed_token_count INTEGER NOT NULL
+            compressed_token_count INTEGER NOT NULL,
+            session_id TEXT
+        )",
+        [],
+    )?;
+    add_column_if_missing(conn, "context_snapshots", "session_id", "TEXT")?;
+
+    // Create sessions table (one row per captioning run)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS sessions (
+            id TEXT PRIMARY KEY,
+            title TEXT NOT NULL,
+            started_at INTEGER NOT NULL,
+            ended_at INTEGER,
+            audio_source TEXT,
+            model_path TEXT,
+            audio_path TEXT
+        )",
+        [],
+    )?;
+    add_column_if_missing(conn, "sessions", "audio_path", "TEXT")?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_sessions_started ON sessions(started_at DESC)",
+        [],
+    )?;
+
+    // Create transcript_lines table (final captions per session)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS transcript_lines (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id TEXT NOT NULL,
+            timestamp INTEGER NOT NULL,
+            text TEXT NOT NULL,
+            source TEXT,
+            speaker TEXT,
+            channel TEXT
+        )",
+        [],
+    )?;
+    add_column_if_missing(conn, "transcript_lines", "speaker", "TEXT")?;
+    add_column_if_missing(conn, "transcript_lines", "channel", "TEXT")?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_transcript_lines_session ON transcript_lines(session_id, timestamp)",
+        [],
+    )?;
+
+    // Create caption_words table (word timing and confidence of final captions)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS caption_words (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            caption_id TEXT NOT NULL,
+            session_id TEXT,
+            position INTEGER NOT NULL,
+            text TEXT NOT NULL,
+            start_ms INTEGER NOT NULL,
+            end_ms INTEGER NOT NULL,
+            confidence REAL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_caption_words_caption ON caption_words(caption_id, position)",
+        [],
+    )?;
+
+    // Create transcript_versions table (alternative transcripts of a session,
+    // e.g. from re-transcribing its recording with another model)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS transcript_versions (
+            id TEXT PRIMARY KEY,
+            session_id TEXT NOT NULL,
+            model_path TEXT,
+            created_at INTEGER NOT NULL,
+            lines TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_transcript_versions_session ON transcript_versions(session_id, created_at)",
+        [],
+    )?;
+
+    // Create tasks table (action items, extracted by AI or added by hand)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS tasks (
+            id TEXT PRIMARY KEY,
+            session_id TEXT,
+            text TEXT NOT NULL,
+            assignee TEXT,
+            due_date TEXT,
+            status TEXT NOT NULL DEFAULT 'open',
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_tasks_session ON tasks(session_id, status)",
+        [],
+    )?;
+
+    // Create tags and entity_mentions tables (people, companies, products and
+    // key terms found in sessions)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS tags (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL COLLATE NOCASE,
+            kind TEXT NOT NULL,
+            UNIQUE (name, kind)
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS entity_mentions (
+            tag_id INTEGER NOT NULL,
+            session_id TEXT NOT NULL,
+            mentions INTEGER NOT NULL DEFAULT 1,
+            PRIMARY KEY (tag_id, session_id)
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_entity_mentions_session ON entity_mentions(session_id)",
+        [],
+    )?;
+
+    // Create session_vocabulary table (per-session custom vocabulary terms)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS session_vocabulary (
+            session_id TEXT NOT NULL,
+            term TEXT NOT NULL,
+            sounds_like TEXT,
+            PRIMARY KEY (session_id, term)
+        )",
+        [],
+    )?;
+
+    // Create redacted_captions table (unredacted text of masked captions, encrypted)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS redacted_captions (
+            caption_id TEXT PRIMARY KEY,
+            session_id TEXT,
+            timestamp INTEGER NOT NULL,
+            original BLOB NOT NULL
         )",
         [],
     )?;
 
+    // Create session_sync table (pushes of sessions to external services)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS session_sync (
+            session_id TEXT NOT NULL,
+            service TEXT NOT NULL,
+            status TEXT NOT NULL,
+            remote_id TEXT,
+            url TEXT,
+            synced_at INTEGER,
+            error TEXT,
+            PRIMARY KEY (session_id, service)
+        )",
+        [],
+    )?;
+
+    // Create webhook_deliveries table (outcome of each outgoing webhook delivery)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS webhook_deliveries (
+            delivery_id TEXT PRIMARY KEY,
+            webhook_id TEXT NOT NULL,
+            event TEXT NOT NULL,
+            attempts INTEGER NOT NULL,
+            status INTEGER,
+            error TEXT,
+            delivered_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_delivered_at ON webhook_deliveries(delivered_at)",
+        [],
+    )?;
+
+    // Create metrics table (caption latency and throughput snapshots)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS metrics (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id TEXT,
+            recorded_at INTEGER NOT NULL,
+            elapsed_ms INTEGER NOT NULL,
+            partials INTEGER NOT NULL,
+            finals INTEGER NOT NULL,
+            words INTEGER NOT NULL,
+            words_per_minute REAL NOT NULL,
+            avg_latency_ms REAL,
+            p95_latency_ms INTEGER,
+            max_latency_ms INTEGER,
+            dropped_lines INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_metrics_session ON metrics(session_id, recorded_at)",
+        [],
+    )?;
+
+    // Create bookmarks table (moments marked during a session)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS bookmarks (
+            id TEXT PRIMARY KEY,
+            session_id TEXT NOT NULL,
+            timestamp INTEGER NOT NULL,
+            label TEXT
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_bookmarks_session ON bookmarks(session_id, timestamp)",
+        [],
+    )?;
+
+    // Create idea_versions table (every corrected script an idea has had)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS idea_versions (
+            idea_id TEXT NOT NULL,
+            version INTEGER NOT NULL,
+            corrected_script TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            PRIMARY KEY (idea_id, version)
+        )",
+        [],
+    )?;
+
+    // Create trash table (deleted knowledge notes, ideas and chat entries, as JSON)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS trash (
+            id TEXT PRIMARY KEY,
+            kind TEXT NOT NULL,
+            label TEXT NOT NULL,
+            deleted_at INTEGER NOT NULL,
+            payload TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_trash_deleted_at ON trash(deleted_at)",
+        [],
+    )?;
+
     // Create ideas table (for backward compatibility)
     conn.execute(
         "CREATE TABLE IF NOT EXISTS ideas (
@@ -87,7 +521,111 @@ pub fn init_db() -> SqliteResult<Connection> {
         [],
     )?;
 
-    Ok(conn)
+    // Key/value flags for one-time upgrades
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS app_meta (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    create_search_index(conn)?;
+
+    Ok(())
+}
+
+/// FTS5 index over chat and knowledge text, kept in sync by triggers
+fn create_search_index(conn: &Connection) -> SqliteResult<()> {
+    let exists = conn
+        .query_row("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'search_index'", [], |_| Ok(()))
+        .is_ok();
+
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS search_index USING fts5(
+            content,
+            source UNINDEXED,
+            entry_type UNINDEXED,
+            ref_id UNINDEXED,
+            timestamp UNINDEXED,
+            session_id UNINDEXED,
+            tokenize = 'unicode61 remove_diacritics 2'
+        );
+
+        -- INSERT OR REPLACE doesn't fire delete triggers, so inserts clear any stale row first
+        CREATE TRIGGER IF NOT EXISTS chat_entries_search_insert AFTER INSERT ON chat_entries BEGIN
+            DELETE FROM search_index WHERE source = 'chat' AND ref_id = new.id;
+            INSERT INTO search_index (content, source, entry_type, ref_id, timestamp, session_id)
+            VALUES (new.content, 'chat', new.entry_type, new.id, new.timestamp, new.session_id);
+        END;
+        CREATE TRIGGER IF NOT EXISTS chat_entries_search_update AFTER UPDATE OF content, entry_type, session_id ON chat_entries BEGIN
+            DELETE FROM search_index WHERE source = 'chat' AND ref_id = old.id;
+            INSERT INTO search_index (content, source, entry_type, ref_id, timestamp, session_id)
+            VALUES (new.content, 'chat', new.entry_type, new.id, new.timestamp, new.session_id);
+        END;
+        CREATE TRIGGER IF NOT EXISTS chat_entries_search_delete AFTER DELETE ON chat_entries BEGIN
+            DELETE FROM search_index WHERE source = 'chat' AND ref_id = old.id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS knowledge_entries_search_insert AFTER INSERT ON knowledge_entries BEGIN
+            DELETE FROM search_index WHERE source = 'knowledge' AND ref_id = new.id;
+            INSERT INTO search_index (content, source, entry_type, ref_id, timestamp)
+            VALUES (new.content, 'knowledge', 'knowledge', new.id, new.created_at);
+        END;
+        CREATE TRIGGER IF NOT EXISTS knowledge_entries_search_update AFTER UPDATE OF content ON knowledge_entries BEGIN
+            DELETE FROM search_index WHERE source = 'knowledge' AND ref_id = old.id;
+            INSERT INTO search_index (content, source, entry_type, ref_id, timestamp)
+            VALUES (new.content, 'knowledge', 'knowledge', new.id, new.created_at);
+        END;
+        CREATE TRIGGER IF NOT EXISTS knowledge_entries_search_delete AFTER DELETE ON knowledge_entries BEGIN
+            DELETE FROM search_index WHERE source = 'knowledge' AND ref_id = old.id;
+        END;",
+    )?;
+
+    // Index rows written before the index existed
+    if !exists {
+        conn.execute_batch(
+            "INSERT INTO search_index (content, source, entry_type, ref_id, timestamp, session_id)
+                SELECT content, 'chat', entry_type, id, timestamp, session_id FROM chat_entries;
+             INSERT INTO search_index (content, source, entry_type, ref_id, timestamp)
+                SELECT content, 'knowledge', 'knowledge', id, created_at FROM knowledge_entries;",
+        )?;
+    }
+    Ok(())
+}
+
+/// Add a column to a table created by an older version
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, decl: &str) -> SqliteResult<()> {
+    let exists = conn
+        .prepare(&format!("PRAGMA table_info({})", table))?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(|name| name.ok())
+        .any(|name| name == column);
+    if !exists {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl), [])?;
+    }
+    Ok(())
+}
+
+const JSON_MIGRATED_KEY: &str = "json_migrated_at";
+
+/// Whether the JSON files have already been imported
+pub fn is_json_migrated(conn: &Connection) -> bool {
+    conn.query_row("SELECT 1 FROM app_meta WHERE key = ?1", params![JSON_MIGRATED_KEY], |_| Ok(()))
+        .is_ok()
+}
+
+/// Record that the JSON import ran so it isn't repeated on later launches
+pub fn mark_json_migrated(conn: &Connection) -> SqliteResult<()> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64;
+    conn.execute(
+        "INSERT OR REPLACE INTO app_meta (key, value) VALUES (?1, ?2)",
+        params![JSON_MIGRATED_KEY, now.to_string()],
+    )?;
+    Ok(())
 }
 
 /// Chat history entry (matches JSON structure for migration)
@@ -99,6 +637,89 @@ pub struct ChatHistoryEntry {
     pub content: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub metadata: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>, // "me", "them" or an ingest source
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>, // "mic" or "monitor"
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+/// Columns read by `chat_entry_from_row`, in order
+pub const CHAT_ENTRY_COLUMNS: &str =
+    "id, timestamp, entry_type, content, metadata, session_id, speaker, source, channel, pinned";
+
+pub fn chat_entry_from_row(row: &rusqlite::Row) -> SqliteResult<ChatHistoryEntry> {
+    Ok(ChatHistoryEntry {
+        id: row.get(0)?,
+        timestamp: row.get(1)?,
+        entry_type: row.get(2)?,
+        content: row.get(3)?,
+        metadata: row
+            .get::<_, Option<String>>(4)?
+            .and_then(|s| serde_json::from_str(&s).ok()),
+        session_id: row.get(5)?,
+        speaker: row.get(6)?,
+        source: row.get(7)?,
+        channel: row.get(8)?,
+        pinned: row.get(9)?,
+    })
+}
+
+/// Insert a chat entry, or update the one with the same id. An existing entry
+/// keeps its pin, and its embedding unless the content changed.
+pub fn save_chat_entry(conn: &Connection, entry: &ChatHistoryEntry) -> SqliteResult<()> {
+    conn.prepare_cached(
+        "INSERT INTO chat_entries
+             (id, timestamp, entry_type, content, metadata, session_id, speaker, source, channel, pinned)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+         ON CONFLICT(id) DO UPDATE SET
+             timestamp = excluded.timestamp,
+             entry_type = excluded.entry_type,
+             embedding = CASE WHEN content = excluded.content THEN embedding END,
+             content = excluded.content,
+             metadata = excluded.metadata,
+             session_id = excluded.session_id,
+             speaker = excluded.speaker,
+             source = excluded.source,
+             channel = excluded.channel",
+    )?
+    .execute(params![
+        entry.id,
+        entry.timestamp,
+        entry.entry_type,
+        entry.content,
+        entry.metadata.as_ref().and_then(|m| serde_json::to_string(m).ok()),
+        entry.session_id,
+        entry.speaker,
+        entry.source,
+        entry.channel,
+        entry.pinned,
+    ])?;
+    Ok(())
+}
+
+/// Save entries in one transaction; none are saved if one fails
+pub fn save_chat_entries(conn: &Connection, entries: &[ChatHistoryEntry]) -> SqliteResult<()> {
+    let tx = conn.unchecked_transaction()?;
+    for entry in entries {
+        save_chat_entry(&tx, entry)?;
+    }
+    tx.commit()
+}
+
+/// Pinned entries of all sessions, oldest first
+pub fn pinned_entries(conn: &Connection) -> SqliteResult<Vec<ChatHistoryEntry>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM chat_entries WHERE pinned = 1 ORDER BY timestamp ASC",
+        CHAT_ENTRY_COLUMNS
+    ))?;
+    let entries = stmt.query_map([], chat_entry_from_row)?.collect();
+    entries
 }
 
 /// Migration statistics
@@ -112,9 +733,7 @@ pub struct MigrationStats {
 
 /// Migrate data from JSON files to SQLite
 pub fn migrate_from_json(conn: &mut Connection) -> Result<MigrationStats, String> {
-    let config_dir = dirs::config_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("zigy");
+    let config_dir = crate::get_config_dir();
 
     let mut stats = MigrationStats {
         chat_entries_migrated: 0,
@@ -185,14 +804,20 @@ pub fn migrate_from_json(conn: &mut Connection) -> Result<MigrationStats, String
 /// Migrate a single chat entry
 fn migrate_chat_entry(conn: &mut Connection, entry: &ChatHistoryEntry) -> SqliteResult<()> {
     conn.execute(
-        "INSERT OR IGNORE INTO chat_entries (id, timestamp, entry_type, content, metadata)
-         VALUES (?1, ?2, ?3, ?4, ?5)",
+        "INSERT OR IGNORE INTO chat_entries
+             (id, timestamp, entry_type, content, metadata, session_id, speaker, source, channel, pinned)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
         params![
             &entry.id,
             &entry.timestamp,
             &entry.entry_type,
             &entry.content,
             &entry.metadata.as_ref().map(|m| serde_json::to_string(m).ok()).flatten(),
+            &entry.session_id,
+            &entry.speaker,
+            &entry.source,
+            &entry.channel,
+            &entry.pinned,
         ],
     )?;
     Ok(())
@@ -257,13 +882,15 @@ pub struct ContextSnapshot {
     pub covered_until: i64,
     pub original_token_count: i64,
     pub compressed_token_count: i64,
+    #[serde(default)]
+    pub session_id: Option<String>,
 }
 
 /// Migrate a single context snapshot
 fn migrate_context_snapshot(conn: &mut Connection, snapshot: &ContextSnapshot) -> SqliteResult<()> {
     conn.execute(
-        "INSERT OR IGNORE INTO context_snapshots (id, created_at, summary, covered_until, original_token_count, compressed_token_count)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        "INSERT OR IGNORE INTO context_snapshots (id, created_at, summary, covered_until, original_token_count, compressed_token_count, session_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
         params![
             &snapshot.id,
             &snapshot.created_at,
@@ -271,13 +898,642 @@ fn migrate_context_snapshot(conn: &mut Connection, snapshot: &ContextSnapshot) -
             &snapshot.covered_until,
             &snapshot.original_token_count,
             &snapshot.compressed_token_count,
+            &snapshot.session_id,
         ],
     )?;
     Ok(())
 }
 
+/// A captioning run: opened by `start_captions`, closed by `stop_captions`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub id: String,
+    pub title: String,
+    pub started_at: i64,
+    pub ended_at: Option<i64>,
+    pub audio_source: Option<String>,
+    pub model_path: Option<String>,
+    pub audio_path: Option<String>, // WAV recording, when record_audio was on
+}
+
+fn session_from_row(row: &rusqlite::Row) -> SqliteResult<Session> {
+    Ok(Session {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        started_at: row.get(2)?,
+        ended_at: row.get(3)?,
+        audio_source: row.get(4)?,
+        model_path: row.get(5)?,
+        audio_path: row.get(6)?,
+    })
+}
+
+const SESSION_COLUMNS: &str = "id, title, started_at, ended_at, audio_source, model_path, audio_path";
+
+/// Open a new session titled after its local start time
+pub fn start_session(
+    conn: &Connection,
+    started_at: i64,
+    audio_source: &str,
+    model_path: &str,
+) -> SqliteResult<Session> {
+    let id = uuid::Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO sessions (id, title, started_at, audio_source, model_path)
+         VALUES (?1, 'Session ' || strftime('%Y-%m-%d %H:%M', ?2 / 1000, 'unixepoch', 'localtime'), ?2, ?3, ?4)",
+        params![id, started_at, audio_source, model_path],
+    )?;
+    get_session(conn, &id)?.ok_or(rusqlite::Error::QueryReturnedNoRows)
+}
+
+/// Mark a session as finished
+pub fn end_session(conn: &Connection, id: &str, ended_at: i64) -> SqliteResult<()> {
+    conn.execute(
+        "UPDATE sessions SET ended_at = ?2 WHERE id = ?1 AND ended_at IS NULL",
+        params![id, ended_at],
+    )?;
+    Ok(())
+}
+
+pub fn set_session_audio_path(conn: &Connection, id: &str, audio_path: &str) -> SqliteResult<()> {
+    conn.execute("UPDATE sessions SET audio_path = ?2 WHERE id = ?1", params![id, audio_path])?;
+    Ok(())
+}
+
+pub fn set_session_model(conn: &Connection, id: &str, model_path: &str) -> SqliteResult<()> {
+    conn.execute("UPDATE sessions SET model_path = ?2 WHERE id = ?1", params![id, model_path])?;
+    Ok(())
+}
+
+pub fn rename_session(conn: &Connection, id: &str, title: &str) -> SqliteResult<bool> {
+    let changed = conn.execute("UPDATE sessions SET title = ?2 WHERE id = ?1", params![id, title])?;
+    Ok(changed > 0)
+}
+
+pub fn get_session(conn: &Connection, id: &str) -> SqliteResult<Option<Session>> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM sessions WHERE id = ?1", SESSION_COLUMNS))?;
+    let mut rows = stmt.query_map(params![id], session_from_row)?;
+    rows.next().transpose()
+}
+
+/// Record the meeting template a session was started from
+pub fn set_session_template(conn: &Connection, id: &str, template_id: &str) -> SqliteResult<()> {
+    conn.execute("UPDATE sessions SET template_id = ?2 WHERE id = ?1", params![id, template_id])?;
+    Ok(())
+}
+
+pub fn session_template(conn: &Connection, id: &str) -> SqliteResult<Option<String>> {
+    conn.query_row("SELECT template_id FROM sessions WHERE id = ?1", params![id], |row| row.get(0))
+        .optional()
+        .map(Option::flatten)
+}
+
+/// All sessions, most recent first
+pub fn list_sessions(conn: &Connection) -> SqliteResult<Vec<Session>> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM sessions ORDER BY started_at DESC", SESSION_COLUMNS))?;
+    let sessions = stmt.query_map([], session_from_row)?.collect();
+    sessions
+}
+
+/// Record a final caption line against its session
+pub fn add_transcript_line(
+    conn: &Connection,
+    session_id: &str,
+    timestamp: i64,
+    text: &str,
+    source: Option<&str>,
+    speaker: Option<&str>,
+) -> SqliteResult<()> {
+    add_caption_line(conn, session_id, timestamp, text, source, None, speaker)
+}
+
+/// Record a final caption line along with the audio channel it was heard on
+pub fn add_caption_line(
+    conn: &Connection,
+    session_id: &str,
+    timestamp: i64,
+    text: &str,
+    source: Option<&str>,
+    channel: Option<&str>,
+    speaker: Option<&str>,
+) -> SqliteResult<()> {
+    conn.prepare_cached(
+        "INSERT INTO transcript_lines (session_id, timestamp, text, source, speaker, channel)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+    )?
+    .execute(params![session_id, timestamp, text, source, speaker, channel])?;
+    Ok(())
+}
+
+/// One recognized word; times are epoch milliseconds like caption timestamps
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptionWord {
+    pub text: String,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f32>, // 0..1, None when the recognizer doesn't report it
+}
+
+fn insert_caption_words(
+    conn: &Connection,
+    caption_id: &str,
+    session_id: Option<&str>,
+    words: &[CaptionWord],
+) -> SqliteResult<()> {
+    let mut stmt = conn.prepare_cached(
+        "INSERT INTO caption_words (caption_id, session_id, position, text, start_ms, end_ms, confidence)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )?;
+    for (position, word) in words.iter().enumerate() {
+        stmt.execute(params![
+            caption_id,
+            session_id,
+            position as i64,
+            word.text,
+            word.start_ms,
+            word.end_ms,
+            word.confidence
+        ])?;
+    }
+    Ok(())
+}
+
+pub fn add_caption_words(
+    conn: &Connection,
+    caption_id: &str,
+    session_id: Option<&str>,
+    words: &[CaptionWord],
+) -> SqliteResult<()> {
+    let tx = conn.unchecked_transaction()?;
+    insert_caption_words(&tx, caption_id, session_id, words)?;
+    tx.commit()
+}
+
+/// A final caption waiting to be written by `add_caption_lines`
+#[derive(Debug, Clone)]
+pub struct NewCaptionLine {
+    pub session_id: String,
+    pub timestamp: i64,
+    pub text: String,
+    pub source: Option<String>,
+    pub channel: Option<String>,
+    pub speaker: Option<String>,
+    pub caption_id: Option<String>, // Id of the final caption; `words` are kept under it
+    pub words: Vec<CaptionWord>,
+}
+
+/// Record final caption lines and their words in one transaction
+pub fn add_caption_lines(conn: &Connection, lines: &[NewCaptionLine]) -> SqliteResult<()> {
+    let tx = conn.unchecked_transaction()?;
+    {
+        let mut insert = tx.prepare_cached(
+            "INSERT INTO transcript_lines (session_id, timestamp, text, source, speaker, channel, caption_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        )?;
+        for line in lines {
+            insert.execute(params![
+                line.session_id,
+                line.timestamp,
+                line.text,
+                line.source,
+                line.speaker,
+                line.channel,
+                line.caption_id
+            ])?;
+            if let Some(caption_id) = line.caption_id.as_deref() {
+                insert_caption_words(&tx, caption_id, Some(&line.session_id), &line.words)?;
+            }
+        }
+    }
+    tx.commit()
+}
+
+/// Give the first of the captions `caption_ids` the text `text` and remove
+/// the others, for a line of the live transcript edited or merged by hand
+pub fn set_caption_text(conn: &Connection, caption_ids: &[String], text: &str) -> SqliteResult<()> {
+    let Some((first, rest)) = caption_ids.split_first() else {
+        return Ok(());
+    };
+    conn.execute("UPDATE transcript_lines SET text = ?1 WHERE caption_id = ?2", params![text, first])?;
+    delete_captions(conn, rest)
+}
+
+/// Remove the stored rows and words of the captions `caption_ids`
+pub fn delete_captions(conn: &Connection, caption_ids: &[String]) -> SqliteResult<()> {
+    for caption_id in caption_ids {
+        conn.execute("DELETE FROM transcript_lines WHERE caption_id = ?1", params![caption_id])?;
+        conn.execute("DELETE FROM caption_words WHERE caption_id = ?1", params![caption_id])?;
+    }
+    Ok(())
+}
+
+/// Words of a final caption in spoken order
+pub fn caption_words(conn: &Connection, caption_id: &str) -> SqliteResult<Vec<CaptionWord>> {
+    let mut stmt = conn.prepare(
+        "SELECT text, start_ms, end_ms, confidence FROM caption_words
+         WHERE caption_id = ?1 ORDER BY position ASC",
+    )?;
+    let words = stmt
+        .query_map(params![caption_id], |row| {
+            Ok(CaptionWord {
+                text: row.get(0)?,
+                start_ms: row.get(1)?,
+                end_ms: row.get(2)?,
+                confidence: row.get(3)?,
+            })
+        })?
+        .collect();
+    words
+}
+
+/// Transcript of a session, oldest line first
+pub fn session_transcript(conn: &Connection, session_id: &str) -> SqliteResult<Vec<String>> {
+    // Lines with a speaker read "Alice: text"
+    let mut stmt = conn.prepare(
+        "SELECT COALESCE(speaker || ': ', '') || text FROM transcript_lines
+         WHERE session_id = ?1 ORDER BY timestamp ASC, id ASC",
+    )?;
+    let lines = stmt.query_map(params![session_id], |row| row.get(0))?.collect();
+    lines
+}
+
+/// A final caption line as stored for a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptLine {
+    pub timestamp: i64,
+    pub text: String,
+    pub speaker: Option<String>,
+}
+
+/// Transcript of a session with timestamps and speakers, oldest line first
+pub fn session_transcript_lines(conn: &Connection, session_id: &str) -> SqliteResult<Vec<TranscriptLine>> {
+    let mut stmt = conn.prepare(
+        "SELECT timestamp, text, speaker FROM transcript_lines
+         WHERE session_id = ?1 ORDER BY timestamp ASC, id ASC",
+    )?;
+    let lines = stmt
+        .query_map(params![session_id], |row| {
+            Ok(TranscriptLine {
+                timestamp: row.get(0)?,
+                text: row.get(1)?,
+                speaker: row.get(2)?,
+            })
+        })?
+        .collect();
+    lines
+}
+
+/// A transcript kept alongside a session's current one; `lines` is stored as JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptVersion {
+    pub id: String,
+    pub session_id: String,
+    pub model_path: Option<String>,
+    pub created_at: i64,
+    pub lines: Vec<TranscriptLine>,
+}
+
+pub fn add_transcript_version(conn: &Connection, version: &TranscriptVersion) -> SqliteResult<()> {
+    let lines = serde_json::to_string(&version.lines).map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))?;
+    conn.execute(
+        "INSERT INTO transcript_versions (id, session_id, model_path, created_at, lines) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![version.id, version.session_id, version.model_path, version.created_at, lines],
+    )?;
+    Ok(())
+}
+
+/// Stored versions of a session's transcript, oldest first
+pub fn transcript_versions(conn: &Connection, session_id: &str) -> SqliteResult<Vec<TranscriptVersion>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, session_id, model_path, created_at, lines FROM transcript_versions
+         WHERE session_id = ?1 ORDER BY created_at ASC",
+    )?;
+    let versions = stmt
+        .query_map(params![session_id], |row| {
+            let lines: String = row.get(4)?;
+            Ok(TranscriptVersion {
+                id: row.get(0)?,
+                session_id: row.get(1)?,
+                model_path: row.get(2)?,
+                created_at: row.get(3)?,
+                lines: serde_json::from_str(&lines).unwrap_or_default(),
+            })
+        })?
+        .collect();
+    versions
+}
+
+/// Swap a session's transcript for `lines`, dropping the old word timings
+pub fn replace_transcript(
+    conn: &Connection,
+    session_id: &str,
+    lines: &[TranscriptLine],
+    source: &str,
+    model_path: &str,
+) -> SqliteResult<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("DELETE FROM transcript_lines WHERE session_id = ?1", params![session_id])?;
+    tx.execute("DELETE FROM caption_words WHERE session_id = ?1", params![session_id])?;
+    for line in lines {
+        add_transcript_line(&tx, session_id, line.timestamp, &line.text, Some(source), line.speaker.as_deref())?;
+    }
+    tx.execute("UPDATE sessions SET model_path = ?2 WHERE id = ?1", params![session_id, model_path])?;
+    tx.commit()
+}
+
+/// State of a session's copy in an external service such as Notion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSync {
+    pub session_id: String,
+    pub service: String,
+    pub status: String, // "pending", "synced" or "failed"
+    pub remote_id: Option<String>,
+    pub url: Option<String>,
+    pub synced_at: Option<i64>, // Last successful push
+    pub error: Option<String>,
+}
+
+fn session_sync_from_row(row: &rusqlite::Row) -> SqliteResult<SessionSync> {
+    Ok(SessionSync {
+        session_id: row.get(0)?,
+        service: row.get(1)?,
+        status: row.get(2)?,
+        remote_id: row.get(3)?,
+        url: row.get(4)?,
+        synced_at: row.get(5)?,
+        error: row.get(6)?,
+    })
+}
+
+pub fn set_session_sync(conn: &Connection, sync: &SessionSync) -> SqliteResult<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO session_sync (session_id, service, status, remote_id, url, synced_at, error)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![sync.session_id, sync.service, sync.status, sync.remote_id, sync.url, sync.synced_at, sync.error],
+    )?;
+    Ok(())
+}
+
+pub fn session_sync(conn: &Connection, session_id: &str, service: &str) -> SqliteResult<Option<SessionSync>> {
+    let mut stmt = conn.prepare(
+        "SELECT session_id, service, status, remote_id, url, synced_at, error FROM session_sync
+         WHERE session_id = ?1 AND service = ?2",
+    )?;
+    let mut rows = stmt.query_map(params![session_id, service], session_sync_from_row)?;
+    rows.next().transpose()
+}
+
+pub fn session_sync_all(conn: &Connection, session_id: &str) -> SqliteResult<Vec<SessionSync>> {
+    let mut stmt = conn.prepare(
+        "SELECT session_id, service, status, remote_id, url, synced_at, error FROM session_sync
+         WHERE session_id = ?1 ORDER BY service",
+    )?;
+    let rows = stmt.query_map(params![session_id], session_sync_from_row)?.collect();
+    rows
+}
+
+/// How long captioning history is kept
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "mode", rename_all = "lowercase")]
+pub enum RetentionPolicy {
+    #[default]
+    Forever,
+    Days { days: u32 },         // Drop sessions and entries older than this
+    Sessions { count: u32 },    // Keep the most recent sessions only
+}
+
+/// What a cleanup pass removed (or would remove, for a dry run)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CleanupReport {
+    pub dry_run: bool,
+    pub sessions: usize,
+    pub transcript_lines: usize,
+    pub chat_entries: usize,
+    pub snapshots: usize,
+    pub expired_session_ids: Vec<String>,
+    pub audio_files: Vec<String>,
+    pub freed_bytes: u64, // Row contents plus deleted files; approximate
+    #[serde(skip)]
+    pub cutoff: Option<i64>, // Entries without a session older than this expire too
+}
+
+/// (session id, audio path) pairs and the age cutoff, see `expired_sessions`
+type ExpiredSessions = (Vec<(String, Option<String>)>, Option<i64>);
+
+/// Sessions the policy expires, and the age limit for data without a session
+fn expired_sessions(
+    conn: &Connection,
+    policy: &RetentionPolicy,
+    now: i64,
+    keep_session: Option<&str>,
+) -> SqliteResult<ExpiredSessions> {
+    let mut stmt = conn.prepare("SELECT id, started_at, audio_path FROM sessions ORDER BY started_at DESC")?;
+    let sessions: Vec<(String, i64, Option<String>)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<SqliteResult<_>>()?;
+
+    let (expired, cutoff): (Vec<_>, Option<i64>) = match policy {
+        RetentionPolicy::Forever => (Vec::new(), None),
+        RetentionPolicy::Days { days } => {
+            let cutoff = now - i64::from(*days) * 24 * 60 * 60 * 1000;
+            (sessions.into_iter().filter(|s| s.1 < cutoff).collect(), Some(cutoff))
+        }
+        RetentionPolicy::Sessions { count } => {
+            let count = *count as usize;
+            let cutoff = match count {
+                _ if sessions.len() <= count => None,
+                0 => Some(now),
+                _ => Some(sessions[count - 1].1), // Start of the oldest kept session
+            };
+            (sessions.into_iter().skip(count).collect(), cutoff)
+        }
+    };
+    let expired = expired
+        .into_iter()
+        .filter(|s| Some(s.0.as_str()) != keep_session)
+        .map(|(id, _, audio)| (id, audio))
+        .collect();
+    Ok((expired, cutoff))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VacuumReport {
+    pub before_bytes: u64,
+    pub after_bytes: u64,
+}
+
+/// Size of the database: its file and WAL, or its pages when in memory
+fn database_size(conn: &Connection) -> SqliteResult<u64> {
+    if let Some(path) = conn.path().filter(|p| !p.is_empty()) {
+        return Ok(["", "-wal"]
+            .iter()
+            .filter_map(|suffix| std::fs::metadata(format!("{}{}", path, suffix)).ok())
+            .map(|m| m.len())
+            .sum());
+    }
+    let pages: u64 = conn.pragma_query_value(None, "page_count", |row| row.get(0))?;
+    let page_size: u64 = conn.pragma_query_value(None, "page_size", |row| row.get(0))?;
+    Ok(pages * page_size)
+}
+
+/// Rewrite the database without the pages freed by deletions
+pub fn vacuum(conn: &Connection) -> SqliteResult<VacuumReport> {
+    let before_bytes = database_size(conn)?;
+    conn.execute_batch("VACUUM")?;
+    // VACUUM goes through the WAL; fold it back in and truncate it
+    conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+    Ok(VacuumReport { before_bytes, after_bytes: database_size(conn)? })
+}
+
+/// Delete sessions expired by `policy` with their transcripts, chat entries
+/// and snapshots. Nothing is deleted on a dry run. Audio files are listed in
+/// the report for the caller to remove.
+pub fn cleanup(
+    conn: &Connection,
+    policy: &RetentionPolicy,
+    now: i64,
+    keep_session: Option<&str>,
+    dry_run: bool,
+) -> SqliteResult<CleanupReport> {
+    let (expired, cutoff) = expired_sessions(conn, policy, now, keep_session)?;
+    let mut report = CleanupReport {
+        dry_run,
+        cutoff,
+        ..Default::default()
+    };
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("CREATE TEMP TABLE IF NOT EXISTS cleanup_sessions (id TEXT PRIMARY KEY)", [])?;
+    tx.execute("DELETE FROM cleanup_sessions", [])?;
+    for (id, audio_path) in &expired {
+        tx.execute("INSERT OR IGNORE INTO cleanup_sessions (id) VALUES (?1)", params![id])?;
+        report.expired_session_ids.push(id.clone());
+        report.audio_files.extend(audio_path.clone());
+    }
+    report.sessions = expired.len();
+
+    // Rows belong to an expired session, or have no session and are too old.
+    // Pinned chat entries are kept whatever their age.
+    let cutoff = cutoff.unwrap_or(i64::MIN);
+    let targets = [
+        ("transcript_lines", "timestamp", "LENGTH(text)", "1"),
+        ("caption_words", "start_ms", "LENGTH(text) + 24", "1"),
+        (
+            "chat_entries",
+            "timestamp",
+            "LENGTH(content) + COALESCE(LENGTH(metadata), 0) + COALESCE(LENGTH(embedding), 0)",
+            "pinned = 0",
+        ),
+        ("context_snapshots", "created_at", "LENGTH(summary)", "1"),
+        ("transcript_versions", "created_at", "LENGTH(lines)", "1"),
+        ("redacted_captions", "timestamp", "LENGTH(original)", "1"),
+    ];
+    for (table, time_column, size, keep) in targets {
+        let filter = format!(
+            "({}) AND (session_id IN (SELECT id FROM cleanup_sessions) OR (session_id IS NULL AND {} < ?1))",
+            keep, time_column
+        );
+        let (rows, bytes): (i64, i64) = tx.query_row(
+            &format!("SELECT COUNT(*), COALESCE(SUM({}), 0) FROM {} WHERE {}", size, table, filter),
+            params![cutoff],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        report.freed_bytes += bytes as u64;
+        match table {
+            "transcript_lines" => report.transcript_lines = rows as usize,
+            "chat_entries" => report.chat_entries = rows as usize,
+            _ => {}
+        }
+        if !dry_run {
+            tx.execute(&format!("DELETE FROM {} WHERE {}", table, filter), params![cutoff])?;
+        }
+    }
+
+    if !dry_run {
+        // Pinned entries outlive their session as session-less entries
+        tx.execute(
+            "UPDATE chat_entries SET session_id = NULL WHERE session_id IN (SELECT id FROM cleanup_sessions)",
+            [],
+        )?;
+        tx.execute("DELETE FROM tasks WHERE session_id IN (SELECT id FROM cleanup_sessions)", [])?;
+        tx.execute("DELETE FROM entity_mentions WHERE session_id IN (SELECT id FROM cleanup_sessions)", [])?;
+        tx.execute("DELETE FROM session_vocabulary WHERE session_id IN (SELECT id FROM cleanup_sessions)", [])?;
+        tx.execute("DELETE FROM session_sync WHERE session_id IN (SELECT id FROM cleanup_sessions)", [])?;
+        tx.execute("DELETE FROM metrics WHERE session_id IN (SELECT id FROM cleanup_sessions)", [])?;
+        tx.execute("DELETE FROM bookmarks WHERE session_id IN (SELECT id FROM cleanup_sessions)", [])?;
+        tx.execute("DELETE FROM sessions WHERE id IN (SELECT id FROM cleanup_sessions)", [])?;
+        tx.commit()?;
+    }
+    Ok(report)
+}
+
+/// One full-text match; `snippet` wraps matched terms in <mark></mark>
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub id: String,
+    pub source: String,     // "chat" or "knowledge"
+    pub entry_type: String, // Chat entry type, or "knowledge"
+    pub timestamp: i64,
+    pub session_id: Option<String>,
+    pub snippet: String,
+}
+
+/// Turn free text into an FTS5 query: every word must match, the last one as a prefix
+fn fts_query(query: &str) -> Option<String> {
+    let words: Vec<String> = query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect();
+    if words.is_empty() {
+        return None;
+    }
+    Some(format!("{}*", words.join(" ")))
+}
+
+/// Ranked full-text search over chat entries and knowledge notes
+pub fn search_text(
+    conn: &Connection,
+    query: &str,
+    types: &[String],
+    from: Option<i64>,
+    to: Option<i64>,
+    limit: usize,
+) -> SqliteResult<Vec<SearchHit>> {
+    let Some(fts) = fts_query(query) else {
+        return Ok(Vec::new());
+    };
+    let types_json = serde_json::to_string(types).unwrap_or_else(|_| "[]".to_string());
+
+    let mut stmt = conn.prepare(
+        "SELECT ref_id, source, entry_type, timestamp, session_id,
+                snippet(search_index, 0, '<mark>', '</mark>', '…', 16)
+         FROM search_index
+         WHERE search_index MATCH ?1
+           AND (json_array_length(?2) = 0 OR entry_type IN (SELECT value FROM json_each(?2)))
+           AND timestamp >= ?3 AND timestamp <= ?4
+         ORDER BY rank LIMIT ?5",
+    )?;
+    let hits = stmt
+        .query_map(
+            params![fts, types_json, from.unwrap_or(i64::MIN), to.unwrap_or(i64::MAX), limit as i64],
+            |row| {
+                Ok(SearchHit {
+                    id: row.get(0)?,
+                    source: row.get(1)?,
+                    entry_type: row.get(2)?,
+                    timestamp: row.get(3)?,
+                    session_id: row.get(4)?,
+                    snippet: row.get(5)?,
+                })
+            },
+        )?
+        .collect();
+    hits
+}
+
 /// Convert embedding Vec<f32> to BLOB for SQLite storage
-#[allow(dead_code)]
 pub fn embedding_to_blob(embedding: &[f32]) -> Option<Vec<u8>> {
     // Convert f32 array to bytes
     let mut bytes = Vec::with_capacity(embedding.len() * 4);
@@ -309,4 +1565,164 @@ mod tests {
         let restored = blob_to_embedding(&blob);
         assert_eq!(original, restored);
     }
+
+    #[test]
+    fn test_migrate() {
+        assert_eq!(MIGRATIONS.last().map(|m| m.version), Some(SCHEMA_VERSION));
+        assert!(MIGRATIONS.windows(2).all(|w| w[0].version < w[1].version));
+
+        let conn = Connection::open_in_memory().unwrap();
+        let report = migrate(&conn, true).unwrap();
+        assert_eq!((report.from_version, report.to_version), (0, SCHEMA_VERSION));
+        assert_eq!(report.applied.len(), MIGRATIONS.len());
+        assert_eq!(schema_version(&conn).unwrap(), 0);
+        assert!(!has_tables(&conn).unwrap());
+
+        migrate(&conn, false).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+        assert!(migrate(&conn, false).unwrap().applied.is_empty());
+    }
+
+    #[test]
+    fn test_json_migration_marker() {
+        let conn = init_memory_db().unwrap();
+        assert!(!is_json_migrated(&conn));
+        mark_json_migrated(&conn).unwrap();
+        assert!(is_json_migrated(&conn));
+    }
+
+    #[test]
+    fn test_session_lifecycle() {
+        let conn = init_memory_db().unwrap();
+        let session = start_session(&conn, 1_700_000_000_000, "mic", "/models/en.april").unwrap();
+        assert!(session.title.starts_with("Session "));
+        assert_eq!(session.ended_at, None);
+        assert_eq!(session_template(&conn, &session.id).unwrap(), None);
+        set_session_template(&conn, &session.id, "standup").unwrap();
+        assert_eq!(session_template(&conn, &session.id).unwrap().as_deref(), Some("standup"));
+
+        add_transcript_line(&conn, &session.id, 1_700_000_001_000, "hello", None, None).unwrap();
+        add_transcript_line(&conn, &session.id, 1_700_000_002_000, "world", None, Some("Alice")).unwrap();
+        end_session(&conn, &session.id, 1_700_000_003_000).unwrap();
+
+        let sessions = list_sessions(&conn).unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].ended_at, Some(1_700_000_003_000));
+        assert_eq!(session_transcript(&conn, &session.id).unwrap(), vec!["hello", "Alice: world"]);
+    }
+
+    #[test]
+    fn test_add_caption_lines() {
+        let conn = init_memory_db().unwrap();
+        let session = start_session(&conn, 1_000, "mic", "m").unwrap();
+        let line = |text: &str, caption_id: Option<&str>| NewCaptionLine {
+            session_id: session.id.clone(),
+            timestamp: 1_100,
+            text: text.to_string(),
+            source: None,
+            channel: Some("mic".to_string()),
+            speaker: None,
+            caption_id: caption_id.map(String::from),
+            words: vec![CaptionWord { text: text.to_string(), start_ms: 1_000, end_ms: 1_100, confidence: None }],
+        };
+        add_caption_lines(&conn, &[line("hello", Some("c1")), line("there", None)]).unwrap();
+
+        assert_eq!(session_transcript(&conn, &session.id).unwrap(), vec!["hello", "there"]);
+        assert_eq!(caption_words(&conn, "c1").unwrap()[0].text, "hello");
+        let words: i64 = conn.query_row("SELECT COUNT(*) FROM caption_words", [], |row| row.get(0)).unwrap();
+        assert_eq!(words, 1);
+
+        add_caption_lines(&conn, &[line("friday", Some("c2"))]).unwrap();
+        set_caption_text(&conn, &["c1".to_string(), "c2".to_string()], "hello on Friday").unwrap();
+        assert_eq!(session_transcript(&conn, &session.id).unwrap(), vec!["hello on Friday", "there"]);
+        assert!(caption_words(&conn, "c2").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_cleanup_sessions_policy() {
+        let conn = init_memory_db().unwrap();
+        let old = start_session(&conn, 1_000, "mic", "m").unwrap();
+        let recent = start_session(&conn, 2_000, "mic", "m").unwrap();
+        add_transcript_line(&conn, &old.id, 1_100, "old line", None, None).unwrap();
+        add_transcript_line(&conn, &recent.id, 2_100, "new line", None, None).unwrap();
+        conn.execute(
+            "INSERT INTO chat_entries (id, timestamp, entry_type, content, session_id, pinned) VALUES
+                ('kept', 1200, 'answer', 'pinned', ?1, 1), ('gone', 1300, 'answer', 'not pinned', ?1, 0)",
+            params![old.id],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO tasks (id, session_id, text, created_at, updated_at) VALUES ('t', ?1, 'follow up', 0, 0)",
+            params![old.id],
+        )
+        .unwrap();
+        let policy = RetentionPolicy::Sessions { count: 1 };
+
+        let report = cleanup(&conn, &policy, 3_000, None, true).unwrap();
+        assert_eq!(report.expired_session_ids, vec![old.id.clone()]);
+        assert_eq!(report.transcript_lines, 1);
+        assert_eq!(list_sessions(&conn).unwrap().len(), 2);
+
+        cleanup(&conn, &policy, 3_000, None, false).unwrap();
+        assert_eq!(list_sessions(&conn).unwrap().len(), 1);
+        assert!(session_transcript(&conn, &old.id).unwrap().is_empty());
+        assert_eq!(session_transcript(&conn, &recent.id).unwrap(), vec!["new line"]);
+        let chat: Vec<(String, Option<String>)> = conn
+            .prepare("SELECT id, session_id FROM chat_entries")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<SqliteResult<_>>()
+            .unwrap();
+        assert_eq!(chat, vec![("kept".to_string(), None)]);
+        let tasks: i64 = conn.query_row("SELECT COUNT(*) FROM tasks", [], |row| row.get(0)).unwrap();
+        assert_eq!(tasks, 0);
+    }
+
+    #[test]
+    fn test_search_text() {
+        let conn = init_memory_db().unwrap();
+        conn.execute(
+            "INSERT INTO chat_entries (id, timestamp, entry_type, content) VALUES
+                ('a', 1000, 'transcript', 'we should ship the release on Friday'),
+                ('b', 2000, 'answer', 'Friday works for the release')",
+            [],
+        )
+        .unwrap();
+        conn.execute("UPDATE chat_entries SET content = 'no plans' WHERE id = 'b'", []).unwrap();
+
+        let hits = search_text(&conn, "relea", &[], None, None, 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, "a");
+        assert!(hits[0].snippet.contains("<mark>release</mark>"));
+        assert!(search_text(&conn, "ship", &["answer".to_string()], None, None, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_save_chat_entry_keeps_pin() {
+        let conn = init_memory_db().unwrap();
+        let mut entry = ChatHistoryEntry {
+            id: "a".to_string(),
+            timestamp: 1000,
+            entry_type: "answer".to_string(),
+            content: "first".to_string(),
+            metadata: None,
+            session_id: None,
+            speaker: None,
+            source: None,
+            channel: None,
+            pinned: false,
+        };
+        save_chat_entry(&conn, &entry).unwrap();
+        conn.execute("UPDATE chat_entries SET pinned = 1", []).unwrap();
+
+        entry.content = "second".to_string();
+        save_chat_entry(&conn, &entry).unwrap();
+        let (content, pinned): (String, bool) = conn
+            .query_row("SELECT content, pinned FROM chat_entries WHERE id = 'a'", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!((content.as_str(), pinned), ("second", true));
+    }
 }
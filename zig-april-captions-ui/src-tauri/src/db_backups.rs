@@ -0,0 +1,298 @@
+// Scheduled database snapshots
+//
+// Unlike `create_backup`, which zips all app data on request, this keeps a
+// rolling set of copies of zigy.db alone in `backups/` inside the data
+// directory. Whenever the newest snapshot is older than the configured
+// interval a new one is taken with SQLite's online backup API, so it is
+// consistent even while captions are being written, and all but the newest
+// `keep` are deleted.
+//
+// `restore_from_backup` copies a snapshot back into the open database in
+// place, after taking one of the current state so the restore can be undone.
+// No snapshots are taken while storage encryption is on, since they would be
+// written in the clear, and the ones taken before it was turned on (including
+// the pre-migration ones) are deleted.
+use rusqlite::backup::Backup;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tracing::{info, warn};
+
+use crate::{database, embeddings, encryption, get_config_dir, now_millis, AppState};
+
+/// How often the schedule is checked
+const CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+const BACKUP_PREFIX: &str = "zigy-";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DbBackupSettings {
+    #[serde(default = "crate::default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_interval_hours")]
+    pub interval_hours: u32,
+    #[serde(default = "default_keep")]
+    pub keep: usize, // Older snapshots are deleted
+}
+
+fn default_interval_hours() -> u32 {
+    24
+}
+
+fn default_keep() -> usize {
+    7
+}
+
+impl Default for DbBackupSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_hours: default_interval_hours(),
+            keep: default_keep(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DbBackup {
+    pub id: String,
+    pub created_at: i64,
+    pub size_bytes: u64,
+}
+
+pub fn backups_dir() -> PathBuf {
+    get_config_dir().join("backups")
+}
+
+fn backup_path(id: &str) -> PathBuf {
+    backups_dir().join(format!("{}.db", id))
+}
+
+/// The time a snapshot was taken, from its id ("zigy-<millis>")
+fn created_at(id: &str) -> Option<i64> {
+    id.strip_prefix(BACKUP_PREFIX)?.parse().ok()
+}
+
+/// Snapshots in `dir`, newest first
+fn list(dir: &Path) -> Vec<DbBackup> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut backups: Vec<DbBackup> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("db") {
+                return None;
+            }
+            let id = path.file_stem()?.to_str()?.to_string();
+            Some(DbBackup {
+                created_at: created_at(&id)?,
+                size_bytes: entry.metadata().ok()?.len(),
+                id,
+            })
+        })
+        .collect();
+    backups.sort_by_key(|b| std::cmp::Reverse(b.created_at));
+    backups
+}
+
+/// Delete all but the newest `keep` snapshots; returns how many were deleted
+fn rotate(dir: &Path, keep: usize) -> usize {
+    let mut deleted = 0;
+    for backup in list(dir).into_iter().skip(keep.max(1)) {
+        match std::fs::remove_file(dir.join(format!("{}.db", backup.id))) {
+            Ok(()) => deleted += 1,
+            Err(e) => warn!("Failed to delete old backup {}: {}", backup.id, e),
+        }
+    }
+    deleted
+}
+
+/// Delete every snapshot, once storage encryption is on; returns how many were deleted
+pub fn remove_all() -> usize {
+    let dir = backups_dir();
+    let mut deleted = 0;
+    for backup in list(&dir) {
+        match std::fs::remove_file(dir.join(format!("{}.db", backup.id))) {
+            Ok(()) => deleted += 1,
+            Err(e) => warn!("Failed to delete backup {}: {}", backup.id, e),
+        }
+    }
+    if deleted > 0 {
+        info!("Removed {} unencrypted database backups", deleted);
+    }
+    deleted
+}
+
+/// Copy the whole database into a new file at `dest` with the backup API
+fn snapshot(conn: &Connection, dest: &Path) -> Result<(), String> {
+    let partial = dest.with_extension("db.tmp");
+    let result = (|| -> rusqlite::Result<()> {
+        let mut out = Connection::open(&partial)?;
+        let backup = Backup::new(conn, &mut out)?;
+        backup.run_to_completion(-1, Duration::ZERO, None)
+    })();
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&partial);
+        return Err(format!("Failed to back up the database: {}", e));
+    }
+    std::fs::rename(&partial, dest).map_err(|e| format!("Failed to save backup: {}", e))
+}
+
+/// Snapshot `conn` into the backups directory, without rotating
+pub fn backup_connection(conn: &Connection) -> Result<DbBackup, String> {
+    let dir = backups_dir();
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let created_at = now_millis();
+    let id = format!("{}{}", BACKUP_PREFIX, created_at);
+    let path = backup_path(&id);
+    snapshot(conn, &path)?;
+    info!("Database backed up to {}", path.display());
+    Ok(DbBackup {
+        id,
+        created_at,
+        size_bytes: std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0),
+    })
+}
+
+/// Take a snapshot now and rotate old ones
+pub fn backup_now(state: &AppState, keep: usize) -> Result<DbBackup, String> {
+    if encryption::is_enabled() {
+        return Err("Database snapshots are not taken while storage encryption is on".to_string());
+    }
+    let backup = {
+        let conn = state.db()?;
+        backup_connection(&conn)?
+    };
+    let rotated = rotate(&backups_dir(), keep);
+    if rotated > 0 {
+        info!("Removed {} old database backups", rotated);
+    }
+    Ok(backup)
+}
+
+fn backup_settings(state: &AppState) -> DbBackupSettings {
+    state
+        .settings
+        .lock()
+        .ok()
+        .and_then(|s| s.db_backups.clone())
+        .unwrap_or_default()
+}
+
+/// Back up whenever the newest snapshot is older than the interval
+pub fn start(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let state = app_handle.state::<Arc<AppState>>().inner().clone();
+            let settings = backup_settings(&state);
+            let interval = settings.interval_hours.max(1) as i64 * 60 * 60 * 1000;
+            if encryption::is_enabled() {
+                remove_all();
+            }
+            let newest = list(&backups_dir()).first().map(|b| b.created_at).unwrap_or(0);
+            if settings.enabled && !encryption::is_enabled() && now_millis() - newest >= interval {
+                let keep = settings.keep;
+                match tauri::async_runtime::spawn_blocking(move || backup_now(&state, keep)).await {
+                    Ok(Err(e)) => warn!("Scheduled backup failed: {}", e),
+                    Err(e) => warn!("Scheduled backup failed: {}", e),
+                    Ok(Ok(_)) => {}
+                }
+            }
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+/// Database snapshots, newest first
+#[tauri::command]
+pub async fn list_backups() -> Result<Vec<DbBackup>, String> {
+    Ok(list(&backups_dir()))
+}
+
+/// Replace the database's contents with a snapshot. The current state is
+/// snapshotted first, so the restore shows up in `list_backups` to undo.
+#[tauri::command]
+pub async fn restore_from_backup(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    id: String,
+) -> Result<(), String> {
+    let path = backup_path(&id);
+    if created_at(&id).is_none() || !path.is_file() {
+        return Err(format!("Backup {} not found", id));
+    }
+    let source = Connection::open_with_flags(&path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("Failed to open backup {}: {}", id, e))?;
+    let integrity: String = source
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if integrity != "ok" {
+        return Err(format!("Backup {} is damaged: {}", id, integrity));
+    }
+    let schema = database::schema_version(&source).map_err(|e| e.to_string())?;
+    if schema > database::SCHEMA_VERSION {
+        return Err(format!(
+            "Backup {} (schema {}) is newer than this app supports (schema {})",
+            id,
+            schema,
+            database::SCHEMA_VERSION
+        ));
+    }
+    // Try the migrations on a copy first, so a snapshot that can't be brought
+    // up to date never replaces the current data
+    let check = (|| -> rusqlite::Result<()> {
+        let mut copy = Connection::open_in_memory()?;
+        Backup::new(&source, &mut copy)?.run_to_completion(-1, Duration::ZERO, None)?;
+        database::migrate(&copy, true).map(|_| ())
+    })();
+    check.map_err(|e| format!("Backup {} can't be brought up to date: {}", id, e))?;
+
+    let settings = backup_settings(&state);
+    if !encryption::is_enabled() {
+        // Keep one more than usual so the snapshot being restored isn't rotated out
+        backup_now(&state, settings.keep + 1)?;
+    }
+    {
+        let mut conn = state.db()?;
+        Backup::new(&source, &mut conn)
+            .and_then(|backup| backup.run_to_completion(-1, Duration::ZERO, None))
+            .map_err(|e| format!("Failed to restore backup {}: {}", id, e))?;
+        // Older snapshots get the migrations added since
+        database::migrate(&conn, false).map_err(|e| format!("Failed to migrate the restored database: {}", e))?;
+    }
+    embeddings::wake(&app_handle);
+    info!("Database restored from backup {}", id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_and_rotate() {
+        let dir = std::env::temp_dir().join(format!("zigy-backups-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let conn = database::init_memory_db().unwrap();
+        conn.execute("INSERT INTO knowledge_folders (name, created_at) VALUES ('Work', 1)", [])
+            .unwrap();
+        for millis in [1000, 3000, 2000] {
+            snapshot(&conn, &dir.join(format!("{}{}.db", BACKUP_PREFIX, millis))).unwrap();
+        }
+
+        assert_eq!(rotate(&dir, 2), 1);
+        let ids: Vec<String> = list(&dir).into_iter().map(|b| b.id).collect();
+        assert_eq!(ids, vec!["zigy-3000", "zigy-2000"]);
+        let copy = Connection::open(dir.join("zigy-2000.db")).unwrap();
+        let folder: String = copy
+            .query_row("SELECT name FROM knowledge_folders", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(folder, "Work");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,114 @@
+// Read connections for slow queries
+//
+// Everything that writes (caption ingestion above all) goes through the one
+// shared connection in AppState, and whoever holds its lock holds up the next
+// caption. Searches, history listings and exports instead read through their
+// own read-only connection to zigy.db, taken from a small pool, so a long
+// query never holds that lock. `read` also moves the query onto a blocking
+// thread, so it doesn't stall the async runtime either.
+//
+// With storage encryption on, the database only exists in memory behind the
+// shared connection, so readers fall back to it.
+use rusqlite::{Connection, OpenFlags};
+use std::ops::Deref;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tracing::warn;
+
+use crate::{database, encryption, AppState};
+
+/// Idle read connections kept open; more are opened while many reads run
+const MAX_IDLE: usize = 4;
+
+/// How long a reader waits for a writer to finish before giving up
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+pub struct ReadPool {
+    idle: Mutex<Vec<Connection>>,
+}
+
+impl ReadPool {
+    fn take(&self) -> rusqlite::Result<Connection> {
+        if let Some(conn) = self.idle.lock().ok().and_then(|mut idle| idle.pop()) {
+            return Ok(conn);
+        }
+        let conn = Connection::open_with_flags(
+            database::get_db_path(),
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX | OpenFlags::SQLITE_OPEN_URI,
+        )?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        Ok(conn)
+    }
+
+    fn put_back(&self, conn: Connection) {
+        if let Ok(mut idle) = self.idle.lock() {
+            if idle.len() < MAX_IDLE {
+                idle.push(conn);
+            }
+        }
+    }
+
+    /// Close the idle connections, e.g. when zigy.db is replaced
+    pub fn clear(&self) {
+        if let Ok(mut idle) = self.idle.lock() {
+            idle.clear();
+        }
+    }
+}
+
+/// A connection to read from: a pooled one, returned to the pool when
+/// dropped, or the shared one
+pub enum Reader<'a> {
+    Pooled(Option<Connection>, &'a ReadPool),
+    Shared(MutexGuard<'a, Connection>),
+}
+
+impl Deref for Reader<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        match self {
+            Reader::Pooled(conn, _) => conn.as_ref().expect("pooled connection is only taken on drop"),
+            Reader::Shared(conn) => conn,
+        }
+    }
+}
+
+impl Drop for Reader<'_> {
+    fn drop(&mut self) {
+        if let Reader::Pooled(conn, pool) = self {
+            if let Some(conn) = conn.take() {
+                pool.put_back(conn);
+            }
+        }
+    }
+}
+
+pub fn reader(state: &AppState) -> Result<Reader<'_>, String> {
+    if encryption::is_enabled() {
+        return Ok(Reader::Shared(state.db()?));
+    }
+    match state.readers.take() {
+        Ok(conn) => Ok(Reader::Pooled(Some(conn), &state.readers)),
+        Err(e) => {
+            warn!("Failed to open a read connection, using the shared one: {}", e);
+            Ok(Reader::Shared(state.db()?))
+        }
+    }
+}
+
+/// Run `f` on a blocking thread with a read connection
+pub async fn read<T, F>(state: &Arc<AppState>, f: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce(&Connection) -> Result<T, String> + Send + 'static,
+{
+    let state = state.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let conn = reader(&state)?;
+        f(&conn)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
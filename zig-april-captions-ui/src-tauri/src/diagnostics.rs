@@ -0,0 +1,403 @@
+// Diagnostics bundle for bug reports
+//
+// Collects everything we usually have to ask for in an issue (versions, OS,
+// settings, binary lookup, logs, child stderr) into a single zip.
+//
+// `run_diagnostics` is the interactive counterpart for the setup wizard: it
+// checks the environment captions depend on and explains how to fix each
+// problem it finds.
+use serde::Serialize;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+use tauri::AppHandle;
+use tracing::info;
+use zip::write::SimpleFileOptions;
+
+use crate::{audio_devices, binary_debug_report, logging, portable, AppState};
+
+/// Keys whose values are replaced before settings leave the machine
+const SECRET_KEY_MARKERS: [&str; 4] = ["key", "token", "secret", "password"];
+
+/// Only the tail of each log file is bundled to keep the zip attachable
+const MAX_LOG_BYTES: usize = 1024 * 1024;
+
+/// Replace secret-looking values (API keys, tokens) anywhere in a JSON tree
+pub fn redact_secrets(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, v) in map.iter_mut() {
+                let lower = key.to_lowercase();
+                let is_secret = SECRET_KEY_MARKERS.iter().any(|m| lower.contains(m));
+                if is_secret && v.as_str().map(|s| !s.is_empty()).unwrap_or(false) {
+                    *v = serde_json::Value::String("<redacted>".to_string());
+                } else {
+                    redact_secrets(v);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact_secrets),
+        _ => {}
+    }
+}
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    std::process::Command::new(program)
+        .args(args)
+        .output()
+        .ok()
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// OS name/version, architecture and desktop session details
+pub fn os_report() -> String {
+    let mut report = String::new();
+    report.push_str(&format!("OS: {}\n", std::env::consts::OS));
+    report.push_str(&format!("Arch: {}\n", std::env::consts::ARCH));
+
+    #[cfg(target_os = "linux")]
+    {
+        if let Ok(os_release) = std::fs::read_to_string("/etc/os-release") {
+            if let Some(name) = os_release.lines().find(|l| l.starts_with("PRETTY_NAME=")) {
+                report.push_str(&format!("Distribution: {}\n", name.trim_start_matches("PRETTY_NAME=").trim_matches('"')));
+            }
+        }
+        if let Some(kernel) = command_output("uname", &["-r"]) {
+            report.push_str(&format!("Kernel: {}\n", kernel));
+        }
+        for var in ["XDG_SESSION_TYPE", "XDG_CURRENT_DESKTOP", "WAYLAND_DISPLAY", "DISPLAY", "APPIMAGE"] {
+            if let Ok(value) = std::env::var(var) {
+                report.push_str(&format!("{}: {}\n", var, value));
+            }
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        if let Some(version) = command_output("sw_vers", &["-productVersion"]) {
+            report.push_str(&format!("macOS version: {}\n", version));
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        if let Some(version) = command_output("cmd", &["/C", "ver"]) {
+            report.push_str(&format!("Windows version: {}\n", version));
+        }
+    }
+
+    report
+}
+
+fn tail_bytes(content: &[u8], max: usize) -> &[u8] {
+    &content[content.len().saturating_sub(max)..]
+}
+
+/// Write a zip with version, OS, redacted settings, binary probe results,
+/// log files and the last child stderr lines. Returns the written path.
+#[tauri::command]
+pub async fn create_diagnostics_bundle(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    path: String,
+) -> Result<String, String> {
+    let file = std::fs::File::create(&path).map_err(|e| format!("Failed to create {}: {}", path, e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    let mut add = |name: &str, content: &[u8]| -> Result<(), String> {
+        zip.start_file(name, options).map_err(|e| e.to_string())?;
+        zip.write_all(content).map_err(|e| e.to_string())
+    };
+
+    // Version and OS
+    let package = app_handle.package_info();
+    let summary = format!(
+        "{} {}\nGenerated at: {}\n\n{}",
+        package.name,
+        package.version,
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis(),
+        os_report()
+    );
+    add("system.txt", summary.as_bytes())?;
+
+    // Settings without secrets
+    let mut settings = {
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        serde_json::to_value(&*settings).map_err(|e| e.to_string())?
+    };
+    redact_secrets(&mut settings);
+    add(
+        "settings.json",
+        serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?.as_bytes(),
+    )?;
+
+    // Binary lookup
+    let binary_report = binary_debug_report(&app_handle).unwrap_or_else(|e| format!("Probe failed: {}", e));
+    add("binary_paths.txt", binary_report.as_bytes())?;
+
+    // Last stderr from the captioning process
+    let stderr = {
+        let tail = state.process_stderr.lock().map_err(|e| e.to_string())?;
+        tail.iter().cloned().collect::<Vec<_>>().join("\n")
+    };
+    add("process_stderr.txt", stderr.as_bytes())?;
+
+    // Rotated log files
+    if let Ok(entries) = std::fs::read_dir(logging::get_log_dir()) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.is_file() {
+                continue;
+            }
+            if let Ok(content) = std::fs::read(&path) {
+                let name = format!("logs/{}", entry.file_name().to_string_lossy());
+                add(&name, tail_bytes(&content, MAX_LOG_BYTES))?;
+            }
+        }
+    }
+
+    zip.finish().map_err(|e| format!("Failed to write bundle: {}", e))?;
+    info!("Diagnostics bundle written to {}", path);
+    Ok(path)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+    Skip, // Not run because an earlier check failed
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticCheck {
+    pub id: &'static str,
+    pub label: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticsReport {
+    pub ok: bool, // No check failed; warnings don't block captioning
+    pub checks: Vec<DiagnosticCheck>,
+}
+
+impl DiagnosticCheck {
+    fn new(id: &'static str, label: &'static str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            id,
+            label,
+            status,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+}
+
+/// April model files start with this magic
+const MODEL_MAGIC: &[u8] = b"APRILMDL";
+
+#[cfg(target_os = "linux")]
+const ONNX_LIBRARY: &str = "libonnxruntime.so";
+#[cfg(target_os = "macos")]
+const ONNX_LIBRARY: &str = "libonnxruntime.dylib";
+#[cfg(target_os = "windows")]
+const ONNX_LIBRARY: &str = "onnxruntime.dll";
+
+fn check_binary(app_handle: &AppHandle) -> (DiagnosticCheck, Option<String>) {
+    match crate::get_zig_binary_path(app_handle) {
+        Ok(path) => (
+            DiagnosticCheck::new("binary", "Caption engine", CheckStatus::Pass, path.clone()),
+            Some(path),
+        ),
+        Err(e) => (
+            DiagnosticCheck::new("binary", "Caption engine", CheckStatus::Fail, e).hint(
+                "The zig-april-captions binary ships with Zigy. Reinstall the app; for .deb installs it \
+                 should be in /usr/lib/zigy.",
+            ),
+            None,
+        ),
+    }
+}
+
+/// Run the engine with --version: it only starts if the dynamic loader can
+/// resolve onnxruntime, using the same library path as a real launch
+fn check_onnx_runtime(binary_path: Option<&str>) -> DiagnosticCheck {
+    const LABEL: &str = "ONNX runtime";
+    let Some(binary_path) = binary_path else {
+        return DiagnosticCheck::new("onnx", LABEL, CheckStatus::Skip, "Caption engine not found");
+    };
+    let binary_dir = Path::new(binary_path)
+        .parent()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let bundled = Path::new(&binary_dir).read_dir().ok().is_some_and(|entries| {
+        entries
+            .flatten()
+            .any(|e| e.file_name().to_string_lossy().starts_with(ONNX_LIBRARY))
+    });
+
+    let mut cmd = std::process::Command::new(binary_path);
+    cmd.arg("--version");
+    crate::set_library_path(&mut cmd, &binary_dir);
+    let output = match cmd.output() {
+        Ok(output) => output,
+        Err(e) => {
+            return DiagnosticCheck::new("onnx", LABEL, CheckStatus::Fail, format!("Could not run the engine: {}", e))
+                .hint("Check that the engine binary is executable and built for this architecture.");
+        }
+    };
+
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    if output.status.success() {
+        let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        let location = if bundled { "bundled" } else { "system" };
+        DiagnosticCheck::new("onnx", LABEL, CheckStatus::Pass, format!("Engine {} loads ({} library)", version, location))
+    } else if stderr.contains("shared librar") || stderr.contains("Library not loaded") || stderr.contains("onnxruntime") {
+        DiagnosticCheck::new("onnx", LABEL, CheckStatus::Fail, stderr).hint(format!(
+            "{} was not found next to the engine. Reinstall Zigy, or install ONNX Runtime 1.16 system-wide.",
+            ONNX_LIBRARY
+        ))
+    } else {
+        DiagnosticCheck::new("onnx", LABEL, CheckStatus::Fail, format!("Engine exited with {}: {}", output.status, stderr))
+            .hint("Create a diagnostics bundle and attach it to a bug report.")
+    }
+}
+
+fn check_audio(settings: &crate::Settings) -> DiagnosticCheck {
+    const LABEL: &str = "Audio input";
+    let devices = match audio_devices::enumerate() {
+        Ok(devices) => devices,
+        Err(e) => {
+            let hint = if cfg!(target_os = "linux") {
+                "Zigy captures audio through PulseAudio. Make sure PulseAudio or pipewire-pulse is running and \
+                 that `pactl` is installed (package pulseaudio-utils)."
+            } else {
+                "Check that an audio input device is connected and enabled in the system sound settings."
+            };
+            return DiagnosticCheck::new("audio", LABEL, CheckStatus::Fail, e).hint(hint);
+        }
+    };
+
+    let inputs = devices.iter().filter(|d| d.kind == "input").count();
+    if inputs == 0 && settings.audio_source != "monitor" {
+        return DiagnosticCheck::new("audio", LABEL, CheckStatus::Fail, "No microphone found")
+            .hint("Connect a microphone, or switch the audio source to system audio.");
+    }
+    if let Some(id) = &settings.audio_device_id {
+        if !devices.iter().any(|d| &d.id == id) {
+            return DiagnosticCheck::new("audio", LABEL, CheckStatus::Warn, format!("Selected device {} is not connected", id))
+                .hint("Pick another device in settings, or reconnect it.");
+        }
+    }
+    DiagnosticCheck::new("audio", LABEL, CheckStatus::Pass, format!("{} device(s) available", devices.len()))
+}
+
+fn check_model(model_path: &str) -> DiagnosticCheck {
+    const LABEL: &str = "Speech model";
+    const DOWNLOAD_HINT: &str = "Download a model from the model manager, or choose an .april file in settings.";
+    if model_path.is_empty() {
+        return DiagnosticCheck::new("model", LABEL, CheckStatus::Fail, "No model selected").hint(DOWNLOAD_HINT);
+    }
+
+    let mut magic = [0u8; 8];
+    let read = std::fs::File::open(model_path).and_then(|mut f| f.read_exact(&mut magic));
+    match read {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            DiagnosticCheck::new("model", LABEL, CheckStatus::Fail, format!("{} does not exist", model_path))
+                .hint(DOWNLOAD_HINT)
+        }
+        Err(e) => DiagnosticCheck::new("model", LABEL, CheckStatus::Fail, format!("Cannot read {}: {}", model_path, e))
+            .hint("Check the file's permissions, or download the model again."),
+        Ok(()) if magic != MODEL_MAGIC => {
+            DiagnosticCheck::new("model", LABEL, CheckStatus::Warn, format!("{} is not a recognized April model", model_path))
+                .hint("The file may be incomplete or not an .april model. Download it again if captions fail to start.")
+        }
+        Ok(()) => DiagnosticCheck::new("model", LABEL, CheckStatus::Pass, model_path),
+    }
+}
+
+/// The data folder must be writable, which a portable install on read-only
+/// media isn't
+fn check_storage() -> DiagnosticCheck {
+    const LABEL: &str = "Data folder";
+    let dir = crate::get_config_dir();
+    let probe = dir.join(".write-test");
+    let mode = if portable::is_portable() { "portable" } else { "installed" };
+    let Err(e) = std::fs::write(&probe, b"").and_then(|()| std::fs::remove_file(&probe)) else {
+        return DiagnosticCheck::new("storage", LABEL, CheckStatus::Pass, format!("{} ({})", dir.display(), mode));
+    };
+    let detail = format!("Cannot write to {}: {}", dir.display(), e);
+    let failed = DiagnosticCheck::new("storage", LABEL, CheckStatus::Fail, detail);
+    if portable::is_portable() {
+        failed.hint(format!(
+            "The drive may be read-only. Copy Zigy to a writable drive, or delete {} to store data in your user \
+             profile instead.",
+            portable::FLAG_FILE
+        ))
+    } else {
+        failed.hint("Check the folder's permissions and free disk space.")
+    }
+}
+
+/// Check the engine binary, ONNX runtime, audio backend and model, with a
+/// remediation hint for every problem found
+#[tauri::command]
+pub async fn run_diagnostics(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<DiagnosticsReport, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?.clone();
+
+    let checks = tokio::task::spawn_blocking(move || {
+        let (binary, binary_path) = check_binary(&app_handle);
+        vec![
+            binary,
+            check_onnx_runtime(binary_path.as_deref()),
+            check_audio(&settings),
+            check_model(&settings.model_path),
+            check_storage(),
+        ]
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let ok = checks.iter().all(|c| !matches!(c.status, CheckStatus::Fail | CheckStatus::Skip));
+    info!("Diagnostics finished: {}", if ok { "all good" } else { "problems found" });
+    Ok(DiagnosticsReport { ok, checks })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_secrets() {
+        let mut value = serde_json::json!({
+            "theme": "dark",
+            "ai": { "api_key": "abc123", "model": "gemini-2.5-flash" },
+            "webhooks": [{ "secret": "s3cr3t", "url": "https://example.com" }],
+            "token": ""
+        });
+        redact_secrets(&mut value);
+        assert_eq!(value["ai"]["api_key"], "<redacted>");
+        assert_eq!(value["ai"]["model"], "gemini-2.5-flash");
+        assert_eq!(value["webhooks"][0]["secret"], "<redacted>");
+        assert_eq!(value["webhooks"][0]["url"], "https://example.com");
+        assert_eq!(value["token"], "");
+    }
+}
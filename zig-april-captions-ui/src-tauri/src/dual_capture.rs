@@ -0,0 +1,190 @@
+// Capturing both sides of a call
+//
+// With audio source "both" a second recognizer runs on the system audio
+// (`--monitor`) next to the microphone one. Captions are tagged with the side
+// they came from, source "me" or "them", and labelled "Me" / "Them" unless the
+// recognizer names a speaker, so the transcript and its exports tell the two
+// apart. Lines keep their own timestamps and the transcript is read back in
+// timestamp order, which interleaves the two streams as they were spoken.
+//
+// The microphone recognizer owns the session: it is the supervised one, and
+// stopping it stops the system audio one too. If the system audio recognizer
+// dies on its own the session carries on with the microphone alone.
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tracing::{debug, info, warn};
+
+use crate::{metrics, process_cleanup, AppState, CaptionEvent};
+
+pub const AUDIO_SOURCE: &str = "both";
+
+/// Side of the call a caption came from
+#[derive(Debug, Clone, Copy)]
+pub enum Side {
+    Me,
+    Them,
+}
+
+impl Side {
+    fn source(self) -> &'static str {
+        match self {
+            Side::Me => "me",
+            Side::Them => "them",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Side::Me => "Me",
+            Side::Them => "Them",
+        }
+    }
+}
+
+/// Mark a caption with the side it came from
+pub fn tag(event: &mut CaptionEvent, side: Side) {
+    if event.event_type != "caption" {
+        return;
+    }
+    event.source = Some(side.source().to_string());
+    if event.speaker.as_deref().map(str::is_empty).unwrap_or(true) {
+        event.speaker = Some(side.label().to_string());
+    }
+}
+
+fn notice(app_handle: &AppHandle, message: &str) {
+    crate::handle_caption_event(
+        app_handle,
+        CaptionEvent {
+            event_type: "warning".to_string(),
+            id: None,
+            caption_type: None,
+            text: None,
+            timestamp: None,
+            message: Some(message.to_string()),
+            version: None,
+            source: Some(Side::Them.source().to_string()),
+            speaker: None,
+            words: Vec::new(),
+            exit_code: None,
+            channel: None,
+        },
+    );
+}
+
+/// Start the system audio recognizer unless it is already running
+pub fn start_monitor(app_handle: &AppHandle, model_path: &str, recording: Option<PathBuf>) -> Result<(), String> {
+    let state = app_handle.state::<Arc<AppState>>();
+    if state.monitor_process.lock().map(|p| p.is_some()).unwrap_or(false) {
+        return Ok(());
+    }
+    let binary_path = crate::get_zig_binary_path(app_handle)?;
+    let mut args = vec!["--json".to_string(), "--monitor".to_string()];
+    if let Some(recording) = recording {
+        info!("Recording system audio to {}", recording.display());
+        args.push("--record".to_string());
+        args.push(recording.to_string_lossy().to_string());
+    }
+    args.extend(crate::vocabulary::engine_args(app_handle));
+    args.push(model_path.to_string());
+
+    let mut cmd = Command::new(&binary_path);
+    cmd.args(&args).stdout(Stdio::piped()).stderr(Stdio::piped());
+    let binary_dir = Path::new(&binary_path)
+        .parent()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+    crate::set_library_path(&mut cmd, &binary_dir);
+
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to start system audio capture: {}", e))?;
+    let stdout = child.stdout.take().ok_or("Failed to capture stdout")?;
+    let stderr = child.stderr.take().ok_or("Failed to capture stderr")?;
+    let pid = child.id();
+    info!("System audio recognizer started, PID: {}", pid);
+    process_cleanup::write_pid(process_cleanup::MONITOR_PID, pid);
+    *state.monitor_process.lock().map_err(|e| e.to_string())? = Some(child);
+
+    let reader_handle = app_handle.clone();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if line.is_empty() {
+                continue;
+            }
+            let state = reader_handle.state::<Arc<AppState>>();
+            match serde_json::from_str::<CaptionEvent>(&line) {
+                Ok(mut event) if event.event_type == "caption" => {
+                    if state.paused.load(Ordering::SeqCst) {
+                        metrics::on_paused_caption(&state);
+                        continue;
+                    }
+                    event.channel = Some("monitor".to_string());
+                    tag(&mut event, Side::Them);
+                    crate::handle_caption_event(&reader_handle, event);
+                }
+                // Status comes from the microphone recognizer
+                Ok(event) => debug!("System audio recognizer: {} {:?}", event.event_type, event.message),
+                Err(e) => {
+                    warn!("Failed to parse JSON: {} ({}-byte line)", e, line.len());
+                    metrics::on_unparsed_line(&state);
+                }
+            }
+        }
+
+        // Still in the slot means nobody stopped it
+        let state = reader_handle.state::<Arc<AppState>>();
+        let status = match state.monitor_process.lock() {
+            Ok(mut process) if process.as_ref().map(|c| c.id() == pid).unwrap_or(false) => {
+                process_cleanup::clear_pid(process_cleanup::MONITOR_PID);
+                process.take().and_then(|mut c| c.wait().ok())
+            }
+            _ => None,
+        };
+        if let Some(status) = status {
+            warn!("System audio recognizer exited: {}", status);
+            notice(&reader_handle, "System audio capture stopped; captioning the microphone only");
+        }
+    });
+
+    std::thread::spawn(move || {
+        for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+            debug!("[zig-april-captions --monitor] {}", line);
+        }
+    });
+    Ok(())
+}
+
+/// Stop the system audio recognizer, if it is running
+pub fn stop_monitor(state: &AppState, timeout: Duration) {
+    let child = state.monitor_process.lock().ok().and_then(|mut p| p.take());
+    if let Some(child) = child {
+        crate::shut_down_child(child, timeout);
+        process_cleanup::clear_pid(process_cleanup::MONITOR_PID);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tag() {
+        let mut event: CaptionEvent =
+            serde_json::from_str(r#"{"type":"caption","captionType":"final","text":"hi"}"#).unwrap();
+        tag(&mut event, Side::Them);
+        assert_eq!(event.source.as_deref(), Some("them"));
+        assert_eq!(event.speaker.as_deref(), Some("Them"));
+
+        let mut event: CaptionEvent =
+            serde_json::from_str(r#"{"type":"caption","text":"hi","speaker":"Alice"}"#).unwrap();
+        tag(&mut event, Side::Me);
+        assert_eq!(event.source.as_deref(), Some("me"));
+        assert_eq!(event.speaker.as_deref(), Some("Alice"));
+    }
+}
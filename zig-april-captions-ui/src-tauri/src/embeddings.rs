@@ -0,0 +1,338 @@
+// Background embedding indexer
+//
+// Chat entries and knowledge notes are saved without vectors. This worker
+// picks up rows whose embedding is still NULL, embeds them in batches with the
+// Gemini embedding model and writes the vectors back so semantic search can
+// use them. Knowledge is edited in its JSON store, so each pass first mirrors
+// it into `knowledge_entries` (tags and folders included), clearing the
+// vector of any note whose text changed.
+use rusqlite::params;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Notify;
+use tracing::{debug, info, warn};
+
+use crate::database::embedding_to_blob;
+use crate::{encryption, AppState};
+
+/// Texts sent per batchEmbedContents request
+const BATCH_SIZE: usize = 32;
+
+/// Poll interval when nothing is queued and nobody wakes the worker
+const IDLE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Wait after a failed request before trying again
+const ERROR_BACKOFF: Duration = Duration::from_secs(120);
+
+/// Longer texts are cut to stay within the model's input limit
+const MAX_TEXT_CHARS: usize = 8000;
+
+/// Chat entry types that semantic search looks at
+const EMBEDDED_CHAT_TYPES: &str = "'transcript', 'summary', 'answer'";
+
+#[derive(Debug, Clone, Copy)]
+enum Table {
+    Chat,
+    Knowledge,
+}
+
+impl Table {
+    fn name(self) -> &'static str {
+        match self {
+            Table::Chat => "chat_entries",
+            Table::Knowledge => "knowledge_entries",
+        }
+    }
+}
+
+struct Pending {
+    table: Table,
+    id: String,
+    content: String,
+}
+
+#[derive(Debug, Default)]
+struct WorkerStatus {
+    indexing: bool,
+    indexed: usize,
+    last_error: Option<String>,
+}
+
+/// Managed state: wake signal and progress of the worker
+#[derive(Default)]
+pub struct EmbeddingState {
+    wake: Notify,
+    status: Mutex<WorkerStatus>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EmbeddingQueueStatus {
+    pub pending_chat: i64,
+    pub pending_knowledge: i64,
+    pub indexing: bool,
+    pub indexed: usize, // Rows embedded since launch
+    pub last_error: Option<String>,
+    pub api_key_configured: bool,
+}
+
+fn api_key(app_handle: &AppHandle) -> Option<String> {
+    let state = app_handle.state::<Arc<AppState>>();
+    let settings = state.settings.lock().ok()?;
+    settings.ai.as_ref().map(|ai| ai.api_key.clone()).filter(|key| !key.is_empty())
+}
+
+fn update_status(app_handle: &AppHandle, update: impl FnOnce(&mut WorkerStatus)) {
+    if let Ok(mut status) = app_handle.state::<EmbeddingState>().status.lock() {
+        update(&mut status);
+    }
+}
+
+/// Mirror the knowledge store into the database
+async fn sync_knowledge(app_handle: &AppHandle) -> Result<(), String> {
+    let entries = crate::get_knowledge(app_handle.state()).await?;
+    let state = app_handle.state::<Arc<AppState>>();
+    let mut conn = state.db()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    for entry in &entries {
+        tx.execute(
+            "INSERT INTO knowledge_entries (id, content, created_at, nominated) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(id) DO UPDATE SET
+                embedding = CASE WHEN content = excluded.content THEN embedding ELSE NULL END,
+                content = excluded.content,
+                nominated = excluded.nominated",
+            params![entry.id, entry.content, entry.created_at, entry.nominated as i32],
+        )
+        .map_err(|e| format!("Failed to sync knowledge: {}", e))?;
+        crate::knowledge::mirror(&tx, entry).map_err(|e| format!("Failed to sync knowledge tags: {}", e))?;
+    }
+
+    let keep: HashSet<&str> = entries.iter().map(|e| e.id.as_str()).collect();
+    let stale: Vec<String> = {
+        let mut stmt = tx.prepare("SELECT id FROM knowledge_entries").map_err(|e| e.to_string())?;
+        let ids = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(|e| e.to_string())?
+            .filter_map(|id| id.ok())
+            .filter(|id| !keep.contains(id.as_str()))
+            .collect();
+        ids
+    };
+    for id in stale {
+        tx.execute("DELETE FROM knowledge_entries WHERE id = ?1", params![id])
+            .map_err(|e| e.to_string())?;
+        tx.execute("DELETE FROM knowledge_tags WHERE entry_id = ?1", params![id])
+            .map_err(|e| e.to_string())?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())
+}
+
+fn pending_batch(app_handle: &AppHandle) -> Result<Vec<Pending>, String> {
+    let state = app_handle.state::<Arc<AppState>>();
+    let conn = state.db()?;
+    let mut batch = Vec::new();
+
+    let queries = [
+        (
+            Table::Chat,
+            format!(
+                "SELECT id, content FROM chat_entries
+                 WHERE embedding IS NULL AND entry_type IN ({}) ORDER BY timestamp DESC LIMIT ?1",
+                EMBEDDED_CHAT_TYPES
+            ),
+        ),
+        (
+            Table::Knowledge,
+            "SELECT id, content FROM knowledge_entries WHERE embedding IS NULL LIMIT ?1".to_string(),
+        ),
+    ];
+    for (table, query) in queries {
+        let remaining = BATCH_SIZE - batch.len();
+        if remaining == 0 {
+            break;
+        }
+        let mut stmt = conn.prepare(&query).map_err(|e| format!("Prepare failed: {}", e))?;
+        let rows = stmt
+            .query_map(params![remaining as i64], |row| {
+                Ok(Pending { table, id: row.get(0)?, content: row.get(1)? })
+            })
+            .map_err(|e| format!("Query failed: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        batch.extend(rows);
+    }
+    Ok(batch)
+}
+
+async fn embed_batch(api_key: &str, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+    let url = format!(
+        "https://generativelanguage.googleapis.com/v1beta/models/text-embedding-004:batchEmbedContents?key={}",
+        api_key
+    );
+    let requests: Vec<serde_json::Value> = texts
+        .iter()
+        .map(|text| {
+            serde_json::json!({
+                "model": "models/text-embedding-004",
+                "content": { "parts": [{ "text": text }] }
+            })
+        })
+        .collect();
+
+    let response = reqwest::Client::new()
+        .post(&url)
+        .json(&serde_json::json!({ "requests": requests }))
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!("API error {}: {}", status, error_text));
+    }
+
+    let data: serde_json::Value = response.json().await.map_err(|e| format!("Parse failed: {}", e))?;
+    let embeddings = data["embeddings"].as_array().ok_or("Invalid embedding response")?;
+    if embeddings.len() != texts.len() {
+        return Err(format!("Expected {} embeddings, got {}", texts.len(), embeddings.len()));
+    }
+    Ok(embeddings
+        .iter()
+        .map(|e| {
+            e["values"]
+                .as_array()
+                .map(|values| values.iter().map(|v| v.as_f64().unwrap_or(0.0) as f32).collect())
+                .unwrap_or_default()
+        })
+        .collect())
+}
+
+/// Rows that can't be embedded (blank text, or no values in the response) get an
+/// empty blob so they leave the queue; the vector index and similarity search
+/// only read blobs of the full vector size. Returns how many real vectors were stored.
+fn store_embeddings(app_handle: &AppHandle, batch: &[Pending], vectors: &[Vec<f32>]) -> Result<usize, String> {
+    let state = app_handle.state::<Arc<AppState>>();
+    let mut conn = state.db()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut stored = 0;
+    for (pending, vector) in batch.iter().zip(vectors) {
+        // Skip rows whose text changed while the request was in flight
+        let updated = tx
+            .execute(
+                &format!("UPDATE {} SET embedding = ?1 WHERE id = ?2 AND content = ?3", pending.table.name()),
+                params![embedding_to_blob(vector), pending.id, pending.content],
+            )
+            .map_err(|e| format!("Failed to store embedding: {}", e))?;
+        if !vector.is_empty() {
+            stored += updated;
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(stored)
+}
+
+/// Embed one batch; returns how many rows were indexed
+async fn run_pass(app_handle: &AppHandle) -> Result<usize, String> {
+    if encryption::is_locked() {
+        return Ok(0);
+    }
+    // Synced even without an API key so full-text search sees knowledge edits
+    sync_knowledge(app_handle).await?;
+    let Some(api_key) = api_key(app_handle) else {
+        return Ok(0);
+    };
+
+    let batch = pending_batch(app_handle)?;
+    if batch.is_empty() {
+        return Ok(0);
+    }
+    let texts: Vec<String> = batch.iter().map(|p| p.content.chars().take(MAX_TEXT_CHARS).collect()).collect();
+
+    // Blank texts are rejected by the API, so only the rest are sent
+    let to_embed: Vec<String> = texts.iter().filter(|t| !t.trim().is_empty()).cloned().collect();
+    let mut embedded = if to_embed.is_empty() {
+        Vec::new().into_iter()
+    } else {
+        update_status(app_handle, |s| s.indexing = true);
+        let result = embed_batch(&api_key, &to_embed).await;
+        update_status(app_handle, |s| s.indexing = false);
+        result?.into_iter()
+    };
+    let vectors: Vec<Vec<f32>> = texts
+        .iter()
+        .map(|t| if t.trim().is_empty() { Vec::new() } else { embedded.next().unwrap_or_default() })
+        .collect();
+
+    let stored = store_embeddings(app_handle, &batch, &vectors)?;
+    debug!("Embedded {} of {} queued entries", stored, batch.len());
+    update_status(app_handle, |s| {
+        s.indexed += stored;
+        s.last_error = None;
+    });
+    Ok(stored)
+}
+
+/// Start the background worker
+pub fn start(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        info!("Embedding worker started");
+        loop {
+            let delay = match run_pass(&app_handle).await {
+                Ok(0) => IDLE_INTERVAL,
+                Ok(_) => continue, // More may be queued
+                Err(e) => {
+                    warn!("Embedding pass failed: {}", e);
+                    update_status(&app_handle, |s| s.last_error = Some(e));
+                    ERROR_BACKOFF
+                }
+            };
+            let embeddings = app_handle.state::<EmbeddingState>();
+            tokio::select! {
+                _ = embeddings.wake.notified() => {}
+                _ = tokio::time::sleep(delay) => {}
+            }
+        }
+    });
+}
+
+/// Let the worker know new entries were saved
+pub fn wake(app_handle: &AppHandle) {
+    app_handle.state::<EmbeddingState>().wake.notify_one();
+}
+
+#[tauri::command]
+pub async fn get_embedding_queue_status(app_handle: AppHandle) -> Result<EmbeddingQueueStatus, String> {
+    let (pending_chat, pending_knowledge) = {
+        let state = app_handle.state::<Arc<AppState>>();
+        let conn = state.db()?;
+        let pending_chat = conn
+            .query_row(
+                &format!(
+                    "SELECT COUNT(*) FROM chat_entries WHERE embedding IS NULL AND entry_type IN ({})",
+                    EMBEDDED_CHAT_TYPES
+                ),
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        let pending_knowledge = conn
+            .query_row("SELECT COUNT(*) FROM knowledge_entries WHERE embedding IS NULL", [], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        (pending_chat, pending_knowledge)
+    };
+
+    let embeddings = app_handle.state::<EmbeddingState>();
+    let status = embeddings.status.lock().map_err(|e| e.to_string())?;
+    Ok(EmbeddingQueueStatus {
+        pending_chat,
+        pending_knowledge,
+        indexing: status.indexing,
+        indexed: status.indexed,
+        last_error: status.last_error.clone(),
+        api_key_configured: api_key(&app_handle).is_some(),
+    })
+}
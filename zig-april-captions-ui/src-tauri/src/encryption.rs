@@ -0,0 +1,321 @@
+// Encrypted storage
+//
+// With a passphrase set, zigy.db is never written in the clear. The database
+// runs in memory and is saved as one AES-256-GCM sealed image,
+// `zigy.db.enc`, whenever it changed: every AUTOSAVE_INTERVAL, when a
+// session ends and at shutdown. The key is derived from the passphrase with
+// PBKDF2 (on a blocking thread); the parameters live in `encryption.json`,
+// whose presence means encryption is on. With `remember` the derived key is
+// also kept in the OS keyring so the app unlocks by itself on launch; portable
+// installs never use the keyring.
+//
+// Until it is unlocked the app runs on an empty, read-only in-memory database
+// (writes would be lost on unlock) and refuses to start captions. The crash-recovery transcript logs are not
+// written while encryption is on, and plain database snapshots in backups/
+// are deleted when it is turned on. Audio recordings and exported files are
+// not encrypted.
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{error, info, warn};
+
+use crate::crypto::{self, SecretKey};
+use crate::{database, db_backups, persist, portable, AppState};
+
+/// How often a changed database is sealed and written out
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(15);
+
+const PBKDF2_ITERATIONS: u32 = 600_000;
+const SALT_LEN: usize = 16;
+const CHECK_PLAINTEXT: &[u8] = b"zigy-storage-key";
+
+const KEYRING_SERVICE: &str = "zigy";
+const KEYRING_USER: &str = "storage-key";
+
+/// Key of the unlocked database; None while locked or not encrypted
+static KEY: Mutex<Option<SecretKey>> = Mutex::new(None);
+
+/// `total_changes()` of the connection when it was last saved
+static SAVED_CHANGES: AtomicI64 = AtomicI64::new(-1);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EncryptionHeader {
+    version: u32,
+    salt: Vec<u8>,
+    iterations: u32,
+    check: Vec<u8>, // CHECK_PLAINTEXT sealed with the key
+}
+
+fn header_path() -> PathBuf {
+    crate::get_config_dir().join("encryption.json")
+}
+
+fn image_path() -> PathBuf {
+    crate::get_config_dir().join("zigy.db.enc")
+}
+
+fn load_header() -> Option<EncryptionHeader> {
+    persist::load_json(&header_path())
+}
+
+pub fn is_enabled() -> bool {
+    header_path().exists()
+}
+
+pub fn is_locked() -> bool {
+    is_enabled() && KEY.lock().map(|k| k.is_none()).unwrap_or(true)
+}
+
+fn current_key() -> Option<SecretKey> {
+    KEY.lock().ok().and_then(|k| *k)
+}
+
+fn set_key(key: Option<SecretKey>) {
+    if let Ok(mut current) = KEY.lock() {
+        *current = key;
+    }
+    SAVED_CHANGES.store(-1, Ordering::SeqCst);
+}
+
+fn new_header(passphrase: &str) -> Result<(EncryptionHeader, SecretKey), String> {
+    let salt = crypto::random_bytes(SALT_LEN);
+    let key = crypto::derive_key(passphrase, &salt, PBKDF2_ITERATIONS);
+    let header = EncryptionHeader {
+        version: 1,
+        salt,
+        iterations: PBKDF2_ITERATIONS,
+        check: crypto::encrypt(&key, CHECK_PLAINTEXT)?,
+    };
+    Ok((header, key))
+}
+
+fn key_matches(header: &EncryptionHeader, key: &SecretKey) -> bool {
+    crypto::decrypt(key, &header.check).is_ok_and(|check| check == CHECK_PLAINTEXT)
+}
+
+fn unlock_with_passphrase(passphrase: &str) -> Result<SecretKey, String> {
+    let header = load_header().ok_or("Storage is not encrypted")?;
+    let key = crypto::derive_key(passphrase, &header.salt, header.iterations);
+    if !key_matches(&header, &key) {
+        return Err("Wrong passphrase".to_string());
+    }
+    Ok(key)
+}
+
+fn keyring_entry() -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER).map_err(|e| e.to_string())
+}
+
+fn remember_key(key: Option<&SecretKey>) {
+    // A portable install leaves nothing in the machine's keyring
+    if portable::is_portable() {
+        return;
+    }
+    let result = keyring_entry().and_then(|entry| match key {
+        Some(key) => entry.set_secret(key).map_err(|e| e.to_string()),
+        None => match entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        },
+    });
+    if let Err(e) = result {
+        warn!("Failed to update the keyring: {}", e);
+    }
+}
+
+fn keyring_key() -> Option<SecretKey> {
+    if portable::is_portable() {
+        return None;
+    }
+    let secret = keyring_entry().ok()?.get_secret().ok()?;
+    secret.try_into().ok()
+}
+
+/// Decrypt the database image into an in-memory connection
+fn open_image(key: &SecretKey) -> Result<Connection, String> {
+    let sealed = std::fs::read(image_path()).map_err(|e| format!("Failed to read encrypted database: {}", e))?;
+    let image = crypto::decrypt(key, &sealed)?;
+    database::load_db_image(&image).map_err(|e| format!("Failed to open encrypted database: {}", e))
+}
+
+fn write_image(key: &SecretKey, image: &[u8]) -> Result<(), String> {
+    let sealed = crypto::encrypt(key, image)?;
+    persist::write_atomic(&image_path(), &sealed).map_err(|e| format!("Failed to write encrypted database: {}", e))
+}
+
+fn total_changes(conn: &Connection) -> i64 {
+    conn.query_row("SELECT total_changes()", [], |row| row.get(0)).unwrap_or(0)
+}
+
+/// The database to start with: the plain file, the encrypted image unlocked
+/// from the keyring, or an empty in-memory one while locked
+pub fn open_db() -> rusqlite::Result<Connection> {
+    if !is_enabled() {
+        return database::init_db();
+    }
+    let header = load_header();
+    if let Some(key) = keyring_key().filter(|k| header.as_ref().is_some_and(|h| key_matches(h, k))) {
+        match open_image(&key) {
+            Ok(conn) => {
+                set_key(Some(key));
+                info!("Encrypted storage unlocked from the keyring");
+                return Ok(conn);
+            }
+            Err(e) => error!("{}", e),
+        }
+    }
+    info!("Storage is encrypted and locked");
+    let conn = database::init_memory_db()?;
+    conn.pragma_update(None, "query_only", true)?;
+    Ok(conn)
+}
+
+/// Seal and write the database if it changed since the last save
+pub fn save(conn: &Connection) -> Result<(), String> {
+    let Some(key) = current_key() else {
+        return Ok(());
+    };
+    let changes = total_changes(conn);
+    if SAVED_CHANGES.load(Ordering::SeqCst) == changes {
+        return Ok(());
+    }
+    let image = database::serialize_db(conn).map_err(|e| e.to_string())?;
+    write_image(&key, &image)?;
+    SAVED_CHANGES.store(changes, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Save the encrypted database periodically; runs for the life of the app
+pub fn start(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(AUTOSAVE_INTERVAL).await;
+            if current_key().is_none() {
+                continue;
+            }
+            let state = app_handle.state::<Arc<AppState>>().inner().clone();
+            let result = tauri::async_runtime::spawn_blocking(move || {
+                let conn = state.db()?;
+                save(&conn)
+            })
+            .await;
+            if let Ok(Err(e)) = result {
+                error!("Failed to save encrypted database: {}", e);
+            }
+        }
+    });
+}
+
+/// Run a slow key derivation without holding up the async runtime
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> Result<T, String> + Send + 'static) -> Result<T, String> {
+    tauri::async_runtime::spawn_blocking(f).await.map_err(|e| e.to_string())?
+}
+
+/// Remove the plain database and its journal files
+fn remove_plain_db() {
+    let path = database::get_db_path();
+    for suffix in ["", "-wal", "-shm", "-journal"] {
+        let file = PathBuf::from(format!("{}{}", path.display(), suffix));
+        if file.exists() {
+            if let Err(e) = std::fs::remove_file(&file) {
+                warn!("Failed to remove {}: {}", file.display(), e);
+            }
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn is_storage_locked() -> Result<bool, String> {
+    Ok(is_locked())
+}
+
+/// Unlock encrypted storage, optionally remembering the key in the OS keyring
+#[tauri::command]
+pub async fn unlock_storage(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    passphrase: String,
+    remember: bool,
+) -> Result<(), String> {
+    if !is_locked() {
+        return Ok(());
+    }
+    let (key, conn) = blocking(move || {
+        let key = unlock_with_passphrase(&passphrase)?;
+        Ok((key, open_image(&key)?))
+    })
+    .await?;
+    *state.db()? = conn;
+    set_key(Some(key));
+    remember_key(remember.then_some(&key));
+    info!("Encrypted storage unlocked");
+    let _ = app_handle.emit("storage-unlocked", ());
+    Ok(())
+}
+
+/// Turn encryption on, change the passphrase, or turn it off with `None`.
+/// Changing or removing it needs the current passphrase.
+#[tauri::command]
+pub async fn set_encryption_passphrase(
+    state: tauri::State<'_, Arc<AppState>>,
+    passphrase: Option<String>,
+    current_passphrase: Option<String>,
+    remember: bool,
+) -> Result<(), String> {
+    let passphrase = passphrase.filter(|p| !p.is_empty());
+    if is_enabled() {
+        if is_locked() {
+            return Err("Unlock the storage first".to_string());
+        }
+        let current = current_passphrase.unwrap_or_default();
+        blocking(move || unlock_with_passphrase(&current))
+            .await
+            .map_err(|_| "The current passphrase is wrong".to_string())?;
+    }
+    let new_key = match passphrase {
+        Some(passphrase) => Some(blocking(move || new_header(&passphrase)).await?),
+        None => None,
+    };
+
+    let mut conn = state.db()?;
+    match new_key {
+        Some((header, key)) => {
+            let image = database::serialize_db(&conn).map_err(|e| e.to_string())?;
+            write_image(&key, &image)?;
+            persist::persist_json(&header_path(), &header)?;
+            if current_key().is_none() {
+                // Move off the plain file onto the in-memory copy
+                *conn = database::load_db_image(&image).map_err(|e| e.to_string())?;
+                state.readers.clear();
+                remove_plain_db();
+                db_backups::remove_all();
+            }
+            set_key(Some(key));
+            SAVED_CHANGES.store(total_changes(&conn), Ordering::SeqCst);
+            remember_key(remember.then_some(&key));
+            info!("Storage encryption passphrase set");
+        }
+        None => {
+            if !is_enabled() {
+                return Ok(());
+            }
+            let path = database::get_db_path();
+            remove_plain_db();
+            conn.execute("VACUUM INTO ?1", [path.to_string_lossy()])
+                .map_err(|e| format!("Failed to write the database: {}", e))?;
+            *conn = database::init_db().map_err(|e| e.to_string())?;
+            state.readers.clear();
+            set_key(None);
+            remember_key(None);
+            for file in [header_path(), persist::backup_path(&header_path()), image_path()] {
+                let _ = std::fs::remove_file(file);
+            }
+            info!("Storage encryption turned off");
+        }
+    }
+    Ok(())
+}
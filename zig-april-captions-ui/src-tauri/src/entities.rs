@@ -0,0 +1,245 @@
+// Entity and keyword tagging
+//
+// `analyze_session` asks the AI model for the people, companies, products
+// and key terms discussed in a session. Each becomes a tag (unique per name
+// and kind, ignoring case) linked to the session through `entity_mentions`
+// with the number of times it appears in the transcript. Re-analyzing a
+// session replaces its mentions. `get_sessions_by_tag` then finds every
+// meeting that mentioned something.
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::AppHandle;
+use tracing::info;
+
+use crate::ai::generate_content;
+use crate::database::{self, Session};
+use crate::{integrations, AppState};
+
+const ANALYZE_SYSTEM_PROMPT: &str = "You index meeting transcripts. \
+    Respond with JSON: {\"people\": [string], \"companies\": [string], \"products\": [string], \"terms\": [string]}. \
+    Use names exactly as written in the transcript. `terms` are up to 10 key topics or jargon worth \
+    searching for later. Leave out generic words. Use empty arrays when nothing fits.";
+
+/// Tags kept per kind for one session
+const MAX_TAGS_PER_KIND: usize = 25;
+
+#[derive(Debug, Default, Deserialize)]
+struct Extracted {
+    #[serde(default)]
+    people: Vec<String>,
+    #[serde(default)]
+    companies: Vec<String>,
+    #[serde(default)]
+    products: Vec<String>,
+    #[serde(default)]
+    terms: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionTag {
+    pub name: String,
+    pub kind: String, // "person", "company", "product" or "term"
+    pub mentions: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TagSummary {
+    pub name: String,
+    pub kind: String,
+    pub sessions: i64,
+}
+
+/// Case-insensitive occurrences of `name` on word boundaries
+fn count_mentions(text: &str, name: &str) -> i64 {
+    let haystack = text.to_lowercase();
+    let needle = name.to_lowercase();
+    if needle.is_empty() {
+        return 0;
+    }
+    let is_word_char = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric());
+    haystack
+        .match_indices(&needle)
+        .filter(|(i, _)| {
+            let before = haystack[..*i].chars().next_back();
+            let after = haystack[i + needle.len()..].chars().next();
+            !is_word_char(before) && !is_word_char(after)
+        })
+        .count() as i64
+}
+
+/// Replace a session's mentions with `tags`
+fn store_tags(conn: &Connection, session_id: &str, tags: &[SessionTag]) -> rusqlite::Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("DELETE FROM entity_mentions WHERE session_id = ?1", params![session_id])?;
+    for tag in tags {
+        tx.execute(
+            "INSERT OR IGNORE INTO tags (name, kind) VALUES (?1, ?2)",
+            params![tag.name, tag.kind],
+        )?;
+        tx.execute(
+            "INSERT OR REPLACE INTO entity_mentions (tag_id, session_id, mentions)
+             SELECT id, ?3, ?4 FROM tags WHERE name = ?1 COLLATE NOCASE AND kind = ?2",
+            params![tag.name, tag.kind, session_id, tag.mentions],
+        )?;
+    }
+    // Tags no session mentions any more
+    tx.execute(
+        "DELETE FROM tags WHERE id NOT IN (SELECT tag_id FROM entity_mentions)",
+        [],
+    )?;
+    tx.commit()
+}
+
+pub(crate) fn session_tags(conn: &Connection, session_id: &str) -> rusqlite::Result<Vec<SessionTag>> {
+    let mut stmt = conn.prepare(
+        "SELECT t.name, t.kind, m.mentions FROM entity_mentions m JOIN tags t ON t.id = m.tag_id
+         WHERE m.session_id = ?1 ORDER BY t.kind, m.mentions DESC, t.name",
+    )?;
+    let tags = stmt
+        .query_map(params![session_id], |row| {
+            Ok(SessionTag {
+                name: row.get(0)?,
+                kind: row.get(1)?,
+                mentions: row.get(2)?,
+            })
+        })?
+        .collect();
+    tags
+}
+
+/// Extract and store the entities and key terms of a session
+#[tauri::command]
+pub async fn analyze_session(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<Vec<SessionTag>, String> {
+    let ai = state
+        .settings
+        .lock()
+        .map_err(|e| e.to_string())?
+        .ai
+        .clone()
+        .ok_or("AI is not configured")?;
+    let text = integrations::session_text(&app_handle, &session_id)?;
+
+    let output = generate_content(&ai, ANALYZE_SYSTEM_PROMPT, &format!("Transcript:\n{}", text), true).await?;
+    let extracted: Extracted = serde_json::from_str(&output).map_err(|e| format!("Unexpected AI response: {}", e))?;
+
+    let mut tags: Vec<SessionTag> = Vec::new();
+    let groups = [
+        ("person", extracted.people),
+        ("company", extracted.companies),
+        ("product", extracted.products),
+        ("term", extracted.terms),
+    ];
+    for (kind, names) in groups {
+        for name in names.into_iter().take(MAX_TAGS_PER_KIND) {
+            let name = name.trim().to_string();
+            let duplicate = tags
+                .iter()
+                .any(|t| t.kind == kind && t.name.eq_ignore_ascii_case(&name));
+            if name.is_empty() || duplicate {
+                continue;
+            }
+            // Names the model made up or paraphrased still count once
+            let mentions = count_mentions(&text, &name).max(1);
+            tags.push(SessionTag {
+                name,
+                kind: kind.to_string(),
+                mentions,
+            });
+        }
+    }
+
+    let conn = state.db()?;
+    store_tags(&conn, &session_id, &tags).map_err(|e| format!("Failed to save tags: {}", e))?;
+    info!("Tagged session {} with {} entities", session_id, tags.len());
+    session_tags(&conn, &session_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_session_tags(
+    state: tauri::State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<Vec<SessionTag>, String> {
+    let conn = state.db()?;
+    session_tags(&conn, &session_id).map_err(|e| format!("Failed to load tags: {}", e))
+}
+
+/// Every tag with the number of sessions mentioning it, most common first
+#[tauri::command]
+pub async fn list_tags(
+    state: tauri::State<'_, Arc<AppState>>,
+    kind: Option<String>,
+) -> Result<Vec<TagSummary>, String> {
+    let conn = state.db()?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.name, t.kind, COUNT(m.session_id) AS sessions
+             FROM tags t JOIN entity_mentions m ON m.tag_id = t.id
+             WHERE ?1 IS NULL OR t.kind = ?1
+             GROUP BY t.id ORDER BY sessions DESC, t.name",
+        )
+        .map_err(|e| format!("Prepare failed: {}", e))?;
+    let tags = stmt
+        .query_map(params![kind], |row| {
+            Ok(TagSummary {
+                name: row.get(0)?,
+                kind: row.get(1)?,
+                sessions: row.get(2)?,
+            })
+        })
+        .map_err(|e| format!("Query failed: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(tags)
+}
+
+/// Sessions mentioning a tag (any kind unless `kind` is given), most recent first
+#[tauri::command]
+pub async fn get_sessions_by_tag(
+    state: tauri::State<'_, Arc<AppState>>,
+    tag: String,
+    kind: Option<String>,
+) -> Result<Vec<Session>, String> {
+    let conn = state.db()?;
+    let ids: Vec<String> = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT DISTINCT m.session_id FROM entity_mentions m JOIN tags t ON t.id = m.tag_id
+                 WHERE t.name = ?1 COLLATE NOCASE AND (?2 IS NULL OR t.kind = ?2)",
+            )
+            .map_err(|e| format!("Prepare failed: {}", e))?;
+        let ids = stmt
+            .query_map(params![tag.trim(), kind], |row| row.get(0))
+            .map_err(|e| format!("Query failed: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        ids
+    };
+
+    let mut sessions = Vec::new();
+    for id in ids {
+        if let Some(session) = database::get_session(&conn, &id).map_err(|e| e.to_string())? {
+            sessions.push(session);
+        }
+    }
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.started_at));
+    Ok(sessions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_mentions() {
+        let text = "Acme wants the Acme Cloud demo. acme, not Acmeville.";
+        assert_eq!(count_mentions(text, "Acme"), 3);
+        assert_eq!(count_mentions(text, "Acme Cloud"), 1);
+        assert_eq!(count_mentions(text, "Cloud demo"), 1);
+        assert_eq!(count_mentions(text, "Globex"), 0);
+    }
+}
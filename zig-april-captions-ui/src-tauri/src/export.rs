@@ -0,0 +1,543 @@
+// Caption export formats
+//
+// Captions with word timings become cues spanning their first to last word.
+// Others only carry the time they were finalized, so their cues end at that
+// timestamp and start where the previous cue ended (at most MAX_CUE_MS
+// earlier). Cue times are relative to the first cue so the file
+// lines up with a recording started alongside the captions. Speaker labels
+// are prefixed as "Alice: ..." (WebVTT uses its <v Alice> voice tag).
+// Captions are put in timestamp order first, so the "Me" and "Them" streams
+// of a two-sided capture interleave as they were spoken.
+//
+// Whole sessions export as meeting notes: a metadata header, the AI summary
+// and action items, bookmarks, the timestamped transcript and the Q&A asked
+// during the session, as Markdown or PDF (see pdf.rs).
+use serde::{Deserialize, Serialize};
+
+use rusqlite::{params, Connection};
+
+use crate::analytics::SessionAnalytics;
+use crate::bookmarks::{self, Bookmark};
+use crate::database::{self, Session, TranscriptLine};
+use crate::tasks::{self, TaskStatus};
+use crate::Caption;
+
+/// Longest a single subtitle cue may stay on screen
+const MAX_CUE_MS: i64 = 7000;
+
+/// Shortest cue, for captions finalized at the same instant
+const MIN_CUE_MS: i64 = 1000;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Srt,
+    Vtt,
+    #[default]
+    #[serde(alias = "text", alias = "txt")]
+    PlainText,
+    #[serde(alias = "md")]
+    Markdown,
+    Json,
+}
+
+#[derive(Debug, Serialize)]
+struct Cue<'a> {
+    index: usize,
+    start_ms: i64,
+    end_ms: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    speaker: Option<&'a str>,
+    text: &'a str,
+}
+
+impl Cue<'_> {
+    fn line(&self) -> String {
+        labelled(self.speaker, self.text)
+    }
+}
+
+/// WebVTT cue text escaping for `&`, `<` and `>`
+fn vtt_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn labelled(speaker: Option<&str>, text: &str) -> String {
+    match speaker.filter(|s| !s.is_empty()) {
+        Some(speaker) => format!("{}: {}", speaker, text),
+        None => text.to_string(),
+    }
+}
+
+/// Final captions in timestamp order
+fn finals(captions: &[Caption]) -> Vec<&Caption> {
+    let mut finals: Vec<&Caption> = captions.iter().filter(|c| c.caption_type == "final").collect();
+    finals.sort_by_key(|c| c.timestamp);
+    finals
+}
+
+fn cues(captions: &[Caption]) -> Vec<Cue<'_>> {
+    let finals: Vec<&Caption> = finals(captions)
+        .into_iter()
+        .filter(|c| !c.text.trim().is_empty())
+        .collect();
+
+    let mut cues = Vec::with_capacity(finals.len());
+    let mut prev_end = i64::MIN;
+    for (i, caption) in finals.iter().enumerate() {
+        let (start, end) = match (caption.words.first(), caption.words.last()) {
+            (Some(first), Some(last)) => {
+                let start = first.start_ms.max(prev_end);
+                (start, last.end_ms.max(start + MIN_CUE_MS))
+            }
+            _ => {
+                let start = prev_end.max(caption.timestamp - MAX_CUE_MS);
+                (start, caption.timestamp.max(start + MIN_CUE_MS))
+            }
+        };
+        cues.push(Cue {
+            index: i + 1,
+            start_ms: start,
+            end_ms: end,
+            speaker: caption.speaker.as_deref().filter(|s| !s.is_empty()),
+            text: caption.text.trim(),
+        });
+        prev_end = end;
+    }
+
+    if let Some(base) = cues.first().map(|c| c.start_ms) {
+        for cue in &mut cues {
+            cue.start_ms -= base;
+            cue.end_ms -= base;
+        }
+    }
+    cues
+}
+
+/// `HH:MM:SS<sep>mmm` from a millisecond offset
+fn cue_time(ms: i64, separator: char) -> String {
+    let ms = ms.max(0);
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        (ms / 60_000) % 60,
+        (ms / 1000) % 60,
+        separator,
+        ms % 1000
+    )
+}
+
+/// Wall-clock `HH:MM:SS` (UTC) of an epoch timestamp
+pub(crate) fn clock_time(timestamp_ms: i64) -> String {
+    let secs = timestamp_ms / 1000;
+    let hours = (secs / 3600) % 24;
+    let mins = (secs / 60) % 60;
+    let secs = secs % 60;
+    format!("{:02}:{:02}:{:02}", hours, mins, secs)
+}
+
+/// Calendar date and time (UTC) of an epoch timestamp
+pub(crate) fn date_time(timestamp_ms: i64) -> String {
+    // Civil-from-days, proleptic Gregorian
+    let days = timestamp_ms.div_euclid(86_400_000);
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02} {} UTC", year, month, day, &clock_time(timestamp_ms)[..5])
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionExportFormat {
+    #[default]
+    #[serde(alias = "md")]
+    Markdown,
+    Pdf,
+}
+
+/// Which sections `export_session` writes, and how
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionExportOptions {
+    #[serde(default)]
+    pub format: SessionExportFormat,
+    #[serde(default)]
+    pub font_size: Option<f32>, // PDF body size in points; derived from the caption font size when unset
+    #[serde(default = "crate::default_true")]
+    pub include_summary: bool,
+    #[serde(default = "crate::default_true")]
+    pub include_action_items: bool,
+    #[serde(default = "crate::default_true")]
+    pub include_transcript: bool,
+    #[serde(default = "crate::default_true")]
+    pub include_qa: bool,
+    #[serde(default = "crate::default_true")]
+    pub include_bookmarks: bool,
+    #[serde(default)]
+    pub generate_summary: bool, // Ask the AI for a fresh summary instead of the last saved one
+    #[serde(default)]
+    pub include_analytics: bool, // Talk time and speaker share, see analytics
+}
+
+impl Default for SessionExportOptions {
+    fn default() -> Self {
+        Self {
+            format: SessionExportFormat::Markdown,
+            font_size: None,
+            include_summary: true,
+            include_action_items: true,
+            include_transcript: true,
+            include_qa: true,
+            include_bookmarks: true,
+            generate_summary: false,
+            include_analytics: false,
+        }
+    }
+}
+
+/// An answer from the chat history, with the question that prompted it
+#[derive(Debug, Clone)]
+pub struct QaPair {
+    pub timestamp: i64,
+    pub question: Option<String>,
+    pub answer: String,
+}
+
+/// A follow-up from the session's tasks, or from a freshly generated summary
+#[derive(Debug, Clone)]
+pub struct ActionItem {
+    pub text: String,
+    pub done: bool,
+}
+
+/// Everything recorded for one session
+#[derive(Debug, Clone)]
+pub struct SessionNotes {
+    pub session: Session,
+    pub summary: Option<String>,
+    pub action_items: Vec<ActionItem>,
+    pub transcript: Vec<TranscriptLine>,
+    pub qa: Vec<QaPair>,
+    pub bookmarks: Vec<Bookmark>,
+    pub analytics: Option<SessionAnalytics>, // Loaded when the export asks for it
+}
+
+impl SessionNotes {
+    /// Speakers in order of first appearance
+    pub(crate) fn speakers(&self) -> Vec<&str> {
+        let mut speakers: Vec<&str> = Vec::new();
+        for line in &self.transcript {
+            if let Some(speaker) = line.speaker.as_deref().filter(|s| !s.is_empty()) {
+                if !speakers.contains(&speaker) {
+                    speakers.push(speaker);
+                }
+            }
+        }
+        speakers
+    }
+}
+
+/// Collect a session's transcript, last saved summary, tasks and answered questions
+pub fn load_session_notes(conn: &Connection, session_id: &str) -> Result<SessionNotes, String> {
+    let session = database::get_session(conn, session_id)
+        .map_err(|e| format!("Failed to load session: {}", e))?
+        .ok_or_else(|| format!("Session {} not found", session_id))?;
+    let transcript = database::session_transcript_lines(conn, session_id)
+        .map_err(|e| format!("Failed to load transcript: {}", e))?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT timestamp, entry_type, content, metadata FROM chat_entries
+             WHERE session_id = ?1 AND entry_type IN ('summary', 'answer') ORDER BY timestamp ASC",
+        )
+        .map_err(|e| format!("Prepare failed: {}", e))?;
+    let entries = stmt
+        .query_map(params![session_id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?,
+            ))
+        })
+        .map_err(|e| format!("Query failed: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut summary = None;
+    let mut qa = Vec::new();
+    for (timestamp, entry_type, content, metadata) in entries {
+        if entry_type == "summary" {
+            summary = Some(content);
+            continue;
+        }
+        let question = metadata
+            .and_then(|m| serde_json::from_str::<serde_json::Value>(&m).ok())
+            .and_then(|m| m["questionAsked"].as_str().map(|q| q.to_string()));
+        qa.push(QaPair {
+            timestamp,
+            question,
+            answer: content,
+        });
+    }
+
+    let bookmarks = bookmarks::session_bookmarks(conn, session_id)
+        .map_err(|e| format!("Failed to load bookmarks: {}", e))?;
+    let action_items = tasks::query_tasks(conn, Some(session_id), None)?
+        .into_iter()
+        .map(|task| ActionItem {
+            text: match &task.assignee {
+                Some(assignee) => format!("{} (@{})", task.text, assignee),
+                None => task.text.clone(),
+            },
+            done: task.status == TaskStatus::Done,
+        })
+        .collect();
+
+    Ok(SessionNotes {
+        session,
+        summary,
+        action_items,
+        transcript,
+        qa,
+        bookmarks,
+        analytics: None,
+    })
+}
+
+/// A bookmark's label, or else the transcript line it was made on
+pub(crate) fn bookmark_text(notes: &SessionNotes, bookmark: &Bookmark) -> String {
+    if let Some(label) = &bookmark.label {
+        return label.clone();
+    }
+    notes
+        .transcript
+        .iter()
+        .rev()
+        .find(|line| line.timestamp <= bookmark.timestamp)
+        .map(|line| format!("\"{}\"", line.text.trim()))
+        .unwrap_or_else(|| "Bookmark".to_string())
+}
+
+/// "12 min", or "45 s" under a minute
+pub(crate) fn duration(ms: i64) -> String {
+    if ms < 60_000 {
+        format!("{} s", (ms.max(0) + 500) / 1000)
+    } else {
+        format!("{} min", (ms + 30_000) / 60_000)
+    }
+}
+
+/// Lines summarizing talk time, one per speaker after the overall figures
+pub(crate) fn analytics_lines(analytics: &SessionAnalytics) -> Vec<String> {
+    let mut lines = vec![format!(
+        "Speaking: {} of {} ({:.0} wpm), silence: {} in {} gaps, interruptions: {}",
+        duration(analytics.speaking_ms),
+        duration(analytics.duration_ms),
+        analytics.words_per_minute,
+        duration(analytics.silence_ms),
+        analytics.silence_gaps.len(),
+        analytics.interruptions
+    )];
+    for speaker in &analytics.speakers {
+        lines.push(format!(
+            "{}: {:.0}% of talk time, {}, {} words ({:.0} wpm), {} interruptions",
+            speaker.speaker,
+            speaker.share * 100.0,
+            duration(speaker.speaking_ms),
+            speaker.words,
+            speaker.words_per_minute,
+            speaker.interruptions
+        ));
+    }
+    lines
+}
+
+/// Render a session as Markdown meeting notes
+pub fn render_session_markdown(notes: &SessionNotes, options: &SessionExportOptions) -> String {
+    let session = &notes.session;
+    let mut content = format!("# {}\n\n", session.title);
+    content.push_str(&format!("- **Date:** {}\n", date_time(session.started_at)));
+    if let Some(ended_at) = session.ended_at {
+        let minutes = ((ended_at - session.started_at).max(0) + 30_000) / 60_000;
+        content.push_str(&format!("- **Duration:** {} min\n", minutes));
+    }
+    if let Some(source) = session.audio_source.as_deref().filter(|s| !s.is_empty()) {
+        content.push_str(&format!("- **Audio source:** {}\n", source));
+    }
+    if let Some(model) = session.model_path.as_deref().filter(|s| !s.is_empty()) {
+        let name = std::path::Path::new(model)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| model.to_string());
+        content.push_str(&format!("- **Model:** {}\n", name));
+    }
+    let speakers = notes.speakers();
+    if !speakers.is_empty() {
+        content.push_str(&format!("- **Speakers:** {}\n", speakers.join(", ")));
+    }
+
+    if options.include_summary {
+        if let Some(summary) = notes.summary.as_deref().filter(|s| !s.trim().is_empty()) {
+            content.push_str(&format!("\n## Summary\n\n{}\n", summary.trim()));
+        }
+    }
+    if options.include_action_items && !notes.action_items.is_empty() {
+        content.push_str("\n## Action items\n\n");
+        for item in &notes.action_items {
+            content.push_str(&format!("- [{}] {}\n", if item.done { "x" } else { " " }, item.text));
+        }
+    }
+    if let Some(analytics) = notes.analytics.as_ref().filter(|_| options.include_analytics) {
+        content.push_str("\n## Talk time\n\n");
+        for line in analytics_lines(analytics) {
+            content.push_str(&format!("- {}\n", line));
+        }
+    }
+    if options.include_bookmarks && !notes.bookmarks.is_empty() {
+        content.push_str("\n## Bookmarks\n\n");
+        for bookmark in &notes.bookmarks {
+            content.push_str(&format!("- **{}** {}\n", clock_time(bookmark.timestamp), bookmark_text(notes, bookmark)));
+        }
+    }
+    if options.include_transcript && !notes.transcript.is_empty() {
+        content.push_str("\n## Transcript\n\n");
+        for line in &notes.transcript {
+            let text = match line.speaker.as_deref().filter(|s| !s.is_empty()) {
+                Some(speaker) => format!("**{}:** {}", speaker, line.text),
+                None => line.text.clone(),
+            };
+            content.push_str(&format!("- **{}** {}\n", clock_time(line.timestamp), text));
+        }
+    }
+    if options.include_qa && !notes.qa.is_empty() {
+        content.push_str("\n## Q&A\n");
+        for pair in &notes.qa {
+            content.push_str(&format!("\n_{}_\n\n", clock_time(pair.timestamp)));
+            if let Some(question) = pair.question.as_deref().filter(|q| !q.is_empty()) {
+                content.push_str(&format!("**Q:** {}\n\n", question));
+            }
+            content.push_str(&format!("**A:** {}\n", pair.answer.trim()));
+        }
+    }
+    content
+}
+
+/// Render captions in the requested format
+pub fn render(captions: &[Caption], format: ExportFormat) -> Result<String, String> {
+    let mut content = String::new();
+    match format {
+        ExportFormat::Srt => {
+            for cue in cues(captions) {
+                content.push_str(&format!(
+                    "{}\n{} --> {}\n{}\n\n",
+                    cue.index,
+                    cue_time(cue.start_ms, ','),
+                    cue_time(cue.end_ms, ','),
+                    cue.line()
+                ));
+            }
+        }
+        ExportFormat::Vtt => {
+            content.push_str("WEBVTT\n\n");
+            for cue in cues(captions) {
+                content.push_str(&format!(
+                    "{}\n{} --> {}\n{}\n\n",
+                    cue.index,
+                    cue_time(cue.start_ms, '.'),
+                    cue_time(cue.end_ms, '.'),
+                    match cue.speaker {
+                        Some(speaker) => format!("<v {}>{}", vtt_escape(speaker), cue.text),
+                        None => cue.text.to_string(),
+                    }
+                ));
+            }
+        }
+        ExportFormat::PlainText => {
+            content.push_str("# Zigy Export\n\n");
+            for caption in finals(captions) {
+                let line = labelled(caption.speaker.as_deref(), &caption.text);
+                content.push_str(&format!("[{}] {}\n", clock_time(caption.timestamp), line));
+            }
+        }
+        ExportFormat::Markdown => {
+            content.push_str("# Zigy Export\n\n");
+            for caption in finals(captions) {
+                let line = match caption.speaker.as_deref().filter(|s| !s.is_empty()) {
+                    Some(speaker) => format!("**{}:** {}", speaker, caption.text),
+                    None => caption.text.clone(),
+                };
+                content.push_str(&format!("- **{}** {}\n", clock_time(caption.timestamp), line));
+            }
+        }
+        ExportFormat::Json => {
+            content = serde_json::to_string_pretty(&cues(captions)).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::CaptionWord;
+
+    fn caption(text: &str, timestamp: i64) -> Caption {
+        Caption {
+            id: text.to_string(),
+            text: text.to_string(),
+            caption_type: "final".to_string(),
+            timestamp,
+            speaker: None,
+            words: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_srt_timing() {
+        let captions = vec![caption("Hello there", 10_000), caption("General Kenobi", 12_500)];
+        let srt = render(&captions, ExportFormat::Srt).unwrap();
+        assert_eq!(
+            srt,
+            "1\n00:00:00,000 --> 00:00:07,000\nHello there\n\n\
+             2\n00:00:07,000 --> 00:00:09,500\nGeneral Kenobi\n\n"
+        );
+    }
+
+    #[test]
+    fn test_vtt_speaker_escaped() {
+        let mut line = caption("Hello", 10_000);
+        line.speaker = Some("Q&A <host>".to_string());
+        let vtt = render(&[line], ExportFormat::Vtt).unwrap();
+        assert!(vtt.contains("<v Q&amp;A &lt;host&gt;>Hello"));
+    }
+
+    #[test]
+    fn test_date_time() {
+        assert_eq!(date_time(0), "1970-01-01 00:00 UTC");
+        assert_eq!(date_time(1_709_210_096_000), "2024-02-29 12:34 UTC");
+    }
+
+    #[test]
+    fn test_srt_word_timing() {
+        let word = |text: &str, start_ms, end_ms| CaptionWord {
+            text: text.to_string(),
+            start_ms,
+            end_ms,
+            confidence: Some(0.9),
+        };
+        let mut first = caption("Hello there", 10_000);
+        first.words = vec![word("Hello", 8_200, 8_600), word("there", 8_700, 9_400)];
+        let srt = render(&[first, caption("General Kenobi", 12_500)], ExportFormat::Srt).unwrap();
+        assert_eq!(
+            srt,
+            "1\n00:00:00,000 --> 00:00:01,200\nHello there\n\n\
+             2\n00:00:01,200 --> 00:00:04,300\nGeneral Kenobi\n\n"
+        );
+    }
+}
@@ -0,0 +1,195 @@
+// Global hotkeys
+//
+// OS-level shortcuts that control captioning while another app has focus.
+// Accelerators use the usual Tauri syntax ("CommandOrControl+Shift+C") and
+// are re-registered whenever settings are saved. Each press emits
+// `hotkey-action` { action, state, error } so the UI can follow along:
+//
+//   start_stop     start with the saved model and source, or stop
+//   pause_resume   pause a running session, or resume a paused one
+//   bookmark       bookmark the current moment of the running session
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
+use tracing::{info, warn};
+
+use crate::AppState;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HotkeySettings {
+    #[serde(default = "crate::default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_start_stop")]
+    pub start_stop: Option<String>, // None leaves the action unbound
+    #[serde(default = "default_pause_resume")]
+    pub pause_resume: Option<String>,
+    #[serde(default = "default_bookmark")]
+    pub bookmark: Option<String>,
+}
+
+fn default_start_stop() -> Option<String> {
+    Some("CommandOrControl+Alt+C".to_string())
+}
+
+fn default_pause_resume() -> Option<String> {
+    Some("CommandOrControl+Alt+P".to_string())
+}
+
+fn default_bookmark() -> Option<String> {
+    Some("CommandOrControl+Alt+B".to_string())
+}
+
+impl Default for HotkeySettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            start_stop: default_start_stop(),
+            pause_resume: default_pause_resume(),
+            bookmark: default_bookmark(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum HotkeyAction {
+    StartStop,
+    PauseResume,
+    Bookmark,
+}
+
+impl HotkeyAction {
+    fn name(self) -> &'static str {
+        match self {
+            HotkeyAction::StartStop => "start_stop",
+            HotkeyAction::PauseResume => "pause_resume",
+            HotkeyAction::Bookmark => "bookmark",
+        }
+    }
+}
+
+/// Shortcuts currently registered with the OS
+#[derive(Default)]
+pub struct HotkeyState {
+    bindings: Mutex<Vec<(Shortcut, HotkeyAction)>>,
+}
+
+/// Replace the registered shortcuts with the ones from settings. Hotkeys are
+/// off unless `Settings.hotkeys` is set.
+pub fn apply_settings(app_handle: &AppHandle) -> Result<(), String> {
+    let settings = app_handle
+        .state::<Arc<AppState>>()
+        .settings
+        .lock()
+        .map_err(|e| e.to_string())?
+        .hotkeys
+        .clone();
+    let hotkeys = app_handle.state::<HotkeyState>();
+
+    // Registration may hop to the main thread, so the binding list is only
+    // locked to swap it in afterwards
+    let shortcuts = app_handle.global_shortcut();
+    shortcuts.unregister_all().map_err(|e| e.to_string())?;
+    let mut bindings = Vec::new();
+    let (start_stop, pause_resume, bookmark) = match settings {
+        Some(s) if s.enabled => (s.start_stop, s.pause_resume, s.bookmark),
+        _ => (None, None, None),
+    };
+    let wanted = [
+        (start_stop, HotkeyAction::StartStop),
+        (pause_resume, HotkeyAction::PauseResume),
+        (bookmark, HotkeyAction::Bookmark),
+    ];
+    let mut errors = Vec::new();
+    for (accelerator, action) in wanted {
+        let Some(accelerator) = accelerator.filter(|a| !a.trim().is_empty()) else {
+            continue;
+        };
+        let shortcut = match accelerator.parse::<Shortcut>() {
+            Ok(s) => s,
+            Err(e) => {
+                errors.push(format!("Invalid hotkey \"{}\": {}", accelerator, e));
+                continue;
+            }
+        };
+        if bindings.iter().any(|(s, _)| *s == shortcut) {
+            errors.push(format!("Hotkey \"{}\" is assigned twice", accelerator));
+            continue;
+        }
+        match shortcuts.register(shortcut) {
+            Ok(()) => bindings.push((shortcut, action)),
+            Err(e) => errors.push(format!("Could not register hotkey \"{}\": {}", accelerator, e)),
+        }
+    }
+
+    info!("Registered {} global hotkey(s)", bindings.len());
+    *hotkeys.bindings.lock().map_err(|e| e.to_string())? = bindings;
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+/// Register the saved hotkeys at launch
+pub fn start(app_handle: &AppHandle) {
+    if let Err(e) = apply_settings(app_handle) {
+        warn!("Global hotkeys: {}", e);
+    }
+}
+
+/// Plugin handler; runs on the event loop so the work is moved off it
+pub fn on_shortcut(app_handle: &AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    if event.state() != ShortcutState::Pressed {
+        return;
+    }
+    let action = app_handle
+        .state::<HotkeyState>()
+        .bindings
+        .lock()
+        .ok()
+        .and_then(|b| b.iter().find(|(s, _)| s == shortcut).map(|(_, a)| *a));
+    let Some(action) = action else {
+        return;
+    };
+
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = run_action(&app_handle, action).await;
+        let state = crate::caption_state(&app_handle.state::<Arc<AppState>>());
+        match &result {
+            Ok(()) => info!("Hotkey {} pressed, captions now {}", action.name(), state),
+            Err(e) => warn!("Hotkey {} failed: {}", action.name(), e),
+        }
+        let _ = app_handle.emit(
+            "hotkey-action",
+            serde_json::json!({ "action": action.name(), "state": state, "error": result.err() }),
+        );
+    });
+}
+
+async fn run_action(app_handle: &AppHandle, action: HotkeyAction) -> Result<(), String> {
+    let current = crate::caption_state(&app_handle.state::<Arc<AppState>>());
+    match (action, current) {
+        (HotkeyAction::StartStop, "stopped") => {
+            let (model_path, audio_source) = {
+                let state = app_handle.state::<Arc<AppState>>();
+                let settings = state.settings.lock().map_err(|e| e.to_string())?;
+                (settings.model_path.clone(), settings.audio_source.clone())
+            };
+            if model_path.is_empty() {
+                return Err("No model configured".to_string());
+            }
+            crate::start_captions(app_handle.clone(), app_handle.state(), model_path, audio_source, None, None).await
+        }
+        (HotkeyAction::StartStop, _) => crate::stop_captions(app_handle.clone(), app_handle.state()).await,
+        (HotkeyAction::PauseResume, "running") => {
+            crate::pause_captions(app_handle.clone(), app_handle.state()).await
+        }
+        (HotkeyAction::PauseResume, "paused") => {
+            crate::resume_captions(app_handle.clone(), app_handle.state()).await
+        }
+        (HotkeyAction::PauseResume, _) => Err("Captions are not running".to_string()),
+        (HotkeyAction::Bookmark, _) => crate::bookmarks::add(app_handle, None).map(|_| ()),
+    }
+}
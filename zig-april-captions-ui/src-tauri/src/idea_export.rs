@@ -0,0 +1,148 @@
+// Idea scripts for editors
+//
+// `export_idea` writes an idea's corrected script (its raw draft when it has
+// none yet) as Markdown, Fountain or plain text. Each file states the word
+// count and how long the script takes to say at WORDS_PER_MINUTE.
+//
+// In Fountain the script is the dialogue of a single NARRATOR, one block per
+// paragraph, after a title page; the counts go in its Notes field.
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::info;
+
+use crate::export::date_time;
+use crate::{AppState, IdeaEntry};
+
+/// Speaking rate used for the duration estimate
+const WORDS_PER_MINUTE: usize = 150;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum IdeaExportFormat {
+    #[default]
+    #[serde(alias = "md")]
+    Markdown,
+    Fountain,
+    #[serde(alias = "text", alias = "txt")]
+    PlainText,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScriptStats {
+    pub words: usize,
+    pub speaking_seconds: u64,
+}
+
+impl ScriptStats {
+    fn of(script: &str) -> Self {
+        let words = script.split_whitespace().count();
+        ScriptStats { words, speaking_seconds: (words as u64 * 60).div_ceil(WORDS_PER_MINUTE as u64) }
+    }
+
+    /// "245 words, about 1:38 spoken"
+    fn describe(&self) -> String {
+        format!(
+            "{} words, about {}:{:02} spoken",
+            self.words,
+            self.speaking_seconds / 60,
+            self.speaking_seconds % 60
+        )
+    }
+}
+
+fn paragraphs(script: &str) -> Vec<String> {
+    script
+        .split("\n\n")
+        .map(|p| p.lines().map(str::trim).filter(|l| !l.is_empty()).collect::<Vec<_>>().join("\n"))
+        .filter(|p| !p.is_empty())
+        .collect()
+}
+
+/// The title with line breaks flattened, or "Untitled idea"
+fn title(idea: &IdeaEntry) -> String {
+    let title = idea.title.split_whitespace().collect::<Vec<_>>().join(" ");
+    if title.is_empty() {
+        "Untitled idea".to_string()
+    } else {
+        title
+    }
+}
+
+fn render_fountain(idea: &IdeaEntry, script: &str, stats: &ScriptStats) -> String {
+    let mut out = format!(
+        "Title: {}\nDraft date: {}\nNotes: {}\n\n",
+        title(idea),
+        &date_time(idea.created_at)[..10],
+        stats.describe()
+    );
+    for paragraph in paragraphs(script) {
+        // Dialogue ends at a blank line, so every paragraph needs its own cue
+        out.push_str("NARRATOR\n");
+        out.push_str(&paragraph);
+        out.push_str("\n\n");
+    }
+    out
+}
+
+pub fn render(idea: &IdeaEntry, format: IdeaExportFormat) -> (String, ScriptStats) {
+    let script = if idea.corrected_script.trim().is_empty() {
+        idea.raw_content.trim()
+    } else {
+        idea.corrected_script.trim()
+    };
+    let stats = ScriptStats::of(script);
+    let content = match format {
+        IdeaExportFormat::Markdown => {
+            format!("# {}\n\n*{}*\n\n{}\n", title(idea), stats.describe(), paragraphs(script).join("\n\n"))
+        }
+        IdeaExportFormat::Fountain => render_fountain(idea, script, &stats),
+        IdeaExportFormat::PlainText => {
+            format!("{}\n\n{}\n\n---\n{}\n", title(idea), paragraphs(script).join("\n\n"), stats.describe())
+        }
+    };
+    (content, stats)
+}
+
+/// Write an idea's script to `path`; returns its word count and speaking time
+#[tauri::command]
+pub async fn export_idea(
+    state: tauri::State<'_, Arc<AppState>>,
+    id: String,
+    format: Option<IdeaExportFormat>,
+    path: String,
+) -> Result<ScriptStats, String> {
+    let idea = state
+        .ideas
+        .read()?
+        .iter()
+        .find(|e| e.id == id)
+        .cloned()
+        .ok_or_else(|| "Idea entry not found".to_string())?;
+    let (content, stats) = render(&idea, format.unwrap_or_default());
+    std::fs::write(&path, content).map_err(|e| format!("Failed to write file: {}", e))?;
+    info!("Exported idea {} to {}", id, path);
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_fountain() {
+        let idea = IdeaEntry {
+            id: "idea".to_string(),
+            title: "Launch\nvideo".to_string(),
+            raw_content: "draft".to_string(),
+            corrected_script: format!("{}\n\nThanks for\nwatching.", "word ".repeat(148)),
+            created_at: 0,
+            source_session_id: None,
+            source_entry_ids: Vec::new(),
+        };
+        let (content, stats) = render(&idea, IdeaExportFormat::Fountain);
+        assert_eq!((stats.words, stats.speaking_seconds), (151, 61));
+        let title_page = "Title: Launch video\nDraft date: 1970-01-01\nNotes: 151 words, about 1:01 spoken\n\n";
+        assert!(content.starts_with(title_page));
+        assert!(content.ends_with("NARRATOR\nThanks for\nwatching.\n\n"));
+    }
+}
@@ -0,0 +1,165 @@
+// Versions of an idea's corrected script
+//
+// Every corrected script an idea gets (the first one, each AI rewrite, each
+// hand edit) is kept in `idea_versions`, numbered from 1, instead of only the
+// latest being stored on the idea. Versions come with a word diff against
+// the one before, so rewrites can be compared. Restoring a version makes it
+// the idea's script again as a new version; nothing is overwritten.
+//
+// Ideas saved before versioning have no rows until their script first
+// changes; the script they had then is recorded as version 1.
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::transcribe::{diff_transcripts, TranscriptDiff};
+use crate::{now_millis, AppState, IdeaEntry};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IdeaVersion {
+    pub idea_id: String,
+    pub version: i64,
+    pub corrected_script: String,
+    pub created_at: i64,
+    pub diff: Option<TranscriptDiff>, // Against the previous version; None for the first
+}
+
+fn latest(conn: &Connection, idea_id: &str) -> rusqlite::Result<Option<(i64, String)>> {
+    let mut stmt = conn.prepare(
+        "SELECT version, corrected_script FROM idea_versions WHERE idea_id = ?1 ORDER BY version DESC LIMIT 1",
+    )?;
+    let mut rows = stmt.query_map(params![idea_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    rows.next().transpose()
+}
+
+fn insert(conn: &Connection, idea_id: &str, version: i64, script: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO idea_versions (idea_id, version, corrected_script, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![idea_id, version, script, now_millis()],
+    )?;
+    Ok(())
+}
+
+/// Record `script` as the idea's newest version unless it already is.
+/// `previous` is the script the idea had, recorded first when the idea has
+/// no versions yet.
+pub fn record(conn: &Connection, idea_id: &str, previous: Option<&str>, script: &str) -> rusqlite::Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    let mut latest = latest(&tx, idea_id)?;
+    if latest.is_none() {
+        if let Some(previous) = previous.filter(|p| *p != script) {
+            insert(&tx, idea_id, 1, previous)?;
+            latest = Some((1, previous.to_string()));
+        }
+    }
+    match latest {
+        Some((_, text)) if text == script => {}
+        Some((version, _)) => insert(&tx, idea_id, version + 1, script)?,
+        None => insert(&tx, idea_id, 1, script)?,
+    }
+    tx.commit()
+}
+
+pub fn delete_versions(conn: &Connection, idea_id: &str) -> rusqlite::Result<()> {
+    conn.execute("DELETE FROM idea_versions WHERE idea_id = ?1", params![idea_id])?;
+    Ok(())
+}
+
+fn with_diffs(versions: Vec<IdeaVersion>) -> Vec<IdeaVersion> {
+    let mut previous: Option<String> = None;
+    versions
+        .into_iter()
+        .map(|mut version| {
+            version.diff = previous
+                .as_ref()
+                .map(|p| diff_transcripts(std::slice::from_ref(p), std::slice::from_ref(&version.corrected_script)));
+            previous = Some(version.corrected_script.clone());
+            version
+        })
+        .collect()
+}
+
+/// An idea's versions, oldest first, each with a diff against the one before
+#[tauri::command]
+pub async fn get_idea_versions(
+    state: tauri::State<'_, Arc<AppState>>,
+    id: String,
+) -> Result<Vec<IdeaVersion>, String> {
+    let conn = state.db()?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT idea_id, version, corrected_script, created_at FROM idea_versions
+             WHERE idea_id = ?1 ORDER BY version ASC",
+        )
+        .map_err(|e| format!("Prepare failed: {}", e))?;
+    let versions = stmt
+        .query_map(params![id], |row| {
+            Ok(IdeaVersion {
+                idea_id: row.get(0)?,
+                version: row.get(1)?,
+                corrected_script: row.get(2)?,
+                created_at: row.get(3)?,
+                diff: None,
+            })
+        })
+        .map_err(|e| format!("Query failed: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(with_diffs(versions))
+}
+
+/// Make an earlier version the idea's script again; it is recorded as the
+/// newest version
+#[tauri::command]
+pub async fn restore_idea_version(
+    state: tauri::State<'_, Arc<AppState>>,
+    id: String,
+    version: i64,
+) -> Result<IdeaEntry, String> {
+    let conn = state.db()?;
+    let script: String = conn
+        .query_row(
+            "SELECT corrected_script FROM idea_versions WHERE idea_id = ?1 AND version = ?2",
+            params![id, version],
+            |row| row.get(0),
+        )
+        .map_err(|_| format!("Version {} of the idea not found", version))?;
+    let updated = state.ideas.update(|entries| {
+        entries.iter_mut().find(|e| e.id == id).map(|e| {
+            e.corrected_script = script.clone();
+            e.clone()
+        })
+    })?;
+    let updated = updated.ok_or_else(|| "Idea entry not found".to_string())?;
+    record(&conn, &id, None, &script).map_err(|e| format!("Failed to save idea version: {}", e))?;
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let conn = crate::database::init_memory_db().unwrap();
+
+        record(&conn, "idea", Some("first draft"), "first draft, rewritten").unwrap();
+        record(&conn, "idea", None, "first draft, rewritten").unwrap();
+        record(&conn, "idea", None, "first draft").unwrap();
+        let scripts: Vec<(i64, String)> = conn
+            .prepare("SELECT version, corrected_script FROM idea_versions ORDER BY version")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            scripts,
+            vec![
+                (1, "first draft".to_string()),
+                (2, "first draft, rewritten".to_string()),
+                (3, "first draft".to_string())
+            ]
+        );
+    }
+}
@@ -0,0 +1,327 @@
+// Transcript import
+//
+// Turns SRT, WebVTT and plain-text transcripts (Zoom, Teams, Meet exports)
+// into a session with transcript lines and `transcript` chat entries, so they
+// are searchable and available to AI Q&A like native recordings.
+//
+// Cue times are offsets into the meeting. Exports are usually written when
+// the meeting ends, so the session is placed to end at the file's
+// modification time. Speakers come from WebVTT voice tags (<v Alice>) or an
+// "Alice: ..." prefix. Plain-text lines may start with an "[HH:MM:SS]"
+// offset; lines without one are spaced by an estimated speaking rate.
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+use tauri::AppHandle;
+use tracing::info;
+
+use crate::database::{self, Session, TranscriptLine};
+use crate::{embeddings, now_millis, AppState};
+
+/// Speaking-rate estimate for untimed plain-text lines
+const MS_PER_WORD: i64 = 400;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    Srt,
+    Vtt,
+    #[serde(alias = "text", alias = "plaintext")]
+    Txt,
+}
+
+impl ImportFormat {
+    fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "srt" => Some(ImportFormat::Srt),
+            "vtt" => Some(ImportFormat::Vtt),
+            "txt" | "text" => Some(ImportFormat::Txt),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ImportedLine {
+    start_ms: i64, // Offset from the start of the transcript
+    end_ms: i64,
+    speaker: Option<String>,
+    text: String,
+}
+
+/// A fraction of a second as milliseconds: ".5" is 500, digits past the third are dropped
+fn parse_millis(fraction: &str) -> Option<i64> {
+    if fraction.is_empty() || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let digits: String = fraction.chars().chain("00".chars()).take(3).collect();
+    digits.parse().ok()
+}
+
+/// "HH:MM:SS,mmm", "HH:MM:SS.mmm", "MM:SS.mmm" or "HH:MM:SS"
+fn parse_time(value: &str) -> Option<i64> {
+    let value = value.trim();
+    let (clock, millis) = match value.rfind([',', '.']) {
+        Some(i) => (&value[..i], parse_millis(&value[i + 1..])?),
+        None => (value, 0),
+    };
+    let mut total = 0i64;
+    let parts: Vec<&str> = clock.split(':').collect();
+    if parts.len() < 2 || parts.len() > 3 {
+        return None;
+    }
+    for part in parts {
+        total = total * 60 + part.parse::<i64>().ok()?;
+    }
+    Some(total * 1000 + millis)
+}
+
+/// Split a leading "Name: " off a line. Names are short and capitalized so
+/// ordinary sentences with a colon are left alone.
+fn split_speaker(text: &str) -> (Option<String>, String) {
+    if let Some((name, rest)) = text.split_once(": ") {
+        let name = name.trim();
+        let plausible = !name.is_empty()
+            && name.len() <= 40
+            && name.split_whitespace().count() <= 3
+            && name.chars().next().is_some_and(|c| c.is_uppercase())
+            && !name.chars().any(|c| c.is_ascii_digit());
+        if plausible && !rest.trim().is_empty() {
+            return (Some(name.to_string()), rest.trim().to_string());
+        }
+    }
+    (None, text.trim().to_string())
+}
+
+/// Cue text without markup; a WebVTT voice tag becomes the speaker
+fn strip_tags(text: &str) -> (Option<String>, String) {
+    let mut speaker = None;
+    let mut plain = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find('<') {
+        plain.push_str(&rest[..open]);
+        let Some(close) = rest[open..].find('>') else {
+            rest = &rest[open..];
+            break;
+        };
+        let tag = &rest[open + 1..open + close];
+        // <v Alice> or <v.loud Alice>
+        if let Some(voice) = tag.strip_prefix('v').filter(|t| t.starts_with([' ', '.'])) {
+            if let Some((_, name)) = voice.split_once(' ') {
+                speaker = Some(name.trim().to_string()).filter(|n| !n.is_empty());
+            }
+        }
+        rest = &rest[open + close + 1..];
+    }
+    plain.push_str(rest);
+    (speaker, plain)
+}
+
+/// SRT and WebVTT share the cue layout: an optional identifier, a
+/// "start --> end" line, then text up to a blank line
+fn parse_cues(content: &str) -> Vec<ImportedLine> {
+    let mut lines = Vec::new();
+    let normalized = content.replace("\r\n", "\n");
+    for block in normalized.split("\n\n") {
+        let mut block_lines = block.lines().skip_while(|l| !l.contains("-->"));
+        let Some(timing) = block_lines.next() else {
+            continue; // WEBVTT header, NOTE and STYLE blocks
+        };
+        let Some((start, end)) = timing.split_once("-->") else {
+            continue;
+        };
+        // WebVTT cue settings follow the end time
+        let end = end.split_whitespace().next().unwrap_or("");
+        let (Some(start_ms), Some(end_ms)) = (parse_time(start), parse_time(end)) else {
+            continue;
+        };
+
+        let raw = block_lines.map(str::trim).filter(|l| !l.is_empty()).collect::<Vec<_>>().join(" ");
+        let (voice, text) = strip_tags(&raw);
+        let (prefix, text) = split_speaker(&text);
+        if text.is_empty() {
+            continue;
+        }
+        lines.push(ImportedLine {
+            start_ms,
+            end_ms: end_ms.max(start_ms),
+            speaker: voice.or(prefix),
+            text,
+        });
+    }
+    lines
+}
+
+fn parse_text(content: &str) -> Vec<ImportedLine> {
+    let mut lines = Vec::new();
+    let mut next_start = 0i64;
+    for line in content.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let (offset, line) = match line.strip_prefix('[').and_then(|l| l.split_once(']')) {
+            Some((time, rest)) if parse_time(time).is_some() => (parse_time(time), rest.trim()),
+            _ => (None, line),
+        };
+        let (speaker, text) = split_speaker(line);
+        if text.is_empty() {
+            continue;
+        }
+        let start_ms = offset.unwrap_or(next_start);
+        let end_ms = start_ms + text.split_whitespace().count() as i64 * MS_PER_WORD;
+        next_start = end_ms;
+        lines.push(ImportedLine {
+            start_ms,
+            end_ms,
+            speaker,
+            text,
+        });
+    }
+    lines
+}
+
+fn parse(content: &str, format: ImportFormat) -> Vec<ImportedLine> {
+    match format {
+        ImportFormat::Srt | ImportFormat::Vtt => parse_cues(content),
+        ImportFormat::Txt => parse_text(content),
+    }
+}
+
+/// Store a finished session with its transcript, mirroring each line into
+/// `transcript` chat entries so search and AI Q&A see it. `source` is the
+/// session's audio source and the lines' source.
+#[allow(clippy::too_many_arguments)]
+pub fn save_session(
+    conn: &Connection,
+    title: &str,
+    started_at: i64,
+    ended_at: i64,
+    source: &str,
+    model_path: &str,
+    lines: &[TranscriptLine],
+    metadata: &serde_json::Value,
+) -> Result<Session, String> {
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let session = database::start_session(&tx, started_at, source, model_path).map_err(|e| e.to_string())?;
+    database::rename_session(&tx, &session.id, title).map_err(|e| e.to_string())?;
+    let metadata = metadata.to_string();
+    for line in lines {
+        database::add_transcript_line(&tx, &session.id, line.timestamp, &line.text, Some(source), line.speaker.as_deref())
+            .map_err(|e| e.to_string())?;
+        tx.execute(
+            "INSERT INTO chat_entries (id, timestamp, entry_type, content, metadata, session_id, speaker)
+             VALUES (?1, ?2, 'transcript', ?3, ?4, ?5, ?6)",
+            params![
+                uuid::Uuid::new_v4().to_string(),
+                line.timestamp,
+                line.text,
+                metadata,
+                session.id,
+                line.speaker
+            ],
+        )
+        .map_err(|e| format!("Failed to save chat history: {}", e))?;
+    }
+    database::end_session(&tx, &session.id, ended_at).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    database::get_session(conn, &session.id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Saved session disappeared".to_string())
+}
+
+/// Import a transcript file as a new, finished session. The format is taken
+/// from the file extension when not given.
+#[tauri::command]
+pub async fn import_transcript(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    path: String,
+    format: Option<ImportFormat>,
+    session_title: Option<String>,
+) -> Result<Session, String> {
+    let file = Path::new(&path);
+    let format = format
+        .or_else(|| ImportFormat::from_path(file))
+        .ok_or("Unknown transcript format; expected .srt, .vtt or .txt")?;
+    let content = std::fs::read_to_string(file).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let content = content.trim_start_matches('\u{feff}');
+    let lines = parse(content, format);
+    if lines.is_empty() {
+        return Err("No transcript lines found in the file".to_string());
+    }
+
+    let duration = lines.iter().map(|l| l.end_ms).max().unwrap_or(0);
+    let ended_at = std::fs::metadata(file)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_else(now_millis);
+    let started_at = ended_at - duration;
+    let file_name = file
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.clone());
+    let title = session_title
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .or_else(|| file.file_stem().map(|s| s.to_string_lossy().to_string()))
+        .unwrap_or_else(|| "Imported transcript".to_string());
+
+    let transcript: Vec<TranscriptLine> = lines
+        .iter()
+        .map(|line| TranscriptLine {
+            timestamp: started_at + line.end_ms,
+            text: line.text.clone(),
+            speaker: line.speaker.clone(),
+        })
+        .collect();
+    let metadata = serde_json::json!({ "imported": file_name });
+    let session = {
+        let conn = state.db()?;
+        save_session(&conn, &title, started_at, ended_at, "import", "", &transcript, &metadata)?
+    };
+    embeddings::wake(&app_handle);
+
+    info!("Imported {} lines from {} into session {}", lines.len(), file_name, session.id);
+    Ok(session)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cues() {
+        let vtt = "WEBVTT\n\nNOTE exported\n\n1\n00:00:01.000 --> 00:00:03.500 align:start\n<v Alice>Hello <i>there</i></v>\n\n\
+                   00:04.000 --> 00:06.000\nBob: General\nKenobi\n";
+        assert_eq!(
+            parse(vtt, ImportFormat::Vtt),
+            vec![
+                ImportedLine {
+                    start_ms: 1000,
+                    end_ms: 3500,
+                    speaker: Some("Alice".to_string()),
+                    text: "Hello there".to_string(),
+                },
+                ImportedLine {
+                    start_ms: 4000,
+                    end_ms: 6000,
+                    speaker: Some("Bob".to_string()),
+                    text: "General Kenobi".to_string(),
+                },
+            ]
+        );
+
+        let text = parse("[00:01:00] Alice: Let's start\nNote that 3: items remain", ImportFormat::Txt);
+        assert_eq!(text[0].start_ms, 60_000);
+        assert_eq!(text[0].speaker.as_deref(), Some("Alice"));
+        assert_eq!(text[1].start_ms, 60_000 + 2 * MS_PER_WORD);
+        assert_eq!(text[1].speaker, None);
+    }
+
+    #[test]
+    fn test_parse_time_fraction() {
+        assert_eq!(parse_time("00:01.5"), Some(1_500));
+        assert_eq!(parse_time("00:00:01,25"), Some(1_250));
+        assert_eq!(parse_time("00:01.1234"), Some(1_123));
+    }
+}
@@ -0,0 +1,178 @@
+// Caption ingestion from external ASR sources
+//
+// Lets a phone app or another machine's recognizer feed captions into Zigy
+// through the embedded server, so they flow through the same pipeline as the
+// local binary (frontend transcript, streaming clients, OBS, webhooks).
+// Disabled unless `ingest_enabled` is set in the server settings. Sources
+// authenticate with the server's ingest token; the server token isn't
+// accepted here (see server.rs).
+//
+//   POST /ingest       one caption object or an array of them
+//   GET  /ingest/ws    WebSocket; each text frame is one caption object
+//
+// Caption object (same shape the local binary emits):
+//   { "captionType": "partial" | "final", "text": "...", "source": "phone", "speaker": "Alice" }
+// `source` is optional and is reported as "external:<source>"; `speaker` is
+// an optional diarization label. Finals may carry
+// `"words": [{ "text", "startMs", "endMs", "confidence" }]` with epoch-ms times.
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Deserialize;
+use tauri::AppHandle;
+use tracing::{debug, info};
+
+use crate::database::CaptionWord;
+use crate::server::ServerContext;
+use crate::{handle_caption_event, CaptionEvent};
+
+#[derive(Debug, Deserialize)]
+pub struct IncomingCaption {
+    #[serde(rename = "captionType")]
+    caption_type: String,
+    text: String,
+    #[serde(default)]
+    source: Option<String>,
+    #[serde(default)]
+    timestamp: Option<i64>,
+    #[serde(default)]
+    speaker: Option<String>,
+    #[serde(default)]
+    words: Vec<CaptionWord>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum IncomingBatch {
+    One(IncomingCaption),
+    Many(Vec<IncomingCaption>),
+}
+
+fn source_label(source: Option<&str>) -> String {
+    format!("external:{}", source.filter(|s| !s.is_empty()).unwrap_or("remote"))
+}
+
+fn ingest_enabled(app_handle: &AppHandle) -> bool {
+    crate::server::current_settings(app_handle).ingest_enabled
+}
+
+/// Validate an incoming caption and hand it to the regular caption pipeline
+fn ingest(app_handle: &AppHandle, caption: IncomingCaption) -> Result<(), String> {
+    if caption.caption_type != "partial" && caption.caption_type != "final" {
+        return Err(format!("Invalid captionType: {}", caption.caption_type));
+    }
+    if caption.text.trim().is_empty() && caption.caption_type == "final" {
+        return Err("Final captions need text".to_string());
+    }
+
+    handle_caption_event(
+        app_handle,
+        CaptionEvent {
+            event_type: "caption".to_string(),
+            id: None,
+            caption_type: Some(caption.caption_type),
+            text: Some(caption.text),
+            timestamp: caption.timestamp.or_else(|| {
+                Some(
+                    std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .unwrap()
+                        .as_millis() as i64,
+                )
+            }),
+            message: None,
+            version: None,
+            source: Some(source_label(caption.source.as_deref())),
+            speaker: caption.speaker.filter(|s| !s.trim().is_empty()),
+            words: caption.words,
+            exit_code: None,
+            channel: None,
+        },
+    );
+    Ok(())
+}
+
+fn status_event(app_handle: &AppHandle, status: &str, source: &str, message: Option<&str>) {
+    handle_caption_event(
+        app_handle,
+        CaptionEvent {
+            event_type: status.to_string(),
+            id: None,
+            caption_type: None,
+            text: None,
+            timestamp: None,
+            message: message.map(|m| m.to_string()),
+            version: None,
+            source: Some(source.to_string()),
+            speaker: None,
+            words: Vec::new(),
+            exit_code: None,
+            channel: None,
+        },
+    );
+}
+
+/// `POST /ingest`
+pub async fn ingest_http(State(ctx): State<ServerContext>, Json(batch): Json<IncomingBatch>) -> Response {
+    if !ingest_enabled(&ctx.app_handle) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    let captions = match batch {
+        IncomingBatch::One(caption) => vec![caption],
+        IncomingBatch::Many(captions) => captions,
+    };
+    let mut accepted = 0;
+    for caption in captions {
+        if let Err(e) = ingest(&ctx.app_handle, caption) {
+            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": e, "accepted": accepted })))
+                .into_response();
+        }
+        accepted += 1;
+    }
+    Json(serde_json::json!({ "accepted": accepted })).into_response()
+}
+
+/// `GET /ingest/ws`
+pub async fn ingest_ws(ws: WebSocketUpgrade, State(ctx): State<ServerContext>) -> Response {
+    if !ingest_enabled(&ctx.app_handle) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    ws.on_upgrade(move |socket| receive_captions(socket, ctx.app_handle))
+}
+
+async fn receive_captions(mut socket: WebSocket, app_handle: AppHandle) {
+    let mut source: Option<String> = None;
+
+    while let Some(Ok(message)) = socket.recv().await {
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        let result = serde_json::from_str::<IncomingCaption>(&text)
+            .map_err(|e| format!("Invalid caption: {}", e))
+            .and_then(|caption| {
+                if source.is_none() {
+                    let label = source_label(caption.source.as_deref());
+                    info!("External caption source connected: {}", label);
+                    status_event(&app_handle, "listening", &label, None);
+                    source = Some(label);
+                }
+                ingest(&app_handle, caption)
+            });
+        if let Err(e) = result {
+            debug!("Rejected ingested caption: {}", e);
+            let reply = serde_json::json!({ "error": e }).to_string();
+            if socket.send(Message::Text(reply.into())).await.is_err() {
+                break;
+            }
+        }
+    }
+
+    if let Some(label) = source {
+        info!("External caption source disconnected: {}", label);
+        status_event(&app_handle, "warning", &label, Some("External caption source disconnected"));
+    }
+}
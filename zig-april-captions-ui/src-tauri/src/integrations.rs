@@ -0,0 +1,290 @@
+// Slack / Discord summary sharing
+//
+// Posts a session summary with action items to Slack incoming webhooks or
+// Discord channel webhooks, formatted natively for each (Block Kit for Slack,
+// an embed for Discord). Targets with `share_on_session_end` receive the
+// summary automatically when captions are stopped; any target can be used on
+// demand with `share_summary`, and `post_summary_to_slack` sends a stored
+// session to the Slack targets.
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+use tracing::{info, warn};
+
+use crate::ai::generate_content;
+use crate::{database, webhooks, AppState};
+
+/// Transcripts shorter than this are not worth summarizing automatically
+const MIN_AUTO_SHARE_LINES: usize = 5;
+
+/// Discord embed limits
+const DISCORD_DESCRIPTION_LIMIT: usize = 4096;
+const DISCORD_FIELD_LIMIT: usize = 1024;
+
+/// Slack section text limit
+const SLACK_SECTION_LIMIT: usize = 3000;
+
+const SUMMARY_SYSTEM_PROMPT: &str = "You summarize meeting transcripts. \
+    Respond with JSON: {\"title\": string (3-8 words), \"summary\": string (a short paragraph), \
+    \"action_items\": [string]} where action_items lists concrete follow-ups with owners when mentioned. \
+    Use an empty array when there are none.";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrationTarget {
+    pub id: String,
+    pub name: String,
+    pub kind: String, // "slack" or "discord"
+    pub webhook_url: String,
+    #[serde(default)]
+    pub share_on_session_end: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub title: String,
+    pub summary: String,
+    #[serde(default)]
+    pub action_items: Vec<String>,
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max_chars.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+fn slack_payload(summary: &SessionSummary) -> serde_json::Value {
+    let mut blocks = vec![
+        serde_json::json!({
+            "type": "header",
+            "text": { "type": "plain_text", "text": truncate(&summary.title, 150) }
+        }),
+        serde_json::json!({
+            "type": "section",
+            "text": { "type": "mrkdwn", "text": truncate(&summary.summary, SLACK_SECTION_LIMIT) }
+        }),
+    ];
+    if !summary.action_items.is_empty() {
+        let items = summary
+            .action_items
+            .iter()
+            .map(|item| format!("• {}", item))
+            .collect::<Vec<_>>()
+            .join("\n");
+        blocks.push(serde_json::json!({
+            "type": "section",
+            "text": { "type": "mrkdwn", "text": truncate(&format!("*Action items*\n{}", items), SLACK_SECTION_LIMIT) }
+        }));
+    }
+    // `text` is the notification fallback for clients that don't render blocks
+    serde_json::json!({ "text": summary.title, "blocks": blocks })
+}
+
+fn discord_payload(summary: &SessionSummary) -> serde_json::Value {
+    let mut embed = serde_json::json!({
+        "title": truncate(&summary.title, 256),
+        "description": truncate(&summary.summary, DISCORD_DESCRIPTION_LIMIT),
+        "color": 0x5865F2,
+        "footer": { "text": "Zigy" }
+    });
+    if !summary.action_items.is_empty() {
+        let items = summary
+            .action_items
+            .iter()
+            .map(|item| format!("- {}", item))
+            .collect::<Vec<_>>()
+            .join("\n");
+        embed["fields"] = serde_json::json!([{
+            "name": "Action items",
+            "value": truncate(&items, DISCORD_FIELD_LIMIT)
+        }]);
+    }
+    serde_json::json!({ "username": "Zigy", "embeds": [embed] })
+}
+
+async fn post_summary(target: &IntegrationTarget, summary: &SessionSummary) -> Result<(), String> {
+    let payload = match target.kind.as_str() {
+        "slack" => slack_payload(summary),
+        "discord" => discord_payload(summary),
+        other => return Err(format!("Unsupported integration: {}", other)),
+    };
+
+    let response = reqwest::Client::new()
+        .post(&target.webhook_url)
+        .json(&payload)
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("{} returned {}: {}", target.name, status, body));
+    }
+    info!("Summary shared to {} ({})", target.name, target.kind);
+    Ok(())
+}
+
+/// Ask the configured AI model for a title, summary and action items, and
+/// announce it with the `summary.ready` webhook
+pub async fn summarize(app_handle: &AppHandle, text: &str) -> Result<SessionSummary, String> {
+    let summary = generate_summary(app_handle, text).await?;
+    webhooks::dispatch(app_handle, webhooks::EVENT_SUMMARY_READY, serde_json::json!(summary));
+    Ok(summary)
+}
+
+/// `summarize` without the webhook, e.g. for an export
+pub async fn generate_summary(app_handle: &AppHandle, text: &str) -> Result<SessionSummary, String> {
+    let ai = app_handle
+        .state::<Arc<AppState>>()
+        .settings
+        .lock()
+        .map_err(|e| e.to_string())?
+        .ai
+        .clone()
+        .ok_or("AI is not configured")?;
+
+    let output = generate_content(&ai, SUMMARY_SYSTEM_PROMPT, &format!("Transcript:\n{}", text), true).await?;
+    let summary = serde_json::from_str::<SessionSummary>(&output).unwrap_or_else(|_| SessionSummary {
+        title: "Meeting summary".to_string(),
+        summary: output.trim().to_string(),
+        action_items: Vec::new(),
+    });
+    Ok(summary)
+}
+
+/// Source text for a stored session: its transcript, then any chat entries
+pub fn session_text(app_handle: &AppHandle, session_id: &str) -> Result<String, String> {
+    let state = app_handle.state::<Arc<AppState>>();
+    let conn = state.db()?;
+    let mut lines = database::session_transcript(&conn, session_id).map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare("SELECT entry_type, content FROM chat_entries WHERE session_id = ?1 ORDER BY timestamp ASC")
+        .map_err(|e| format!("Prepare failed: {}", e))?;
+    let entries = stmt
+        .query_map([session_id], |row| {
+            Ok(format!("[{}] {}", row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|e| format!("Query failed: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    lines.extend(entries);
+    if lines.is_empty() {
+        return Err(format!("Session {} not found", session_id));
+    }
+    Ok(lines.join("\n"))
+}
+
+fn targets(app_handle: &AppHandle) -> Vec<IntegrationTarget> {
+    app_handle
+        .state::<Arc<AppState>>()
+        .settings
+        .lock()
+        .map(|s| s.integrations.clone())
+        .unwrap_or_default()
+}
+
+/// Summarize the finished session and post it to auto-share targets
+pub fn on_session_end(app_handle: &AppHandle, transcript: Vec<String>) {
+    let auto_targets: Vec<IntegrationTarget> = targets(app_handle)
+        .into_iter()
+        .filter(|t| t.share_on_session_end)
+        .collect();
+    if auto_targets.is_empty() || transcript.len() < MIN_AUTO_SHARE_LINES {
+        return;
+    }
+
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let summary = match summarize(&app_handle, &transcript.join("\n")).await {
+            Ok(summary) => summary,
+            Err(e) => {
+                warn!("Could not summarize session for sharing: {}", e);
+                return;
+            }
+        };
+        for target in auto_targets {
+            if let Err(e) = post_summary(&target, &summary).await {
+                warn!("Failed to share summary: {}", e);
+            }
+        }
+    });
+}
+
+/// Summarize a stored session and post it to a Slack target (by id or
+/// name), or to every Slack target when none is given. Action items tracked
+/// for the session are used when the summary comes back without any.
+#[tauri::command]
+pub async fn post_summary_to_slack(
+    app_handle: AppHandle,
+    session_id: String,
+    target: Option<String>,
+) -> Result<SessionSummary, String> {
+    let slack_targets: Vec<IntegrationTarget> = targets(&app_handle)
+        .into_iter()
+        .filter(|t| t.kind == "slack")
+        .filter(|t| target.as_ref().map(|name| t.id == *name || t.name == *name).unwrap_or(true))
+        .collect();
+    if slack_targets.is_empty() {
+        return Err(match target {
+            Some(name) => format!("Slack integration {} not found", name),
+            None => "No Slack webhook is configured".to_string(),
+        });
+    }
+
+    let text = session_text(&app_handle, &session_id)?;
+    let mut summary = summarize(&app_handle, &text).await?;
+    if summary.action_items.is_empty() {
+        let state = app_handle.state::<Arc<AppState>>();
+        let conn = state.db()?;
+        summary.action_items = crate::tasks::query_tasks(&conn, Some(&session_id), None)?
+            .into_iter()
+            .map(|task| match task.assignee {
+                Some(assignee) => format!("{} ({})", task.text, assignee),
+                None => task.text,
+            })
+            .collect();
+    }
+
+    let mut errors = Vec::new();
+    for target in &slack_targets {
+        if let Err(e) = post_summary(target, &summary).await {
+            errors.push(e);
+        }
+    }
+    if errors.len() == slack_targets.len() {
+        return Err(errors.join("; "));
+    }
+    for e in errors {
+        warn!("Failed to share summary: {}", e);
+    }
+    Ok(summary)
+}
+
+/// Summarize a session and post it to one target. Without `session_id` the
+/// current transcript is used; otherwise the stored session.
+#[tauri::command]
+pub async fn share_summary(
+    app_handle: AppHandle,
+    session_id: Option<String>,
+    target: String,
+) -> Result<SessionSummary, String> {
+    let target = targets(&app_handle)
+        .into_iter()
+        .find(|t| t.id == target || t.name == target)
+        .ok_or_else(|| format!("Integration {} not found", target))?;
+
+    let text = match session_id.as_deref() {
+        Some(id) => session_text(&app_handle, id)?,
+        None => crate::api::transcript_lines(&app_handle)?.join("\n"),
+    };
+    if text.trim().is_empty() {
+        return Err("Nothing to summarize".to_string());
+    }
+
+    let summary = summarize(&app_handle, &text).await?;
+    post_summary(&target, &summary).await?;
+    Ok(summary)
+}
@@ -0,0 +1,443 @@
+// Tags, folders and duplicates of knowledge notes
+//
+// Notes live in the knowledge JSON store (and sync between devices with it),
+// so an entry carries its own tags and folder name. Folders are kept in the
+// `knowledge_folders` table, which is also where folders that exist only
+// because a synced note uses them get added. The embedding worker mirrors
+// each note's folder and tags into `knowledge_entries.folder` and
+// `knowledge_tags`, so queries over the database can group and filter by them.
+//
+// Tags compare case-insensitively; the spelling first given is kept.
+//
+// Near-duplicates (the same fact added twice, overlapping imports) are found
+// by comparing note embeddings and offered as groups; merging a group keeps
+// its oldest note with the text the user settled on, and records the ids it
+// absorbed under "merged_from" in the note's metadata.
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::sync::Arc;
+use tauri::AppHandle;
+
+use crate::{cosine_similarity, database, embeddings, now_millis, AppState, KnowledgeEntry};
+
+/// Similarity above which two notes count as duplicates by default
+const DUPLICATE_THRESHOLD: f32 = 0.9;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KnowledgeFolder {
+    pub name: String,
+    pub created_at: i64,
+    pub entry_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateGroup {
+    pub entries: Vec<KnowledgeEntry>,
+    pub similarity: f32, // Highest similarity between two of the entries
+}
+
+/// Whether two tags are the same, ignoring case (including non-ASCII letters)
+fn same_tag(a: &str, b: &str) -> bool {
+    a.to_lowercase() == b.to_lowercase()
+}
+
+/// Trimmed, without a leading '#', empty and duplicate tags dropped
+pub fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().trim_start_matches('#').trim();
+        if !tag.is_empty() && !normalized.iter().any(|t| same_tag(t, tag)) {
+            normalized.push(tag.to_string());
+        }
+    }
+    normalized
+}
+
+fn has_tag(entry: &KnowledgeEntry, tag: &str) -> bool {
+    entry.tags.iter().any(|t| same_tag(t, tag))
+}
+
+/// Rename `from` to `to` on every entry; returns how many entries had it
+fn rename_in(entries: &mut [KnowledgeEntry], from: &str, to: &str) -> usize {
+    let mut renamed = 0;
+    for entry in entries.iter_mut().filter(|e| has_tag(e, from)) {
+        let tags = entry
+            .tags
+            .iter()
+            .map(|t| if same_tag(t, from) { to.to_string() } else { t.clone() })
+            .collect();
+        entry.tags = normalize_tags(tags);
+        renamed += 1;
+    }
+    renamed
+}
+
+/// Write a note's folder and tags to the database; called by the embedding
+/// worker after the note itself is mirrored
+pub fn mirror(conn: &Connection, entry: &KnowledgeEntry) -> rusqlite::Result<()> {
+    conn.execute("UPDATE knowledge_entries SET folder = ?1 WHERE id = ?2", params![entry.folder, entry.id])?;
+    if let Some(folder) = &entry.folder {
+        conn.execute(
+            "INSERT OR IGNORE INTO knowledge_folders (name, created_at) VALUES (?1, ?2)",
+            params![folder, entry.created_at],
+        )?;
+    }
+    conn.execute("DELETE FROM knowledge_tags WHERE entry_id = ?1", params![entry.id])?;
+    for tag in &entry.tags {
+        conn.execute(
+            "INSERT OR IGNORE INTO knowledge_tags (entry_id, tag) VALUES (?1, ?2)",
+            params![entry.id, tag],
+        )?;
+    }
+    Ok(())
+}
+
+fn folder_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Folder name is empty".to_string());
+    }
+    Ok(name.to_string())
+}
+
+fn folder_exists(conn: &Connection, name: &str) -> Result<bool, String> {
+    conn.query_row("SELECT COUNT(*) FROM knowledge_folders WHERE name = ?1", params![name], |row| {
+        row.get::<_, i64>(0)
+    })
+    .map(|count| count > 0)
+    .map_err(|e| e.to_string())
+}
+
+/// Notes with `tag`, in store order
+#[tauri::command]
+pub async fn get_knowledge_by_tag(
+    state: tauri::State<'_, Arc<AppState>>,
+    tag: String,
+) -> Result<Vec<KnowledgeEntry>, String> {
+    let tag = tag.trim().trim_start_matches('#');
+    Ok(state.knowledge.read()?.iter().filter(|e| has_tag(e, tag)).cloned().collect())
+}
+
+#[tauri::command]
+pub async fn set_knowledge_tags(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    id: String,
+    tags: Vec<String>,
+) -> Result<KnowledgeEntry, String> {
+    let updated = state.knowledge.update(|entries| {
+        entries.iter_mut().find(|e| e.id == id).map(|e| {
+            e.tags = normalize_tags(tags);
+            e.clone()
+        })
+    })?;
+    let updated = updated.ok_or_else(|| "Knowledge entry not found".to_string())?;
+    embeddings::wake(&app_handle);
+    Ok(updated)
+}
+
+/// Rename a tag on all notes, merging it into `to` where a note has both;
+/// returns how many notes changed
+#[tauri::command]
+pub async fn rename_tag(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    from: String,
+    to: String,
+) -> Result<usize, String> {
+    let to = normalize_tags(vec![to]).pop().ok_or("New tag name is empty")?;
+    let renamed = state.knowledge.update(|entries| rename_in(entries, from.trim().trim_start_matches('#'), &to))?;
+    embeddings::wake(&app_handle);
+    Ok(renamed)
+}
+
+/// All folders by name, with how many notes each holds
+#[tauri::command]
+pub async fn get_knowledge_folders(state: tauri::State<'_, Arc<AppState>>) -> Result<Vec<KnowledgeFolder>, String> {
+    let mut folders: Vec<KnowledgeFolder> = {
+        let conn = state.db()?;
+        let mut stmt = conn
+            .prepare("SELECT name, created_at FROM knowledge_folders ORDER BY name COLLATE NOCASE")
+            .map_err(|e| format!("Prepare failed: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| Ok(KnowledgeFolder { name: row.get(0)?, created_at: row.get(1)?, entry_count: 0 }))
+            .map_err(|e| format!("Query failed: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        rows
+    };
+    for entry in state.knowledge.read()?.iter() {
+        let Some(name) = &entry.folder else {
+            continue;
+        };
+        match folders.iter_mut().find(|f| &f.name == name) {
+            Some(folder) => folder.entry_count += 1,
+            // Used by a note that arrived before the worker mirrored it
+            None => folders.push(KnowledgeFolder { name: name.clone(), created_at: entry.created_at, entry_count: 1 }),
+        }
+    }
+    Ok(folders)
+}
+
+#[tauri::command]
+pub async fn create_knowledge_folder(
+    state: tauri::State<'_, Arc<AppState>>,
+    name: String,
+) -> Result<KnowledgeFolder, String> {
+    let name = folder_name(&name)?;
+    let conn = state.db()?;
+    if folder_exists(&conn, &name)? {
+        return Err(format!("Folder \"{}\" already exists", name));
+    }
+    let created_at = now_millis();
+    conn.execute(
+        "INSERT INTO knowledge_folders (name, created_at) VALUES (?1, ?2)",
+        params![name, created_at],
+    )
+    .map_err(|e| format!("Failed to create folder: {}", e))?;
+    Ok(KnowledgeFolder { name, created_at, entry_count: 0 })
+}
+
+/// Rename a folder and move its notes along
+#[tauri::command]
+pub async fn rename_knowledge_folder(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    name: String,
+    new_name: String,
+) -> Result<(), String> {
+    let new_name = folder_name(&new_name)?;
+    {
+        let conn = state.db()?;
+        if new_name != name && folder_exists(&conn, &new_name)? {
+            return Err(format!("Folder \"{}\" already exists", new_name));
+        }
+        conn.execute("UPDATE knowledge_folders SET name = ?1 WHERE name = ?2", params![new_name, name])
+            .map_err(|e| format!("Failed to rename folder: {}", e))?;
+    }
+    state.knowledge.update(|entries| {
+        for entry in entries.iter_mut().filter(|e| e.folder.as_deref() == Some(name.as_str())) {
+            entry.folder = Some(new_name.clone());
+        }
+    })?;
+    embeddings::wake(&app_handle);
+    Ok(())
+}
+
+/// Delete a folder; its notes are kept, outside any folder
+#[tauri::command]
+pub async fn delete_knowledge_folder(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    name: String,
+) -> Result<(), String> {
+    {
+        let conn = state.db()?;
+        conn.execute("DELETE FROM knowledge_folders WHERE name = ?1", params![name])
+            .map_err(|e| format!("Failed to delete folder: {}", e))?;
+    }
+    state.knowledge.update(|entries| {
+        for entry in entries.iter_mut().filter(|e| e.folder.as_deref() == Some(name.as_str())) {
+            entry.folder = None;
+        }
+    })?;
+    embeddings::wake(&app_handle);
+    Ok(())
+}
+
+/// Put a note in a folder, or take it out of its folder with None
+#[tauri::command]
+pub async fn move_knowledge_entry(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    id: String,
+    folder: Option<String>,
+) -> Result<KnowledgeEntry, String> {
+    let folder = folder.map(|f| folder_name(&f)).transpose()?;
+    if let Some(folder) = &folder {
+        let conn = state.db()?;
+        if !folder_exists(&conn, folder)? {
+            return Err(format!("Folder \"{}\" not found", folder));
+        }
+    }
+    let updated = state.knowledge.update(|entries| {
+        entries.iter_mut().find(|e| e.id == id).map(|e| {
+            e.folder = folder;
+            e.clone()
+        })
+    })?;
+    let updated = updated.ok_or_else(|| "Knowledge entry not found".to_string())?;
+    embeddings::wake(&app_handle);
+    Ok(updated)
+}
+
+/// Groups of vectors linked by a similarity of at least `threshold`, as
+/// indexes with the group's highest similarity; single vectors are left out
+fn clusters(vectors: &[Vec<f32>], threshold: f32) -> Vec<(Vec<usize>, f32)> {
+    fn root(parents: &mut [usize], i: usize) -> usize {
+        let mut i = i;
+        while parents[i] != i {
+            parents[i] = parents[parents[i]];
+            i = parents[i];
+        }
+        i
+    }
+
+    let mut parents: Vec<usize> = (0..vectors.len()).collect();
+    let mut best = vec![0.0f32; vectors.len()];
+    for a in 0..vectors.len() {
+        for b in a + 1..vectors.len() {
+            let similarity = cosine_similarity(&vectors[a], &vectors[b]);
+            if similarity < threshold {
+                continue;
+            }
+            let (ra, rb) = (root(&mut parents, a), root(&mut parents, b));
+            parents[rb] = ra;
+            best[ra] = best[ra].max(best[rb]).max(similarity);
+        }
+    }
+
+    let mut groups: Vec<(usize, Vec<usize>)> = Vec::new();
+    for i in 0..vectors.len() {
+        let r = root(&mut parents, i);
+        match groups.iter_mut().find(|(group_root, _)| *group_root == r) {
+            Some((_, members)) => members.push(i),
+            None => groups.push((r, vec![i])),
+        }
+    }
+    groups
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(r, members)| (members, best[r]))
+        .collect()
+}
+
+/// Groups of near-duplicate notes, most similar first. Only notes the
+/// embedding worker has indexed are compared.
+#[tauri::command]
+pub async fn find_duplicate_knowledge(
+    state: tauri::State<'_, Arc<AppState>>,
+    threshold: Option<f32>,
+) -> Result<Vec<DuplicateGroup>, String> {
+    let threshold = threshold.unwrap_or(DUPLICATE_THRESHOLD);
+    let indexed: Vec<(String, Vec<f32>)> = {
+        let conn = state.db()?;
+        let mut stmt = conn
+            .prepare("SELECT id, embedding FROM knowledge_entries WHERE length(embedding) > 0")
+            .map_err(|e| format!("Prepare failed: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, database::blob_to_embedding(&row.get::<_, Vec<u8>>(1)?)))
+            })
+            .map_err(|e| format!("Query failed: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        rows
+    };
+
+    let knowledge = state.knowledge.read()?;
+    let (entries, vectors): (Vec<&KnowledgeEntry>, Vec<Vec<f32>>) = indexed
+        .into_iter()
+        .filter_map(|(id, vector)| knowledge.iter().find(|e| e.id == id).map(|e| (e, vector)))
+        .unzip();
+    let mut groups: Vec<DuplicateGroup> = clusters(&vectors, threshold)
+        .into_iter()
+        .map(|(members, similarity)| DuplicateGroup {
+            entries: members.into_iter().map(|i| entries[i].clone()).collect(),
+            similarity,
+        })
+        .collect();
+    groups.sort_by(|a, b| b.similarity.partial_cmp(&a.similarity).unwrap_or(std::cmp::Ordering::Equal));
+    Ok(groups)
+}
+
+/// Merge notes into the oldest of them with `merged_content` as its text. It
+/// takes all their tags and stays nominated if any of them was; the other
+/// notes are deleted and their ids added to its "merged_from" metadata.
+#[tauri::command]
+pub async fn merge_knowledge_entries(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    ids: Vec<String>,
+    merged_content: String,
+) -> Result<KnowledgeEntry, String> {
+    let content = merged_content.trim().to_string();
+    if content.is_empty() {
+        return Err("Merged text is empty".to_string());
+    }
+    let merged = state.knowledge.update(|entries| {
+        let mut merging: Vec<KnowledgeEntry> = entries.iter().filter(|e| ids.contains(&e.id)).cloned().collect();
+        if merging.len() < 2 {
+            return Err("Select at least two existing notes to merge".to_string());
+        }
+        merging.sort_by_key(|e| e.created_at);
+        let mut kept = merging[0].clone();
+
+        let mut merged_from: Vec<serde_json::Value> = Vec::new();
+        for entry in &merging {
+            if let Some(earlier) = entry.metadata.as_ref().and_then(|m| m["merged_from"].as_array()) {
+                merged_from.extend(earlier.iter().cloned());
+            }
+            if entry.id != kept.id {
+                merged_from.push(serde_json::json!(entry.id));
+            }
+        }
+        let mut metadata = kept.metadata.take().filter(|m| m.is_object()).unwrap_or_else(|| serde_json::json!({}));
+        metadata["merged_from"] = serde_json::json!(merged_from);
+        metadata["merged_at"] = serde_json::json!(now_millis());
+
+        kept.content = content;
+        kept.tags = normalize_tags(merging.iter().flat_map(|e| e.tags.iter().cloned()).collect());
+        kept.nominated = merging.iter().any(|e| e.nominated);
+        kept.folder = merging.iter().find_map(|e| e.folder.clone());
+        kept.metadata = Some(metadata);
+
+        entries.retain(|e| e.id == kept.id || !ids.contains(&e.id));
+        if let Some(entry) = entries.iter_mut().find(|e| e.id == kept.id) {
+            *entry = kept.clone();
+        }
+        Ok(kept)
+    })??;
+    embeddings::wake(&app_handle);
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rename_tag() {
+        assert_eq!(
+            normalize_tags(vec!["#Rust".into(), " ".into(), "rust".into(), "hiring ".into()]),
+            vec!["Rust", "hiring"]
+        );
+        assert_eq!(normalize_tags(vec!["Über".into(), "über".into(), "ÜBER".into()]), vec!["Über"]);
+
+        let entry = |id: &str, tags: &[&str]| KnowledgeEntry {
+            id: id.to_string(),
+            content: String::new(),
+            created_at: 1,
+            nominated: true,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            folder: None,
+            metadata: None,
+            source_session_id: None,
+            source_entry_ids: Vec::new(),
+        };
+        let mut entries = vec![entry("a", &["interview", "Rust"]), entry("b", &["rust", "RustLang"]), entry("c", &[])];
+        assert_eq!(rename_in(&mut entries, "rust", "RustLang"), 2);
+        assert_eq!(entries[0].tags, vec!["interview", "RustLang"]);
+        assert_eq!(entries[1].tags, vec!["RustLang"]);
+        assert!(entries[2].tags.is_empty());
+    }
+
+    #[test]
+    fn test_clusters() {
+        let vectors = vec![vec![1.0, 0.0], vec![0.0, 1.0], vec![0.99, 0.05], vec![0.98, 0.1], vec![-1.0, 0.0]];
+        let groups = clusters(&vectors, 0.95);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].0, vec![0, 2, 3]);
+        assert!(groups[0].1 > 0.99);
+    }
+}
@@ -0,0 +1,322 @@
+// Knowledge import from documents and web pages
+//
+// Prep material (a job description, a spec, meeting notes) is turned into
+// knowledge notes instead of being pasted in by hand. Text comes from .txt
+// and .md files as is, from PDFs through pdf-extract, and from web pages by
+// a small readability pass: scripts, navigation and other page furniture are
+// dropped, the <article> or <main> element is preferred over the whole body,
+// and the rest is reduced to paragraphs of plain text.
+//
+// The text is cut into chunks of about CHUNK_CHARS at paragraph boundaries
+// (a Markdown heading starts a new chunk once the current one is big enough)
+// so each note stays small enough to be picked individually for context.
+// Every note records where it came from in its metadata:
+//   {"source": "file" | "url", "location", "title", "chunk", "chunks"}
+// Imported notes aren't nominated: a long document would otherwise fill
+// every answer's context, so they are only used when retrieved.
+//
+// PDFs are parsed on a blocking thread, and a parser panic on a malformed
+// file is turned into an error. Downloads stop at MAX_DOWNLOAD_BYTES.
+use regex::Regex;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::AppHandle;
+use tracing::info;
+
+use crate::{embeddings, now_millis, AppState, KnowledgeEntry};
+
+/// Target chunk size; a single longer paragraph is split at sentence ends
+const CHUNK_CHARS: usize = 1500;
+
+/// A heading only starts a new chunk once the current one has this much
+const MIN_CHUNK_CHARS: usize = 300;
+
+/// Largest web page or document fetched for import
+const MAX_DOWNLOAD_BYTES: usize = 20 * 1024 * 1024;
+
+/// Elements whose content is never part of the readable text
+const SKIPPED_ELEMENTS: [&str; 10] =
+    ["script", "style", "noscript", "svg", "nav", "header", "footer", "aside", "form", "template"];
+
+/// Split a paragraph longer than CHUNK_CHARS at sentence ends, or at spaces
+/// when a sentence alone is too long
+fn split_long(paragraph: &str) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut current = String::new();
+    for word in paragraph.split_whitespace() {
+        if !current.is_empty() && current.len() + word.len() + 1 > CHUNK_CHARS {
+            pieces.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+        if current.len() > CHUNK_CHARS / 2 && (word.ends_with('.') || word.ends_with('?') || word.ends_with('!')) {
+            pieces.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        pieces.push(current);
+    }
+    pieces
+}
+
+/// Cut text into chunks of whole paragraphs
+pub fn chunk(text: &str) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let paragraphs = text.split("\n\n").map(str::trim).filter(|p| !p.is_empty());
+    for paragraph in paragraphs {
+        let heading = paragraph.starts_with('#');
+        if !current.is_empty()
+            && (current.len() + paragraph.len() + 2 > CHUNK_CHARS || (heading && current.len() >= MIN_CHUNK_CHARS))
+        {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if paragraph.len() > CHUNK_CHARS {
+            chunks.extend(split_long(paragraph));
+            continue;
+        }
+        if !current.is_empty() {
+            current.push_str("\n\n");
+        }
+        current.push_str(paragraph);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+fn decode_entities(text: &str) -> String {
+    let entity = Regex::new(r"&(#[0-9]+|#[xX][0-9a-fA-F]+|[a-zA-Z]+);").unwrap();
+    entity
+        .replace_all(text, |caps: &regex::Captures| {
+            let name = &caps[1];
+            let decoded = match name {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                "mdash" => Some('—'),
+                "ndash" => Some('–'),
+                "hellip" => Some('…'),
+                _ => match name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+                    None => name.strip_prefix('#').and_then(|n| n.parse().ok()).and_then(char::from_u32),
+                },
+            };
+            decoded.map(String::from).unwrap_or_else(|| caps[0].to_string())
+        })
+        .into_owned()
+}
+
+/// The page title and its readable text, paragraphs separated by blank lines
+pub fn readable_text(html: &str) -> (Option<String>, String) {
+    let title = Regex::new(r"(?is)<title[^>]*>(.*?)</title>")
+        .unwrap()
+        .captures(html)
+        .map(|caps| decode_entities(caps[1].trim()))
+        .filter(|t| !t.is_empty());
+
+    let mut html = Regex::new(r"(?s)<!--.*?-->").unwrap().replace_all(html, "").into_owned();
+    for element in SKIPPED_ELEMENTS {
+        let pattern = Regex::new(&format!(r"(?is)<{0}\b[^>]*>.*?</{0}\s*>", element)).unwrap();
+        html = pattern.replace_all(&html, "").into_owned();
+    }
+    let content = ["article", "main", "body"]
+        .iter()
+        .find_map(|element| {
+            Regex::new(&format!(r"(?is)<{0}\b[^>]*>(.*)</{0}\s*>", element))
+                .unwrap()
+                .captures(&html)
+                .map(|caps| caps[1].to_string())
+        })
+        .unwrap_or(html);
+
+    let content = Regex::new(r"(?i)<h[1-6]\b[^>]*>").unwrap().replace_all(&content, "\n\n# ");
+    let content = Regex::new(r"(?i)<li\b[^>]*>").unwrap().replace_all(&content, "\n- ");
+    let content = Regex::new(r"(?i)<br\s*/?>").unwrap().replace_all(&content, "\n");
+    let content = Regex::new(r"(?i)</?(p|div|section|h[1-6]|ul|ol|table|tr|blockquote|pre)\b[^>]*>")
+        .unwrap()
+        .replace_all(&content, "\n\n");
+    let content = Regex::new(r"(?s)<[^>]*>").unwrap().replace_all(&content, "");
+    let content = decode_entities(&content);
+
+    let mut paragraphs: Vec<String> = Vec::new();
+    for block in content.split("\n\n") {
+        let lines: Vec<String> = block
+            .lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|line| !line.is_empty() && line != "#" && line != "-")
+            .collect();
+        if !lines.is_empty() {
+            paragraphs.push(lines.join("\n"));
+        }
+    }
+    (title, paragraphs.join("\n\n"))
+}
+
+/// Store the chunks of a document as knowledge notes
+fn save_chunks(
+    app_handle: &AppHandle,
+    state: &AppState,
+    source: &str,
+    location: &str,
+    title: Option<String>,
+    text: &str,
+    folder: Option<String>,
+) -> Result<Vec<KnowledgeEntry>, String> {
+    let chunks = chunk(text);
+    if chunks.is_empty() {
+        return Err(format!("No text found in {}", location));
+    }
+    let created_at = now_millis();
+    let folder = folder.map(|f| f.trim().to_string()).filter(|f| !f.is_empty());
+    let count = chunks.len();
+    let entries: Vec<KnowledgeEntry> = chunks
+        .into_iter()
+        .enumerate()
+        .map(|(i, content)| KnowledgeEntry {
+            id: uuid::Uuid::new_v4().to_string(),
+            content,
+            created_at,
+            nominated: false,
+            tags: Vec::new(),
+            folder: folder.clone(),
+            metadata: Some(serde_json::json!({
+                "source": source,
+                "location": location,
+                "title": title,
+                "chunk": i + 1,
+                "chunks": count,
+            })),
+            source_session_id: None,
+            source_entry_ids: Vec::new(),
+        })
+        .collect();
+    state.knowledge.update(|knowledge| knowledge.extend(entries.iter().cloned()))?;
+    embeddings::wake(app_handle);
+    info!("Imported {} knowledge notes from {}", count, location);
+    Ok(entries)
+}
+
+/// Text of a PDF, parsed on a blocking thread; pdf-extract panics on some
+/// malformed files
+async fn pdf_text<E: std::fmt::Display + Send + 'static>(
+    extract: impl FnOnce() -> Result<String, E> + Send + 'static,
+) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || std::panic::catch_unwind(std::panic::AssertUnwindSafe(extract)))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|_| "The PDF could not be parsed".to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// Body of a response, failing once it grows past MAX_DOWNLOAD_BYTES
+async fn read_capped(mut response: reqwest::Response) -> Result<Vec<u8>, String> {
+    let too_large = || format!("The document is larger than {} MB", MAX_DOWNLOAD_BYTES / (1024 * 1024));
+    if response.content_length().is_some_and(|len| len > MAX_DOWNLOAD_BYTES as u64) {
+        return Err(too_large());
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+        if body.len() + chunk.len() > MAX_DOWNLOAD_BYTES {
+            return Err(too_large());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+/// Import a .txt, .md or .pdf file as knowledge notes, optionally into a folder
+#[tauri::command]
+pub async fn import_knowledge_from_file(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    path: String,
+    folder: Option<String>,
+) -> Result<Vec<KnowledgeEntry>, String> {
+    let file = Path::new(&path);
+    let extension = file.extension().and_then(|e| e.to_str()).unwrap_or_default().to_lowercase();
+    let text = match extension.as_str() {
+        "txt" | "text" | "md" | "markdown" => {
+            std::fs::read_to_string(file).map_err(|e| format!("Failed to read {}: {}", path, e))?
+        }
+        "pdf" => {
+            let file = file.to_path_buf();
+            pdf_text(move || pdf_extract::extract_text(file))
+                .await
+                .map_err(|e| format!("Failed to read PDF {}: {}", path, e))?
+        }
+        _ => return Err("Unsupported file type; expected .txt, .md or .pdf".to_string()),
+    };
+    let title = file.file_stem().map(|s| s.to_string_lossy().to_string());
+    save_chunks(&app_handle, &state, "file", &path, title, text.trim_start_matches('\u{feff}'), folder)
+}
+
+/// Fetch a web page (or a text or PDF document) and import its readable text
+#[tauri::command]
+pub async fn import_knowledge_from_url(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    url: String,
+    folder: Option<String>,
+) -> Result<Vec<KnowledgeEntry>, String> {
+    let url = url.trim().to_string();
+    if !(url.starts_with("https://") || url.starts_with("http://")) {
+        return Err("Only http and https URLs can be imported".to_string());
+    }
+    let response = reqwest::Client::new()
+        .get(&url)
+        .timeout(Duration::from_secs(30))
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("{} returned {}", url, response.status()));
+    }
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_lowercase();
+    let body = read_capped(response).await?;
+    let (title, text) = if content_type.contains("application/pdf") {
+        let text = pdf_text(move || pdf_extract::extract_text_from_mem(&body))
+            .await
+            .map_err(|e| format!("Failed to read PDF: {}", e))?;
+        (None, text)
+    } else if content_type.starts_with("text/plain") || content_type.starts_with("text/markdown") {
+        (None, String::from_utf8_lossy(&body).into_owned())
+    } else {
+        readable_text(&String::from_utf8_lossy(&body))
+    };
+    save_chunks(&app_handle, &state, "url", &url, title, &text, folder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_readable_text_and_chunks() {
+        let html = "<html><head><title>Role &amp; team</title><script>var x = '<p>';</script></head>\
+                    <body><nav><a href='/'>Home</a></nav><article><h1>About the role</h1>\
+                    <p>We build   <b>caption</b> tools.</p><ul><li>Rust</li><li>React</li></ul></article>\
+                    <footer>© 2026</footer></body></html>";
+        let (title, text) = readable_text(html);
+        assert_eq!(title.as_deref(), Some("Role & team"));
+        assert_eq!(text, "# About the role\n\nWe build caption tools.\n\n- Rust\n- React");
+
+        let long = format!("# One\n\n{}\n\n# Two\n\nshort", "word ".repeat(100));
+        let chunks = chunk(&long);
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[1].starts_with("# Two"));
+        assert!(chunk(&"Sentence. ".repeat(400)).iter().all(|c| c.len() <= CHUNK_CHARS));
+    }
+}
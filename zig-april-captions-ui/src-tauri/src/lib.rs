@@ -1,10 +1,13 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet, VecDeque};
 use std::io::{BufRead, BufReader};
 use std::path::Path;
 use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use tauri::{AppHandle, Emitter, Manager};
-use rusqlite::params;
+use rusqlite::{params, OptionalExtension};
+use tracing::{debug, error, info, warn};
 
 // ============================================================================
 // macOS Microphone Permission Request
@@ -24,6 +27,7 @@ mod macos_permissions {
     use std::sync::atomic::{AtomicBool, Ordering};
     use std::sync::{Arc, Condvar, Mutex};
     use std::time::Duration;
+    use tracing::{debug, error, info, warn};
 
     static PERMISSION_REQUESTED: AtomicBool = AtomicBool::new(false);
 
@@ -31,14 +35,14 @@ mod macos_permissions {
     /// This triggers the system permission dialog if not already granted.
     /// Returns true if permission is granted, false otherwise.
     pub fn request_microphone_permission() -> bool {
-        println!("=== macOS Microphone Permission Check ===");
+        info!("=== macOS Microphone Permission Check ===");
 
         unsafe {
             // Get AVCaptureDevice class
             let av_capture_device = match Class::get("AVCaptureDevice") {
                 Some(cls) => cls,
                 None => {
-                    println!("ERROR: AVCaptureDevice class not found");
+                    error!("AVCaptureDevice class not found");
                     return false;
                 }
             };
@@ -50,32 +54,32 @@ mod macos_permissions {
             // Check current authorization status
             // AVAuthorizationStatus: 0=NotDetermined, 1=Restricted, 2=Denied, 3=Authorized
             let status: i64 = msg_send![av_capture_device, authorizationStatusForMediaType: audio_type];
-            println!("Current authorization status: {} (0=NotDetermined, 1=Restricted, 2=Denied, 3=Authorized)", status);
+            debug!("Current authorization status: {} (0=NotDetermined, 1=Restricted, 2=Denied, 3=Authorized)", status);
 
             match status {
                 3 => {
                     // Already authorized
-                    println!("Microphone permission already GRANTED");
+                    info!("Microphone permission already GRANTED");
                     return true;
                 }
                 1 => {
-                    println!("Microphone access is RESTRICTED by system policy");
+                    warn!("Microphone access is RESTRICTED by system policy");
                     return false;
                 }
                 2 => {
-                    println!("Microphone access was DENIED by user");
-                    println!("User needs to grant permission in: System Settings > Privacy & Security > Microphone");
+                    warn!("Microphone access was DENIED by user");
+                    warn!("User needs to grant permission in: System Settings > Privacy & Security > Microphone");
                     return false;
                 }
                 0 => {
                     // Not determined - need to request permission
                     if PERMISSION_REQUESTED.load(Ordering::SeqCst) {
-                        println!("Permission already requested this session, checking status...");
+                        info!("Permission already requested this session, checking status...");
                         let new_status: i64 = msg_send![av_capture_device, authorizationStatusForMediaType: audio_type];
                         return new_status == 3;
                     }
 
-                    println!("Permission not determined, requesting access...");
+                    info!("Permission not determined, requesting access...");
                     PERMISSION_REQUESTED.store(true, Ordering::SeqCst);
 
                     // Use a synchronization primitive to wait for the callback
@@ -84,7 +88,7 @@ mod macos_permissions {
 
                     // Create the completion handler block
                     let handler = block::ConcreteBlock::new(move |granted: BOOL| {
-                        println!("Permission callback received: granted = {}", granted);
+                        debug!("Permission callback received: granted = {}", granted);
                         let (lock, cvar) = &*result_clone;
                         let mut guard = lock.lock().unwrap();
                         *guard = Some(granted);
@@ -94,7 +98,7 @@ mod macos_permissions {
                     // Request access - this should trigger the permission dialog
                     let _: () = msg_send![av_capture_device, requestAccessForMediaType: audio_type completionHandler: handler.copy()];
 
-                    println!("Permission dialog should appear now...");
+                    debug!("Permission dialog should appear now...");
 
                     // Wait for the callback with a timeout
                     let (lock, cvar) = &*result;
@@ -102,16 +106,16 @@ mod macos_permissions {
                     let wait_result = cvar.wait_timeout(guard, Duration::from_secs(30)).unwrap();
 
                     if let Some(granted) = *wait_result.0 {
-                        println!("Permission request completed: {}", if granted { "GRANTED" } else { "DENIED" });
+                        info!("Permission request completed: {}", if granted { "GRANTED" } else { "DENIED" });
                         return granted;
                     } else {
-                        println!("Permission request timed out, checking status...");
+                        warn!("Permission request timed out, checking status...");
                         let new_status: i64 = msg_send![av_capture_device, authorizationStatusForMediaType: audio_type];
                         return new_status == 3;
                     }
                 }
                 _ => {
-                    println!("Unknown authorization status: {}", status);
+                    warn!("Unknown authorization status: {}", status);
                     return false;
                 }
             }
@@ -150,13 +154,307 @@ mod macos_permissions {
 
 // Database module
 mod database;
-use database::{init_db, migrate_from_json, ChatHistoryEntry};
+use database::{migrate_from_json, ChatHistoryEntry, MigrationStats, Session};
+
+// DND-aware desktop notifications
+mod notifications;
+
+// Caption overlay window (placement memory, display cycling)
+mod overlay;
+
+// Crash detection and transcript recovery
+mod recovery;
+
+// tracing setup, rotating log files, in-app log viewer
+mod logging;
+
+// Support bundle generation
+mod diagnostics;
+
+// GitHub release update checker
+mod updater;
+
+// Keep the machine awake while captioning
+mod power;
+
+// Text-to-speech readback of AI answers
+mod tts;
+
+// Local WebSocket server streaming captions to other apps
+mod server;
+
+// REST API served by the caption server
+mod api;
+
+// OBS text file and browser-source output
+mod obs;
+
+// Outgoing webhooks with retries and HMAC signing
+mod webhooks;
+
+// Second-screen caption viewer for other devices on the LAN
+mod viewer;
+
+// Model Context Protocol endpoint for AI agents
+mod mcp;
+
+// Slack / Discord summary sharing
+mod integrations;
+
+// Caption ingestion from external ASR sources
+mod ingest;
+
+// MQTT publishing of caption and session events
+mod mqtt;
+
+// Caption export formats (SRT, WebVTT, text, Markdown, JSON) and session notes
+mod export;
+// PDF layout for exported session notes
+mod pdf;
+
+// SRT / WebVTT / plain-text transcript import
+mod import;
+
+// Offline transcription of recordings with the caption engine
+mod transcribe;
+
+// Background queue for transcribing audio files
+mod transcription_queue;
+
+// Action items stored as tasks
+mod tasks;
+
+// Entity and keyword tagging of sessions
+mod entities;
+
+// Custom vocabulary for the recognizer and corrections of final captions
+mod vocabulary;
+
+// User-defined substitution rules for final captions
+mod corrections;
+
+// AES-GCM helpers for data kept encrypted at rest
+mod crypto;
+
+// Blocklist and personal data masking of captions
+mod redaction;
+
+// Passphrase-encrypted database at rest
+mod encryption;
+
+// Backup and restore of the data directory
+mod backup;
+
+// Scheduled snapshots of the database, with rotation
+mod db_backups;
+
+// Pooled read-only connections, so slow reads don't block caption writes
+mod db_pool;
+
+// Final captions written in batches instead of one transaction each
+mod caption_batch;
+
+// Knowledge and idea sync through a shared folder
+mod sync;
+
+// Session notes in an Obsidian vault
+mod obsidian;
+
+// Session pages in a Notion database
+mod notion;
+
+// Meetings from calendar feeds: session titles, meeting context, auto-start
+mod calendar;
+
+// Prompt to start captions when a conferencing app joins a call
+mod meeting_detection;
+
+// Caption latency and throughput of the current session
+mod metrics;
+
+// Killing the caption engine on signals, panics and stale pid files
+mod process_cleanup;
+
+// Microphone and system audio captured together, tagged "me" / "them"
+mod dual_capture;
+
+// Live transcript lines with ids for editing
+mod transcript;
+
+// Moments bookmarked during a session
+mod bookmarks;
+
+// Tags, folders and duplicates of knowledge notes
+mod knowledge;
+
+// Knowledge notes imported from documents and web pages
+mod knowledge_import;
+
+// Versions of ideas' corrected scripts
+mod idea_versions;
+
+// Idea scripts exported for editors (Markdown, Fountain, plain text)
+mod idea_export;
+
+// Recycle bin for deleted knowledge notes, ideas and chat entries
+mod trash;
+
+// Background embedding of chat and knowledge entries
+mod embeddings;
+
+// sqlite-vec index of the embeddings, used by semantic search
+mod vector_index;
+
+// Audio input device listing
+mod audio_devices;
+
+// Atomic JSON writes with a backup generation
+mod persist;
+
+// Gemini requests and context assembly
+mod ai;
+
+// Live translation of final captions
+mod translation;
+
+// Global hotkeys for start/stop and pause/resume
+mod hotkeys;
+
+// Speech model downloads and management
+mod models;
+
+// Talk time, pace and speaker share of a session
+mod analytics;
+
+// Mentions of a topic across sessions, oldest first
+mod topics;
+
+// Reusable setups for recurring meetings
+mod templates;
+
+// Stopping a run after a maximum duration or a long silence
+mod auto_stop;
+
+// Launch at login and captions on launch
+mod autostart;
+
+// Named copies of the settings to switch between
+mod profiles;
+
+// Field-by-field repair of settings.json
+mod settings_check;
+
+// Storing data next to the executable
+mod portable;
+
+// Partial settings updates and change events
+mod settings_update;
 
 // Global state to manage the child process and transcript history
 struct AppState {
     process: Mutex<Option<Child>>,
+    monitor_process: Mutex<Option<Child>>, // System audio recognizer when capturing both sides
     settings: Mutex<Settings>,
-    transcript_lines: Mutex<Vec<String>>,
+    settings_warnings: Mutex<Vec<settings_check::SettingsWarning>>, // Fields repaired when settings were loaded
+    transcript_lines: Mutex<transcript::Transcript>,
+    process_stderr: Mutex<VecDeque<String>>, // Tail of the child's stderr
+    recent_events: Mutex<VecDeque<BufferedEvent>>, // Replayed to windows that subscribe late
+    sleep_inhibitor: Mutex<Option<power::SleepInhibitor>>,
+    db: Mutex<rusqlite::Connection>, // Shared connection to zigy.db
+    readers: db_pool::ReadPool, // Read-only connections for slow queries
+    finals: caption_batch::CaptionBatch, // Final captions waiting to be written
+    session_id: Mutex<Option<String>>, // Session of the current captioning run
+    meeting_context: Mutex<Option<String>>, // Calendar event of the current session
+    metrics: Mutex<metrics::Metrics>, // Latency and throughput of the current session
+    stopped_exit: Mutex<Option<StoppedChild>>, // Child stopped on purpose, for its stdout reader
+    generation: AtomicU64, // Bumped on every manual stop/start and model switch; cancels pending restarts
+    run: AtomicU64, // Bumped on manual stop/start only; ends auto-stop watchers
+    paused: AtomicBool, // Captions from the running process are dropped while set
+    last_caption_at: AtomicI64, // When the latest caption arrived, for auto-stop on silence
+    knowledge: persist::JsonStore<Vec<KnowledgeEntry>>, // knowledge.json
+    ideas: persist::JsonStore<Vec<IdeaEntry>>, // ideas.json, newest first
+    snapshots: persist::JsonStore<Vec<ContextSnapshot>>, // context_snapshots.json
+    correction_rules: persist::JsonStore<Vec<corrections::CorrectionRule>>, // correction_rules.json
+    templates: persist::JsonStore<Vec<templates::MeetingTemplate>>, // templates.json
+    template: Mutex<Option<templates::AppliedTemplate>>, // Template applied to the current session
+}
+
+impl AppState {
+    /// Lock the shared database connection
+    fn db(&self) -> Result<std::sync::MutexGuard<'_, rusqlite::Connection>, String> {
+        self.db.lock().map_err(|e| format!("Database unavailable: {}", e))
+    }
+
+    /// A read-only connection for queries that may take a while
+    fn reader(&self) -> Result<db_pool::Reader<'_>, String> {
+        db_pool::reader(self)
+    }
+
+    /// Write any pending store changes, e.g. at shutdown
+    fn flush_stores(&self) {
+        caption_batch::flush(self);
+        let results = [
+            self.knowledge.flush(),
+            self.ideas.flush(),
+            self.snapshots.flush(),
+            self.correction_rules.flush(),
+            self.templates.flush(),
+            self.db().and_then(|conn| encryption::save(&conn)),
+        ];
+        for e in results.into_iter().filter_map(Result::err) {
+            error!("Failed to save store: {}", e);
+        }
+    }
+}
+
+impl Drop for AppState {
+    fn drop(&mut self) {
+        process_cleanup::kill_child(self);
+    }
+}
+
+/// Number of child stderr lines kept for diagnostics
+const STDERR_TAIL_LINES: usize = 200;
+
+/// Number of caption events kept for `replay_recent_events`
+const RECENT_EVENTS: usize = 500;
+
+/// Number of stderr lines attached to a `caption-error` event
+const CRASH_STDERR_LINES: usize = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupervisorSettings {
+    #[serde(default = "default_true")]
+    pub auto_restart: bool, // Relaunch the recognizer after a crash
+    #[serde(default = "default_max_restarts")]
+    pub max_restarts: u32, // Per captioning run
+    #[serde(default = "default_restart_backoff_ms")]
+    pub backoff_ms: u64, // Doubles after each restart
+    #[serde(default = "default_stop_timeout_ms")]
+    pub stop_timeout_ms: u64, // Wait for a clean exit on stop before killing
+}
+
+fn default_max_restarts() -> u32 {
+    3
+}
+
+fn default_restart_backoff_ms() -> u64 {
+    1000
+}
+
+fn default_stop_timeout_ms() -> u64 {
+    2000
+}
+
+impl Default for SupervisorSettings {
+    fn default() -> Self {
+        Self {
+            auto_restart: true,
+            max_restarts: default_max_restarts(),
+            backoff_ms: default_restart_backoff_ms(),
+            stop_timeout_ms: default_stop_timeout_ms(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -178,28 +476,108 @@ fn default_model() -> String {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Settings {
     pub model_path: String,
-    pub audio_source: String, // "mic" or "monitor"
+    pub audio_source: String, // "mic", "monitor" or "both"
+    #[serde(default)]
+    pub audio_device_id: Option<String>, // Specific capture device, None for the default
+    #[serde(default)]
+    pub record_audio: bool, // Save each session's audio as WAV next to the transcript
     pub font_size: u32,
     pub theme: String, // "light" or "dark"
     #[serde(default = "default_language")]
     pub language: String, // "en" or "vi"
     #[serde(default)]
     pub ai: Option<AISettings>,
+    #[serde(default = "default_true")]
+    pub respect_dnd: bool, // Queue notifications while Do-Not-Disturb is on
+    #[serde(default = "default_log_level")]
+    pub log_level: String, // "trace", "debug", "info", "warn", "error" or "off"
+    #[serde(default = "default_true")]
+    pub check_for_updates: bool,
+    #[serde(default = "default_true")]
+    pub prevent_sleep: bool, // Block system suspend while captions are running
+    #[serde(default)]
+    pub tts: Option<tts::TtsSettings>,
+    #[serde(default)]
+    pub server: Option<server::ServerSettings>,
+    #[serde(default)]
+    pub obs: Option<obs::ObsSettings>,
+    #[serde(default)]
+    pub webhooks: Vec<webhooks::WebhookConfig>,
+    #[serde(default)]
+    pub integrations: Vec<integrations::IntegrationTarget>,
+    #[serde(default)]
+    pub mqtt: Option<mqtt::MqttSettings>,
+    #[serde(default)]
+    pub supervisor: Option<SupervisorSettings>,
+    #[serde(default)]
+    pub hotkeys: Option<hotkeys::HotkeySettings>,
+    #[serde(default)]
+    pub retention: database::RetentionPolicy,
+    #[serde(default)]
+    pub custom_vocabulary: Vec<vocabulary::VocabularyTerm>, // Passed to the recognizer and applied to final captions
+    #[serde(default)]
+    pub redaction: Option<redaction::RedactionSettings>,
+    #[serde(default)]
+    pub sync: Option<sync::SyncSettings>,
+    #[serde(default)]
+    pub notion: Option<notion::NotionSettings>,
+    #[serde(default)]
+    pub calendar: Option<calendar::CalendarSettings>,
+    #[serde(default)]
+    pub meeting_detection: Option<meeting_detection::MeetingDetectionSettings>,
+    #[serde(default)]
+    pub db_backups: Option<db_backups::DbBackupSettings>, // Scheduled snapshots; on when unset
+    #[serde(default)]
+    pub autostart: Option<autostart::AutostartSettings>,
+    #[serde(default)]
+    pub overlay_placements: BTreeMap<String, overlay::OverlayPlacement>, // Display configuration key -> where the overlay was last
 }
 
 fn default_language() -> String {
     "en".to_string()
 }
 
+fn default_true() -> bool {
+    true
+}
+
+fn default_log_level() -> String {
+    "info".to_string()
+}
+
 impl Default for Settings {
     fn default() -> Self {
         Self {
             model_path: String::new(),
             audio_source: "mic".to_string(),
+            audio_device_id: None,
+            record_audio: false,
             font_size: 24,
             theme: "dark".to_string(),
             language: "en".to_string(),
             ai: None,
+            respect_dnd: true,
+            log_level: default_log_level(),
+            check_for_updates: true,
+            prevent_sleep: true,
+            tts: None,
+            server: None,
+            obs: None,
+            webhooks: Vec::new(),
+            integrations: Vec::new(),
+            mqtt: None,
+            supervisor: None,
+            hotkeys: None,
+            retention: database::RetentionPolicy::Forever,
+            custom_vocabulary: Vec::new(),
+            redaction: None,
+            sync: None,
+            notion: None,
+            calendar: None,
+            meeting_detection: None,
+            db_backups: None,
+            autostart: None,
+            overlay_placements: BTreeMap::new(),
         }
     }
 }
@@ -210,12 +588,18 @@ pub struct Caption {
     pub text: String,
     pub caption_type: String, // "partial" or "final"
     pub timestamp: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<String>, // Diarization label, when the recognizer provides one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<database::CaptionWord>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct CaptionEvent {
     #[serde(rename = "type")]
     event_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>, // Assigned to final captions so translations can refer to them
     #[serde(rename = "captionType", default)]
     caption_type: Option<String>,
     #[serde(default)]
@@ -228,46 +612,47 @@ struct CaptionEvent {
     version: Option<String>,
     #[serde(default)]
     source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    speaker: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    words: Vec<database::CaptionWord>, // Word timing and confidence, when the recognizer reports them
+    #[serde(rename = "exitCode", default, skip_serializing_if = "Option::is_none")]
+    exit_code: Option<i32>, // Of the recognizer, on "stopped"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    channel: Option<String>, // Audio the recognizer listened to: "mic" or "monitor"
 }
 
-fn get_settings_path() -> std::path::PathBuf {
-    let config_dir = dirs::config_dir()
-        .unwrap_or_else(|| std::path::PathBuf::from("."))
-        .join("zigy");
+/// Where all app data is stored: the `data` folder of a portable install,
+/// or zigy under the user's config dir
+fn get_config_dir() -> std::path::PathBuf {
+    let config_dir = match portable::data_dir() {
+        Some(dir) => dir.to_path_buf(),
+        None => dirs::config_dir()
+            .unwrap_or_else(|| std::path::PathBuf::from("."))
+            .join("zigy"),
+    };
     std::fs::create_dir_all(&config_dir).ok();
-    config_dir.join("settings.json")
+    config_dir
+}
+
+fn get_settings_path() -> std::path::PathBuf {
+    get_config_dir().join("settings.json")
 }
 
 fn get_knowledge_path() -> std::path::PathBuf {
-    let config_dir = dirs::config_dir()
-        .unwrap_or_else(|| std::path::PathBuf::from("."))
-        .join("zigy");
-    std::fs::create_dir_all(&config_dir).ok();
-    config_dir.join("knowledge.json")
+    get_config_dir().join("knowledge.json")
 }
 
 fn get_ideas_path() -> std::path::PathBuf {
-    let config_dir = dirs::config_dir()
-        .unwrap_or_else(|| std::path::PathBuf::from("."))
-        .join("zigy");
-    std::fs::create_dir_all(&config_dir).ok();
-    config_dir.join("ideas.json")
+    get_config_dir().join("ideas.json")
 }
 
-fn get_chat_history_path() -> std::path::PathBuf {
-    let config_dir = dirs::config_dir()
-        .unwrap_or_else(|| std::path::PathBuf::from("."))
-        .join("zigy");
-    std::fs::create_dir_all(&config_dir).ok();
-    config_dir.join("chat_history.json")
+fn get_context_snapshots_path() -> std::path::PathBuf {
+    get_config_dir().join("context_snapshots.json")
 }
 
-fn get_context_snapshots_path() -> std::path::PathBuf {
-    let config_dir = dirs::config_dir()
-        .unwrap_or_else(|| std::path::PathBuf::from("."))
-        .join("zigy");
-    std::fs::create_dir_all(&config_dir).ok();
-    config_dir.join("context_snapshots.json")
+fn get_correction_rules_path() -> std::path::PathBuf {
+    get_config_dir().join("correction_rules.json")
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -277,6 +662,16 @@ pub struct KnowledgeEntry {
     pub created_at: i64,
     #[serde(default)]
     pub nominated: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>, // Name of a knowledge folder
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>, // Where an imported note came from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_session_id: Option<String>, // Session the note was taken from
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub source_entry_ids: Vec<String>, // Chat entries it was taken from
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -286,6 +681,10 @@ pub struct IdeaEntry {
     pub raw_content: String,
     pub corrected_script: String,
     pub created_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_session_id: Option<String>, // Session the idea was taken from
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub source_entry_ids: Vec<String>, // Chat entries (transcript selection) it was taken from
 }
 
 // Context compression snapshot
@@ -297,6 +696,8 @@ pub struct ContextSnapshot {
     pub covered_until: i64,        // Timestamp of last message in summary
     pub original_token_count: i64, // Estimated tokens before compression
     pub compressed_token_count: i64, // Estimated tokens after compression
+    #[serde(default)]
+    pub session_id: Option<String>, // Captioning session the snapshot belongs to
 }
 
 // ============================================================================
@@ -332,8 +733,8 @@ fn get_zig_binary_path(app_handle: &AppHandle) -> Result<String, String> {
     let exe_path = std::env::current_exe()
         .map_err(|e| format!("Failed to get executable path: {}", e))?;
 
-    println!("Executable path: {}", exe_path.display());
-    println!("Executable parent: {}", exe_path.parent().unwrap_or_else(|| Path::new("")).display());
+    debug!("Executable path: {}", exe_path.display());
+    debug!("Executable parent: {}", exe_path.parent().unwrap_or_else(|| Path::new("")).display());
 
     // Check if we're in development mode (debug build)
     let is_dev_mode = exe_path.to_string_lossy().contains("/target/debug/") ||
@@ -341,7 +742,7 @@ fn get_zig_binary_path(app_handle: &AppHandle) -> Result<String, String> {
 
     // Dev mode: check local build first
     if is_dev_mode {
-        println!("Running in development mode, checking dev builds first");
+        info!("Running in development mode, checking dev builds first");
 
         // In the same parent directory (dev mode)
         let dev_candidates = vec![
@@ -357,9 +758,9 @@ fn get_zig_binary_path(app_handle: &AppHandle) -> Result<String, String> {
         ];
 
         for candidate in &dev_candidates {
-            println!("Checking dev path: {}", candidate);
+            debug!("Checking dev path: {}", candidate);
             if Path::new(&candidate).exists() {
-                println!("Found zig-april-captions at: {}", candidate);
+                info!("Found zig-april-captions at: {}", candidate);
                 return Ok(candidate.to_string());
             }
         }
@@ -378,9 +779,9 @@ fn get_zig_binary_path(app_handle: &AppHandle) -> Result<String, String> {
     ];
 
     for candidate in &candidates {
-        println!("Checking: {}", candidate.display());
+        debug!("Checking: {}", candidate.display());
         if candidate.exists() {
-            println!("Found zig-april-captions at: {}", candidate.display());
+            info!("Found zig-april-captions at: {}", candidate.display());
             return Ok(candidate.to_string_lossy().to_string());
         }
     }
@@ -391,7 +792,7 @@ fn get_zig_binary_path(app_handle: &AppHandle) -> Result<String, String> {
         .path()
         .resolve("", tauri::path::BaseDirectory::Resource)
     {
-        println!("Tauri resource directory: {}", resource_dir.display());
+        debug!("Tauri resource directory: {}", resource_dir.display());
 
         // Check multiple possible locations within the resource directory
         let resource_candidates = vec![
@@ -400,9 +801,9 @@ fn get_zig_binary_path(app_handle: &AppHandle) -> Result<String, String> {
         ];
 
         for resource_path in resource_candidates {
-            println!("Checking Tauri resource path: {}", resource_path.display());
+            debug!("Checking Tauri resource path: {}", resource_path.display());
             if resource_path.exists() {
-                println!("Found zig-april-captions in Tauri resources at: {}", resource_path.display());
+                info!("Found zig-april-captions in Tauri resources at: {}", resource_path.display());
 
                 #[cfg(unix)]
                 {
@@ -410,9 +811,9 @@ fn get_zig_binary_path(app_handle: &AppHandle) -> Result<String, String> {
                     use std::os::unix::fs::PermissionsExt;
                     if let Ok(metadata) = std::fs::metadata(&resource_path) {
                         let mode = metadata.permissions().mode();
-                        println!("Binary permissions: {:o}", mode);
+                        debug!("Binary permissions: {:o}", mode);
                         if mode & 0o111 == 0 {
-                            println!("Binary is not executable, attempting to set +x");
+                            warn!("Binary is not executable, attempting to set +x");
                             let _ = std::fs::set_permissions(&resource_path, std::fs::Permissions::from_mode(mode | 0o111));
                         }
                     }
@@ -427,17 +828,17 @@ fn get_zig_binary_path(app_handle: &AppHandle) -> Result<String, String> {
     #[cfg(target_os = "linux")]
     {
         let deb_path = Path::new("/usr/lib/zigy").join(&binary_name);
-        println!("Checking .deb installation path: {}", deb_path.display());
+        debug!("Checking .deb installation path: {}", deb_path.display());
         if deb_path.exists() {
-            println!("Found zig-april-captions at: {}", deb_path.display());
+            info!("Found zig-april-captions at: {}", deb_path.display());
 
             // Make sure it's executable
             use std::os::unix::fs::PermissionsExt;
             if let Ok(metadata) = std::fs::metadata(&deb_path) {
                 let mode = metadata.permissions().mode();
-                println!("Binary permissions: {:o}", mode);
+                debug!("Binary permissions: {:o}", mode);
                 if mode & 0o111 == 0 {
-                    println!("Binary is not executable, attempting to set +x");
+                    warn!("Binary is not executable, attempting to set +x");
                     let _ = std::fs::set_permissions(&deb_path, std::fs::Permissions::from_mode(mode | 0o111));
                 }
             }
@@ -466,16 +867,16 @@ fn get_zig_binary_path(app_handle: &AppHandle) -> Result<String, String> {
         ];
 
         for candidate in &app_bundle_candidates {
-            println!("Checking macOS app bundle path: {}", candidate.display());
+            debug!("Checking macOS app bundle path: {}", candidate.display());
             if candidate.exists() {
-                println!("Found zig-april-captions at: {}", candidate.display());
+                info!("Found zig-april-captions at: {}", candidate.display());
                 return Ok(candidate.to_string_lossy().to_string());
             }
         }
     }
 
     // Try in PATH as last resort
-    println!("Warning: zig-april-captions not found in any location, trying system PATH");
+    warn!("zig-april-captions not found in any location, trying system PATH");
     Ok(binary_name.to_string())
 }
 
@@ -485,38 +886,92 @@ async fn start_captions(
     state: tauri::State<'_, Arc<AppState>>,
     model_path: String,
     audio_source: String,
+    template_id: Option<String>,
+    auto_stop: Option<auto_stop::AutoStopOptions>,
 ) -> Result<(), String> {
+    if encryption::is_locked() {
+        return Err("Storage is locked; unlock it before starting captions".to_string());
+    }
+    let template = match template_id {
+        Some(id) => Some(templates::find(&state, &id)?),
+        None => None,
+    };
+
     // CRITICAL: Request microphone permission BEFORE spawning child process
     // On macOS, the main app bundle must request permission first, otherwise
     // the child process (zig-april-captions) will fail with "device not found"
     #[cfg(target_os = "macos")]
     {
-        println!("Checking microphone permission before starting captions...");
+        debug!("Checking microphone permission before starting captions...");
         let has_permission = macos_permissions::request_microphone_permission();
         if !has_permission {
             return Err("Microphone permission not granted. Please allow microphone access in System Settings > Privacy & Security > Microphone, then restart Zigy.".to_string());
         }
-        println!("Microphone permission granted, proceeding to start captions");
+        info!("Microphone permission granted, proceeding to start captions");
 
         // Give macOS a moment to fully propagate the permission to audio subsystem
         // This helps prevent race condition where child process starts before
         // CoreAudio HAL has fully updated its device list after permission grant
-        std::thread::sleep(std::time::Duration::from_millis(500));
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
     }
 
     // Stop any existing process first
-    stop_captions_internal(&state)?;
+    stop_captions_internal(&state).await?;
+
+    if let Some(template) = template {
+        templates::apply(&state, template)?;
+    }
+    if let Err(e) = launch_captions(&app_handle, &model_path, &audio_source, true, 0) {
+        templates::clear(&state);
+        return Err(e);
+    }
+    if let Some(options) = auto_stop {
+        auto_stop::watch(&app_handle, options, state.run.load(Ordering::SeqCst));
+    }
+    Ok(())
+}
 
-    let binary_path = get_zig_binary_path(&app_handle)?;
+/// Start captions with the model and audio source saved in settings, for
+/// automatic starts
+async fn start_with_saved_settings(app_handle: &AppHandle) -> Result<(), String> {
+    let (model_path, audio_source) = {
+        let state = app_handle.state::<Arc<AppState>>();
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        (settings.model_path.clone(), settings.audio_source.clone())
+    };
+    if model_path.is_empty() {
+        return Err("No model configured".to_string());
+    }
+    start_captions(app_handle.clone(), app_handle.state(), model_path, audio_source, None, None).await
+}
+
+/// Spawn the recognizer and its reader threads. `new_session` opens a session
+/// for it, otherwise it carries on the current one; `restarts` counts
+/// automatic relaunches after crashes in a row.
+fn launch_captions(
+    app_handle: &AppHandle,
+    model_path: &str,
+    audio_source: &str,
+    new_session: bool,
+    restarts: u32,
+) -> Result<(), String> {
+    let state = app_handle.state::<Arc<AppState>>();
+    let binary_path = get_zig_binary_path(app_handle)?;
 
     // Build command arguments
     let mut args = vec!["--json".to_string()];
     if audio_source == "monitor" {
         args.push("--monitor".to_string());
     }
-    args.push(model_path.clone());
+    // The chosen device replaces the microphone; system audio is always the default output
+    let device = state.settings.lock().ok().and_then(|s| s.audio_device_id.clone());
+    if let Some(device) = device.filter(|d| !d.is_empty() && audio_source != "monitor") {
+        args.push("--device".to_string());
+        args.push(device);
+    }
+    args.push(model_path.to_string());
 
-    println!("Starting: {} {:?}", binary_path, args);
+    info!("Starting: {} {:?}", binary_path, args);
 
     // Check if binary exists and is executable
     let binary_path_obj = Path::new(&binary_path);
@@ -531,17 +986,17 @@ async fn start_captions(
             .map_err(|e| format!("Failed to get binary metadata: {}", e))?;
         let permissions = metadata.permissions();
         let mode = permissions.mode();
-        println!("Binary permissions: {:o}", mode);
+        debug!("Binary permissions: {:o}", mode);
 
         if mode & 0o111 == 0 {
-            println!("Warning: Binary is not executable, attempting to set +x");
+            warn!("Binary is not executable, attempting to set +x");
             std::fs::set_permissions(&binary_path, std::fs::Permissions::from_mode(mode | 0o111))
                 .map_err(|e| format!("Failed to make binary executable: {}", e))?;
         }
     }
 
     // Spawn the process
-    println!("Spawning process: {} {:?}", binary_path, args);
+    info!("Spawning process: {} {:?}", binary_path, args);
 
     // CRITICAL: Set LD_LIBRARY_PATH to include the binary's directory
     // The zig-april-captions binary depends on libonnxruntime.so which is
@@ -552,49 +1007,54 @@ async fn start_captions(
         .map(|p| p.to_string_lossy().to_string())
         .unwrap_or_default();
 
+    // Open the session before spawning so the recording can be named after it
+    let session_id = if new_session {
+        let session_id = begin_session(&state, audio_source, model_path);
+        if let Some(id) = &session_id {
+            calendar::apply_to_session(app_handle, id);
+            templates::apply_to_session(app_handle, id);
+        }
+        session_id
+    } else {
+        current_session_id(&state)
+    };
+    let record_audio = state.settings.lock().map(|s| s.record_audio).unwrap_or(false);
+    let recording = match (record_audio, session_id.as_deref()) {
+        // Later runs in a session record to the next free numbered part
+        (true, Some(id)) => Some(recording_path(id, if new_session { 0 } else { restarts.max(1) })),
+        _ => None,
+    };
+    if let (Some(recording), Some(id)) = (&recording, session_id.as_deref()) {
+        info!("Recording audio to {}", recording.display());
+        if new_session {
+            set_session_audio_path(&state, id, recording);
+        }
+        args.insert(args.len() - 1, "--record".to_string());
+        args.insert(args.len() - 1, recording.to_string_lossy().to_string());
+    }
+    // After the session is set up, so its and its template's terms are included
+    for arg in vocabulary::engine_args(app_handle) {
+        args.insert(args.len() - 1, arg);
+    }
+
     let mut cmd = Command::new(&binary_path);
     cmd.args(&args)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped());
 
-    // On Linux, set LD_LIBRARY_PATH so the binary can find libonnxruntime.so
-    #[cfg(target_os = "linux")]
-    {
-        let current_ld_path = std::env::var("LD_LIBRARY_PATH").unwrap_or_default();
-        let new_ld_path = if current_ld_path.is_empty() {
-            binary_dir.clone()
-        } else {
-            format!("{}:{}", binary_dir, current_ld_path)
-        };
-        println!("Setting LD_LIBRARY_PATH: {}", new_ld_path);
-        cmd.env("LD_LIBRARY_PATH", new_ld_path);
-    }
-
-    // On macOS, set DYLD_LIBRARY_PATH so the binary can find libonnxruntime.dylib
-    #[cfg(target_os = "macos")]
-    {
-        let current_dyld_path = std::env::var("DYLD_LIBRARY_PATH").unwrap_or_default();
-        let new_dyld_path = if current_dyld_path.is_empty() {
-            binary_dir.clone()
-        } else {
-            format!("{}:{}", binary_dir, current_dyld_path)
-        };
-        println!("Setting DYLD_LIBRARY_PATH: {}", new_dyld_path);
-        cmd.env("DYLD_LIBRARY_PATH", new_dyld_path);
-    }
-
-    // On Windows, hide the console window that would otherwise pop up
-    #[cfg(target_os = "windows")]
-    {
-        use std::os::windows::process::CommandExt;
-        const CREATE_NO_WINDOW: u32 = 0x08000000;
-        cmd.creation_flags(CREATE_NO_WINDOW);
-    }
+    set_library_path(&mut cmd, &binary_dir);
 
-    let mut child = cmd.spawn()
-        .map_err(|e| format!("Failed to start zig-april-captions at {}: {}", binary_path, e))?;
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            if new_session {
+                end_session(&state, session_id.as_deref());
+            }
+            return Err(format!("Failed to start zig-april-captions at {}: {}", binary_path, e));
+        }
+    };
 
-    println!("Process spawned successfully, PID: {:?}", child.id());
+    info!("Process spawned successfully, PID: {:?}", child.id());
 
     let stdout = child
         .stdout
@@ -606,12 +1066,46 @@ async fn start_captions(
         .take()
         .ok_or_else(|| "Failed to capture stderr".to_string())?;
 
+    let child_pid = child.id();
+    process_cleanup::write_pid(process_cleanup::ENGINE_PID, child_pid);
+
     // Store the process
     {
         let mut process_guard = state.process.lock().map_err(|e| e.to_string())?;
         *process_guard = Some(child);
     }
 
+    // The other side of the call; a crash relaunch finds it still running
+    let dual = audio_source == dual_capture::AUDIO_SOURCE;
+    let channel = if audio_source == "monitor" { "monitor" } else { "mic" };
+    if dual {
+        let monitor_recording = recording.map(|r| r.with_extension("them.wav"));
+        if let Err(e) = dual_capture::start_monitor(app_handle, model_path, monitor_recording) {
+            warn!("{}", e);
+        }
+    }
+
+    // Keep the laptop from suspending mid-session
+    if state.settings.lock().map(|s| s.prevent_sleep).unwrap_or(false) {
+        set_sleep_inhibited(&state, true);
+    }
+
+    if new_session {
+        server::publish(
+            app_handle,
+            "session",
+            serde_json::json!({ "state": "started", "audioSource": audio_source, "sessionId": session_id }),
+        );
+        mqtt::publish_session_state(app_handle, "started");
+        webhooks::dispatch(
+            app_handle,
+            webhooks::EVENT_SESSION_START,
+            serde_json::json!({ "session_id": session_id, "audio_source": audio_source }),
+        );
+    }
+    let generation = state.generation.load(Ordering::SeqCst);
+    let (model_path, audio_source) = (model_path.to_string(), audio_source.to_string());
+
     // Spawn a thread to read stdout and emit events
     let app_handle_clone = app_handle.clone();
     std::thread::spawn(move || {
@@ -624,45 +1118,124 @@ async fn start_captions(
                     }
                     // Parse JSON and emit to frontend
                     match serde_json::from_str::<CaptionEvent>(&json_line) {
-                        Ok(event) => {
-                            let _ = app_handle_clone.emit("caption-event", event);
+                        // The reader reports the stop itself once the process has
+                        // exited, with its exit code
+                        Ok(event) if event.event_type == "stopped" => {}
+                        Ok(mut event) => {
+                            let state = app_handle_clone.state::<Arc<AppState>>();
+                            if event.event_type == "caption" && state.paused.load(Ordering::SeqCst) {
+                                metrics::on_paused_caption(&state);
+                                continue;
+                            }
+                            if event.event_type == "caption" {
+                                event.channel.get_or_insert_with(|| channel.to_string());
+                            }
+                            if dual {
+                                dual_capture::tag(&mut event, dual_capture::Side::Me);
+                            }
+                            handle_caption_event(&app_handle_clone, event);
                         }
                         Err(e) => {
-                            eprintln!("Failed to parse JSON: {} - line: {}", e, json_line);
+                            warn!("Failed to parse JSON: {} ({}-byte line)", e, json_line.len());
+                            metrics::on_unparsed_line(&app_handle_clone.state::<Arc<AppState>>());
                         }
                     }
                 }
                 Err(e) => {
-                    eprintln!("Error reading stdout: {}", e);
+                    error!("Error reading stdout: {}", e);
                     break;
                 }
             }
         }
-        // Process ended
-        let _ = app_handle_clone.emit(
-            "caption-event",
+        // Process ended - collect its exit status if it died on its own
+        let state = app_handle_clone.state::<Arc<AppState>>();
+        let (replaced, status) = match state.process.lock() {
+            Ok(mut process) => match process.as_ref().map(|c| c.id() == child_pid) {
+                Some(true) => {
+                    process_cleanup::clear_pid(process_cleanup::ENGINE_PID);
+                    (false, process.take().and_then(|mut c| c.wait().ok()))
+                }
+                Some(false) => (true, None),
+                None => (false, None),
+            },
+            Err(_) => (false, None),
+        };
+        // Release the sleep lock unless a newer process has replaced this one
+        if !replaced {
+            set_sleep_inhibited(&state, false);
+        }
+
+        // A stop in progress hands over the exit code once the child is gone
+        let stopped = match &status {
+            Some(_) => None,
+            None if !replaced => take_stopped_exit(&state, child_pid),
+            None => None,
+        };
+        if stopped.map(|s| s.keep_session).unwrap_or(false) {
+            return;
+        }
+        let exit_code = match &status {
+            Some(status) => status.code(),
+            None => stopped.and_then(|s| s.exit_code),
+        };
+
+        if let Some(status) = status.filter(|s| !s.success()) {
+            if supervise_crash(&app_handle_clone, status, &model_path, &audio_source, restarts, generation) {
+                return;
+            }
+        }
+        if dual && !replaced {
+            dual_capture::stop_monitor(&state, stop_timeout(&state));
+        }
+        if let Some(session_id) = &session_id {
+            end_session(&state, Some(session_id.as_str()));
+        }
+        handle_caption_event(
+            &app_handle_clone,
             CaptionEvent {
                 event_type: "stopped".to_string(),
+                id: None,
                 caption_type: None,
                 text: None,
                 timestamp: None,
                 message: None,
                 version: None,
                 source: None,
+                speaker: None,
+                words: Vec::new(),
+                exit_code,
+                channel: None,
             },
         );
     });
 
     // Spawn a thread to read stderr for debugging
+    let stderr_state = state.inner().clone();
+    if let Ok(mut tail) = stderr_state.process_stderr.lock() {
+        tail.clear();
+    }
+    let stderr_app_handle = app_handle.clone();
     std::thread::spawn(move || {
         let reader = BufReader::new(stderr);
         for line in reader.lines() {
             match line {
                 Ok(stderr_line) => {
-                    // Drain stderr to prevent subprocess from blocking (not logged)
+                    // Drain stderr to prevent subprocess from blocking, keeping
+                    // a short tail for diagnostics and the log panel
+                    debug!("[zig-april-captions] {}", stderr_line);
+                    let _ = stderr_app_handle.emit(
+                        "caption-log",
+                        serde_json::json!({ "line": stderr_line, "timestamp": now_millis() }),
+                    );
+                    if let Ok(mut tail) = stderr_state.process_stderr.lock() {
+                        if tail.len() >= STDERR_TAIL_LINES {
+                            tail.pop_front();
+                        }
+                        tail.push_back(stderr_line);
+                    }
                 }
                 Err(e) => {
-                    eprintln!("Error reading stderr: {}", e);
+                    error!("Error reading stderr: {}", e);
                     break;
                 }
             }
@@ -672,82 +1245,770 @@ async fn start_captions(
     Ok(())
 }
 
-fn stop_captions_internal(state: &tauri::State<'_, Arc<AppState>>) -> Result<(), String> {
-    let mut process_guard = state.process.lock().map_err(|e| e.to_string())?;
-    if let Some(mut child) = process_guard.take() {
-        // Try to kill gracefully first
-        let _ = child.kill();
-        let _ = child.wait();
+/// A child stopped on purpose, as handed to its stdout reader
+#[derive(Debug, Clone, Copy)]
+struct StoppedChild {
+    pid: u32,
+    exit_code: Option<i32>,
+    keep_session: bool, // Model switch: the next child carries on the session
+}
+
+/// How long a stop waits for the stdout reader to take the exit code
+const EXIT_HANDOFF_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// Check `done` every 20ms until it returns true or `timeout` passes
+fn wait_until(timeout: std::time::Duration, mut done: impl FnMut() -> bool) -> bool {
+    let deadline = std::time::Instant::now() + timeout;
+    loop {
+        if done() {
+            return true;
+        }
+        if std::time::Instant::now() >= deadline {
+            return false;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    }
+}
+
+/// Ask the recognizer to exit so it can flush its last caption. Windows gets
+/// no request: the engine runs without a console (CREATE_NO_WINDOW), so it
+/// can't receive CTRL_BREAK, and is killed right away.
+#[cfg(unix)]
+fn request_exit(child: &Child) -> bool {
+    Command::new("kill")
+        .args(["-TERM", &child.id().to_string()])
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn request_exit(_child: &Child) -> bool {
+    false
+}
+
+/// Stop the child, killing it if it hasn't exited within `timeout`; returns its exit code
+fn shut_down_child(mut child: Child, timeout: std::time::Duration) -> Option<i32> {
+    let mut status = None;
+    if request_exit(&child) {
+        wait_until(timeout, || {
+            status = child.try_wait().ok().flatten();
+            status.is_some()
+        });
+    }
+    let status = match status {
+        Some(status) => status,
+        None => {
+            warn!("Caption engine didn't exit within {:?}, killing it", timeout);
+            let _ = child.kill();
+            child.wait().ok()?
+        }
+    };
+    info!("Caption engine exited: {}", status);
+    status.code()
+}
+
+/// How long a stopped recognizer gets to exit before it is killed
+fn stop_timeout(state: &AppState) -> std::time::Duration {
+    let timeout = state
+        .settings
+        .lock()
+        .ok()
+        .and_then(|s| s.supervisor.as_ref().map(|sv| sv.stop_timeout_ms))
+        .unwrap_or_else(default_stop_timeout_ms);
+    std::time::Duration::from_millis(timeout)
+}
+
+/// What `stop_child` left for the child `pid`
+fn take_stopped_exit(state: &AppState, pid: u32) -> Option<StoppedChild> {
+    let mut taken = None;
+    wait_until(EXIT_HANDOFF_TIMEOUT, || match state.stopped_exit.lock() {
+        Ok(mut stopped) if stopped.map(|s| s.pid == pid).unwrap_or(false) => {
+            taken = stopped.take();
+            true
+        }
+        Ok(_) => false,
+        Err(_) => true,
+    });
+    taken
+}
+
+/// Shut the child down and wait for its stdout reader to finish with it.
+/// With `keep_session` the reader leaves the session open and reports no stop.
+fn stop_child(state: &AppState, keep_session: bool) -> Result<(), String> {
+    let timeout = stop_timeout(state);
+    dual_capture::stop_monitor(state, timeout);
+    let child = state.process.lock().map_err(|e| e.to_string())?.take();
+    if let Some(child) = child {
+        let pid = child.id();
+        let code = shut_down_child(child, timeout);
+        process_cleanup::clear_pid(process_cleanup::ENGINE_PID);
+
+        // Let the stdout reader handle the last caption and report the exit
+        // before the session closes
+        if let Ok(mut stopped) = state.stopped_exit.lock() {
+            *stopped = Some(StoppedChild { pid, exit_code: code, keep_session });
+        }
+        let handed_over = wait_until(EXIT_HANDOFF_TIMEOUT, || {
+            state.stopped_exit.lock().map(|s| s.is_none()).unwrap_or(true)
+        });
+        if !handed_over {
+            if let Ok(mut stopped) = state.stopped_exit.lock() {
+                *stopped = None;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `stop_child` off the async runtime; it blocks until the child is gone
+async fn stop_child_async(state: &Arc<AppState>, keep_session: bool) -> Result<(), String> {
+    let state = state.clone();
+    tauri::async_runtime::spawn_blocking(move || stop_child(&state, keep_session))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+async fn stop_captions_internal(state: &tauri::State<'_, Arc<AppState>>) -> Result<(), String> {
+    stop_child_async(state, false).await?;
+    state.generation.fetch_add(1, Ordering::SeqCst);
+    state.run.fetch_add(1, Ordering::SeqCst);
+    state.paused.store(false, Ordering::SeqCst);
+    set_sleep_inhibited(state, false);
+    end_session(state, None);
+    Ok(())
+}
+
+/// Point the dynamic loader at the binary's directory, where the bundled
+/// onnxruntime library lives, and keep Windows from opening a console
+#[cfg_attr(target_os = "windows", allow(unused_variables))]
+fn set_library_path(cmd: &mut Command, binary_dir: &str) {
+    // On Linux, set LD_LIBRARY_PATH so the binary can find libonnxruntime.so
+    #[cfg(target_os = "linux")]
+    {
+        let current_ld_path = std::env::var("LD_LIBRARY_PATH").unwrap_or_default();
+        let new_ld_path = if current_ld_path.is_empty() {
+            binary_dir.to_string()
+        } else {
+            format!("{}:{}", binary_dir, current_ld_path)
+        };
+        debug!("Setting LD_LIBRARY_PATH: {}", new_ld_path);
+        cmd.env("LD_LIBRARY_PATH", new_ld_path);
+    }
+
+    // On macOS, set DYLD_LIBRARY_PATH so the binary can find libonnxruntime.dylib
+    #[cfg(target_os = "macos")]
+    {
+        let current_dyld_path = std::env::var("DYLD_LIBRARY_PATH").unwrap_or_default();
+        let new_dyld_path = if current_dyld_path.is_empty() {
+            binary_dir.to_string()
+        } else {
+            format!("{}:{}", binary_dir, current_dyld_path)
+        };
+        debug!("Setting DYLD_LIBRARY_PATH: {}", new_dyld_path);
+        cmd.env("DYLD_LIBRARY_PATH", new_dyld_path);
+    }
+
+    // On Windows, hide the console window that would otherwise pop up
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+}
+
+/// Report a recognizer that exited with an error and relaunch it if the
+/// supervisor settings allow. Returns true when the run was taken over (by
+/// the restart, or by a manual stop/start during the backoff).
+fn supervise_crash(
+    app_handle: &AppHandle,
+    status: std::process::ExitStatus,
+    model_path: &str,
+    audio_source: &str,
+    restarts: u32,
+    generation: u64,
+) -> bool {
+    let state = app_handle.state::<Arc<AppState>>();
+    // Give the stderr reader a moment to catch the last lines
+    std::thread::sleep(std::time::Duration::from_millis(200));
+    let stderr: Vec<String> = state
+        .process_stderr
+        .lock()
+        .map(|tail| tail.iter().skip(tail.len().saturating_sub(CRASH_STDERR_LINES)).cloned().collect())
+        .unwrap_or_default();
+    let supervisor = state
+        .settings
+        .lock()
+        .ok()
+        .and_then(|s| s.supervisor.clone())
+        .unwrap_or_default();
+    let restart = supervisor.auto_restart && restarts < supervisor.max_restarts;
+
+    error!("Caption engine exited unexpectedly: {}", status);
+    let _ = app_handle.emit(
+        "caption-error",
+        serde_json::json!({
+            "message": format!("Caption engine exited unexpectedly ({})", status),
+            "exitCode": status.code(),
+            "stderr": stderr,
+            "restarting": restart,
+            "attempt": restarts + 1,
+            "maxRestarts": supervisor.max_restarts,
+        }),
+    );
+    if !restart {
+        return false;
+    }
+
+    let delay = supervisor.backoff_ms.saturating_mul(1 << restarts.min(10));
+    std::thread::sleep(std::time::Duration::from_millis(delay));
+    if state.generation.load(Ordering::SeqCst) != generation {
+        return true;
+    }
+
+    match launch_captions(app_handle, model_path, audio_source, false, restarts + 1) {
+        Ok(()) => {
+            info!("Caption engine restarted (attempt {} of {})", restarts + 1, supervisor.max_restarts);
+            true
+        }
+        Err(e) => {
+            error!("Failed to restart caption engine: {}", e);
+            let _ = app_handle.emit(
+                "caption-error",
+                serde_json::json!({ "message": format!("Failed to restart caption engine: {}", e), "restarting": false }),
+            );
+            false
+        }
+    }
+}
+
+fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
+/// Open a session for a new captioning run and make it current
+fn begin_session(state: &AppState, audio_source: &str, model_path: &str) -> Option<String> {
+    let session = state
+        .db()
+        .and_then(|conn| {
+            database::start_session(&conn, now_millis(), audio_source, model_path).map_err(|e| e.to_string())
+        });
+    match session {
+        Ok(session) => {
+            info!("Started session {} ({})", session.id, session.title);
+            metrics::reset(state);
+            if let Ok(mut recent) = state.recent_events.lock() {
+                recent.clear();
+            }
+            if let Ok(mut current) = state.session_id.lock() {
+                *current = Some(session.id.clone());
+            }
+            Some(session.id)
+        }
+        Err(e) => {
+            warn!("Could not record session: {}", e);
+            None
+        }
+    }
+}
+
+/// Recordings made by the app; the only audio retention cleanup deletes
+fn get_recordings_dir() -> std::path::PathBuf {
+    let dir = get_config_dir().join("recordings");
+    std::fs::create_dir_all(&dir).ok();
+    dir
+}
+
+/// Where a session's audio goes; relaunches after a crash or a model switch
+/// get their own file
+fn recording_path(session_id: &str, restarts: u32) -> std::path::PathBuf {
+    let dir = get_recordings_dir();
+    if restarts == 0 {
+        return dir.join(format!("{}.wav", session_id));
+    }
+    (restarts..)
+        .map(|n| dir.join(format!("{}-{}.wav", session_id, n)))
+        .find(|path| !path.exists())
+        .unwrap_or_else(|| dir.join(format!("{}-{}.wav", session_id, restarts)))
+}
+
+/// Every recording of a session: the first part in `audio_path`, later parts
+/// and the other side of a dual capture (`<id>.them.wav`), all named after it
+fn session_recordings(session_id: &str) -> Vec<std::path::PathBuf> {
+    let Ok(entries) = std::fs::read_dir(get_recordings_dir()) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.strip_prefix(session_id)
+                .is_some_and(|rest| rest.starts_with('.') || rest.starts_with('-'))
+        })
+        .map(|entry| entry.path())
+        .collect()
+}
+
+fn set_session_audio_path(state: &AppState, session_id: &str, path: &Path) {
+    let result = state.db().and_then(|conn| {
+        database::set_session_audio_path(&conn, session_id, &path.to_string_lossy()).map_err(|e| e.to_string())
+    });
+    if let Err(e) = result {
+        warn!("Could not store recording path: {}", e);
+    }
+}
+
+/// Close the current session; with `only`, just when it is still that session
+fn end_session(state: &AppState, only: Option<&str>) {
+    let ended = {
+        let Ok(mut current) = state.session_id.lock() else {
+            return;
+        };
+        if only.is_some() && current.as_deref() != only {
+            return;
+        }
+        current.take()
+    };
+    if let Ok(mut context) = state.meeting_context.lock() {
+        *context = None;
+    }
+    templates::clear(state);
+    if let Some(id) = ended {
+        caption_batch::flush(state);
+        metrics::finish(state, &id);
+        let result = state.db().and_then(|conn| {
+            database::end_session(&conn, &id, now_millis()).map_err(|e| e.to_string())?;
+            encryption::save(&conn)
+        });
+        if let Err(e) = result {
+            warn!("Could not close session {}: {}", id, e);
+        }
+    }
+}
+
+fn current_session_id(state: &AppState) -> Option<String> {
+    state.session_id.lock().ok().and_then(|id| id.clone())
+}
+
+/// Queue a final caption line for the current session
+fn record_transcript_line(app_handle: &AppHandle, event: &CaptionEvent, text: &str) {
+    let state = app_handle.state::<Arc<AppState>>();
+    let Some(session_id) = current_session_id(&state) else {
+        return;
+    };
+    // Written by caption_batch within FLUSH_INTERVAL
+    state.finals.push(database::NewCaptionLine {
+        session_id,
+        timestamp: event.timestamp.unwrap_or_else(now_millis),
+        text: text.to_string(),
+        source: event.source.clone(),
+        channel: event.channel.clone(),
+        speaker: event.speaker.clone(),
+        caption_id: event.id.clone(),
+        words: event.words.clone(),
+    });
+}
+
+/// Acquire or release the OS sleep inhibitor
+fn set_sleep_inhibited(state: &AppState, inhibited: bool) {
+    let Ok(mut inhibitor) = state.sleep_inhibitor.lock() else {
+        return;
+    };
+    if !inhibited {
+        *inhibitor = None; // Dropping releases the lock
+    } else if inhibitor.is_none() {
+        match power::SleepInhibitor::acquire("Live captioning in progress") {
+            Ok(guard) => *inhibitor = Some(guard),
+            Err(e) => warn!("Could not prevent system sleep: {}", e),
+        }
+    }
+}
+
+/// A `caption-event` as emitted, with the time it was emitted
+#[derive(Debug, Clone, Serialize)]
+struct BufferedEvent {
+    #[serde(rename = "receivedAt")]
+    received_at: i64,
+    event: CaptionEvent,
+}
+
+fn is_partial(event: &CaptionEvent) -> bool {
+    event.event_type == "caption" && event.caption_type.as_deref() == Some("partial")
+}
+
+/// Keep an emitted event for replay. A partial caption is only kept until the
+/// next caption from the same source supersedes it.
+fn remember_event(state: &AppState, event: &CaptionEvent) {
+    let Ok(mut recent) = state.recent_events.lock() else {
+        return;
+    };
+    let superseded = recent
+        .back()
+        .map(|last| is_partial(&last.event) && event.event_type == "caption" && last.event.source == event.source)
+        .unwrap_or(false);
+    if superseded {
+        recent.pop_back();
+    }
+    if recent.len() >= RECENT_EVENTS {
+        recent.pop_front();
+    }
+    recent.push_back(BufferedEvent { received_at: now_millis(), event: event.clone() });
+}
+
+/// Forward a recognizer event to the frontend and streaming clients
+fn handle_caption_event(app_handle: &AppHandle, mut event: CaptionEvent) {
+    let is_final = event.event_type == "caption" && event.caption_type.as_deref() == Some("final");
+    if is_final && event.id.is_none() {
+        event.id = Some(uuid::Uuid::new_v4().to_string());
+    }
+    if event.event_type == "caption" {
+        let text = event.text.as_deref().unwrap_or_default();
+        auto_stop::on_caption(&app_handle.state::<Arc<AppState>>());
+        // Word times are epoch ms (the stream origin plus the word's offset)
+        let spoken_at = event.words.last().map(|word| word.end_ms).or(event.timestamp);
+        metrics::on_caption(&app_handle.state::<Arc<AppState>>(), is_final, spoken_at, text);
+    }
+    if is_final {
+        event.text = event
+            .text
+            .map(|text| corrections::apply(app_handle, &vocabulary::correct(app_handle, &text)));
+    }
+    let original = event
+        .text
+        .as_mut()
+        .and_then(|text| redaction::redact_caption(app_handle, text, &mut event.words));
+    server::publish_caption_event(app_handle, &event);
+    obs::on_caption_event(app_handle, &event);
+    if is_final {
+        if let (Some(text), Some(id)) = (&event.text, &event.id) {
+            record_transcript_line(app_handle, &event, text);
+            if let Some(original) = &original {
+                redaction::keep_original(app_handle, id, event.timestamp.unwrap_or_else(now_millis), original);
+            }
+            translation::on_final_caption(app_handle, id, text);
+            webhooks::on_final_caption(app_handle, text);
+            mqtt::on_final_caption(app_handle, text, event.source.as_deref());
+        }
+    }
+    remember_event(&app_handle.state::<Arc<AppState>>(), &event);
+    let _ = app_handle.emit("caption-event", event);
+}
+
+/// Caption events of the current session emitted at or after `since_ts`,
+/// oldest first, so a window opened mid-session can catch up
+#[tauri::command]
+async fn replay_recent_events(
+    state: tauri::State<'_, Arc<AppState>>,
+    since_ts: Option<i64>,
+) -> Result<Vec<BufferedEvent>, String> {
+    let recent = state.recent_events.lock().map_err(|e| e.to_string())?;
+    let since = since_ts.unwrap_or(i64::MIN);
+    Ok(recent.iter().filter(|e| e.received_at >= since).cloned().collect())
+}
+
+/// Restart the recognizer with another model without closing the session.
+/// A marker line in the transcript shows where the model changed.
+#[tauri::command]
+async fn switch_model(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    model_path: String,
+) -> Result<(), String> {
+    if !Path::new(&model_path).exists() {
+        return Err(format!("Model not found: {}", model_path));
+    }
+    let running = state.process.lock().map(|p| p.is_some()).unwrap_or(false);
+    let session_id = current_session_id(&state).filter(|_| running).ok_or("Captions are not running")?;
+    let audio_source = {
+        let saved = state.settings.lock().map(|s| s.audio_source.clone()).unwrap_or_default();
+        let conn = state.db()?;
+        database::get_session(&conn, &session_id)
+            .map_err(|e| e.to_string())?
+            .and_then(|s| s.audio_source)
+            .unwrap_or(saved)
+    };
+
+    stop_child_async(&state, true).await?;
+    // Drop crash restarts still pending for the old child
+    state.generation.fetch_add(1, Ordering::SeqCst);
+
+    let model_name = Path::new(&model_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| model_path.clone());
+    let marker = format!("[Model switched to {}]", model_name);
+    let timestamp = now_millis();
+    let result = state.db().and_then(|conn| {
+        database::set_session_model(&conn, &session_id, &model_path).map_err(|e| e.to_string())?;
+        database::add_transcript_line(&conn, &session_id, timestamp, &marker, Some("marker"), None)
+            .map_err(|e| e.to_string())
+    });
+    if let Err(e) = result {
+        warn!("Failed to record model switch: {}", e);
+    }
+    info!("Switching model to {}", model_path);
+
+    // A fresh model gets the full crash-restart budget
+    if let Err(e) = launch_captions(&app_handle, &model_path, &audio_source, false, 0) {
+        end_session(&state, Some(session_id.as_str()));
+        return Err(e);
+    }
+    if let Ok(mut settings) = state.settings.lock() {
+        settings.model_path = model_path.clone();
+        if let Err(e) = persist::persist_json(&get_settings_path(), &*settings) {
+            warn!("Failed to save settings: {}", e);
+        }
     }
+    handle_caption_event(
+        &app_handle,
+        CaptionEvent {
+            event_type: "model-switched".to_string(),
+            id: None,
+            caption_type: None,
+            text: None,
+            timestamp: Some(timestamp),
+            message: Some(marker),
+            version: None,
+            source: None,
+            speaker: None,
+            words: Vec::new(),
+            exit_code: None,
+            channel: None,
+        },
+    );
     Ok(())
 }
 
 #[tauri::command]
-async fn stop_captions(state: tauri::State<'_, Arc<AppState>>) -> Result<(), String> {
-    stop_captions_internal(&state)
+async fn stop_captions(app_handle: AppHandle, state: tauri::State<'_, Arc<AppState>>) -> Result<(), String> {
+    let session_id = current_session_id(&state);
+    stop_captions_internal(&state).await?;
+    obs::end_session(&app_handle);
+    let transcript = state.transcript_lines.lock().map(|l| l.texts()).unwrap_or_default();
+    webhooks::dispatch(
+        &app_handle,
+        webhooks::EVENT_SESSION_END,
+        serde_json::json!({ "session_id": session_id, "transcript_lines": transcript.len() }),
+    );
+    integrations::on_session_end(&app_handle, transcript);
+    server::publish(
+        &app_handle,
+        "session",
+        serde_json::json!({ "state": "stopped", "audioSource": null, "sessionId": session_id }),
+    );
+    mqtt::publish_session_state(&app_handle, "stopped");
+    Ok(())
 }
 
+/// Most recent stderr lines from the caption engine, oldest first
 #[tauri::command]
-async fn is_running(state: tauri::State<'_, Arc<AppState>>) -> Result<bool, String> {
-    let process_guard = state.process.lock().map_err(|e| e.to_string())?;
-    if let Some(_child) = process_guard.as_ref() {
-        // Check if process is still running
-        // Note: We can't easily check without consuming the child, so we assume it's running
-        // The actual status is tracked via events
-        Ok(true)
+async fn get_process_logs(
+    state: tauri::State<'_, Arc<AppState>>,
+    last_n: Option<usize>,
+) -> Result<Vec<String>, String> {
+    let tail = state.process_stderr.lock().map_err(|e| e.to_string())?;
+    let skip = tail.len().saturating_sub(last_n.unwrap_or(STDERR_TAIL_LINES));
+    Ok(tail.iter().skip(skip).cloned().collect())
+}
+
+/// "running", "paused" or "stopped"
+fn caption_state(state: &AppState) -> &'static str {
+    let running = state.process.lock().map(|p| p.is_some()).unwrap_or(false);
+    if !running {
+        "stopped"
+    } else if state.paused.load(Ordering::SeqCst) {
+        "paused"
     } else {
-        Ok(false)
+        "running"
+    }
+}
+
+#[tauri::command]
+async fn is_running(state: tauri::State<'_, Arc<AppState>>) -> Result<String, String> {
+    // The child may have exited without us noticing yet; the reader thread
+    // reports that through a "stopped" event
+    Ok(caption_state(&state).to_string())
+}
+
+/// Notify every consumer that captions were paused or resumed
+fn publish_pause_state(app_handle: &AppHandle, paused: bool) {
+    let status = if paused { "paused" } else { "resumed" };
+    handle_caption_event(
+        app_handle,
+        CaptionEvent {
+            event_type: status.to_string(),
+            id: None,
+            caption_type: None,
+            text: None,
+            timestamp: Some(now_millis()),
+            message: None,
+            version: None,
+            source: None,
+            speaker: None,
+            words: Vec::new(),
+            exit_code: None,
+            channel: None,
+        },
+    );
+    server::publish(app_handle, "session", serde_json::json!({ "state": status }));
+    mqtt::publish_session_state(app_handle, status);
+}
+
+/// Stop emitting captions while keeping the recognizer (and its model) loaded
+#[tauri::command]
+async fn pause_captions(app_handle: AppHandle, state: tauri::State<'_, Arc<AppState>>) -> Result<(), String> {
+    match caption_state(&state) {
+        "stopped" => return Err("Captions are not running".to_string()),
+        "paused" => return Ok(()),
+        _ => {}
+    }
+    state.paused.store(true, Ordering::SeqCst);
+    info!("Captions paused");
+    publish_pause_state(&app_handle, true);
+    Ok(())
+}
+
+#[tauri::command]
+async fn resume_captions(app_handle: AppHandle, state: tauri::State<'_, Arc<AppState>>) -> Result<(), String> {
+    match caption_state(&state) {
+        "stopped" => return Err("Captions are not running".to_string()),
+        "running" => return Ok(()),
+        _ => {}
     }
+    state.paused.store(false, Ordering::SeqCst);
+    info!("Captions resumed");
+    publish_pause_state(&app_handle, false);
+    Ok(())
 }
 
 #[tauri::command]
 async fn get_settings(state: tauri::State<'_, Arc<AppState>>) -> Result<Settings, String> {
     let settings_guard = state.settings.lock().map_err(|e| e.to_string())?;
-    Ok(settings_guard.clone())
+    Ok(settings_update::redacted(&settings_guard))
 }
 
 #[tauri::command]
 async fn save_settings(
+    app_handle: AppHandle,
     state: tauri::State<'_, Arc<AppState>>,
     settings: Settings,
 ) -> Result<(), String> {
     // Update in-memory settings
-    {
+    let (previous, settings) = {
         let mut settings_guard = state.settings.lock().map_err(|e| e.to_string())?;
-        *settings_guard = settings.clone();
-    }
-
-    // Save to file
-    let path = get_settings_path();
-    let json = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
-    std::fs::write(&path, json).map_err(|e| format!("Failed to save settings: {}", e))?;
+        let settings = settings_update::unmask(&settings_guard, settings);
+        persist_settings(&settings)?;
+        (std::mem::replace(&mut *settings_guard, settings.clone()), settings)
+    };
+    apply_saved_settings(&app_handle, &state, &previous, &settings).await;
+    Ok(())
+}
 
+/// Write settings to disk and to the active profile. Callers hold the settings
+/// lock, so concurrent saves reach the file in the order they reach memory
+fn persist_settings(settings: &Settings) -> Result<(), String> {
+    persist::persist_json(&get_settings_path(), settings).map_err(|e| format!("Failed to save settings: {}", e))?;
+    profiles::store_active(settings);
     Ok(())
 }
 
-#[tauri::command]
-async fn export_captions(captions: Vec<Caption>, file_path: String) -> Result<(), String> {
-    let mut content = String::new();
-    content.push_str("# Zigy Export\n\n");
-
-    for caption in captions {
-        if caption.caption_type == "final" {
-            let time = chrono_lite_format(caption.timestamp);
-            content.push_str(&format!("[{}] {}\n", time, caption.text));
-        }
+/// Apply settings that were just saved, then tell every window what changed.
+/// The save already happened, so a subsystem that fails to follow is logged
+/// rather than reported as a failed save
+async fn apply_saved_settings(app_handle: &AppHandle, state: &AppState, previous: &Settings, settings: &Settings) {
+    // Apply the sleep toggle to a session that's already running
+    let running = state.process.lock().map(|p| p.is_some()).unwrap_or(false);
+    if running {
+        set_sleep_inhibited(state, settings.prevent_sleep);
+    }
+
+    settings_update::emit_changes(app_handle, previous, settings);
+
+    // Start/stop/restart the caption server and MQTT client if their settings changed
+    let results = [
+        server::apply_settings(app_handle).await,
+        mqtt::apply_settings(app_handle),
+        hotkeys::apply_settings(app_handle),
+        autostart::apply_settings(app_handle),
+    ];
+    for e in results.into_iter().filter_map(Result::err) {
+        warn!("{}", e);
     }
+}
 
+#[tauri::command]
+async fn export_captions(
+    captions: Vec<Caption>,
+    file_path: String,
+    format: Option<export::ExportFormat>,
+) -> Result<(), String> {
+    let content = export::render(&captions, format.unwrap_or_default())?;
     std::fs::write(&file_path, content).map_err(|e| format!("Failed to write file: {}", e))?;
 
     Ok(())
 }
 
-fn chrono_lite_format(timestamp_ms: i64) -> String {
-    let secs = timestamp_ms / 1000;
-    let hours = (secs / 3600) % 24;
-    let mins = (secs / 60) % 60;
-    let secs = secs % 60;
-    format!("{:02}:{:02}:{:02}", hours, mins, secs)
+/// Write a session's meeting notes (summary, action items, transcript, Q&A)
+/// to `path` as Markdown or PDF
+#[tauri::command]
+async fn export_session(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    session_id: String,
+    path: String,
+    options: Option<export::SessionExportOptions>,
+) -> Result<(), String> {
+    // Without options, those of the template the session was started from
+    let options = match options {
+        Some(options) => options,
+        None => {
+            let conn = state.reader()?;
+            templates::export_options(&state, &conn, &session_id).unwrap_or_default()
+        }
+    };
+    let id = session_id.clone();
+    let with_analytics = options.include_analytics;
+    let mut notes = db_pool::read(&state, move |conn| {
+        let mut notes = export::load_session_notes(conn, &id)?;
+        if with_analytics {
+            notes.analytics = Some(analytics::session_analytics(conn, &id)?);
+        }
+        Ok(notes)
+    })
+    .await?;
+
+    if options.generate_summary && (options.include_summary || options.include_action_items) {
+        let text = integrations::session_text(&app_handle, &session_id)?;
+        let summary = integrations::generate_summary(&app_handle, &text).await?;
+        notes.summary = Some(summary.summary);
+        // Tasks already tracked for the session take precedence
+        if notes.action_items.is_empty() {
+            notes.action_items = summary
+                .action_items
+                .into_iter()
+                .map(|text| export::ActionItem { text, done: false })
+                .collect();
+        }
+    }
+
+    let content = match options.format {
+        export::SessionExportFormat::Markdown => export::render_session_markdown(&notes, &options).into_bytes(),
+        export::SessionExportFormat::Pdf => {
+            // Captions use large on-screen sizes; half of that reads well on paper
+            let font_size = match options.font_size {
+                Some(size) => size,
+                None => state.settings.lock().map_err(|e| e.to_string())?.font_size as f32 * 0.5,
+            };
+            pdf::render_session_pdf(&notes, &options, font_size.clamp(8.0, 18.0))?
+        }
+    };
+    std::fs::write(&path, content).map_err(|e| format!("Failed to write file: {}", e))?;
+    info!("Exported session {} to {}", session_id, path);
+    Ok(())
 }
 
 #[tauri::command]
@@ -831,6 +2092,11 @@ async fn get_bundled_model_path(app_handle: AppHandle) -> Result<Option<String>,
 
 #[tauri::command]
 async fn get_binary_debug_info(app_handle: AppHandle) -> Result<String, String> {
+    binary_debug_report(&app_handle)
+}
+
+/// Describe every location probed for the zig-april-captions binary
+fn binary_debug_report(app_handle: &AppHandle) -> Result<String, String> {
     #[cfg(target_os = "windows")]
     let binary_name = "zig-april-captions.exe";
     #[cfg(not(target_os = "windows"))]
@@ -916,27 +2182,31 @@ async fn get_binary_debug_info(app_handle: AppHandle) -> Result<String, String>
 #[tauri::command]
 async fn get_transcript(state: tauri::State<'_, Arc<AppState>>) -> Result<Vec<String>, String> {
     let lines = state.transcript_lines.lock().map_err(|e| e.to_string())?;
-    Ok(lines.clone())
+    Ok(lines.texts())
 }
 
+/// Add a line to the live transcript; `caption_id` is the final caption it
+/// was made from, if any. Returns the line with its id.
 #[tauri::command]
-async fn add_transcript_line(state: tauri::State<'_, Arc<AppState>>, line: String) -> Result<Vec<String>, String> {
+async fn add_transcript_line(
+    state: tauri::State<'_, Arc<AppState>>,
+    line: String,
+    speaker: Option<String>,
+    caption_id: Option<String>,
+) -> Result<transcript::TranscriptLine, String> {
     let mut lines = state.transcript_lines.lock().map_err(|e| e.to_string())?;
-    lines.push(line);
-    Ok(lines.clone())
+    Ok(lines.push(line, speaker, caption_id))
 }
 
 #[tauri::command]
-async fn update_last_transcript_line(state: tauri::State<'_, Arc<AppState>>, line: String) -> Result<Vec<String>, String> {
+async fn update_last_transcript_line(
+    state: tauri::State<'_, Arc<AppState>>,
+    line: String,
+    caption_id: Option<String>,
+) -> Result<transcript::TranscriptLine, String> {
     let mut lines = state.transcript_lines.lock().map_err(|e| e.to_string())?;
-    if lines.is_empty() {
-        lines.push(line);
-    } else {
-        // Replace the last line (used for smart merging)
-        let last_idx = lines.len() - 1;
-        lines[last_idx] = line;
-    }
-    Ok(lines.clone())
+    // Replace the last line (used for smart merging)
+    Ok(lines.replace_last(line, caption_id))
 }
 
 #[tauri::command]
@@ -947,35 +2217,52 @@ async fn clear_transcript(state: tauri::State<'_, Arc<AppState>>) -> Result<(),
 }
 
 #[tauri::command]
-async fn get_knowledge() -> Result<Vec<KnowledgeEntry>, String> {
-    let path = get_knowledge_path();
-    if path.exists() {
-        let content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
-        let entries: Vec<KnowledgeEntry> = serde_json::from_str(&content).unwrap_or_default();
-        Ok(entries)
-    } else {
-        Ok(vec![])
-    }
+async fn get_knowledge(state: tauri::State<'_, Arc<AppState>>) -> Result<Vec<KnowledgeEntry>, String> {
+    Ok(state.knowledge.read()?.clone())
 }
 
 #[tauri::command]
-async fn save_knowledge(entries: Vec<KnowledgeEntry>) -> Result<(), String> {
-    let path = get_knowledge_path();
-    let json = serde_json::to_string_pretty(&entries).map_err(|e| e.to_string())?;
-    std::fs::write(&path, json).map_err(|e| format!("Failed to save knowledge: {}", e))?;
+async fn save_knowledge(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    entries: Vec<KnowledgeEntry>,
+) -> Result<(), String> {
+    state.knowledge.update(|current| *current = entries)?;
+    embeddings::wake(&app_handle);
     Ok(())
 }
 
-#[tauri::command]
-async fn add_knowledge_entry(content: String) -> Result<KnowledgeEntry, String> {
-    let path = get_knowledge_path();
-    let mut entries: Vec<KnowledgeEntry> = if path.exists() {
-        let file_content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
-        serde_json::from_str(&file_content).unwrap_or_default()
-    } else {
-        vec![]
-    };
+/// Provenance of something taken from a transcript selection: the session is
+/// looked up from the selected chat entries when not given
+fn selection_source(
+    state: &AppState,
+    session_id: Option<String>,
+    entry_ids: Option<Vec<String>>,
+) -> Result<(Option<String>, Vec<String>), String> {
+    let entry_ids = entry_ids.unwrap_or_default();
+    if session_id.is_some() || entry_ids.is_empty() {
+        return Ok((session_id, entry_ids));
+    }
+    let conn = state.db()?;
+    let session_id = entry_ids.iter().find_map(|id| {
+        conn.query_row("SELECT session_id FROM chat_entries WHERE id = ?1", params![id], |row| {
+            row.get::<_, Option<String>>(0)
+        })
+        .ok()
+        .flatten()
+    });
+    Ok((session_id, entry_ids))
+}
 
+#[tauri::command]
+async fn add_knowledge_entry(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    content: String,
+    source_session_id: Option<String>,
+    source_entry_ids: Option<Vec<String>>,
+) -> Result<KnowledgeEntry, String> {
+    let (source_session_id, source_entry_ids) = selection_source(&state, source_session_id, source_entry_ids)?;
     let entry = KnowledgeEntry {
         id: uuid::Uuid::new_v4().to_string(),
         content,
@@ -984,106 +2271,82 @@ async fn add_knowledge_entry(content: String) -> Result<KnowledgeEntry, String>
             .unwrap()
             .as_millis() as i64,
         nominated: true, // Default to nominated when adding new entries
+        tags: Vec::new(),
+        folder: None,
+        metadata: None,
+        source_session_id,
+        source_entry_ids,
     };
 
-    entries.push(entry.clone());
-
-    let json = serde_json::to_string_pretty(&entries).map_err(|e| e.to_string())?;
-    std::fs::write(&path, json).map_err(|e| format!("Failed to save knowledge: {}", e))?;
+    state.knowledge.update(|entries| entries.push(entry.clone()))?;
+    embeddings::wake(&app_handle);
 
     Ok(entry)
 }
 
 #[tauri::command]
-async fn delete_knowledge_entry(id: String) -> Result<(), String> {
-    let path = get_knowledge_path();
-    if path.exists() {
-        let content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
-        let mut entries: Vec<KnowledgeEntry> = serde_json::from_str(&content).unwrap_or_default();
-        entries.retain(|e| e.id != id);
-        let json = serde_json::to_string_pretty(&entries).map_err(|e| e.to_string())?;
-        std::fs::write(&path, json).map_err(|e| format!("Failed to save knowledge: {}", e))?;
+async fn delete_knowledge_entry(state: tauri::State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    let removed = state.knowledge.update(|entries| {
+        let index = entries.iter().position(|e| e.id == id)?;
+        Some(entries.remove(index))
+    })?;
+    if let Some(entry) = removed {
+        let conn = state.db()?;
+        trash::put(&conn, trash::TrashKind::Knowledge, &entry.id, &entry.content, &entry)?;
     }
     Ok(())
 }
 
 #[tauri::command]
-async fn update_knowledge_entry(id: String, content: String) -> Result<KnowledgeEntry, String> {
-    let path = get_knowledge_path();
-    if !path.exists() {
-        return Err("Knowledge file not found".to_string());
-    }
-
-    let file_content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
-    let mut entries: Vec<KnowledgeEntry> = serde_json::from_str(&file_content).unwrap_or_default();
-
-    let entry = entries.iter_mut().find(|e| e.id == id);
-    match entry {
-        Some(e) => {
+async fn update_knowledge_entry(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    id: String,
+    content: String,
+) -> Result<KnowledgeEntry, String> {
+    let updated = state.knowledge.update(|entries| {
+        entries.iter_mut().find(|e| e.id == id).map(|e| {
             e.content = content;
-            let updated = e.clone();
-
-            let json = serde_json::to_string_pretty(&entries).map_err(|e| e.to_string())?;
-            std::fs::write(&path, json).map_err(|e| format!("Failed to save knowledge: {}", e))?;
-
-            Ok(updated)
-        }
-        None => Err("Knowledge entry not found".to_string()),
-    }
+            e.clone()
+        })
+    })?;
+    let updated = updated.ok_or_else(|| "Knowledge entry not found".to_string())?;
+    embeddings::wake(&app_handle);
+    Ok(updated)
 }
 
 #[tauri::command]
-async fn toggle_knowledge_nomination(id: String) -> Result<KnowledgeEntry, String> {
-    let path = get_knowledge_path();
-    if !path.exists() {
-        return Err("Knowledge file not found".to_string());
-    }
-
-    let file_content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
-    let mut entries: Vec<KnowledgeEntry> = serde_json::from_str(&file_content).unwrap_or_default();
-
-    let entry = entries.iter_mut().find(|e| e.id == id);
-    match entry {
-        Some(e) => {
+async fn toggle_knowledge_nomination(
+    state: tauri::State<'_, Arc<AppState>>,
+    id: String,
+) -> Result<KnowledgeEntry, String> {
+    let updated = state.knowledge.update(|entries| {
+        entries.iter_mut().find(|e| e.id == id).map(|e| {
             e.nominated = !e.nominated;
-            let updated = e.clone();
-
-            let json = serde_json::to_string_pretty(&entries).map_err(|e| e.to_string())?;
-            std::fs::write(&path, json).map_err(|e| format!("Failed to save knowledge: {}", e))?;
-
-            Ok(updated)
-        }
-        None => Err("Knowledge entry not found".to_string()),
-    }
+            e.clone()
+        })
+    })?;
+    updated.ok_or_else(|| "Knowledge entry not found".to_string())
 }
 
 // Idea CRUD commands
 #[tauri::command]
-async fn get_ideas() -> Result<Vec<IdeaEntry>, String> {
-    let path = get_ideas_path();
-    if path.exists() {
-        let content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
-        let entries: Vec<IdeaEntry> = serde_json::from_str(&content).unwrap_or_default();
-        Ok(entries)
-    } else {
-        Ok(vec![])
-    }
+async fn get_ideas(state: tauri::State<'_, Arc<AppState>>) -> Result<Vec<IdeaEntry>, String> {
+    Ok(state.ideas.read()?.clone())
 }
 
+/// Add an idea; `source_entry_ids` are the transcript entries it was created
+/// from, if any
 #[tauri::command]
 async fn add_idea(
+    state: tauri::State<'_, Arc<AppState>>,
     title: String,
     raw_content: String,
-    corrected_script: String
+    corrected_script: String,
+    source_session_id: Option<String>,
+    source_entry_ids: Option<Vec<String>>,
 ) -> Result<IdeaEntry, String> {
-    let path = get_ideas_path();
-    let mut entries: Vec<IdeaEntry> = if path.exists() {
-        let file_content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
-        serde_json::from_str(&file_content).unwrap_or_default()
-    } else {
-        vec![]
-    };
-
+    let (source_session_id, source_entry_ids) = selection_source(&state, source_session_id, source_entry_ids)?;
     let entry = IdeaEntry {
         id: uuid::Uuid::new_v4().to_string(),
         title,
@@ -1093,219 +2356,426 @@ async fn add_idea(
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_millis() as i64,
+        source_session_id,
+        source_entry_ids,
     };
 
-    entries.insert(0, entry.clone()); // Insert at beginning for newest first
+    // Insert at beginning for newest first
+    state.ideas.update(|entries| entries.insert(0, entry.clone()))?;
+    let conn = state.db()?;
+    idea_versions::record(&conn, &entry.id, None, &entry.corrected_script)
+        .map_err(|e| format!("Failed to save idea version: {}", e))?;
+
+    Ok(entry)
+}
+
+#[tauri::command]
+async fn update_idea(
+    state: tauri::State<'_, Arc<AppState>>,
+    id: String,
+    title: String,
+    raw_content: String,
+    corrected_script: String
+) -> Result<IdeaEntry, String> {
+    let updated = state.ideas.update(|entries| {
+        entries.iter_mut().find(|e| e.id == id).map(|e| {
+            let previous = std::mem::replace(&mut e.corrected_script, corrected_script);
+            e.title = title;
+            e.raw_content = raw_content;
+            (previous, e.clone())
+        })
+    })?;
+    let (previous, updated) = updated.ok_or_else(|| "Idea entry not found".to_string())?;
+    let conn = state.db()?;
+    idea_versions::record(&conn, &id, Some(&previous), &updated.corrected_script)
+        .map_err(|e| format!("Failed to save idea version: {}", e))?;
+    Ok(updated)
+}
+
+/// Ideas taken from a session, newest first
+#[tauri::command]
+async fn get_ideas_for_session(
+    state: tauri::State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<Vec<IdeaEntry>, String> {
+    let ideas = state.ideas.read()?;
+    Ok(ideas.iter().filter(|e| e.source_session_id.as_deref() == Some(session_id.as_str())).cloned().collect())
+}
+
+#[tauri::command]
+async fn delete_idea(state: tauri::State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    let removed = state.ideas.update(|entries| {
+        let index = entries.iter().position(|e| e.id == id)?;
+        Some(entries.remove(index))
+    })?;
+    if let Some(entry) = removed {
+        // Its versions stay until the idea is purged from the trash
+        let conn = state.db()?;
+        trash::put(&conn, trash::TrashKind::Idea, &entry.id, &entry.title, &entry)?;
+    }
+    Ok(())
+}
+
+/// Replace the live transcript with edited text; returns the lines with their ids
+#[tauri::command]
+async fn update_transcript(
+    state: tauri::State<'_, Arc<AppState>>,
+    lines: Vec<String>,
+) -> Result<Vec<transcript::TranscriptLine>, String> {
+    transcript::update(&state, |transcript| {
+        let changes = transcript.set_texts(lines);
+        Ok((transcript.lines().to_vec(), changes))
+    })
+}
+
+// Chat history CRUD commands
+/// `source` ("me", "them", ...) and `channel` ("mic", "monitor") narrow the
+/// history to one side of the conversation
+#[tauri::command]
+async fn get_chat_history(
+    state: tauri::State<'_, Arc<AppState>>,
+    since: Option<i64>,
+    limit: Option<usize>,
+    source: Option<String>,
+    channel: Option<String>,
+) -> Result<Vec<ChatHistoryEntry>, String> {
+    db_pool::read(&state, move |conn| {
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {} FROM chat_entries
+                 WHERE timestamp >= ?1 AND (?3 IS NULL OR source = ?3) AND (?4 IS NULL OR channel = ?4)
+                 ORDER BY timestamp DESC LIMIT ?2",
+                database::CHAT_ENTRY_COLUMNS
+            ))
+            .map_err(|e| format!("Prepare failed: {}", e))?;
+
+        // Newest `limit` entries, returned oldest first
+        let mut entries = stmt
+            .query_map(
+                params![since.unwrap_or(i64::MIN), limit.map(|l| l as i64).unwrap_or(-1), source, channel],
+                database::chat_entry_from_row,
+            )
+            .map_err(|e| format!("Query failed: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        entries.reverse();
+        Ok(entries)
+    })
+    .await
+}
 
-    let json = serde_json::to_string_pretty(&entries).map_err(|e| e.to_string())?;
-    std::fs::write(&path, json).map_err(|e| format!("Failed to save idea: {}", e))?;
+#[tauri::command]
+async fn add_chat_entry(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    mut entry: ChatHistoryEntry,
+) -> Result<ChatHistoryEntry, String> {
+    if entry.session_id.is_none() {
+        entry.session_id = current_session_id(&state);
+    }
+    let conn = state.db()?;
+    database::save_chat_entry(&conn, &entry).map_err(|e| format!("Failed to save chat history: {}", e))?;
+    drop(conn);
+    embeddings::wake(&app_handle);
 
     Ok(entry)
 }
 
+/// Save several entries in one transaction, e.g. a burst of finals
 #[tauri::command]
-async fn update_idea(
-    id: String,
-    title: String,
-    raw_content: String,
-    corrected_script: String
-) -> Result<IdeaEntry, String> {
-    let path = get_ideas_path();
-    if !path.exists() {
-        return Err("Ideas file not found".to_string());
+async fn add_chat_entries_batch(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    mut entries: Vec<ChatHistoryEntry>,
+) -> Result<Vec<ChatHistoryEntry>, String> {
+    if entries.is_empty() {
+        return Ok(entries);
     }
+    let session_id = current_session_id(&state);
+    for entry in entries.iter_mut().filter(|e| e.session_id.is_none()) {
+        entry.session_id = session_id.clone();
+    }
+    let conn = state.db()?;
+    database::save_chat_entries(&conn, &entries).map_err(|e| format!("Failed to save chat history: {}", e))?;
+    drop(conn);
+    embeddings::wake(&app_handle);
 
-    let file_content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
-    let mut entries: Vec<IdeaEntry> = serde_json::from_str(&file_content).unwrap_or_default();
-
-    let entry = entries.iter_mut().find(|e| e.id == id);
-    match entry {
-        Some(e) => {
-            e.title = title;
-            e.raw_content = raw_content;
-            e.corrected_script = corrected_script;
-            let updated = e.clone();
-
-            let json = serde_json::to_string_pretty(&entries).map_err(|e| e.to_string())?;
-            std::fs::write(&path, json).map_err(|e| format!("Failed to save idea: {}", e))?;
+    Ok(entries)
+}
 
-            Ok(updated)
-        }
-        None => Err("Idea entry not found".to_string()),
+/// Pin or unpin an entry; returns whether it is now pinned
+#[tauri::command]
+async fn toggle_pin_entry(state: tauri::State<'_, Arc<AppState>>, id: String) -> Result<bool, String> {
+    let conn = state.db()?;
+    let changed = conn
+        .execute("UPDATE chat_entries SET pinned = 1 - pinned WHERE id = ?1", params![id])
+        .map_err(|e| format!("Failed to pin entry: {}", e))?;
+    if changed == 0 {
+        return Err(format!("Chat entry {} not found", id));
     }
+    conn.query_row("SELECT pinned FROM chat_entries WHERE id = ?1", params![id], |row| row.get(0))
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-async fn delete_idea(id: String) -> Result<(), String> {
-    let path = get_ideas_path();
-    if path.exists() {
-        let content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
-        let mut entries: Vec<IdeaEntry> = serde_json::from_str(&content).unwrap_or_default();
-        entries.retain(|e| e.id != id);
-        let json = serde_json::to_string_pretty(&entries).map_err(|e| e.to_string())?;
-        std::fs::write(&path, json).map_err(|e| format!("Failed to save ideas: {}", e))?;
-    }
-    Ok(())
+async fn get_pinned_entries(state: tauri::State<'_, Arc<AppState>>) -> Result<Vec<ChatHistoryEntry>, String> {
+    let conn = state.db()?;
+    database::pinned_entries(&conn).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-async fn update_transcript(state: tauri::State<'_, Arc<AppState>>, lines: Vec<String>) -> Result<(), String> {
-    let mut transcript = state.transcript_lines.lock().map_err(|e| e.to_string())?;
-    *transcript = lines;
+async fn clear_chat_history(state: tauri::State<'_, Arc<AppState>>) -> Result<(), String> {
+    let conn = state.db()?;
+    let trashed = trash::trash_chat_entries(&conn)?;
+    info!("Moved {} chat entries to the trash", trashed);
     Ok(())
 }
 
-// Chat history CRUD commands
 #[tauri::command]
-async fn get_chat_history(since: Option<i64>, limit: Option<usize>) -> Result<Vec<ChatHistoryEntry>, String> {
-    let path = get_chat_history_path();
-    if path.exists() {
-        let content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
-        let mut entries: Vec<ChatHistoryEntry> = serde_json::from_str(&content).unwrap_or_default();
+async fn get_chat_history_stats(state: tauri::State<'_, Arc<AppState>>) -> Result<serde_json::Value, String> {
+    let conn = state.db()?;
+    let (total_entries, total_chars): (i64, i64) = conn
+        .query_row(
+            "SELECT COUNT(*), COALESCE(SUM(LENGTH(CAST(content AS BLOB))), 0) FROM chat_entries",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| format!("Query failed: {}", e))?;
 
-        // Filter by timestamp if since is provided
-        if let Some(since_ts) = since {
-            entries.retain(|e| e.timestamp >= since_ts);
-        }
+    // Count by type
+    let mut stmt = conn
+        .prepare("SELECT entry_type, COUNT(*) FROM chat_entries GROUP BY entry_type")
+        .map_err(|e| format!("Prepare failed: {}", e))?;
+    let counts: std::collections::HashMap<String, i64> = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))
+        .map_err(|e| format!("Query failed: {}", e))?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let count = |entry_type: &str| counts.get(entry_type).copied().unwrap_or(0);
 
-        // Sort by timestamp (oldest first)
-        entries.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+    if total_entries == 0 {
+        return Ok(serde_json::json!({
+            "total_entries": 0,
+            "total_chars": 0,
+            "estimated_tokens": 0,
+            "by_type": {}
+        }));
+    }
 
-        // Apply limit if provided
-        if let Some(max) = limit {
-            if entries.len() > max {
-                entries = entries.into_iter().rev().take(max).collect::<Vec<_>>();
-                entries.reverse();
-            }
+    Ok(serde_json::json!({
+        "total_entries": total_entries,
+        "total_chars": total_chars,
+        "estimated_tokens": total_chars / 4, // ~4 chars per token
+        "by_type": {
+            "transcript": count("transcript"),
+            "question": count("question"),
+            "answer": count("answer"),
+            "summary": count("summary"),
+            "idea": count("idea")
         }
+    }))
+}
 
-        Ok(entries)
-    } else {
-        Ok(vec![])
+// Context snapshot commands
+#[tauri::command]
+async fn save_context_snapshot(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    mut snapshot: ContextSnapshot,
+) -> Result<ContextSnapshot, String> {
+    if snapshot.session_id.is_none() {
+        snapshot.session_id = current_session_id(&state);
     }
+    store_snapshot(&app_handle, snapshot)
 }
 
-#[tauri::command]
-async fn add_chat_entry(entry: ChatHistoryEntry) -> Result<ChatHistoryEntry, String> {
-    let path = get_chat_history_path();
-    let mut entries: Vec<ChatHistoryEntry> = if path.exists() {
-        let file_content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
-        serde_json::from_str(&file_content).unwrap_or_default()
-    } else {
-        vec![]
-    };
+/// Append a snapshot to the store and announce it
+fn store_snapshot(app_handle: &AppHandle, snapshot: ContextSnapshot) -> Result<ContextSnapshot, String> {
+    let state = app_handle.state::<Arc<AppState>>();
+    state.snapshots.update(|snapshots| snapshots.push(snapshot.clone()))?;
 
-    entries.push(entry.clone());
+    webhooks::dispatch(
+        app_handle,
+        webhooks::EVENT_SUMMARY_READY,
+        serde_json::json!({ "id": snapshot.id, "summary": snapshot.summary, "created_at": snapshot.created_at }),
+    );
 
-    let json = serde_json::to_string_pretty(&entries).map_err(|e| e.to_string())?;
-    std::fs::write(&path, json).map_err(|e| format!("Failed to save chat history: {}", e))?;
+    Ok(snapshot)
+}
 
-    Ok(entry)
+#[tauri::command]
+async fn get_latest_snapshot(state: tauri::State<'_, Arc<AppState>>) -> Result<Option<ContextSnapshot>, String> {
+    let snapshots = state.snapshots.read()?;
+    Ok(snapshots.iter().max_by_key(|s| s.created_at).cloned())
 }
 
 #[tauri::command]
-async fn clear_chat_history() -> Result<(), String> {
-    let path = get_chat_history_path();
-    if path.exists() {
-        std::fs::remove_file(&path).map_err(|e| format!("Failed to clear chat history: {}", e))?;
-    }
-    Ok(())
+async fn get_all_snapshots(state: tauri::State<'_, Arc<AppState>>) -> Result<Vec<ContextSnapshot>, String> {
+    let mut snapshots = state.snapshots.read()?.clone();
+    snapshots.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+    Ok(snapshots)
 }
 
 #[tauri::command]
-async fn get_chat_history_stats() -> Result<serde_json::Value, String> {
-    let path = get_chat_history_path();
+async fn clear_context_snapshots(state: tauri::State<'_, Arc<AppState>>) -> Result<(), String> {
+    state.snapshots.update(|snapshots| snapshots.clear())?;
+    let path = state.snapshots.path();
     if path.exists() {
-        let content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
-        let entries: Vec<ChatHistoryEntry> = serde_json::from_str(&content).unwrap_or_default();
-
-        let total_entries = entries.len();
-        let total_chars: usize = entries.iter().map(|e| e.content.len()).sum();
-        let estimated_tokens = total_chars / 4; // ~4 chars per token
+        std::fs::remove_file(path).map_err(|e| format!("Failed to clear snapshots: {}", e))?;
+    }
+    // Otherwise the cleared snapshots would live on in the backup
+    let _ = std::fs::remove_file(persist::backup_path(path));
+    Ok(())
+}
 
-        // Count by type
-        let transcript_count = entries.iter().filter(|e| e.entry_type == "transcript").count();
-        let question_count = entries.iter().filter(|e| e.entry_type == "question").count();
-        let answer_count = entries.iter().filter(|e| e.entry_type == "answer").count();
-        let summary_count = entries.iter().filter(|e| e.entry_type == "summary").count();
-        let idea_count = entries.iter().filter(|e| e.entry_type == "idea").count();
+/// Apply the retention policy: expired sessions lose their transcripts, chat
+/// entries, snapshots, recordings and transcript logs. The running session
+/// is never touched.
+fn cleanup_storage_internal(state: &AppState, dry_run: bool) -> Result<database::CleanupReport, String> {
+    let policy = state.settings.lock().map_err(|e| e.to_string())?.retention.clone();
+    let keep = current_session_id(state);
+    let mut report = {
+        let conn = state.db()?;
+        database::cleanup(&conn, &policy, now_millis(), keep.as_deref(), dry_run)
+            .map_err(|e| format!("Cleanup failed: {}", e))?
+    };
 
-        Ok(serde_json::json!({
-            "total_entries": total_entries,
-            "total_chars": total_chars,
-            "estimated_tokens": estimated_tokens,
-            "by_type": {
-                "transcript": transcript_count,
-                "question": question_count,
-                "answer": answer_count,
-                "summary": summary_count,
-                "idea": idea_count
-            }
-        }))
+    let expired: HashSet<&str> = report.expired_session_ids.iter().map(|id| id.as_str()).collect();
+    let cutoff = report.cutoff.unwrap_or(i64::MIN);
+    let is_expired = |s: &ContextSnapshot| match &s.session_id {
+        Some(id) => expired.contains(id.as_str()),
+        None => s.created_at < cutoff,
+    };
+    let (snapshots, snapshot_bytes) = if dry_run {
+        let snapshots = state.snapshots.read()?;
+        let stale: Vec<&ContextSnapshot> = snapshots.iter().filter(|s| is_expired(s)).collect();
+        (stale.len(), stale.iter().map(|s| s.summary.len() as u64).sum::<u64>())
     } else {
-        Ok(serde_json::json!({
-            "total_entries": 0,
-            "total_chars": 0,
-            "estimated_tokens": 0,
-            "by_type": {}
-        }))
+        state.snapshots.update(|snapshots| {
+            let (stale, kept): (Vec<_>, Vec<_>) = snapshots.drain(..).partition(|s| is_expired(s));
+            *snapshots = kept;
+            (stale.len(), stale.iter().map(|s| s.summary.len() as u64).sum::<u64>())
+        })?
+    };
+    report.snapshots = snapshots;
+    report.freed_bytes += snapshot_bytes;
+
+    // A transcribed file's audio_path is the user's own file; leave it alone
+    let recordings = std::fs::canonicalize(get_recordings_dir()).unwrap_or_else(|_| get_recordings_dir());
+    report
+        .audio_files
+        .retain(|path| std::fs::canonicalize(path).is_ok_and(|path| path.starts_with(&recordings)));
+    let named: Vec<String> = report
+        .expired_session_ids
+        .iter()
+        .flat_map(|id| session_recordings(id))
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    report.audio_files.extend(named);
+    report.audio_files.sort();
+    report.audio_files.dedup();
+    let files = report
+        .audio_files
+        .iter()
+        .map(std::path::PathBuf::from)
+        .chain(report.expired_session_ids.iter().map(|id| recovery::transcript_log_path(id)));
+    for file in files {
+        let Ok(metadata) = std::fs::metadata(&file) else {
+            continue;
+        };
+        if dry_run {
+            report.freed_bytes += metadata.len();
+        } else {
+            match std::fs::remove_file(&file) {
+                Ok(()) => report.freed_bytes += metadata.len(),
+                Err(e) => warn!("Failed to delete {}: {}", file.display(), e),
+            }
+        }
     }
+
+    if !dry_run && report.sessions + report.chat_entries + report.snapshots > 0 {
+        info!(
+            "Retention cleanup removed {} sessions, {} chat entries, {} snapshots ({} bytes)",
+            report.sessions, report.chat_entries, report.snapshots, report.freed_bytes
+        );
+    }
+    Ok(report)
 }
 
-// Context snapshot commands
+/// Remove data the retention policy no longer keeps. With `dry_run`, only
+/// report what would be removed.
 #[tauri::command]
-async fn save_context_snapshot(snapshot: ContextSnapshot) -> Result<ContextSnapshot, String> {
-    let path = get_context_snapshots_path();
-    let mut snapshots: Vec<ContextSnapshot> = if path.exists() {
-        let file_content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
-        serde_json::from_str(&file_content).unwrap_or_default()
-    } else {
-        vec![]
-    };
-
-    snapshots.push(snapshot.clone());
+async fn cleanup_storage(
+    state: tauri::State<'_, Arc<AppState>>,
+    dry_run: Option<bool>,
+) -> Result<database::CleanupReport, String> {
+    cleanup_storage_internal(&state, dry_run.unwrap_or(false))
+}
 
-    let json = serde_json::to_string_pretty(&snapshots).map_err(|e| e.to_string())?;
-    std::fs::write(&path, json).map_err(|e| format!("Failed to save context snapshot: {}", e))?;
+/// Reclaim the space of deleted rows; reports the database size before and after
+#[tauri::command]
+async fn vacuum_database(state: tauri::State<'_, Arc<AppState>>) -> Result<database::VacuumReport, String> {
+    let conn = state.db()?;
+    let report = database::vacuum(&conn).map_err(|e| format!("Failed to vacuum the database: {}", e))?;
+    info!("Database vacuumed: {} bytes before, {} after", report.before_bytes, report.after_bytes);
+    Ok(report)
+}
 
-    Ok(snapshot)
+// Session commands
+#[tauri::command]
+async fn get_sessions(state: tauri::State<'_, Arc<AppState>>) -> Result<Vec<Session>, String> {
+    db_pool::read(&state, |conn| {
+        database::list_sessions(conn).map_err(|e| format!("Failed to load sessions: {}", e))
+    })
+    .await
 }
 
 #[tauri::command]
-async fn get_latest_snapshot() -> Result<Option<ContextSnapshot>, String> {
-    let path = get_context_snapshots_path();
-    if path.exists() {
-        let content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
-        let mut snapshots: Vec<ContextSnapshot> = serde_json::from_str(&content).unwrap_or_default();
+async fn get_current_session(state: tauri::State<'_, Arc<AppState>>) -> Result<Option<Session>, String> {
+    let Some(id) = current_session_id(&state) else {
+        return Ok(None);
+    };
+    let conn = state.db()?;
+    database::get_session(&conn, &id).map_err(|e| format!("Failed to load session: {}", e))
+}
 
-        // Sort by created_at descending and return the latest
-        snapshots.sort_by(|a, b| b.created_at.cmp(&a.created_at));
-        Ok(snapshots.into_iter().next())
-    } else {
-        Ok(None)
-    }
+#[tauri::command]
+async fn get_session_transcript(
+    state: tauri::State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<Vec<String>, String> {
+    db_pool::read(&state, move |conn| {
+        database::session_transcript(conn, &session_id).map_err(|e| format!("Failed to load transcript: {}", e))
+    })
+    .await
 }
 
+/// Word timings and confidence saved for a final caption
 #[tauri::command]
-async fn get_all_snapshots() -> Result<Vec<ContextSnapshot>, String> {
-    let path = get_context_snapshots_path();
-    if path.exists() {
-        let content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
-        let mut snapshots: Vec<ContextSnapshot> = serde_json::from_str(&content).unwrap_or_default();
-        snapshots.sort_by(|a, b| b.created_at.cmp(&a.created_at));
-        Ok(snapshots)
-    } else {
-        Ok(vec![])
-    }
+async fn get_caption_words(
+    state: tauri::State<'_, Arc<AppState>>,
+    caption_id: String,
+) -> Result<Vec<database::CaptionWord>, String> {
+    let conn = state.db()?;
+    database::caption_words(&conn, &caption_id).map_err(|e| format!("Failed to load caption words: {}", e))
 }
 
 #[tauri::command]
-async fn clear_context_snapshots() -> Result<(), String> {
-    let path = get_context_snapshots_path();
-    if path.exists() {
-        std::fs::remove_file(&path).map_err(|e| format!("Failed to clear snapshots: {}", e))?;
+async fn rename_session(
+    state: tauri::State<'_, Arc<AppState>>,
+    session_id: String,
+    title: String,
+) -> Result<(), String> {
+    let title = title.trim();
+    if title.is_empty() {
+        return Err("Session title cannot be empty".to_string());
+    }
+    let conn = state.db()?;
+    match database::rename_session(&conn, &session_id, title) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(format!("Session {} not found", session_id)),
+        Err(e) => Err(format!("Failed to rename session: {}", e)),
     }
-    Ok(())
 }
 
 // ============================================================================
@@ -1314,26 +2784,32 @@ async fn clear_context_snapshots() -> Result<(), String> {
 
 /// Initialize the SQLite database and migrate from JSON if needed
 #[tauri::command]
-async fn init_database() -> Result<String, String> {
-    let mut conn = init_db().map_err(|e| format!("Failed to init database: {}", e))?;
-
-    // Check if we need to migrate (no chat entries yet)
-    let count: i64 = conn.query_row("SELECT COUNT(*) FROM chat_entries", [], |r| r.get(0))
-        .unwrap_or(0);
+async fn init_database(state: tauri::State<'_, Arc<AppState>>) -> Result<String, String> {
+    let mut conn = state.db()?;
+    if !database::is_json_migrated(&conn) {
+        migrate_json_once(&mut conn)?;
+    }
+    Ok("Database initialized".to_string())
+}
 
-    if count == 0 {
-        // Migrate from JSON files
-        let stats = migrate_from_json(&mut conn)
-            .map_err(|e| format!("Migration failed: {}", e))?;
+/// Import the legacy JSON files and record that the upgrade happened
+fn migrate_json_once(conn: &mut rusqlite::Connection) -> Result<MigrationStats, String> {
+    let stats = migrate_from_json(conn).map_err(|e| format!("Migration failed: {}", e))?;
+    database::mark_json_migrated(conn).map_err(|e| format!("Migration failed: {}", e))?;
 
-        println!("Migration complete: {} chat entries, {} ideas, {} knowledge, {} snapshots",
-            stats.chat_entries_migrated,
-            stats.ideas_migrated,
-            stats.knowledge_migrated,
-            stats.snapshots_migrated);
-    }
+    info!("Migration complete: {} chat entries, {} ideas, {} knowledge, {} snapshots",
+        stats.chat_entries_migrated,
+        stats.ideas_migrated,
+        stats.knowledge_migrated,
+        stats.snapshots_migrated);
+    Ok(stats)
+}
 
-    Ok("Database initialized".to_string())
+/// Re-run the JSON to SQLite import (entries already present are skipped)
+#[tauri::command]
+async fn migrate_storage(state: tauri::State<'_, Arc<AppState>>) -> Result<MigrationStats, String> {
+    let mut conn = state.db()?;
+    migrate_json_once(&mut conn)
 }
 
 /// Generate embedding using Gemini API
@@ -1396,138 +2872,113 @@ fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
 /// Search for similar entries using vector similarity (proper implementation)
 #[tauri::command]
 async fn vector_search(
+    state: tauri::State<'_, Arc<AppState>>,
     query_embedding: Vec<f32>,
     limit: usize,
     entry_types: Option<Vec<String>>,
 ) -> Result<Vec<ChatHistoryEntry>, String> {
-    let conn = init_db().map_err(|e| format!("Failed to open database: {}", e))?;
-
-    let type_filter = entry_types
-        .map(|types| format!("'{}'", types.join("','")))
-        .unwrap_or_else(|| "'transcript','summary','answer'".to_string());
+    let conn = state.reader()?;
 
-    // Fetch entries that have embeddings
-    let query = format!(r#"
-        SELECT id, timestamp, entry_type, content, metadata, embedding
-        FROM chat_entries
-        WHERE entry_type IN ({}) AND embedding IS NOT NULL
-        ORDER BY timestamp DESC
-        LIMIT 100
-    "#, type_filter);
+    let types = entry_types
+        .unwrap_or_else(|| vec!["transcript".to_string(), "summary".to_string(), "answer".to_string()]);
+    let nearest =
+        vector_index::nearest(&conn, vector_index::Indexed::Chat, &query_embedding, vector_index::oversample(limit))
+            .map_err(|e| format!("Vector search failed: {}", e))?;
 
-    let mut stmt = conn.prepare(&query)
+    let mut stmt = conn
+        .prepare(&format!("SELECT {} FROM chat_entries WHERE id = ?1", database::CHAT_ENTRY_COLUMNS))
         .map_err(|e| format!("Query failed: {}", e))?;
 
-    // Collect entries with their embeddings
-    let mut entries_with_scores: Vec<(ChatHistoryEntry, f32)> = Vec::new();
-
-    let rows = stmt.query_map([], |row| {
-        let id: String = row.get(0)?;
-        let timestamp: i64 = row.get(1)?;
-        let entry_type: String = row.get(2)?;
-        let content: String = row.get(3)?;
-        let metadata: Option<String> = row.get(4)?;
-        let embedding_blob: Option<Vec<u8>> = row.get(5)?;
-        Ok((id, timestamp, entry_type, content, metadata, embedding_blob))
-    })
-    .map_err(|e| format!("Execute failed: {}", e))?;
-
-    for row_result in rows {
-        let (id, timestamp, entry_type, content, metadata, embedding_blob) =
-            row_result.map_err(|e| format!("Row extraction failed: {}", e))?;
-
-        // Calculate similarity if embedding exists
-        let similarity = if let Some(blob) = embedding_blob {
-            let entry_embedding = database::blob_to_embedding(&blob);
-            cosine_similarity(&query_embedding, &entry_embedding)
-        } else {
-            0.0
-        };
-
-        let entry = ChatHistoryEntry {
-            id,
-            timestamp,
-            entry_type,
-            content,
-            metadata: metadata.and_then(|s| serde_json::from_str(&s).ok()),
-        };
-
-        entries_with_scores.push((entry, similarity));
+    // Most similar first
+    let mut entries = Vec::new();
+    for (id, _) in nearest {
+        if entries.len() >= limit {
+            break;
+        }
+        let entry = stmt
+            .query_row(params![id], database::chat_entry_from_row)
+            .optional()
+            .map_err(|e| format!("Row extraction failed: {}", e))?;
+        if let Some(entry) = entry.filter(|e| types.contains(&e.entry_type)) {
+            entries.push(entry);
+        }
     }
 
-    // Sort by similarity (highest first)
-    entries_with_scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    Ok(entries)
+}
 
-    // Return top N entries
-    let entries: Vec<ChatHistoryEntry> = entries_with_scores
-        .into_iter()
-        .take(limit)
-        .map(|(entry, _)| entry)
-        .collect();
+/// Inclusive epoch-millisecond bounds; either end may be open
+#[derive(Debug, Clone, Deserialize)]
+struct DateRange {
+    from: Option<i64>,
+    to: Option<i64>,
+}
 
-    Ok(entries)
+/// Full-text search over transcripts, AI answers and knowledge notes
+#[tauri::command]
+async fn search_text(
+    state: tauri::State<'_, Arc<AppState>>,
+    query: String,
+    types: Option<Vec<String>>,
+    date_range: Option<DateRange>,
+    limit: Option<usize>,
+) -> Result<Vec<database::SearchHit>, String> {
+    let (from, to) = date_range.map(|r| (r.from, r.to)).unwrap_or((None, None));
+    db_pool::read(&state, move |conn| {
+        database::search_text(conn, &query, &types.unwrap_or_default(), from, to, limit.unwrap_or(50))
+            .map_err(|e| format!("Search failed: {}", e))
+    })
+    .await
 }
 
 /// Search knowledge entries by semantic similarity
 #[tauri::command]
 async fn search_knowledge_semantic(
+    state: tauri::State<'_, Arc<AppState>>,
     query_embedding: Vec<f32>,
     limit: usize,
     nominated_only: bool,
 ) -> Result<Vec<KnowledgeEntry>, String> {
-    let conn = init_db().map_err(|e| format!("Failed to open database: {}", e))?;
+    let conn = state.reader()?;
 
-    let nominated_filter = if nominated_only { "AND nominated = 1" } else { "" };
+    let candidates = if nominated_only { vector_index::oversample(limit) } else { limit };
+    let nearest = vector_index::nearest(&conn, vector_index::Indexed::Knowledge, &query_embedding, candidates)
+        .map_err(|e| format!("Vector search failed: {}", e))?;
 
-    let query = format!(r#"
-        SELECT id, content, created_at, nominated, embedding
+    let mut stmt = conn.prepare(r#"
+        SELECT id, content, created_at, nominated, folder,
+               (SELECT GROUP_CONCAT(tag, char(31)) FROM knowledge_tags t WHERE t.entry_id = knowledge_entries.id)
         FROM knowledge_entries
-        WHERE embedding IS NOT NULL {}
-    "#, nominated_filter);
-
-    let mut stmt = conn.prepare(&query)
-        .map_err(|e| format!("Query failed: {}", e))?;
-
-    let mut entries_with_scores: Vec<(KnowledgeEntry, f32)> = Vec::new();
-
-    let rows = stmt.query_map([], |row| {
-        let id: String = row.get(0)?;
-        let content: String = row.get(1)?;
-        let created_at: i64 = row.get(2)?;
-        let nominated: i32 = row.get(3)?;
-        let embedding_blob: Option<Vec<u8>> = row.get(4)?;
-        Ok((id, content, created_at, nominated, embedding_blob))
-    })
-    .map_err(|e| format!("Execute failed: {}", e))?;
-
-    for row_result in rows {
-        let (id, content, created_at, nominated, embedding_blob) =
-            row_result.map_err(|e| format!("Row extraction failed: {}", e))?;
-
-        let similarity = if let Some(blob) = embedding_blob {
-            let entry_embedding = database::blob_to_embedding(&blob);
-            cosine_similarity(&query_embedding, &entry_embedding)
-        } else {
-            0.0
-        };
+        WHERE id = ?1
+    "#).map_err(|e| format!("Query failed: {}", e))?;
 
-        entries_with_scores.push((KnowledgeEntry {
-            id,
-            content,
-            created_at,
-            nominated: nominated == 1,
-        }, similarity));
+    // Most similar first
+    let mut entries = Vec::new();
+    for (id, _) in nearest {
+        if entries.len() >= limit {
+            break;
+        }
+        let entry = stmt.query_row(params![id], |row| {
+            let tags: Option<String> = row.get(5)?;
+            Ok(KnowledgeEntry {
+                id: row.get(0)?,
+                content: row.get(1)?,
+                created_at: row.get(2)?,
+                nominated: row.get::<_, i32>(3)? == 1,
+                tags: tags.map(|t| t.split('\u{1f}').map(String::from).collect()).unwrap_or_default(),
+                folder: row.get(4)?,
+                metadata: None,
+                source_session_id: None,
+                source_entry_ids: Vec::new(),
+            })
+        })
+        .optional()
+        .map_err(|e| format!("Row extraction failed: {}", e))?;
+        if let Some(entry) = entry.filter(|e| e.nominated || !nominated_only) {
+            entries.push(entry);
+        }
     }
 
-    // Sort by similarity
-    entries_with_scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-
-    let entries: Vec<KnowledgeEntry> = entries_with_scores
-        .into_iter()
-        .take(limit)
-        .map(|(entry, _)| entry)
-        .collect();
-
     Ok(entries)
 }
 
@@ -1538,16 +2989,25 @@ async fn chat_send_message_stream(
     session_id: String,
     message: String,
     context: String,
-    api_key: String,
-    model: String,
+    api_key: Option<String>, // Defaults to the key in settings
+    model: Option<String>,
 ) -> Result<String, String> {
     use tokio::spawn;
 
+    let configured = ai::settings(&app_handle.state::<Arc<AppState>>()).ok();
+    let api_key = api_key
+        .filter(|k| !k.is_empty())
+        .or_else(|| configured.as_ref().map(|ai| ai.api_key.clone()))
+        .ok_or_else(|| "No AI API key configured".to_string())?;
+    let model = model
+        .filter(|m| !m.is_empty())
+        .or_else(|| configured.as_ref().map(|ai| ai.model.clone()))
+        .unwrap_or_else(default_model);
+
     let message_id = uuid::Uuid::new_v4().to_string();
     let message_id_clone = message_id.clone();
     let session_id_clone = session_id.clone();
     let app_handle_clone = app_handle.clone();
-    let message_clone = message.clone();
 
     spawn(async move {
         // Use alt=sse for proper Server-Sent Events streaming
@@ -1563,15 +3023,11 @@ async fn chat_send_message_stream(
             format!("{}\n\nUser question: {}", context, message)
         };
 
-        println!("Chat request: model={}, message={}", model, message_clone);
+        // Sizes only: log files end up in diagnostics bundles
+        info!("Chat request {}: model={}, {} chars", message_id_clone, model, message.chars().count());
 
         // System instruction for meeting/interview assistant
-        let system_instruction = "You are a personal meeting/interview assistant. Your job is to help the user speak confidently. \
-            IMPORTANT: Generate responses in FIRST PERSON that the user can READ ALOUD or say directly. \
-            Example: If asked 'introduce yourself', respond with 'I'm a fullstack developer at...' NOT 'You are a developer...'. \
-            Use the knowledge base context to personalize responses with the user's actual background, skills, and experience. \
-            Keep responses concise and natural-sounding (2-4 sentences). \
-            Write as if you ARE the user speaking to others in a meeting or interview.";
+        let system_instruction = ai::ANSWER_SYSTEM_PROMPT;
 
         let response = match client
             .post(&url)
@@ -1592,7 +3048,7 @@ async fn chat_send_message_stream(
         {
             Ok(resp) => resp,
             Err(e) => {
-                eprintln!("Request error: {}", e);
+                error!("Request error: {}", e);
                 let _ = app_handle_clone.emit("chat-error", serde_json::json!({
                     "sessionId": session_id_clone,
                     "messageId": message_id_clone,
@@ -1604,11 +3060,11 @@ async fn chat_send_message_stream(
 
         // Check response status
         let status = response.status();
-        println!("Response status: {}", status);
+        info!("Response status: {}", status);
 
         if !status.is_success() {
             let error_text = response.text().await.unwrap_or_default();
-            eprintln!("API error response: {}", error_text);
+            error!("API error response: {}", error_text);
             let _ = app_handle_clone.emit("chat-error", serde_json::json!({
                 "sessionId": session_id_clone,
                 "messageId": message_id_clone,
@@ -1619,12 +3075,12 @@ async fn chat_send_message_stream(
 
         // Read the full response and process SSE events
         // Note: bytes_stream() may not work well with all server configurations
-        println!("Starting to read response body...");
+        debug!("Starting to read response body...");
 
         let response_bytes = match response.bytes().await {
             Ok(bytes) => bytes,
             Err(e) => {
-                eprintln!("Failed to read response bytes: {}", e);
+                error!("Failed to read response bytes: {}", e);
                 let _ = app_handle_clone.emit("chat-error", serde_json::json!({
                     "sessionId": session_id_clone,
                     "messageId": message_id_clone,
@@ -1637,24 +3093,26 @@ async fn chat_send_message_stream(
         let response_text = match String::from_utf8(response_bytes.to_vec()) {
             Ok(text) => text,
             Err(e) => {
-                eprintln!("Failed to decode response as UTF-8: {}", e);
+                error!("Failed to decode response as UTF-8: {}", e);
                 return;
             }
         };
 
-        println!("Response body length: {}", response_text.len());
-        println!("Response preview: {}", &response_text[..response_text.len().min(500)]);
+        debug!("Response body length: {}", response_text.len());
 
         // Normalize line endings
         let normalized = response_text.replace("\r\n", "\n").replace("\r", "\n");
 
+        // Full answer text, for readback once streaming completes
+        let mut full_text = String::new();
+
         // Process SSE events
         // Format: "data: {...}\n\n" or "data: {...}\ndata: {...}\n"
         for line in normalized.lines() {
             let line = line.trim();
             if line.starts_with("data: ") {
                 let json_str = &line[6..];
-                println!("Processing SSE line: {}", &json_str[..json_str.len().min(100)]);
+                debug!("Processing SSE line ({} bytes)", json_str.len());
 
                 if let Ok(json) = serde_json::from_str::<serde_json::Value>(json_str) {
                     // Extract text from Gemini response format
@@ -1664,7 +3122,8 @@ async fn chat_send_message_stream(
                                 if let Some(parts) = content.get("parts").and_then(|v| v.as_array()) {
                                     for part in parts {
                                         if let Some(text) = part.get("text").and_then(|v| v.as_str()) {
-                                            println!("Emitting text chunk: {}", text);
+                                            debug!("Emitting text chunk ({} chars)", text.chars().count());
+                                            full_text.push_str(text);
                                             let _ = app_handle_clone.emit("chat-chunk", serde_json::json!({
                                                 "sessionId": session_id_clone,
                                                 "messageId": message_id_clone,
@@ -1677,14 +3136,14 @@ async fn chat_send_message_stream(
                         }
                     }
                 } else {
-                    println!("Failed to parse JSON from line: {}", json_str);
+                    warn!("Failed to parse JSON from a {}-byte line", json_str.len());
                 }
             }
         }
 
         // If no SSE format detected, try parsing as JSON array (non-streaming format)
         if !normalized.contains("data: ") {
-            println!("No SSE format detected, trying JSON array format...");
+            info!("No SSE format detected, trying JSON array format...");
             if let Ok(json_array) = serde_json::from_str::<serde_json::Value>(&normalized) {
                 if let Some(array) = json_array.as_array() {
                     for chunk in array {
@@ -1694,7 +3153,8 @@ async fn chat_send_message_stream(
                                     if let Some(parts) = content.get("parts").and_then(|v| v.as_array()) {
                                         for part in parts {
                                             if let Some(text) = part.get("text").and_then(|v| v.as_str()) {
-                                                println!("Emitting text (array): {}", text);
+                                                debug!("Emitting text ({} chars, array)", text.chars().count());
+                                                full_text.push_str(text);
                                                 let _ = app_handle_clone.emit("chat-chunk", serde_json::json!({
                                                     "sessionId": session_id_clone,
                                                     "messageId": message_id_clone,
@@ -1711,8 +3171,10 @@ async fn chat_send_message_stream(
             }
         }
 
+        tts::speak_answer_if_enabled(&app_handle_clone, &full_text);
+
         // Emit completion event
-        println!("Emitting complete event");
+        debug!("Emitting complete event");
         let _ = app_handle_clone.emit("chat-complete", serde_json::json!({
             "sessionId": session_id_clone,
             "messageId": message_id_clone
@@ -1725,44 +3187,32 @@ async fn chat_send_message_stream(
 /// Get chat history from SQLite
 #[tauri::command]
 async fn chat_get_history(
+    state: tauri::State<'_, Arc<AppState>>,
     session_id: Option<String>,
     _since: Option<i64>,
     _limit: Option<usize>,
 ) -> Result<Vec<ChatHistoryEntry>, String> {
-    let conn = init_db().map_err(|e| format!("Failed to open database: {}", e))?;
+    let conn = state.db()?;
 
     let entries = if let Some(ref sid) = session_id {
-        let mut stmt = conn.prepare("SELECT id, timestamp, entry_type, content, metadata FROM chat_entries WHERE session_id = ? ORDER BY timestamp DESC")
+        let sql = format!(
+            "SELECT {} FROM chat_entries WHERE session_id = ? ORDER BY timestamp DESC",
+            database::CHAT_ENTRY_COLUMNS
+        );
+        let mut stmt = conn.prepare(&sql)
             .map_err(|e| format!("Prepare failed: {}", e))?;
 
-        let result = stmt.query_map(params![sid], |row| {
-            Ok(ChatHistoryEntry {
-                id: row.get(0)?,
-                timestamp: row.get(1)?,
-                entry_type: row.get(2)?,
-                content: row.get(3)?,
-                metadata: row.get::<_, Option<String>>(4)?
-                    .and_then(|s| serde_json::from_str(&s).ok()),
-            })
-        })
+        let result = stmt.query_map(params![sid], database::chat_entry_from_row)
         .map_err(|e| format!("Query failed: {}", e))?
         .collect::<Result<Vec<_>, _>>()
         .map_err(|e| e.to_string())?;
         result
     } else {
-        let mut stmt = conn.prepare("SELECT id, timestamp, entry_type, content, metadata FROM chat_entries ORDER BY timestamp DESC")
+        let sql = format!("SELECT {} FROM chat_entries ORDER BY timestamp DESC", database::CHAT_ENTRY_COLUMNS);
+        let mut stmt = conn.prepare(&sql)
             .map_err(|e| format!("Prepare failed: {}", e))?;
 
-        let result = stmt.query_map(params![], |row| {
-            Ok(ChatHistoryEntry {
-                id: row.get(0)?,
-                timestamp: row.get(1)?,
-                entry_type: row.get(2)?,
-                content: row.get(3)?,
-                metadata: row.get::<_, Option<String>>(4)?
-                    .and_then(|s| serde_json::from_str(&s).ok()),
-            })
-        })
+        let result = stmt.query_map(params![], database::chat_entry_from_row)
         .map_err(|e| format!("Query failed: {}", e))?
         .collect::<Result<Vec<_>, _>>()
         .map_err(|e| e.to_string())?;
@@ -1788,82 +3238,26 @@ async fn get_chat_context(
     query: Option<String>,
     api_key: Option<String>,
 ) -> Result<serde_json::Value, String> {
-    // 1. Get nominated knowledge entries
-    let knowledge_path = get_knowledge_path();
-    let knowledge_context = if knowledge_path.exists() {
-        let content = std::fs::read_to_string(&knowledge_path).map_err(|e| e.to_string())?;
-        let entries: Vec<KnowledgeEntry> = serde_json::from_str(&content).unwrap_or_default();
-        let nominated: Vec<&KnowledgeEntry> = entries.iter().filter(|e| e.nominated).collect();
-        if nominated.is_empty() {
-            String::new()
-        } else {
-            format!("=== User's Knowledge Base ===\n{}\n",
-                nominated.iter().map(|e| format!("- {}", e.content)).collect::<Vec<_>>().join("\n"))
-        }
-    } else {
-        String::new()
-    };
-
-    // 2. Get current transcript lines (drop lock before await)
-    let transcript_context = {
-        let transcript_lines = state.transcript_lines.lock().map_err(|e| e.to_string())?;
-        if transcript_lines.is_empty() {
-            String::new()
-        } else {
-            let recent_lines: Vec<String> = transcript_lines.iter().rev().take(20).cloned().collect::<Vec<_>>().into_iter().rev().collect();
-            format!("=== Current Conversation Transcript (Recent Lines) ===\n{}\n",
-                recent_lines.join("\n"))
-        }
-    };
-
-    // 4. Get meeting context if set (do this before await too)
-    let meeting_context = {
-        let settings = state.settings.lock().map_err(|e| e.to_string())?;
-        settings.ai.as_ref()
-            .and_then(|ai| ai.meeting_context.as_ref())
-            .map(|ctx| format!("=== Meeting Context ===\n{}\n", ctx))
-            .unwrap_or_default()
-    };
-
-    // 3. Get relevant history - use semantic search if query and api_key provided
-    let history_limit = limit.unwrap_or(10);
-    let history_context = if let (Some(q), Some(key)) = (&query, &api_key) {
-        // Try semantic search
-        match get_semantic_history_context(q, &key, history_limit).await {
-            Ok(ctx) => ctx,
-            Err(e) => {
-                println!("Semantic search failed, falling back to recent: {}", e);
-                get_recent_history_context(history_limit)?
-            }
-        }
-    } else {
-        get_recent_history_context(history_limit)?
-    };
-
-    // Combine all context
-    let full_context = format!("{}{}{}{}",
-        meeting_context,
-        knowledge_context,
-        transcript_context,
-        history_context
-    );
+    let api_key = api_key.filter(|k| !k.is_empty()).or_else(|| ai::settings(&state).ok().map(|ai| ai.api_key));
+    let opts = ai::ContextOptions { snapshots: false, history_limit: limit, ..Default::default() };
+    let context = ai::build_context(&state, query.as_deref(), api_key.as_deref(), &opts).await?;
 
     Ok(serde_json::json!({
-        "context": full_context,
-        "has_knowledge": !knowledge_context.is_empty(),
-        "has_transcript": !transcript_context.is_empty(),
-        "has_history": !history_context.is_empty(),
-        "has_meeting_context": !meeting_context.is_empty()
+        "context": context.text(),
+        "has_knowledge": !context.knowledge.is_empty(),
+        "has_transcript": !context.transcript.is_empty(),
+        "has_history": !context.history.is_empty(),
+        "has_meeting_context": !context.meeting.is_empty()
     }))
 }
 
-/// Get recent history context (fallback when no semantic search)
-fn get_recent_history_context(limit: usize) -> Result<String, String> {
-    let conn = init_db().map_err(|e| format!("Failed to open database: {}", e))?;
+/// Get recent history context (fallback when no semantic search), newest first
+fn get_recent_history_context(state: &AppState, limit: usize) -> Result<Vec<String>, String> {
+    let conn = state.reader()?;
 
     let mut stmt = conn.prepare(
         "SELECT content, entry_type FROM chat_entries
-         WHERE entry_type IN ('answer', 'summary')
+         WHERE entry_type IN ('answer', 'summary') AND compressed = 0 AND pinned = 0
          ORDER BY timestamp DESC
          LIMIT ?"
     ).map_err(|e| format!("Prepare failed: {}", e))?;
@@ -1875,71 +3269,48 @@ fn get_recent_history_context(limit: usize) -> Result<String, String> {
     .collect::<Result<Vec<_>, _>>()
     .map_err(|e| e.to_string())?;
 
-    if history_entries.is_empty() {
-        Ok(String::new())
-    } else {
-        Ok(format!("=== Recent AI Responses ===\n{}\n",
-            history_entries.iter()
-                .map(|(content, entry_type)| format!("[{}]: {}", entry_type, content))
-                .collect::<Vec<_>>()
-                .join("\n\n")))
-    }
+    Ok(history_entries.iter()
+        .map(|(content, entry_type)| format!("[{}]: {}", entry_type, content))
+        .collect())
 }
 
-/// Get semantically relevant history context using embeddings
-async fn get_semantic_history_context(query: &str, api_key: &str, limit: usize) -> Result<String, String> {
+/// Get semantically relevant history context using embeddings, most similar first
+async fn get_semantic_history_context(
+    state: &AppState,
+    query: &str,
+    api_key: &str,
+    limit: usize,
+) -> Result<Vec<String>, String> {
     // Generate embedding for query
     let embedding = generate_embedding(query, api_key).await?;
 
     // Search for similar entries
-    let conn = init_db().map_err(|e| format!("Failed to open database: {}", e))?;
+    let conn = state.reader()?;
+    let nearest = vector_index::nearest(&conn, vector_index::Indexed::Chat, &embedding, vector_index::oversample(limit))
+        .map_err(|e| format!("Vector search failed: {}", e))?;
 
     let mut stmt = conn.prepare(r#"
-        SELECT id, content, entry_type, embedding
+        SELECT content, entry_type
         FROM chat_entries
-        WHERE entry_type IN ('answer', 'summary', 'transcript') AND embedding IS NOT NULL
-        ORDER BY timestamp DESC
-        LIMIT 50
+        WHERE id = ?1 AND entry_type IN ('answer', 'summary', 'transcript')
+          AND compressed = 0 AND pinned = 0
     "#).map_err(|e| format!("Query failed: {}", e))?;
 
-    let mut entries_with_scores: Vec<(String, String, f32)> = Vec::new();
-
-    let rows = stmt.query_map([], |row| {
-        let content: String = row.get(1)?;
-        let entry_type: String = row.get(2)?;
-        let embedding_blob: Option<Vec<u8>> = row.get(3)?;
-        Ok((content, entry_type, embedding_blob))
-    }).map_err(|e| format!("Execute failed: {}", e))?;
-
-    for row_result in rows {
-        let (content, entry_type, embedding_blob) = row_result.map_err(|e| e.to_string())?;
-
-        let similarity = if let Some(blob) = embedding_blob {
-            let entry_embedding = database::blob_to_embedding(&blob);
-            cosine_similarity(&embedding, &entry_embedding)
-        } else {
-            0.0
-        };
-
-        if similarity > 0.3 { // Only include entries with decent similarity
-            entries_with_scores.push((content, entry_type, similarity));
+    let mut items = Vec::new();
+    for (id, score) in nearest {
+        // Only include entries with decent similarity
+        if items.len() >= limit || score <= 0.3 {
+            break;
+        }
+        let row = stmt
+            .query_row(params![id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+            .optional()
+            .map_err(|e| e.to_string())?;
+        if let Some((content, entry_type)) = row {
+            items.push(format!("[{} ({:.0}%)]: {}", entry_type, score * 100.0, content));
         }
     }
-
-    // Sort by similarity
-    entries_with_scores.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(std::cmp::Ordering::Equal));
-
-    let top_entries: Vec<_> = entries_with_scores.into_iter().take(limit).collect();
-
-    if top_entries.is_empty() {
-        Ok(String::new())
-    } else {
-        Ok(format!("=== Relevant Context (Semantic) ===\n{}\n",
-            top_entries.iter()
-                .map(|(content, entry_type, score)| format!("[{} ({:.0}%)]: {}", entry_type, score * 100.0, content))
-                .collect::<Vec<_>>()
-                .join("\n\n")))
-    }
+    Ok(items)
 }
 
 /// Helper to generate embedding
@@ -1977,42 +3348,257 @@ async fn generate_embedding(text: &str, api_key: &str) -> Result<Vec<f32>, Strin
     Ok(embedding)
 }
 
-fn load_settings() -> Settings {
-    let path = get_settings_path();
-    if path.exists() {
-        if let Ok(content) = std::fs::read_to_string(&path) {
-            if let Ok(settings) = serde_json::from_str(&content) {
-                return settings;
-            }
-        }
-    }
-    Settings::default()
+fn load_settings() -> (Settings, Vec<settings_check::SettingsWarning>) {
+    settings_check::load(&get_settings_path())
+}
+
+/// Write the in-memory JSON stores shortly after they change
+fn start_store_flusher(app_handle: &AppHandle) {
+    let state = app_handle.state::<Arc<AppState>>().inner().clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::join!(
+            state.knowledge.flush_on_change(),
+            state.ideas.flush_on_change(),
+            state.snapshots.flush_on_change(),
+            state.correction_rules.flush_on_change(),
+            state.templates.flush_on_change(),
+        );
+    });
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let settings = load_settings();
+    let context = tauri::generate_context!();
+
+    // Before anything touches the data dir or the engine: a second launch
+    // only hands over to the running instance, which the single-instance
+    // plugin does (and exits) while the app is built
+    let _instance_lock = match persist::lock_instance() {
+        Ok(Some(lock)) => Some(lock),
+        Ok(None) => {
+            let handed_over = tauri::Builder::default()
+                .plugin(tauri_plugin_single_instance::init(|_, _, _| {}))
+                .build(context);
+            if let Err(e) = handed_over {
+                eprintln!("Zigy is already running: {}", e);
+            }
+            return;
+        }
+        Err(e) => {
+            eprintln!("Running without the instance lock: {}", e);
+            None
+        }
+    };
+
+    let restored = backup::apply_pending_restore();
+    let (settings, settings_warnings) = load_settings();
+    logging::init(&settings.log_level);
+    match restored {
+        Ok(true) => info!("Restored data from a backup"),
+        Ok(false) => {}
+        Err(e) => error!("{}", e),
+    }
+    if let Some(dir) = portable::data_dir() {
+        info!("Portable mode, storing data in {}", dir.display());
+    }
+    for w in &settings_warnings {
+        warn!("Settings {}: {}", w.field, w.message);
+    }
+    process_cleanup::kill_stale_child();
+
+    let mut db = encryption::open_db()
+        .or_else(|e| {
+            error!("Failed to open database, falling back to in-memory storage: {}", e);
+            database::init_memory_db()
+        })
+        .expect("failed to initialize database");
+    if !encryption::is_locked() && !database::is_json_migrated(&db) {
+        if let Err(e) = migrate_json_once(&mut db) {
+            error!("{}", e);
+        }
+    }
 
     let state = Arc::new(AppState {
         process: Mutex::new(None),
+        monitor_process: Mutex::new(None),
         settings: Mutex::new(settings),
-        transcript_lines: Mutex::new(Vec::new()),
+        settings_warnings: Mutex::new(settings_warnings),
+        transcript_lines: Mutex::new(transcript::Transcript::default()),
+        process_stderr: Mutex::new(VecDeque::new()),
+        recent_events: Mutex::new(VecDeque::new()),
+        sleep_inhibitor: Mutex::new(None),
+        db: Mutex::new(db),
+        readers: db_pool::ReadPool::default(),
+        finals: caption_batch::CaptionBatch::default(),
+        session_id: Mutex::new(None),
+        meeting_context: Mutex::new(None),
+        metrics: Mutex::new(metrics::Metrics::default()),
+        stopped_exit: Mutex::new(None),
+        generation: AtomicU64::new(0),
+        run: AtomicU64::new(0),
+        last_caption_at: AtomicI64::new(0),
+        paused: AtomicBool::new(false),
+        knowledge: persist::JsonStore::load(get_knowledge_path()),
+        ideas: persist::JsonStore::load(get_ideas_path()),
+        snapshots: persist::JsonStore::load(get_context_snapshots_path()),
+        correction_rules: persist::JsonStore::load(get_correction_rules_path()),
+        templates: persist::JsonStore::load(templates::get_templates_path()),
+        template: Mutex::new(None),
     });
 
     let state_clone = state.clone();
+    process_cleanup::install(state.clone());
 
     tauri::Builder::default()
+        // Must come first: a second launch hands over to the running instance
+        // instead of opening the same stores again
+        .plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
+            info!("Another instance was started, focusing this one");
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.unminimize();
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+        }))
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_autostart::init(
+            tauri_plugin_autostart::MacosLauncher::LaunchAgent,
+            Some(vec![autostart::LOGIN_ARG]),
+        ))
+        .plugin(
+            tauri_plugin_global_shortcut::Builder::new()
+                .with_handler(hotkeys::on_shortcut)
+                .build(),
+        )
         .manage(state)
+        .manage(notifications::NotificationQueue::default())
+        .manage(overlay::OverlayState::default())
+        .manage(recovery::RecoveryState::default())
+        .manage(tts::TtsState::default())
+        .manage(server::ServerState::default())
+        .manage(obs::ObsState::default())
+        .manage(mqtt::MqttState::default())
+        .manage(embeddings::EmbeddingState::default())
+        .manage(ai::AiRequests::default())
+        .manage(translation::TranslationState::default())
+        .manage(hotkeys::HotkeyState::default())
+        .manage(models::ModelDownloads::default())
+        .manage(transcription_queue::TranscriptionQueue::default())
+        .manage(vocabulary::VocabularyState::default())
+        .manage(corrections::CompiledRules::default())
+        .manage(redaction::RedactionState::default())
+        .manage(calendar::CalendarState::default())
+        .manage(meeting_detection::DetectionState::default())
+        .setup(|app| {
+            // The main window is built here rather than from the config so a
+            // portable install can keep its WebView data in the data folder
+            if let Some(config) = app.config().app.windows.iter().find(|w| w.label == "main").cloned() {
+                let mut main_window = tauri::WebviewWindowBuilder::from_config(app.handle(), &config)?;
+                if let Some(dir) = portable::webview_dir() {
+                    main_window = main_window.data_directory(dir);
+                }
+                main_window.build()?;
+            }
+            persist::init(app.handle());
+            // Check for a crashed previous run before the heartbeat replaces its lock
+            if let Some(recovered) = recovery::detect_unclean_shutdown() {
+                recovery::set_pending(app.handle(), recovered);
+            }
+            recovery::start_heartbeat(app.handle().clone());
+            overlay::start_monitor_watcher(app.handle().clone());
+            updater::start_update_checker(app.handle().clone());
+            server::start_if_enabled(app.handle().clone());
+            mqtt::start_if_enabled(app.handle());
+            embeddings::start(app.handle().clone());
+            transcription_queue::start(app.handle().clone());
+            encryption::start(app.handle().clone());
+            sync::start(app.handle().clone());
+            db_backups::start(app.handle().clone());
+            caption_batch::start(app.handle().clone());
+            calendar::start(app.handle().clone());
+            meeting_detection::start(app.handle().clone());
+            translation::start(app.handle().clone());
+            obs::start_writer(app.handle().clone());
+            hotkeys::start(app.handle());
+            if let Err(e) = autostart::apply_settings(app.handle()) {
+                warn!("{}", e);
+            }
+            start_store_flusher(app.handle());
+
+            // Enforce the retention policy once per launch
+            let cleanup_state = app.state::<Arc<AppState>>().inner().clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                if let Err(e) = cleanup_storage_internal(&cleanup_state, false) {
+                    warn!("{}", e);
+                }
+            });
+            Ok(())
+        })
+        .on_page_load(|webview, payload| {
+            if webview.label() == "main" && payload.event() == tauri::webview::PageLoadEvent::Finished {
+                recovery::emit_if_pending(webview.app_handle());
+                autostart::on_main_window_ready(webview.app_handle());
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             start_captions,
             stop_captions,
+            switch_model,
             is_running,
+            pause_captions,
+            resume_captions,
+            get_process_logs,
+            replay_recent_events,
+            audio_devices::list_audio_devices,
+            models::list_available_models,
+            models::list_local_models,
+            models::download_model,
+            models::delete_model,
             get_settings,
             save_settings,
             export_captions,
+            export_session,
+            import::import_transcript,
+            transcribe::retranscribe_session,
+            transcribe::get_transcript_versions,
+            transcription_queue::enqueue_files,
+            transcription_queue::get_queue_status,
+            transcription_queue::cancel_job,
+            tasks::extract_action_items,
+            tasks::get_tasks,
+            tasks::add_task,
+            tasks::update_task,
+            tasks::delete_task,
+            tasks::export_tasks_markdown,
+            entities::analyze_session,
+            entities::get_session_tags,
+            entities::list_tags,
+            entities::get_sessions_by_tag,
+            vocabulary::get_session_vocabulary,
+            vocabulary::set_session_vocabulary,
+            corrections::get_correction_rules,
+            corrections::add_correction_rule,
+            corrections::update_correction_rule,
+            corrections::delete_correction_rule,
+            corrections::save_correction_rules,
+            redaction::get_unredacted_captions,
+            encryption::is_storage_locked,
+            encryption::unlock_storage,
+            encryption::set_encryption_passphrase,
+            backup::create_backup,
+            backup::restore_backup,
+            db_backups::list_backups,
+            db_backups::restore_from_backup,
+            sync::sync_now,
+            obsidian::export_to_obsidian,
+            notion::push_session_to_notion,
+            notion::get_session_sync_status,
+            calendar::get_upcoming_meetings,
+            metrics::get_live_metrics,
+            metrics::get_metrics_history,
             select_model_file,
             check_binary_exists,
             check_microphone_permission,
@@ -2023,6 +3609,13 @@ pub fn run() {
             add_transcript_line,
             update_last_transcript_line,
             clear_transcript,
+            transcript::get_transcript_lines,
+            transcript::edit_transcript_line,
+            transcript::delete_transcript_line,
+            transcript::merge_lines,
+            bookmarks::add_bookmark,
+            bookmarks::get_session_bookmarks,
+            bookmarks::delete_bookmark,
             update_transcript,
             get_knowledge,
             save_knowledge,
@@ -2030,13 +3623,35 @@ pub fn run() {
             update_knowledge_entry,
             toggle_knowledge_nomination,
             delete_knowledge_entry,
+            knowledge::get_knowledge_by_tag,
+            knowledge::set_knowledge_tags,
+            knowledge::rename_tag,
+            knowledge::get_knowledge_folders,
+            knowledge::create_knowledge_folder,
+            knowledge::rename_knowledge_folder,
+            knowledge::delete_knowledge_folder,
+            knowledge::move_knowledge_entry,
+            knowledge::find_duplicate_knowledge,
+            knowledge::merge_knowledge_entries,
+            knowledge_import::import_knowledge_from_file,
+            knowledge_import::import_knowledge_from_url,
             get_ideas,
             add_idea,
             update_idea,
             delete_idea,
+            get_ideas_for_session,
+            idea_versions::get_idea_versions,
+            idea_versions::restore_idea_version,
+            idea_export::export_idea,
+            trash::list_trashed,
+            trash::restore_entry,
+            trash::purge_trash,
             // Chat history commands
             get_chat_history,
             add_chat_entry,
+            add_chat_entries_batch,
+            toggle_pin_entry,
+            get_pinned_entries,
             clear_chat_history,
             get_chat_history_stats,
             // Context snapshot commands
@@ -2044,28 +3659,109 @@ pub fn run() {
             get_latest_snapshot,
             get_all_snapshots,
             clear_context_snapshots,
+            // Session commands
+            get_sessions,
+            get_current_session,
+            get_session_transcript,
+            get_caption_words,
+            cleanup_storage,
+            vacuum_database,
+            rename_session,
             // NEW: Database and chat commands
             init_database,
+            migrate_storage,
             vector_generate_embedding,
             vector_search,
             search_knowledge_semantic,
+            search_text,
             chat_send_message_stream,
             chat_get_history,
             create_session,
             get_chat_context,
+            ai::ai_summarize,
+            ai::ai_answer,
+            ai::ai_translate,
+            ai::ai_answer_stream,
+            ai::ai_translate_stream,
+            ai::gemini_request,
+            ai::cancel_ai_request,
+            ai::compress_context,
+            ai::build_ai_context,
+            ai::retrieve_knowledge,
+            // Notification commands
+            notifications::send_notification,
+            notifications::get_notification_state,
+            // Overlay commands
+            overlay::open_overlay_window,
+            overlay::close_overlay_window,
+            overlay::set_overlay_click_through,
+            overlay::list_displays,
+            overlay::move_overlay_to_display,
+            overlay::move_overlay_to_next_display,
+            // Crash recovery commands
+            recovery::get_recovery_info,
+            recovery::restore_recovered_session,
+            recovery::recover_last_session,
+            recovery::discard_recovered_session,
+            // Logging commands
+            logging::set_log_level,
+            logging::get_recent_logs,
+            logging::get_log_tail,
+            // Diagnostics commands
+            diagnostics::create_diagnostics_bundle,
+            diagnostics::run_diagnostics,
+            // Update commands
+            updater::check_for_updates,
+            updater::download_update,
+            // Text-to-speech commands
+            tts::speak,
+            tts::stop_speaking,
+            tts::list_voices,
+            // Caption server commands
+            server::get_server_status,
+            server::regenerate_server_token,
+            // OBS commands
+            obs::set_obs_session_settings,
+            obs::get_obs_sources,
+            // Webhook commands
+            webhooks::test_webhook,
+            webhooks::get_webhook_deliveries,
+            // Viewer commands
+            viewer::get_viewer_qr,
+            // Integration commands
+            integrations::share_summary,
+            integrations::post_summary_to_slack,
+            // MQTT commands
+            mqtt::get_mqtt_status,
+            // Embedding commands
+            embeddings::get_embedding_queue_status,
+            vector_index::rebuild_vector_index,
+            analytics::get_session_analytics,
+            topics::get_topic_timeline,
+            templates::get_templates,
+            templates::save_template,
+            templates::delete_template,
+            profiles::list_profiles,
+            profiles::switch_profile,
+            profiles::duplicate_profile,
+            settings_check::validate_settings,
+            settings_update::update_setting,
         ])
-        .on_window_event(move |_window, event| {
+        .on_window_event(move |window, event| {
+            if window.label() == overlay::OVERLAY_LABEL {
+                if let tauri::WindowEvent::Moved(_) | tauri::WindowEvent::Resized(_) = event {
+                    overlay::remember_placement(window);
+                }
+                return;
+            }
+
             if let tauri::WindowEvent::Destroyed = event {
                 // Kill the zig process when the window is closed
-                if let Ok(mut process_guard) = state_clone.process.lock() {
-                    if let Some(mut child) = process_guard.take() {
-                        println!("Cleaning up zig-april-captions process on exit...");
-                        let _ = child.kill();
-                        let _ = child.wait();
-                    }
-                }
+                process_cleanup::kill_child(&state_clone);
+                state_clone.flush_stores();
+                recovery::mark_clean_shutdown();
             }
         })
-        .run(tauri::generate_context!())
+        .run(context)
         .expect("error while running tauri application");
 }
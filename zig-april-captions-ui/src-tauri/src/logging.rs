@@ -0,0 +1,156 @@
+// Structured logging via `tracing`
+//
+// Events go to three places: stdout (for `tauri dev`), a daily-rotated file
+// under `<config>/zigy/logs/`, and an in-memory ring buffer backing the in-app
+// log viewer. The level can be changed at runtime with `set_log_level`.
+// `get_log_tail` reads the files instead of the buffer, so it also covers
+// earlier runs, such as one that crashed.
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, OnceLock};
+use tracing::{info, warn};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{fmt, reload, Registry};
+
+use crate::{get_config_dir, AppState};
+
+/// Number of formatted lines kept in memory for `get_recent_logs`
+const RECENT_LOG_CAPACITY: usize = 2000;
+
+/// Number of rotated daily log files kept on disk
+const MAX_LOG_FILES: usize = 7;
+
+static RECENT_LOGS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+static LEVEL_HANDLE: OnceLock<reload::Handle<LevelFilter, Registry>> = OnceLock::new();
+static FILE_GUARD: OnceLock<WorkerGuard> = OnceLock::new();
+
+pub fn get_log_dir() -> std::path::PathBuf {
+    let dir = get_config_dir().join("logs");
+    std::fs::create_dir_all(&dir).ok();
+    dir
+}
+
+/// Writer that appends formatted events to the in-memory ring buffer
+#[derive(Clone, Copy)]
+struct RingBufferWriter;
+
+impl std::io::Write for RingBufferWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        if let Ok(mut lines) = RECENT_LOGS.lock() {
+            for line in text.lines().filter(|l| !l.is_empty()) {
+                if lines.len() >= RECENT_LOG_CAPACITY {
+                    lines.pop_front();
+                }
+                lines.push_back(line.to_string());
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for RingBufferWriter {
+    type Writer = RingBufferWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        *self
+    }
+}
+
+/// Install the global subscriber. Call once, before anything logs.
+pub fn init(level: &str) {
+    let initial = level.parse::<LevelFilter>().unwrap_or(LevelFilter::INFO);
+    let (filter, handle) = reload::Layer::new(initial);
+
+    let file_layer = match RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix("zigy")
+        .filename_suffix("log")
+        .max_log_files(MAX_LOG_FILES)
+        .build(get_log_dir())
+    {
+        Ok(appender) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            let _ = FILE_GUARD.set(guard);
+            Some(fmt::layer().with_ansi(false).with_writer(writer))
+        }
+        Err(e) => {
+            eprintln!("Failed to create log file appender: {}", e);
+            None
+        }
+    };
+
+    let result = tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer())
+        .with(file_layer)
+        .with(fmt::layer().with_ansi(false).with_writer(RingBufferWriter))
+        .try_init();
+
+    if result.is_ok() {
+        let _ = LEVEL_HANDLE.set(handle);
+        info!("Logging initialized at level {}, writing to {}", initial, get_log_dir().display());
+    }
+}
+
+/// Change the log level at runtime and remember it in settings
+#[tauri::command]
+pub async fn set_log_level(state: tauri::State<'_, Arc<AppState>>, level: String) -> Result<(), String> {
+    let filter = level
+        .parse::<LevelFilter>()
+        .map_err(|_| format!("Invalid log level: {}", level))?;
+
+    let handle = LEVEL_HANDLE
+        .get()
+        .ok_or_else(|| "Logging is not initialized".to_string())?;
+    handle.reload(filter).map_err(|e| e.to_string())?;
+    warn!("Log level changed to {}", filter);
+
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.log_level = filter.to_string().to_lowercase();
+    crate::persist::persist_json(&crate::get_settings_path(), &*settings)
+        .map_err(|e| format!("Failed to save settings: {}", e))?;
+    Ok(())
+}
+
+/// Last `lines` lines of the log files on disk, oldest first
+#[tauri::command]
+pub async fn get_log_tail(lines: usize) -> Result<Vec<String>, String> {
+    let entries = std::fs::read_dir(get_log_dir()).map_err(|e| format!("Failed to read log directory: {}", e))?;
+    // Daily files are named zigy.YYYY-MM-DD.log, so name order is date order
+    let mut files: Vec<std::path::PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().and_then(|e| e.to_str()) == Some("log"))
+        .collect();
+    files.sort();
+
+    let mut tail: VecDeque<String> = VecDeque::new();
+    for path in files.iter().rev() {
+        if tail.len() >= lines {
+            break;
+        }
+        let content = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let text = String::from_utf8_lossy(&content);
+        let wanted = lines - tail.len();
+        for line in text.lines().rev().filter(|l| !l.is_empty()).take(wanted) {
+            tail.push_front(line.to_string());
+        }
+    }
+    Ok(tail.into())
+}
+
+/// Get the last `n` formatted log lines (oldest first)
+#[tauri::command]
+pub async fn get_recent_logs(n: usize) -> Result<Vec<String>, String> {
+    let lines = RECENT_LOGS.lock().map_err(|e| e.to_string())?;
+    let skip = lines.len().saturating_sub(n);
+    Ok(lines.iter().skip(skip).cloned().collect())
+}
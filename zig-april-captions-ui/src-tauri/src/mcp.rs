@@ -0,0 +1,236 @@
+// Model Context Protocol endpoint
+//
+// Lets agent clients (Claude Desktop, IDE assistants, ...) use meeting history
+// as context. Served by the embedded server at `POST /mcp` using the MCP
+// Streamable HTTP transport in its plain JSON-response form, behind the same
+// bearer token as the rest of the server. Disabled unless `mcp_enabled` is
+// set in the server settings.
+//
+// Tools:     search_transcripts, get_current_transcript, get_knowledge,
+//            add_knowledge_entry, list_sessions, get_session_summary,
+//            get_session_summaries
+// Resources: zigy://transcript/current, zigy://knowledge, zigy://summaries
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tauri::Manager;
+
+use crate::server::ServerContext;
+use crate::{export, tasks, AppState};
+
+const SUPPORTED_PROTOCOL_VERSIONS: [&str; 3] = ["2025-06-18", "2025-03-26", "2024-11-05"];
+
+// JSON-RPC error codes
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+fn tool_definitions() -> Value {
+    json!([
+        {
+            "name": "search_transcripts",
+            "description": "Case-insensitive search over the live meeting transcript and past AI chat history.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "Text to look for" },
+                    "limit": { "type": "integer", "description": "Maximum matches per source (default 50)" }
+                },
+                "required": ["query"]
+            }
+        },
+        {
+            "name": "get_current_transcript",
+            "description": "Full transcript of the current captioning session, one line per utterance.",
+            "inputSchema": { "type": "object", "properties": {} }
+        },
+        {
+            "name": "get_knowledge",
+            "description": "User-curated knowledge entries (background facts, glossary, project notes).",
+            "inputSchema": { "type": "object", "properties": {} }
+        },
+        {
+            "name": "add_knowledge_entry",
+            "description": "Save a fact or note to the user's knowledge base so it is used as context in later meetings.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "content": { "type": "string", "description": "The note to save" }
+                },
+                "required": ["content"]
+            }
+        },
+        {
+            "name": "list_sessions",
+            "description": "Past captioning sessions with title, start and end time, transcript line count and chat message count.",
+            "inputSchema": { "type": "object", "properties": {} }
+        },
+        {
+            "name": "get_session_summary",
+            "description": "Title, time, summary and action items of one past session (ids come from list_sessions).",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "session_id": { "type": "string", "description": "Session id" }
+                },
+                "required": ["session_id"]
+            }
+        },
+        {
+            "name": "get_session_summaries",
+            "description": "AI-generated summaries of earlier conversation, newest first.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "limit": { "type": "integer", "description": "Maximum summaries to return (default all)" }
+                }
+            }
+        }
+    ])
+}
+
+fn resource_definitions() -> Value {
+    json!([
+        { "uri": "zigy://transcript/current", "name": "Current transcript", "mimeType": "text/plain" },
+        { "uri": "zigy://knowledge", "name": "Knowledge base", "mimeType": "application/json" },
+        { "uri": "zigy://summaries", "name": "Session summaries", "mimeType": "application/json" }
+    ])
+}
+
+fn session_summary(ctx: &ServerContext, session_id: &str) -> Result<Value, String> {
+    let state = ctx.app_handle.state::<Arc<AppState>>();
+    let conn = state.db()?;
+    let notes = export::load_session_notes(&conn, session_id)?;
+    let action_items: Vec<Value> = tasks::query_tasks(&conn, Some(session_id), None)?
+        .into_iter()
+        .map(|task| json!({ "text": task.text, "assignee": task.assignee, "status": task.status }))
+        .collect();
+    Ok(json!({
+        "id": notes.session.id,
+        "title": notes.session.title,
+        "started_at": notes.session.started_at,
+        "ended_at": notes.session.ended_at,
+        "speakers": notes.speakers(),
+        "summary": notes.summary,
+        "action_items": action_items,
+        "transcript_lines": notes.transcript.len(),
+    }))
+}
+
+async fn call_tool(ctx: &ServerContext, name: &str, args: &Value) -> Result<String, String> {
+    let limit = args["limit"].as_u64().map(|l| l as usize);
+    let value = match name {
+        "search_transcripts" => {
+            let query = args["query"].as_str().filter(|q| !q.trim().is_empty()).ok_or("query is required")?;
+            crate::api::search_all(&ctx.app_handle, query, limit)?
+        }
+        "get_current_transcript" => return Ok(crate::api::transcript_lines(&ctx.app_handle)?.join("\n")),
+        "get_knowledge" => json!(crate::get_knowledge(ctx.app_handle.state()).await?),
+        "add_knowledge_entry" => {
+            let content = args["content"]
+                .as_str()
+                .map(str::trim)
+                .filter(|c| !c.is_empty())
+                .ok_or("content is required")?;
+            let app_handle = ctx.app_handle.clone();
+            let entry =
+                crate::add_knowledge_entry(app_handle.clone(), app_handle.state(), content.to_string(), None, None).await?;
+            json!(entry)
+        }
+        "list_sessions" => json!(crate::api::list_sessions(&ctx.app_handle)?),
+        "get_session_summary" => {
+            let session_id = args["session_id"].as_str().ok_or("session_id is required")?;
+            session_summary(ctx, session_id)?
+        }
+        "get_session_summaries" => {
+            let summaries = crate::get_all_snapshots(ctx.app_handle.state()).await?;
+            json!(summaries.into_iter().take(limit.unwrap_or(usize::MAX)).collect::<Vec<_>>())
+        }
+        _ => return Err(format!("Unknown tool: {}", name)),
+    };
+    serde_json::to_string_pretty(&value).map_err(|e| e.to_string())
+}
+
+async fn read_resource(ctx: &ServerContext, uri: &str) -> Result<Value, String> {
+    let (mime, text) = match uri {
+        "zigy://transcript/current" => ("text/plain", crate::api::transcript_lines(&ctx.app_handle)?.join("\n")),
+        "zigy://knowledge" => ("application/json", call_tool(ctx, "get_knowledge", &json!({})).await?),
+        "zigy://summaries" => ("application/json", call_tool(ctx, "get_session_summaries", &json!({})).await?),
+        _ => return Err(format!("Unknown resource: {}", uri)),
+    };
+    Ok(json!({ "contents": [{ "uri": uri, "mimeType": mime, "text": text }] }))
+}
+
+/// Handle one JSON-RPC message; notifications (no id) produce no response
+async fn handle_message(ctx: &ServerContext, message: &Value) -> Option<Value> {
+    let id = message.get("id")?.clone();
+    let method = message["method"].as_str().unwrap_or_default();
+    let params = &message["params"];
+
+    let result: Result<Value, (i64, String)> = match method {
+        "initialize" => {
+            let requested = params["protocolVersion"].as_str().unwrap_or_default();
+            let version = SUPPORTED_PROTOCOL_VERSIONS
+                .iter()
+                .find(|v| **v == requested)
+                .unwrap_or(&SUPPORTED_PROTOCOL_VERSIONS[0]);
+            Ok(json!({
+                "protocolVersion": version,
+                "capabilities": { "tools": {}, "resources": {} },
+                "serverInfo": {
+                    "name": "zigy",
+                    "version": ctx.app_handle.package_info().version.to_string()
+                },
+                "instructions": "Meeting transcripts, knowledge notes and summaries recorded by Zigy."
+            }))
+        }
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": tool_definitions() })),
+        "tools/call" => {
+            let name = params["name"].as_str().unwrap_or_default();
+            // Tool failures are reported in the result so the model can see them
+            Ok(match call_tool(ctx, name, &params["arguments"]).await {
+                Ok(text) => json!({ "content": [{ "type": "text", "text": text }], "isError": false }),
+                Err(e) => json!({ "content": [{ "type": "text", "text": e }], "isError": true }),
+            })
+        }
+        "resources/list" => Ok(json!({ "resources": resource_definitions() })),
+        "resources/read" => {
+            let uri = params["uri"].as_str().unwrap_or_default();
+            read_resource(ctx, uri).await.map_err(|e| (INVALID_PARAMS, e))
+        }
+        _ => Err((METHOD_NOT_FOUND, format!("Method not found: {}", method))),
+    };
+
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } }),
+    })
+}
+
+/// `POST /mcp`: a single JSON-RPC message or a batch
+pub async fn mcp_handler(State(ctx): State<ServerContext>, Json(body): Json<Value>) -> Response {
+    if !crate::server::current_settings(&ctx.app_handle).mcp_enabled {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    let response = match &body {
+        Value::Array(messages) => {
+            let mut responses = Vec::new();
+            for message in messages {
+                if let Some(response) = handle_message(&ctx, message).await {
+                    responses.push(response);
+                }
+            }
+            (!responses.is_empty()).then_some(Value::Array(responses))
+        }
+        message => handle_message(&ctx, message).await,
+    };
+
+    match response {
+        Some(response) => Json(response).into_response(),
+        None => StatusCode::ACCEPTED.into_response(),
+    }
+}
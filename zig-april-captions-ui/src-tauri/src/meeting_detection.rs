@@ -0,0 +1,226 @@
+// Conferencing app detection
+//
+// Polls for a call starting and emits `meeting-detected` so the UI can ask
+// "Start captions?", or starts captions itself when `auto_start` is set.
+//
+// On Linux a call is an app recording from the microphone: PulseAudio (and
+// PipeWire's PulseAudio server) lists it under `pactl list source-outputs`,
+// which catches desktop clients and browser tabs (Meet, Jitsi) alike. Other
+// platforms only see running processes, so there it works for apps whose
+// process exists just for the call, like Zoom's `CptHost`.
+//
+// Each app is announced once per call; it can be announced again after it
+// has stopped recording. Nothing is announced while captions are running.
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{debug, info, warn};
+
+use crate::AppState;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Process or stream names (lowercase substrings) and the app they belong to
+#[cfg(target_os = "linux")]
+const KNOWN_APPS: &[(&str, &str)] = &[
+    ("zoom", "Zoom"),
+    ("teams", "Microsoft Teams"),
+    ("webex", "Webex"),
+    ("slack", "Slack"),
+    ("discord", "Discord"),
+    ("skype", "Skype"),
+    ("chrome", "Browser call"),
+    ("chromium", "Browser call"),
+    ("firefox", "Browser call"),
+    ("brave", "Browser call"),
+    ("msedge", "Browser call"),
+];
+
+#[cfg(not(target_os = "linux"))]
+const KNOWN_APPS: &[(&str, &str)] = &[
+    ("cpthost", "Zoom"),
+    ("webexmeetings", "Webex"),
+    ("ms-teams_modulehost", "Microsoft Teams"),
+];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MeetingDetectionSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub auto_start: bool, // Start captions instead of asking
+    #[serde(default)]
+    pub extra_apps: Vec<String>, // More process names to treat as calls
+}
+
+/// Managed state: apps seen in a call at the last poll
+#[derive(Default)]
+pub struct DetectionState {
+    active: Mutex<HashSet<String>>,
+}
+
+fn detection_settings(app_handle: &AppHandle) -> Option<MeetingDetectionSettings> {
+    app_handle
+        .state::<Arc<AppState>>()
+        .settings
+        .lock()
+        .ok()
+        .and_then(|s| s.meeting_detection.clone())
+        .filter(|d| d.enabled)
+}
+
+/// The app a process or stream name belongs to, if it is a conferencing app
+fn match_app(name: &str, extra_apps: &[String]) -> Option<String> {
+    let name = name.to_lowercase();
+    if name.is_empty() || name.contains("zig-april-captions") {
+        return None;
+    }
+    if let Some(extra) = extra_apps
+        .iter()
+        .map(|a| a.trim())
+        .find(|a| !a.is_empty() && name.contains(&a.to_lowercase()))
+    {
+        return Some(extra.to_string());
+    }
+    KNOWN_APPS
+        .iter()
+        .find(|(pattern, _)| name.contains(pattern))
+        .map(|(_, app)| app.to_string())
+}
+
+/// Binaries of the streams in `pactl list source-outputs`
+#[cfg(target_os = "linux")]
+fn recording_clients(listing: &str) -> Vec<String> {
+    listing
+        .lines()
+        .map(str::trim)
+        .filter_map(|line| {
+            line.strip_prefix("application.process.binary = ")
+                .or_else(|| line.strip_prefix("application.name = "))
+        })
+        .map(|value| value.trim_matches('"').to_string())
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+fn candidates() -> Result<Vec<String>, String> {
+    let listing = crate::audio_devices::pactl(&["list", "source-outputs"])?;
+    Ok(recording_clients(&listing))
+}
+
+#[cfg(target_os = "macos")]
+fn candidates() -> Result<Vec<String>, String> {
+    let output = std::process::Command::new("ps")
+        .args(["-axco", "comm"])
+        .output()
+        .map_err(|e| format!("Failed to list processes: {}", e))?;
+    Ok(String::from_utf8_lossy(&output.stdout).lines().map(|l| l.trim().to_string()).collect())
+}
+
+#[cfg(target_os = "windows")]
+fn candidates() -> Result<Vec<String>, String> {
+    let output = std::process::Command::new("tasklist")
+        .args(["/fo", "csv", "/nh"])
+        .output()
+        .map_err(|e| format!("Failed to list processes: {}", e))?;
+    // "CptHost.exe","1234",...
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|l| l.split(',').next())
+        .map(|name| name.trim_matches('"').trim_end_matches(".exe").to_string())
+        .collect())
+}
+
+fn detect(settings: &MeetingDetectionSettings) -> Result<HashSet<String>, String> {
+    Ok(candidates()?
+        .iter()
+        .filter_map(|name| match_app(name, &settings.extra_apps))
+        .collect())
+}
+
+async fn poll(app_handle: &AppHandle, settings: &MeetingDetectionSettings) {
+    let detected = match tauri::async_runtime::spawn_blocking({
+        let settings = settings.clone();
+        move || detect(&settings)
+    })
+    .await
+    {
+        Ok(Ok(detected)) => detected,
+        Ok(Err(e)) => {
+            debug!("Meeting detection failed: {}", e);
+            return;
+        }
+        Err(e) => {
+            debug!("Meeting detection failed: {}", e);
+            return;
+        }
+    };
+
+    let started: Vec<String> = {
+        let state = app_handle.state::<DetectionState>();
+        let Ok(mut active) = state.active.lock() else {
+            return;
+        };
+        let started = detected.difference(&active).cloned().collect();
+        *active = detected;
+        started
+    };
+    let state = app_handle.state::<Arc<AppState>>();
+    if started.is_empty() || crate::caption_state(&state) != "stopped" {
+        return;
+    }
+
+    let app = started.join(", ");
+    info!("Call detected in {}", app);
+    let mut auto_started = false;
+    if settings.auto_start {
+        match crate::start_with_saved_settings(app_handle).await {
+            Ok(()) => auto_started = true,
+            Err(e) => warn!("Could not start captions for the call in {}: {}", app, e),
+        }
+    }
+    let _ = app_handle.emit(
+        "meeting-detected",
+        serde_json::json!({ "app": app, "autoStarted": auto_started }),
+    );
+}
+
+/// Watch for calls while detection is enabled
+pub fn start(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match detection_settings(&app_handle) {
+                Some(settings) => poll(&app_handle, &settings).await,
+                None => {
+                    if let Ok(mut active) = app_handle.state::<DetectionState>().active.lock() {
+                        active.clear();
+                    }
+                }
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_app() {
+        let extra = vec!["jitsi".to_string()];
+        assert_eq!(match_app("Jitsi Meet", &extra).as_deref(), Some("jitsi"));
+        assert_eq!(match_app("zig-april-captions", &extra), None);
+        assert_eq!(match_app("vim", &extra), None);
+        #[cfg(target_os = "linux")]
+        {
+            let listing = "Source Output #42\n\tProperties:\n\t\tapplication.name = \"ZOOM VoiceEngine\"\n\
+                           \t\tapplication.process.binary = \"zoom\"\n";
+            let clients = recording_clients(listing);
+            assert_eq!(clients, vec!["ZOOM VoiceEngine", "zoom"]);
+            assert_eq!(match_app(&clients[1], &[]).as_deref(), Some("Zoom"));
+        }
+    }
+}
@@ -0,0 +1,277 @@
+// Caption latency and throughput metrics
+//
+// Counted live for the current session, so users can tell whether their
+// model and hardware keep up:
+//   - latency: receipt time minus the end of the caption's last spoken word
+//     (the caption timestamp when there is no word timing), over the last
+//     LATENCY_WINDOW final captions
+//   - words per minute over the session and over the last RATE_WINDOW_MS
+//   - partial and final caption counts and their ratio
+//   - dropped lines: recognizer output that couldn't be parsed, and captions
+//     discarded while paused
+//
+// A snapshot is stored in the `metrics` table at most every SAMPLE_INTERVAL_MS
+// while captions arrive, and when the session ends.
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::{now_millis, AppState};
+
+/// Final captions whose latency is kept for the average and percentile
+const LATENCY_WINDOW: usize = 200;
+
+/// Window of the recent words-per-minute rate
+const RATE_WINDOW_MS: i64 = 60_000;
+
+const SAMPLE_INTERVAL_MS: i64 = 60_000;
+
+/// Counters of the current session; lives in `AppState`
+#[derive(Debug, Default)]
+pub struct Metrics {
+    started_at: Option<i64>,
+    partials: u64,
+    finals: u64,
+    words: u64,
+    unparsed_lines: u64,
+    paused_captions: u64,
+    latencies: VecDeque<i64>,
+    max_latency: Option<i64>,
+    recent_words: VecDeque<(i64, u64)>, // (received at, words) of recent finals
+    last_sample_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsSnapshot {
+    pub session_id: Option<String>,
+    pub recorded_at: i64,
+    pub elapsed_ms: i64,
+    pub partials: u64,
+    pub finals: u64,
+    pub partials_per_final: Option<f64>,
+    pub words: u64,
+    pub words_per_minute: f64,
+    pub recent_words_per_minute: f64,
+    pub avg_latency_ms: Option<f64>,
+    pub p95_latency_ms: Option<i64>,
+    pub max_latency_ms: Option<i64>,
+    pub dropped_lines: u64,
+}
+
+impl Metrics {
+    fn on_caption(&mut self, is_final: bool, spoken_at: Option<i64>, text: &str, now: i64) {
+        self.started_at.get_or_insert(now);
+        if !is_final {
+            self.partials += 1;
+            return;
+        }
+        self.finals += 1;
+        let words = text.split_whitespace().count() as u64;
+        self.words += words;
+        self.recent_words.push_back((now, words));
+        while self.recent_words.front().map(|(t, _)| now - t > RATE_WINDOW_MS).unwrap_or(false) {
+            self.recent_words.pop_front();
+        }
+        if let Some(spoken_at) = spoken_at {
+            let latency = (now - spoken_at).max(0);
+            if self.latencies.len() >= LATENCY_WINDOW {
+                self.latencies.pop_front();
+            }
+            self.latencies.push_back(latency);
+            self.max_latency = Some(self.max_latency.map(|m| m.max(latency)).unwrap_or(latency));
+        }
+    }
+
+    fn snapshot(&self, session_id: Option<String>, now: i64) -> MetricsSnapshot {
+        let elapsed_ms = self.started_at.map(|t| (now - t).max(0)).unwrap_or(0);
+        let recent: u64 = self
+            .recent_words
+            .iter()
+            .filter(|(t, _)| now - t <= RATE_WINDOW_MS)
+            .map(|(_, w)| w)
+            .sum();
+        let mut sorted: Vec<i64> = self.latencies.iter().copied().collect();
+        sorted.sort();
+        MetricsSnapshot {
+            session_id,
+            recorded_at: now,
+            elapsed_ms,
+            partials: self.partials,
+            finals: self.finals,
+            partials_per_final: (self.finals > 0).then(|| self.partials as f64 / self.finals as f64),
+            words: self.words,
+            words_per_minute: if elapsed_ms > 0 {
+                self.words as f64 * 60_000.0 / elapsed_ms as f64
+            } else {
+                0.0
+            },
+            recent_words_per_minute: recent as f64 * 60_000.0 / RATE_WINDOW_MS.min(elapsed_ms.max(1)) as f64,
+            avg_latency_ms: (!sorted.is_empty()).then(|| sorted.iter().sum::<i64>() as f64 / sorted.len() as f64),
+            p95_latency_ms: sorted.get((sorted.len() * 95).div_ceil(100).saturating_sub(1)).copied(),
+            max_latency_ms: self.max_latency,
+            dropped_lines: self.unparsed_lines + self.paused_captions,
+        }
+    }
+}
+
+fn session_id(state: &AppState) -> Option<String> {
+    state.session_id.lock().ok().and_then(|id| id.clone())
+}
+
+fn save(state: &AppState, snapshot: &MetricsSnapshot) {
+    let result = state
+        .db()
+        .and_then(|conn| insert_snapshot(&conn, snapshot).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        warn!("Failed to record caption metrics: {}", e);
+    }
+}
+
+/// Start counting for a new session
+pub fn reset(state: &AppState) {
+    if let Ok(mut metrics) = state.metrics.lock() {
+        *metrics = Metrics { last_sample_at: now_millis(), ..Metrics::default() };
+    }
+}
+
+/// Count a caption as it arrives; stores a snapshot when one is due.
+/// `spoken_at` is when the audio of its last word ended.
+pub fn on_caption(state: &AppState, is_final: bool, spoken_at: Option<i64>, text: &str) {
+    let now = now_millis();
+    let due = {
+        let Ok(mut metrics) = state.metrics.lock() else {
+            return;
+        };
+        metrics.on_caption(is_final, spoken_at, text, now);
+        let due = is_final && now - metrics.last_sample_at >= SAMPLE_INTERVAL_MS;
+        if due {
+            metrics.last_sample_at = now;
+        }
+        due.then(|| metrics.snapshot(session_id(state), now))
+    };
+    if let Some(snapshot) = due {
+        save(state, &snapshot);
+    }
+}
+
+/// Recognizer output that wasn't a valid event
+pub fn on_unparsed_line(state: &AppState) {
+    if let Ok(mut metrics) = state.metrics.lock() {
+        metrics.unparsed_lines += 1;
+    }
+}
+
+/// A caption discarded because captions are paused
+pub fn on_paused_caption(state: &AppState) {
+    if let Ok(mut metrics) = state.metrics.lock() {
+        metrics.paused_captions += 1;
+    }
+}
+
+/// Store the final numbers of a session that is ending
+pub fn finish(state: &AppState, session_id: &str) {
+    let snapshot = {
+        let Ok(metrics) = state.metrics.lock() else {
+            return;
+        };
+        if metrics.started_at.is_none() {
+            return;
+        }
+        metrics.snapshot(Some(session_id.to_string()), now_millis())
+    };
+    save(state, &snapshot);
+}
+
+fn insert_snapshot(conn: &Connection, snapshot: &MetricsSnapshot) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO metrics (session_id, recorded_at, elapsed_ms, partials, finals, words, words_per_minute,
+                              avg_latency_ms, p95_latency_ms, max_latency_ms, dropped_lines)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            snapshot.session_id,
+            snapshot.recorded_at,
+            snapshot.elapsed_ms,
+            snapshot.partials as i64,
+            snapshot.finals as i64,
+            snapshot.words as i64,
+            snapshot.words_per_minute,
+            snapshot.avg_latency_ms,
+            snapshot.p95_latency_ms,
+            snapshot.max_latency_ms,
+            snapshot.dropped_lines as i64
+        ],
+    )?;
+    Ok(())
+}
+
+/// Numbers for the current session
+#[tauri::command]
+pub async fn get_live_metrics(state: tauri::State<'_, Arc<AppState>>) -> Result<MetricsSnapshot, String> {
+    let metrics = state.metrics.lock().map_err(|e| e.to_string())?;
+    Ok(metrics.snapshot(session_id(&state), now_millis()))
+}
+
+/// Stored snapshots, oldest first; all sessions when `session_id` is None
+#[tauri::command]
+pub async fn get_metrics_history(
+    state: tauri::State<'_, Arc<AppState>>,
+    session_id: Option<String>,
+    limit: Option<i64>,
+) -> Result<Vec<MetricsSnapshot>, String> {
+    let conn = state.db()?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT session_id, recorded_at, elapsed_ms, partials, finals, words, words_per_minute,
+                    avg_latency_ms, p95_latency_ms, max_latency_ms, dropped_lines
+             FROM (SELECT * FROM metrics WHERE ?1 IS NULL OR session_id = ?1 ORDER BY recorded_at DESC LIMIT ?2)
+             ORDER BY recorded_at ASC",
+        )
+        .map_err(|e| format!("Prepare failed: {}", e))?;
+    let rows = stmt
+        .query_map(params![session_id, limit.unwrap_or(500)], |row| {
+            let partials = row.get::<_, i64>(3)? as u64;
+            let finals = row.get::<_, i64>(4)? as u64;
+            Ok(MetricsSnapshot {
+                session_id: row.get(0)?,
+                recorded_at: row.get(1)?,
+                elapsed_ms: row.get(2)?,
+                partials,
+                finals,
+                partials_per_final: (finals > 0).then(|| partials as f64 / finals as f64),
+                words: row.get::<_, i64>(5)? as u64,
+                words_per_minute: row.get(6)?,
+                recent_words_per_minute: 0.0,
+                avg_latency_ms: row.get(7)?,
+                p95_latency_ms: row.get(8)?,
+                max_latency_ms: row.get(9)?,
+                dropped_lines: row.get::<_, i64>(10)? as u64,
+            })
+        })
+        .map_err(|e| format!("Query failed: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot() {
+        let mut metrics = Metrics::default();
+        metrics.on_caption(false, None, "hello", 0);
+        metrics.on_caption(false, None, "hello there", 100);
+        metrics.on_caption(true, Some(0), "hello there everyone", 300);
+        metrics.on_caption(true, Some(29_500), "next line", 30_000);
+        let snapshot = metrics.snapshot(None, 60_000);
+        assert_eq!((snapshot.partials, snapshot.finals, snapshot.words), (2, 2, 5));
+        assert_eq!(snapshot.partials_per_final, Some(1.0));
+        assert_eq!(snapshot.words_per_minute, 5.0);
+        assert_eq!(snapshot.avg_latency_ms, Some(400.0));
+        assert_eq!(snapshot.p95_latency_ms, Some(500));
+        assert_eq!(snapshot.max_latency_ms, Some(500));
+    }
+}
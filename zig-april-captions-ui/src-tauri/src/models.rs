@@ -0,0 +1,253 @@
+// Speech model manager
+//
+// Lists the April models published in the project's `models.json` manifest,
+// downloads them into `<config>/models/<id>.april` after checking them against
+// the manifest's SHA-256 digest (models without one can't be downloaded),
+// and manages the models already on disk. Downloads emit
+// `model-download-progress` { id, downloaded, total, percent }.
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter};
+use tracing::info;
+
+use crate::{get_config_dir, AppState};
+
+const MANIFEST_URL: &str = "https://raw.githubusercontent.com/minhtranin/zigy/main/models.json";
+
+const MODEL_EXTENSION: &str = "april";
+
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    models: Vec<ModelInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelInfo {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub language: Option<String>,
+    pub url: String,
+    #[serde(default)]
+    pub size: Option<u64>,
+    #[serde(default)]
+    pub sha256: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub installed: bool, // Filled in locally, not part of the manifest
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalModel {
+    pub id: String,
+    pub path: String,
+    pub size: u64,
+    pub in_use: bool, // Selected as `model_path` in settings
+}
+
+/// Model ids currently being downloaded
+#[derive(Default)]
+pub struct ModelDownloads {
+    active: Mutex<HashSet<String>>,
+}
+
+fn get_models_dir() -> PathBuf {
+    let dir = get_config_dir().join("models");
+    std::fs::create_dir_all(&dir).ok();
+    dir
+}
+
+/// Ids become file names, so only allow a conservative character set
+fn validate_id(id: &str) -> Result<(), String> {
+    let valid = !id.is_empty()
+        && !id.starts_with('.')
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid model id: {}", id))
+    }
+}
+
+fn model_path(id: &str) -> Result<PathBuf, String> {
+    validate_id(id)?;
+    Ok(get_models_dir().join(format!("{}.{}", id, MODEL_EXTENSION)))
+}
+
+async fn fetch_manifest() -> Result<Vec<ModelInfo>, String> {
+    let response = reqwest::Client::new()
+        .get(MANIFEST_URL)
+        .header("User-Agent", "zigy-model-manager")
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Model manifest unavailable: {}", response.status()));
+    }
+    let manifest = response
+        .json::<Manifest>()
+        .await
+        .map_err(|e| format!("Failed to parse model manifest: {}", e))?;
+    Ok(manifest.models)
+}
+
+/// Models published in the manifest, flagged when already downloaded
+#[tauri::command]
+pub async fn list_available_models() -> Result<Vec<ModelInfo>, String> {
+    let mut models = fetch_manifest().await?;
+    for model in &mut models {
+        model.installed = model_path(&model.id).map(|p| p.exists()).unwrap_or(false);
+    }
+    Ok(models)
+}
+
+/// Models in the config dir's models folder
+#[tauri::command]
+pub async fn list_local_models(state: tauri::State<'_, Arc<AppState>>) -> Result<Vec<LocalModel>, String> {
+    let selected = state.settings.lock().map_err(|e| e.to_string())?.model_path.clone();
+    let entries = std::fs::read_dir(get_models_dir()).map_err(|e| e.to_string())?;
+
+    let mut models: Vec<LocalModel> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|e| e.to_str()) == Some(MODEL_EXTENSION))
+        .filter_map(|path| {
+            let id = path.file_stem()?.to_string_lossy().to_string();
+            let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            let path = path.to_string_lossy().to_string();
+            Some(LocalModel {
+                in_use: path == selected,
+                id,
+                path,
+                size,
+            })
+        })
+        .collect();
+    models.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(models)
+}
+
+/// Download a model from the manifest and return its path. The file only
+/// appears under its final name once the checksum has been verified.
+#[tauri::command]
+pub async fn download_model(
+    app_handle: AppHandle,
+    downloads: tauri::State<'_, ModelDownloads>,
+    id: String,
+) -> Result<String, String> {
+    let dest = model_path(&id)?;
+    let model = fetch_manifest()
+        .await?
+        .into_iter()
+        .find(|m| m.id == id)
+        .ok_or_else(|| format!("Model {} not found in the manifest", id))?;
+    let Some(expected) = model.sha256.as_ref().map(|h| h.trim().to_lowercase()) else {
+        return Err(format!("Model {} has no published checksum", id));
+    };
+
+    if !downloads.active.lock().map_err(|e| e.to_string())?.insert(id.clone()) {
+        return Err(format!("Model {} is already downloading", id));
+    }
+    let partial = dest.with_extension(format!("{}.part", MODEL_EXTENSION));
+    let result = match fetch_to_file(&app_handle, &model, &partial).await {
+        Ok(actual) if actual == expected => {
+            info!("Checksum verified for {}", model.id);
+            std::fs::rename(&partial, &dest).map_err(|e| e.to_string())
+        }
+        Ok(actual) => Err(format!("Checksum mismatch for {}: expected {}, got {}", model.id, expected, actual)),
+        Err(e) => Err(e),
+    };
+    if result.is_err() {
+        let _ = std::fs::remove_file(&partial);
+    }
+    if let Ok(mut active) = downloads.active.lock() {
+        active.remove(&id);
+    }
+    result?;
+
+    info!("Downloaded model {} to {}", id, dest.display());
+    Ok(dest.to_string_lossy().to_string())
+}
+
+/// Download the model into `partial`; returns its SHA-256 digest
+async fn fetch_to_file(app_handle: &AppHandle, model: &ModelInfo, partial: &std::path::Path) -> Result<String, String> {
+    let response = reqwest::Client::new()
+        .get(&model.url)
+        .header("User-Agent", "zigy-model-manager")
+        .send()
+        .await
+        .map_err(|e| format!("Download failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Download failed: {}", response.status()));
+    }
+
+    let total = response.content_length().or(model.size).unwrap_or(0);
+    let mut file = std::fs::File::create(partial).map_err(|e| e.to_string())?;
+    let mut hasher = Sha256::new();
+    let mut downloaded: u64 = 0;
+    let mut last_percent = 0;
+    let mut stream = response.bytes_stream();
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| format!("Download interrupted: {}", e))?;
+        file.write_all(&chunk).map_err(|e| e.to_string())?;
+        hasher.update(&chunk);
+        downloaded += chunk.len() as u64;
+
+        let percent = (downloaded * 100).checked_div(total).unwrap_or(0);
+        if percent != last_percent {
+            last_percent = percent;
+            let _ = app_handle.emit(
+                "model-download-progress",
+                serde_json::json!({
+                    "id": model.id,
+                    "downloaded": downloaded,
+                    "total": total,
+                    "percent": percent
+                }),
+            );
+        }
+    }
+    file.sync_all().map_err(|e| e.to_string())?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Delete a downloaded model. The model captions are currently running with
+/// can't be removed.
+#[tauri::command]
+pub async fn delete_model(state: tauri::State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    let path = model_path(&id)?;
+    if !path.exists() {
+        return Err(format!("Model {} is not installed", id));
+    }
+
+    let selected = state.settings.lock().map_err(|e| e.to_string())?.model_path.clone();
+    if selected == path.to_string_lossy() && crate::caption_state(&state) != "stopped" {
+        return Err("Stop captions before deleting the model in use".to_string());
+    }
+
+    std::fs::remove_file(&path).map_err(|e| format!("Failed to delete model: {}", e))?;
+    info!("Deleted model {}", id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_id() {
+        assert!(validate_id("april-english-dev-01110").is_ok());
+        assert!(validate_id("model_v1.2").is_ok());
+        assert!(validate_id("").is_err());
+        assert!(validate_id("../settings").is_err());
+        assert!(validate_id("a/b").is_err());
+        assert!(validate_id(".hidden").is_err());
+    }
+}
@@ -0,0 +1,304 @@
+// MQTT publishing
+//
+// Publishes caption and session events to a broker so home-automation setups
+// and LED ticker displays can react to live speech. Topics (under the
+// configured prefix, default "zigy"):
+//
+//   <prefix>/status           "online" / "offline" (retained, offline is the last will)
+//   <prefix>/session/state    { "state": "started" | "stopped", "timestamp": ms } (retained)
+//   <prefix>/caption/final    { "text": "...", "source": "...", "timestamp": ms }
+//   <prefix>/keyword          { "text": "...", "keywords": ["..."], "timestamp": ms }
+//
+// Use an `mqtts://` broker URL (or set `tls`) for encrypted connections.
+use rumqttc::{AsyncClient, Event, LastWill, MqttOptions, Packet, QoS, Transport};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tracing::{debug, info, warn};
+
+use crate::AppState;
+
+/// Wait between reconnect attempts after the broker connection drops
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Outgoing messages buffered while the connection is down
+const REQUEST_CAPACITY: usize = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MqttSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_broker_url")]
+    pub broker_url: String, // mqtt://host:1883 or mqtts://host:8883
+    #[serde(default = "default_topic_prefix")]
+    pub topic_prefix: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub tls: bool, // Force TLS even for mqtt:// URLs
+    #[serde(default)]
+    pub keywords: Vec<String>, // Published to <prefix>/keyword when heard
+}
+
+fn default_broker_url() -> String {
+    "mqtt://localhost:1883".to_string()
+}
+
+fn default_topic_prefix() -> String {
+    "zigy".to_string()
+}
+
+impl Default for MqttSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            broker_url: default_broker_url(),
+            topic_prefix: default_topic_prefix(),
+            username: None,
+            password: None,
+            tls: false,
+            keywords: Vec::new(),
+        }
+    }
+}
+
+struct Connection {
+    settings: MqttSettings,
+    client: AsyncClient,
+    task: tauri::async_runtime::JoinHandle<()>,
+}
+
+/// Managed state: the live broker connection, if any
+#[derive(Default)]
+pub struct MqttState {
+    connection: Mutex<Option<Connection>>,
+    connected: Arc<AtomicBool>,
+}
+
+fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
+fn topic(settings: &MqttSettings, suffix: &str) -> String {
+    format!("{}/{}", settings.topic_prefix.trim_end_matches('/'), suffix)
+}
+
+/// Host, port and whether TLS is implied, from "mqtt://host[:port]" or
+/// "mqtts://host[:port]"; the port defaults to 1883, or 8883 for mqtts
+fn parse_broker_url(url: &str) -> Result<(String, u16, bool), String> {
+    let url = url.trim();
+    let (rest, tls) = if let Some(rest) = url.strip_prefix("mqtts://") {
+        (rest, true)
+    } else if let Some(rest) = url.strip_prefix("mqtt://") {
+        (rest, false)
+    } else {
+        return Err(format!("Invalid broker URL: {} (expected mqtt:// or mqtts://)", url));
+    };
+    let authority = rest.split(['/', '?']).next().unwrap_or("");
+    let default_port = if tls { 8883 } else { 1883 };
+
+    // IPv6 addresses are bracketed: [::1]:1883
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(bracketed) => {
+            let (host, after) = bracketed
+                .split_once(']')
+                .ok_or_else(|| format!("Invalid broker URL: {}", url))?;
+            (host, after.strip_prefix(':'))
+        }
+        None => match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+    if host.is_empty() {
+        return Err(format!("Invalid broker URL: {} (missing host)", url));
+    }
+    let port = match port {
+        Some(port) => port
+            .parse::<u16>()
+            .map_err(|_| format!("Invalid broker URL: {} (bad port)", url))?,
+        None => default_port,
+    };
+    Ok((host.to_string(), port, tls))
+}
+
+fn build_options(settings: &MqttSettings) -> Result<MqttOptions, String> {
+    let client_id = format!("zigy-{}", &uuid::Uuid::new_v4().simple().to_string()[..8]);
+    let (host, port, tls) = parse_broker_url(&settings.broker_url)?;
+    let mut options = MqttOptions::new(client_id, host, port);
+
+    if tls || settings.tls {
+        options.set_transport(Transport::tls_with_default_config());
+    }
+    if let Some(username) = settings.username.as_ref().filter(|u| !u.is_empty()) {
+        options.set_credentials(username, settings.password.clone().unwrap_or_default());
+    }
+    options.set_keep_alive(Duration::from_secs(30));
+    options.set_last_will(LastWill::new(topic(settings, "status"), "offline", QoS::AtLeastOnce, true));
+    Ok(options)
+}
+
+fn disconnect(mqtt: &MqttState) {
+    if let Ok(mut connection) = mqtt.connection.lock() {
+        if let Some(connection) = connection.take() {
+            let _ = connection.client.try_publish(
+                topic(&connection.settings, "status"),
+                QoS::AtLeastOnce,
+                true,
+                "offline",
+            );
+            let _ = connection.client.try_disconnect();
+            connection.task.abort();
+            info!("MQTT disconnected");
+        }
+    }
+    mqtt.connected.store(false, Ordering::SeqCst);
+}
+
+fn connect(mqtt: &MqttState, settings: MqttSettings) -> Result<(), String> {
+    let options = build_options(&settings)?;
+    let (client, mut eventloop) = AsyncClient::new(options, REQUEST_CAPACITY);
+
+    let connected = mqtt.connected.clone();
+    let status_client = client.clone();
+    let status_topic = topic(&settings, "status");
+    let broker = settings.broker_url.clone();
+    let task = tauri::async_runtime::spawn(async move {
+        loop {
+            match eventloop.poll().await {
+                Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                    info!("MQTT connected to {}", broker);
+                    connected.store(true, Ordering::SeqCst);
+                    let _ = status_client.try_publish(status_topic.as_str(), QoS::AtLeastOnce, true, "online");
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    if connected.swap(false, Ordering::SeqCst) {
+                        warn!("MQTT connection lost: {}", e);
+                    } else {
+                        debug!("MQTT connect failed: {}", e);
+                    }
+                    // The next poll reconnects
+                    tokio::time::sleep(RECONNECT_DELAY).await;
+                }
+            }
+        }
+    });
+
+    *mqtt.connection.lock().map_err(|e| e.to_string())? = Some(Connection { settings, client, task });
+    Ok(())
+}
+
+/// Connect, disconnect or reconnect to match the saved settings
+pub fn apply_settings(app_handle: &AppHandle) -> Result<(), String> {
+    let settings = app_handle
+        .state::<Arc<AppState>>()
+        .settings
+        .lock()
+        .map_err(|e| e.to_string())?
+        .mqtt
+        .clone()
+        .unwrap_or_default();
+    let mqtt = app_handle.state::<MqttState>();
+
+    let unchanged = mqtt
+        .connection
+        .lock()
+        .map(|c| c.as_ref().map(|c| c.settings == settings).unwrap_or(false))
+        .unwrap_or(false);
+    if unchanged {
+        return Ok(());
+    }
+
+    disconnect(&mqtt);
+    if settings.enabled {
+        connect(&mqtt, settings)?;
+    }
+    Ok(())
+}
+
+/// Connect at launch if MQTT was left enabled
+pub fn start_if_enabled(app_handle: &AppHandle) {
+    if let Err(e) = apply_settings(app_handle) {
+        warn!("MQTT not started: {}", e);
+    }
+}
+
+fn publish(app_handle: &AppHandle, suffix: &str, retain: bool, payload: serde_json::Value) {
+    let mqtt = app_handle.state::<MqttState>();
+    let Ok(connection) = mqtt.connection.lock() else {
+        return;
+    };
+    if let Some(connection) = connection.as_ref() {
+        let topic = topic(&connection.settings, suffix);
+        if let Err(e) = connection
+            .client
+            .try_publish(topic.as_str(), QoS::AtLeastOnce, retain, payload.to_string())
+        {
+            debug!("MQTT publish to {} dropped: {}", topic, e);
+        }
+    }
+}
+
+/// Publish a final caption, plus a keyword hit if it mentions a watched word
+pub fn on_final_caption(app_handle: &AppHandle, text: &str, source: Option<&str>) {
+    let keywords = {
+        let mqtt = app_handle.state::<MqttState>();
+        let Ok(connection) = mqtt.connection.lock() else {
+            return;
+        };
+        match connection.as_ref() {
+            Some(connection) => connection.settings.keywords.clone(),
+            None => return,
+        }
+    };
+
+    let timestamp = now_millis();
+    publish(
+        app_handle,
+        "caption/final",
+        false,
+        serde_json::json!({ "text": text, "source": source, "timestamp": timestamp }),
+    );
+
+    let lower = text.to_lowercase();
+    let hits: Vec<&String> = keywords
+        .iter()
+        .filter(|k| !k.trim().is_empty() && lower.contains(&k.trim().to_lowercase()))
+        .collect();
+    if !hits.is_empty() {
+        publish(
+            app_handle,
+            "keyword",
+            false,
+            serde_json::json!({ "text": text, "keywords": hits, "timestamp": timestamp }),
+        );
+    }
+}
+
+/// Publish the retained session state ("started" / "stopped")
+pub fn publish_session_state(app_handle: &AppHandle, state: &str) {
+    publish(
+        app_handle,
+        "session/state",
+        true,
+        serde_json::json!({ "state": state, "timestamp": now_millis() }),
+    );
+}
+
+#[tauri::command]
+pub async fn get_mqtt_status(mqtt: tauri::State<'_, MqttState>) -> Result<serde_json::Value, String> {
+    let connection = mqtt.connection.lock().map_err(|e| e.to_string())?;
+    Ok(serde_json::json!({
+        "enabled": connection.is_some(),
+        "connected": mqtt.connected.load(Ordering::SeqCst),
+        "broker_url": connection.as_ref().map(|c| c.settings.broker_url.clone()),
+    }))
+}
@@ -0,0 +1,245 @@
+// Desktop notifications with Do-Not-Disturb awareness
+//
+// Alerts raised while the OS is in Do-Not-Disturb / Focus / presentation mode
+// are queued instead of shown, and flushed by a watcher thread once DND ends.
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+use tracing::{error, info};
+
+use crate::AppState;
+
+/// Maximum number of alerts kept while DND is active (oldest are dropped)
+const MAX_QUEUED: usize = 50;
+
+/// How often the watcher re-checks the DND state while alerts are queued
+const DND_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingNotification {
+    pub title: String,
+    pub body: String,
+    pub created_at: i64,
+}
+
+/// Managed state holding alerts suppressed during DND
+#[derive(Default)]
+pub struct NotificationQueue {
+    pending: Mutex<VecDeque<PendingNotification>>,
+    watcher_running: AtomicBool,
+}
+
+/// Show a notification, or queue it if the OS is in Do-Not-Disturb mode
+pub fn notify(app_handle: &AppHandle, title: &str, body: &str) {
+    let respect_dnd = app_handle
+        .state::<Arc<AppState>>()
+        .settings
+        .lock()
+        .map(|s| s.respect_dnd)
+        .unwrap_or(true);
+
+    if respect_dnd && is_dnd_active() {
+        info!("DND active, queueing notification: {}", title);
+        let queue = app_handle.state::<NotificationQueue>();
+        if let Ok(mut pending) = queue.pending.lock() {
+            if pending.len() >= MAX_QUEUED {
+                pending.pop_front();
+            }
+            pending.push_back(PendingNotification {
+                title: title.to_string(),
+                body: body.to_string(),
+                created_at: std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_millis() as i64,
+            });
+        }
+        start_dnd_watcher(app_handle);
+        return;
+    }
+
+    show(app_handle, title, body);
+}
+
+fn show(app_handle: &AppHandle, title: &str, body: &str) {
+    if let Err(e) = app_handle
+        .notification()
+        .builder()
+        .title(title)
+        .body(body)
+        .show()
+    {
+        error!("Failed to show notification: {}", e);
+    }
+}
+
+/// Spawn (once) a thread that waits for DND to end and then flushes the queue
+fn start_dnd_watcher(app_handle: &AppHandle) {
+    let queue = app_handle.state::<NotificationQueue>();
+    if queue.watcher_running.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let app_handle = app_handle.clone();
+    std::thread::spawn(move || {
+        loop {
+            std::thread::sleep(DND_POLL_INTERVAL);
+            if !is_dnd_active() {
+                break;
+            }
+        }
+
+        // The flag is cleared under the lock, so an alert queued after the
+        // drain finds it cleared and starts a new watcher
+        let queue = app_handle.state::<NotificationQueue>();
+        let pending: Vec<PendingNotification> = {
+            let mut pending = queue.pending.lock().unwrap_or_else(|e| e.into_inner());
+            queue.watcher_running.store(false, Ordering::SeqCst);
+            pending.drain(..).collect()
+        };
+
+        info!("DND ended, delivering {} queued notification(s)", pending.len());
+        match pending.len() {
+            0 => {}
+            1 => show(&app_handle, &pending[0].title, &pending[0].body),
+            n => {
+                // Collapse a backlog into one summary instead of a burst of popups
+                let body = pending
+                    .iter()
+                    .map(|p| format!("- {}", p.title))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                show(&app_handle, &format!("{} alerts while Do Not Disturb was on", n), &body);
+            }
+        }
+        let _ = app_handle.emit("notifications-flushed", &pending);
+    });
+}
+
+/// Best-effort check of the OS Do-Not-Disturb / Focus / presentation state
+pub fn is_dnd_active() -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        // GNOME: banners are disabled while DND is on
+        if let Ok(output) = std::process::Command::new("gsettings")
+            .args(["get", "org.gnome.desktop.notifications", "show-banners"])
+            .output()
+        {
+            if String::from_utf8_lossy(&output.stdout).trim() == "false" {
+                return true;
+            }
+        }
+
+        // KDE Plasma / other freedesktop servers expose an Inhibited property
+        if let Ok(output) = std::process::Command::new("gdbus")
+            .args([
+                "call",
+                "--session",
+                "--dest",
+                "org.freedesktop.Notifications",
+                "--object-path",
+                "/org/freedesktop/Notifications",
+                "--method",
+                "org.freedesktop.DBus.Properties.Get",
+                "org.freedesktop.Notifications",
+                "Inhibited",
+            ])
+            .output()
+        {
+            if String::from_utf8_lossy(&output.stdout).contains("true") {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        // macOS 12+: Focus modes are recorded as assertions in this file
+        if let Some(home) = dirs::home_dir() {
+            let assertions = home.join("Library/DoNotDisturb/DB/Assertions.json");
+            if let Ok(content) = std::fs::read_to_string(&assertions) {
+                if let Ok(json) = serde_json::from_str::<serde_json::Value>(&content) {
+                    let active = json["data"]
+                        .as_array()
+                        .map(|data| {
+                            data.iter().any(|d| {
+                                d["storeAssertionRecords"]
+                                    .as_array()
+                                    .map(|r| !r.is_empty())
+                                    .unwrap_or(false)
+                            })
+                        })
+                        .unwrap_or(false);
+                    if active {
+                        return true;
+                    }
+                }
+            }
+        }
+
+        // macOS 11 and older
+        if let Ok(output) = std::process::Command::new("defaults")
+            .args(["-currentHost", "read", "com.apple.notificationcenterui", "doNotDisturb"])
+            .output()
+        {
+            if String::from_utf8_lossy(&output.stdout).trim() == "1" {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        // SHQueryUserNotificationState covers Focus Assist, full-screen apps
+        // and presentation mode in one call
+        #[link(name = "shell32")]
+        extern "system" {
+            fn SHQueryUserNotificationState(pquns: *mut i32) -> i32;
+        }
+
+        const QUNS_BUSY: i32 = 2;
+        const QUNS_RUNNING_D3D_FULL_SCREEN: i32 = 3;
+        const QUNS_PRESENTATION_MODE: i32 = 4;
+        const QUNS_QUIET_TIME: i32 = 6;
+
+        let mut state: i32 = 0;
+        let hr = unsafe { SHQueryUserNotificationState(&mut state) };
+        hr == 0
+            && matches!(
+                state,
+                QUNS_BUSY | QUNS_RUNNING_D3D_FULL_SCREEN | QUNS_PRESENTATION_MODE | QUNS_QUIET_TIME
+            )
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    {
+        false
+    }
+}
+
+/// Show a notification from the frontend (subject to DND suppression)
+#[tauri::command]
+pub async fn send_notification(app_handle: AppHandle, title: String, body: String) -> Result<(), String> {
+    notify(&app_handle, &title, &body);
+    Ok(())
+}
+
+/// Report whether DND is active and how many alerts are waiting
+#[tauri::command]
+pub async fn get_notification_state(
+    queue: tauri::State<'_, NotificationQueue>,
+) -> Result<serde_json::Value, String> {
+    let pending = queue.pending.lock().map_err(|e| e.to_string())?;
+    Ok(serde_json::json!({
+        "dnd_active": is_dnd_active(),
+        "queued": pending.len(),
+    }))
+}
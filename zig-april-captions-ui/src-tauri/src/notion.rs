@@ -0,0 +1,354 @@
+// Notion meeting notes
+//
+// `push_session_to_notion` creates a page in the configured Notion database
+// with the session's summary, action items (as to-dos) and transcript. The
+// page title goes into the database's title property and the session date
+// into its first date property, if it has one. Pushing a session again
+// archives the page made last time and creates a fresh one.
+//
+// Requests are spaced MIN_REQUEST_INTERVAL apart to stay under Notion's
+// average of three per second, and a 429 is retried after the time the API
+// asks for. The outcome is kept per session in `session_sync`.
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+use tracing::{info, warn};
+
+use crate::database::{self, SessionSync};
+use crate::export::{self, SessionNotes};
+use crate::{integrations, now_millis, tasks, AppState};
+
+const API_BASE: &str = "https://api.notion.com/v1";
+const NOTION_VERSION: &str = "2022-06-28";
+
+/// Spacing between API calls
+const MIN_REQUEST_INTERVAL: Duration = Duration::from_millis(350);
+
+/// Attempts per request when rate limited
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Notion limits: characters per rich text object, blocks per request
+const RICH_TEXT_LIMIT: usize = 2000;
+const BLOCKS_PER_REQUEST: usize = 100;
+
+const SERVICE: &str = "notion";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotionSettings {
+    pub token: String, // Internal integration secret
+    pub database_id: String,
+}
+
+/// When the last request was sent
+static LAST_REQUEST: Mutex<Option<Instant>> = Mutex::new(None);
+
+async fn throttle() {
+    let wait = {
+        let Ok(mut last) = LAST_REQUEST.lock() else {
+            return;
+        };
+        let now = Instant::now();
+        let next = last.map(|t| t + MIN_REQUEST_INTERVAL).filter(|t| *t > now).unwrap_or(now);
+        *last = Some(next);
+        next - now
+    };
+    if !wait.is_zero() {
+        tokio::time::sleep(wait).await;
+    }
+}
+
+async fn request(
+    settings: &NotionSettings,
+    method: reqwest::Method,
+    path: &str,
+    body: Option<&serde_json::Value>,
+) -> Result<serde_json::Value, String> {
+    let client = reqwest::Client::new();
+    for attempt in 1..=MAX_ATTEMPTS {
+        throttle().await;
+        let mut builder = client
+            .request(method.clone(), format!("{}{}", API_BASE, path))
+            .bearer_auth(&settings.token)
+            .header("Notion-Version", NOTION_VERSION);
+        if let Some(body) = body {
+            builder = builder.json(body);
+        }
+        let response = builder.send().await.map_err(|e| format!("Notion request failed: {}", e))?;
+        let status = response.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS && attempt < MAX_ATTEMPTS {
+            let retry_after = response
+                .headers()
+                .get("retry-after")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(1);
+            warn!("Notion rate limit hit, retrying in {}s", retry_after);
+            tokio::time::sleep(Duration::from_secs(retry_after)).await;
+            continue;
+        }
+        let json: serde_json::Value = response.json().await.unwrap_or_default();
+        if !status.is_success() {
+            let message = json["message"].as_str().unwrap_or("unknown error");
+            return Err(format!("Notion returned {}: {}", status, message));
+        }
+        return Ok(json);
+    }
+    Err("Notion kept rate limiting the request".to_string())
+}
+
+fn rich_text(text: &str) -> serde_json::Value {
+    let chars: Vec<char> = text.chars().collect();
+    let parts: Vec<serde_json::Value> = chars
+        .chunks(RICH_TEXT_LIMIT)
+        .map(|chunk| {
+            serde_json::json!({ "type": "text", "text": { "content": chunk.iter().collect::<String>() } })
+        })
+        .collect();
+    serde_json::Value::Array(parts)
+}
+
+fn block(kind: &str, text: &str) -> serde_json::Value {
+    let mut block = serde_json::json!({ "object": "block", "type": kind });
+    block[kind] = serde_json::json!({ "rich_text": rich_text(text) });
+    block
+}
+
+fn to_do(text: &str, checked: bool) -> serde_json::Value {
+    serde_json::json!({
+        "object": "block",
+        "type": "to_do",
+        "to_do": { "rich_text": rich_text(text), "checked": checked }
+    })
+}
+
+fn page_blocks(notes: &SessionNotes, summary: Option<&str>, action_items: &[(String, bool)]) -> Vec<serde_json::Value> {
+    let mut blocks = Vec::new();
+    if let Some(summary) = summary.filter(|s| !s.trim().is_empty()) {
+        blocks.push(block("heading_2", "Summary"));
+        for paragraph in summary.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+            blocks.push(block("paragraph", paragraph));
+        }
+    }
+    if !action_items.is_empty() {
+        blocks.push(block("heading_2", "Action items"));
+        for (text, done) in action_items {
+            blocks.push(to_do(text, *done));
+        }
+    }
+    if !notes.transcript.is_empty() {
+        blocks.push(block("heading_2", "Transcript"));
+        for line in &notes.transcript {
+            let text = match line.speaker.as_deref().filter(|s| !s.is_empty()) {
+                Some(speaker) => format!("[{}] {}: {}", export::clock_time(line.timestamp), speaker, line.text),
+                None => format!("[{}] {}", export::clock_time(line.timestamp), line.text),
+            };
+            blocks.push(block("paragraph", &text));
+        }
+    }
+    blocks
+}
+
+/// Page properties: the title, and the date if the database has a date column
+fn page_properties(database: &serde_json::Value, title: &str, started_at: i64) -> Result<serde_json::Value, String> {
+    let properties = database["properties"].as_object().ok_or("Unexpected Notion database response")?;
+    let title_property = properties
+        .iter()
+        .find(|(_, p)| p["type"] == "title")
+        .map(|(name, _)| name.clone())
+        .ok_or("The Notion database has no title property")?;
+    let mut values = serde_json::Map::new();
+    values.insert(title_property, serde_json::json!({ "title": rich_text(title) }));
+    if let Some((name, _)) = properties.iter().find(|(_, p)| p["type"] == "date") {
+        let date = &export::date_time(started_at)[..10];
+        values.insert(name.clone(), serde_json::json!({ "date": { "start": date } }));
+    }
+    Ok(serde_json::Value::Object(values))
+}
+
+fn record_status(state: &AppState, status: &SessionSync) {
+    let result = state
+        .db()
+        .and_then(|conn| database::set_session_sync(&conn, status).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        warn!("Failed to record Notion sync status: {}", e);
+    }
+}
+
+async fn push(
+    app_handle: &AppHandle,
+    state: &AppState,
+    settings: &NotionSettings,
+    session_id: &str,
+) -> Result<SessionSync, String> {
+    let (notes, mut action_items, previous) = {
+        let conn = state.db()?;
+        let notes = export::load_session_notes(&conn, session_id)?;
+        let action_items: Vec<(String, bool)> = tasks::query_tasks(&conn, Some(session_id), None)?
+            .into_iter()
+            .map(|task| {
+                let text = match &task.assignee {
+                    Some(assignee) => format!("{} (@{})", task.text, assignee),
+                    None => task.text.clone(),
+                };
+                (text, task.status == tasks::TaskStatus::Done)
+            })
+            .collect();
+        let previous = database::session_sync(&conn, session_id, SERVICE).map_err(|e| e.to_string())?;
+        (notes, action_items, previous)
+    };
+
+    // Summarize now if the session has no saved summary and AI is set up
+    let mut summary = notes.summary.clone();
+    let has_ai = state.settings.lock().map(|s| s.ai.is_some()).unwrap_or(false);
+    if summary.is_none() && has_ai && !notes.transcript.is_empty() {
+        let text = integrations::session_text(app_handle, session_id)?;
+        let generated = integrations::summarize(app_handle, &text).await?;
+        if action_items.is_empty() {
+            action_items = generated.action_items.iter().map(|item| (item.clone(), false)).collect();
+        }
+        summary = Some(generated.summary);
+    }
+
+    let database = request(
+        settings,
+        reqwest::Method::GET,
+        &format!("/databases/{}", settings.database_id.trim()),
+        None,
+    )
+    .await?;
+    let properties = page_properties(&database, &notes.session.title, notes.session.started_at)?;
+    let blocks = page_blocks(&notes, summary.as_deref(), &action_items);
+    let mut batches = blocks.chunks(BLOCKS_PER_REQUEST);
+
+    let page = request(
+        settings,
+        reqwest::Method::POST,
+        "/pages",
+        Some(&serde_json::json!({
+            "parent": { "database_id": settings.database_id.trim() },
+            "properties": properties,
+            "children": batches.next().unwrap_or_default(),
+        })),
+    )
+    .await?;
+    let page_id = page["id"].as_str().ok_or("Notion returned no page id")?.to_string();
+    for batch in batches {
+        request(
+            settings,
+            reqwest::Method::PATCH,
+            &format!("/blocks/{}/children", page_id),
+            Some(&serde_json::json!({ "children": batch })),
+        )
+        .await?;
+    }
+
+    if let Some(old_page) = previous.and_then(|p| p.remote_id).filter(|id| *id != page_id) {
+        let archived = request(
+            settings,
+            reqwest::Method::PATCH,
+            &format!("/pages/{}", old_page),
+            Some(&serde_json::json!({ "archived": true })),
+        )
+        .await;
+        if let Err(e) = archived {
+            warn!("Could not archive the previous Notion page: {}", e);
+        }
+    }
+
+    Ok(SessionSync {
+        session_id: session_id.to_string(),
+        service: SERVICE.to_string(),
+        status: "synced".to_string(),
+        remote_id: Some(page_id),
+        url: page["url"].as_str().map(String::from),
+        synced_at: Some(now_millis()),
+        error: None,
+    })
+}
+
+/// Create a Notion page for a session; returns its sync status
+#[tauri::command]
+pub async fn push_session_to_notion(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<SessionSync, String> {
+    let settings = state
+        .settings
+        .lock()
+        .map_err(|e| e.to_string())?
+        .notion
+        .clone()
+        .filter(|n| !n.token.is_empty() && !n.database_id.is_empty())
+        .ok_or("Notion is not configured")?;
+
+    let previous = {
+        let conn = state.db()?;
+        database::session_sync(&conn, &session_id, SERVICE).map_err(|e| e.to_string())?
+    };
+    let mut pending = SessionSync {
+        session_id: session_id.clone(),
+        service: SERVICE.to_string(),
+        status: "pending".to_string(),
+        remote_id: None,
+        url: None,
+        synced_at: None,
+        error: None,
+    };
+    if let Some(previous) = previous {
+        pending.remote_id = previous.remote_id;
+        pending.url = previous.url;
+        pending.synced_at = previous.synced_at;
+    }
+    record_status(&state, &pending);
+
+    match push(&app_handle, &state, &settings, &session_id).await {
+        Ok(synced) => {
+            record_status(&state, &synced);
+            info!("Session {} pushed to Notion", session_id);
+            Ok(synced)
+        }
+        Err(e) => {
+            record_status(
+                &state,
+                &SessionSync {
+                    status: "failed".to_string(),
+                    error: Some(e.clone()),
+                    ..pending
+                },
+            );
+            Err(e)
+        }
+    }
+}
+
+/// Where a session has been pushed to, and how that went
+#[tauri::command]
+pub async fn get_session_sync_status(
+    state: tauri::State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<Vec<SessionSync>, String> {
+    let conn = state.db()?;
+    database::session_sync_all(&conn, &session_id).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rich_text_chunks() {
+        let long = "a".repeat(RICH_TEXT_LIMIT + 10);
+        let parts = rich_text(&long);
+        assert_eq!(parts.as_array().unwrap().len(), 2);
+        assert_eq!(parts[1]["text"]["content"].as_str().unwrap().len(), 10);
+
+        let database = serde_json::json!({ "properties": {
+            "Name": { "type": "title" }, "When": { "type": "date" }, "Tags": { "type": "multi_select" }
+        }});
+        let properties = page_properties(&database, "Standup", 0).unwrap();
+        assert_eq!(properties["Name"]["title"][0]["text"]["content"], "Standup");
+        assert_eq!(properties["When"]["date"]["start"], "1970-01-01");
+    }
+}
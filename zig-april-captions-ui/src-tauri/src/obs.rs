@@ -0,0 +1,313 @@
+// OBS output
+//
+// Two ways to get captions on stream without extra glue:
+// - text files for an OBS "Text (GDI+/FreeType 2)" source (or vMix title)
+//   with "Read from file" enabled: `obs_captions.txt` holds the last few
+//   final lines and `captions_now.txt` only the line being spoken. Writes are
+//   debounced so a burst of partials touches the disk a few times a second.
+// - a styled browser-source page served by the embedded server at
+//   `/obs?token=<token>`, fed by the same WebSocket stream
+//
+// Settings can be overridden for the current session only; the override is
+// dropped when captions are stopped.
+use axum::extract::{Query, State};
+use axum::response::Html;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Notify;
+use tracing::warn;
+
+use crate::server::ServerContext;
+use crate::{AppState, CaptionEvent};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObsSettings {
+    #[serde(default)]
+    pub text_file_enabled: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_file_path: Option<String>, // Defaults to <config>/obs_captions.txt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partial_file_path: Option<String>, // Defaults to captions_now.txt beside the text file
+    #[serde(default = "default_max_lines")]
+    pub max_lines: usize,
+    #[serde(default = "default_font_family")]
+    pub font_family: String,
+    #[serde(default = "default_font_size")]
+    pub font_size: u32,
+    #[serde(default = "default_text_color")]
+    pub text_color: String,
+    #[serde(default = "default_background")]
+    pub background: String,
+}
+
+/// Minimum gap between text file rewrites
+const WRITE_DEBOUNCE: Duration = Duration::from_millis(150);
+
+fn default_max_lines() -> usize {
+    2
+}
+
+fn default_font_family() -> String {
+    "sans-serif".to_string()
+}
+
+fn default_font_size() -> u32 {
+    36
+}
+
+fn default_text_color() -> String {
+    "#ffffff".to_string()
+}
+
+fn default_background() -> String {
+    "rgba(0, 0, 0, 0.6)".to_string()
+}
+
+impl Default for ObsSettings {
+    fn default() -> Self {
+        Self {
+            text_file_enabled: false,
+            text_file_path: None,
+            partial_file_path: None,
+            max_lines: default_max_lines(),
+            font_family: default_font_family(),
+            font_size: default_font_size(),
+            text_color: default_text_color(),
+            background: default_background(),
+        }
+    }
+}
+
+/// Managed state: per-session override and the lines currently shown
+#[derive(Default)]
+pub struct ObsState {
+    session_override: Mutex<Option<ObsSettings>>,
+    lines: Mutex<VecDeque<String>>,
+    partial: Mutex<String>,
+    changed: Notify,
+}
+
+/// Effective OBS settings: the session override if set, else saved settings
+fn effective_settings(app_handle: &AppHandle) -> ObsSettings {
+    let obs = app_handle.state::<ObsState>();
+    if let Some(settings) = obs.session_override.lock().ok().and_then(|o| o.clone()) {
+        return settings;
+    }
+    app_handle
+        .state::<Arc<AppState>>()
+        .settings
+        .lock()
+        .ok()
+        .and_then(|s| s.obs.clone())
+        .unwrap_or_default()
+}
+
+fn text_file_path(settings: &ObsSettings) -> std::path::PathBuf {
+    settings
+        .text_file_path
+        .as_ref()
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| crate::get_config_dir().join("obs_captions.txt"))
+}
+
+fn partial_file_path(settings: &ObsSettings) -> std::path::PathBuf {
+    match &settings.partial_file_path {
+        Some(path) => std::path::PathBuf::from(path),
+        None => text_file_path(settings).with_file_name("captions_now.txt"),
+    }
+}
+
+/// Replace the file in one step so OBS never reads a half-written update
+fn write_text_file(path: &std::path::Path, content: &str) -> std::io::Result<()> {
+    crate::persist::write_atomic(path, content.as_bytes())
+}
+
+/// Update the caption lines from a recognizer event; the writer task flushes them
+pub fn on_caption_event(app_handle: &AppHandle, event: &CaptionEvent) {
+    let settings = effective_settings(app_handle);
+    if !settings.text_file_enabled {
+        return;
+    }
+    let obs = app_handle.state::<ObsState>();
+    let (Ok(mut lines), Ok(mut partial)) = (obs.lines.lock(), obs.partial.lock()) else {
+        return;
+    };
+
+    match (event.event_type.as_str(), event.caption_type.as_deref()) {
+        ("caption", Some("final")) => {
+            if let Some(text) = event.text.as_ref().filter(|t| !t.trim().is_empty()) {
+                lines.push_back(text.trim().to_string());
+            }
+            partial.clear();
+        }
+        ("caption", _) => *partial = event.text.as_deref().unwrap_or_default().trim().to_string(),
+        ("stopped", _) => {
+            lines.clear();
+            partial.clear();
+        }
+        _ => return,
+    }
+    while lines.len() > settings.max_lines {
+        lines.pop_front();
+    }
+    obs.changed.notify_one();
+}
+
+fn write_files(app_handle: &AppHandle) {
+    let settings = effective_settings(app_handle);
+    if !settings.text_file_enabled {
+        return;
+    }
+    let obs = app_handle.state::<ObsState>();
+    let (finals, partial) = match (obs.lines.lock(), obs.partial.lock()) {
+        (Ok(lines), Ok(partial)) => (lines.iter().cloned().collect::<Vec<_>>().join("\n"), partial.clone()),
+        _ => return,
+    };
+    if let Err(e) = write_text_file(&text_file_path(&settings), &finals) {
+        warn!("Failed to update OBS caption file: {}", e);
+    }
+    if let Err(e) = write_text_file(&partial_file_path(&settings), &partial) {
+        warn!("Failed to update OBS partial caption file: {}", e);
+    }
+}
+
+/// Start the task that writes the text files at most once per debounce window
+pub fn start_writer(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            app_handle.state::<ObsState>().changed.notified().await;
+            tokio::time::sleep(WRITE_DEBOUNCE).await;
+            write_files(&app_handle);
+        }
+    });
+}
+
+/// Drop the per-session override when captioning is stopped
+pub fn end_session(app_handle: &AppHandle) {
+    if let Ok(mut session_override) = app_handle.state::<ObsState>().session_override.lock() {
+        *session_override = None;
+    }
+}
+
+#[derive(Deserialize)]
+pub struct BrowserSourceQuery {
+    token: Option<String>,
+    font_size: Option<u32>,
+    lines: Option<usize>,
+}
+
+/// Keep user-supplied CSS values from closing the style block
+fn css_value(value: &str) -> String {
+    value.chars().filter(|c| !matches!(c, '<' | '>' | '{' | '}' | ';')).collect()
+}
+
+/// Styled caption page for an OBS browser source
+pub async fn browser_source(State(ctx): State<ServerContext>, Query(query): Query<BrowserSourceQuery>) -> Html<String> {
+    let settings = effective_settings(&ctx.app_handle);
+    let token = serde_json::to_string(&query.token.unwrap_or_default()).unwrap_or_default();
+    Html(
+        BROWSER_SOURCE_HTML
+            .replace("{{font_family}}", &css_value(&settings.font_family))
+            .replace("{{font_size}}", &query.font_size.unwrap_or(settings.font_size).to_string())
+            .replace("{{text_color}}", &css_value(&settings.text_color))
+            .replace("{{background}}", &css_value(&settings.background))
+            .replace("{{max_lines}}", &query.lines.unwrap_or(settings.max_lines).to_string())
+            .replace("{{token}}", &token),
+    )
+}
+
+const BROWSER_SOURCE_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<style>
+  html, body { margin: 0; background: transparent; overflow: hidden; }
+  #captions {
+    position: absolute; left: 0; right: 0; bottom: 0;
+    padding: 0.3em 0.6em;
+    font-family: {{font_family}}; font-size: {{font_size}}px; line-height: 1.3;
+    color: {{text_color}}; background: {{background}};
+  }
+  #captions:empty { display: none; }
+  .partial { opacity: 0.8; }
+</style>
+</head>
+<body>
+<div id="captions"></div>
+<script>
+  const maxLines = {{max_lines}};
+  const token = {{token}};
+  const box = document.getElementById("captions");
+  let lines = [];
+  let partial = "";
+
+  function render() {
+    const keep = Math.max(0, partial ? maxLines - 1 : maxLines);
+    const shown = lines.slice(lines.length - Math.min(keep, lines.length));
+    box.replaceChildren(...shown.map(t => {
+      const div = document.createElement("div");
+      div.textContent = t;
+      return div;
+    }));
+    if (partial) {
+      const div = document.createElement("div");
+      div.className = "partial";
+      div.textContent = partial;
+      box.appendChild(div);
+    }
+  }
+
+  function connect() {
+    const proto = location.protocol === "https:" ? "wss" : "ws";
+    const ws = new WebSocket(`${proto}://${location.host}/ws?token=${encodeURIComponent(token)}`);
+    ws.onmessage = (msg) => {
+      const { event, data } = JSON.parse(msg.data);
+      if (event === "caption") {
+        if (data.captionType === "final") {
+          if (data.text && data.text.trim()) lines.push(data.text.trim());
+          lines = lines.slice(-maxLines);
+          partial = "";
+        } else {
+          partial = data.text || "";
+        }
+        render();
+      } else if (event === "session" && data.state === "stopped") {
+        lines = [];
+        partial = "";
+        render();
+      }
+    };
+    ws.onclose = () => setTimeout(connect, 2000);
+  }
+  connect();
+</script>
+</body>
+</html>
+"#;
+
+/// Override OBS output for the current session (None clears the override)
+#[tauri::command]
+pub async fn set_obs_session_settings(
+    obs: tauri::State<'_, ObsState>,
+    settings: Option<ObsSettings>,
+) -> Result<(), String> {
+    *obs.session_override.lock().map_err(|e| e.to_string())? = settings;
+    Ok(())
+}
+
+/// Browser-source URL and text file path to paste into OBS
+#[tauri::command]
+pub async fn get_obs_sources(app_handle: AppHandle) -> Result<serde_json::Value, String> {
+    let settings = effective_settings(&app_handle);
+    let browser_source_url = crate::server::local_url(&app_handle)
+        .map(|base| format!("{}/obs?token={}", base, crate::server::token(&app_handle)));
+    Ok(serde_json::json!({
+        "browser_source_url": browser_source_url,
+        "text_file_path": text_file_path(&settings).to_string_lossy(),
+        "partial_file_path": partial_file_path(&settings).to_string_lossy(),
+        "text_file_enabled": settings.text_file_enabled,
+    }))
+}
@@ -0,0 +1,224 @@
+// Obsidian vault export
+//
+// `export_to_obsidian` writes a session as a note in `<vault>/<folder>`
+// (default "Zigy"): YAML frontmatter with the date, attendees and tags for
+// Obsidian's properties and search, the usual meeting notes, and wiki-links
+// to the people, companies and products tagged on the session so they show
+// up in the graph and backlinks. Exporting again updates the same note,
+// found through its `zigy_session` property even if the title changed.
+//
+// The folder also gets an index note linking every exported session, newest
+// first, rebuilt from the notes' frontmatter on each export.
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::info;
+
+use crate::entities::{self, SessionTag};
+use crate::export::{self, SessionExportOptions, SessionNotes};
+use crate::{tasks, AppState};
+
+const DEFAULT_FOLDER: &str = "Zigy";
+const INDEX_NOTE: &str = "Zigy Index.md";
+
+/// Characters Obsidian doesn't allow in note names and links
+const FORBIDDEN_CHARS: &[char] = &['\\', '/', ':', '*', '?', '"', '<', '>', '|', '#', '^', '[', ']'];
+
+fn note_name(text: &str) -> String {
+    let name: String = text
+        .chars()
+        .map(|c| if FORBIDDEN_CHARS.contains(&c) { ' ' } else { c })
+        .collect();
+    name.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Obsidian tags can't contain spaces or most punctuation
+fn tag(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '/'))
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// JSON strings are valid YAML scalars and quote everything that needs it
+fn yaml_string(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+fn yaml_list(content: &mut String, key: &str, items: &[String]) {
+    if items.is_empty() {
+        return;
+    }
+    content.push_str(&format!("{}:\n", key));
+    for item in items {
+        content.push_str(&format!("  - {}\n", yaml_string(item)));
+    }
+}
+
+fn render_note(notes: &SessionNotes, tags: &[SessionTag]) -> String {
+    let session = &notes.session;
+    let started = export::date_time(session.started_at);
+    let attendees: Vec<String> = notes.speakers().into_iter().map(String::from).collect();
+    let mut note_tags = vec!["meeting".to_string()];
+    for term in tags.iter().filter(|t| t.kind == "term") {
+        let term = tag(&term.name);
+        if !term.is_empty() && !note_tags.contains(&term) {
+            note_tags.push(term);
+        }
+    }
+
+    let mut content = String::from("---\n");
+    content.push_str(&format!("zigy_session: {}\n", yaml_string(&session.id)));
+    content.push_str(&format!("title: {}\n", yaml_string(&session.title)));
+    content.push_str(&format!("date: {}\n", &started[..10]));
+    content.push_str(&format!("time: {}\n", yaml_string(&started[11..16])));
+    if let Some(ended_at) = session.ended_at {
+        content.push_str(&format!(
+            "duration_minutes: {}\n",
+            ((ended_at - session.started_at).max(0) + 30_000) / 60_000
+        ));
+    }
+    yaml_list(&mut content, "attendees", &attendees);
+    yaml_list(&mut content, "tags", &note_tags);
+    content.push_str("---\n\n");
+
+    content.push_str(&export::render_session_markdown(notes, &SessionExportOptions::default()));
+
+    let mut mentioned = String::new();
+    for (kind, label) in [("person", "People"), ("company", "Companies"), ("product", "Products")] {
+        let links: Vec<String> = tags
+            .iter()
+            .filter(|t| t.kind == kind)
+            .map(|t| note_name(&t.name))
+            .filter(|name| !name.is_empty())
+            .map(|name| format!("[[{}]]", name))
+            .collect();
+        if !links.is_empty() {
+            mentioned.push_str(&format!("- **{}:** {}\n", label, links.join(", ")));
+        }
+    }
+    if !mentioned.is_empty() {
+        content.push_str(&format!("\n## Mentioned\n\n{}", mentioned));
+    }
+    content
+}
+
+/// Frontmatter value of `key` in a note
+fn frontmatter_value(content: &str, key: &str) -> Option<String> {
+    let body = content.strip_prefix("---\n")?;
+    let (frontmatter, _) = body.split_once("\n---")?;
+    let prefix = format!("{}: ", key);
+    let value = frontmatter.lines().find_map(|line| line.strip_prefix(&prefix))?.trim();
+    Some(serde_json::from_str::<String>(value).unwrap_or_else(|_| value.to_string()))
+}
+
+/// Notes exported from zigy in `dir`, as (path, content)
+fn exported_notes(dir: &Path) -> Vec<(PathBuf, String)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("md"))
+        .filter_map(|path| {
+            let content = std::fs::read_to_string(&path).ok()?;
+            frontmatter_value(&content, "zigy_session")?;
+            Some((path, content))
+        })
+        .collect()
+}
+
+fn render_index(notes: &[(PathBuf, String)]) -> String {
+    let mut rows: Vec<(String, String, String)> = notes
+        .iter()
+        .filter_map(|(path, content)| {
+            let name = path.file_stem()?.to_string_lossy().to_string();
+            let date = frontmatter_value(content, "date").unwrap_or_default();
+            let title = frontmatter_value(content, "title").unwrap_or_else(|| name.clone());
+            Some((date, name, title))
+        })
+        .collect();
+    rows.sort_by(|a, b| b.cmp(a));
+    let mut content = String::from("# Meetings\n\n");
+    for (date, name, title) in rows {
+        content.push_str(&format!("- {} [[{}|{}]]\n", date, name, note_name(&title)));
+    }
+    content
+}
+
+/// Write or update a session's note in an Obsidian vault; returns its path
+#[tauri::command]
+pub async fn export_to_obsidian(
+    state: tauri::State<'_, Arc<AppState>>,
+    session_id: String,
+    vault_path: String,
+    folder: Option<String>,
+) -> Result<String, String> {
+    let vault = Path::new(&vault_path);
+    if !vault.is_dir() {
+        return Err(format!("Vault not found: {}", vault_path));
+    }
+    let (notes, tags) = {
+        let conn = state.db()?;
+        let mut notes = export::load_session_notes(&conn, &session_id)?;
+        notes.action_items = tasks::query_tasks(&conn, Some(&session_id), None)?
+            .into_iter()
+            .map(|task| export::ActionItem {
+                text: match task.assignee {
+                    Some(assignee) => format!("{} ([[{}]])", task.text, note_name(&assignee)),
+                    None => task.text,
+                },
+                done: task.status == tasks::TaskStatus::Done,
+            })
+            .collect();
+        let tags = entities::session_tags(&conn, &session_id).map_err(|e| e.to_string())?;
+        (notes, tags)
+    };
+
+    let folder = folder
+        .map(|f| f.trim().trim_matches('/').to_string())
+        .filter(|f| !f.is_empty())
+        .unwrap_or_else(|| DEFAULT_FOLDER.to_string());
+    let dir = vault.join(folder);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    let title = note_name(&notes.session.title);
+    let file_name = format!("{} {}.md", &export::date_time(notes.session.started_at)[..10], title);
+    let path = dir.join(file_name.trim());
+
+    // Drop the previous note of this session if it was saved under another title
+    for (existing, content) in exported_notes(&dir) {
+        if existing != path && frontmatter_value(&content, "zigy_session").as_deref() == Some(session_id.as_str()) {
+            std::fs::remove_file(&existing).map_err(|e| format!("Failed to replace {}: {}", existing.display(), e))?;
+        }
+    }
+    std::fs::write(&path, render_note(&notes, &tags)).map_err(|e| format!("Failed to write note: {}", e))?;
+    std::fs::write(dir.join(INDEX_NOTE), render_index(&exported_notes(&dir)))
+        .map_err(|e| format!("Failed to write index note: {}", e))?;
+
+    info!("Exported session {} to {}", session_id, path.display());
+    Ok(path.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frontmatter() {
+        assert_eq!(note_name("Q3 plan: [draft] #2"), "Q3 plan draft 2");
+        assert_eq!(tag("Release Train (v2)"), "release-train-v2");
+
+        let note = "---\nzigy_session: \"abc\"\ntitle: \"Plan: \\\"v2\\\"\"\ndate: 2026-03-02\n---\n\n# Plan\n";
+        assert_eq!(frontmatter_value(note, "zigy_session").as_deref(), Some("abc"));
+        assert_eq!(frontmatter_value(note, "title").as_deref(), Some("Plan: \"v2\""));
+        assert_eq!(frontmatter_value(note, "date").as_deref(), Some("2026-03-02"));
+        assert_eq!(frontmatter_value("# No frontmatter\n", "date"), None);
+    }
+}
@@ -0,0 +1,496 @@
+// Caption overlay window management
+//
+// The overlay is a frameless, transparent, always-on-top window that loads the
+// frontend at `#overlay` and receives the same `caption-event` stream as the
+// main window. It ignores the mouse by default so it never steals clicks from
+// the app underneath; turn click-through off to drag or resize it.
+//
+// The overlay's placement is remembered per display configuration (the set of
+// connected monitors) in `Settings.overlay_placements`, so plugging a laptop
+// into a dock and back restores the overlay where it was last used on each
+// setup.
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{
+    AppHandle, Emitter, LogicalSize, Manager, Monitor, PhysicalPosition, PhysicalSize, WebviewUrl, WebviewWindowBuilder,
+};
+use tracing::{error, info};
+
+use crate::{settings_update, AppState};
+
+// ============================================================================
+// Wayland layer-shell stacking
+// ============================================================================
+// On Wayland, clients cannot position themselves or force always-on-top, and
+// the XWayland "keep above" hint is ignored by most compositors. wlr-layer-shell
+// (supported by Sway, Hyprland, KDE, and others - but not GNOME) lets the
+// overlay live in the compositor's overlay layer instead. Everywhere else we
+// fall back to a regular always-on-top window.
+// ============================================================================
+
+#[cfg(target_os = "linux")]
+mod layer_shell {
+    use gtk::prelude::*;
+    use gtk_layer_shell::{Edge, KeyboardMode, Layer, LayerShell};
+
+    /// True when running under a Wayland session with the GTK Wayland backend
+    pub fn is_wayland_session() -> bool {
+        let wayland = std::env::var("WAYLAND_DISPLAY").map(|v| !v.is_empty()).unwrap_or(false);
+        let forced_x11 = std::env::var("GDK_BACKEND").map(|v| v.starts_with("x11")).unwrap_or(false);
+        wayland && !forced_x11
+    }
+
+    /// Turn the overlay into a layer surface. Must run on the main thread.
+    /// Returns false if the compositor doesn't support wlr-layer-shell.
+    pub fn init(window: &gtk::ApplicationWindow, monitor_origin: (i32, i32), x: i32, y: i32) -> bool {
+        if !gtk_layer_shell::is_supported() {
+            return false;
+        }
+
+        // Layer surfaces must be set up before the window is mapped
+        window.hide();
+        window.unrealize();
+        window.init_layer_shell();
+        window.set_namespace("zigy-overlay");
+        window.set_layer(Layer::Overlay);
+        window.set_keyboard_mode(KeyboardMode::None);
+        window.set_exclusive_zone(-1);
+        set_offset(window, monitor_origin, x, y);
+        window.show_all();
+        true
+    }
+
+    /// The GDK monitor whose top-left corner is at `origin`, in the physical
+    /// pixels Tauri reports
+    fn gdk_monitor(window: &gtk::ApplicationWindow, origin: (i32, i32)) -> Option<gtk::gdk::Monitor> {
+        let display = window.display();
+        (0..display.n_monitors()).filter_map(|i| display.monitor(i)).find(|monitor| {
+            let geometry = monitor.geometry();
+            let scale = monitor.scale_factor();
+            (geometry.x() * scale, geometry.y() * scale) == origin
+        })
+    }
+
+    /// Put a layer surface on the monitor at `monitor_origin`, positioned by
+    /// anchoring to its top-left and using margins
+    pub fn set_offset(window: &gtk::ApplicationWindow, monitor_origin: (i32, i32), x: i32, y: i32) {
+        match gdk_monitor(window, monitor_origin) {
+            Some(monitor) => window.set_monitor(&monitor),
+            None => tracing::warn!("No monitor at {:?} for the overlay", monitor_origin),
+        }
+        window.set_anchor(Edge::Top, true);
+        window.set_anchor(Edge::Left, true);
+        window.set_layer_shell_margin(Edge::Left, x.max(0));
+        window.set_layer_shell_margin(Edge::Top, y.max(0));
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod layer_shell {
+    #[allow(dead_code)]
+    pub fn is_wayland_session() -> bool {
+        false // Wayland is Linux-only
+    }
+}
+
+/// Keep the overlay above other windows, using layer-shell on Wayland when the
+/// compositor supports it. Emits `overlay-mode` with the mechanism in use.
+pub fn apply_stacking(window: &tauri::WebviewWindow) -> Result<(), String> {
+    #[cfg(target_os = "linux")]
+    {
+        if layer_shell::is_wayland_session() {
+            let position = window.outer_position().unwrap_or_default();
+            let monitor_origin = window
+                .current_monitor()
+                .ok()
+                .flatten()
+                .map(|m| *m.position())
+                .unwrap_or_default();
+            let (x, y) = (position.x - monitor_origin.x, position.y - monitor_origin.y);
+            let monitor_origin = (monitor_origin.x, monitor_origin.y);
+
+            let handle = window.clone();
+            return window
+                .run_on_main_thread(move || {
+                    let mode = match handle.gtk_window() {
+                        Ok(gtk_window) if layer_shell::init(&gtk_window, monitor_origin, x, y) => "layer-shell",
+                        _ => {
+                            tracing::warn!("wlr-layer-shell unavailable, falling back to always-on-top");
+                            let _ = handle.set_always_on_top(true);
+                            "always-on-top"
+                        }
+                    };
+                    if mode == "layer-shell" {
+                        handle.state::<OverlayState>().layer_shell.store(true, Ordering::SeqCst);
+                    }
+                    let _ = handle.emit("overlay-mode", mode);
+                })
+                .map_err(|e| e.to_string());
+        }
+    }
+
+    window.set_always_on_top(true).map_err(|e| e.to_string())?;
+    let _ = window.emit("overlay-mode", "always-on-top");
+    Ok(())
+}
+
+/// Move the overlay to a monitor-relative offset. Layer surfaces can't be
+/// moved with set_position, so they are repositioned via margins instead.
+fn move_window(window: &tauri::WebviewWindow, monitor: &Monitor, x: i32, y: i32) -> Result<(), String> {
+    #[cfg(target_os = "linux")]
+    {
+        if window.state::<OverlayState>().layer_shell.load(Ordering::SeqCst) {
+            let handle = window.clone();
+            let monitor_origin = (monitor.position().x, monitor.position().y);
+            return window
+                .run_on_main_thread(move || {
+                    if let Ok(gtk_window) = handle.gtk_window() {
+                        layer_shell::set_offset(&gtk_window, monitor_origin, x, y);
+                    }
+                })
+                .map_err(|e| e.to_string());
+        }
+    }
+
+    window
+        .set_position(PhysicalPosition::new(monitor.position().x + x, monitor.position().y + y))
+        .map_err(|e| e.to_string())
+}
+
+/// Window label used for the caption overlay
+pub const OVERLAY_LABEL: &str = "overlay";
+
+/// How often monitors are polled for hotplug changes
+const MONITOR_POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Delay before a burst of move/resize events is written to disk
+const SAVE_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Size of a newly opened overlay with no remembered placement
+const DEFAULT_OVERLAY_SIZE: LogicalSize<f64> = LogicalSize::new(900.0, 160.0);
+
+/// Overlay placement relative to the top-left corner of its monitor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OverlayPlacement {
+    pub monitor_name: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DisplayInfo {
+    pub id: usize,
+    pub name: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f64,
+    pub primary: bool,
+}
+
+/// Managed state for the overlay window
+#[derive(Default)]
+pub struct OverlayState {
+    /// Latest placement and its display configuration key, not yet saved
+    unsaved: Mutex<Option<(String, OverlayPlacement)>>,
+    /// Display configuration seen on the last monitor poll
+    last_config: Mutex<String>,
+    save_pending: AtomicBool,
+    /// Whether the overlay is a Wayland layer surface
+    layer_shell: AtomicBool,
+}
+
+fn monitor_name(monitor: &Monitor, index: usize) -> String {
+    monitor
+        .name()
+        .cloned()
+        .unwrap_or_else(|| format!("display-{}", index))
+}
+
+/// Stable key identifying the current set of monitors and their layout
+fn display_config_key(monitors: &[Monitor]) -> String {
+    let mut parts: Vec<String> = monitors
+        .iter()
+        .enumerate()
+        .map(|(i, m)| {
+            format!(
+                "{}@{}x{}+{}+{}",
+                monitor_name(m, i),
+                m.size().width,
+                m.size().height,
+                m.position().x,
+                m.position().y
+            )
+        })
+        .collect();
+    parts.sort();
+    parts.join("|")
+}
+
+/// Record the overlay's current placement for the active display configuration
+pub fn remember_placement(window: &tauri::Window) {
+    let app_handle = window.app_handle().clone();
+    let state = app_handle.state::<OverlayState>();
+
+    let (Ok(monitors), Ok(Some(monitor)), Ok(position), Ok(size)) = (
+        window.available_monitors(),
+        window.current_monitor(),
+        window.outer_position(),
+        window.outer_size(),
+    ) else {
+        return;
+    };
+
+    let index = monitors
+        .iter()
+        .position(|m| m.name() == monitor.name() && m.position() == monitor.position())
+        .unwrap_or(0);
+
+    let placement = OverlayPlacement {
+        monitor_name: monitor_name(&monitor, index),
+        x: position.x - monitor.position().x,
+        y: position.y - monitor.position().y,
+        width: size.width,
+        height: size.height,
+    };
+
+    if let Ok(mut unsaved) = state.unsaved.lock() {
+        *unsaved = Some((display_config_key(&monitors), placement));
+    }
+
+    // Debounce: dragging fires dozens of Moved events per second
+    if !state.save_pending.swap(true, Ordering::SeqCst) {
+        std::thread::spawn(move || {
+            std::thread::sleep(SAVE_DEBOUNCE);
+            app_handle.state::<OverlayState>().save_pending.store(false, Ordering::SeqCst);
+            if let Err(e) = save_placement(&app_handle) {
+                error!("Failed to save overlay placement: {}", e);
+            }
+        });
+    }
+}
+
+/// Store the latest placement in the settings and save them
+fn save_placement(app_handle: &AppHandle) -> Result<(), String> {
+    let Some((key, placement)) = app_handle.state::<OverlayState>().unsaved.lock().map_err(|e| e.to_string())?.take()
+    else {
+        return Ok(());
+    };
+    let state = app_handle.state::<Arc<AppState>>();
+    let (previous, settings) = {
+        let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+        let previous = settings.clone();
+        settings.overlay_placements.insert(key, placement);
+        crate::persist_settings(&settings)?;
+        (previous, settings.clone())
+    };
+    settings_update::emit_changes(app_handle, &previous, &settings);
+    Ok(())
+}
+
+/// Move the overlay to the placement remembered for the current display
+/// configuration, falling back to the primary monitor if none is known or the
+/// remembered monitor is no longer connected.
+pub fn restore_placement(app_handle: &AppHandle) -> Result<(), String> {
+    let Some(window) = app_handle.get_webview_window(OVERLAY_LABEL) else {
+        return Ok(());
+    };
+
+    let monitors = window.available_monitors().map_err(|e| e.to_string())?;
+    let key = display_config_key(&monitors);
+    let saved = {
+        let state = app_handle.state::<Arc<AppState>>();
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        settings.overlay_placements.get(&key).cloned()
+    };
+
+    if let Some(placement) = saved {
+        let target = monitors
+            .iter()
+            .enumerate()
+            .find(|(i, m)| monitor_name(m, *i) == placement.monitor_name);
+        if let Some((_, monitor)) = target {
+            window
+                .set_size(PhysicalSize::new(placement.width, placement.height))
+                .map_err(|e| e.to_string())?;
+            move_window(&window, monitor, placement.x, placement.y)?;
+            return Ok(());
+        }
+    }
+
+    // Unknown configuration: keep the overlay visible on the primary monitor
+    let primary = window
+        .primary_monitor()
+        .map_err(|e| e.to_string())?
+        .or_else(|| monitors.first().cloned());
+    if let Some(monitor) = primary {
+        place_on_monitor(&window, &monitor)?;
+    }
+    Ok(())
+}
+
+/// Place the overlay near the bottom of the given monitor, keeping its size
+fn place_on_monitor(window: &tauri::WebviewWindow, monitor: &Monitor) -> Result<(), String> {
+    let size = window.outer_size().map_err(|e| e.to_string())?;
+    let width = size.width.min(monitor.size().width);
+    let x = (monitor.size().width.saturating_sub(width) / 2) as i32;
+    let y = monitor.size().height.saturating_sub(size.height + monitor.size().height / 10) as i32;
+    move_window(window, monitor, x, y)
+}
+
+/// Poll for monitor hotplug and re-apply the remembered placement when the
+/// display configuration changes. Tauri has no monitor-change event.
+pub fn start_monitor_watcher(app_handle: AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(MONITOR_POLL_INTERVAL);
+
+        let Some(window) = app_handle.get_webview_window(OVERLAY_LABEL) else {
+            continue;
+        };
+        let Ok(monitors) = window.available_monitors() else {
+            continue;
+        };
+        let key = display_config_key(&monitors);
+
+        let changed = {
+            let state = app_handle.state::<OverlayState>();
+            let Ok(mut last) = state.last_config.lock() else {
+                continue;
+            };
+            let changed = !last.is_empty() && *last != key;
+            *last = key;
+            changed
+        };
+
+        if changed {
+            info!("Display configuration changed, restoring overlay placement");
+            if let Err(e) = restore_placement(&app_handle) {
+                error!("Failed to restore overlay placement: {}", e);
+            }
+        }
+    });
+}
+
+/// List connected displays (ids are indices usable with move_overlay_to_display)
+#[tauri::command]
+pub async fn list_displays(app_handle: AppHandle) -> Result<Vec<DisplayInfo>, String> {
+    let monitors = app_handle.available_monitors().map_err(|e| e.to_string())?;
+    let primary = app_handle.primary_monitor().map_err(|e| e.to_string())?;
+
+    Ok(monitors
+        .iter()
+        .enumerate()
+        .map(|(i, m)| DisplayInfo {
+            id: i,
+            name: monitor_name(m, i),
+            x: m.position().x,
+            y: m.position().y,
+            width: m.size().width,
+            height: m.size().height,
+            scale_factor: m.scale_factor(),
+            primary: primary
+                .as_ref()
+                .map(|p| p.name() == m.name() && p.position() == m.position())
+                .unwrap_or(false),
+        })
+        .collect())
+}
+
+/// Move the overlay to the given display
+#[tauri::command]
+pub async fn move_overlay_to_display(app_handle: AppHandle, id: usize) -> Result<(), String> {
+    let window = app_handle
+        .get_webview_window(OVERLAY_LABEL)
+        .ok_or_else(|| "Overlay window is not open".to_string())?;
+    let monitors = window.available_monitors().map_err(|e| e.to_string())?;
+    let monitor = monitors
+        .get(id)
+        .ok_or_else(|| format!("Display {} not found", id))?;
+    place_on_monitor(&window, monitor)
+}
+
+/// Move the overlay to the next display (wraps around), for hotkey cycling
+#[tauri::command]
+pub async fn move_overlay_to_next_display(app_handle: AppHandle) -> Result<usize, String> {
+    let window = app_handle
+        .get_webview_window(OVERLAY_LABEL)
+        .ok_or_else(|| "Overlay window is not open".to_string())?;
+    let monitors = window.available_monitors().map_err(|e| e.to_string())?;
+    if monitors.is_empty() {
+        return Err("No displays found".to_string());
+    }
+
+    let current = window.current_monitor().map_err(|e| e.to_string())?;
+    let current_index = current
+        .and_then(|c| {
+            monitors
+                .iter()
+                .position(|m| m.name() == c.name() && m.position() == c.position())
+        })
+        .unwrap_or(0);
+    let next = (current_index + 1) % monitors.len();
+
+    place_on_monitor(&window, &monitors[next])?;
+    Ok(next)
+}
+
+/// Open the caption overlay, or bring back the existing one
+#[tauri::command]
+pub async fn open_overlay_window(app_handle: AppHandle, click_through: Option<bool>) -> Result<(), String> {
+    let click_through = click_through.unwrap_or(true);
+    if let Some(window) = app_handle.get_webview_window(OVERLAY_LABEL) {
+        window.show().map_err(|e| e.to_string())?;
+        return window.set_ignore_cursor_events(click_through).map_err(|e| e.to_string());
+    }
+
+    let window = WebviewWindowBuilder::new(&app_handle, OVERLAY_LABEL, WebviewUrl::App("index.html#overlay".into()))
+        .title("Zigy Captions")
+        .inner_size(DEFAULT_OVERLAY_SIZE.width, DEFAULT_OVERLAY_SIZE.height)
+        .decorations(false)
+        .transparent(true)
+        .shadow(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .focused(false)
+        .build()
+        .map_err(|e| format!("Failed to open overlay window: {}", e))?;
+
+    // Place first: a layer surface takes its margins from the current position
+    restore_placement(&app_handle)?;
+    apply_stacking(&window)?;
+    window.set_ignore_cursor_events(click_through).map_err(|e| e.to_string())?;
+
+    info!("Caption overlay opened");
+    let _ = app_handle.emit("overlay-window", serde_json::json!({ "open": true, "clickThrough": click_through }));
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn close_overlay_window(app_handle: AppHandle) -> Result<(), String> {
+    let Some(window) = app_handle.get_webview_window(OVERLAY_LABEL) else {
+        return Ok(());
+    };
+    // Placement is already saved from the last move/resize
+    window.close().map_err(|e| e.to_string())?;
+    app_handle.state::<OverlayState>().layer_shell.store(false, Ordering::SeqCst);
+
+    info!("Caption overlay closed");
+    let _ = app_handle.emit("overlay-window", serde_json::json!({ "open": false }));
+    Ok(())
+}
+
+/// Let the overlay receive the mouse (to move or resize it) or pass clicks through
+#[tauri::command]
+pub async fn set_overlay_click_through(app_handle: AppHandle, enabled: bool) -> Result<(), String> {
+    let window = app_handle
+        .get_webview_window(OVERLAY_LABEL)
+        .ok_or_else(|| "Overlay window is not open".to_string())?;
+    window.set_ignore_cursor_events(enabled).map_err(|e| e.to_string())?;
+    let _ = app_handle.emit("overlay-window", serde_json::json!({ "open": true, "clickThrough": enabled }));
+    Ok(())
+}
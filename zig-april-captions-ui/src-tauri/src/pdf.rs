@@ -0,0 +1,286 @@
+// PDF rendering of session notes
+//
+// Lays out the same sections as the Markdown export on A4 pages using the
+// built-in Helvetica faces, so no font files need to ship with the app. Every
+// page gets a header with the session title and date and a page number in
+// the footer. Transcript lines are colored per speaker. The built-in fonts
+// only cover Latin-1; other characters are replaced with "?".
+use printpdf::{BuiltinFont, Color, IndirectFontRef, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference, Rgb};
+
+use crate::export::{analytics_lines, bookmark_text, clock_time, date_time, SessionExportOptions, SessionNotes};
+
+const PAGE_WIDTH_MM: f32 = 210.0;
+const PAGE_HEIGHT_MM: f32 = 297.0;
+const MARGIN_MM: f32 = 18.0;
+const HEADER_MM: f32 = 10.0;
+
+const PT_TO_MM: f32 = 0.3528;
+
+/// Average Helvetica glyph width relative to the font size, for wrapping
+const AVG_CHAR_WIDTH: f32 = 0.5;
+
+const TEXT_COLOR: (f32, f32, f32) = (0.13, 0.13, 0.13);
+const MUTED_COLOR: (f32, f32, f32) = (0.45, 0.45, 0.45);
+
+/// Speaker colors, assigned in order of first appearance
+const SPEAKER_COLORS: [(f32, f32, f32); 6] = [
+    (0.12, 0.38, 0.75),
+    (0.75, 0.22, 0.17),
+    (0.13, 0.55, 0.30),
+    (0.55, 0.27, 0.68),
+    (0.80, 0.45, 0.05),
+    (0.05, 0.52, 0.58),
+];
+
+struct Writer {
+    doc: PdfDocumentReference,
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+    layer: PdfLayerReference,
+    font_size: f32,
+    y: f32, // Baseline of the next line, mm from the bottom
+    page: usize,
+    header: String,
+}
+
+impl Writer {
+    fn new(title: &str, header: String, font_size: f32) -> Result<Self, String> {
+        let (doc, page, layer) = PdfDocument::new(title, Mm(PAGE_WIDTH_MM), Mm(PAGE_HEIGHT_MM), "Notes");
+        let regular = doc.add_builtin_font(BuiltinFont::Helvetica).map_err(|e| e.to_string())?;
+        let bold = doc.add_builtin_font(BuiltinFont::HelveticaBold).map_err(|e| e.to_string())?;
+        let layer = doc.get_page(page).get_layer(layer);
+        let mut writer = Self {
+            doc,
+            regular,
+            bold,
+            layer,
+            font_size,
+            y: 0.0,
+            page: 1,
+            header,
+        };
+        writer.decorate_page();
+        Ok(writer)
+    }
+
+    fn line_height(&self, size: f32) -> f32 {
+        size * PT_TO_MM * 1.4
+    }
+
+    /// Header, footer and starting position of the current page
+    fn decorate_page(&mut self) {
+        let size = self.font_size * 0.8;
+        set_color(&self.layer, MUTED_COLOR);
+        self.layer.use_text(
+            latin1(&self.header),
+            size,
+            Mm(MARGIN_MM),
+            Mm(PAGE_HEIGHT_MM - MARGIN_MM + HEADER_MM / 2.0),
+            &self.regular,
+        );
+        self.layer.use_text(
+            format!("Page {}", self.page),
+            size,
+            Mm(PAGE_WIDTH_MM - MARGIN_MM - 15.0),
+            Mm(MARGIN_MM / 2.0),
+            &self.regular,
+        );
+        self.y = PAGE_HEIGHT_MM - MARGIN_MM - HEADER_MM;
+    }
+
+    fn ensure_space(&mut self, height: f32) {
+        if self.y - height >= MARGIN_MM {
+            return;
+        }
+        self.page += 1;
+        let (page, layer) = self.doc.add_page(Mm(PAGE_WIDTH_MM), Mm(PAGE_HEIGHT_MM), "Notes");
+        self.layer = self.doc.get_page(page).get_layer(layer);
+        self.decorate_page();
+    }
+
+    /// Write wrapped text; `prefix` is drawn in bold before the first line
+    fn paragraph(&mut self, prefix: Option<&str>, text: &str, size: f32, color: (f32, f32, f32)) {
+        let width_chars = ((PAGE_WIDTH_MM - 2.0 * MARGIN_MM) / (size * PT_TO_MM * AVG_CHAR_WIDTH)) as usize;
+        let prefix = prefix.map(|p| format!("{} ", p));
+        let prefix_chars = prefix.as_ref().map(|p| p.chars().count()).unwrap_or(0);
+        let line_height = self.line_height(size);
+
+        for (i, line) in wrap(text, width_chars.max(20), prefix_chars).into_iter().enumerate() {
+            self.ensure_space(line_height);
+            self.y -= line_height;
+            let mut x = MARGIN_MM;
+            set_color(&self.layer, color);
+            if let (0, Some(prefix)) = (i, &prefix) {
+                self.layer.use_text(latin1(prefix), size, Mm(x), Mm(self.y), &self.bold);
+                x += prefix_chars as f32 * size * PT_TO_MM * AVG_CHAR_WIDTH;
+            }
+            self.layer.use_text(latin1(&line), size, Mm(x), Mm(self.y), &self.regular);
+        }
+    }
+
+    fn heading(&mut self, text: &str, size: f32) {
+        let line_height = self.line_height(size);
+        // Keep headings with at least two lines of what follows
+        self.ensure_space(line_height * 1.5 + self.line_height(self.font_size) * 2.0);
+        self.y -= line_height * 1.5;
+        set_color(&self.layer, TEXT_COLOR);
+        self.layer.use_text(latin1(text), size, Mm(MARGIN_MM), Mm(self.y), &self.bold);
+    }
+
+    fn gap(&mut self) {
+        self.y -= self.line_height(self.font_size) * 0.5;
+    }
+}
+
+fn set_color(layer: &PdfLayerReference, (r, g, b): (f32, f32, f32)) {
+    layer.set_fill_color(Color::Rgb(Rgb::new(r, g, b, None)));
+}
+
+/// Replace characters the built-in fonts can't draw
+fn latin1(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\u{2018}' | '\u{2019}' => '\'',
+            '\u{201C}' | '\u{201D}' => '"',
+            '\u{2013}' | '\u{2014}' => '-',
+            '\u{2026}' => '.',
+            c if (c as u32) < 0x100 && !c.is_control() => c,
+            _ => '?',
+        })
+        .collect()
+}
+
+/// Greedy word wrap by character count; the first line leaves room for a
+/// `first_indent`-character prefix
+fn wrap(text: &str, width: usize, first_indent: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    let mut available = width.saturating_sub(first_indent).max(1);
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        loop {
+            let len = current.chars().count();
+            let needed = if len == 0 { word.len() } else { len + 1 + word.len() };
+            if needed <= available {
+                if len > 0 {
+                    current.push(' ');
+                }
+                current.extend(word.iter());
+                break;
+            }
+            if len > 0 {
+                lines.push(std::mem::take(&mut current));
+            } else {
+                // A single word longer than the line is split
+                let rest = word.split_off(available);
+                lines.push(word.iter().collect());
+                word = rest;
+            }
+            available = width;
+        }
+    }
+    if !current.is_empty() || lines.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+/// Render a session as a PDF document
+pub fn render_session_pdf(
+    notes: &SessionNotes,
+    options: &SessionExportOptions,
+    font_size: f32,
+) -> Result<Vec<u8>, String> {
+    let session = &notes.session;
+    let header = format!("{}  -  {}", session.title, date_time(session.started_at));
+    let mut writer = Writer::new(&session.title, header, font_size)?;
+    let small = font_size * 0.9;
+
+    writer.heading(&session.title, font_size * 1.8);
+    writer.gap();
+    let mut meta = vec![format!("Date: {}", date_time(session.started_at))];
+    if let Some(ended_at) = session.ended_at {
+        meta.push(format!("Duration: {} min", ((ended_at - session.started_at).max(0) + 30_000) / 60_000));
+    }
+    let speakers = notes.speakers();
+    if !speakers.is_empty() {
+        meta.push(format!("Speakers: {}", speakers.join(", ")));
+    }
+    for line in meta {
+        writer.paragraph(None, &line, small, MUTED_COLOR);
+    }
+
+    if options.include_summary {
+        if let Some(summary) = notes.summary.as_deref().filter(|s| !s.trim().is_empty()) {
+            writer.heading("Summary", font_size * 1.3);
+            for paragraph in summary.trim().split("\n\n") {
+                writer.gap();
+                writer.paragraph(None, paragraph, font_size, TEXT_COLOR);
+            }
+        }
+    }
+    if options.include_action_items && !notes.action_items.is_empty() {
+        writer.heading("Action items", font_size * 1.3);
+        writer.gap();
+        for item in &notes.action_items {
+            let mark = if item.done { "[x]" } else { "[ ]" };
+            writer.paragraph(Some(mark), &item.text, font_size, TEXT_COLOR);
+        }
+    }
+    if let Some(analytics) = notes.analytics.as_ref().filter(|_| options.include_analytics) {
+        writer.heading("Talk time", font_size * 1.3);
+        writer.gap();
+        for line in analytics_lines(analytics) {
+            writer.paragraph(Some("-"), &line, font_size, TEXT_COLOR);
+        }
+    }
+    if options.include_bookmarks && !notes.bookmarks.is_empty() {
+        writer.heading("Bookmarks", font_size * 1.3);
+        writer.gap();
+        for bookmark in &notes.bookmarks {
+            let text = bookmark_text(notes, bookmark);
+            writer.paragraph(Some(&clock_time(bookmark.timestamp)), &text, font_size, TEXT_COLOR);
+        }
+    }
+    if options.include_transcript && !notes.transcript.is_empty() {
+        writer.heading("Transcript", font_size * 1.3);
+        writer.gap();
+        for line in &notes.transcript {
+            let speaker = line.speaker.as_deref().filter(|s| !s.is_empty());
+            let color = speaker
+                .and_then(|s| speakers.iter().position(|known| *known == s))
+                .map(|i| SPEAKER_COLORS[i % SPEAKER_COLORS.len()])
+                .unwrap_or(TEXT_COLOR);
+            let prefix = match speaker {
+                Some(speaker) => format!("{} {}:", clock_time(line.timestamp), speaker),
+                None => clock_time(line.timestamp),
+            };
+            writer.paragraph(Some(&prefix), &line.text, font_size, color);
+        }
+    }
+    if options.include_qa && !notes.qa.is_empty() {
+        writer.heading("Q&A", font_size * 1.3);
+        for pair in &notes.qa {
+            writer.gap();
+            if let Some(question) = pair.question.as_deref().filter(|q| !q.is_empty()) {
+                writer.paragraph(Some("Q:"), question, font_size, TEXT_COLOR);
+            }
+            writer.paragraph(Some("A:"), pair.answer.trim(), font_size, TEXT_COLOR);
+        }
+    }
+
+    writer.doc.save_to_bytes().map_err(|e| format!("Failed to render PDF: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("the quick brown fox", 10, 0), vec!["the quick", "brown fox"]);
+        assert_eq!(wrap("the quick brown fox", 10, 6), vec!["the", "quick", "brown fox"]);
+        assert_eq!(wrap("abcdefghijkl", 5, 0), vec!["abcde", "fghij", "kl"]);
+        assert_eq!(wrap("", 10, 0), vec![""]);
+    }
+}
@@ -0,0 +1,247 @@
+// Crash-safe JSON persistence
+//
+// Files are written to a sibling temp file, synced and renamed over the
+// original, so a crash leaves either the old or the new contents. The previous
+// good version is kept as `<name>.bak`, and loads fall back to it when the
+// main file is missing or unreadable.
+//
+// Reads and writes hold an advisory lock on `<name>.lock`, so two processes
+// (or threads) never interleave a backup and a rename. If the lock can't be
+// taken within LOCK_TIMEOUT the operation fails and `storage-locked` is
+// emitted.
+//
+// `JsonStore` keeps a whole file in memory for stores the UI edits often
+// (knowledge, ideas, snapshots): reads never touch the disk, and writes are
+// flushed FLUSH_DEBOUNCE after the last change and once more at shutdown.
+use fs2::FileExt;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{OnceLock, RwLock, RwLockReadGuard};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Notify;
+use tracing::{error, warn};
+
+/// Quiet period before a changed store is written out
+const FLUSH_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// How long to wait for another process to release a store
+const LOCK_TIMEOUT: Duration = Duration::from_secs(2);
+
+const LOCK_RETRY: Duration = Duration::from_millis(25);
+
+/// Set once the app is running so lock failures can be reported to the UI
+static APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
+
+/// Take the lock that makes this the only running instance. It lives next
+/// to the data dir, not in it, because a restore swaps the data dir out.
+/// Ok(None) means another instance holds it; keep the file open to hold it.
+pub fn lock_instance() -> Result<Option<File>, String> {
+    let path = crate::get_config_dir().with_file_name("zigy.instance.lock");
+    let file = File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    match FileExt::try_lock_exclusive(&file) {
+        Ok(()) => Ok(Some(file)),
+        Err(_) => Ok(None),
+    }
+}
+
+pub fn init(app_handle: &AppHandle) {
+    let _ = APP_HANDLE.set(app_handle.clone());
+}
+
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+pub fn backup_path(path: &Path) -> PathBuf {
+    sibling(path, ".bak")
+}
+
+/// Advisory lock on `<path>.lock`, released when dropped
+fn lock(path: &Path, exclusive: bool) -> Result<File, String> {
+    let lock_path = sibling(path, ".lock");
+    let file = File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&lock_path)
+        .map_err(|e| format!("Failed to open {}: {}", lock_path.display(), e))?;
+
+    let deadline = Instant::now() + LOCK_TIMEOUT;
+    loop {
+        // Called through fs2 explicitly: std's inherent File locks shadow them
+        // on newer toolchains and return a different error type
+        let result = if exclusive {
+            FileExt::try_lock_exclusive(&file)
+        } else {
+            FileExt::try_lock_shared(&file)
+        };
+        match result {
+            Ok(()) => return Ok(file),
+            Err(_) if Instant::now() < deadline => std::thread::sleep(LOCK_RETRY),
+            Err(e) => {
+                let message = format!("{} is locked by another process: {}", path.display(), e);
+                if let Some(app_handle) = APP_HANDLE.get() {
+                    let _ = app_handle.emit(
+                        "storage-locked",
+                        serde_json::json!({ "path": path.to_string_lossy(), "message": message }),
+                    );
+                }
+                return Err(message);
+            }
+        }
+    }
+}
+
+/// Replace `path` with `contents` in one step
+pub fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let tmp = sibling(path, ".tmp");
+    {
+        let mut file = std::fs::File::create(&tmp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+    }
+    std::fs::rename(&tmp, path)
+}
+
+/// Serialize `value` as pretty JSON, keeping the previous version as a backup
+pub fn persist_json<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(value).map_err(|e| e.to_string())?;
+    let _lock = lock(path, true)?;
+
+    // Only a readable file is worth keeping; never back up a corrupt one
+    let current_is_valid = std::fs::read(path)
+        .ok()
+        .is_some_and(|bytes| serde_json::from_slice::<serde::de::IgnoredAny>(&bytes).is_ok());
+    if current_is_valid {
+        if let Err(e) = std::fs::copy(path, backup_path(path)) {
+            warn!("Could not back up {}: {}", path.display(), e);
+        }
+    }
+
+    write_atomic(path, &json).map_err(|e| e.to_string())
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let content = std::fs::read(path).map_err(|e| e.to_string())?;
+    serde_json::from_slice(&content).map_err(|e| e.to_string())
+}
+
+/// Load a JSON file, falling back to its backup; None when neither is usable
+pub fn load_json<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    // Writes replace the file atomically, so a read without the lock still
+    // sees a whole file; treating it as missing could lose the data on save
+    let _lock = lock(path, false).map_err(|e| warn!("Reading without lock: {}", e)).ok();
+    match read_json(path) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            let backup = backup_path(path);
+            if path.exists() {
+                warn!("{} is unreadable ({}), trying {}", path.display(), e, backup.display());
+            }
+            match read_json(&backup) {
+                Ok(value) => {
+                    warn!("Recovered {} from backup", path.display());
+                    Some(value)
+                }
+                Err(_) => None,
+            }
+        }
+    }
+}
+
+pub struct JsonStore<T> {
+    path: PathBuf,
+    data: RwLock<T>,
+    dirty: AtomicBool,
+    changed: Notify,
+}
+
+impl<T: Serialize + DeserializeOwned + Default> JsonStore<T> {
+    /// Load `path` (or its backup); a missing file starts out empty
+    pub fn load(path: PathBuf) -> Self {
+        let data = load_json(&path).unwrap_or_default();
+        Self {
+            path,
+            data: RwLock::new(data),
+            dirty: AtomicBool::new(false),
+            changed: Notify::new(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn read(&self) -> Result<RwLockReadGuard<'_, T>, String> {
+        self.data.read().map_err(|e| e.to_string())
+    }
+
+    /// Change the data in memory and schedule a flush
+    pub fn update<R>(&self, f: impl FnOnce(&mut T) -> R) -> Result<R, String> {
+        let result = {
+            let mut data = self.data.write().map_err(|e| e.to_string())?;
+            f(&mut data)
+        };
+        self.dirty.store(true, Ordering::SeqCst);
+        self.changed.notify_one();
+        Ok(result)
+    }
+
+    /// Write the data out if it changed since the last flush
+    pub fn flush(&self) -> Result<(), String> {
+        if !self.dirty.swap(false, Ordering::SeqCst) {
+            return Ok(());
+        }
+        let result = self.read().and_then(|data| persist_json(&self.path, &*data));
+        if result.is_err() {
+            self.dirty.store(true, Ordering::SeqCst); // Retry on the next change or at shutdown
+        }
+        result
+    }
+
+    /// Flush after each burst of changes; runs for the life of the app
+    pub async fn flush_on_change(&self) {
+        loop {
+            self.changed.notified().await;
+            tokio::time::sleep(FLUSH_DEBOUNCE).await;
+            if let Err(e) = self.flush() {
+                error!("Failed to save {}: {}", self.path.display(), e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recovers_from_backup() {
+        let dir = std::env::temp_dir().join(format!("zigy-persist-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("store.json");
+
+        persist_json(&path, &vec![1, 2]).unwrap();
+        persist_json(&path, &vec![1, 2, 3]).unwrap();
+        assert_eq!(load_json::<Vec<i32>>(&path), Some(vec![1, 2, 3]));
+
+        std::fs::write(&path, "[1, 2, 3, 4").unwrap(); // Torn write
+        assert_eq!(load_json::<Vec<i32>>(&path), Some(vec![1, 2]));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
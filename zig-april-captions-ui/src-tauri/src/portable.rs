@@ -0,0 +1,79 @@
+// Portable mode
+//
+// With a `portable.flag` file next to the executable, or when started with
+// `--portable`, everything Zigy stores (settings, zigy.db, JSON stores,
+// models, logs, backups) goes into a `data` folder next to the executable
+// instead of the user's config dir, so the app can run from a USB stick. On
+// macOS the flag and the data folder sit next to the .app bundle rather than
+// inside it, and for an AppImage next to the .AppImage file.
+//
+// The mode is decided once per launch; every storage path derives from
+// `get_config_dir`, which asks `data_dir` first. The main window keeps its
+// WebView data in `data/webview`, the encryption key is never remembered in
+// the OS keyring, and launch at login is not registered. macOS ignores the
+// WebView data folder, so WKWebView still keeps its caches in ~/Library.
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+pub const FLAG_FILE: &str = "portable.flag";
+pub const ARG: &str = "--portable";
+const DATA_DIR: &str = "data";
+
+static DATA: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+/// Folder the flag file and data folder live in
+fn base_dir(exe: &Path) -> Option<PathBuf> {
+    let bundle = exe.ancestors().find(|p| p.extension().and_then(|e| e.to_str()) == Some("app"));
+    bundle.unwrap_or(exe).parent().map(Path::to_path_buf)
+}
+
+/// Data folder for an executable at `exe` started with `args`, if portable
+fn resolve(exe: &Path, mut args: impl Iterator<Item = String>) -> Option<PathBuf> {
+    let base = base_dir(exe)?;
+    if args.any(|a| a == ARG) || base.join(FLAG_FILE).exists() {
+        Some(base.join(DATA_DIR))
+    } else {
+        None
+    }
+}
+
+/// Data folder of a portable install, None for a regular one
+pub fn data_dir() -> Option<&'static Path> {
+    DATA.get_or_init(|| {
+        // An AppImage runs from a temporary mount; APPIMAGE is the real file
+        let exe = std::env::var_os("APPIMAGE")
+            .map(PathBuf::from)
+            .or_else(|| std::env::current_exe().ok())?;
+        resolve(&exe, std::env::args().skip(1))
+    })
+    .as_deref()
+}
+
+/// WebView data folder of a portable install
+pub fn webview_dir() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("webview"))
+}
+
+pub fn is_portable() -> bool {
+    data_dir().is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        let dir = std::env::temp_dir().join(format!("zigy-portable-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let exe = dir.join("zigy");
+        assert_eq!(resolve(&exe, std::iter::empty()), None);
+        assert_eq!(resolve(&exe, [ARG.to_string()].into_iter()), Some(dir.join("data")));
+
+        std::fs::write(dir.join(FLAG_FILE), "").unwrap();
+        assert_eq!(resolve(&exe, std::iter::empty()), Some(dir.join("data")));
+        let bundled = dir.join("Zigy.app").join("Contents").join("MacOS").join("zigy");
+        assert_eq!(resolve(&bundled, std::iter::empty()), Some(dir.join("data")));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
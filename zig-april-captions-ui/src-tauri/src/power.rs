@@ -0,0 +1,109 @@
+// Sleep inhibition while captions are running
+//
+// Each platform has its own mechanism; all of them are released by dropping
+// the SleepInhibitor:
+// - Linux: a `systemd-inhibit` child holding a logind sleep/idle lock for as
+//   long as its `cat` reads our pipe; closing the pipe ends both, so no
+//   process is orphaned (killing systemd-inhibit alone would leave its
+//   command running)
+// - macOS: a `caffeinate -i` child (IOPMAssertion under the hood)
+// - Windows: SetThreadExecutionState on a dedicated thread (the state is
+//   per-thread, so it can't be set from a tokio worker)
+use tracing::info;
+
+pub struct SleepInhibitor {
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    child: std::process::Child,
+    #[cfg(target_os = "windows")]
+    release: std::sync::mpsc::Sender<()>,
+}
+
+impl SleepInhibitor {
+    /// Ask the OS not to suspend until the returned guard is dropped
+    pub fn acquire(reason: &str) -> Result<Self, String> {
+        #[cfg(target_os = "linux")]
+        {
+            let child = std::process::Command::new("systemd-inhibit")
+                .args([
+                    "--what=sleep:idle",
+                    "--who=Zigy",
+                    &format!("--why={}", reason),
+                    "--mode=block",
+                    "cat",
+                ])
+                .stdin(std::process::Stdio::piped())
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .spawn()
+                .map_err(|e| format!("Failed to run systemd-inhibit: {}", e))?;
+            info!("Sleep inhibited via systemd-inhibit: {}", reason);
+            Ok(Self { child })
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            let child = std::process::Command::new("caffeinate")
+                .args(["-i", "-w", &std::process::id().to_string()])
+                .stdin(std::process::Stdio::null())
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .spawn()
+                .map_err(|e| format!("Failed to run caffeinate: {}", e))?;
+            info!("Sleep inhibited via caffeinate: {}", reason);
+            Ok(Self { child })
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            #[link(name = "kernel32")]
+            extern "system" {
+                fn SetThreadExecutionState(flags: u32) -> u32;
+            }
+            const ES_CONTINUOUS: u32 = 0x8000_0000;
+            const ES_SYSTEM_REQUIRED: u32 = 0x0000_0001;
+
+            let (release, wait) = std::sync::mpsc::channel::<()>();
+            let (ready_tx, ready_rx) = std::sync::mpsc::channel::<bool>();
+            std::thread::spawn(move || {
+                let ok = unsafe { SetThreadExecutionState(ES_CONTINUOUS | ES_SYSTEM_REQUIRED) } != 0;
+                let _ = ready_tx.send(ok);
+                // Block until released (or the sender is dropped)
+                let _ = wait.recv();
+                unsafe { SetThreadExecutionState(ES_CONTINUOUS) };
+            });
+
+            if !ready_rx.recv().unwrap_or(false) {
+                return Err("SetThreadExecutionState failed".to_string());
+            }
+            info!("Sleep inhibited via SetThreadExecutionState: {}", reason);
+            Ok(Self { release })
+        }
+
+        #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+        {
+            let _ = reason;
+            Err("Sleep inhibition is not supported on this platform".to_string())
+        }
+    }
+}
+
+impl Drop for SleepInhibitor {
+    fn drop(&mut self) {
+        #[cfg(any(target_os = "linux", target_os = "macos"))]
+        {
+            // EOF ends `cat`, and systemd-inhibit exits with it
+            drop(self.child.stdin.take());
+            if let Err(e) = self.child.kill() {
+                tracing::warn!("Failed to release sleep inhibitor: {}", e);
+            }
+            let _ = self.child.wait();
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            let _ = self.release.send(());
+        }
+
+        info!("Sleep inhibitor released");
+    }
+}
@@ -0,0 +1,144 @@
+// Cleanup of the caption engine process when the app goes away
+//
+// Closing the window kills the child, but being killed by a signal or
+// panicking on the main thread skips that and leaves zig-april-captions
+// running and holding the microphone. So:
+//   - SIGINT/SIGTERM/SIGHUP (Ctrl-C / Ctrl-Break on Windows) kill the child
+//     and save stores before exiting
+//   - a panic hook kills it when the main thread panics
+//   - dropping `AppState` kills it
+//   - the child's pid is kept in `captions.pid` (`captions-monitor.pid` for
+//     the system audio recognizer when capturing both sides); at startup a
+//     process still running under that pid is killed if it is
+//     zig-april-captions
+use std::path::PathBuf;
+use std::process::{Child, Command};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+use crate::{get_config_dir, recovery, AppState};
+
+const PROCESS_NAME: &str = "zig-april-captions";
+
+/// Pid files of the recognizer and of the system audio one
+pub const ENGINE_PID: &str = "captions.pid";
+pub const MONITOR_PID: &str = "captions-monitor.pid";
+
+fn pid_path(file: &str) -> PathBuf {
+    get_config_dir().join(file)
+}
+
+/// Remember a running child's pid
+pub fn write_pid(file: &str, pid: u32) {
+    if let Err(e) = std::fs::write(pid_path(file), pid.to_string()) {
+        warn!("Failed to write {}: {}", pid_path(file).display(), e);
+    }
+}
+
+pub fn clear_pid(file: &str) {
+    let _ = std::fs::remove_file(pid_path(file));
+}
+
+fn kill(process: &mut Option<Child>, file: &str) {
+    if let Some(mut child) = process.take() {
+        info!("Cleaning up zig-april-captions process (pid {})", child.id());
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+    clear_pid(file);
+}
+
+/// Kill and reap the children, if any
+pub fn kill_child(state: &AppState) {
+    let mut process = state.process.lock().unwrap_or_else(|e| e.into_inner());
+    kill(&mut process, ENGINE_PID);
+    let mut monitor = state.monitor_process.lock().unwrap_or_else(|e| e.into_inner());
+    kill(&mut monitor, MONITOR_PID);
+}
+
+fn command_output(program: &str, args: &[&str]) -> String {
+    Command::new(program)
+        .args(args)
+        .output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).into_owned())
+        .unwrap_or_default()
+}
+
+/// Command line (or image name) of the process running under `pid`, empty if there is none
+fn process_command(pid: u32) -> String {
+    #[cfg(target_os = "linux")]
+    let name = std::fs::read(format!("/proc/{}/cmdline", pid))
+        .map(|c| String::from_utf8_lossy(&c).into_owned())
+        .unwrap_or_default();
+    #[cfg(target_os = "macos")]
+    let name = command_output("ps", &["-p", &pid.to_string(), "-o", "comm="]);
+    #[cfg(target_os = "windows")]
+    let name = command_output("tasklist", &["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"]);
+    name
+}
+
+/// Whether `pid` is a running caption engine (and not a process that reused the pid)
+fn is_caption_process(pid: u32) -> bool {
+    process_command(pid).contains(PROCESS_NAME)
+}
+
+/// Whether `pid` is running this app's executable
+pub fn is_app_process(pid: u32) -> bool {
+    let exe = std::env::current_exe().ok();
+    let Some(name) = exe.as_ref().and_then(|p| p.file_stem()).and_then(|s| s.to_str()) else {
+        return false;
+    };
+    process_command(pid).contains(name)
+}
+
+fn kill_pid(pid: u32) {
+    #[cfg(unix)]
+    let _ = command_output("kill", &["-9", &pid.to_string()]);
+    #[cfg(windows)]
+    let _ = command_output("taskkill", &["/PID", &pid.to_string(), "/F"]);
+}
+
+/// Kill caption engines left running by a previous instance that didn't exit cleanly
+pub fn kill_stale_child() {
+    for file in [ENGINE_PID, MONITOR_PID] {
+        let Some(pid) = std::fs::read_to_string(pid_path(file)).ok().and_then(|s| s.trim().parse::<u32>().ok())
+        else {
+            continue;
+        };
+        if is_caption_process(pid) {
+            warn!("Killing zig-april-captions (pid {}) left over from a previous run", pid);
+            kill_pid(pid);
+        }
+        clear_pid(file);
+    }
+}
+
+/// Install the signal handler and panic hook
+pub fn install(state: Arc<AppState>) {
+    let signal_state = state.clone();
+    let result = ctrlc::set_handler(move || {
+        info!("Termination signal received, shutting down");
+        kill_child(&signal_state);
+        signal_state.flush_stores();
+        recovery::mark_clean_shutdown();
+        std::process::exit(130);
+    });
+    if let Err(e) = result {
+        error!("Failed to install signal handler: {}", e);
+    }
+
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // Worker threads recover from their panics; only the main thread's are
+        // fatal. try_lock because the panicking code may hold the lock.
+        if std::thread::current().name() == Some("main") {
+            if let Ok(mut process) = state.process.try_lock() {
+                kill(&mut process, ENGINE_PID);
+            }
+            if let Ok(mut monitor) = state.monitor_process.try_lock() {
+                kill(&mut monitor, MONITOR_PID);
+            }
+        }
+        previous(info);
+    }));
+}
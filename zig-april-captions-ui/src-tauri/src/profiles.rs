@@ -0,0 +1,221 @@
+// Named settings profiles
+//
+// A profile ("Work EN", "Personal VI") is a full copy of the settings kept
+// in profiles/<name>.json under the config dir. settings.json stays the live
+// settings; the active profile's name is in profiles/active_profile and every
+// settings save is copied into that profile, so switching away never loses
+// changes.
+//
+// `switch_profile` replaces the live settings with the profile's and applies
+// them like a settings save. When captions are running and the profile uses
+// another model or audio source, the recognizer is stopped and started again
+// with the new ones, which begins a new session.
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+use crate::{get_config_dir, now_millis, persist, AppState, Settings};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ProfileFile {
+    name: String,
+    #[serde(default)]
+    updated_at: i64,
+    settings: Settings,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileInfo {
+    pub name: String,
+    pub active: bool,
+    pub model_path: String,
+    pub audio_source: String,
+    pub language: String,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ProfileSwitched {
+    name: String,
+    restarted: bool, // Captions were restarted with the profile's model or audio source
+}
+
+fn profiles_dir() -> PathBuf {
+    let dir = get_config_dir().join("profiles");
+    std::fs::create_dir_all(&dir).ok();
+    dir
+}
+
+fn active_path(dir: &Path) -> PathBuf {
+    dir.join("active_profile")
+}
+
+/// File name for a profile name; characters unsafe in paths become '_'
+fn file_name(name: &str) -> String {
+    let stem: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == ' ' { c } else { '_' })
+        .collect();
+    format!("{}.json", stem)
+}
+
+fn validate_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Profile name is required".to_string());
+    }
+    if name.chars().count() > 64 {
+        return Err("Profile name is too long".to_string());
+    }
+    Ok(name.to_string())
+}
+
+fn active_name(dir: &Path) -> Option<String> {
+    std::fs::read_to_string(active_path(dir))
+        .ok()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+fn set_active_name(dir: &Path, name: &str) -> Result<(), String> {
+    std::fs::write(active_path(dir), name).map_err(|e| format!("Failed to save the active profile: {}", e))
+}
+
+fn load(dir: &Path, name: &str) -> Option<ProfileFile> {
+    persist::load_json(&dir.join(file_name(name)))
+}
+
+fn store(dir: &Path, name: &str, settings: &Settings) -> Result<(), String> {
+    let profile = ProfileFile {
+        name: name.to_string(),
+        updated_at: now_millis(),
+        settings: settings.clone(),
+    };
+    persist::persist_json(&dir.join(file_name(name)), &profile)
+        .map_err(|e| format!("Failed to save profile {}: {}", name, e))
+}
+
+/// Profiles in `dir`, by name
+fn list(dir: &Path) -> Vec<ProfileInfo> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let active = active_name(dir);
+    let mut profiles: Vec<ProfileInfo> = entries
+        .flatten()
+        .filter(|entry| entry.path().extension().and_then(|e| e.to_str()) == Some("json"))
+        .filter_map(|entry| persist::load_json::<ProfileFile>(&entry.path()))
+        .map(|profile| ProfileInfo {
+            active: active.as_deref() == Some(profile.name.as_str()),
+            model_path: profile.settings.model_path,
+            audio_source: profile.settings.audio_source,
+            language: profile.settings.language,
+            updated_at: profile.updated_at,
+            name: profile.name,
+        })
+        .collect();
+    profiles.sort_by_key(|p| p.name.to_lowercase());
+    profiles
+}
+
+/// Copy saved settings into the active profile, if there is one
+pub fn store_active(settings: &Settings) {
+    let dir = profiles_dir();
+    if let Some(name) = active_name(&dir) {
+        if let Err(e) = store(&dir, &name, settings) {
+            warn!("{}", e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn list_profiles() -> Result<Vec<ProfileInfo>, String> {
+    Ok(list(&profiles_dir()))
+}
+
+/// Copy the profile `source`, or the current settings when it's None, to a
+/// new profile called `name`
+#[tauri::command]
+pub async fn duplicate_profile(
+    state: tauri::State<'_, Arc<AppState>>,
+    source: Option<String>,
+    name: String,
+) -> Result<ProfileInfo, String> {
+    let name = validate_name(&name)?;
+    let dir = profiles_dir();
+    if list(&dir).iter().any(|p| file_name(&p.name) == file_name(&name)) {
+        return Err(format!("A profile named {} already exists", name));
+    }
+    let settings = match source {
+        Some(source) => load(&dir, &source).ok_or_else(|| format!("Profile {} not found", source))?.settings,
+        None => state.settings.lock().map_err(|e| e.to_string())?.clone(),
+    };
+    store(&dir, &name, &settings)?;
+    list(&dir)
+        .into_iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| format!("Failed to save profile {}", name))
+}
+
+/// Make `name` the active profile and apply its settings
+#[tauri::command]
+pub async fn switch_profile(app_handle: AppHandle, name: String) -> Result<(), String> {
+    let dir = profiles_dir();
+    let profile = load(&dir, &name).ok_or_else(|| format!("Profile {} not found", name))?;
+    let state = app_handle.state::<Arc<AppState>>();
+    let previous = state.settings.lock().map_err(|e| e.to_string())?.clone();
+    store_active(&previous);
+
+    let running = state.process.lock().map(|p| p.is_some()).unwrap_or(false);
+    let restart = running
+        && (profile.settings.model_path != previous.model_path
+            || profile.settings.audio_source != previous.audio_source);
+    if restart {
+        crate::stop_captions(app_handle.clone(), app_handle.state()).await?;
+    }
+
+    set_active_name(&dir, &profile.name)?;
+    crate::save_settings(app_handle.clone(), app_handle.state(), profile.settings).await?;
+    info!("Switched to profile {}", profile.name);
+
+    if restart {
+        crate::start_with_saved_settings(&app_handle).await?;
+    }
+    let _ = app_handle.emit(
+        "profile-switched",
+        ProfileSwitched {
+            name: profile.name,
+            restarted: restart,
+        },
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_and_list() {
+        let dir = std::env::temp_dir().join(format!("zigy-profiles-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let settings = Settings {
+            language: "vi".to_string(),
+            ..Settings::default()
+        };
+        store(&dir, "Personal VI", &settings).unwrap();
+        store(&dir, "work/en", &Settings::default()).unwrap();
+        set_active_name(&dir, "work/en").unwrap();
+
+        let profiles = list(&dir);
+        let names: Vec<&str> = profiles.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["Personal VI", "work/en"]);
+        assert_eq!(profiles[0].language, "vi");
+        assert!(!profiles[0].active && profiles[1].active);
+        assert!(dir.join("work_en.json").exists());
+        assert_eq!(load(&dir, "work/en").unwrap().name, "work/en");
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,318 @@
+// Unclean shutdown detection
+//
+// While the app runs, a heartbeat thread keeps `session.lock` fresh and mirrors
+// the in-memory transcript to `recovery_transcript.json`. A clean exit stops the
+// heartbeat and removes both files; if they are still there on the next launch
+// (and the pid in the lock no longer runs this app), the previous run crashed
+// and its transcript is offered back to the user via the `recovery-available`
+// event.
+//
+// Final captions are also appended to `transcripts/<session id>.jsonl` as they
+// are written out (see caption_batch), one flushed batch at a time, so a crash
+// or power loss loses at most the last half second of captions. Recovery
+// prefers that log over the periodic mirror.
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{error, info};
+
+use crate::{get_config_dir, AppState};
+
+/// How often the lock file and transcript mirror are refreshed
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Set on a clean exit; held while the heartbeat writes so it can't recreate
+/// the files after they were removed
+static STOPPED: Mutex<bool> = Mutex::new(false);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SessionLock {
+    pid: u32,
+    started_at: i64,
+    heartbeat_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveredSession {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    pub started_at: i64,
+    pub last_heartbeat: i64,
+    pub transcript: Vec<String>,
+}
+
+/// Managed state holding a crashed session awaiting restore/discard
+#[derive(Default)]
+pub struct RecoveryState {
+    pending: Mutex<Option<RecoveredSession>>,
+}
+
+fn get_lock_path() -> std::path::PathBuf {
+    get_config_dir().join("session.lock")
+}
+
+fn get_recovery_transcript_path() -> std::path::PathBuf {
+    get_config_dir().join("recovery_transcript.json")
+}
+
+/// One final caption in a session's transcript log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggedLine {
+    pub timestamp: i64,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<String>,
+}
+
+fn get_transcript_log_dir() -> PathBuf {
+    let dir = get_config_dir().join("transcripts");
+    std::fs::create_dir_all(&dir).ok();
+    dir
+}
+
+pub fn transcript_log_path(session_id: &str) -> PathBuf {
+    get_transcript_log_dir().join(format!("{}.jsonl", session_id))
+}
+
+/// Append final captions to the session's log and flush them to disk
+pub fn append_transcript_lines(session_id: &str, lines: &[LoggedLine]) -> Result<(), String> {
+    let mut json = String::new();
+    for line in lines {
+        json.push_str(&serde_json::to_string(line).map_err(|e| e.to_string())?);
+        json.push('\n');
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(transcript_log_path(session_id))
+        .map_err(|e| e.to_string())?;
+    file.write_all(json.as_bytes()).map_err(|e| e.to_string())?;
+    file.sync_data().map_err(|e| e.to_string())
+}
+
+/// Transcript lines from a session log, "Speaker: text" where labelled. A
+/// torn last line from a crash is skipped.
+fn read_transcript_log(path: &std::path::Path) -> Vec<String> {
+    let Ok(file) = std::fs::File::open(path) else {
+        return Vec::new();
+    };
+    BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str::<LoggedLine>(&line).ok())
+        .map(|line| match line.speaker {
+            Some(speaker) => format!("{}: {}", speaker, line.text),
+            None => line.text,
+        })
+        .collect()
+}
+
+/// The most recently written session log, optionally skipping one session
+fn latest_transcript_log(except: Option<&str>) -> Option<(String, i64, Vec<String>)> {
+    let (path, modified) = std::fs::read_dir(get_transcript_log_dir())
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+        .filter(|path| except.is_none() || path.file_stem().and_then(|s| s.to_str()) != except)
+        .filter_map(|path| {
+            let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+            Some((path, modified))
+        })
+        .max_by_key(|(_, modified)| *modified)?;
+
+    let session_id = path.file_stem()?.to_string_lossy().to_string();
+    let modified_ms = modified
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default();
+    Some((session_id, modified_ms, read_transcript_log(&path)))
+}
+
+fn now_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
+/// Whether the process that wrote the lock is still running; a pid reused by
+/// another program doesn't count
+fn lock_owner_alive(lock: &SessionLock) -> bool {
+    crate::process_cleanup::is_app_process(lock.pid)
+}
+
+/// Inspect the previous run's lock file. Call once at startup, before the
+/// heartbeat overwrites it.
+pub fn detect_unclean_shutdown() -> Option<RecoveredSession> {
+    let content = std::fs::read_to_string(get_lock_path()).ok()?;
+    let lock: SessionLock = serde_json::from_str(&content).ok()?;
+
+    if lock.pid == std::process::id() || lock_owner_alive(&lock) {
+        return None;
+    }
+
+    // The append-only log is complete; the mirror may be a heartbeat behind
+    let (session_id, transcript) = match latest_transcript_log(None) {
+        Some((session_id, modified, lines)) if modified >= lock.started_at && !lines.is_empty() => {
+            (Some(session_id), lines)
+        }
+        _ => {
+            let mirror = std::fs::read_to_string(get_recovery_transcript_path())
+                .ok()
+                .and_then(|c| serde_json::from_str(&c).ok())
+                .unwrap_or_default();
+            (None, mirror)
+        }
+    };
+
+    info!(
+        "Detected unclean shutdown of session started at {} ({} transcript lines)",
+        lock.started_at,
+        transcript.len()
+    );
+
+    Some(RecoveredSession {
+        session_id,
+        started_at: lock.started_at,
+        last_heartbeat: lock.heartbeat_at,
+        transcript,
+    })
+}
+
+/// Start the heartbeat thread that keeps the lock and transcript mirror fresh
+pub fn start_heartbeat(app_handle: AppHandle) {
+    let started_at = now_ms();
+    std::thread::spawn(move || {
+        // Start from "empty" so a crashed session's mirror survives until
+        // this run has transcript lines of its own
+        let mut last_written: Option<u64> = Some(0);
+        loop {
+            let stopped = STOPPED.lock().unwrap_or_else(|e| e.into_inner());
+            if *stopped {
+                break;
+            }
+            let lock = SessionLock {
+                pid: std::process::id(),
+                started_at,
+                heartbeat_at: now_ms(),
+            };
+            if let Ok(json) = serde_json::to_string(&lock) {
+                if let Err(e) = crate::persist::write_atomic(&get_lock_path(), json.as_bytes()) {
+                    error!("Failed to write session lock: {}", e);
+                }
+            }
+
+            // Mirror the transcript only when it changed since the last beat
+            let state = app_handle.state::<Arc<AppState>>();
+            let (marker, snapshot) = state
+                .transcript_lines
+                .lock()
+                .map(|lines| (lines.revision(), lines.texts()))
+                .unwrap_or_default();
+            if last_written != Some(marker) && !crate::encryption::is_enabled() {
+                if let Ok(json) = serde_json::to_string(&snapshot) {
+                    if crate::persist::write_atomic(&get_recovery_transcript_path(), json.as_bytes()).is_ok() {
+                        last_written = Some(marker);
+                    }
+                }
+            }
+            drop(stopped);
+
+            std::thread::sleep(HEARTBEAT_INTERVAL);
+        }
+    });
+}
+
+/// Stop the heartbeat and remove the lock and transcript mirror on a clean exit
+pub fn mark_clean_shutdown() {
+    let mut stopped = STOPPED.lock().unwrap_or_else(|e| e.into_inner());
+    *stopped = true;
+    let _ = std::fs::remove_file(get_lock_path());
+    let _ = std::fs::remove_file(get_recovery_transcript_path());
+}
+
+/// Store a recovered session for the frontend to restore or discard
+pub fn set_pending(app_handle: &AppHandle, recovered: RecoveredSession) {
+    let state = app_handle.state::<RecoveryState>();
+    if let Ok(mut pending) = state.pending.lock() {
+        *pending = Some(recovered);
+    };
+}
+
+/// Emit `recovery-available` if a crashed session is waiting. Called when the
+/// main window finishes loading so the frontend listener is in place.
+pub fn emit_if_pending(app_handle: &AppHandle) {
+    let state = app_handle.state::<RecoveryState>();
+    let pending = state.pending.lock().ok().and_then(|p| p.clone());
+    if let Some(recovered) = pending {
+        let _ = app_handle.emit("recovery-available", recovered);
+    }
+}
+
+/// Get the crashed session awaiting a decision, if any
+#[tauri::command]
+pub async fn get_recovery_info(
+    recovery: tauri::State<'_, RecoveryState>,
+) -> Result<Option<RecoveredSession>, String> {
+    let pending = recovery.pending.lock().map_err(|e| e.to_string())?;
+    Ok(pending.clone())
+}
+
+/// Load the recovered transcript back into the current session
+#[tauri::command]
+pub async fn restore_recovered_session(
+    state: tauri::State<'_, Arc<AppState>>,
+    recovery: tauri::State<'_, RecoveryState>,
+) -> Result<Vec<String>, String> {
+    let recovered = recovery
+        .pending
+        .lock()
+        .map_err(|e| e.to_string())?
+        .take()
+        .ok_or_else(|| "No session to recover".to_string())?;
+
+    let mut lines = state.transcript_lines.lock().map_err(|e| e.to_string())?;
+    lines.prepend(recovered.transcript);
+    Ok(lines.texts())
+}
+
+/// Reload the most recent session's transcript log into the current
+/// transcript, e.g. after a crash that left no lock file behind
+#[tauri::command]
+pub async fn recover_last_session(
+    state: tauri::State<'_, Arc<AppState>>,
+    recovery: tauri::State<'_, RecoveryState>,
+) -> Result<Option<RecoveredSession>, String> {
+    let current = crate::current_session_id(&state);
+    let Some((session_id, modified, transcript)) = latest_transcript_log(current.as_deref()) else {
+        return Ok(None);
+    };
+    if let Ok(mut pending) = recovery.pending.lock() {
+        *pending = None;
+    }
+
+    let mut lines = state.transcript_lines.lock().map_err(|e| e.to_string())?;
+    lines.prepend(transcript.clone());
+    info!("Recovered {} transcript lines from session {}", transcript.len(), session_id);
+
+    Ok(Some(RecoveredSession {
+        session_id: Some(session_id),
+        started_at: modified,
+        last_heartbeat: modified,
+        transcript,
+    }))
+}
+
+/// Drop the recovered transcript and start fresh
+#[tauri::command]
+pub async fn discard_recovered_session(recovery: tauri::State<'_, RecoveryState>) -> Result<(), String> {
+    let mut pending = recovery.pending.lock().map_err(|e| e.to_string())?;
+    *pending = None;
+    Ok(())
+}
@@ -0,0 +1,292 @@
+// Caption redaction
+//
+// When enabled, every caption is masked before it is emitted, stored or sent
+// anywhere: blocklisted words keep their first letters ("d***"), email
+// addresses and phone numbers become "[email]" and "[phone]". Partial
+// captions are masked too, so nothing slips through while someone is still
+// speaking.
+//
+// With `keep_original` the unredacted text of final captions is kept in
+// `redacted_captions`, encrypted with a key stored next to the settings that
+// only the current user can read. `get_unredacted_captions` decrypts it.
+use regex::{Captures, Regex, RegexBuilder};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+use tracing::warn;
+
+use crate::database::CaptionWord;
+use crate::{crypto, AppState};
+
+/// Digits a match needs before it is treated as a phone number
+const MIN_PHONE_DIGITS: usize = 9;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct RedactionSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub blocklist: Vec<String>, // Words or phrases, matched whole and ignoring case
+    #[serde(default)]
+    pub mask_emails: bool,
+    #[serde(default)]
+    pub mask_phone_numbers: bool,
+    #[serde(default)]
+    pub keep_original: bool, // Store the unredacted text of final captions, encrypted
+}
+
+#[derive(Clone)]
+struct Redactor {
+    blocklist: Option<Regex>,
+    email: Option<Regex>,
+    phone: Option<Regex>,
+}
+
+/// Redactor for the current settings, rebuilt when they change
+#[derive(Default)]
+pub struct RedactionState {
+    cache: Mutex<Option<(RedactionSettings, Redactor)>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OriginalCaption {
+    pub caption_id: String,
+    pub timestamp: i64,
+    pub text: String,
+}
+
+impl Redactor {
+    fn new(settings: &RedactionSettings) -> Result<Self, String> {
+        let words: Vec<String> = settings
+            .blocklist
+            .iter()
+            .map(|w| w.trim())
+            .filter(|w| !w.is_empty())
+            .map(regex::escape)
+            .collect();
+        let blocklist = if words.is_empty() {
+            None
+        } else {
+            let pattern = format!(r"\b(?:{})\b", words.join("|"));
+            Some(
+                RegexBuilder::new(&pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .map_err(|e| e.to_string())?,
+            )
+        };
+        let email = settings
+            .mask_emails
+            .then(|| Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}"))
+            .transpose()
+            .map_err(|e| e.to_string())?;
+        let phone = settings
+            .mask_phone_numbers
+            .then(|| Regex::new(r"\+?\(?\d[\d\s().-]{6,}\d"))
+            .transpose()
+            .map_err(|e| e.to_string())?;
+        Ok(Self { blocklist, email, phone })
+    }
+
+    fn mask_blocklist(&self, text: &str) -> String {
+        match &self.blocklist {
+            Some(regex) => regex
+                .replace_all(text, |caps: &Captures| {
+                    // Keep the first letter of each word
+                    let mut word_start = true;
+                    caps[0]
+                        .chars()
+                        .map(|c| {
+                            let masked = if word_start || c.is_whitespace() { c } else { '*' };
+                            word_start = c.is_whitespace();
+                            masked
+                        })
+                        .collect::<String>()
+                })
+                .into_owned(),
+            None => text.to_string(),
+        }
+    }
+
+    /// Masks personal data; returns whether anything matched
+    fn mask_pii(&self, text: &mut String) -> bool {
+        let mut found = false;
+        if let Some(regex) = &self.email {
+            if regex.is_match(text) {
+                *text = regex.replace_all(text, "[email]").into_owned();
+                found = true;
+            }
+        }
+        if let Some(regex) = &self.phone {
+            let masked = regex.replace_all(text, |caps: &Captures| {
+                if caps[0].chars().filter(char::is_ascii_digit).count() >= MIN_PHONE_DIGITS {
+                    found = true;
+                    "[phone]".to_string()
+                } else {
+                    caps[0].to_string()
+                }
+            });
+            *text = masked.into_owned();
+        }
+        found
+    }
+
+    /// Mask `text` and its word timings; returns whether anything changed
+    fn redact(&self, text: &mut String, words: &mut Vec<CaptionWord>) -> bool {
+        let mut masked = self.mask_blocklist(text);
+        let had_pii = self.mask_pii(&mut masked);
+        if masked == *text {
+            return false;
+        }
+        *text = masked;
+        if had_pii {
+            // Masked spans can't be mapped back onto individual words
+            words.clear();
+        } else {
+            for word in words.iter_mut() {
+                word.text = self.mask_blocklist(&word.text);
+            }
+        }
+        true
+    }
+}
+
+fn redactor(app_handle: &AppHandle) -> Option<Redactor> {
+    let settings = app_handle
+        .state::<Arc<AppState>>()
+        .settings
+        .lock()
+        .ok()?
+        .redaction
+        .clone()
+        .filter(|r| r.enabled)?;
+    let state = app_handle.state::<RedactionState>();
+    let mut cache = state.cache.lock().ok()?;
+    if cache.as_ref().map(|(cached, _)| cached != &settings).unwrap_or(true) {
+        match Redactor::new(&settings) {
+            Ok(redactor) => *cache = Some((settings, redactor)),
+            Err(e) => {
+                warn!("Invalid redaction settings: {}", e);
+                return None;
+            }
+        }
+    }
+    cache.as_ref().map(|(_, redactor)| redactor.clone())
+}
+
+/// Mask a caption in place. Returns the original text when something was
+/// masked.
+pub fn redact_caption(app_handle: &AppHandle, text: &mut String, words: &mut Vec<CaptionWord>) -> Option<String> {
+    let redactor = redactor(app_handle)?;
+    let original = text.clone();
+    redactor.redact(text, words).then_some(original)
+}
+
+fn key_path() -> std::path::PathBuf {
+    crate::get_config_dir().join("redaction.key")
+}
+
+/// Keep the unredacted text of a final caption, if the settings ask for it
+pub fn keep_original(app_handle: &AppHandle, caption_id: &str, timestamp: i64, original: &str) {
+    let state = app_handle.state::<Arc<AppState>>();
+    let keep = state
+        .settings
+        .lock()
+        .map(|s| s.redaction.as_ref().is_some_and(|r| r.keep_original))
+        .unwrap_or(false);
+    if !keep {
+        return;
+    }
+    let session_id = crate::current_session_id(&state);
+    let result = crypto::load_or_create_key(&key_path())
+        .and_then(|key| crypto::encrypt(&key, original.as_bytes()))
+        .and_then(|sealed| {
+            let conn = state.db()?;
+            conn.execute(
+                "INSERT OR REPLACE INTO redacted_captions (caption_id, session_id, timestamp, original)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![caption_id, session_id, timestamp, sealed],
+            )
+            .map_err(|e| e.to_string())
+        });
+    if let Err(e) = result {
+        warn!("Failed to keep unredacted caption: {}", e);
+    }
+}
+
+/// Unredacted text of a session's masked captions, oldest first
+#[tauri::command]
+pub async fn get_unredacted_captions(
+    state: tauri::State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<Vec<OriginalCaption>, String> {
+    let sealed: Vec<(String, i64, Vec<u8>)> = {
+        let conn = state.db()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT caption_id, timestamp, original FROM redacted_captions
+                 WHERE session_id = ?1 ORDER BY timestamp",
+            )
+            .map_err(|e| format!("Prepare failed: {}", e))?;
+        let rows = stmt
+            .query_map(params![session_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .map_err(|e| format!("Query failed: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        rows
+    };
+    if sealed.is_empty() {
+        return Ok(Vec::new());
+    }
+    let key = crypto::load_or_create_key(&key_path())?;
+    sealed
+        .into_iter()
+        .map(|(caption_id, timestamp, sealed)| {
+            let text = crypto::decrypt(&key, &sealed)?;
+            Ok(OriginalCaption {
+                caption_id,
+                timestamp,
+                text: String::from_utf8_lossy(&text).into_owned(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact() {
+        let redactor = Redactor::new(&RedactionSettings {
+            enabled: true,
+            blocklist: vec!["darn".to_string(), "project falcon".to_string()],
+            mask_emails: true,
+            mask_phone_numbers: true,
+            keep_original: false,
+        })
+        .unwrap();
+
+        let word = |text: &str| CaptionWord {
+            text: text.to_string(),
+            start_ms: 0,
+            end_ms: 0,
+            confidence: None,
+        };
+        let mut text = "Darn, Project Falcon slipped again".to_string();
+        let mut words = vec![word("Darn"), word("slipped")];
+        assert!(redactor.redact(&mut text, &mut words));
+        assert_eq!(text, "D***, P****** F***** slipped again");
+        assert_eq!(words[0].text, "D***");
+
+        let mut text = "mail jo@example.com or call +1 (555) 010-2030 before 2024-05".to_string();
+        let mut words = vec![word("mail")];
+        assert!(redactor.redact(&mut text, &mut words));
+        assert_eq!(text, "mail [email] or call [phone] before 2024-05");
+        assert!(words.is_empty());
+
+        let mut text = "nothing to hide".to_string();
+        assert!(!redactor.redact(&mut text, &mut Vec::new()));
+    }
+}
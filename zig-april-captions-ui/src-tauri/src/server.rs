@@ -0,0 +1,509 @@
+// Local caption streaming server
+//
+// An opt-in WebSocket endpoint (`ws://127.0.0.1:<port>/ws?token=<token>`)
+// that re-broadcasts caption and session events so OBS overlays, note apps
+// and dashboards can follow along live. The same listener also serves the
+// REST API under `/api` (see api.rs), the OBS browser source at `/obs`
+// (see obs.rs), the second-screen viewer at `/viewer` (see viewer.rs), the
+// MCP endpoint at `/mcp` (see mcp.rs) and caption ingestion at `/ingest`
+// (see ingest.rs).
+// Clients may authenticate with an `Authorization: Bearer <token>` header
+// instead of the query parameter.
+//
+// There are three tokens. The server token opens everything except
+// ingestion. The viewer token, which goes into the QR code shown to a room,
+// only opens `/viewer` and the read-only `/ws` stream. The ingest token only
+// opens `/ingest`, so a phone feeding captions can't read or control the
+// rest. Clients that can't speak WebSocket can read
+// the same events as Server-Sent Events from `/api/captions/stream`, with the
+// envelope as the data and its event name as the SSE event.
+//
+// Every message is a JSON text frame with the same envelope:
+//
+//   { "event": "<name>", "timestamp": <unix ms>, "data": { ... } }
+//
+// Events:
+//   hello    sent once on connect
+//            { "app": "zigy", "version": "1.0.5", "running": true }
+//   caption  a recognized caption; partials are replaced by the next
+//            partial/final until a final arrives
+//            { "captionType": "partial" | "final", "text": "..." }
+//   status   recognizer status change
+//            { "status": "ready" | "listening" | "warning" | "error" | "stopped",
+//              "message": "..." | null, "source": "mic" | "monitor" | null }
+//   session  captioning started or stopped by the user
+//            { "state": "started" | "stopped", "audioSource": "mic" | null }
+//
+// Clients that fall behind skip missed events rather than being disconnected.
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Query, Request, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::Router;
+use futures_util::stream::{self, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Manager};
+use tokio::sync::{broadcast, watch};
+use tracing::{debug, error, info, warn};
+
+use crate::{AppState, CaptionEvent};
+
+/// Events buffered per client before slow clients start skipping
+const EVENT_BUFFER: usize = 256;
+
+/// Time open connections get to close before the old listener is aborted
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ServerSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub allow_lan: bool, // Bind to all interfaces instead of localhost only
+    #[serde(default)]
+    pub token: String, // Generated on first start when empty
+    #[serde(default)]
+    pub viewer_token: String, // Read-only access for /viewer and /ws
+    #[serde(default)]
+    pub ingest_token: String, // The only token /ingest accepts
+    #[serde(default)]
+    pub mcp_enabled: bool, // Expose the MCP endpoint at /mcp
+    #[serde(default)]
+    pub ingest_enabled: bool, // Accept captions from external sources at /ingest
+}
+
+fn default_port() -> u16 {
+    7890
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: default_port(),
+            allow_lan: false,
+            token: String::new(),
+            viewer_token: String::new(),
+            ingest_token: String::new(),
+            mcp_enabled: false,
+            ingest_enabled: false,
+        }
+    }
+}
+
+struct RunningServer {
+    settings: ServerSettings,
+    addr: SocketAddr,
+    shutdown: watch::Sender<()>, // Dropping it stops the listener and open sockets
+    task: JoinHandle<()>, // Serves until shutdown; the port is free once it ends
+}
+
+/// Managed state: the event fan-out channel and the running listener, if any
+pub struct ServerState {
+    events: broadcast::Sender<String>,
+    running: Mutex<Option<RunningServer>>,
+}
+
+impl Default for ServerState {
+    fn default() -> Self {
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+        Self {
+            events,
+            running: Mutex::new(None),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ServerContext {
+    pub app_handle: AppHandle,
+    token: String,
+    viewer_token: String,
+    ingest_token: String,
+    shutdown: watch::Receiver<()>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ServerStatus {
+    pub running: bool,
+    pub url: Option<String>,
+    pub api_url: Option<String>,
+    pub token: String,
+    pub viewer_token: String,
+    pub ingest_token: String,
+    pub clients: usize,
+}
+
+#[derive(Deserialize)]
+struct AuthQuery {
+    token: Option<String>,
+}
+
+fn envelope(event: &str, data: serde_json::Value) -> String {
+    serde_json::json!({
+        "event": event,
+        "timestamp": std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as i64,
+        "data": data,
+    })
+    .to_string()
+}
+
+/// Broadcast an event to connected clients (no-op when nobody is listening)
+pub fn publish(app_handle: &AppHandle, event: &str, data: serde_json::Value) {
+    let server = app_handle.state::<ServerState>();
+    if server.events.receiver_count() > 0 {
+        let _ = server.events.send(envelope(event, data));
+    }
+}
+
+/// Map a recognizer event onto the `caption` / `status` stream events
+pub fn publish_caption_event(app_handle: &AppHandle, event: &CaptionEvent) {
+    if event.event_type == "caption" {
+        publish(
+            app_handle,
+            "caption",
+            serde_json::json!({
+                "id": event.id,
+                "captionType": event.caption_type,
+                "text": event.text,
+                "speaker": event.speaker,
+            }),
+        );
+    } else {
+        publish(
+            app_handle,
+            "status",
+            serde_json::json!({
+                "status": event.event_type,
+                "message": event.message,
+                "source": event.source,
+            }),
+        );
+    }
+}
+
+/// Compare a presented token without leaking where it differs through timing
+pub fn token_matches(presented: &str, expected: &str) -> bool {
+    let (a, b) = (presented.as_bytes(), expected.as_bytes());
+    if a.len() != b.len() || b.is_empty() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Tokens that open `path`
+fn accepted_tokens<'a>(ctx: &'a ServerContext, path: &str) -> Vec<&'a str> {
+    if path == "/ingest" || path.starts_with("/ingest/") {
+        vec![&ctx.ingest_token]
+    } else if path == "/viewer" || path == "/ws" {
+        vec![&ctx.token, &ctx.viewer_token]
+    } else {
+        vec![&ctx.token]
+    }
+}
+
+fn authorized(ctx: &ServerContext, path: &str, headers: &HeaderMap, query_token: Option<&str>) -> bool {
+    let bearer = headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    let Some(presented) = bearer.or(query_token) else {
+        return false;
+    };
+    // Check every accepted token so the time taken doesn't tell which matched
+    accepted_tokens(ctx, path)
+        .into_iter()
+        .fold(false, |ok, expected| token_matches(presented, expected) | ok)
+}
+
+/// Reject requests without a token that opens the requested path
+async fn require_token(
+    State(ctx): State<ServerContext>,
+    Query(query): Query<AuthQuery>,
+    headers: HeaderMap,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !authorized(&ctx, request.uri().path(), &headers, query.token.as_deref()) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    next.run(request).await
+}
+
+async fn ws_handler(ws: WebSocketUpgrade, State(ctx): State<ServerContext>) -> Response {
+    let events = ctx.app_handle.state::<ServerState>().events.subscribe();
+    ws.on_upgrade(move |socket| stream_events(socket, ctx, events))
+}
+
+fn hello(ctx: &ServerContext) -> String {
+    let running = ctx
+        .app_handle
+        .state::<Arc<AppState>>()
+        .process
+        .lock()
+        .map(|p| p.is_some())
+        .unwrap_or(false);
+    envelope(
+        "hello",
+        serde_json::json!({
+            "app": "zigy",
+            "version": ctx.app_handle.package_info().version.to_string(),
+            "running": running,
+        }),
+    )
+}
+
+async fn stream_events(mut socket: WebSocket, ctx: ServerContext, mut events: broadcast::Receiver<String>) {
+    let hello = hello(&ctx);
+    if socket.send(Message::Text(hello.into())).await.is_err() {
+        return;
+    }
+    debug!("Streaming client connected");
+
+    let mut shutdown = ctx.shutdown.clone();
+    loop {
+        tokio::select! {
+            _ = shutdown.changed() => break,
+            event = events.recv() => match event {
+                Ok(text) => {
+                    if socket.send(Message::Text(text.into())).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    debug!("Streaming client lagged, skipped {} event(s)", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                // Clients only listen; anything but a close frame is ignored
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+    debug!("Streaming client disconnected");
+}
+
+fn sse_event(text: String) -> Event {
+    let name = serde_json::from_str::<serde_json::Value>(&text)
+        .ok()
+        .and_then(|v| v["event"].as_str().map(String::from))
+        .unwrap_or_else(|| "message".to_string());
+    Event::default().event(name).data(text)
+}
+
+/// The event stream as Server-Sent Events, for clients without WebSocket support
+pub async fn caption_stream(State(ctx): State<ServerContext>) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let events = ctx.app_handle.state::<ServerState>().events.subscribe();
+    let hello = stream::once(std::future::ready(Ok(sse_event(hello(&ctx)))));
+    let live = stream::unfold((events, ctx.shutdown.clone()), |(mut events, mut shutdown)| async move {
+        loop {
+            tokio::select! {
+                _ = shutdown.changed() => return None,
+                event = events.recv() => match event {
+                    Ok(text) => return Some((Ok(sse_event(text)), (events, shutdown))),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        debug!("SSE client lagged, skipped {} event(s)", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                },
+            }
+        }
+    });
+    Sse::new(hello.chain(live)).keep_alive(KeepAlive::default())
+}
+
+fn router(ctx: ServerContext) -> Router {
+    Router::new()
+        .route("/ws", get(ws_handler))
+        .route("/obs", get(crate::obs::browser_source))
+        .route("/viewer", get(crate::viewer::viewer_page))
+        .route("/mcp", post(crate::mcp::mcp_handler))
+        .route("/ingest", post(crate::ingest::ingest_http))
+        .route("/ingest/ws", get(crate::ingest::ingest_ws))
+        .nest("/api", crate::api::routes())
+        .route_layer(middleware::from_fn_with_state(ctx.clone(), require_token))
+        .with_state(ctx)
+}
+
+pub fn current_settings(app_handle: &AppHandle) -> ServerSettings {
+    app_handle
+        .state::<Arc<AppState>>()
+        .settings
+        .lock()
+        .ok()
+        .and_then(|s| s.server.clone())
+        .unwrap_or_default()
+}
+
+/// Fill in the tokens the first time the server is enabled and persist them
+fn ensure_token(app_handle: &AppHandle) -> Result<ServerSettings, String> {
+    let state = app_handle.state::<Arc<AppState>>();
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    let server = settings.server.get_or_insert_with(ServerSettings::default);
+    let mut generated = false;
+    for token in [&mut server.token, &mut server.viewer_token, &mut server.ingest_token] {
+        if token.is_empty() {
+            *token = uuid::Uuid::new_v4().simple().to_string();
+            generated = true;
+        }
+    }
+    if generated {
+        crate::persist::persist_json(&crate::get_settings_path(), &*settings)
+            .map_err(|e| format!("Failed to save settings: {}", e))?;
+    }
+    Ok(settings.server.clone().unwrap_or_default())
+}
+
+/// Stop the listener and wait until it has released its port
+async fn stop(server: &ServerState) {
+    let Some(running) = server.running.lock().ok().and_then(|mut r| r.take()) else {
+        return;
+    };
+    drop(running.shutdown);
+    let mut task = running.task;
+    if tokio::time::timeout(SHUTDOWN_TIMEOUT, &mut task).await.is_err() {
+        warn!("Caption server on {} didn't shut down in time, aborting it", running.addr);
+        task.abort();
+        let _ = task.await;
+    }
+    info!("Caption server on {} stopped", running.addr);
+}
+
+async fn start(app_handle: &AppHandle, settings: ServerSettings) -> Result<(), String> {
+    let server = app_handle.state::<ServerState>();
+    stop(&server).await;
+
+    let ip = if settings.allow_lan { [0, 0, 0, 0] } else { [127, 0, 0, 1] };
+    let addr = SocketAddr::from((ip, settings.port));
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .map_err(|e| format!("Failed to bind {}: {}", addr, e))?;
+
+    let (shutdown, mut shutdown_rx) = watch::channel(());
+    let app = router(ServerContext {
+        app_handle: app_handle.clone(),
+        token: settings.token.clone(),
+        viewer_token: settings.viewer_token.clone(),
+        ingest_token: settings.ingest_token.clone(),
+        shutdown: shutdown_rx.clone(),
+    });
+    let task = tauri::async_runtime::spawn(async move {
+        let result = axum::serve(listener, app)
+            .with_graceful_shutdown(async move {
+                let _ = shutdown_rx.changed().await;
+            })
+            .await;
+        if let Err(e) = result {
+            error!("Caption server stopped with error: {}", e);
+        }
+    });
+
+    info!("Caption server listening on {} (ws: /ws, REST: /api)", addr);
+    *server.running.lock().map_err(|e| e.to_string())? = Some(RunningServer {
+        settings,
+        addr,
+        shutdown,
+        task,
+    });
+    Ok(())
+}
+
+/// Start, stop or restart the server to match the saved settings
+pub async fn apply_settings(app_handle: &AppHandle) -> Result<(), String> {
+    let settings = current_settings(app_handle);
+    let server = app_handle.state::<ServerState>();
+
+    if !settings.enabled {
+        stop(&server).await;
+        return Ok(());
+    }
+
+    let settings = ensure_token(app_handle)?;
+    let unchanged = server
+        .running
+        .lock()
+        .map(|r| r.as_ref().map(|r| r.settings == settings).unwrap_or(false))
+        .unwrap_or(false);
+    if unchanged {
+        return Ok(());
+    }
+    start(app_handle, settings).await
+}
+
+/// Start the server at launch if it was left enabled
+pub fn start_if_enabled(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = apply_settings(&app_handle).await {
+            warn!("Caption server not started: {}", e);
+        }
+    });
+}
+
+/// Base `http://host:port` of the running server, if any
+pub fn local_url(app_handle: &AppHandle) -> Option<String> {
+    let server = app_handle.state::<ServerState>();
+    let running = server.running.lock().ok()?;
+    running.as_ref().map(|r| format!("http://{}", r.addr))
+}
+
+/// Access token clients must present
+pub fn token(app_handle: &AppHandle) -> String {
+    current_settings(app_handle).token
+}
+
+#[tauri::command]
+pub async fn get_server_status(
+    app_handle: AppHandle,
+    server: tauri::State<'_, ServerState>,
+) -> Result<ServerStatus, String> {
+    let running = server.running.lock().map_err(|e| e.to_string())?;
+    let settings = current_settings(&app_handle);
+    Ok(ServerStatus {
+        running: running.is_some(),
+        url: running.as_ref().map(|r| format!("ws://{}/ws", r.addr)),
+        api_url: running.as_ref().map(|r| format!("http://{}/api", r.addr)),
+        token: settings.token,
+        viewer_token: settings.viewer_token,
+        ingest_token: settings.ingest_token,
+        clients: server.events.receiver_count(),
+    })
+}
+
+/// Replace a token ("server" by default, "viewer" or "ingest"), disconnecting
+/// clients that used the old one
+#[tauri::command]
+pub async fn regenerate_server_token(app_handle: AppHandle, kind: Option<String>) -> Result<String, String> {
+    fn token_of<'a>(server: &'a mut ServerSettings, kind: &str) -> Result<&'a mut String, String> {
+        match kind {
+            "server" => Ok(&mut server.token),
+            "viewer" => Ok(&mut server.viewer_token),
+            "ingest" => Ok(&mut server.ingest_token),
+            _ => Err(format!("Unknown token kind: {}", kind)),
+        }
+    }
+    let kind = kind.unwrap_or_else(|| "server".to_string());
+    {
+        let state = app_handle.state::<Arc<AppState>>();
+        let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+        let server = settings.server.get_or_insert_with(ServerSettings::default);
+        token_of(server, &kind)?.clear();
+    }
+    let mut settings = ensure_token(&app_handle)?;
+    apply_settings(&app_handle).await?;
+    token_of(&mut settings, &kind).cloned()
+}
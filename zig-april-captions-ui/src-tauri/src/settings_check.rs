@@ -0,0 +1,179 @@
+// Settings validation and repair
+//
+// settings.json is edited by hand now and then, and older versions wrote
+// values newer ones don't accept. Rather than dropping the whole file for
+// defaults when one field doesn't parse, `load` keeps every field that does
+// and resets only the others, then resets values that parse but make no
+// sense (a 3px font, an unknown theme). Each reset is reported as a
+// SettingsWarning.
+//
+// `validate_settings` returns the warnings from launch plus the problems in
+// the current settings; with `repair` it also resets what can be reset and
+// saves. A model path that doesn't exist is only reported, since the model
+// may be on a drive that isn't connected.
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+use tracing_subscriber::filter::LevelFilter;
+
+use crate::{persist, AppState, Settings};
+
+/// Font sizes the settings slider offers
+const FONT_SIZES: std::ops::RangeInclusive<u32> = 14..=48;
+
+const THEMES: &[&str] = &["light", "dark", "system"];
+const AUDIO_SOURCES: &[&str] = &["mic", "monitor", "both"];
+const LANGUAGES: &[&str] = &["en", "vi"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SettingsWarning {
+    pub field: String, // Top-level settings key, "" for the whole file
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>, // The rejected value
+    pub reset_to_default: bool, // Replaced by the default (or will be, on repair)
+}
+
+fn warning(field: &str, message: impl Into<String>, value: Option<Value>, reset_to_default: bool) -> SettingsWarning {
+    SettingsWarning {
+        field: field.to_string(),
+        message: message.into(),
+        value,
+        reset_to_default,
+    }
+}
+
+/// Settings from a parsed file, keeping each field that deserializes
+fn from_value(value: Value) -> (Settings, Vec<SettingsWarning>) {
+    let Value::Object(fields) = value else {
+        let message = "Settings file is not a JSON object, using defaults";
+        return (Settings::default(), vec![warning("", message, None, true)]);
+    };
+    if let Ok(settings) = serde_json::from_value::<Settings>(Value::Object(fields.clone())) {
+        return (settings, Vec::new());
+    }
+
+    let mut warnings = Vec::new();
+    let mut merged = serde_json::to_value(Settings::default()).unwrap_or_else(|_| Value::Object(Default::default()));
+    for (key, field) in fields {
+        let mut candidate = merged.clone();
+        candidate[key.as_str()] = field.clone();
+        if serde_json::from_value::<Settings>(candidate.clone()).is_ok() {
+            merged = candidate;
+        } else {
+            warnings.push(warning(&key, "Invalid value, reset to default", Some(field), true));
+        }
+    }
+    let settings = serde_json::from_value(merged).unwrap_or_default();
+    (settings, warnings)
+}
+
+/// Reset values that parse but aren't usable; reports every problem found
+pub fn check(settings: &mut Settings) -> Vec<SettingsWarning> {
+    let defaults = Settings::default();
+    let mut warnings = Vec::new();
+
+    if !FONT_SIZES.contains(&settings.font_size) {
+        let message = format!(
+            "Font size must be between {} and {}",
+            FONT_SIZES.start(),
+            FONT_SIZES.end()
+        );
+        warnings.push(warning("font_size", message, Some(settings.font_size.into()), true));
+        settings.font_size = defaults.font_size;
+    }
+    if !THEMES.contains(&settings.theme.as_str()) {
+        let value = Some(settings.theme.clone().into());
+        warnings.push(warning("theme", format!("Unknown theme, expected one of {}", THEMES.join(", ")), value, true));
+        settings.theme = defaults.theme;
+    }
+    if !AUDIO_SOURCES.contains(&settings.audio_source.as_str()) {
+        let value = Some(settings.audio_source.clone().into());
+        let message = format!("Unknown audio source, expected one of {}", AUDIO_SOURCES.join(", "));
+        warnings.push(warning("audio_source", message, value, true));
+        settings.audio_source = defaults.audio_source;
+    }
+    if !LANGUAGES.contains(&settings.language.as_str()) {
+        let value = Some(settings.language.clone().into());
+        let message = format!("Unsupported language, expected one of {}", LANGUAGES.join(", "));
+        warnings.push(warning("language", message, value, true));
+        settings.language = defaults.language;
+    }
+    if settings.log_level.parse::<LevelFilter>().is_err() {
+        let value = Some(settings.log_level.clone().into());
+        warnings.push(warning("log_level", "Unknown log level", value, true));
+        settings.log_level = defaults.log_level;
+    }
+    if !settings.model_path.is_empty() && !Path::new(&settings.model_path).exists() {
+        let value = Some(settings.model_path.clone().into());
+        warnings.push(warning("model_path", "Model file not found", value, false));
+    }
+    warnings
+}
+
+/// Load settings from `path`, repairing what's invalid field by field
+pub fn load(path: &Path) -> (Settings, Vec<SettingsWarning>) {
+    let (mut settings, mut warnings) = match persist::load_json::<Value>(path) {
+        Some(value) => from_value(value),
+        None if path.exists() => {
+            let message = "Settings file is unreadable, using defaults";
+            (Settings::default(), vec![warning("", message, None, true)])
+        }
+        None => (Settings::default(), Vec::new()),
+    };
+    warnings.extend(check(&mut settings));
+    (settings, warnings)
+}
+
+/// Problems found in the settings at launch and in the current ones; with
+/// `repair`, the current ones are fixed and saved
+#[tauri::command]
+pub async fn validate_settings(app_handle: AppHandle, repair: Option<bool>) -> Result<Vec<SettingsWarning>, String> {
+    let state = app_handle.state::<Arc<AppState>>();
+    let mut warnings = state.settings_warnings.lock().map_err(|e| e.to_string())?.clone();
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?.clone();
+    let found = check(&mut settings);
+    if repair.unwrap_or(false) {
+        let changed = found.iter().any(|w| w.reset_to_default);
+        // Saving also replaces a file that only loaded after repairs
+        if changed || !warnings.is_empty() {
+            crate::save_settings(app_handle.clone(), app_handle.state(), settings).await?;
+        }
+        state.settings_warnings.lock().map_err(|e| e.to_string())?.clear();
+        warnings.clear();
+    }
+    for w in found {
+        // A missing model is already reported from launch
+        if !warnings.iter().any(|seen| seen.field == w.field && seen.message == w.message) {
+            warnings.push(w);
+        }
+    }
+    Ok(warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repairs_per_field() {
+        let value = serde_json::json!({
+            "model_path": "",
+            "audio_source": "monitor",
+            "font_size": "huge",
+            "theme": "solarized",
+            "language": "vi",
+            "record_audio": true,
+        });
+        let (mut settings, mut warnings) = from_value(value);
+        warnings.extend(check(&mut settings));
+        assert_eq!(settings.audio_source, "monitor");
+        assert_eq!(settings.language, "vi");
+        assert!(settings.record_audio);
+        assert_eq!((settings.font_size, settings.theme.as_str()), (24, "dark"));
+        let fields: Vec<&str> = warnings.iter().map(|w| w.field.as_str()).collect();
+        assert_eq!(fields, vec!["font_size", "theme"]);
+    }
+}
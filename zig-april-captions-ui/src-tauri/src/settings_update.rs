@@ -0,0 +1,325 @@
+// Partial settings updates and change events
+//
+// Windows used to keep a whole Settings object each and send it back with
+// `save_settings`, so two windows saving at once overwrote each other's
+// changes. `update_setting` changes a single value by its dotted key path
+// ("font_size", "ai.model") under the settings lock, and every save emits
+// `settings-changed` with the values that changed, so each window can patch
+// its copy instead of reloading.
+//
+// Secrets (SECRET_PATHS) never leave the backend: windows and change events
+// see SECRET_MASK in their place, and a save that sends the mask back keeps
+// the stored value. Secrets inside lists (webhooks, integrations) are matched
+// to the stored entry with the same id.
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::warn;
+
+use crate::{settings_check, AppState, Settings};
+
+/// Shown in place of a secret that is set
+pub const SECRET_MASK: &str = "••••••••";
+
+/// Settings the webview only ever sees masked; `*` stands for every entry of a list
+const SECRET_PATHS: &[&str] = &[
+    "ai.api_key",
+    "mqtt.password",
+    "notion.token",
+    "server.token",
+    "server.viewer_token",
+    "server.ingest_token",
+    "webhooks.*.secret",
+    "integrations.*.webhook_url",
+];
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SettingChange {
+    pub key_path: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct SettingsChanged {
+    changes: Vec<SettingChange>,
+}
+
+/// Leaf values that differ between `old` and `new`; lists count as one value
+fn diff(prefix: &str, old: &Value, new: &Value, changes: &mut Vec<SettingChange>) {
+    match (old, new) {
+        (Value::Object(old_fields), Value::Object(new_fields)) => {
+            let mut keys: Vec<&String> = old_fields.keys().chain(new_fields.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let key_path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+                let old = old_fields.get(key).unwrap_or(&Value::Null);
+                let new = new_fields.get(key).unwrap_or(&Value::Null);
+                diff(&key_path, old, new, changes);
+            }
+        }
+        _ if old != new => changes.push(SettingChange {
+            key_path: prefix.to_string(),
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        _ => {}
+    }
+}
+
+/// Set the value at a dotted key path, creating groups that are unset
+fn set_path(root: &mut Value, key_path: &str, value: Value) -> Result<(), String> {
+    let mut target = root;
+    let mut keys = key_path.split('.').peekable();
+    while let Some(key) = keys.next() {
+        if key.is_empty() {
+            return Err(format!("Invalid setting key {}", key_path));
+        }
+        if target.is_null() {
+            *target = Value::Object(Map::new());
+        }
+        let Value::Object(fields) = target else {
+            return Err(format!("{} is not a group of settings", key_path));
+        };
+        if keys.peek().is_none() {
+            fields.insert(key.to_string(), value);
+            return Ok(());
+        }
+        target = fields.entry(key.to_string()).or_insert(Value::Null);
+    }
+    Ok(())
+}
+
+fn get_path<'a>(root: &'a Value, key_path: &str) -> Option<&'a Value> {
+    key_path.split('.').try_fold(root, |value, key| value.get(key))
+}
+
+/// Every value at `keys`, stepping into each entry of a list at `*`
+fn values_at<'a>(value: &'a mut Value, keys: &[&str], found: &mut Vec<&'a mut Value>) {
+    match keys.split_first() {
+        None => found.push(value),
+        Some((&"*", rest)) => {
+            if let Value::Array(items) = value {
+                for item in items {
+                    values_at(item, rest, found);
+                }
+            }
+        }
+        Some((key, rest)) => {
+            if let Some(inner) = value.get_mut(*key) {
+                values_at(inner, rest, found);
+            }
+        }
+    }
+}
+
+/// Mask the secrets inside `value`, the setting at `key_path`
+fn mask(key_path: &str, value: &mut Value) {
+    let prefix: Vec<&str> = key_path.split('.').filter(|k| !k.is_empty()).collect();
+    for secret in SECRET_PATHS {
+        let keys: Vec<&str> = secret.split('.').collect();
+        let Some(rest) = keys.strip_prefix(prefix.as_slice()) else {
+            continue;
+        };
+        let mut found = Vec::new();
+        values_at(value, rest, &mut found);
+        for inner in found {
+            if inner.as_str().is_some_and(|s| !s.is_empty()) {
+                *inner = Value::String(SECRET_MASK.to_string());
+            }
+        }
+    }
+}
+
+/// Blank every secret in serialized settings, for copies that leave the machine
+pub fn strip_secrets(settings: &mut Value) {
+    for secret in SECRET_PATHS {
+        let keys: Vec<&str> = secret.split('.').collect();
+        let mut found = Vec::new();
+        values_at(settings, &keys, &mut found);
+        for inner in found.into_iter().filter(|v| v.is_string()) {
+            *inner = Value::String(String::new());
+        }
+    }
+}
+
+/// Put the stored secret back wherever `replace(stored, incoming)` holds at `keys`
+fn restore(stored: &Value, incoming: &mut Value, keys: &[&str], replace: fn(&Value, &Value) -> bool) -> bool {
+    match keys.split_first() {
+        None if replace(stored, incoming) => {
+            *incoming = stored.clone();
+            true
+        }
+        None => false,
+        Some((&"*", rest)) => {
+            let (Value::Array(items), Some(stored_items)) = (incoming, stored.as_array()) else {
+                return false;
+            };
+            let mut restored = false;
+            for item in items {
+                let id = item.get("id").filter(|id| !id.is_null());
+                if let Some(previous) = stored_items.iter().find(|s| id.is_some() && s.get("id") == id) {
+                    restored |= restore(previous, item, rest, replace);
+                }
+            }
+            restored
+        }
+        Some((key, rest)) => match incoming.get_mut(*key) {
+            Some(inner) => restore(stored.get(*key).unwrap_or(&Value::Null), inner, rest, replace),
+            None => false,
+        },
+    }
+}
+
+/// Settings as windows see them, with secrets masked
+pub fn redacted(settings: &Settings) -> Settings {
+    let Ok(mut json) = serde_json::to_value(settings) else {
+        return Settings::default();
+    };
+    mask("", &mut json);
+    serde_json::from_value(json).unwrap_or_default()
+}
+
+/// Settings sent back by a window, with masked secrets taken from `current`
+pub fn unmask(current: &Settings, incoming: Settings) -> Settings {
+    let (Ok(stored), Ok(mut json)) = (serde_json::to_value(current), serde_json::to_value(&incoming)) else {
+        return incoming;
+    };
+    let mut restored = false;
+    for secret in SECRET_PATHS {
+        let keys: Vec<&str> = secret.split('.').collect();
+        restored |= restore(&stored, &mut json, &keys, |_, incoming| incoming.as_str() == Some(SECRET_MASK));
+    }
+    if !restored {
+        return incoming;
+    }
+    serde_json::from_value(json).unwrap_or(incoming)
+}
+
+/// Fill secrets that `strip_secrets` blanked in `settings` from `previous`,
+/// where it holds them; returns whether any were filled
+pub fn refill_secrets(previous: &Value, settings: &mut Value) -> bool {
+    let mut refilled = false;
+    for secret in SECRET_PATHS {
+        let keys: Vec<&str> = secret.split('.').collect();
+        refilled |= restore(previous, settings, &keys, |stored, blank| {
+            blank.as_str() == Some("") && stored.as_str().is_some_and(|s| !s.is_empty())
+        });
+    }
+    refilled
+}
+
+/// Settings with one value replaced; fails if the result isn't valid
+fn updated(settings: &Settings, key_path: &str, value: Value) -> Result<Settings, String> {
+    let mut json = serde_json::to_value(settings).map_err(|e| e.to_string())?;
+    set_path(&mut json, key_path, value.clone())?;
+    let updated: Settings =
+        serde_json::from_value(json).map_err(|e| format!("Invalid value for {}: {}", key_path, e))?;
+
+    // Unknown keys are dropped when deserializing, and so they don't round-trip
+    let stored = serde_json::to_value(&updated).map_err(|e| e.to_string())?;
+    if get_path(&stored, key_path).unwrap_or(&Value::Null) != &value {
+        return Err(format!("Unknown setting {}", key_path));
+    }
+    let field = key_path.split('.').next().unwrap_or_default();
+    let rejected = settings_check::check(&mut updated.clone())
+        .into_iter()
+        .find(|w| w.field == field && w.reset_to_default);
+    if let Some(w) = rejected {
+        return Err(format!("Invalid value for {}: {}", key_path, w.message));
+    }
+    Ok(updated)
+}
+
+/// Emit `settings-changed` to every window if anything changed
+pub fn emit_changes(app_handle: &AppHandle, previous: &Settings, settings: &Settings) {
+    let (Ok(old), Ok(new)) = (serde_json::to_value(previous), serde_json::to_value(settings)) else {
+        warn!("Could not compare settings");
+        return;
+    };
+    let mut changes = Vec::new();
+    diff("", &old, &new, &mut changes);
+    for change in &mut changes {
+        mask(&change.key_path, &mut change.old);
+        mask(&change.key_path, &mut change.new);
+    }
+    if !changes.is_empty() {
+        let _ = app_handle.emit("settings-changed", SettingsChanged { changes });
+    }
+}
+
+/// Change one setting by its dotted key path and save; returns the new settings
+#[tauri::command]
+pub async fn update_setting(app_handle: AppHandle, key_path: String, value: Value) -> Result<Settings, String> {
+    let state = app_handle.state::<Arc<AppState>>();
+    let (previous, settings) = {
+        let mut guard = state.settings.lock().map_err(|e| e.to_string())?;
+        let settings = unmask(&guard, updated(&guard, &key_path, value)?);
+        crate::persist_settings(&settings)?;
+        (std::mem::replace(&mut *guard, settings.clone()), settings)
+    };
+    crate::apply_saved_settings(&app_handle, &state, &previous, &settings).await;
+    Ok(redacted(&settings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_update_and_diff() {
+        let settings = Settings::default();
+        let changed = updated(&settings, "ai.model", json!("gemini-2.5-pro")).unwrap();
+        assert_eq!(changed.ai.as_ref().unwrap().model, "gemini-2.5-pro");
+        assert!(updated(&settings, "font_size", json!("big")).is_err());
+        assert!(updated(&settings, "font_size", json!(2)).is_err());
+        assert!(updated(&settings, "no_such_setting", json!(true)).is_err());
+
+        let mut changes = Vec::new();
+        let old = serde_json::to_value(&settings).unwrap();
+        let new = serde_json::to_value(updated(&changed, "font_size", json!(30)).unwrap()).unwrap();
+        diff("", &old, &new, &mut changes);
+        let paths: Vec<&str> = changes.iter().map(|c| c.key_path.as_str()).collect();
+        assert_eq!(paths, vec!["ai", "font_size"]);
+        assert_eq!(changes[1].new, json!(30));
+
+        let keyed = updated(&changed, "ai.api_key", json!("secret")).unwrap();
+        let mut ai = serde_json::to_value(&keyed.ai).unwrap();
+        mask("ai", &mut ai);
+        assert_eq!(ai["api_key"], json!(SECRET_MASK));
+        let shown = redacted(&keyed);
+        assert_eq!(shown.ai.as_ref().unwrap().api_key, SECRET_MASK);
+        assert_eq!(unmask(&keyed, shown).ai.unwrap().api_key, "secret");
+
+        let hooked = updated(
+            &keyed,
+            "webhooks",
+            json!([
+                {"id": "a", "url": "https://a.example", "secret": "one", "enabled": true, "events": [], "keywords": []},
+                {"id": "b", "url": "https://b.example", "secret": "two", "enabled": true, "events": [], "keywords": []}
+            ]),
+        )
+        .unwrap();
+        let mut shown = redacted(&hooked);
+        assert!(shown.webhooks.iter().all(|w| w.secret == SECRET_MASK));
+        let mut webhooks = serde_json::to_value(&hooked.webhooks).unwrap();
+        mask("webhooks", &mut webhooks);
+        assert_eq!(webhooks[1]["secret"], json!(SECRET_MASK));
+        shown.webhooks.remove(0);
+        let restored = unmask(&hooked, shown);
+        assert_eq!(restored.webhooks.len(), 1);
+        assert_eq!(restored.webhooks[0].secret, "two");
+
+        let mut stripped = serde_json::to_value(&hooked).unwrap();
+        strip_secrets(&mut stripped);
+        assert_eq!(stripped["ai"]["api_key"], json!(""));
+        assert_eq!(stripped["webhooks"][0]["secret"], json!(""));
+        assert_eq!(stripped["webhooks"][0]["url"], json!("https://a.example"));
+        let previous = serde_json::to_value(&hooked).unwrap();
+        assert!(refill_secrets(&previous, &mut stripped));
+        assert_eq!(stripped, previous);
+    }
+}
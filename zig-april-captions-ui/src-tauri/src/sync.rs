@@ -0,0 +1,364 @@
+// Folder sync of knowledge and ideas
+//
+// Opt-in sync through a folder another tool keeps in step between machines
+// (Dropbox, Syncthing, a network share). Each device only ever appends to its
+// own `zigy-sync/<device id>.jsonl`, so the sync tool never sees two writers
+// on one file. A line records one entry's new contents, or its deletion, with
+// the time the change was noticed here.
+//
+// A pass (at startup, every SYNC_INTERVAL and on `sync_now`) first logs local
+// changes, found by comparing each entry with the hash recorded at the last
+// pass, then replays every device's log: per entry id the newest change wins,
+// and it is applied if it is newer than what this device last saw.
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tracing::{info, warn};
+
+use crate::{embeddings, get_config_dir, now_millis, persist, AppState, IdeaEntry, KnowledgeEntry};
+
+/// How often a sync pass runs while the app is open
+const SYNC_INTERVAL: Duration = Duration::from_secs(60);
+
+const SYNC_DIR_NAME: &str = "zigy-sync";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub folder: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SyncChange {
+    store: String, // "knowledge" or "ideas"
+    entry_id: String,
+    updated_at: i64,
+    device: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    entry: Option<serde_json::Value>, // None for a deletion
+}
+
+/// What this device last synced of one entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EntryState {
+    hash: Option<String>, // None once deleted
+    updated_at: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct SyncState {
+    device_id: String,
+    #[serde(default)]
+    entries: HashMap<String, HashMap<String, EntryState>>, // store -> entry id -> state
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncReport {
+    pub exported: usize,
+    pub imported: usize,
+    pub devices: usize,
+}
+
+/// Serializes sync passes
+static SYNC_LOCK: Mutex<()> = Mutex::new(());
+
+trait SyncEntry: Serialize + DeserializeOwned + Clone {
+    const STORE: &'static str;
+    fn id(&self) -> &str;
+}
+
+impl SyncEntry for KnowledgeEntry {
+    const STORE: &'static str = "knowledge";
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl SyncEntry for IdeaEntry {
+    const STORE: &'static str = "ideas";
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+fn state_path() -> PathBuf {
+    get_config_dir().join("sync_state.json")
+}
+
+fn load_state() -> SyncState {
+    let mut state: SyncState = persist::load_json(&state_path()).unwrap_or_default();
+    if state.device_id.is_empty() {
+        state.device_id = uuid::Uuid::new_v4().to_string();
+    }
+    state
+}
+
+fn entry_hash<T: Serialize>(entry: &T) -> String {
+    let json = serde_json::to_vec(entry).unwrap_or_default();
+    Sha256::digest(&json).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Changes of entries added, edited or removed since the last pass; updates
+/// `known` to match
+fn local_changes<T: SyncEntry>(
+    entries: &[T],
+    known: &mut HashMap<String, EntryState>,
+    device: &str,
+    now: i64,
+) -> Vec<SyncChange> {
+    let mut changes = Vec::new();
+    for entry in entries {
+        let hash = entry_hash(entry);
+        if known.get(entry.id()).and_then(|s| s.hash.as_ref()) == Some(&hash) {
+            continue;
+        }
+        known.insert(entry.id().to_string(), EntryState { hash: Some(hash), updated_at: now });
+        changes.push(SyncChange {
+            store: T::STORE.to_string(),
+            entry_id: entry.id().to_string(),
+            updated_at: now,
+            device: device.to_string(),
+            entry: serde_json::to_value(entry).ok(),
+        });
+    }
+    for (id, state) in known.iter_mut() {
+        if state.hash.is_some() && !entries.iter().any(|e| e.id() == id) {
+            *state = EntryState { hash: None, updated_at: now };
+            changes.push(SyncChange {
+                store: T::STORE.to_string(),
+                entry_id: id.clone(),
+                updated_at: now,
+                device: device.to_string(),
+                entry: None,
+            });
+        }
+    }
+    changes
+}
+
+/// Apply the winning remote changes of one store to `entries`; returns how
+/// many entries changed
+fn apply_changes<T: SyncEntry>(
+    entries: &mut Vec<T>,
+    known: &mut HashMap<String, EntryState>,
+    latest: &HashMap<(String, String), SyncChange>,
+) -> usize {
+    let mut applied = 0;
+    for ((store, id), change) in latest {
+        if store != T::STORE {
+            continue;
+        }
+        if known.get(id).is_some_and(|s| s.updated_at >= change.updated_at) {
+            continue;
+        }
+        let incoming = match &change.entry {
+            Some(value) => match serde_json::from_value::<T>(value.clone()) {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    warn!("Skipping unreadable synced {} entry {}: {}", store, id, e);
+                    continue;
+                }
+            },
+            None => None,
+        };
+        entries.retain(|e| e.id() != id);
+        let hash = incoming.as_ref().map(entry_hash);
+        if let Some(entry) = incoming {
+            entries.push(entry);
+        }
+        known.insert(id.clone(), EntryState { hash, updated_at: change.updated_at });
+        applied += 1;
+    }
+    applied
+}
+
+fn append_changes(path: &Path, changes: &[SyncChange]) -> Result<(), String> {
+    let mut lines = String::new();
+    for change in changes {
+        lines.push_str(&serde_json::to_string(change).map_err(|e| e.to_string())?);
+        lines.push('\n');
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    file.write_all(lines.as_bytes()).map_err(|e| e.to_string())?;
+    file.sync_data().map_err(|e| e.to_string())
+}
+
+/// Newest change per (store, entry id)
+type LatestChanges = HashMap<(String, String), SyncChange>;
+
+/// Newest change per (store, entry id) across every device's log. Ties go to
+/// the larger device id so all devices pick the same winner.
+fn read_logs(dir: &Path) -> Result<(LatestChanges, usize), String> {
+    let mut latest: LatestChanges = HashMap::new();
+    let mut devices = 0;
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("jsonl") {
+            continue;
+        }
+        let Ok(file) = std::fs::File::open(&path) else {
+            continue;
+        };
+        devices += 1;
+        // A line still being synced may be torn; skip anything unparsable
+        for change in BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str::<SyncChange>(&line).ok())
+        {
+            let key = (change.store.clone(), change.entry_id.clone());
+            let newer = latest
+                .get(&key)
+                .map(|c| (change.updated_at, &change.device) > (c.updated_at, &c.device))
+                .unwrap_or(true);
+            if newer {
+                latest.insert(key, change);
+            }
+        }
+    }
+    Ok((latest, devices))
+}
+
+fn sync_settings(state: &AppState) -> Option<SyncSettings> {
+    state
+        .settings
+        .lock()
+        .ok()?
+        .sync
+        .clone()
+        .filter(|s| s.enabled && !s.folder.trim().is_empty())
+}
+
+/// Run one sync pass
+pub fn sync_once(app_handle: &AppHandle) -> Result<SyncReport, String> {
+    let state = app_handle.state::<Arc<AppState>>();
+    let settings = sync_settings(&state).ok_or("Sync is not configured")?;
+    let _guard = SYNC_LOCK.lock().map_err(|e| e.to_string())?;
+
+    let dir = Path::new(settings.folder.trim()).join(SYNC_DIR_NAME);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Sync folder unavailable: {}", e))?;
+    let mut sync_state = load_state();
+    let device = sync_state.device_id.clone();
+    let now = now_millis();
+
+    let mut changes = Vec::new();
+    {
+        let known = sync_state.entries.entry(KnowledgeEntry::STORE.to_string()).or_default();
+        changes.extend(local_changes(&state.knowledge.read()?, known, &device, now));
+    }
+    {
+        let known = sync_state.entries.entry(IdeaEntry::STORE.to_string()).or_default();
+        changes.extend(local_changes(&state.ideas.read()?, known, &device, now));
+    }
+    if !changes.is_empty() {
+        append_changes(&dir.join(format!("{}.jsonl", device)), &changes)?;
+    }
+
+    let (latest, devices) = read_logs(&dir)?;
+    let known = sync_state.entries.entry(KnowledgeEntry::STORE.to_string()).or_default();
+    let knowledge_applied = state.knowledge.update(|entries| apply_changes(entries, known, &latest))?;
+    let known = sync_state.entries.entry(IdeaEntry::STORE.to_string()).or_default();
+    let ideas_applied = state.ideas.update(|entries| {
+        let applied = apply_changes(entries, known, &latest);
+        entries.sort_by_key(|e| std::cmp::Reverse(e.created_at));
+        applied
+    })?;
+    if knowledge_applied > 0 {
+        embeddings::wake(app_handle);
+    }
+    persist::persist_json(&state_path(), &sync_state)?;
+
+    let report = SyncReport {
+        exported: changes.len(),
+        imported: knowledge_applied + ideas_applied,
+        devices,
+    };
+    if report.exported > 0 || report.imported > 0 {
+        info!("Synced: {} changes out, {} in", report.exported, report.imported);
+    }
+    Ok(report)
+}
+
+/// Sync at startup and then periodically while enabled
+pub fn start(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let configured = sync_settings(&app_handle.state::<Arc<AppState>>()).is_some();
+            if configured {
+                let handle = app_handle.clone();
+                match tauri::async_runtime::spawn_blocking(move || sync_once(&handle)).await {
+                    Ok(Err(e)) => warn!("Sync failed: {}", e),
+                    Err(e) => warn!("Sync failed: {}", e),
+                    Ok(Ok(_)) => {}
+                }
+            }
+            tokio::time::sleep(SYNC_INTERVAL).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn sync_now(app_handle: AppHandle) -> Result<SyncReport, String> {
+    tauri::async_runtime::spawn_blocking(move || sync_once(&app_handle))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_last_write_wins() {
+        let knowledge = |id: &str, content: &str| KnowledgeEntry {
+            id: id.to_string(),
+            content: content.to_string(),
+            created_at: 1,
+            nominated: true,
+            tags: Vec::new(),
+            folder: None,
+            metadata: None,
+            source_session_id: None,
+            source_entry_ids: Vec::new(),
+        };
+        let mut laptop = vec![knowledge("a", "laptop edit"), knowledge("b", "keep")];
+        let mut laptop_known = HashMap::new();
+        let mut changes = local_changes(&laptop, &mut laptop_known, "laptop", 100);
+        assert_eq!(changes.len(), 2);
+
+        // The desktop edited "a" later and deleted "b"
+        let mut desktop_known = HashMap::new();
+        let desktop = vec![knowledge("a", "desktop edit")];
+        local_changes(&[knowledge("a", "x"), knowledge("b", "keep")], &mut desktop_known, "desktop", 50);
+        changes.extend(local_changes(&desktop, &mut desktop_known, "desktop", 200));
+
+        let mut latest = HashMap::new();
+        for change in changes {
+            let key = (change.store.clone(), change.entry_id.clone());
+            if latest
+                .get(&key)
+                .map(|c: &SyncChange| change.updated_at > c.updated_at)
+                .unwrap_or(true)
+            {
+                latest.insert(key, change);
+            }
+        }
+        assert_eq!(apply_changes(&mut laptop, &mut laptop_known, &latest), 2);
+        assert_eq!(laptop.len(), 1);
+        assert_eq!(laptop[0].content, "desktop edit");
+        assert!(local_changes(&laptop, &mut laptop_known, "laptop", 300).is_empty());
+    }
+}
@@ -0,0 +1,362 @@
+// Action items as tasks
+//
+// `extract_action_items` asks the AI model for the follow-ups agreed in a
+// session (text, assignee, due date) and stores them in the `tasks` table,
+// skipping ones already recorded for that session. Tasks can also be added
+// by hand; each is open or done, and any set of them exports as a Markdown
+// checklist.
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::AppHandle;
+use tracing::info;
+
+use crate::ai::generate_content;
+use crate::export::date_time;
+use crate::{database, integrations, now_millis, AppState};
+
+const EXTRACT_SYSTEM_PROMPT: &str = "You extract action items from meeting transcripts. \
+    Respond with JSON: {\"items\": [{\"text\": string, \"assignee\": string or null, \"due_date\": string or null}]}. \
+    Only include concrete follow-ups somebody agreed to or was asked to do. `text` is a short imperative \
+    sentence. `assignee` is the person's name as spoken. `due_date` is YYYY-MM-DD, resolved against the \
+    meeting date when a relative day is mentioned, or null. Use an empty array when there are none.";
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    #[default]
+    Open,
+    Done,
+}
+
+impl TaskStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            TaskStatus::Open => "open",
+            TaskStatus::Done => "done",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Task {
+    pub id: String,
+    pub session_id: Option<String>,
+    pub text: String,
+    pub assignee: Option<String>,
+    pub due_date: Option<String>, // YYYY-MM-DD
+    pub status: TaskStatus,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Fields to change; an empty assignee or due date clears it
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TaskUpdate {
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub assignee: Option<String>,
+    #[serde(default)]
+    pub due_date: Option<String>,
+    #[serde(default)]
+    pub status: Option<TaskStatus>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExtractedItems {
+    #[serde(default)]
+    items: Vec<ExtractedItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExtractedItem {
+    text: String,
+    #[serde(default)]
+    assignee: Option<String>,
+    #[serde(default)]
+    due_date: Option<String>,
+}
+
+const TASK_COLUMNS: &str = "id, session_id, text, assignee, due_date, status, created_at, updated_at";
+
+fn task_from_row(row: &rusqlite::Row) -> rusqlite::Result<Task> {
+    let status: String = row.get(5)?;
+    Ok(Task {
+        id: row.get(0)?,
+        session_id: row.get(1)?,
+        text: row.get(2)?,
+        assignee: row.get(3)?,
+        due_date: row.get(4)?,
+        status: if status == "done" { TaskStatus::Done } else { TaskStatus::Open },
+        created_at: row.get(6)?,
+        updated_at: row.get(7)?,
+    })
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+fn insert_task(conn: &Connection, task: &Task) -> Result<(), String> {
+    conn.execute(
+        &format!("INSERT INTO tasks ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)", TASK_COLUMNS),
+        params![
+            task.id,
+            task.session_id,
+            task.text,
+            task.assignee,
+            task.due_date,
+            task.status.as_str(),
+            task.created_at,
+            task.updated_at
+        ],
+    )
+    .map_err(|e| format!("Failed to save task: {}", e))?;
+    Ok(())
+}
+
+fn get_task(conn: &Connection, id: &str) -> Result<Task, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM tasks WHERE id = ?1", TASK_COLUMNS),
+        params![id],
+        task_from_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Task {} not found", id))
+}
+
+pub(crate) fn query_tasks(conn: &Connection, session_id: Option<&str>, status: Option<TaskStatus>) -> Result<Vec<Task>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM tasks
+             WHERE (?1 IS NULL OR session_id = ?1) AND (?2 IS NULL OR status = ?2)
+             ORDER BY status = 'done', due_date IS NULL, due_date, created_at",
+            TASK_COLUMNS
+        ))
+        .map_err(|e| format!("Prepare failed: {}", e))?;
+    let tasks = stmt
+        .query_map(params![session_id, status.map(|s| s.as_str())], task_from_row)
+        .map_err(|e| format!("Query failed: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(tasks)
+}
+
+/// Markdown checklist; open tasks first
+fn render_checklist(tasks: &[Task]) -> String {
+    let mut content = String::from("# Action items\n\n");
+    for task in tasks {
+        let mut line = format!(
+            "- [{}] {}",
+            if task.status == TaskStatus::Done { "x" } else { " " },
+            task.text
+        );
+        if let Some(assignee) = &task.assignee {
+            line.push_str(&format!(" (@{})", assignee));
+        }
+        if let Some(due_date) = &task.due_date {
+            line.push_str(&format!(" — due {}", due_date));
+        }
+        content.push_str(&line);
+        content.push('\n');
+    }
+    content
+}
+
+/// Ask the AI for a session's action items and store the new ones as tasks
+#[tauri::command]
+pub async fn extract_action_items(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<Vec<Task>, String> {
+    let ai = state
+        .settings
+        .lock()
+        .map_err(|e| e.to_string())?
+        .ai
+        .clone()
+        .ok_or("AI is not configured")?;
+    let started_at = {
+        let conn = state.db()?;
+        database::get_session(&conn, &session_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Session {} not found", session_id))?
+            .started_at
+    };
+    let text = integrations::session_text(&app_handle, &session_id)?;
+
+    let prompt = format!("Meeting date: {}\n\nTranscript:\n{}", &date_time(started_at)[..10], text);
+    let output = generate_content(&ai, EXTRACT_SYSTEM_PROMPT, &prompt, true).await?;
+    let extracted: ExtractedItems =
+        serde_json::from_str(&output).map_err(|e| format!("Unexpected AI response: {}", e))?;
+
+    let conn = state.db()?;
+    let existing: Vec<String> = query_tasks(&conn, Some(&session_id), None)?
+        .into_iter()
+        .map(|t| t.text.to_lowercase())
+        .collect();
+    let now = now_millis();
+    let mut added = Vec::new();
+    for item in extracted.items {
+        let text = item.text.trim().to_string();
+        if text.is_empty() || existing.contains(&text.to_lowercase()) {
+            continue;
+        }
+        let task = Task {
+            id: uuid::Uuid::new_v4().to_string(),
+            session_id: Some(session_id.clone()),
+            text,
+            assignee: non_empty(item.assignee),
+            due_date: non_empty(item.due_date),
+            status: TaskStatus::Open,
+            created_at: now,
+            updated_at: now,
+        };
+        insert_task(&conn, &task)?;
+        added.push(task);
+    }
+    info!("Extracted {} action items from session {}", added.len(), session_id);
+    Ok(added)
+}
+
+/// Tasks, optionally limited to a session and/or status
+#[tauri::command]
+pub async fn get_tasks(
+    state: tauri::State<'_, Arc<AppState>>,
+    session_id: Option<String>,
+    status: Option<TaskStatus>,
+) -> Result<Vec<Task>, String> {
+    let conn = state.db()?;
+    query_tasks(&conn, session_id.as_deref(), status)
+}
+
+#[tauri::command]
+pub async fn add_task(
+    state: tauri::State<'_, Arc<AppState>>,
+    text: String,
+    session_id: Option<String>,
+    assignee: Option<String>,
+    due_date: Option<String>,
+) -> Result<Task, String> {
+    let text = text.trim().to_string();
+    if text.is_empty() {
+        return Err("Task text cannot be empty".to_string());
+    }
+    let now = now_millis();
+    let task = Task {
+        id: uuid::Uuid::new_v4().to_string(),
+        session_id,
+        text,
+        assignee: non_empty(assignee),
+        due_date: non_empty(due_date),
+        status: TaskStatus::Open,
+        created_at: now,
+        updated_at: now,
+    };
+    let conn = state.db()?;
+    insert_task(&conn, &task)?;
+    Ok(task)
+}
+
+#[tauri::command]
+pub async fn update_task(
+    state: tauri::State<'_, Arc<AppState>>,
+    id: String,
+    update: TaskUpdate,
+) -> Result<Task, String> {
+    let conn = state.db()?;
+    let mut task = get_task(&conn, &id)?;
+    if let Some(text) = non_empty(update.text) {
+        task.text = text;
+    }
+    if let Some(assignee) = update.assignee {
+        task.assignee = non_empty(Some(assignee));
+    }
+    if let Some(due_date) = update.due_date {
+        task.due_date = non_empty(Some(due_date));
+    }
+    if let Some(status) = update.status {
+        task.status = status;
+    }
+    task.updated_at = now_millis();
+    conn.execute(
+        "UPDATE tasks SET text = ?2, assignee = ?3, due_date = ?4, status = ?5, updated_at = ?6 WHERE id = ?1",
+        params![
+            task.id,
+            task.text,
+            task.assignee,
+            task.due_date,
+            task.status.as_str(),
+            task.updated_at
+        ],
+    )
+    .map_err(|e| format!("Failed to update task: {}", e))?;
+    Ok(task)
+}
+
+#[tauri::command]
+pub async fn delete_task(state: tauri::State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    let conn = state.db()?;
+    let deleted = conn
+        .execute("DELETE FROM tasks WHERE id = ?1", params![id])
+        .map_err(|e| format!("Failed to delete task: {}", e))?;
+    if deleted == 0 {
+        return Err(format!("Task {} not found", id));
+    }
+    Ok(())
+}
+
+/// Tasks as a Markdown checklist, also written to `path` when given
+#[tauri::command]
+pub async fn export_tasks_markdown(
+    state: tauri::State<'_, Arc<AppState>>,
+    session_id: Option<String>,
+    status: Option<TaskStatus>,
+    path: Option<String>,
+) -> Result<String, String> {
+    let tasks = {
+        let conn = state.db()?;
+        query_tasks(&conn, session_id.as_deref(), status)?
+    };
+    let content = render_checklist(&tasks);
+    if let Some(path) = path {
+        std::fs::write(&path, &content).map_err(|e| format!("Failed to write file: {}", e))?;
+    }
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tasks_checklist() {
+        let conn = database::init_memory_db().unwrap();
+        let task = |id: &str, text: &str, status, due_date: Option<&str>| Task {
+            id: id.to_string(),
+            session_id: Some("s1".to_string()),
+            text: text.to_string(),
+            assignee: Some("Alice".to_string()).filter(|_| id == "a"),
+            due_date: due_date.map(|d| d.to_string()),
+            status,
+            created_at: 1,
+            updated_at: 1,
+        };
+        insert_task(&conn, &task("a", "Send the slides", TaskStatus::Open, Some("2026-03-02"))).unwrap();
+        insert_task(&conn, &task("b", "Book a room", TaskStatus::Done, None)).unwrap();
+        insert_task(&conn, &task("c", "Draft the agenda", TaskStatus::Open, None)).unwrap();
+
+        let tasks = query_tasks(&conn, Some("s1"), None).unwrap();
+        assert_eq!(
+            render_checklist(&tasks),
+            "# Action items\n\n\
+             - [ ] Send the slides (@Alice) — due 2026-03-02\n\
+             - [ ] Draft the agenda\n\
+             - [x] Book a room\n"
+        );
+        assert_eq!(query_tasks(&conn, None, Some(TaskStatus::Done)).unwrap().len(), 1);
+    }
+}
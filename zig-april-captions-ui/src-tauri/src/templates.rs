@@ -0,0 +1,183 @@
+// Meeting templates
+//
+// A template bundles what a recurring meeting (a standup, a weekly 1:1)
+// needs so it can be set up in one go: meeting context for the AI, the
+// knowledge notes to nominate, extra vocabulary, a translation language and
+// export options. Templates live in templates.json.
+//
+// `start_captions` with a `template_id` applies one to the new session, before
+// the engine starts:
+//   - the nominated knowledge set is replaced by the template's notes until
+//     the session ends, when the notes it changed get their nominations back
+//   - its vocabulary becomes the session's own vocabulary
+//   - its meeting context and translation language apply until the session
+//     ends, on top of the settings (see `current`)
+//   - the session remembers the template, so `export_session` without options
+//     uses the template's
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+use tracing::{info, warn};
+
+use crate::export::SessionExportOptions;
+use crate::vocabulary::{self, VocabularyTerm};
+use crate::{database, now_millis, AppState};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeetingTemplate {
+    pub id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meeting_context: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub knowledge_ids: Option<Vec<String>>, // Notes to nominate; None leaves nominations alone
+    #[serde(default)]
+    pub vocabulary: Vec<VocabularyTerm>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translation_language: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub export: Option<SessionExportOptions>,
+    #[serde(default)]
+    pub created_at: i64,
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+pub fn get_templates_path() -> std::path::PathBuf {
+    crate::get_config_dir().join("templates.json")
+}
+
+pub fn find(state: &AppState, id: &str) -> Result<MeetingTemplate, String> {
+    state
+        .templates
+        .read()?
+        .iter()
+        .find(|t| t.id == id)
+        .cloned()
+        .ok_or_else(|| format!("Template {} not found", id))
+}
+
+/// A template in use, with the nominations it changed
+pub struct AppliedTemplate {
+    template: MeetingTemplate,
+    replaced: Vec<(String, bool)>, // (knowledge id, nominated before)
+}
+
+/// The template applied to the running session, if any
+pub fn current(state: &AppState) -> Option<MeetingTemplate> {
+    state.template.lock().ok().and_then(|t| t.as_ref().map(|a| a.template.clone()))
+}
+
+/// Put `template` in place for the session about to start
+pub fn apply(state: &AppState, template: MeetingTemplate) -> Result<(), String> {
+    clear(state);
+    let mut replaced = Vec::new();
+    if let Some(ids) = &template.knowledge_ids {
+        state.knowledge.update(|entries| {
+            for entry in entries.iter_mut() {
+                let nominated = ids.contains(&entry.id);
+                if entry.nominated != nominated {
+                    replaced.push((entry.id.clone(), entry.nominated));
+                    entry.nominated = nominated;
+                }
+            }
+        })?;
+    }
+    info!("Applied meeting template {}", template.name);
+    *state.template.lock().map_err(|e| e.to_string())? = Some(AppliedTemplate { template, replaced });
+    Ok(())
+}
+
+/// Give the new session the applied template's vocabulary, and remember the
+/// template for its export
+pub fn apply_to_session(app_handle: &AppHandle, session_id: &str) {
+    let state = app_handle.state::<Arc<AppState>>();
+    let Some(template) = current(&state) else {
+        return;
+    };
+    if !template.vocabulary.is_empty() {
+        let terms = template.vocabulary.clone();
+        if let Err(e) = vocabulary::replace_session_terms(&state, &app_handle.state(), session_id.to_string(), terms) {
+            warn!("Could not set the vocabulary of session {}: {}", session_id, e);
+        }
+    }
+    let recorded = state.db().and_then(|conn| {
+        database::set_session_template(&conn, session_id, &template.id).map_err(|e| e.to_string())
+    });
+    if let Err(e) = recorded {
+        warn!("Could not record the template of session {}: {}", session_id, e);
+    }
+}
+
+/// Drop the applied template and give back the nominations it changed
+pub fn clear(state: &AppState) {
+    let Some(applied) = state.template.lock().ok().and_then(|mut t| t.take()) else {
+        return;
+    };
+    if applied.replaced.is_empty() {
+        return;
+    }
+    let restored = state.knowledge.update(|entries| {
+        for entry in entries.iter_mut() {
+            if let Some((_, nominated)) = applied.replaced.iter().find(|(id, _)| *id == entry.id) {
+                entry.nominated = *nominated;
+            }
+        }
+    });
+    if let Err(e) = restored {
+        warn!("Could not restore knowledge nominations after template {}: {}", applied.template.name, e);
+    }
+}
+
+/// Export options of the template a session was started from
+pub fn export_options(state: &AppState, conn: &Connection, session_id: &str) -> Option<SessionExportOptions> {
+    let template_id = database::session_template(conn, session_id).ok().flatten()?;
+    find(state, &template_id).ok()?.export
+}
+
+#[tauri::command]
+pub async fn get_templates(state: tauri::State<'_, Arc<AppState>>) -> Result<Vec<MeetingTemplate>, String> {
+    Ok(state.templates.read()?.clone())
+}
+
+/// Create a template, or replace the one with the same id
+#[tauri::command]
+pub async fn save_template(
+    state: tauri::State<'_, Arc<AppState>>,
+    mut template: MeetingTemplate,
+) -> Result<MeetingTemplate, String> {
+    template.name = template.name.trim().to_string();
+    if template.name.is_empty() {
+        return Err("Template name is required".to_string());
+    }
+    let now = now_millis();
+    if template.id.is_empty() {
+        template.id = uuid::Uuid::new_v4().to_string();
+    }
+    template.updated_at = now;
+    state.templates.update(|templates| match templates.iter_mut().find(|t| t.id == template.id) {
+        Some(existing) => {
+            template.created_at = existing.created_at;
+            *existing = template.clone();
+        }
+        None => {
+            template.created_at = now;
+            templates.push(template.clone());
+        }
+    })?;
+    Ok(template)
+}
+
+#[tauri::command]
+pub async fn delete_template(state: tauri::State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    let removed = state.templates.update(|templates| {
+        let before = templates.len();
+        templates.retain(|t| t.id != id);
+        templates.len() < before
+    })?;
+    if !removed {
+        return Err(format!("Template {} not found", id));
+    }
+    Ok(())
+}
@@ -0,0 +1,289 @@
+// Cross-session topic timeline
+//
+// `get_topic_timeline` collects every place a topic came up, across all
+// sessions, and lists them oldest first so the way a decision evolved over
+// weeks of meetings can be followed. A mention is found by any of:
+//   - text: the full-text index over chat entries (transcripts, answers, summaries)
+//   - semantic: chat entries whose embedding is close to the query's, when an
+//     API key is set to embed the query with
+//   - tag: transcript lines containing the topic in sessions that
+//     `analyze_session` tagged with it
+// Each mention comes with the transcript lines spoken just before and after
+// it in its session.
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::database::{self, ChatHistoryEntry};
+use crate::{db_pool, vector_index, AppState, DateRange};
+
+/// Least similarity for a semantic match
+const MIN_SIMILARITY: f32 = 0.6;
+
+/// Full-text hits considered before merging
+const MAX_TEXT_HITS: usize = 500;
+
+/// Transcript lines shown on each side of a mention
+const CONTEXT_LINES: usize = 3;
+
+/// Context lines further than this from the mention are left out
+const CONTEXT_WINDOW_MS: i64 = 2 * 60 * 1000;
+
+const DEFAULT_LIMIT: usize = 200;
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    Text,
+    Semantic,
+    Tag,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TopicMention {
+    pub id: String, // Chat entry id, or "line-<rowid>" for a transcript line
+    pub session_id: Option<String>,
+    pub session_title: Option<String>,
+    pub timestamp: i64,
+    pub entry_type: String, // Chat entry type, or "transcript_line"
+    pub text: String,
+    pub speaker: Option<String>,
+    pub matched_by: Vec<MatchKind>,
+    pub similarity: Option<f32>, // Set for semantic matches
+    pub before: Vec<String>, // Transcript lines leading up to the mention, oldest first
+    pub after: Vec<String>,
+}
+
+fn mention(entry: ChatHistoryEntry, kind: MatchKind, similarity: Option<f32>) -> TopicMention {
+    TopicMention {
+        id: entry.id,
+        session_id: entry.session_id,
+        session_title: None,
+        timestamp: entry.timestamp,
+        entry_type: entry.entry_type,
+        text: entry.content,
+        speaker: entry.speaker,
+        matched_by: vec![kind],
+        similarity,
+        before: Vec::new(),
+        after: Vec::new(),
+    }
+}
+
+/// Add a mention, or note another way an already found one matched
+fn merge(mentions: &mut Vec<TopicMention>, found: TopicMention) {
+    match mentions.iter_mut().find(|m| m.id == found.id) {
+        Some(existing) => {
+            for kind in found.matched_by {
+                if !existing.matched_by.contains(&kind) {
+                    existing.matched_by.push(kind);
+                }
+            }
+            existing.similarity = existing.similarity.or(found.similarity);
+        }
+        None => mentions.push(found),
+    }
+}
+
+fn chat_entry(conn: &Connection, id: &str) -> rusqlite::Result<Option<ChatHistoryEntry>> {
+    conn.prepare_cached(&format!("SELECT {} FROM chat_entries WHERE id = ?1", database::CHAT_ENTRY_COLUMNS))?
+        .query_row(params![id], database::chat_entry_from_row)
+        .optional()
+}
+
+/// Transcript lines of sessions tagged `tag` that contain it
+fn tagged_lines(conn: &Connection, tag: &str, from: i64, to: i64) -> rusqlite::Result<Vec<TopicMention>> {
+    let mut stmt = conn.prepare(
+        "SELECT l.id, l.session_id, l.timestamp, l.text, l.speaker FROM transcript_lines l
+         WHERE l.session_id IN (
+                 SELECT m.session_id FROM entity_mentions m JOIN tags t ON t.id = m.tag_id
+                 WHERE t.name = ?1 COLLATE NOCASE)
+           AND instr(lower(l.text), lower(?1)) > 0
+           AND l.timestamp >= ?2 AND l.timestamp <= ?3
+         ORDER BY l.timestamp ASC",
+    )?;
+    let lines = stmt
+        .query_map(params![tag, from, to], |row| {
+            Ok(TopicMention {
+                id: format!("line-{}", row.get::<_, i64>(0)?),
+                session_id: Some(row.get(1)?),
+                session_title: None,
+                timestamp: row.get(2)?,
+                entry_type: "transcript_line".to_string(),
+                text: row.get(3)?,
+                speaker: row.get(4)?,
+                matched_by: vec![MatchKind::Tag],
+                similarity: None,
+                before: Vec::new(),
+                after: Vec::new(),
+            })
+        })?
+        .collect();
+    lines
+}
+
+/// Lines of a session spoken just before or after `timestamp`, oldest first
+fn context(conn: &Connection, session_id: &str, timestamp: i64, before: bool) -> rusqlite::Result<Vec<String>> {
+    let sql = if before {
+        "SELECT text, speaker FROM transcript_lines
+         WHERE session_id = ?1 AND timestamp < ?2 AND timestamp >= ?3 ORDER BY timestamp DESC, id DESC LIMIT ?4"
+    } else {
+        "SELECT text, speaker FROM transcript_lines
+         WHERE session_id = ?1 AND timestamp > ?2 AND timestamp <= ?3 ORDER BY timestamp ASC, id ASC LIMIT ?4"
+    };
+    let bound = if before { timestamp - CONTEXT_WINDOW_MS } else { timestamp + CONTEXT_WINDOW_MS };
+    let mut stmt = conn.prepare_cached(sql)?;
+    let mut lines: Vec<String> = stmt
+        .query_map(params![session_id, timestamp, bound, CONTEXT_LINES as i64], |row| {
+            let text: String = row.get(0)?;
+            Ok(match row.get::<_, Option<String>>(1)?.filter(|s| !s.is_empty()) {
+                Some(speaker) => format!("{}: {}", speaker, text),
+                None => text,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+    if before {
+        lines.reverse();
+    }
+    Ok(lines)
+}
+
+/// The latest `limit` mentions of `query` between `from` and `to`, oldest first
+pub fn timeline(
+    conn: &Connection,
+    query: &str,
+    query_embedding: Option<&[f32]>,
+    from: i64,
+    to: i64,
+    limit: usize,
+) -> Result<Vec<TopicMention>, String> {
+    let mut mentions = Vec::new();
+
+    let hits = database::search_text(conn, query, &[], Some(from), Some(to), MAX_TEXT_HITS)
+        .map_err(|e| format!("Search failed: {}", e))?;
+    for hit in hits.into_iter().filter(|hit| hit.source == "chat") {
+        if let Some(entry) = chat_entry(conn, &hit.id).map_err(|e| e.to_string())? {
+            merge(&mut mentions, mention(entry, MatchKind::Text, None));
+        }
+    }
+
+    if let Some(embedding) = query_embedding {
+        let k = vector_index::oversample(limit);
+        let nearest = vector_index::nearest(conn, vector_index::Indexed::Chat, embedding, k)
+            .map_err(|e| format!("Vector search failed: {}", e))?;
+        for (id, similarity) in nearest.into_iter().filter(|(_, s)| *s >= MIN_SIMILARITY) {
+            let entry = chat_entry(conn, &id).map_err(|e| e.to_string())?;
+            if let Some(entry) = entry.filter(|e| e.timestamp >= from && e.timestamp <= to) {
+                merge(&mut mentions, mention(entry, MatchKind::Semantic, Some(similarity)));
+            }
+        }
+    }
+
+    for line in tagged_lines(conn, query, from, to).map_err(|e| e.to_string())? {
+        // The same words are often also saved as a transcript chat entry
+        let duplicate = mentions
+            .iter_mut()
+            .find(|m| m.session_id == line.session_id && m.timestamp == line.timestamp && m.text == line.text);
+        match duplicate {
+            Some(existing) => {
+                if !existing.matched_by.contains(&MatchKind::Tag) {
+                    existing.matched_by.push(MatchKind::Tag);
+                }
+            }
+            None => merge(&mut mentions, line),
+        }
+    }
+
+    // Keep the most recent mentions, then show them oldest first
+    mentions.sort_by_key(|m| std::cmp::Reverse(m.timestamp));
+    mentions.truncate(limit);
+    mentions.reverse();
+
+    let mut titles: HashMap<String, Option<String>> = HashMap::new();
+    for mention in &mut mentions {
+        let Some(session_id) = mention.session_id.clone() else {
+            continue;
+        };
+        if !titles.contains_key(&session_id) {
+            let session = database::get_session(conn, &session_id).map_err(|e| e.to_string())?;
+            titles.insert(session_id.clone(), session.map(|s| s.title));
+        }
+        mention.session_title = titles[&session_id].clone();
+        mention.before = context(conn, &session_id, mention.timestamp, true).map_err(|e| e.to_string())?;
+        mention.after = context(conn, &session_id, mention.timestamp, false).map_err(|e| e.to_string())?;
+    }
+    Ok(mentions)
+}
+
+/// Every mention of a tag or free-text topic across sessions, oldest first,
+/// with the conversation around it
+#[tauri::command]
+pub async fn get_topic_timeline(
+    state: tauri::State<'_, Arc<AppState>>,
+    tag_or_query: String,
+    date_range: Option<DateRange>,
+    limit: Option<usize>,
+) -> Result<Vec<TopicMention>, String> {
+    let query = tag_or_query.trim().to_string();
+    if query.is_empty() {
+        return Err("Enter a topic or tag to trace".to_string());
+    }
+    let api_key = state
+        .settings
+        .lock()
+        .ok()
+        .and_then(|s| s.ai.as_ref().map(|ai| ai.api_key.clone()))
+        .filter(|key| !key.is_empty());
+    let embedding = match api_key {
+        Some(key) => crate::generate_embedding(&query, &key).await.map_or_else(
+            |e| {
+                warn!("Topic timeline without semantic matches: {}", e);
+                None
+            },
+            Some,
+        ),
+        None => None,
+    };
+    let (from, to) = date_range.map(|r| (r.from, r.to)).unwrap_or((None, None));
+    let (from, to) = (from.unwrap_or(i64::MIN), to.unwrap_or(i64::MAX));
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+    db_pool::read(&state, move |conn| timeline(conn, &query, embedding.as_deref(), from, to, limit)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timeline_merges_and_orders() {
+        let conn = database::init_memory_db().unwrap();
+        let session = database::start_session(&conn, 0, "mic", "m").unwrap();
+        for (timestamp, text) in [(1_000, "hello all"), (2_000, "the pricing change ships in May"), (3_000, "ok")] {
+            database::add_caption_line(&conn, &session.id, timestamp, text, None, None, None).unwrap();
+        }
+        conn.execute(
+            "INSERT INTO chat_entries (id, timestamp, entry_type, content, session_id) VALUES
+                ('later', 9000, 'summary', 'Pricing was pushed to June', ?1),
+                ('first', 2000, 'transcript', 'the pricing change ships in May', ?1)",
+            params![session.id],
+        )
+        .unwrap();
+        conn.execute("INSERT INTO tags (name, kind) VALUES ('Pricing', 'term')", []).unwrap();
+        conn.execute(
+            "INSERT INTO entity_mentions (tag_id, session_id, mentions) VALUES (1, ?1, 2)",
+            params![session.id],
+        )
+        .unwrap();
+
+        let mentions = timeline(&conn, "pricing", None, i64::MIN, i64::MAX, 10).unwrap();
+        let ids: Vec<&str> = mentions.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["first", "later"]);
+        assert_eq!(mentions[0].matched_by, vec![MatchKind::Text, MatchKind::Tag]);
+        assert_eq!(mentions[0].before, vec!["hello all"]);
+        assert_eq!(mentions[0].after, vec!["ok"]);
+        assert!(mentions[1].session_title.is_some());
+        assert!(timeline(&conn, "pricing", None, 5_000, i64::MAX, 10).unwrap().len() == 1);
+    }
+}
@@ -0,0 +1,330 @@
+// Offline transcription of recordings
+//
+// Runs the caption engine in its file mode (`--json --file <wav> <model>`)
+// and collects the final captions. `retranscribe_session` uses this to redo
+// a recorded session with another model: the result is stored as a
+// transcript version, or replaces the current transcript (which is then
+// kept as a version), and a word-level diff against the current transcript
+// is returned for comparing models. Progress is emitted as
+// `retranscribe-progress` { sessionId, percent }.
+//
+// Only the session's main recording is used; segments recorded after an
+// engine restart are not included.
+use serde::{Deserialize, Serialize};
+use similar::{Algorithm, DiffTag};
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter};
+use tracing::{info, warn};
+
+use crate::database::{self, CaptionWord, TranscriptLine, TranscriptVersion};
+use crate::{now_millis, AppState};
+
+/// Changes listed in a diff report; the counts cover all of them
+const MAX_DIFF_CHANGES: usize = 200;
+
+/// A final caption from a file; times are offsets into the file
+#[derive(Debug, Clone)]
+pub struct FileCaption {
+    pub offset_ms: i64,
+    pub text: String,
+    pub words: Vec<CaptionWord>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EngineLine {
+    #[serde(rename = "type")]
+    event_type: String,
+    #[serde(rename = "captionType", default)]
+    caption_type: Option<String>,
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    timestamp: Option<i64>,
+    #[serde(default)]
+    message: Option<String>,
+    #[serde(default)]
+    percent: Option<u32>,
+    #[serde(default)]
+    words: Vec<CaptionWord>,
+}
+
+/// Transcribe a 16-bit mono WAV file. Blocks until the engine exits;
+/// `on_progress` receives the percentage read and setting `cancel` kills
+/// the engine.
+pub fn transcribe_file(
+    binary_path: &str,
+    model_path: &str,
+    audio_path: &str,
+    cancel: &AtomicBool,
+    mut on_progress: impl FnMut(u32),
+) -> Result<Vec<FileCaption>, String> {
+    if !Path::new(audio_path).exists() {
+        return Err(format!("Audio file not found: {}", audio_path));
+    }
+    let binary_dir = Path::new(binary_path)
+        .parent()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut cmd = Command::new(binary_path);
+    cmd.args(["--json", "--file", audio_path, model_path])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    crate::set_library_path(&mut cmd, &binary_dir);
+    let mut child = cmd.spawn().map_err(|e| format!("Failed to start the caption engine: {}", e))?;
+    let stdout = child.stdout.take().ok_or("Failed to capture engine output")?;
+
+    let mut captions = Vec::new();
+    let mut error = None;
+    for line in BufReader::new(stdout).lines() {
+        if cancel.load(Ordering::Relaxed) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err("Cancelled".to_string());
+        }
+        let Ok(line) = line else {
+            break;
+        };
+        let Ok(event) = serde_json::from_str::<EngineLine>(&line) else {
+            continue;
+        };
+        match event.event_type.as_str() {
+            "progress" => on_progress(event.percent.unwrap_or(0).min(100)),
+            "error" => error = event.message,
+            "caption" if event.caption_type.as_deref() == Some("final") => {
+                let text = event.text.unwrap_or_default().trim().to_string();
+                if !text.is_empty() {
+                    captions.push(FileCaption {
+                        offset_ms: event.timestamp.unwrap_or(0),
+                        text,
+                        words: event.words,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let status = child.wait().map_err(|e| e.to_string())?;
+    match error {
+        Some(message) => Err(message),
+        None if !status.success() => Err(format!("Caption engine exited with {}", status)),
+        None => Ok(captions),
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffChange {
+    pub old: String, // Empty for insertions
+    pub new: String, // Empty for deletions
+}
+
+/// Word-level comparison of two transcripts; case and punctuation are ignored
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TranscriptDiff {
+    pub old_words: usize,
+    pub new_words: usize,
+    pub unchanged: usize,
+    pub inserted: usize,
+    pub deleted: usize,
+    pub substituted: usize,
+    pub difference: f32, // (substituted + inserted + deleted) / old_words, like a word error rate
+    pub changes: Vec<DiffChange>,
+}
+
+fn normalize(word: &str) -> String {
+    word.chars()
+        .filter(|c| c.is_alphanumeric() || *c == '\'')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+pub fn diff_transcripts(old: &[String], new: &[String]) -> TranscriptDiff {
+    let old_words: Vec<&str> = old.iter().flat_map(|l| l.split_whitespace()).collect();
+    let new_words: Vec<&str> = new.iter().flat_map(|l| l.split_whitespace()).collect();
+    let old_keys: Vec<String> = old_words.iter().map(|w| normalize(w)).collect();
+    let new_keys: Vec<String> = new_words.iter().map(|w| normalize(w)).collect();
+
+    let mut diff = TranscriptDiff {
+        old_words: old_words.len(),
+        new_words: new_words.len(),
+        ..Default::default()
+    };
+    for op in similar::capture_diff_slices(Algorithm::Myers, &old_keys, &new_keys) {
+        let (tag, old_range, new_range) = op.as_tag_tuple();
+        match tag {
+            DiffTag::Equal => {
+                diff.unchanged += old_range.len();
+                continue;
+            }
+            DiffTag::Delete => diff.deleted += old_range.len(),
+            DiffTag::Insert => diff.inserted += new_range.len(),
+            DiffTag::Replace => {
+                let common = old_range.len().min(new_range.len());
+                diff.substituted += common;
+                diff.deleted += old_range.len() - common;
+                diff.inserted += new_range.len() - common;
+            }
+        }
+        if diff.changes.len() < MAX_DIFF_CHANGES {
+            diff.changes.push(DiffChange {
+                old: old_words[old_range].join(" "),
+                new: new_words[new_range].join(" "),
+            });
+        }
+    }
+    let errors = diff.substituted + diff.inserted + diff.deleted;
+    diff.difference = errors as f32 / diff.old_words.max(1) as f32;
+    diff
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RetranscribeReport {
+    pub session_id: String,
+    pub model_path: String,
+    pub lines: usize,
+    pub replaced: bool,
+    pub version_id: String, // The new transcript, or the archived one when replaced
+    pub diff: TranscriptDiff,
+}
+
+/// Re-run a session's recording through `model_path`. With `replace`, the
+/// new transcript becomes the session's and the old one is kept as a
+/// version; otherwise the new one is stored as a version.
+#[tauri::command]
+pub async fn retranscribe_session(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    session_id: String,
+    model_path: String,
+    replace: Option<bool>,
+) -> Result<RetranscribeReport, String> {
+    if crate::current_session_id(&state).as_deref() == Some(session_id.as_str()) {
+        return Err("Stop captions before re-transcribing the current session".to_string());
+    }
+    if !Path::new(&model_path).exists() {
+        return Err(format!("Model not found: {}", model_path));
+    }
+    let (session, old_lines) = {
+        let conn = state.db()?;
+        let session = database::get_session(&conn, &session_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Session {} not found", session_id))?;
+        let lines = database::session_transcript_lines(&conn, &session_id).map_err(|e| e.to_string())?;
+        (session, lines)
+    };
+    let audio_path = session
+        .audio_path
+        .clone()
+        .ok_or("This session has no recording; turn on audio recording to re-transcribe later")?;
+    let binary_path = crate::get_zig_binary_path(&app_handle)?;
+
+    info!("Re-transcribing session {} with {}", session_id, model_path);
+    let captions = {
+        let app_handle = app_handle.clone();
+        let session_id = session_id.clone();
+        let model_path = model_path.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            let never = AtomicBool::new(false);
+            transcribe_file(&binary_path, &model_path, &audio_path, &never, |percent| {
+                let _ = app_handle.emit(
+                    "retranscribe-progress",
+                    serde_json::json!({ "sessionId": session_id, "percent": percent }),
+                );
+            })
+        })
+        .await
+        .map_err(|e| e.to_string())??
+    };
+
+    let new_lines: Vec<TranscriptLine> = captions
+        .iter()
+        .map(|c| TranscriptLine {
+            timestamp: session.started_at + c.offset_ms,
+            text: c.text.clone(),
+            speaker: None,
+        })
+        .collect();
+    let diff = diff_transcripts(
+        &old_lines.iter().map(|l| l.text.clone()).collect::<Vec<_>>(),
+        &new_lines.iter().map(|l| l.text.clone()).collect::<Vec<_>>(),
+    );
+
+    let replace = replace.unwrap_or(false);
+    let version = TranscriptVersion {
+        id: uuid::Uuid::new_v4().to_string(),
+        session_id: session_id.clone(),
+        model_path: if replace { session.model_path.clone() } else { Some(model_path.clone()) },
+        created_at: now_millis(),
+        lines: if replace { old_lines } else { new_lines.clone() },
+    };
+    {
+        let conn = state.db()?;
+        database::add_transcript_version(&conn, &version).map_err(|e| e.to_string())?;
+        if replace {
+            database::replace_transcript(&conn, &session_id, &new_lines, "retranscribe", &model_path)
+                .map_err(|e| e.to_string())?;
+            for caption in captions.iter().filter(|c| !c.words.is_empty()) {
+                let words: Vec<CaptionWord> = caption
+                    .words
+                    .iter()
+                    .map(|w| CaptionWord {
+                        start_ms: session.started_at + w.start_ms,
+                        end_ms: session.started_at + w.end_ms,
+                        ..w.clone()
+                    })
+                    .collect();
+                let caption_id = uuid::Uuid::new_v4().to_string();
+                database::add_caption_words(&conn, &caption_id, Some(&session_id), &words)
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+    }
+    if diff.difference > 0.5 {
+        warn!("Re-transcription of {} differs from the original in {:.0}% of words", session_id, diff.difference * 100.0);
+    }
+
+    Ok(RetranscribeReport {
+        session_id,
+        model_path,
+        lines: new_lines.len(),
+        replaced: replace,
+        version_id: version.id,
+        diff,
+    })
+}
+
+/// Stored alternative transcripts of a session
+#[tauri::command]
+pub async fn get_transcript_versions(
+    state: tauri::State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<Vec<TranscriptVersion>, String> {
+    let conn = state.db()?;
+    database::transcript_versions(&conn, &session_id).map_err(|e| format!("Failed to load versions: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_transcripts() {
+        let old = vec!["We ship on Friday.".to_string(), "Any questions".to_string()];
+        let new = vec!["we ship on monday".to_string(), "any questions at all".to_string()];
+        let diff = diff_transcripts(&old, &new);
+        assert_eq!((diff.old_words, diff.new_words), (6, 8));
+        assert_eq!(diff.unchanged, 5);
+        assert_eq!(diff.substituted, 1);
+        assert_eq!(diff.inserted, 2);
+        assert_eq!(diff.deleted, 0);
+        assert_eq!(diff.changes.len(), 2);
+        assert_eq!(diff.changes[0].old, "Friday.");
+        assert_eq!(diff.changes[0].new, "monday");
+        assert_eq!(diff.changes[1].new, "at all");
+    }
+}
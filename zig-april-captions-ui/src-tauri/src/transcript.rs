@@ -0,0 +1,328 @@
+// Live transcript of the current session
+//
+// Lines are added by the frontend as captions settle. Each gets an id that
+// is never reused, so edits, deletions and merges made in the UI land on the
+// line they were made on even when new lines arrived in between. A merge
+// keeps the id, timestamp and speaker of its earliest line.
+//
+// A line made from final captions knows their ids, and so the rows they were
+// stored as in transcript_lines. Manual edits, deletions and merges are
+// written to those rows, so the saved session matches what was corrected: an
+// edited line's text goes into its first row and its other rows are removed.
+// Captions that arrive after a line was edited by hand are appended to it
+// rather than replacing the edit.
+//
+// `revision` goes up on every change; the recovery heartbeat uses it to tell
+// whether the on-disk mirror is stale.
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::{caption_batch, database, now_millis, AppState};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptLine {
+    pub id: String,
+    pub text: String,
+    pub timestamp: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<String>,
+    #[serde(default)]
+    pub edited: bool, // Changed by hand after it was captioned
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub caption_ids: Vec<String>, // Final captions the line was made from, as stored
+    #[serde(skip)]
+    captioned: String, // Text as last captioned, before any edit
+}
+
+impl TranscriptLine {
+    fn new(text: String, speaker: Option<String>, caption_id: Option<String>) -> Self {
+        TranscriptLine {
+            id: uuid::Uuid::new_v4().to_string(),
+            captioned: text.clone(),
+            text,
+            timestamp: now_millis(),
+            speaker,
+            edited: false,
+            caption_ids: caption_id.into_iter().collect(),
+        }
+    }
+}
+
+/// A change to the stored caption rows of a line
+#[derive(Debug, PartialEq)]
+pub enum RowChange {
+    Text { caption_ids: Vec<String>, text: String },
+    Delete { caption_ids: Vec<String> },
+}
+
+impl RowChange {
+    fn text(line: &TranscriptLine) -> Option<Self> {
+        (!line.caption_ids.is_empty()).then(|| RowChange::Text {
+            caption_ids: line.caption_ids.clone(),
+            text: line.text.clone(),
+        })
+    }
+
+    fn delete(line: TranscriptLine) -> Option<Self> {
+        (!line.caption_ids.is_empty()).then_some(RowChange::Delete { caption_ids: line.caption_ids })
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Transcript {
+    lines: Vec<TranscriptLine>,
+    revision: u64,
+}
+
+impl Transcript {
+    pub fn lines(&self) -> &[TranscriptLine] {
+        &self.lines
+    }
+
+    pub fn texts(&self) -> Vec<String> {
+        self.lines.iter().map(|l| l.text.clone()).collect()
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    fn changed(&mut self) {
+        self.revision += 1;
+    }
+
+    pub fn push(&mut self, text: String, speaker: Option<String>, caption_id: Option<String>) -> TranscriptLine {
+        let line = TranscriptLine::new(text, speaker, caption_id);
+        self.lines.push(line.clone());
+        self.changed();
+        line
+    }
+
+    /// Replace the newest line as more of it is captioned (smart merging);
+    /// that is not a manual edit. A line edited by hand keeps its text and
+    /// only gets what was captioned since appended.
+    pub fn replace_last(&mut self, text: String, caption_id: Option<String>) -> TranscriptLine {
+        let Some(last) = self.lines.last_mut() else {
+            return self.push(text, None, caption_id);
+        };
+        if last.edited {
+            let Some(added) = text.strip_prefix(last.captioned.as_str()) else {
+                return self.push(text, None, caption_id);
+            };
+            let added = added.trim();
+            if !added.is_empty() {
+                last.text = format!("{} {}", last.text.trim_end(), added);
+            }
+        } else {
+            last.text = text.clone();
+        }
+        last.captioned = text;
+        last.caption_ids.extend(caption_id);
+        let last = last.clone();
+        self.changed();
+        last
+    }
+
+    /// Take over a whole transcript from the frontend. Lines whose text is
+    /// unchanged at their position are kept; changed ones keep their id and
+    /// count as edited.
+    pub fn set_texts(&mut self, texts: Vec<String>) -> Vec<RowChange> {
+        let mut previous = std::mem::take(&mut self.lines).into_iter();
+        let mut changes = Vec::new();
+        for text in texts {
+            let line = match previous.next() {
+                Some(line) if line.text == text => line,
+                Some(mut line) => {
+                    line.text = text;
+                    line.edited = true;
+                    changes.extend(RowChange::text(&line));
+                    line
+                }
+                None => TranscriptLine::new(text, None, None),
+            };
+            self.lines.push(line);
+        }
+        changes.extend(previous.filter_map(RowChange::delete));
+        self.changed();
+        changes
+    }
+
+    /// Put recovered lines in front of the current ones
+    pub fn prepend(&mut self, texts: Vec<String>) {
+        let mut lines: Vec<TranscriptLine> =
+            texts.into_iter().map(|t| TranscriptLine::new(t, None, None)).collect();
+        lines.append(&mut self.lines);
+        self.lines = lines;
+        self.changed();
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.changed();
+    }
+
+    fn position(&self, id: &str) -> Result<usize, String> {
+        self.lines
+            .iter()
+            .position(|l| l.id == id)
+            .ok_or_else(|| format!("Transcript line {} not found", id))
+    }
+
+    pub fn edit(&mut self, id: &str, text: String) -> Result<TranscriptLine, String> {
+        let index = self.position(id)?;
+        let line = &mut self.lines[index];
+        line.text = text;
+        line.edited = true;
+        let line = line.clone();
+        self.changed();
+        Ok(line)
+    }
+
+    /// Remove a line; returns it
+    pub fn delete(&mut self, id: &str) -> Result<TranscriptLine, String> {
+        let index = self.position(id)?;
+        let line = self.lines.remove(index);
+        self.changed();
+        Ok(line)
+    }
+
+    /// Join lines into the earliest of them, in transcript order
+    pub fn merge(&mut self, ids: &[String]) -> Result<TranscriptLine, String> {
+        let mut indexes = ids.iter().map(|id| self.position(id)).collect::<Result<Vec<_>, _>>()?;
+        indexes.sort_unstable();
+        indexes.dedup();
+        if indexes.len() < 2 {
+            return Err("Select at least two lines to merge".to_string());
+        }
+        let text = indexes
+            .iter()
+            .map(|&i| self.lines[i].text.trim())
+            .filter(|t| !t.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut caption_ids = Vec::new();
+        for &i in indexes[1..].iter().rev() {
+            let mut removed = self.lines.remove(i);
+            removed.caption_ids.append(&mut caption_ids);
+            caption_ids = removed.caption_ids;
+        }
+        let merged = &mut self.lines[indexes[0]];
+        merged.text = text;
+        merged.edited = true;
+        merged.caption_ids.append(&mut caption_ids);
+        let merged = merged.clone();
+        self.changed();
+        Ok(merged)
+    }
+}
+
+/// The live transcript with line ids, oldest first
+#[tauri::command]
+pub async fn get_transcript_lines(state: tauri::State<'_, Arc<AppState>>) -> Result<Vec<TranscriptLine>, String> {
+    let transcript = state.transcript_lines.lock().map_err(|e| e.to_string())?;
+    Ok(transcript.lines().to_vec())
+}
+
+/// Write changes to the stored caption rows. Finals still queued are written
+/// first so the rows exist.
+pub fn store_changes(state: &AppState, changes: &[RowChange]) -> Result<(), String> {
+    if changes.is_empty() {
+        return Ok(());
+    }
+    caption_batch::flush(state);
+    let conn = state.db()?;
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    for change in changes {
+        match change {
+            RowChange::Text { caption_ids, text } => database::set_caption_text(&tx, caption_ids, text),
+            RowChange::Delete { caption_ids } => database::delete_captions(&tx, caption_ids),
+        }
+        .map_err(|e| format!("Failed to save the transcript edit: {}", e))?;
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Apply `change` to a copy of the transcript and keep it once the rows it
+/// touches are saved
+pub fn update<T>(
+    state: &AppState,
+    change: impl FnOnce(&mut Transcript) -> Result<(T, Vec<RowChange>), String>,
+) -> Result<T, String> {
+    let mut transcript = state.transcript_lines.lock().map_err(|e| e.to_string())?;
+    let mut updated = transcript.clone();
+    let (result, changes) = change(&mut updated)?;
+    store_changes(state, &changes)?;
+    *transcript = updated;
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn edit_transcript_line(
+    state: tauri::State<'_, Arc<AppState>>,
+    id: String,
+    text: String,
+) -> Result<TranscriptLine, String> {
+    update(&state, |transcript| {
+        let line = transcript.edit(&id, text)?;
+        let changes = RowChange::text(&line).into_iter().collect();
+        Ok((line, changes))
+    })
+}
+
+#[tauri::command]
+pub async fn delete_transcript_line(state: tauri::State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    update(&state, |transcript| {
+        let line = transcript.delete(&id)?;
+        Ok(((), RowChange::delete(line).into_iter().collect()))
+    })
+}
+
+/// Merge lines into one; returns the merged line
+#[tauri::command]
+pub async fn merge_lines(state: tauri::State<'_, Arc<AppState>>, ids: Vec<String>) -> Result<TranscriptLine, String> {
+    update(&state, |transcript| {
+        let merged = transcript.merge(&ids)?;
+        let changes = RowChange::text(&merged).into_iter().collect();
+        Ok((merged, changes))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_and_merge() {
+        let mut transcript = Transcript::default();
+        for (i, text) in ["so the plan", "is to ship", "on friday"].into_iter().enumerate() {
+            transcript.push(text.to_string(), None, Some(format!("c{}", i)));
+        }
+        let ids: Vec<String> = transcript.lines().iter().map(|l| l.id.clone()).collect();
+
+        let edited = transcript.edit(&ids[2], "on Friday".to_string()).unwrap();
+        assert!(edited.edited);
+
+        let merged = transcript.merge(&[ids[1].clone(), ids[0].clone()]).unwrap();
+        assert_eq!(merged.id, ids[0]);
+        assert_eq!(merged.caption_ids, vec!["c0", "c1"]);
+        assert_eq!(transcript.texts(), vec!["so the plan is to ship", "on Friday"]);
+
+        // More captions for an edited line don't undo the edit
+        let last = transcript.replace_last("on friday at noon".to_string(), Some("c3".to_string()));
+        assert_eq!((last.text.as_str(), last.caption_ids.len()), ("on Friday at noon", 2));
+
+        let changes = transcript.set_texts(vec!["So the plan is to ship".to_string()]);
+        assert_eq!(
+            changes,
+            vec![
+                RowChange::Text {
+                    caption_ids: vec!["c0".to_string(), "c1".to_string()],
+                    text: "So the plan is to ship".to_string(),
+                },
+                RowChange::Delete { caption_ids: vec!["c2".to_string(), "c3".to_string()] },
+            ]
+        );
+        assert!(transcript.delete(&ids[2]).is_err());
+        assert_eq!(transcript.delete(&ids[0]).unwrap().id, ids[0]);
+    }
+}
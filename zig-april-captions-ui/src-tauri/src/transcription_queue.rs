@@ -0,0 +1,272 @@
+// Batch file transcription
+//
+// Audio files queued with `enqueue_files` are transcribed one at a time by a
+// background worker using the caption engine's file mode. Each finished file
+// becomes a session named after it, with the file kept as the session's
+// recording so it can be re-transcribed later. Every change to a job emits
+// `transcription-job` with the job, including progress while it runs.
+//
+// The queue lives in memory; jobs still queued when the app quits are
+// dropped.
+use serde::Serialize;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+use crate::database::{self, CaptionWord, TranscriptLine};
+use crate::transcribe::{transcribe_file, FileCaption};
+use crate::{embeddings, import, now_millis, AppState};
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptionJob {
+    pub id: String,
+    pub path: String,
+    pub model_path: String,
+    pub status: JobStatus,
+    pub progress: u32, // Percent of the file read
+    pub queued_at: i64,
+    pub session_id: Option<String>, // Set once the job is done
+    pub lines: usize,
+    pub error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct TranscriptionQueue {
+    jobs: Mutex<Vec<TranscriptionJob>>,
+    cancel: Mutex<Option<(String, Arc<AtomicBool>)>>, // Flag of the running job
+    wake: Notify,
+}
+
+/// Apply `f` to a job and emit its new state
+fn update_job(app_handle: &AppHandle, id: &str, f: impl FnOnce(&mut TranscriptionJob)) {
+    let queue = app_handle.state::<TranscriptionQueue>();
+    let job = queue.jobs.lock().ok().and_then(|mut jobs| {
+        let job = jobs.iter_mut().find(|j| j.id == id)?;
+        f(job);
+        Some(job.clone())
+    });
+    if let Some(job) = job {
+        let _ = app_handle.emit("transcription-job", &job);
+    }
+}
+
+/// Take the oldest queued job and mark it running
+fn next_job(app_handle: &AppHandle) -> Option<(TranscriptionJob, Arc<AtomicBool>)> {
+    let queue = app_handle.state::<TranscriptionQueue>();
+    let job = {
+        let mut jobs = queue.jobs.lock().ok()?;
+        let job = jobs.iter_mut().find(|j| j.status == JobStatus::Queued)?;
+        job.status = JobStatus::Running;
+        job.clone()
+    };
+    let cancel = Arc::new(AtomicBool::new(false));
+    if let Ok(mut current) = queue.cancel.lock() {
+        *current = Some((job.id.clone(), cancel.clone()));
+    }
+    let _ = app_handle.emit("transcription-job", &job);
+    Some((job, cancel))
+}
+
+/// Store the captions of a transcribed file as a finished session
+fn save_result(app_handle: &AppHandle, job: &TranscriptionJob, captions: &[FileCaption]) -> Result<String, String> {
+    let path = Path::new(&job.path);
+    // Place the session so it ends when the file was last written
+    let duration = captions.last().map(|c| c.offset_ms).unwrap_or(0);
+    let ended_at = std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_else(now_millis);
+    let started_at = ended_at - duration;
+    let title = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "Transcribed file".to_string());
+    let lines: Vec<TranscriptLine> = captions
+        .iter()
+        .map(|c| TranscriptLine {
+            timestamp: started_at + c.offset_ms,
+            text: c.text.clone(),
+            speaker: None,
+        })
+        .collect();
+
+    let state = app_handle.state::<Arc<AppState>>();
+    let conn = state.db()?;
+    let metadata = serde_json::json!({ "transcribed": job.path });
+    let session = import::save_session(&conn, &title, started_at, ended_at, "file", &job.model_path, &lines, &metadata)?;
+    database::set_session_audio_path(&conn, &session.id, &job.path).map_err(|e| e.to_string())?;
+    for caption in captions.iter().filter(|c| !c.words.is_empty()) {
+        let words: Vec<CaptionWord> = caption
+            .words
+            .iter()
+            .map(|w| CaptionWord {
+                start_ms: started_at + w.start_ms,
+                end_ms: started_at + w.end_ms,
+                ..w.clone()
+            })
+            .collect();
+        database::add_caption_words(&conn, &uuid::Uuid::new_v4().to_string(), Some(&session.id), &words)
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(session.id)
+}
+
+async fn run_job(app_handle: &AppHandle, job: TranscriptionJob, cancel: Arc<AtomicBool>) {
+    info!("Transcribing {} with {}", job.path, job.model_path);
+    let result = match crate::get_zig_binary_path(app_handle) {
+        Ok(binary_path) => {
+            let app_handle = app_handle.clone();
+            let job = job.clone();
+            let cancel = cancel.clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                transcribe_file(&binary_path, &job.model_path, &job.path, &cancel, |percent| {
+                    update_job(&app_handle, &job.id, |j| j.progress = percent);
+                })
+            })
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|r| r)
+        }
+        Err(e) => Err(e),
+    };
+
+    let outcome = result.and_then(|captions| {
+        let session_id = save_result(app_handle, &job, &captions)?;
+        Ok((session_id, captions.len()))
+    });
+    match outcome {
+        Ok((session_id, lines)) => {
+            info!("Transcribed {} into session {} ({} lines)", job.path, session_id, lines);
+            embeddings::wake(app_handle);
+            update_job(app_handle, &job.id, |j| {
+                j.status = JobStatus::Done;
+                j.progress = 100;
+                j.session_id = Some(session_id);
+                j.lines = lines;
+            });
+        }
+        Err(_) if cancel.load(Ordering::Relaxed) => {
+            update_job(app_handle, &job.id, |j| j.status = JobStatus::Cancelled);
+        }
+        Err(e) => {
+            warn!("Transcription of {} failed: {}", job.path, e);
+            update_job(app_handle, &job.id, |j| {
+                j.status = JobStatus::Failed;
+                j.error = Some(e);
+            });
+        }
+    }
+}
+
+/// Start the background worker
+pub fn start(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            while let Some((job, cancel)) = next_job(&app_handle) {
+                run_job(&app_handle, job, cancel).await;
+            }
+            let queue = app_handle.state::<TranscriptionQueue>();
+            if let Ok(mut current) = queue.cancel.lock() {
+                *current = None;
+            }
+            queue.wake.notified().await;
+        }
+    });
+}
+
+/// Queue audio files (16-bit mono WAV) for transcription with `model_path`,
+/// or the model from settings
+#[tauri::command]
+pub async fn enqueue_files(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    queue: tauri::State<'_, TranscriptionQueue>,
+    paths: Vec<String>,
+    model_path: Option<String>,
+) -> Result<Vec<TranscriptionJob>, String> {
+    let model_path = match model_path.filter(|m| !m.is_empty()) {
+        Some(model_path) => model_path,
+        None => state.settings.lock().map_err(|e| e.to_string())?.model_path.clone(),
+    };
+    if !Path::new(&model_path).exists() {
+        return Err(format!("Model not found: {}", model_path));
+    }
+    if let Some(missing) = paths.iter().find(|p| !Path::new(p).is_file()) {
+        return Err(format!("File not found: {}", missing));
+    }
+
+    let added: Vec<TranscriptionJob> = paths
+        .into_iter()
+        .map(|path| TranscriptionJob {
+            id: uuid::Uuid::new_v4().to_string(),
+            path,
+            model_path: model_path.clone(),
+            status: JobStatus::Queued,
+            progress: 0,
+            queued_at: now_millis(),
+            session_id: None,
+            lines: 0,
+            error: None,
+        })
+        .collect();
+    queue.jobs.lock().map_err(|e| e.to_string())?.extend(added.iter().cloned());
+    for job in &added {
+        let _ = app_handle.emit("transcription-job", job);
+    }
+    queue.wake.notify_one();
+    Ok(added)
+}
+
+/// All jobs of this run, oldest first
+#[tauri::command]
+pub async fn get_queue_status(queue: tauri::State<'_, TranscriptionQueue>) -> Result<Vec<TranscriptionJob>, String> {
+    Ok(queue.jobs.lock().map_err(|e| e.to_string())?.clone())
+}
+
+/// Cancel a queued or running job
+#[tauri::command]
+pub async fn cancel_job(
+    app_handle: AppHandle,
+    queue: tauri::State<'_, TranscriptionQueue>,
+    id: String,
+) -> Result<(), String> {
+    let status = queue
+        .jobs
+        .lock()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .find(|j| j.id == id)
+        .map(|j| j.status)
+        .ok_or_else(|| format!("Job {} not found", id))?;
+    match status {
+        JobStatus::Queued => {
+            update_job(&app_handle, &id, |j| j.status = JobStatus::Cancelled);
+            Ok(())
+        }
+        JobStatus::Running => {
+            // The worker marks the job cancelled once the engine has stopped
+            if let Some((running, flag)) = queue.cancel.lock().map_err(|e| e.to_string())?.as_ref() {
+                if *running == id {
+                    flag.store(true, Ordering::Relaxed);
+                }
+            }
+            Ok(())
+        }
+        _ => Err(format!("Job {} has already finished", id)),
+    }
+}
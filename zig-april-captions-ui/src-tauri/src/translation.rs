@@ -0,0 +1,132 @@
+// Live caption translation
+//
+// When `AISettings.translation_language` is set, final captions are queued
+// here, translated in small batches (one request per burst of speech rather
+// than per line) and sent back as `caption-translation` events carrying the
+// original caption id, so the UI can pair them for bilingual subtitles. Each
+// translated batch is read aloud when `TtsSettings.speak_translations` is on.
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tracing::{info, warn};
+
+use crate::ai::{self, generate_content};
+use crate::AppState;
+
+/// How long to wait for more captions before sending a batch
+const BATCH_WINDOW: Duration = Duration::from_millis(1500);
+
+/// Most captions translated in one request
+const MAX_BATCH: usize = 8;
+
+const SYSTEM_PROMPT: &str = "You translate live meeting captions. \
+    You receive a JSON array of caption lines in order. Respond with a JSON array of strings \
+    holding their translations, same length and order. Keep names and technical terms as they are.";
+
+struct PendingCaption {
+    id: String,
+    text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CaptionTranslation {
+    caption_id: String,
+    text: String,
+    translation: String,
+    language: String,
+}
+
+/// Managed state: queue feeding the translation worker
+pub struct TranslationState {
+    tx: UnboundedSender<PendingCaption>,
+    rx: Mutex<Option<UnboundedReceiver<PendingCaption>>>,
+}
+
+impl Default for TranslationState {
+    fn default() -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        Self { tx, rx: Mutex::new(Some(rx)) }
+    }
+}
+
+/// The language of the session's template, else the configured one
+fn target_language(app_handle: &AppHandle) -> Option<String> {
+    let state = app_handle.state::<Arc<AppState>>();
+    let template = crate::templates::current(&state).and_then(|t| t.translation_language);
+    let settings = state.settings.lock().ok()?;
+    let ai = settings.ai.as_ref().filter(|ai| !ai.api_key.is_empty())?;
+    template
+        .or_else(|| ai.translation_language.clone())
+        .filter(|lang| !lang.trim().is_empty())
+}
+
+/// Queue a final caption for translation if a target language is set
+pub fn on_final_caption(app_handle: &AppHandle, id: &str, text: &str) {
+    if text.trim().is_empty() || target_language(app_handle).is_none() {
+        return;
+    }
+    let caption = PendingCaption { id: id.to_string(), text: text.to_string() };
+    let _ = app_handle.state::<TranslationState>().tx.send(caption);
+}
+
+async fn translate_batch(app_handle: &AppHandle, batch: &[PendingCaption], language: &str) -> Result<Vec<String>, String> {
+    let ai = ai::settings(&app_handle.state::<Arc<AppState>>())?;
+    let lines: Vec<&str> = batch.iter().map(|c| c.text.as_str()).collect();
+    let prompt = format!(
+        "Translate into {}:\n{}",
+        language,
+        serde_json::to_string(&lines).map_err(|e| e.to_string())?
+    );
+    let output = generate_content(&ai, SYSTEM_PROMPT, &prompt, true).await?;
+    match serde_json::from_str::<Vec<String>>(&output) {
+        Ok(translations) if translations.len() == batch.len() => Ok(translations),
+        _ if batch.len() == 1 => Ok(vec![output.trim().to_string()]),
+        _ => Err(format!("Expected {} translations, got: {}", batch.len(), output)),
+    }
+}
+
+/// Start the background worker
+pub fn start(app_handle: AppHandle) {
+    let Some(mut rx) = app_handle.state::<TranslationState>().rx.lock().ok().and_then(|mut rx| rx.take()) else {
+        return;
+    };
+    tauri::async_runtime::spawn(async move {
+        info!("Caption translation worker started");
+        while let Some(first) = rx.recv().await {
+            // Collect the rest of the burst
+            let mut batch = vec![first];
+            let deadline = tokio::time::Instant::now() + BATCH_WINDOW;
+            while batch.len() < MAX_BATCH {
+                match tokio::time::timeout_at(deadline, rx.recv()).await {
+                    Ok(Some(caption)) => batch.push(caption),
+                    _ => break,
+                }
+            }
+
+            // Translation may have been switched off while captions were queued
+            let Some(language) = target_language(&app_handle) else {
+                continue;
+            };
+            match translate_batch(&app_handle, &batch, &language).await {
+                Ok(translations) => {
+                    crate::tts::speak_translation_if_enabled(&app_handle, &translations.join(" "));
+                    for (caption, translation) in batch.into_iter().zip(translations) {
+                        let _ = app_handle.emit(
+                            "caption-translation",
+                            CaptionTranslation {
+                                caption_id: caption.id,
+                                text: caption.text,
+                                translation,
+                                language: language.clone(),
+                            },
+                        );
+                    }
+                }
+                Err(e) => warn!("Caption translation failed: {}", e),
+            }
+        }
+    });
+}
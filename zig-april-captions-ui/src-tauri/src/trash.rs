@@ -0,0 +1,254 @@
+// Recycle bin for knowledge notes, ideas and chat entries
+//
+// Deleting one of them moves it into the `trash` table, as JSON with the time
+// it was deleted (`deleted_at`), instead of dropping it. `restore_entry` puts
+// it back where it came from and `purge_trash` removes for good whatever has
+// been in the bin for longer than a number of days. An idea's script
+// versions are kept until the idea is purged.
+//
+// Knowledge notes and ideas live in JSON stores, so folder sync sees a
+// deletion when they are trashed and a new change when they are restored.
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::AppHandle;
+use tracing::info;
+
+use crate::database::{self, ChatHistoryEntry};
+use crate::{embeddings, idea_versions, now_millis, AppState, IdeaEntry, KnowledgeEntry};
+
+/// Characters of an entry's text shown as its label in the bin
+const LABEL_CHARS: usize = 80;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TrashKind {
+    Knowledge,
+    Idea,
+    ChatEntry,
+}
+
+impl TrashKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            TrashKind::Knowledge => "knowledge",
+            TrashKind::Idea => "idea",
+            TrashKind::ChatEntry => "chat_entry",
+        }
+    }
+
+    fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "knowledge" => Some(TrashKind::Knowledge),
+            "idea" => Some(TrashKind::Idea),
+            "chat_entry" => Some(TrashKind::ChatEntry),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TrashedEntry {
+    pub id: String,
+    pub kind: TrashKind,
+    pub label: String,
+    pub deleted_at: i64,
+    pub entry: serde_json::Value,
+}
+
+fn label(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.char_indices().nth(LABEL_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text,
+    }
+}
+
+/// Put an entry in the bin; a later deletion of the same id replaces it
+pub fn put<T: Serialize>(conn: &Connection, kind: TrashKind, id: &str, text: &str, entry: &T) -> Result<(), String> {
+    let payload = serde_json::to_string(entry).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT OR REPLACE INTO trash (id, kind, label, deleted_at, payload) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![id, kind.as_str(), label(text), now_millis(), payload],
+    )
+    .map_err(|e| format!("Failed to move entry to the trash: {}", e))?;
+    Ok(())
+}
+
+/// Move every chat entry into the bin; returns how many there were
+pub fn trash_chat_entries(conn: &Connection) -> Result<usize, String> {
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let mut stmt = tx
+        .prepare(&format!("SELECT {} FROM chat_entries", database::CHAT_ENTRY_COLUMNS))
+        .map_err(|e| format!("Prepare failed: {}", e))?;
+    let entries = stmt
+        .query_map([], database::chat_entry_from_row)
+        .map_err(|e| format!("Query failed: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read chat history: {}", e))?;
+    drop(stmt);
+    for entry in &entries {
+        put(&tx, TrashKind::ChatEntry, &entry.id, &entry.content, entry)?;
+    }
+    tx.execute("DELETE FROM chat_entries", [])
+        .map_err(|e| format!("Failed to clear chat history: {}", e))?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(entries.len())
+}
+
+fn take(conn: &Connection, id: &str) -> Result<TrashedEntry, String> {
+    let (kind, label, deleted_at, payload): (String, String, i64, String) = conn
+        .query_row(
+            "SELECT kind, label, deleted_at, payload FROM trash WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .map_err(|_| format!("Entry {} is not in the trash", id))?;
+    Ok(TrashedEntry {
+        id: id.to_string(),
+        kind: TrashKind::parse(&kind).ok_or_else(|| format!("Unknown trashed entry kind: {}", kind))?,
+        label,
+        deleted_at,
+        entry: serde_json::from_str(&payload).map_err(|e| e.to_string())?,
+    })
+}
+
+/// Remove trashed entries deleted before `before`; returns how many
+pub fn purge(conn: &Connection, before: i64) -> rusqlite::Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+    let ideas: Vec<String> = tx
+        .prepare("SELECT id FROM trash WHERE kind = 'idea' AND deleted_at < ?1")?
+        .query_map(params![before], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    for id in &ideas {
+        idea_versions::delete_versions(&tx, id)?;
+    }
+    let purged = tx.execute("DELETE FROM trash WHERE deleted_at < ?1", params![before])?;
+    tx.commit()?;
+    Ok(purged)
+}
+
+/// Trashed entries, most recently deleted first, optionally of one kind
+#[tauri::command]
+pub async fn list_trashed(
+    state: tauri::State<'_, Arc<AppState>>,
+    kind: Option<TrashKind>,
+) -> Result<Vec<TrashedEntry>, String> {
+    let conn = state.db()?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, kind, label, deleted_at, payload FROM trash
+             WHERE ?1 IS NULL OR kind = ?1 ORDER BY deleted_at DESC",
+        )
+        .map_err(|e| format!("Prepare failed: {}", e))?;
+    let rows = stmt
+        .query_map(params![kind.map(TrashKind::as_str)], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i64>(3)?,
+                row.get::<_, String>(4)?,
+            ))
+        })
+        .map_err(|e| format!("Query failed: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|(id, kind, label, deleted_at, payload)| {
+            Some(TrashedEntry {
+                id,
+                kind: TrashKind::parse(&kind)?,
+                label,
+                deleted_at,
+                entry: serde_json::from_str(&payload).ok()?,
+            })
+        })
+        .collect())
+}
+
+/// Put a trashed entry back where it was deleted from
+#[tauri::command]
+pub async fn restore_entry(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    id: String,
+) -> Result<TrashedEntry, String> {
+    let conn = state.db()?;
+    let trashed = take(&conn, &id)?;
+    let unreadable = |e: serde_json::Error| format!("Trashed entry {} is unreadable: {}", id, e);
+    match trashed.kind {
+        TrashKind::Knowledge => {
+            let entry: KnowledgeEntry = serde_json::from_value(trashed.entry.clone()).map_err(unreadable)?;
+            state.knowledge.update(|entries| {
+                if !entries.iter().any(|e| e.id == entry.id) {
+                    entries.push(entry);
+                }
+            })?;
+        }
+        TrashKind::Idea => {
+            let entry: IdeaEntry = serde_json::from_value(trashed.entry.clone()).map_err(unreadable)?;
+            state.ideas.update(|entries| {
+                if !entries.iter().any(|e| e.id == entry.id) {
+                    // The store is kept newest first
+                    let at = entries.iter().position(|e| e.created_at < entry.created_at).unwrap_or(entries.len());
+                    entries.insert(at, entry);
+                }
+            })?;
+        }
+        TrashKind::ChatEntry => {
+            let entry: ChatHistoryEntry = serde_json::from_value(trashed.entry.clone()).map_err(unreadable)?;
+            database::save_chat_entry(&conn, &entry).map_err(|e| format!("Failed to restore chat entry: {}", e))?;
+        }
+    }
+    conn.execute("DELETE FROM trash WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    drop(conn);
+    if trashed.kind != TrashKind::Idea {
+        embeddings::wake(&app_handle);
+    }
+    info!("Restored {} {} from the trash", trashed.kind.as_str(), id);
+    Ok(trashed)
+}
+
+/// Permanently delete entries trashed more than `older_than_days` ago, or
+/// everything in the bin when no age is given; returns how many were removed
+#[tauri::command]
+pub async fn purge_trash(
+    state: tauri::State<'_, Arc<AppState>>,
+    older_than_days: Option<u32>,
+) -> Result<usize, String> {
+    let before = match older_than_days {
+        Some(days) => now_millis() - days as i64 * 24 * 60 * 60 * 1000,
+        None => i64::MAX,
+    };
+    let conn = state.db()?;
+    let purged = purge(&conn, before).map_err(|e| format!("Failed to empty the trash: {}", e))?;
+    info!("Purged {} entries from the trash", purged);
+    Ok(purged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trash_and_purge() {
+        let conn = database::init_memory_db().unwrap();
+        let idea = serde_json::json!({"id": "idea", "title": "Launch video"});
+        put(&conn, TrashKind::Idea, "idea", "Launch   video", &idea).unwrap();
+        idea_versions::record(&conn, "idea", None, "script").unwrap();
+
+        let trashed = take(&conn, "idea").unwrap();
+        assert_eq!((trashed.kind, trashed.label.as_str()), (TrashKind::Idea, "Launch video"));
+        assert_eq!(trashed.entry, idea);
+        assert_eq!(label(&"x".repeat(100)).chars().count(), LABEL_CHARS + 1);
+
+        assert_eq!(purge(&conn, trashed.deleted_at).unwrap(), 0);
+        assert_eq!(purge(&conn, i64::MAX).unwrap(), 1);
+        let versions: i64 = conn.query_row("SELECT COUNT(*) FROM idea_versions", [], |row| row.get(0)).unwrap();
+        assert_eq!(versions, 0);
+        assert!(take(&conn, "idea").is_err());
+    }
+}
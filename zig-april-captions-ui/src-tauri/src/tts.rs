@@ -0,0 +1,299 @@
+// Text-to-speech readback
+//
+// Speaks AI answers (and optionally translated captions) using the OS speech
+// engine through its command-line front end:
+// - Linux: espeak-ng (piped to paplay when an output device is chosen),
+//   falling back to speech-dispatcher's spd-say
+// - macOS: say
+// - Windows: System.Speech via PowerShell
+//
+// Text is passed after `--` (or over stdin) so it is never read as an option.
+// Finished processes are reaped in the background.
+use serde::{Deserialize, Serialize};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tracing::{debug, warn};
+
+use crate::AppState;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TtsSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub voice: Option<String>,
+    #[serde(default = "default_rate")]
+    pub rate: u32, // Words per minute
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_device: Option<String>, // Not supported on Windows
+    #[serde(default = "crate::default_true")]
+    pub speak_answers: bool,
+    #[serde(default)]
+    pub speak_translations: bool,
+}
+
+fn default_rate() -> u32 {
+    180
+}
+
+impl Default for TtsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            voice: None,
+            rate: default_rate(),
+            output_device: None,
+            speak_answers: true,
+            speak_translations: false,
+        }
+    }
+}
+
+/// Managed state holding the processes of the utterance being spoken
+#[derive(Default)]
+pub struct TtsState {
+    speaking: Mutex<Vec<Child>>,
+}
+
+/// How often finished speech processes are checked for
+const REAP_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Reap processes that finished; returns whether any are still running
+fn reap(tts: &TtsState) -> bool {
+    let Ok(mut children) = tts.speaking.lock() else {
+        return false;
+    };
+    children.retain_mut(|child| matches!(child.try_wait(), Ok(None)));
+    !children.is_empty()
+}
+
+fn stop(tts: &TtsState) {
+    if let Ok(mut children) = tts.speaking.lock() {
+        for mut child in children.drain(..) {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn spawn_speech(text: &str, settings: &TtsSettings) -> Result<Vec<Child>, String> {
+    let mut args = vec!["-s".to_string(), settings.rate.to_string()];
+    if let Some(voice) = &settings.voice {
+        args.push("-v".to_string());
+        args.push(voice.clone());
+    }
+
+    if let Some(device) = &settings.output_device {
+        // Route espeak-ng's WAV output to the chosen PulseAudio/PipeWire sink
+        let mut espeak = Command::new("espeak-ng")
+            .args(&args)
+            .arg("--stdout")
+            .arg("--")
+            .arg(text)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Failed to run espeak-ng: {}", e))?;
+        let audio = espeak.stdout.take().ok_or("Failed to capture espeak-ng output")?;
+        let player = Command::new("paplay")
+            .arg(format!("--device={}", device))
+            .stdin(Stdio::from(audio))
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Failed to run paplay: {}", e))?;
+        return Ok(vec![espeak, player]);
+    }
+
+    match Command::new("espeak-ng").args(&args).arg("--").arg(text).stderr(Stdio::null()).spawn() {
+        Ok(child) => Ok(vec![child]),
+        Err(_) => {
+            // speech-dispatcher rate is -100..100 around a ~180 wpm default
+            let rate = ((settings.rate as i32 - 180) / 2).clamp(-100, 100);
+            let mut cmd = Command::new("spd-say");
+            cmd.args(["-w", "-r", &rate.to_string()]);
+            if let Some(voice) = &settings.voice {
+                cmd.args(["-y", voice]);
+            }
+            cmd.arg("--")
+                .arg(text)
+                .spawn()
+                .map(|child| vec![child])
+                .map_err(|e| format!("No speech engine found (install espeak-ng or speech-dispatcher): {}", e))
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn spawn_speech(text: &str, settings: &TtsSettings) -> Result<Vec<Child>, String> {
+    let mut cmd = Command::new("say");
+    cmd.args(["-r", &settings.rate.to_string()]);
+    if let Some(voice) = &settings.voice {
+        cmd.args(["-v", voice]);
+    }
+    if let Some(device) = &settings.output_device {
+        cmd.args(["-a", device]);
+    }
+    cmd.arg("--")
+        .arg(text)
+        .spawn()
+        .map(|child| vec![child])
+        .map_err(|e| format!("Failed to run say: {}", e))
+}
+
+#[cfg(target_os = "windows")]
+fn spawn_speech(text: &str, settings: &TtsSettings) -> Result<Vec<Child>, String> {
+    use std::io::Write;
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    // SpeechSynthesizer.Rate is -10..10 around the ~180 wpm default
+    let rate = ((settings.rate as i32 - 180) / 20).clamp(-10, 10);
+    let select_voice = settings
+        .voice
+        .as_ref()
+        .map(|v| format!("$s.SelectVoice('{}');", v.replace('\'', "''")))
+        .unwrap_or_default();
+    let script = format!(
+        "Add-Type -AssemblyName System.Speech; $s = New-Object System.Speech.Synthesis.SpeechSynthesizer; $s.Rate = {}; {} $s.Speak([Console]::In.ReadToEnd())",
+        rate, select_voice
+    );
+
+    // Text goes over stdin so it never needs PowerShell escaping
+    let mut child = Command::new("powershell")
+        .args(["-NoProfile", "-Command", &script])
+        .stdin(Stdio::piped())
+        .creation_flags(CREATE_NO_WINDOW)
+        .spawn()
+        .map_err(|e| format!("Failed to run PowerShell speech: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    Ok(vec![child])
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn spawn_speech(_text: &str, _settings: &TtsSettings) -> Result<Vec<Child>, String> {
+    Err("Text-to-speech is not supported on this platform".to_string())
+}
+
+/// Speak `text`, interrupting anything currently being spoken
+pub fn say(app_handle: &AppHandle, text: &str) -> Result<(), String> {
+    let settings = app_handle
+        .state::<Arc<AppState>>()
+        .settings
+        .lock()
+        .map_err(|e| e.to_string())?
+        .tts
+        .clone()
+        .unwrap_or_default();
+
+    let tts = app_handle.state::<TtsState>();
+    stop(&tts);
+
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(());
+    }
+    debug!("Speaking {} chars", text.len());
+    let children = spawn_speech(text, &settings)?;
+    tts.speaking.lock().map_err(|e| e.to_string())?.extend(children);
+
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(REAP_INTERVAL).await;
+            if !reap(&app_handle.state::<TtsState>()) {
+                break;
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Speak `text` if TTS is enabled and `wanted` picks this kind of readback
+fn speak_if(app_handle: &AppHandle, text: &str, wanted: fn(&TtsSettings) -> bool) {
+    let enabled = app_handle
+        .state::<Arc<AppState>>()
+        .settings
+        .lock()
+        .ok()
+        .and_then(|s| s.tts.as_ref().map(|t| t.enabled && wanted(t)))
+        .unwrap_or(false);
+    if enabled {
+        if let Err(e) = say(app_handle, text) {
+            warn!("Text-to-speech failed: {}", e);
+        }
+    }
+}
+
+/// Speak an AI answer if readback of answers is enabled in settings
+pub fn speak_answer_if_enabled(app_handle: &AppHandle, text: &str) {
+    speak_if(app_handle, text, |t| t.speak_answers);
+}
+
+/// Speak translated captions if readback of translations is enabled in settings
+pub fn speak_translation_if_enabled(app_handle: &AppHandle, text: &str) {
+    speak_if(app_handle, text, |t| t.speak_translations);
+}
+
+#[tauri::command]
+pub async fn speak(app_handle: AppHandle, text: String) -> Result<(), String> {
+    say(&app_handle, &text)
+}
+
+#[tauri::command]
+pub async fn stop_speaking(tts: tauri::State<'_, TtsState>) -> Result<(), String> {
+    stop(&tts);
+    Ok(())
+}
+
+/// List voice names accepted by the `voice` setting
+#[tauri::command]
+pub async fn list_voices() -> Result<Vec<String>, String> {
+    #[cfg(target_os = "linux")]
+    let output = Command::new("espeak-ng").arg("--voices").output();
+    #[cfg(target_os = "macos")]
+    let output = Command::new("say").args(["-v", "?"]).output();
+    #[cfg(target_os = "windows")]
+    let output = Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            "Add-Type -AssemblyName System.Speech; (New-Object System.Speech.Synthesis.SpeechSynthesizer).GetInstalledVoices() | ForEach-Object { $_.VoiceInfo.Name }",
+        ])
+        .output();
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    return Ok(vec![]);
+
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    {
+        let output = output.map_err(|e| format!("Failed to list voices: {}", e))?;
+        let text = String::from_utf8_lossy(&output.stdout);
+
+        // espeak-ng: "Pty Language Age/Gender VoiceName File Other"
+        #[cfg(target_os = "linux")]
+        let voices = text
+            .lines()
+            .skip(1)
+            .filter_map(|l| l.split_whitespace().nth(3).map(|s| s.to_string()))
+            .collect();
+        // say: "Alex               en_US    # Most people recognize me..."
+        #[cfg(target_os = "macos")]
+        let voices = text
+            .lines()
+            .filter_map(|l| l.split("  ").next().map(|s| s.trim().to_string()))
+            .filter(|s| !s.is_empty())
+            .collect();
+        #[cfg(target_os = "windows")]
+        let voices = text
+            .lines()
+            .map(|l| l.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        Ok(voices)
+    }
+}
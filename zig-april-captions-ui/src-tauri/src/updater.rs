@@ -0,0 +1,326 @@
+// Update checker
+//
+// Polls the GitHub releases feed and emits `update-available` when a newer
+// version is published. Installers can be downloaded with checksum
+// verification (assets without a published digest are refused), but
+// installing is left to the user.
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+use crate::AppState;
+
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/minhtranin/zigy/releases/latest";
+
+/// Delay before the first check so startup isn't slowed down
+const INITIAL_CHECK_DELAY: Duration = Duration::from_secs(15);
+
+/// Interval between background checks
+const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Clone, Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    body: Option<String>,
+    html_url: String,
+    #[serde(default)]
+    published_at: Option<String>,
+    #[serde(default)]
+    assets: Vec<GithubAsset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GithubAsset {
+    name: String,
+    browser_download_url: String,
+    size: u64,
+    /// "sha256:<hex>" (populated by GitHub for newer uploads)
+    #[serde(default)]
+    digest: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub url: String,
+    pub size: u64,
+    pub sha256: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateInfo {
+    pub current_version: String,
+    pub latest_version: String,
+    pub update_available: bool,
+    pub release_name: Option<String>,
+    pub release_notes: String,
+    pub release_url: String,
+    pub published_at: Option<String>,
+    pub assets: Vec<ReleaseAsset>,
+    /// Installer best suited to the running platform
+    pub recommended_asset: Option<String>,
+}
+
+/// Parse "v1.2.3" / "1.2.3-beta" into comparable numeric components
+fn parse_version(version: &str) -> Vec<u64> {
+    version
+        .trim()
+        .trim_start_matches(['v', 'V'])
+        .split(['-', '+'])
+        .next()
+        .unwrap_or("")
+        .split('.')
+        .map(|part| part.parse::<u64>().unwrap_or(0))
+        .collect()
+}
+
+/// Whether `latest` is a strictly newer version than `current`
+pub fn is_newer(latest: &str, current: &str) -> bool {
+    let mut latest = parse_version(latest);
+    let mut current = parse_version(current);
+    let len = latest.len().max(current.len());
+    latest.resize(len, 0);
+    current.resize(len, 0);
+    latest > current
+}
+
+/// Pick the installer format matching how this build is distributed
+fn recommended_asset(assets: &[ReleaseAsset]) -> Option<String> {
+    let suffixes: &[&str] = if cfg!(target_os = "linux") {
+        if std::env::var("APPIMAGE").is_ok() {
+            &[".AppImage"]
+        } else {
+            &[".deb", ".AppImage"]
+        }
+    } else if cfg!(target_os = "macos") {
+        &[".dmg"]
+    } else if cfg!(target_os = "windows") {
+        &[".msi", "-setup.exe"]
+    } else {
+        &[]
+    };
+
+    let arch_hint = match std::env::consts::ARCH {
+        "x86_64" => ["x86_64", "amd64", "x64"],
+        "aarch64" => ["aarch64", "arm64", "arm64"],
+        _ => ["", "", ""],
+    };
+
+    suffixes.iter().find_map(|suffix| {
+        let matching: Vec<&ReleaseAsset> = assets
+            .iter()
+            .filter(|a| a.sha256.is_some() && a.name.ends_with(suffix))
+            .collect();
+        matching
+            .iter()
+            .find(|a| arch_hint.iter().any(|h| !h.is_empty() && a.name.contains(h)))
+            .or_else(|| matching.first())
+            .map(|a| a.name.clone())
+    })
+}
+
+async fn fetch_latest_release() -> Result<GithubRelease, String> {
+    let client = reqwest::Client::new();
+    let response = client
+        .get(LATEST_RELEASE_URL)
+        .header("User-Agent", "zigy-update-checker")
+        .header("Accept", "application/vnd.github+json")
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("GitHub API error: {}", response.status()));
+    }
+
+    response
+        .json::<GithubRelease>()
+        .await
+        .map_err(|e| format!("Failed to parse release: {}", e))
+}
+
+async fn check(app_handle: &AppHandle) -> Result<UpdateInfo, String> {
+    let release = fetch_latest_release().await?;
+    let current_version = app_handle.package_info().version.to_string();
+
+    let assets: Vec<ReleaseAsset> = release
+        .assets
+        .into_iter()
+        .map(|a| ReleaseAsset {
+            name: a.name,
+            url: a.browser_download_url,
+            size: a.size,
+            sha256: a
+                .digest
+                .and_then(|d| d.strip_prefix("sha256:").map(|h| h.to_lowercase())),
+        })
+        .collect();
+
+    let info = UpdateInfo {
+        update_available: is_newer(&release.tag_name, &current_version),
+        current_version,
+        latest_version: release.tag_name.trim_start_matches('v').to_string(),
+        release_name: release.name,
+        release_notes: release.body.unwrap_or_default(),
+        release_url: release.html_url,
+        published_at: release.published_at,
+        recommended_asset: recommended_asset(&assets),
+        assets,
+    };
+
+    if info.update_available {
+        info!("Update available: {} -> {}", info.current_version, info.latest_version);
+        let _ = app_handle.emit("update-available", &info);
+    }
+
+    Ok(info)
+}
+
+/// Background task: check shortly after startup and then once a day
+pub fn start_update_checker(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(INITIAL_CHECK_DELAY).await;
+        loop {
+            let enabled = app_handle
+                .state::<Arc<AppState>>()
+                .settings
+                .lock()
+                .map(|s| s.check_for_updates)
+                .unwrap_or(false);
+
+            if enabled {
+                if let Err(e) = check(&app_handle).await {
+                    warn!("Update check failed: {}", e);
+                }
+            }
+
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+/// Check GitHub for a newer release (emits `update-available` if found)
+#[tauri::command]
+pub async fn check_for_updates(app_handle: AppHandle) -> Result<UpdateInfo, String> {
+    check(&app_handle).await
+}
+
+/// Download a release asset to `dest_dir` (default: Downloads), emitting
+/// `update-download-progress` and verifying the SHA-256 digest published by
+/// GitHub. Returns the path of the downloaded file.
+#[tauri::command]
+pub async fn download_update(
+    app_handle: AppHandle,
+    asset_name: String,
+    dest_dir: Option<String>,
+) -> Result<String, String> {
+    let release = fetch_latest_release().await?;
+    let asset = release
+        .assets
+        .into_iter()
+        .find(|a| a.name == asset_name)
+        .ok_or_else(|| format!("Asset {} not found in latest release", asset_name))?;
+    let expected = asset
+        .digest
+        .as_ref()
+        .and_then(|d| d.strip_prefix("sha256:"))
+        .map(|h| h.to_lowercase())
+        .ok_or_else(|| format!("{} has no published checksum", asset.name))?;
+
+    let dest_dir = dest_dir
+        .map(std::path::PathBuf::from)
+        .or_else(dirs::download_dir)
+        .unwrap_or_else(std::env::temp_dir);
+    std::fs::create_dir_all(&dest_dir).map_err(|e| e.to_string())?;
+    let dest = dest_dir.join(&asset.name);
+    let partial = dest_dir.join(format!("{}.part", asset.name));
+
+    let result = match fetch_to_file(&app_handle, &asset, &partial).await {
+        Ok(actual) if actual == expected => {
+            info!("Checksum verified for {}", asset.name);
+            std::fs::rename(&partial, &dest).map_err(|e| e.to_string())
+        }
+        Ok(actual) => Err(format!(
+            "Checksum mismatch for {}: expected {}, got {}",
+            asset.name, expected, actual
+        )),
+        Err(e) => Err(e),
+    };
+    if result.is_err() {
+        let _ = std::fs::remove_file(&partial);
+    }
+    result?;
+
+    #[cfg(unix)]
+    {
+        if asset.name.ends_with(".AppImage") {
+            use std::os::unix::fs::PermissionsExt;
+            let _ = std::fs::set_permissions(&dest, std::fs::Permissions::from_mode(0o755));
+        }
+    }
+
+    Ok(dest.to_string_lossy().to_string())
+}
+
+/// Download the asset into `partial`; returns its SHA-256 digest
+async fn fetch_to_file(app_handle: &AppHandle, asset: &GithubAsset, partial: &std::path::Path) -> Result<String, String> {
+    let response = reqwest::Client::new()
+        .get(&asset.browser_download_url)
+        .header("User-Agent", "zigy-update-checker")
+        .send()
+        .await
+        .map_err(|e| format!("Download failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Download failed: {}", response.status()));
+    }
+
+    let total = response.content_length().unwrap_or(asset.size);
+    let mut file = std::fs::File::create(partial).map_err(|e| e.to_string())?;
+    let mut hasher = Sha256::new();
+    let mut downloaded: u64 = 0;
+    let mut last_percent = 0;
+    let mut stream = response.bytes_stream();
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| format!("Download interrupted: {}", e))?;
+        file.write_all(&chunk).map_err(|e| e.to_string())?;
+        hasher.update(&chunk);
+        downloaded += chunk.len() as u64;
+
+        let percent = (downloaded * 100).checked_div(total).unwrap_or(0);
+        if percent != last_percent {
+            last_percent = percent;
+            let _ = app_handle.emit("update-download-progress", serde_json::json!({
+                "asset": asset.name,
+                "downloaded": downloaded,
+                "total": total,
+                "percent": percent
+            }));
+        }
+    }
+    file.flush().map_err(|e| e.to_string())?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_newer() {
+        assert!(is_newer("v1.0.6", "1.0.5"));
+        assert!(is_newer("1.1", "1.0.9"));
+        assert!(is_newer("v2.0.0-beta", "1.9.9"));
+        assert!(!is_newer("v1.0.5", "1.0.5"));
+        assert!(!is_newer("1.0", "1.0.0"));
+        assert!(!is_newer("v1.0.4", "1.0.5"));
+    }
+}
@@ -0,0 +1,198 @@
+// Vector index for semantic search
+//
+// Embeddings stay in the `embedding` BLOB of their row. sqlite-vec `vec0`
+// tables (`vec_chat_entries`, `vec_knowledge_entries`) index them, so the
+// entries nearest to a query are found inside SQLite instead of by decoding
+// and scoring every BLOB in Rust. Triggers keep each index in step with its
+// table: writing, replacing or clearing a row's embedding, or deleting the
+// row, updates the index in the same statement. Rows whose vector doesn't
+// have EMBEDDING_DIMENSIONS are left out.
+//
+// The extension is linked in and registered for every connection opened
+// after `register`. `rebuild_vector_index` refills both indexes from the
+// stored BLOBs.
+use rusqlite::{ffi, params, Connection, Result as SqliteResult};
+use serde::Serialize;
+use std::os::raw::{c_char, c_int};
+use std::sync::{Arc, Once};
+use tracing::{error, info};
+
+use crate::database::embedding_to_blob;
+use crate::AppState;
+
+/// Size of text-embedding-004 vectors
+pub const EMBEDDING_DIMENSIONS: usize = 768;
+
+/// Most neighbours sqlite-vec returns for one query
+const MAX_K: usize = 4096;
+
+type EntryPoint =
+    unsafe extern "C" fn(*mut ffi::sqlite3, *mut *mut c_char, *const ffi::sqlite3_api_routines) -> c_int;
+
+static REGISTER: Once = Once::new();
+
+#[derive(Debug, Clone, Copy)]
+pub enum Indexed {
+    Chat,
+    Knowledge,
+}
+
+impl Indexed {
+    const ALL: [Indexed; 2] = [Indexed::Chat, Indexed::Knowledge];
+
+    fn table(self) -> &'static str {
+        match self {
+            Indexed::Chat => "chat_entries",
+            Indexed::Knowledge => "knowledge_entries",
+        }
+    }
+
+    fn index(self) -> &'static str {
+        match self {
+            Indexed::Chat => "vec_chat_entries",
+            Indexed::Knowledge => "vec_knowledge_entries",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VectorIndexStats {
+    pub chat_entries: usize,
+    pub knowledge_entries: usize,
+}
+
+/// Make sqlite-vec available to connections opened from now on
+pub fn register() {
+    REGISTER.call_once(|| {
+        // SAFETY: sqlite3_vec_init is the extension's entry point, linked in
+        // statically, and has the signature SQLite calls it with
+        let rc = unsafe {
+            let init = std::mem::transmute::<*const (), EntryPoint>(sqlite_vec::sqlite3_vec_init as *const ());
+            ffi::sqlite3_auto_extension(Some(init))
+        };
+        if rc != ffi::SQLITE_OK {
+            error!("Failed to register sqlite-vec (code {})", rc);
+        }
+    });
+}
+
+/// Create the indexes and their triggers and index existing embeddings
+pub fn create(conn: &Connection) -> SqliteResult<()> {
+    let bytes = EMBEDDING_DIMENSIONS * 4;
+    for indexed in Indexed::ALL {
+        let (table, index) = (indexed.table(), indexed.index());
+        conn.execute_batch(&format!(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS {index} USING vec0(
+                id TEXT PRIMARY KEY,
+                embedding float[{EMBEDDING_DIMENSIONS}] distance_metric=cosine
+            );
+
+            -- INSERT OR REPLACE doesn't fire delete triggers, so inserts clear any stale row first
+            CREATE TRIGGER IF NOT EXISTS {table}_vec_insert AFTER INSERT ON {table} BEGIN
+                DELETE FROM {index} WHERE id = new.id;
+                INSERT INTO {index} (id, embedding) SELECT new.id, new.embedding WHERE length(new.embedding) = {bytes};
+            END;
+            CREATE TRIGGER IF NOT EXISTS {table}_vec_update AFTER UPDATE OF embedding ON {table} BEGIN
+                DELETE FROM {index} WHERE id = old.id;
+                INSERT INTO {index} (id, embedding) SELECT new.id, new.embedding WHERE length(new.embedding) = {bytes};
+            END;
+            CREATE TRIGGER IF NOT EXISTS {table}_vec_delete AFTER DELETE ON {table} BEGIN
+                DELETE FROM {index} WHERE id = old.id;
+            END;"
+        ))?;
+    }
+    rebuild(conn).map(|_| ())
+}
+
+/// Refill both indexes from the stored embeddings. Doesn't open a
+/// transaction of its own, so it also runs inside a migration's; callers
+/// outside one should wrap it in a transaction.
+pub fn rebuild(conn: &Connection) -> SqliteResult<VectorIndexStats> {
+    let mut counts = Vec::new();
+    for indexed in Indexed::ALL {
+        conn.execute(&format!("DELETE FROM {}", indexed.index()), [])?;
+        counts.push(conn.execute(
+            &format!(
+                "INSERT INTO {} (id, embedding) SELECT id, embedding FROM {} WHERE length(embedding) = ?1",
+                indexed.index(),
+                indexed.table()
+            ),
+            params![(EMBEDDING_DIMENSIONS * 4) as i64],
+        )?);
+    }
+    Ok(VectorIndexStats { chat_entries: counts[0], knowledge_entries: counts[1] })
+}
+
+/// How many neighbours to ask for when `limit` results are wanted after
+/// filtering them
+pub fn oversample(limit: usize) -> usize {
+    (limit * 4).clamp(50, MAX_K)
+}
+
+/// Ids of the `k` indexed rows nearest to `query`, with their cosine
+/// similarity, most similar first
+pub fn nearest(conn: &Connection, indexed: Indexed, query: &[f32], k: usize) -> SqliteResult<Vec<(String, f32)>> {
+    if query.len() != EMBEDDING_DIMENSIONS || k == 0 {
+        return Ok(Vec::new());
+    }
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT id, distance FROM {} WHERE embedding MATCH ?1 AND k = ?2 ORDER BY distance",
+        indexed.index()
+    ))?;
+    let rows = stmt
+        .query_map(params![embedding_to_blob(query), k.min(MAX_K) as i64], |row| {
+            Ok((row.get(0)?, 1.0 - row.get::<_, f64>(1)? as f32))
+        })?
+        .collect();
+    rows
+}
+
+/// Reindex every stored embedding, e.g. after the index was damaged
+#[tauri::command]
+pub async fn rebuild_vector_index(state: tauri::State<'_, Arc<AppState>>) -> Result<VectorIndexStats, String> {
+    let conn = state.db()?;
+    let stats = conn
+        .unchecked_transaction()
+        .and_then(|tx| {
+            let stats = rebuild(&tx)?;
+            tx.commit()?;
+            Ok(stats)
+        })
+        .map_err(|e| format!("Failed to rebuild the vector index: {}", e))?;
+    info!(
+        "Vector index rebuilt: {} chat entries, {} knowledge notes",
+        stats.chat_entries, stats.knowledge_entries
+    );
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nearest_follows_table() {
+        let conn = crate::database::init_memory_db().unwrap();
+        let unit = |axis: usize| {
+            let mut v = vec![0.0f32; EMBEDDING_DIMENSIONS];
+            v[axis] = 1.0;
+            v
+        };
+        for (id, axis) in [("a", 0), ("b", 1)] {
+            conn.execute(
+                "INSERT INTO knowledge_entries (id, content, created_at, embedding) VALUES (?1, ?1, 0, ?2)",
+                params![id, embedding_to_blob(&unit(axis))],
+            )
+            .unwrap();
+        }
+
+        let found = nearest(&conn, Indexed::Knowledge, &unit(1), 2).unwrap();
+        assert_eq!(found[0].0, "b");
+        assert!((found[0].1 - 1.0).abs() < 1e-5 && found[1].1.abs() < 1e-5);
+
+        conn.execute("UPDATE knowledge_entries SET embedding = NULL WHERE id = 'b'", []).unwrap();
+        conn.execute("DELETE FROM knowledge_entries WHERE id = 'a'", []).unwrap();
+        assert!(nearest(&conn, Indexed::Knowledge, &unit(1), 2).unwrap().is_empty());
+        assert_eq!(rebuild(&conn).unwrap().knowledge_entries, 0);
+    }
+}
@@ -0,0 +1,134 @@
+// Second-screen caption viewer
+//
+// A minimal full-screen page served by the embedded server at
+// `/viewer?token=<viewer token>` so a phone, tablet or classroom display on the same
+// network can follow the captions. `get_viewer_qr` returns the LAN URL and a
+// QR code for it; the server must have `allow_lan` enabled to be reachable.
+// The URL carries the read-only viewer token, never the server token, since
+// anyone who sees the QR code can use it.
+use axum::extract::Query;
+use axum::response::Html;
+use qrcode::render::svg;
+use qrcode::QrCode;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+#[derive(Debug, Serialize)]
+pub struct ViewerQr {
+    pub url: String,
+    pub svg: String,
+}
+
+#[derive(Deserialize)]
+pub struct ViewerQuery {
+    token: Option<String>,
+}
+
+/// Address other devices on the LAN can reach this machine at
+fn lan_ip() -> Option<std::net::IpAddr> {
+    // Connecting a UDP socket sends nothing; it just selects the outbound interface
+    let socket = std::net::UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("192.0.2.1:80").ok()?;
+    socket.local_addr().ok().map(|addr| addr.ip())
+}
+
+/// Caption page for other screens in the room
+pub async fn viewer_page(Query(query): Query<ViewerQuery>) -> Html<String> {
+    let token = serde_json::to_string(&query.token.unwrap_or_default()).unwrap_or_default();
+    Html(VIEWER_HTML.replace("{{token}}", &token))
+}
+
+const VIEWER_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Zigy live captions</title>
+<style>
+  html, body { margin: 0; height: 100%; background: #111; color: #f5f5f5; font-family: sans-serif; }
+  #captions { position: absolute; inset: 0 0 3.5em 0; overflow-y: auto; padding: 1em; line-height: 1.35; }
+  #captions p { margin: 0 0 0.5em 0; }
+  .partial { opacity: 0.7; }
+  #bar { position: fixed; left: 0; right: 0; bottom: 0; height: 3.5em; display: flex; align-items: center;
+         gap: 0.5em; padding: 0 1em; background: #222; font-size: 16px; }
+  #bar button { font-size: 1.2em; min-width: 2.5em; }
+  #status { margin-left: auto; opacity: 0.7; }
+</style>
+</head>
+<body>
+<div id="captions"></div>
+<div id="bar">
+  <button id="smaller">A-</button>
+  <button id="larger">A+</button>
+  <span id="status">Connecting...</span>
+</div>
+<script>
+  const token = {{token}};
+  const box = document.getElementById("captions");
+  const status = document.getElementById("status");
+  let fontSize = parseInt(localStorage.getItem("zigy-viewer-font") || "32", 10);
+  let partialEl = null;
+
+  function applyFont() {
+    box.style.fontSize = fontSize + "px";
+    localStorage.setItem("zigy-viewer-font", String(fontSize));
+  }
+  document.getElementById("smaller").onclick = () => { fontSize = Math.max(12, fontSize - 4); applyFont(); };
+  document.getElementById("larger").onclick = () => { fontSize = Math.min(120, fontSize + 4); applyFont(); };
+  applyFont();
+
+  function show(text, isFinal) {
+    const atBottom = box.scrollHeight - box.scrollTop - box.clientHeight < 40;
+    if (!partialEl) {
+      partialEl = document.createElement("p");
+      box.appendChild(partialEl);
+    }
+    partialEl.textContent = text;
+    partialEl.className = isFinal ? "" : "partial";
+    if (isFinal) partialEl = null;
+    while (box.children.length > 500) box.removeChild(box.firstChild);
+    if (atBottom) box.scrollTop = box.scrollHeight;
+  }
+
+  function connect() {
+    const proto = location.protocol === "https:" ? "wss" : "ws";
+    const ws = new WebSocket(`${proto}://${location.host}/ws?token=${encodeURIComponent(token)}`);
+    ws.onopen = () => { status.textContent = "Connected"; };
+    ws.onmessage = (msg) => {
+      const { event, data } = JSON.parse(msg.data);
+      if (event === "caption" && data.text) {
+        show(data.text, data.captionType === "final");
+      } else if (event === "hello") {
+        status.textContent = data.running ? "Live" : "Waiting for captions";
+      } else if (event === "session") {
+        status.textContent = data.state === "started" ? "Live" : "Paused";
+      }
+    };
+    ws.onclose = () => { status.textContent = "Reconnecting..."; setTimeout(connect, 2000); };
+  }
+  connect();
+</script>
+</body>
+</html>
+"#;
+
+/// LAN URL of the viewer page and an SVG QR code encoding it
+#[tauri::command]
+pub async fn get_viewer_qr(app_handle: AppHandle) -> Result<ViewerQr, String> {
+    let settings = crate::server::current_settings(&app_handle);
+    if crate::server::local_url(&app_handle).is_none() {
+        return Err("The caption server is not running".to_string());
+    }
+    if !settings.allow_lan {
+        return Err("Enable LAN access for the caption server to use the viewer on other devices".to_string());
+    }
+    let ip = lan_ip().ok_or("Could not determine this machine's LAN address")?;
+
+    let url = format!("http://{}:{}/viewer?token={}", ip, settings.port, settings.viewer_token);
+    let svg = QrCode::new(url.as_bytes())
+        .map_err(|e| format!("Failed to build QR code: {}", e))?
+        .render::<svg::Color>()
+        .min_dimensions(256, 256)
+        .build();
+    Ok(ViewerQr { url, svg })
+}
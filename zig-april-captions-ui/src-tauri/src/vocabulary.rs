@@ -0,0 +1,302 @@
+// Custom vocabulary
+//
+// Jargon, names and acronyms the recognizer tends to get wrong. April models
+// can't be biased towards extra words at runtime, so the vocabulary is
+// applied to what they recognize instead. Terms are written to
+// `vocabulary.txt` and passed to the recognizer with `--vocabulary` when it
+// starts, so partials already show them; final captions are corrected again
+// here, which also catches terms added mid-session. Runs of up to MAX_SPAN
+// words that spell a term once case, spacing and punctuation are ignored
+// ("git hub" → "GitHub"), that match one of its `sounds_like` variants
+// ("jig" → "Zig"), or (here only) that are one letter away from a long term
+// are replaced by the term as written.
+//
+// The global list lives in `Settings.custom_vocabulary`; sessions can add
+// or override terms, stored in the `session_vocabulary` table.
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+use tracing::warn;
+
+use crate::{get_config_dir, persist, AppState};
+
+/// Longest run of words matched against one term
+const MAX_SPAN: usize = 3;
+
+/// Terms at least this long also match with one letter wrong
+const FUZZY_MIN_CHARS: usize = 6;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VocabularyTerm {
+    pub term: String,
+    #[serde(default)]
+    pub sounds_like: Vec<String>, // Misrecognitions to replace with `term`
+}
+
+/// Vocabulary of the current session, loaded on first use
+#[derive(Default)]
+pub struct VocabularyState {
+    session: Mutex<Option<(String, Vec<VocabularyTerm>)>>,
+}
+
+/// Lowercase letters and digits only
+fn key(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Whether `a` and `b` differ by at most one inserted, removed or changed char
+fn within_one_edit(a: &str, b: &str) -> bool {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let (short, long) = if a.len() <= b.len() { (&a, &b) } else { (&b, &a) };
+    if long.len() - short.len() > 1 {
+        return false;
+    }
+    let prefix = short.iter().zip(long.iter()).take_while(|(x, y)| x == y).count();
+    if short.len() == long.len() {
+        prefix == short.len() || short[prefix + 1..] == long[prefix + 1..]
+    } else {
+        short[prefix..] == long[prefix + 1..]
+    }
+}
+
+struct Token<'a> {
+    leading: &'a str, // Punctuation kept around a replacement
+    core: &'a str,
+    trailing: &'a str,
+}
+
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    text.split_whitespace()
+        .map(|word| {
+            let start = word.find(|c: char| c.is_alphanumeric()).unwrap_or(word.len());
+            let end = word
+                .rfind(|c: char| c.is_alphanumeric())
+                .map(|i| i + word[i..].chars().next().map_or(1, char::len_utf8))
+                .unwrap_or(start)
+                .max(start);
+            Token {
+                leading: &word[..start],
+                core: &word[start..end],
+                trailing: &word[end..],
+            }
+        })
+        .collect()
+}
+
+/// Replace misrecognized terms in `text`
+pub fn apply(text: &str, terms: &[VocabularyTerm]) -> String {
+    if terms.is_empty() {
+        return text.to_string();
+    }
+    // (key to match, term, fuzzy)
+    let mut keys: Vec<(String, &str, bool)> = Vec::new();
+    for term in terms {
+        let term_key = key(&term.term);
+        if term_key.is_empty() {
+            continue;
+        }
+        let fuzzy = term_key.chars().count() >= FUZZY_MIN_CHARS;
+        keys.push((term_key, &term.term, fuzzy));
+        for variant in &term.sounds_like {
+            let variant_key = key(variant);
+            if !variant_key.is_empty() {
+                keys.push((variant_key, &term.term, false));
+            }
+        }
+    }
+
+    let tokens = tokenize(text);
+    let mut words: Vec<String> = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+    while i < tokens.len() {
+        let mut replaced = false;
+        for span in (1..=MAX_SPAN.min(tokens.len() - i)).rev() {
+            let window = &tokens[i..i + span];
+            if window.iter().any(|t| t.core.is_empty()) {
+                continue;
+            }
+            let window_key: String = window.iter().map(|t| key(t.core)).collect();
+            let hit = keys
+                .iter()
+                .find(|(k, _, _)| *k == window_key)
+                .or_else(|| {
+                    keys.iter()
+                        .find(|(k, _, fuzzy)| *fuzzy && window_key.len() >= FUZZY_MIN_CHARS && within_one_edit(k, &window_key))
+                });
+            if let Some((_, term, _)) = hit {
+                words.push(format!("{}{}{}", window[0].leading, term, window[span - 1].trailing));
+                i += span;
+                replaced = true;
+                break;
+            }
+        }
+        if !replaced {
+            let t = &tokens[i];
+            words.push(format!("{}{}{}", t.leading, t.core, t.trailing));
+            i += 1;
+        }
+    }
+    words.join(" ")
+}
+
+fn load_session_terms(conn: &Connection, session_id: &str) -> rusqlite::Result<Vec<VocabularyTerm>> {
+    let mut stmt = conn.prepare("SELECT term, sounds_like FROM session_vocabulary WHERE session_id = ?1 ORDER BY term")?;
+    let terms = stmt
+        .query_map(params![session_id], |row| {
+            let sounds_like: Option<String> = row.get(1)?;
+            Ok(VocabularyTerm {
+                term: row.get(0)?,
+                sounds_like: sounds_like.and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default(),
+            })
+        })?
+        .collect();
+    terms
+}
+
+/// Global terms with the session's added on top; a session term replaces a
+/// global one with the same spelling
+fn effective_terms(app_handle: &AppHandle) -> Vec<VocabularyTerm> {
+    let state = app_handle.state::<Arc<AppState>>();
+    let mut terms = state
+        .settings
+        .lock()
+        .map(|s| s.custom_vocabulary.clone())
+        .unwrap_or_default();
+    let Some(session_id) = crate::current_session_id(&state) else {
+        return terms;
+    };
+
+    let vocabulary = app_handle.state::<VocabularyState>();
+    let Ok(mut cached) = vocabulary.session.lock() else {
+        return terms;
+    };
+    if cached.as_ref().map(|(id, _)| id != &session_id).unwrap_or(true) {
+        let loaded = state
+            .db()
+            .and_then(|conn| load_session_terms(&conn, &session_id).map_err(|e| e.to_string()))
+            .unwrap_or_default();
+        *cached = Some((session_id, loaded));
+    }
+    if let Some((_, session_terms)) = cached.as_ref() {
+        terms.retain(|t| !session_terms.iter().any(|s| key(&s.term) == key(&t.term)));
+        terms.extend(session_terms.iter().cloned());
+    }
+    terms
+}
+
+/// Recognizer arguments passing the current vocabulary, empty when there is none
+pub fn engine_args(app_handle: &AppHandle) -> Vec<String> {
+    let path = get_config_dir().join("vocabulary.txt");
+    let terms = effective_terms(app_handle);
+    if terms.is_empty() {
+        let _ = std::fs::remove_file(&path);
+        return Vec::new();
+    }
+    // One term per line with its sound-alikes after tabs
+    let field = |s: &str| s.replace(['\t', '\n', '\r'], " ");
+    let mut content = String::new();
+    for term in &terms {
+        content.push_str(&field(&term.term));
+        for variant in &term.sounds_like {
+            content.push('\t');
+            content.push_str(&field(variant));
+        }
+        content.push('\n');
+    }
+    if let Err(e) = persist::write_atomic(&path, content.as_bytes()) {
+        warn!("Failed to write {}: {}", path.display(), e);
+        return Vec::new();
+    }
+    vec!["--vocabulary".to_string(), path.to_string_lossy().to_string()]
+}
+
+/// Apply the current vocabulary to a final caption
+pub fn correct(app_handle: &AppHandle, text: &str) -> String {
+    apply(text, &effective_terms(app_handle))
+}
+
+#[tauri::command]
+pub async fn get_session_vocabulary(
+    state: tauri::State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<Vec<VocabularyTerm>, String> {
+    let conn = state.db()?;
+    load_session_terms(&conn, &session_id).map_err(|e| format!("Failed to load vocabulary: {}", e))
+}
+
+/// Replace a session's own terms
+pub fn replace_session_terms(
+    state: &AppState,
+    vocabulary: &VocabularyState,
+    session_id: String,
+    terms: Vec<VocabularyTerm>,
+) -> Result<(), String> {
+    let terms: Vec<VocabularyTerm> = terms
+        .into_iter()
+        .filter(|t| !key(&t.term).is_empty())
+        .map(|t| VocabularyTerm {
+            term: t.term.trim().to_string(),
+            sounds_like: t.sounds_like,
+        })
+        .collect();
+    {
+        let conn = state.db()?;
+        let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+        tx.execute("DELETE FROM session_vocabulary WHERE session_id = ?1", params![session_id])
+            .map_err(|e| e.to_string())?;
+        for term in &terms {
+            let sounds_like = serde_json::to_string(&term.sounds_like).map_err(|e| e.to_string())?;
+            tx.execute(
+                "INSERT OR REPLACE INTO session_vocabulary (session_id, term, sounds_like) VALUES (?1, ?2, ?3)",
+                params![session_id, term.term, sounds_like],
+            )
+            .map_err(|e| format!("Failed to save vocabulary: {}", e))?;
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+    }
+    *vocabulary.session.lock().map_err(|e| e.to_string())? = Some((session_id, terms));
+    Ok(())
+}
+
+/// Replace a session's own terms
+#[tauri::command]
+pub async fn set_session_vocabulary(
+    state: tauri::State<'_, Arc<AppState>>,
+    vocabulary: tauri::State<'_, VocabularyState>,
+    session_id: String,
+    terms: Vec<VocabularyTerm>,
+) -> Result<(), String> {
+    replace_session_terms(&state, &vocabulary, session_id, terms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_vocabulary() {
+        let term = |term: &str, sounds_like: &[&str]| VocabularyTerm {
+            term: term.to_string(),
+            sounds_like: sounds_like.iter().map(|s| s.to_string()).collect(),
+        };
+        let terms = vec![
+            term("GitHub", &[]),
+            term("Zig", &["jig"]),
+            term("Kubernetes", &[]),
+            term("CI/CD", &[]),
+        ];
+        assert_eq!(
+            apply("we push to git hub, then jig builds it.", &terms),
+            "we push to GitHub, then Zig builds it."
+        );
+        assert_eq!(apply("deploy on kubernetis with ci cd", &terms), "deploy on Kubernetes with CI/CD");
+        assert_eq!(apply("a jigsaw puzzle", &terms), "a jigsaw puzzle");
+        assert!(within_one_edit("abc", "abd"));
+        assert!(within_one_edit("abc", "ab"));
+        assert!(!within_one_edit("abc", "bca"));
+    }
+}
@@ -0,0 +1,296 @@
+// Outgoing webhooks
+//
+// POSTs JSON to user-configured URLs when selected events happen, so Zapier,
+// n8n or custom pipelines can react to meetings. Payload:
+//
+//   { "event": "<name>", "delivery_id": "<uuid>", "timestamp": <unix ms>, "data": { ... } }
+//
+// Events: `session.start`, `caption.final`, `keyword.hit`, `session.end`,
+// `summary.ready` and `ping` (test deliveries only). When a secret is set, requests carry
+// `X-Zigy-Signature: sha256=<hex>`, the HMAC-SHA256 of
+// "<X-Zigy-Timestamp>.<body>" keyed with the secret.
+//
+// The outcome of every delivery is kept in `webhook_deliveries`, trimmed to
+// the most recent MAX_DELIVERY_LOG rows.
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use rusqlite::{params, Connection};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tracing::{debug, warn};
+
+use crate::AppState;
+
+pub const EVENT_SESSION_START: &str = "session.start";
+pub const EVENT_CAPTION_FINAL: &str = "caption.final";
+pub const EVENT_KEYWORD_HIT: &str = "keyword.hit";
+pub const EVENT_SESSION_END: &str = "session.end";
+pub const EVENT_SUMMARY_READY: &str = "summary.ready";
+
+/// Attempts per delivery (first try included)
+const MAX_ATTEMPTS: u32 = 4;
+
+/// Delay before the first retry; doubled after each failure
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Delivery results kept for the settings screen
+const MAX_DELIVERY_LOG: i64 = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub id: String,
+    pub url: String,
+    #[serde(default = "crate::default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub events: Vec<String>, // Empty means every event
+    #[serde(default)]
+    pub keywords: Vec<String>, // Matched case-insensitively for keyword.hit
+    #[serde(default)]
+    pub secret: String, // HMAC signing key; unsigned when empty
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeliveryRecord {
+    pub delivery_id: String,
+    pub webhook_id: String,
+    pub event: String,
+    pub attempts: u32,
+    pub status: Option<u16>,
+    pub error: Option<String>,
+    pub delivered_at: i64,
+}
+
+
+fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
+fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn webhooks(app_handle: &AppHandle) -> Vec<WebhookConfig> {
+    app_handle
+        .state::<Arc<AppState>>()
+        .settings
+        .lock()
+        .map(|s| s.webhooks.clone())
+        .unwrap_or_default()
+}
+
+fn wants(webhook: &WebhookConfig, event: &str) -> bool {
+    webhook.enabled && (webhook.events.is_empty() || webhook.events.iter().any(|e| e == event))
+}
+
+/// POST one payload, retrying network errors, 429 and 5xx with backoff
+async fn deliver(app_handle: AppHandle, webhook: WebhookConfig, event: String, data: serde_json::Value) -> DeliveryRecord {
+    let delivery_id = uuid::Uuid::new_v4().to_string();
+    let timestamp = now_millis();
+    let body = serde_json::json!({
+        "event": event,
+        "delivery_id": delivery_id,
+        "timestamp": timestamp,
+        "data": data,
+    })
+    .to_string();
+
+    let client = reqwest::Client::new();
+    let mut delay = INITIAL_RETRY_DELAY;
+    let mut record = DeliveryRecord {
+        delivery_id: delivery_id.clone(),
+        webhook_id: webhook.id.clone(),
+        event: event.clone(),
+        attempts: 0,
+        status: None,
+        error: None,
+        delivered_at: timestamp,
+    };
+
+    while record.attempts < MAX_ATTEMPTS {
+        if record.attempts > 0 {
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+        record.attempts += 1;
+
+        let mut request = client
+            .post(&webhook.url)
+            .timeout(REQUEST_TIMEOUT)
+            .header("Content-Type", "application/json")
+            .header("User-Agent", "zigy-webhooks")
+            .header("X-Zigy-Event", &event)
+            .header("X-Zigy-Delivery", &delivery_id)
+            .header("X-Zigy-Timestamp", timestamp.to_string());
+        if !webhook.secret.is_empty() {
+            request = request.header(
+                "X-Zigy-Signature",
+                format!("sha256={}", sign(&webhook.secret, timestamp, &body)),
+            );
+        }
+
+        match request.body(body.clone()).send().await {
+            Ok(response) => {
+                let status = response.status();
+                record.status = Some(status.as_u16());
+                record.error = None;
+                if status.is_success() {
+                    break;
+                }
+                record.error = Some(format!("HTTP {}", status));
+                if !(status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS) {
+                    break;
+                }
+            }
+            Err(e) => {
+                record.status = None;
+                record.error = Some(e.to_string());
+            }
+        }
+        debug!("Webhook {} attempt {} failed: {:?}", webhook.id, record.attempts, record.error);
+    }
+
+    record.delivered_at = now_millis();
+    if let Some(error) = &record.error {
+        warn!("Webhook {} gave up on {} after {} attempt(s): {}", webhook.id, event, record.attempts, error);
+    }
+
+    let logged = app_handle
+        .state::<Arc<AppState>>()
+        .db()
+        .and_then(|conn| log_delivery(&conn, &record).map_err(|e| e.to_string()));
+    if let Err(e) = logged {
+        warn!("Failed to log webhook delivery: {}", e);
+    }
+    record
+}
+
+fn log_delivery(conn: &Connection, record: &DeliveryRecord) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO webhook_deliveries (delivery_id, webhook_id, event, attempts, status, error, delivered_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            record.delivery_id,
+            record.webhook_id,
+            record.event,
+            record.attempts,
+            record.status,
+            record.error,
+            record.delivered_at
+        ],
+    )?;
+    conn.execute(
+        "DELETE FROM webhook_deliveries WHERE delivery_id NOT IN
+         (SELECT delivery_id FROM webhook_deliveries ORDER BY delivered_at DESC LIMIT ?1)",
+        params![MAX_DELIVERY_LOG],
+    )?;
+    Ok(())
+}
+
+fn recent_deliveries(conn: &Connection, webhook_id: Option<&str>, limit: i64) -> rusqlite::Result<Vec<DeliveryRecord>> {
+    let mut stmt = conn.prepare(
+        "SELECT delivery_id, webhook_id, event, attempts, status, error, delivered_at FROM webhook_deliveries
+         WHERE ?1 IS NULL OR webhook_id = ?1 ORDER BY delivered_at DESC LIMIT ?2",
+    )?;
+    let rows = stmt
+        .query_map(params![webhook_id, limit], |row| {
+            Ok(DeliveryRecord {
+                delivery_id: row.get(0)?,
+                webhook_id: row.get(1)?,
+                event: row.get(2)?,
+                attempts: row.get(3)?,
+                status: row.get(4)?,
+                error: row.get(5)?,
+                delivered_at: row.get(6)?,
+            })
+        })?
+        .collect();
+    rows
+}
+
+/// Send an event to every enabled webhook subscribed to it
+pub fn dispatch(app_handle: &AppHandle, event: &str, data: serde_json::Value) {
+    for webhook in webhooks(app_handle).into_iter().filter(|w| wants(w, event)) {
+        let app_handle = app_handle.clone();
+        let event = event.to_string();
+        let data = data.clone();
+        tauri::async_runtime::spawn(async move {
+            deliver(app_handle, webhook, event, data).await;
+        });
+    }
+}
+
+/// Fire `caption.final` and, per webhook, `keyword.hit` for a final caption
+pub fn on_final_caption(app_handle: &AppHandle, text: &str) {
+    let configured = webhooks(app_handle);
+    if configured.is_empty() {
+        return;
+    }
+    dispatch(app_handle, EVENT_CAPTION_FINAL, serde_json::json!({ "text": text }));
+
+    let lower = text.to_lowercase();
+    for webhook in configured.into_iter().filter(|w| wants(w, EVENT_KEYWORD_HIT)) {
+        let hits: Vec<&String> = webhook
+            .keywords
+            .iter()
+            .filter(|k| !k.trim().is_empty() && lower.contains(&k.trim().to_lowercase()))
+            .collect();
+        if hits.is_empty() {
+            continue;
+        }
+        let data = serde_json::json!({ "text": text, "keywords": hits });
+        let app_handle = app_handle.clone();
+        tauri::async_runtime::spawn(async move {
+            deliver(app_handle, webhook, EVENT_KEYWORD_HIT.to_string(), data).await;
+        });
+    }
+}
+
+/// Send a `ping` to one webhook regardless of its event filter
+#[tauri::command]
+pub async fn test_webhook(app_handle: AppHandle, id: String) -> Result<DeliveryRecord, String> {
+    let webhook = webhooks(&app_handle)
+        .into_iter()
+        .find(|w| w.id == id)
+        .ok_or_else(|| format!("Webhook {} not found", id))?;
+    Ok(deliver(app_handle, webhook, "ping".to_string(), serde_json::json!({})).await)
+}
+
+/// Recent delivery outcomes, newest first, optionally for one webhook
+#[tauri::command]
+pub async fn get_webhook_deliveries(
+    state: tauri::State<'_, Arc<AppState>>,
+    webhook_id: Option<String>,
+    limit: Option<i64>,
+) -> Result<Vec<DeliveryRecord>, String> {
+    let conn = state.db()?;
+    let limit = limit.unwrap_or(50).clamp(1, MAX_DELIVERY_LOG);
+    recent_deliveries(&conn, webhook_id.as_deref(), limit).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign() {
+        // HMAC-SHA256("key", "1.body")
+        assert_eq!(
+            sign("key", 1, "body"),
+            "91b5374b153842ad05b2c4eab9349b8321b14703165bd3fb8b034dfb8be98ae5"
+        );
+    }
+}
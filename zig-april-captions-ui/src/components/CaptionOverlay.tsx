@@ -0,0 +1,79 @@
+import { useEffect, useState, type MouseEvent } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { getCurrentWindow } from '@tauri-apps/api/window';
+import type { CaptionEvent, Settings, SettingsChanged } from '../types';
+
+// Lines of final captions kept above the partial one
+const MAX_LINES = 2;
+
+// Contents of the overlay window (loaded at index.html#overlay). It follows the
+// same caption-event stream as the main window and shows only the latest lines.
+export function CaptionOverlay() {
+  const [lines, setLines] = useState<string[]>([]);
+  const [partial, setPartial] = useState('');
+  const [fontSize, setFontSize] = useState(24);
+
+  useEffect(() => {
+    invoke<Settings>('get_settings')
+      .then((settings) => setFontSize(settings.font_size))
+      .catch((e) => console.error('Failed to load settings:', e));
+
+    const unlistenCaptions = listen<CaptionEvent>('caption-event', (event) => {
+      const data = event.payload;
+      if (data.type === 'stopped') {
+        setPartial('');
+        return;
+      }
+      if (data.type !== 'caption' || !data.text) return;
+      if (data.captionType === 'final') {
+        const text = data.text;
+        setLines((prev) => [...prev, text].slice(-MAX_LINES));
+        setPartial('');
+      } else {
+        setPartial(data.text);
+      }
+    });
+
+    const unlistenSettings = listen<SettingsChanged>('settings-changed', (event) => {
+      const change = event.payload.changes.find((c) => c.key_path === 'font_size');
+      if (typeof change?.new === 'number') setFontSize(change.new);
+    });
+
+    return () => {
+      unlistenCaptions.then((fn) => fn());
+      unlistenSettings.then((fn) => fn());
+    };
+  }, []);
+
+  // Only reachable with click-through off
+  const handleMouseDown = async (e: MouseEvent) => {
+    if (e.button !== 0) return;
+    try {
+      await getCurrentWindow().startDragging();
+    } catch (err) {
+      console.error('Failed to drag overlay:', err);
+    }
+  };
+
+  const hasText = lines.length > 0 || partial;
+
+  return (
+    <div
+      className="h-screen w-screen flex flex-col justify-end p-2 select-none overflow-hidden"
+      onMouseDown={handleMouseDown}
+    >
+      {hasText && (
+        <div
+          className="rounded-lg bg-black/60 px-4 py-2 text-white leading-snug"
+          style={{ fontSize: `${fontSize}px` }}
+        >
+          {lines.map((line, i) => (
+            <div key={i}>{line}</div>
+          ))}
+          {partial && <div className="text-white/80">{partial}</div>}
+        </div>
+      )}
+    </div>
+  );
+}
@@ -11,6 +11,7 @@ import {
   INFO_SYSTEM_PROMPT,
   ChatIntent
 } from '../services/geminiService';
+import { geminiFetch } from '../services/geminiClient';
 
 // Translate text using Gemini
 async function translateText(
@@ -19,13 +20,13 @@ async function translateText(
   apiKey: string,
   model: string
 ): Promise<string> {
-  const url = `https://generativelanguage.googleapis.com/v1beta/models/${model}:generateContent?key=${apiKey}`;
+  const path = `v1beta/models/${model}`;
 
   // Pro models need more tokens due to thinking overhead
   const isPro = model.includes('pro');
   const maxTokens = isPro ? 2048 : 512;
 
-  const response = await fetch(url, {
+  const response = await geminiFetch(path, {
     method: 'POST',
     headers: { 'Content-Type': 'application/json' },
     body: JSON.stringify({
@@ -88,9 +89,9 @@ async function summarizeMessages(
     .map(m => `${m.role === 'user' ? 'User' : 'Assistant'}: ${m.content}`)
     .join('\n');
 
-  const url = `https://generativelanguage.googleapis.com/v1beta/models/${model}:generateContent?key=${apiKey}`;
+  const path = `v1beta/models/${model}`;
 
-  const response = await fetch(url, {
+  const response = await geminiFetch(path, {
     method: 'POST',
     headers: { 'Content-Type': 'application/json' },
     body: JSON.stringify({
@@ -118,7 +119,7 @@ async function generateDynamicSuggestions(
 ): Promise<PromptSuggestion[]> {
   if (!transcript || !apiKey) return [];
 
-  const url = `https://generativelanguage.googleapis.com/v1beta/models/${model}:generateContent?key=${apiKey}`;
+  const path = `v1beta/models/${model}`;
 
   // For non-English, we need both display label (in app language) and English prompt
   const needsTranslation = appLanguage !== 'en';
@@ -173,7 +174,7 @@ ${contextText}
 
 Return ONLY a JSON array of strings. Example: ["Challenge the proposed approach", "Ask about budget constraints", "Suggest a phased rollout"]`;
 
-  const response = await fetch(url, {
+  const response = await geminiFetch(path, {
     method: 'POST',
     headers: { 'Content-Type': 'application/json' },
     body: JSON.stringify({
@@ -302,7 +303,7 @@ Example output: "I believe garbage collection is really important for maintainin
 
   contents.push({ role: 'user', parts: [{ text: message }] });
 
-  const url = `https://generativelanguage.googleapis.com/v1beta/models/${model}:generateContent?key=${apiKey}`;
+  const path = `v1beta/models/${model}`;
 
   // Adjust generation config based on chat mode
   // Note: Gemini 2.5 Pro uses "thinking tokens" internally, so needs higher maxOutputTokens
@@ -314,7 +315,7 @@ Example output: "I believe garbage collection is really important for maintainin
     ? { maxOutputTokens: baseTokens, temperature: 0.3, topP: 0.8 }
     : { maxOutputTokens: scriptTokens, temperature: 0.7 };
 
-  const response = await fetch(url, {
+  const response = await geminiFetch(path, {
     method: 'POST',
     headers: { 'Content-Type': 'application/json' },
     body: JSON.stringify({
@@ -9,6 +9,7 @@ import {
 import type { GeminiModel, TranslationLanguage } from '../types';
 import { TRANSLATION_LANGUAGES } from '../types';
 import { Translations } from '../translations';
+import { geminiFetch } from '../services/geminiClient';
 
 interface Props {
   text: string;
@@ -31,9 +32,9 @@ async function translateLine(
   apiKey: string,
   model: string
 ): Promise<string> {
-  const url = `https://generativelanguage.googleapis.com/v1beta/models/${model}:generateContent?key=${apiKey}`;
+  const path = `v1beta/models/${model}`;
 
-  const response = await fetch(url, {
+  const response = await geminiFetch(path, {
     method: 'POST',
     headers: { 'Content-Type': 'application/json' },
     body: JSON.stringify({
@@ -145,12 +145,19 @@ export function SettingsPanel({
               {t.microphone}
             </button>
             <button
-              className={`px-4 py-2 text-sm rounded-r-md border transition-colors ${settings.audio_source === 'monitor' ? 'bg-indigo-600 text-white border-indigo-600' : 'bg-transparent border-slate-300 dark:border-[#30363D] text-slate-700 dark:text-[#E6EDF3] hover:bg-slate-100 dark:hover:bg-[#21262D]'}`}
+              className={`px-4 py-2 text-sm border border-r-0 transition-colors ${settings.audio_source === 'monitor' ? 'bg-indigo-600 text-white border-indigo-600' : 'bg-transparent border-slate-300 dark:border-[#30363D] text-slate-700 dark:text-[#E6EDF3] hover:bg-slate-100 dark:hover:bg-[#21262D]'}`}
               onClick={() => onSettingsChange({ ...settings, audio_source: 'monitor' })}
               disabled={disabled}
             >
               {t.systemAudio}
             </button>
+            <button
+              className={`px-4 py-2 text-sm rounded-r-md border transition-colors ${settings.audio_source === 'both' ? 'bg-indigo-600 text-white border-indigo-600' : 'bg-transparent border-slate-300 dark:border-[#30363D] text-slate-700 dark:text-[#E6EDF3] hover:bg-slate-100 dark:hover:bg-[#21262D]'}`}
+              onClick={() => onSettingsChange({ ...settings, audio_source: 'both' })}
+              disabled={disabled}
+            >
+              {t.bothSides}
+            </button>
           </div>
         </SettingRow>
       </Section>
@@ -1,18 +1,36 @@
 import { useState, useEffect, useCallback, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
-import { CaptionEvent, Settings, SummaryState, QuestionsState, TimelineItem, IdeaEntry, ChatHistoryStats, ChatHistoryEntry } from '../types';
+import { CaptionEvent, Settings, SettingChange, SettingsChanged, SummaryState, QuestionsState, TimelineItem, IdeaEntry, ChatHistoryStats, ChatHistoryEntry } from '../types';
 import { generateSummary, generateQuestions, generateSummaryWithContext, generateQuestionsWithContext } from '../services/geminiService';
-import { addChatEntry, getChatHistoryStats, createSessionSnapshot } from '../services/contextService';
+import { addChatEntry, queueTranscriptEntry, flushTranscriptEntries, getChatHistoryStats, createSessionSnapshot } from '../services/contextService';
 
 // Global state outside React
 let globalHistory: string[] = [];  // All finalized sentences
-let lastText = '';                 // Last text shown (to detect replacement)
+// Last partial text shown (to detect replacement), with the side and channel it came from
+let pending: { text: string; source?: string; channel?: string } = { text: '' };
 let listenerRegistered = false;
 let silenceTimer: ReturnType<typeof setTimeout> | null = null;  // Timer for auto-move to history
 let autoSummaryInProgress = false;  // Prevent multiple auto-summary triggers
 let lastLineAddedTime = 0;         // Timestamp of last line added (for smart merging)
 
+// Copy of `settings` with the values from a settings-changed event
+function applySettingChanges(settings: Settings, changes: SettingChange[]): Settings {
+  const next = structuredClone(settings) as unknown as Record<string, unknown>;
+  for (const change of changes) {
+    const keys = change.key_path.split('.');
+    let target = next;
+    for (const key of keys.slice(0, -1)) {
+      if (typeof target[key] !== 'object' || target[key] === null) {
+        target[key] = {};
+      }
+      target = target[key] as Record<string, unknown>;
+    }
+    target[keys[keys.length - 1]] = change.new;
+  }
+  return next as unknown as Settings;
+}
+
 // Auto-summary threshold (in words)
 const AUTO_SUMMARY_WORD_THRESHOLD = 1000;
 
@@ -143,11 +161,7 @@ export function useCaptions() {
           const currentSettings = await invoke<Settings>('get_settings');
           if (!currentSettings.model_path) {
             console.log('No model set, using bundled model');
-            await invoke('save_settings', {
-              settings: { ...currentSettings, model_path: bundledPath }
-            });
-            // Reload settings to pick up the bundled model
-            loadSettings();
+            await invoke('update_setting', { keyPath: 'model_path', value: bundledPath });
           }
         }
       } catch (e) {
@@ -228,6 +242,7 @@ export function useCaptions() {
           case 'caption':
             if (data.text !== undefined && data.captionType) {
               const newText = data.text.trim();
+              const origin = { source: data.source, channel: data.channel };
 
               // Clear any existing silence timer
               if (silenceTimer) {
@@ -238,9 +253,9 @@ export function useCaptions() {
               if (data.captionType === 'partial') {
                 // Check if this is a replacement (new text doesn't start with old text)
                 // This means ASR started a new sentence
-                if (lastText && newText && !newText.startsWith(lastText.substring(0, Math.min(10, lastText.length)))) {
+                if (pending.text && newText && !newText.startsWith(pending.text.substring(0, Math.min(10, pending.text.length)))) {
                   // Old text is being replaced - save it to history with smart merge
-                  const oldText = lastText.trim();
+                  const oldText = pending.text.trim();
                   if (oldText) {
                     const result = addLineWithSmartMerge(oldText);
                     setHistoryRef.current([...result.history]);
@@ -251,21 +266,19 @@ export function useCaptions() {
                       console.error('Failed to persist:', e);
                     });
                     // Also save to chat history for context management
-                    addChatEntry('transcript', result.persistText).catch(e => {
-                      console.error('Failed to save to chat history:', e);
-                    });
+                    queueTranscriptEntry(result.persistText, pending);
                   }
                 }
 
                 // Update current text
-                lastText = newText;
+                pending = { text: newText, ...origin };
                 setCurrentTextRef.current(data.text);
 
                 // Start silence timer - move to history after 2 seconds of no new text
                 if (newText) {
                   silenceTimer = setTimeout(() => {
-                    if (lastText && lastText.trim()) {
-                      const textToMove = lastText.trim();
+                    if (pending.text && pending.text.trim()) {
+                      const textToMove = pending.text.trim();
                       const result = addLineWithSmartMerge(textToMove);
                       setHistoryRef.current([...result.history]);
 
@@ -274,11 +287,9 @@ export function useCaptions() {
                       invoke(command, { line: result.persistText }).catch(e => {
                         console.error('Failed to persist:', e);
                       });
-                      addChatEntry('transcript', result.persistText).catch(e => {
-                        console.error('Failed to save to chat history:', e);
-                      });
+                      queueTranscriptEntry(result.persistText, pending);
 
-                      lastText = '';
+                      pending = { text: '' };
                       setCurrentTextRef.current('');
                     }
                     silenceTimer = null;
@@ -292,15 +303,13 @@ export function useCaptions() {
 
                   // Persist to backend - use update if merged, add if new
                   const command = result.wasMerged ? 'update_last_transcript_line' : 'add_transcript_line';
-                  invoke(command, { line: result.persistText }).catch(e => {
+                  invoke(command, { line: result.persistText, captionId: data.id }).catch(e => {
                     console.error('Failed to persist:', e);
                   });
                   // Also save to chat history for context management
-                  addChatEntry('transcript', result.persistText).catch(e => {
-                    console.error('Failed to save to chat history:', e);
-                  });
+                  queueTranscriptEntry(result.persistText, origin);
                 }
-                lastText = '';
+                pending = { text: '' };
                 setCurrentTextRef.current('');
               }
             }
@@ -320,13 +329,13 @@ export function useCaptions() {
             setStatusRef.current('stopped');
             setIsRunningRef.current(false);
             // Save any remaining current text to history
-            if (lastText && lastText.trim()) {
-              const finalText = lastText.trim();
+            if (pending.text && pending.text.trim()) {
+              const finalText = pending.text.trim();
               globalHistory = [...globalHistory, finalText];
               setHistoryRef.current([...globalHistory]);
               invoke('add_transcript_line', { line: finalText }).catch(() => {});
             }
-            lastText = '';
+            pending = { text: '' };
             setCurrentTextRef.current('');
             break;
         }
@@ -337,6 +346,16 @@ export function useCaptions() {
     return () => {};
   }, []);
 
+  // Keep settings in sync with changes saved from any window
+  useEffect(() => {
+    const unlisten = listen<SettingsChanged>('settings-changed', (event) => {
+      setSettings(prev => applySettingChanges(prev, event.payload.changes));
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
   const loadSettings = async () => {
     try {
       const savedSettings = await invoke<Settings>('get_settings');
@@ -378,6 +397,10 @@ export function useCaptions() {
 
   const stopCaptions = useCallback(async () => {
     try {
+      // Queued lines belong to the session that's ending
+      await flushTranscriptEntries().catch(e => {
+        console.error('Failed to save to chat history:', e);
+      });
       await invoke('stop_captions');
       setIsRunning(false);
     } catch (e) {
@@ -401,7 +424,7 @@ export function useCaptions() {
 
       // Clear UI immediately
       globalHistory = [];
-      lastText = '';
+      pending = { text: '' };
       setHistory([]);
       setCurrentText('');
 
@@ -442,8 +465,8 @@ export function useCaptions() {
     }
 
     // Move current live text to history immediately with smart merge
-    if (lastText && lastText.trim()) {
-      const textToMove = lastText.trim();
+    if (pending.text && pending.text.trim()) {
+      const textToMove = pending.text.trim();
       const result = addLineWithSmartMerge(textToMove);
       setHistory([...result.history]);
 
@@ -452,13 +475,15 @@ export function useCaptions() {
       invoke(command, { line: result.persistText }).catch(e => {
         console.error('Failed to persist:', e);
       });
-      addChatEntry('transcript', result.persistText).catch(e => {
-        console.error('Failed to save to chat history:', e);
-      });
+      queueTranscriptEntry(result.persistText, pending);
 
-      lastText = '';
+      pending = { text: '' };
       setCurrentText('');
     }
+    // Callers read the transcript back right after this
+    flushTranscriptEntries().catch(e => {
+      console.error('Failed to save to chat history:', e);
+    });
   }, []);
 
   const exportCaptions = useCallback(async (filePath: string) => {
@@ -829,7 +854,7 @@ export function useCaptions() {
 
           // Clear UI and backend
           globalHistory = [];
-          lastText = '';
+          pending = { text: '' };
           setHistory([]);
           setCurrentText('');
 
@@ -2,9 +2,17 @@ import './tailwind.css';
 import React from "react";
 import ReactDOM from "react-dom/client";
 import App from "./App";
+import { CaptionOverlay } from "./components/CaptionOverlay";
+
+// The overlay window loads the same bundle at #overlay
+const isOverlay = window.location.hash === "#overlay";
+if (isOverlay) {
+  document.documentElement.style.background = "transparent";
+  document.body.style.background = "transparent";
+}
 
 ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
   <React.StrictMode>
-    <App />
+    {isOverlay ? <CaptionOverlay /> : <App />}
   </React.StrictMode>,
 );
@@ -8,8 +8,9 @@ import {
   GeminiModel,
   GeminiResponse,
 } from '../types';
+import { geminiFetch } from './geminiClient';
 
-const GEMINI_API_BASE = 'https://generativelanguage.googleapis.com/v1beta/models';
+const GEMINI_API_BASE = 'v1beta/models';
 
 // Token estimation: ~4 characters per token for Gemini
 export function estimateTokens(text: string): number {
@@ -50,7 +51,7 @@ async function generateContextSummary(
     return '';
   }
 
-  const url = `${GEMINI_API_BASE}/${model}:generateContent?key=${apiKey}`;
+  const path = `${GEMINI_API_BASE}/${model}`;
 
   const requestBody = {
     contents: [
@@ -68,7 +69,7 @@ async function generateContextSummary(
   };
 
   try {
-    const response = await fetch(url, {
+    const response = await geminiFetch(path, {
       method: 'POST',
       headers: {
         'Content-Type': 'application/json',
@@ -235,23 +236,68 @@ export async function buildCompressedContext(
   };
 }
 
-// Add a chat entry to history
-export async function addChatEntry(
+function newChatEntry(
   entryType: ChatHistoryEntry['entry_type'],
   content: string,
-  metadata?: Record<string, unknown>
-): Promise<ChatHistoryEntry> {
-  const entry: ChatHistoryEntry = {
+  metadata?: Record<string, unknown>,
+  origin?: { source?: string; channel?: string }
+): ChatHistoryEntry {
+  return {
     id: `${entryType}-${Date.now()}-${Math.random().toString(36).substr(2, 9)}`,
     timestamp: Date.now(),
     entry_type: entryType,
     content,
     metadata,
+    source: origin?.source,
+    channel: origin?.channel,
   };
+}
 
+// Add a chat entry to history
+export async function addChatEntry(
+  entryType: ChatHistoryEntry['entry_type'],
+  content: string,
+  metadata?: Record<string, unknown>,
+  origin?: { source?: string; channel?: string }  // Side of the call for transcript entries
+): Promise<ChatHistoryEntry> {
+  const entry = newChatEntry(entryType, content, metadata, origin);
   return await invoke<ChatHistoryEntry>('add_chat_entry', { entry });
 }
 
+// Save several chat entries in one transaction
+export async function addChatEntries(entries: ChatHistoryEntry[]): Promise<ChatHistoryEntry[]> {
+  return await invoke<ChatHistoryEntry[]>('add_chat_entries_batch', { entries });
+}
+
+// Transcript lines waiting to be saved, written together every TRANSCRIPT_FLUSH_MS
+const TRANSCRIPT_FLUSH_MS = 500;
+let queuedTranscript: ChatHistoryEntry[] = [];
+let transcriptFlushTimer: ReturnType<typeof setTimeout> | null = null;
+
+// Queue a transcript line for the next batched write
+export function queueTranscriptEntry(content: string, origin?: { source?: string; channel?: string }): void {
+  queuedTranscript.push(newChatEntry('transcript', content, undefined, origin));
+  if (!transcriptFlushTimer) {
+    transcriptFlushTimer = setTimeout(() => {
+      flushTranscriptEntries().catch(e => {
+        console.error('Failed to save to chat history:', e);
+      });
+    }, TRANSCRIPT_FLUSH_MS);
+  }
+}
+
+// Write queued transcript lines now
+export async function flushTranscriptEntries(): Promise<void> {
+  if (transcriptFlushTimer) {
+    clearTimeout(transcriptFlushTimer);
+    transcriptFlushTimer = null;
+  }
+  if (queuedTranscript.length === 0) return;
+  const entries = queuedTranscript;
+  queuedTranscript = [];
+  await addChatEntries(entries);
+}
+
 // Get chat history stats
 export async function getChatHistoryStats(): Promise<ChatHistoryStats> {
   return await invoke<ChatHistoryStats>('get_chat_history_stats');
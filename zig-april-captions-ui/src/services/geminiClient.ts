@@ -0,0 +1,26 @@
+import { invoke } from '@tauri-apps/api/core';
+
+// Gemini requests are sent by the backend, which adds the API key, so the key
+// never reaches the webview. `path` is "<version>/models/<model>"; the result
+// has the parts of fetch's Response the callers use. The backend's error
+// message says what failed, so `status` is only 200 or 0.
+export interface GeminiFetchResponse {
+  ok: boolean;
+  status: number;
+  json: () => Promise<any>;
+  text: () => Promise<string>;
+}
+
+export async function geminiFetch(
+  path: string,
+  init: { body: string; method?: string; headers?: Record<string, string> },
+  apiKey?: string
+): Promise<GeminiFetchResponse> {
+  try {
+    const data = await invoke('gemini_request', { path, body: JSON.parse(init.body), apiKey });
+    return { ok: true, status: 200, json: async () => data, text: async () => JSON.stringify(data) };
+  } catch (e) {
+    const message = String(e);
+    return { ok: false, status: 0, json: async () => ({ error: { message } }), text: async () => message };
+  }
+}
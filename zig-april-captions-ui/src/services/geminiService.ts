@@ -1,7 +1,8 @@
 import { GeminiModel, GeminiResponse } from '../types';
 import { buildCompressedContext, buildContextString, addChatEntry } from './contextService';
+import { geminiFetch } from './geminiClient';
 
-const GEMINI_API_BASE = 'https://generativelanguage.googleapis.com/v1/models';
+const GEMINI_API_BASE = 'v1/models';
 
 const QUESTIONS_SYSTEM_PROMPT = `You are a helpful meeting assistant. Based on the meeting transcript provided, suggest 3 smart, relevant questions that the user could ask to contribute meaningfully to the discussion.
 
@@ -137,7 +138,7 @@ export async function generateSummary(
     throw new Error('No transcript text to summarize');
   }
 
-  const url = `${GEMINI_API_BASE}/${model}:generateContent?key=${apiKey}`;
+  const path = `${GEMINI_API_BASE}/${model}`;
 
   const requestBody = {
     contents: [
@@ -154,7 +155,7 @@ export async function generateSummary(
     }
   };
 
-  const response = await fetch(url, {
+  const response = await geminiFetch(path, {
     method: 'POST',
     headers: {
       'Content-Type': 'application/json',
@@ -192,7 +193,7 @@ export async function generateQuestions(
     throw new Error('No transcript text to analyze');
   }
 
-  const url = `${GEMINI_API_BASE}/${model}:generateContent?key=${apiKey}`;
+  const path = `${GEMINI_API_BASE}/${model}`;
 
   const requestBody = {
     contents: [
@@ -209,7 +210,7 @@ export async function generateQuestions(
     }
   };
 
-  const response = await fetch(url, {
+  const response = await geminiFetch(path, {
     method: 'POST',
     headers: {
       'Content-Type': 'application/json',
@@ -260,7 +261,7 @@ export async function generateAskResponse(
     throw new Error('Please enter a question');
   }
 
-  const url = `${GEMINI_API_BASE}/${model}:generateContent?key=${apiKey}`;
+  const path = `${GEMINI_API_BASE}/${model}`;
 
   let contextParts = '';
   if (knowledgeContext.trim()) {
@@ -285,7 +286,7 @@ export async function generateAskResponse(
     }
   };
 
-  const response = await fetch(url, {
+  const response = await geminiFetch(path, {
     method: 'POST',
     headers: {
       'Content-Type': 'application/json',
@@ -325,7 +326,7 @@ export async function generateAnswerResponse(
     throw new Error('Question is required');
   }
 
-  const url = `${GEMINI_API_BASE}/${model}:generateContent?key=${apiKey}`;
+  const path = `${GEMINI_API_BASE}/${model}`;
 
   let contextParts = '';
   if (knowledgeContext.trim()) {
@@ -359,7 +360,7 @@ Generate a natural speaking script to answer this question. Use the meeting tran
     }
   };
 
-  const response = await fetch(url, {
+  const response = await geminiFetch(path, {
     method: 'POST',
     headers: {
       'Content-Type': 'application/json',
@@ -400,7 +401,7 @@ export async function generateIdeaScript(
     throw new Error('Please enter your idea content');
   }
 
-  const url = `${GEMINI_API_BASE}/${model}:generateContent?key=${apiKey}`;
+  const path = `${GEMINI_API_BASE}/${model}`;
 
   let contextParts = '';
   if (knowledgeContext.trim()) {
@@ -456,7 +457,7 @@ SCRIPT: [your corrected speaking script here]`;
     }
   };
 
-  const response = await fetch(url, {
+  const response = await geminiFetch(path, {
     method: 'POST',
     headers: {
       'Content-Type': 'application/json',
@@ -503,7 +504,7 @@ export async function generateTalkScript(
     throw new Error('Line to talk about is required');
   }
 
-  const url = `${GEMINI_API_BASE}/${model}:generateContent?key=${apiKey}`;
+  const path = `${GEMINI_API_BASE}/${model}`;
 
   let contextParts = '';
   if (knowledgeContext.trim()) {
@@ -533,7 +534,7 @@ Generate a natural speaking script that relates to this line and contributes to
     }
   };
 
-  const response = await fetch(url, {
+  const response = await geminiFetch(path, {
     method: 'POST',
     headers: {
       'Content-Type': 'application/json',
@@ -577,7 +578,7 @@ export async function generateClarifyingQuestions(
     throw new Error('Line to clarify is required');
   }
 
-  const url = `${GEMINI_API_BASE}/${model}:generateContent?key=${apiKey}`;
+  const path = `${GEMINI_API_BASE}/${model}`;
 
   let contextParts = '';
   if (transcriptText.trim()) {
@@ -604,7 +605,7 @@ Generate 1-3 natural clarifying questions about this specific statement:`;
     }
   };
 
-  const response = await fetch(url, {
+  const response = await geminiFetch(path, {
     method: 'POST',
     headers: {
       'Content-Type': 'application/json',
@@ -663,7 +664,7 @@ export async function translateText(
     throw new Error('No text to translate');
   }
 
-  const url = `${GEMINI_API_BASE}/${model}:generateContent?key=${apiKey}`;
+  const path = `${GEMINI_API_BASE}/${model}`;
 
   const prompt = `${TRANSLATION_SYSTEM_PROMPT}
 
@@ -687,7 +688,7 @@ Provide the translation:`;
     }
   };
 
-  const response = await fetch(url, {
+  const response = await geminiFetch(path, {
     method: 'POST',
     headers: {
       'Content-Type': 'application/json',
@@ -716,15 +717,14 @@ export async function validateApiKey(apiKey: string): Promise<boolean> {
   if (!apiKey) return false;
 
   try {
-    const url = `${GEMINI_API_BASE}/gemini-2.5-flash:generateContent?key=${apiKey}`;
-    const response = await fetch(url, {
+    const response = await geminiFetch(`${GEMINI_API_BASE}/gemini-2.5-flash`, {
       method: 'POST',
       headers: { 'Content-Type': 'application/json' },
       body: JSON.stringify({
         contents: [{ parts: [{ text: 'Hi' }] }],
         generationConfig: { maxOutputTokens: 1 }
       }),
-    });
+    }, apiKey);
     return response.ok;
   } catch {
     return false;
@@ -753,7 +753,7 @@ export async function generateSummaryWithContext(
     throw new Error('No context available to summarize');
   }
 
-  const url = `${GEMINI_API_BASE}/${model}:generateContent?key=${apiKey}`;
+  const path = `${GEMINI_API_BASE}/${model}`;
 
   const prompt = `${SUMMARY_SYSTEM_PROMPT}
 
@@ -774,7 +774,7 @@ Generate a summary of the conversation above:`;
     }
   };
 
-  const response = await fetch(url, {
+  const response = await geminiFetch(path, {
     method: 'POST',
     headers: {
       'Content-Type': 'application/json',
@@ -820,7 +820,7 @@ export async function generateQuestionsWithContext(
     throw new Error('No context available to analyze');
   }
 
-  const url = `${GEMINI_API_BASE}/${model}:generateContent?key=${apiKey}`;
+  const path = `${GEMINI_API_BASE}/${model}`;
 
   const prompt = `${QUESTIONS_SYSTEM_PROMPT}
 
@@ -841,7 +841,7 @@ Based on the conversation above, generate 3 smart questions:`;
     }
   };
 
-  const response = await fetch(url, {
+  const response = await geminiFetch(path, {
     method: 'POST',
     headers: {
       'Content-Type': 'application/json',
@@ -899,7 +899,7 @@ export async function generateIdeaScriptWithContext(
   const context = await buildCompressedContext(apiKey, model, undefined, undefined, meetingContext);
   const contextStr = buildContextString(context);
 
-  const url = `${GEMINI_API_BASE}/${model}:generateContent?key=${apiKey}`;
+  const path = `${GEMINI_API_BASE}/${model}`;
 
   const prompt = `${IDEA_CORRECTION_SYSTEM_PROMPT}
 
@@ -929,7 +929,7 @@ SCRIPT: [your corrected speaking script here]`;
     }
   };
 
-  const response = await fetch(url, {
+  const response = await geminiFetch(path, {
     method: 'POST',
     headers: {
       'Content-Type': 'application/json',
@@ -983,7 +983,7 @@ export async function generateAnswerWithContext(
   const context = await buildCompressedContext(apiKey, model, undefined, undefined, meetingContext);
   const contextStr = buildContextString(context);
 
-  const url = `${GEMINI_API_BASE}/${model}:generateContent?key=${apiKey}`;
+  const path = `${GEMINI_API_BASE}/${model}`;
 
   const prompt = `${ANSWER_SYSTEM_PROMPT}
 
@@ -1007,7 +1007,7 @@ Generate a natural speaking script to answer this question:`;
     }
   };
 
-  const response = await fetch(url, {
+  const response = await geminiFetch(path, {
     method: 'POST',
     headers: {
       'Content-Type': 'application/json',
@@ -1054,7 +1054,7 @@ export async function generateTalkScriptWithContext(
   const context = await buildCompressedContext(apiKey, model, undefined, undefined, meetingContext);
   const contextStr = buildContextString(context);
 
-  const url = `${GEMINI_API_BASE}/${model}:generateContent?key=${apiKey}`;
+  const path = `${GEMINI_API_BASE}/${model}`;
 
   const prompt = `${TALK_ABOUT_LINE_PROMPT}
 
@@ -1078,7 +1078,7 @@ Generate a natural speaking script that relates to this line and contributes to
     }
   };
 
-  const response = await fetch(url, {
+  const response = await geminiFetch(path, {
     method: 'POST',
     headers: {
       'Content-Type': 'application/json',
@@ -1130,7 +1130,7 @@ export async function generateClarifyingQuestionsWithContext(
   const context = await buildCompressedContext(apiKey, model, undefined, undefined, meetingContext);
   const contextStr = buildContextString(context);
 
-  const url = `${GEMINI_API_BASE}/${model}:generateContent?key=${apiKey}`;
+  const path = `${GEMINI_API_BASE}/${model}`;
 
   const prompt = `${ASK_CLARIFYING_QUESTIONS_PROMPT}
 
@@ -1154,7 +1154,7 @@ Generate 1-3 natural clarifying questions about this specific statement:`;
     }
   };
 
-  const response = await fetch(url, {
+  const response = await geminiFetch(path, {
     method: 'POST',
     headers: {
       'Content-Type': 'application/json',
@@ -1204,7 +1204,7 @@ export async function generateMeetingGreeting(
     throw new Error('API key is required');
   }
 
-  const url = `${GEMINI_API_BASE}/${model}:generateContent?key=${apiKey}`;
+  const path = `${GEMINI_API_BASE}/${model}`;
 
   // Build context-aware prompt
   let contextSection = '';
@@ -1256,7 +1256,7 @@ SCRIPT:
     }
   };
 
-  const response = await fetch(url, {
+  const response = await geminiFetch(path, {
     method: 'POST',
     headers: {
       'Content-Type': 'application/json',
@@ -38,6 +38,7 @@ export interface Translations {
   audioSource: string;
   microphone: string;
   systemAudio: string;
+  bothSides: string;
   aiSettings: string;
   apiKey: string;
   apiKeyPlaceholder: string;
@@ -206,6 +207,7 @@ export const translations: Record<AppLanguage, Translations> = {
     audioSource: 'Audio Source',
     microphone: 'Microphone',
     systemAudio: 'System Audio',
+    bothSides: 'Both',
     aiSettings: 'AI Settings (Gemini)',
     apiKey: 'API Key',
     apiKeyPlaceholder: 'Enter Gemini API key',
@@ -400,6 +402,7 @@ export const translations: Record<AppLanguage, Translations> = {
     audioSource: 'Nguồn âm thanh',
     microphone: 'Microphone',
     systemAudio: 'Âm thanh hệ thống',
+    bothSides: 'Cả hai',
     aiSettings: 'Cài đặt AI (Gemini)',
     apiKey: 'API Key',
     apiKeyPlaceholder: 'Nhập Gemini API key',
@@ -16,7 +16,7 @@ export interface MeetingContext {
 
 // AI Settings for Gemini integration
 export interface AISettings {
-  api_key: string;
+  api_key: string;  // Masked once saved; the backend keeps the key and makes the requests
   model: GeminiModel;
   translation_language?: TranslationLanguage;
   meeting_context?: string; // Legacy: User's meeting purpose/agenda (for backward compatibility)
@@ -64,11 +64,53 @@ export const TRANSLATION_LANGUAGES: Record<TranslationLanguage, string> = {
 
 export interface Settings {
   model_path: string;
-  audio_source: 'mic' | 'monitor';
+  audio_source: 'mic' | 'monitor' | 'both';
   font_size: number;
   theme: 'light' | 'dark' | 'system';
   language: AppLanguage;
   ai?: AISettings;
+  autostart?: AutostartSettings;
+}
+
+// Launch at login and captions on launch
+export interface AutostartSettings {
+  launch_at_login: boolean;
+  start_captions: boolean; // Start with the last-used model and audio source
+}
+
+// Problem found in the settings (validate_settings)
+export interface SettingsWarning {
+  field: string; // Top-level settings key, "" for the whole file
+  message: string;
+  value?: unknown; // The rejected value
+  reset_to_default: boolean; // Replaced by the default (or will be, on repair)
+}
+
+// Payload of the settings-changed event
+export interface SettingsChanged {
+  changes: SettingChange[];
+}
+
+export interface SettingChange {
+  key_path: string; // Dotted path, e.g. "ai.model"
+  old: unknown;
+  new: unknown;
+}
+
+// Named settings profile (list_profiles)
+export interface ProfileInfo {
+  name: string;
+  active: boolean;
+  model_path: string;
+  audio_source: string;
+  language: string;
+  updated_at: number;
+}
+
+// Payload of the profile-switched event
+export interface ProfileSwitched {
+  name: string;
+  restarted: boolean; // Captions restarted with the profile's model or audio source
 }
 
 // Summary state
@@ -93,6 +135,22 @@ export interface KnowledgeEntry {
   content: string;
   created_at: number;
   nominated: boolean; // Whether this entry is selected for use with Gemini
+  tags?: string[];
+  folder?: string; // Name of a knowledge folder
+  metadata?: Record<string, unknown>; // Source of imported notes
+  source_session_id?: string;  // Session the note was taken from
+  source_entry_ids?: string[]; // Chat entries it was taken from
+}
+
+// Knowledge note retrieved for a question (retrieve_knowledge)
+export interface RetrievedKnowledge extends KnowledgeEntry {
+  score: number; // Cosine similarity to the question
+}
+
+export interface KnowledgeFolder {
+  name: string;
+  created_at: number;
+  entry_count: number;
 }
 
 // Note view for Knowledge Panel UI
@@ -120,6 +178,123 @@ export interface IdeaEntry {
   raw_content: string;        // User's raw input with mistakes
   corrected_script: string;   // Gemini corrected script
   created_at: number;         // Unix timestamp in milliseconds
+  source_session_id?: string;  // Session the idea was taken from
+  source_entry_ids?: string[]; // Transcript entries it was created from
+}
+
+// A corrected script an idea has had (get_idea_versions)
+export interface IdeaVersion {
+  idea_id: string;
+  version: number;
+  corrected_script: string;
+  created_at: number;
+  diff?: {                    // Word diff against the previous version
+    old_words: number;
+    new_words: number;
+    unchanged: number;
+    inserted: number;
+    deleted: number;
+    substituted: number;
+    difference: number;
+    changes: { old: string; new: string }[];
+  };
+}
+
+// A deleted knowledge note, idea or chat entry (list_trashed)
+export type TrashKind = 'knowledge' | 'idea' | 'chat_entry';
+
+export interface TrashedEntry {
+  id: string;
+  kind: TrashKind;
+  label: string;              // Start of its text, or the idea's title
+  deleted_at: number;
+  entry: KnowledgeEntry | IdeaEntry | ChatHistoryEntry;
+}
+
+// A scheduled database snapshot (list_backups)
+export interface DbBackup {
+  id: string;
+  created_at: number;
+  size_bytes: number;
+}
+
+// Rows in the vector index after rebuild_vector_index
+export interface VectorIndexStats {
+  chat_entries: number;
+  knowledge_entries: number;
+}
+
+// get_session_analytics; times are milliseconds
+export interface SpeakerStats {
+  speaker: string;
+  lines: number;
+  words: number;
+  speaking_ms: number;
+  share: number; // Fraction of all speaking time, 0..1
+  words_per_minute: number;
+  interruptions: number;
+}
+
+export interface SilenceGap {
+  start: number;
+  end: number;
+  duration_ms: number;
+}
+
+export interface SessionAnalytics {
+  session_id: string;
+  duration_ms: number;
+  speaking_ms: number;
+  silence_ms: number;
+  words: number;
+  words_per_minute: number;
+  interruptions: number;
+  speakers: SpeakerStats[]; // Most speaking time first
+  silence_gaps: SilenceGap[];
+}
+
+// get_topic_timeline: how a topic came up across sessions, oldest first
+export type TopicMatchKind = 'text' | 'semantic' | 'tag';
+
+export interface TopicMention {
+  id: string; // Chat entry id, or "line-<rowid>" for a transcript line
+  session_id?: string;
+  session_title?: string;
+  timestamp: number;
+  entry_type: string; // Chat entry type, or "transcript_line"
+  text: string;
+  speaker?: string;
+  matched_by: TopicMatchKind[];
+  similarity?: number;
+  before: string[]; // Transcript lines leading up to the mention
+  after: string[];
+}
+
+// start_captions `autoStop` option; unset or 0 turns a limit off
+export interface AutoStopOptions {
+  max_duration_minutes?: number;
+  stop_on_silence_minutes?: number;
+}
+
+// Payload of `captions-auto-stopped`, emitted just before the stop
+export interface CaptionsAutoStopped {
+  reason: 'max_duration' | 'silence';
+  session_id: string | null;
+  elapsed_ms: number;
+  silent_ms: number;
+}
+
+// Reusable setup for a recurring meeting, applied by start_captions
+export interface MeetingTemplate {
+  id: string; // Empty to create a new template with save_template
+  name: string;
+  meeting_context?: string;
+  knowledge_ids?: string[]; // Notes to nominate; unset leaves nominations alone
+  vocabulary: { term: string; sounds_like: string[] }[];
+  translation_language?: string;
+  export?: Record<string, unknown>; // Options for export_session
+  created_at: number;
+  updated_at: number;
 }
 
 // Timeline items for unified Ideas tab display
@@ -160,12 +335,25 @@ export type TimelineItem = SummaryTimelineItem | QuestionsTimelineItem | IdeaTim
 
 export interface CaptionEvent {
   type: 'ready' | 'listening' | 'caption' | 'warning' | 'error' | 'stopped';
+  id?: string;       // Set on final captions
   captionType?: 'partial' | 'final';
   text?: string;
   timestamp?: number;
   message?: string;
   version?: string;
-  source?: string;
+  source?: string;   // "me" / "them" when capturing both sides
+  channel?: string;  // "mic" or "monitor"
+  speaker?: string;
+}
+
+// Live transcript line, addressed by id for edit/delete/merge
+export interface TranscriptLine {
+  id: string;
+  text: string;
+  timestamp: number;
+  speaker?: string;
+  edited: boolean;
+  caption_ids?: string[];  // Final captions the line was made from
 }
 
 // Gemini API response types
@@ -186,6 +374,9 @@ export interface ChatHistoryEntry {
   entry_type: ChatHistoryEntryType;
   content: string;
   metadata?: Record<string, unknown>; // For type-specific data
+  source?: string;
+  channel?: string;
+  pinned?: boolean; // Always included in AI context
 }
 
 // Context compression snapshot
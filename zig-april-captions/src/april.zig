@@ -85,13 +85,16 @@ pub fn freeModel(model: Model) void {
     c.aam_free(model);
 }
 
-/// Create a recognition session with a model and handler
-pub fn createSession(model: Model, handler: ResultHandler, userdata: ?*anyopaque) ?Session {
+/// Create a recognition session with a model and handler. Real-time
+/// sessions process audio on a background thread and drop input they can't
+/// keep up with; otherwise feeding blocks until the audio is processed and
+/// the handler runs on the caller's thread.
+pub fn createSession(model: Model, handler: ResultHandler, userdata: ?*anyopaque, realtime: bool) ?Session {
     const config = c.AprilConfig{
         .speaker = std.mem.zeroes(c.AprilSpeakerID),
         .handler = handler,
         .userdata = userdata,
-        .flags = c.APRIL_CONFIG_FLAG_ASYNC_RT_BIT,
+        .flags = if (realtime) c.APRIL_CONFIG_FLAG_ASYNC_RT_BIT else c.APRIL_CONFIG_FLAG_ZERO_BIT,
     };
     return c.aas_create_session(model, config);
 }
@@ -117,6 +120,14 @@ pub fn freeSession(session: Session) void {
     c.aas_free(session);
 }
 
+/// APRIL_TOKEN_FLAG_WORD_BOUNDARY_BIT: the token begins a new word
+pub const TOKEN_FLAG_WORD_BOUNDARY = 1;
+
+/// Whether a token starts a new word
+pub fn startsWord(token: anytype) bool {
+    return (token.flags & TOKEN_FLAG_WORD_BOUNDARY) != 0;
+}
+
 /// Helper to get token text as a Zig slice
 pub fn getTokenText(token: anytype) []const u8 {
     if (token.token) |ptr| {
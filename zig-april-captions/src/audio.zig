@@ -28,7 +28,10 @@ pub const AudioCapture = struct {
     const Self = @This();
 
     /// Initialize audio capture
-    pub fn init(allocator: std.mem.Allocator, sample_rate: u32, source: AudioSource, verbose: bool) !Self {
+    /// @param device: Input device to open instead of the default microphone,
+    /// or null. Monitor capture always records the default output.
+    pub fn init(allocator: std.mem.Allocator, sample_rate: u32, source: AudioSource, device: ?[:0]const u8, verbose: bool) !Self {
+        const input_device = if (source == .microphone) device else null;
         if (builtin.os.tag == .linux) {
             // Linux: Use PulseAudio Simple API (synchronous, no blocking)
             std.debug.print("DEBUG: Using PulseAudio Simple API on Linux\n", .{});
@@ -39,6 +42,7 @@ pub const AudioCapture = struct {
                         .microphone => .microphone,
                         .monitor => .monitor,
                     },
+                    input_device,
                     verbose,
                 ),
                 .audioqueue_capture = undefined,
@@ -58,6 +62,7 @@ pub const AudioCapture = struct {
                         .microphone => .microphone,
                         .monitor => .monitor,
                     },
+                    input_device,
                     verbose,
                 ),
                 .miniaudio_capture = undefined,
@@ -77,6 +82,7 @@ pub const AudioCapture = struct {
                         .microphone => .microphone,
                         .monitor => .monitor,
                     },
+                    input_device,
                     verbose,
                 ),
                 .sample_rate = sample_rate,
@@ -6,10 +6,72 @@ const std = @import("std");
 
 // Import AudioToolbox framework for AudioQueue
 const c = @cImport({
+    @cInclude("CoreFoundation/CoreFoundation.h");
+    @cInclude("CoreAudio/CoreAudio.h");
     @cInclude("AudioToolbox/AudioQueue.h");
     @cInclude("AudioToolbox/AudioFormat.h");
 });
 
+// AudioObject constants (FourCC), spelled out as in coreaudio.zig
+const kAudioObjectSystemObject: u32 = 1;
+const kAudioObjectPropertyScopeGlobal: u32 = 0x676c6f62; // 'glob'
+const kAudioObjectPropertyElementMain: u32 = 0;
+const kAudioHardwarePropertyDevices: u32 = 0x64657623; // 'dev#'
+const kAudioObjectPropertyName: u32 = 0x6c6e616d; // 'lnam'
+const kAudioDevicePropertyDeviceUID: u32 = 0x75696420; // 'uid '
+const kAudioQueueProperty_CurrentDevice: u32 = 0x61716364; // 'aqcd'
+
+const PropertyAddress = extern struct {
+    mSelector: u32,
+    mScope: u32,
+    mElement: u32,
+};
+
+/// Read a CFString property of an audio object (caller releases it)
+fn getStringProperty(object_id: c.AudioObjectID, selector: u32) c.CFStringRef {
+    const address = PropertyAddress{
+        .mSelector = selector,
+        .mScope = kAudioObjectPropertyScopeGlobal,
+        .mElement = kAudioObjectPropertyElementMain,
+    };
+    var value: c.CFStringRef = null;
+    var size: u32 = @sizeOf(c.CFStringRef);
+    if (c.AudioObjectGetPropertyData(object_id, @ptrCast(&address), 0, null, &size, @ptrCast(&value)) != 0) {
+        return null;
+    }
+    return value;
+}
+
+/// UID of the device called `name`, or null (caller releases it)
+fn findDeviceUid(name: []const u8) c.CFStringRef {
+    const address = PropertyAddress{
+        .mSelector = kAudioHardwarePropertyDevices,
+        .mScope = kAudioObjectPropertyScopeGlobal,
+        .mElement = kAudioObjectPropertyElementMain,
+    };
+    var size: u32 = 0;
+    if (c.AudioObjectGetPropertyDataSize(kAudioObjectSystemObject, @ptrCast(&address), 0, null, &size) != 0) {
+        return null;
+    }
+    const devices = std.heap.page_allocator.alloc(c.AudioObjectID, size / @sizeOf(c.AudioObjectID)) catch return null;
+    defer std.heap.page_allocator.free(devices);
+    if (c.AudioObjectGetPropertyData(kAudioObjectSystemObject, @ptrCast(&address), 0, null, &size, devices.ptr) != 0) {
+        return null;
+    }
+
+    var buf: [256]u8 = undefined;
+    for (devices) |device_id| {
+        const device_name = getStringProperty(device_id, kAudioObjectPropertyName);
+        if (device_name == null) continue;
+        defer c.CFRelease(device_name);
+        if (c.CFStringGetCString(device_name, &buf, buf.len, c.kCFStringEncodingUTF8) == 0) continue;
+        if (std.mem.eql(u8, std.mem.sliceTo(&buf, 0), name)) {
+            return getStringProperty(device_id, kAudioDevicePropertyDeviceUID);
+        }
+    }
+    return null;
+}
+
 pub const AudioError = error{
     DeviceNotFound,
     FormatMismatch,
@@ -145,7 +207,7 @@ pub const AudioCapture = struct {
     const NUMBER_BUFFERS = 3;
 
     /// Initialize audio capture using Apple's AudioQueue Services
-    pub fn init(allocator: std.mem.Allocator, sample_rate: u32, source: AudioSource, verbose: bool) AudioError!Self {
+    pub fn init(allocator: std.mem.Allocator, sample_rate: u32, source: AudioSource, device_name: ?[:0]const u8, verbose: bool) AudioError!Self {
         if (verbose) {
             std.log.info("AudioQueue: Initializing audio capture at {d} Hz", .{sample_rate});
         }
@@ -214,6 +276,20 @@ pub const AudioCapture = struct {
             return AudioError.InitializeFailed;
         }
 
+        // Switch to the requested input device before buffers are allocated
+        if (device_name) |name| {
+            const uid = findDeviceUid(name);
+            if (uid != null) {
+                defer c.CFRelease(uid);
+                const set_status = c.AudioQueueSetProperty(queue, kAudioQueueProperty_CurrentDevice, @ptrCast(&uid), @sizeOf(c.CFStringRef));
+                if (set_status != c.noErr) {
+                    std.log.warn("AudioQueue: Could not select device '{s}' (status: {d})", .{ name, set_status });
+                }
+            } else {
+                std.log.warn("AudioQueue: Device '{s}' not found, using the default input", .{name});
+            }
+        }
+
         // Get the actual format from the queue (it might be different)
         var actual_format: c.AudioStreamBasicDescription = undefined;
         var size: u32 = @sizeOf(c.AudioStreamBasicDescription);
@@ -7,6 +7,10 @@
 //!   zig-april-captions <model.april>              # Microphone input
 //!   zig-april-captions --monitor <model.april>    # System audio (YouTube, etc.)
 //!   zig-april-captions --json <model.april>       # JSON output mode (for UI integration)
+//!   zig-april-captions --device <name> <model.april>  # Specific microphone
+//!   zig-april-captions --record <out.wav> <model.april>  # Also save the audio
+//!   zig-april-captions --json --file <in.wav> <model.april>  # Transcribe a recording
+//!   zig-april-captions --vocabulary <terms.txt> <model.april>  # Fix jargon and names
 //!
 //! Press Ctrl+C to exit
 
@@ -15,6 +19,10 @@ const builtin = @import("builtin");
 const april = @import("april.zig");
 const audio = @import("audio.zig");
 const AsrProcessor = @import("processor.zig").AsrProcessor;
+const Word = @import("processor.zig").Word;
+const WavRecorder = @import("recorder.zig").WavRecorder;
+const WavReader = @import("wavreader.zig").WavReader;
+const Vocabulary = @import("vocabulary.zig").Vocabulary;
 
 const VERSION = "0.3.0";
 
@@ -37,6 +45,10 @@ pub fn main() !void {
     var audio_source = audio.AudioSource.microphone;
     var output_mode = OutputMode.terminal;
     var verbose = false;
+    var device: ?[]const u8 = null;
+    var record_path: ?[]const u8 = null;
+    var file_path: ?[]const u8 = null;
+    var vocabulary_path: ?[]const u8 = null;
 
     var i: usize = 1;
     while (i < args.len) : (i += 1) {
@@ -56,6 +68,38 @@ pub fn main() !void {
             output_mode = OutputMode.json;
         } else if (std.mem.eql(u8, arg, "--verbose") or std.mem.eql(u8, arg, "-V")) {
             verbose = true;
+        } else if (std.mem.eql(u8, arg, "--device") or std.mem.eql(u8, arg, "-d")) {
+            i += 1;
+            if (i >= args.len) {
+                std.debug.print("Error: --device needs a device name\n\n", .{});
+                printUsage(args[0]);
+                return;
+            }
+            device = args[i];
+        } else if (std.mem.eql(u8, arg, "--record") or std.mem.eql(u8, arg, "-r")) {
+            i += 1;
+            if (i >= args.len) {
+                std.debug.print("Error: --record needs an output file\n\n", .{});
+                printUsage(args[0]);
+                return;
+            }
+            record_path = args[i];
+        } else if (std.mem.eql(u8, arg, "--file") or std.mem.eql(u8, arg, "-f")) {
+            i += 1;
+            if (i >= args.len) {
+                std.debug.print("Error: --file needs an input file\n\n", .{});
+                printUsage(args[0]);
+                return;
+            }
+            file_path = args[i];
+        } else if (std.mem.eql(u8, arg, "--vocabulary")) {
+            i += 1;
+            if (i >= args.len) {
+                std.debug.print("Error: --vocabulary needs a file\n\n", .{});
+                printUsage(args[0]);
+                return;
+            }
+            vocabulary_path = args[i];
         } else if (arg[0] != '-') {
             model_path = arg;
         } else {
@@ -75,7 +119,10 @@ pub fn main() !void {
     const model_path_z = try allocator.dupeZ(u8, model_path.?);
     defer allocator.free(model_path_z);
 
-    const source_name = switch (audio_source) {
+    const device_z: ?[:0]u8 = if (device) |name| try allocator.dupeZ(u8, name) else null;
+    defer if (device_z) |name| allocator.free(name);
+
+    const source_name = if (file_path != null) "File" else switch (audio_source) {
         .microphone => "Microphone",
         .monitor => "System Audio",
     };
@@ -96,12 +143,22 @@ pub fn main() !void {
         try stdout.print("{{\"type\":\"ready\",\"version\":\"{s}\",\"source\":\"{s}\"}}\n", .{ VERSION, source_name });
     }
 
+    // Loaded before the processor so it outlives the April session
+    var vocabulary: ?Vocabulary = null;
+    if (vocabulary_path) |path| {
+        vocabulary = Vocabulary.load(allocator, path) catch |err| blk: {
+            std.log.warn("Could not load vocabulary {s}: {}", .{ path, err });
+            break :blk null;
+        };
+    }
+    defer if (vocabulary) |*v| v.deinit();
+
     // Initialize ASR processor
     // Reference: LiveCaptions main.c - create_asr_thread()
     if (output_mode == .terminal) {
         std.debug.print("Loading model: {s}\n", .{model_path.?});
     }
-    const processor = AsrProcessor.init(allocator, model_path_z) catch |err| {
+    const processor = AsrProcessor.init(allocator, model_path_z, file_path == null) catch |err| {
         if (output_mode == .json) {
             stdout.print("{{\"type\":\"error\",\"message\":\"Failed to initialize ASR: {}\"}}\n", .{err}) catch {};
         } else {
@@ -114,13 +171,18 @@ pub fn main() !void {
         return;
     };
     defer processor.deinit(allocator);
+    if (vocabulary) |*v| processor.vocabulary = v;
+
+    if (file_path) |path| {
+        return transcribeFile(processor, path, output_mode);
+    }
 
     // Initialize audio capture
     // Reference: LiveCaptions main.c - create_audio_thread()
     if (output_mode == .terminal) {
         std.debug.print("Initializing {s}...\n", .{source_name});
     }
-    var audio_capture = audio.AudioCapture.init(allocator, @intCast(processor.getSampleRate()), audio_source, verbose) catch |err| {
+    var audio_capture = audio.AudioCapture.init(allocator, @intCast(processor.getSampleRate()), audio_source, device_z, verbose) catch |err| {
         if (output_mode == .json) {
             stdout.print("{{\"type\":\"error\",\"message\":\"Failed to open {s}: {}\"}}\n", .{ source_name, err }) catch {};
         } else {
@@ -137,6 +199,16 @@ pub fn main() !void {
     };
     defer audio_capture.deinit();
 
+    // Optional WAV copy of everything fed to the recognizer
+    var recorder: ?WavRecorder = null;
+    if (record_path) |path| {
+        recorder = WavRecorder.init(path, @intCast(processor.getSampleRate())) catch |err| blk: {
+            std.log.warn("Could not create recording {s}: {}", .{ path, err });
+            break :blk null;
+        };
+    }
+    defer if (recorder) |*r| r.deinit();
+
     // Start audio capture (required for CoreAudio on macOS)
     std.debug.print("DEBUG: About to start audio capture...\n", .{});
 
@@ -198,14 +270,14 @@ pub fn main() !void {
     var audio_buffer: [4096]i16 = undefined;
     const buffer_slice = audio_buffer[0..chunk_samples];
 
-    // Text output buffer
-    var text_buffer: [4096]u8 = undefined;
-    var last_text_len: usize = 0;
-    var last_was_final = false;
+    var output = CaptionOutput{ .mode = output_mode };
 
     // Main loop
     // Reference: LiveCaptions - audio capture → ASR processing → display
     std.debug.print("DEBUG: Entering main loop (READ/WRITE mode)\n", .{});
+    // April reports word times relative to the start of the audio stream;
+    // capture is real time, so this maps them onto the wall clock
+    const stream_origin_ms = std.time.milliTimestamp();
     while (audio_capture.isRunning()) {
         // Read audio (blocks until data is available)
         const samples = audio_capture.read(buffer_slice) catch |err| {
@@ -216,53 +288,20 @@ pub fn main() !void {
             continue;
         };
 
+        if (recorder) |*r| {
+            r.write(samples) catch |err| {
+                std.log.warn("Recording stopped: {}", .{err});
+                r.deinit();
+                recorder = null;
+            };
+        }
+
         // Feed to ASR processor
         processor.processAudio(samples);
 
         // Check for new captions
         if (processor.hasNewText()) {
-            const result = processor.getText(&text_buffer);
-
-            if (result.len > 0) {
-                const text = text_buffer[0..result.len];
-                const timestamp = std.time.milliTimestamp();
-
-                if (output_mode == .json) {
-                    // JSON output mode - escape text for JSON
-                    const caption_type = if (result.is_final) "final" else "partial";
-                    stdout.print("{{\"type\":\"caption\",\"captionType\":\"{s}\",\"text\":\"", .{caption_type}) catch {};
-                    // Write escaped text
-                    for (text) |c| {
-                        switch (c) {
-                            '"' => stdout.writeAll("\\\"") catch {},
-                            '\\' => stdout.writeAll("\\\\") catch {},
-                            '\n' => stdout.writeAll("\\n") catch {},
-                            '\r' => stdout.writeAll("\\r") catch {},
-                            '\t' => stdout.writeAll("\\t") catch {},
-                            else => stdout.writeByte(c) catch {},
-                        }
-                    }
-                    stdout.print("\",\"timestamp\":{d}}}\n", .{timestamp}) catch {};
-                } else {
-                    // Terminal output mode
-                    // Clear previous partial text (move cursor up and clear line)
-                    if (last_text_len > 0 and !last_was_final) {
-                        std.debug.print("\r\x1b[K", .{}); // Clear current line
-                    }
-
-                    // Print caption
-                    if (result.is_final) {
-                        // Final result - print with newline
-                        std.debug.print("{s}\n", .{text});
-                    } else {
-                        // Partial result - print without newline (will be updated)
-                        std.debug.print("\x1b[90m{s}\x1b[0m", .{text}); // Gray for partial
-                    }
-                }
-
-                last_text_len = result.len;
-                last_was_final = result.is_final;
-            }
+            output.print(processor, stream_origin_ms, std.time.milliTimestamp());
         }
 
         // Check for errors
@@ -275,6 +314,12 @@ pub fn main() !void {
         }
     }
 
+    // Emit whatever April still holds before reporting the stop
+    processor.flush();
+    if (processor.hasNewText()) {
+        output.print(processor, stream_origin_ms, std.time.milliTimestamp());
+    }
+
     if (output_mode == .json) {
         stdout.print("{{\"type\":\"stopped\"}}\n", .{}) catch {};
     } else {
@@ -283,6 +328,151 @@ pub fn main() !void {
     }
 }
 
+/// Prints captions as they come out of the processor. Terminal mode redraws
+/// the partial line in place.
+const CaptionOutput = struct {
+    mode: OutputMode,
+    text_buffer: [4096]u8 = undefined,
+    word_buffer: [512]Word = undefined,
+    last_text_len: usize = 0,
+    last_was_final: bool = false,
+
+    /// Word times are offsets from `origin_ms`; `timestamp` is the caption's
+    fn print(self: *CaptionOutput, processor: *AsrProcessor, origin_ms: i64, timestamp: i64) void {
+        const stdout = std.io.getStdOut().writer();
+        const result = processor.getText(&self.text_buffer, &self.word_buffer);
+        if (result.len == 0) return;
+        const text = self.text_buffer[0..result.len];
+
+        if (self.mode == .json) {
+            // JSON output mode - escape text for JSON
+            const caption_type = if (result.is_final) "final" else "partial";
+            stdout.print("{{\"type\":\"caption\",\"captionType\":\"{s}\",\"text\":\"", .{caption_type}) catch {};
+            writeJsonEscaped(stdout, text);
+            stdout.print("\",\"timestamp\":{d},\"words\":[", .{timestamp}) catch {};
+            for (self.word_buffer[0..result.num_words], 0..) |word, i| {
+                if (i > 0) stdout.writeByte(',') catch {};
+                stdout.writeAll("{\"text\":\"") catch {};
+                writeJsonEscaped(stdout, text[word.start .. word.start + word.len]);
+                stdout.print("\",\"startMs\":{d},\"endMs\":{d},\"confidence\":{d:.3}}}", .{
+                    origin_ms + @as(i64, @intCast(word.start_ms)),
+                    origin_ms + @as(i64, @intCast(word.end_ms)),
+                    word.confidence,
+                }) catch {};
+            }
+            stdout.writeAll("]}\n") catch {};
+        } else {
+            // Terminal output mode
+            // Clear previous partial text (move cursor up and clear line)
+            if (self.last_text_len > 0 and !self.last_was_final) {
+                std.debug.print("\r\x1b[K", .{}); // Clear current line
+            }
+
+            // Print caption
+            if (result.is_final) {
+                // Final result - print with newline
+                std.debug.print("{s}\n", .{text});
+            } else {
+                // Partial result - print without newline (will be updated)
+                std.debug.print("\x1b[90m{s}\x1b[0m", .{text}); // Gray for partial
+            }
+        }
+
+        self.last_text_len = result.len;
+        self.last_was_final = result.is_final;
+    }
+};
+
+/// Transcribe a WAV file as fast as the CPU allows. Caption timestamps and
+/// word times are offsets into the file in milliseconds. JSON mode also
+/// reports `{"type":"progress","percent":N}` as the file is read.
+fn transcribeFile(processor: *AsrProcessor, path: []const u8, output_mode: OutputMode) void {
+    const stdout = std.io.getStdOut().writer();
+    var reader = WavReader.init(path) catch |err| {
+        if (output_mode == .json) {
+            stdout.print("{{\"type\":\"error\",\"message\":\"Failed to open audio file: {}\"}}\n", .{err}) catch {};
+        } else {
+            std.debug.print("Error: Failed to open {s} - {}\n", .{ path, err });
+            std.debug.print("\nFiles must be 16-bit mono WAV, e.g. ffmpeg -i in.mp3 -ac 1 -ar 16000 out.wav\n", .{});
+        }
+        return;
+    };
+    defer reader.deinit();
+
+    if (reader.sample_rate != processor.getSampleRate()) {
+        if (output_mode == .json) {
+            stdout.print("{{\"type\":\"error\",\"message\":\"Audio is {d} Hz but the model needs {d} Hz\"}}\n", .{
+                reader.sample_rate,
+                processor.getSampleRate(),
+            }) catch {};
+        } else {
+            std.debug.print("Error: Audio is {d} Hz but the model needs {d} Hz\n", .{ reader.sample_rate, processor.getSampleRate() });
+        }
+        return;
+    }
+
+    if (output_mode == .json) {
+        stdout.print("{{\"type\":\"listening\",\"source\":\"File\",\"durationMs\":{d}}}\n", .{
+            reader.total_samples * 1000 / reader.sample_rate,
+        }) catch {};
+    } else {
+        std.debug.print("Transcribing {s}...\n\n", .{path});
+    }
+
+    const chunk_samples = audio.samplesForMs(@intCast(processor.getSampleRate()), 50);
+    var audio_buffer: [4096]i16 = undefined;
+    var output = CaptionOutput{ .mode = output_mode };
+    var last_percent: usize = 0;
+
+    while (true) {
+        const samples = reader.read(audio_buffer[0..chunk_samples]) catch |err| {
+            if (output_mode == .json) {
+                stdout.print("{{\"type\":\"error\",\"message\":\"Failed to read audio file: {}\"}}\n", .{err}) catch {};
+            } else {
+                std.debug.print("Error: Failed to read {s} - {}\n", .{ path, err });
+            }
+            return;
+        };
+        if (samples.len == 0) break;
+
+        processor.processAudio(samples);
+        if (processor.hasNewText()) {
+            output.print(processor, 0, reader.positionMs());
+        }
+
+        const percent = reader.samples_read * 100 / @max(reader.total_samples, 1);
+        if (output_mode == .json and percent != last_percent) {
+            last_percent = percent;
+            stdout.print("{{\"type\":\"progress\",\"percent\":{d}}}\n", .{percent}) catch {};
+        }
+    }
+
+    processor.flush();
+    if (processor.hasNewText()) {
+        output.print(processor, 0, reader.positionMs());
+    }
+
+    if (output_mode == .json) {
+        stdout.print("{{\"type\":\"stopped\"}}\n", .{}) catch {};
+    } else {
+        std.debug.print("\nDone.\n", .{});
+    }
+}
+
+/// Write text as the inside of a JSON string literal
+fn writeJsonEscaped(writer: anytype, text: []const u8) void {
+    for (text) |c| {
+        switch (c) {
+            '"' => writer.writeAll("\\\"") catch {},
+            '\\' => writer.writeAll("\\\\") catch {},
+            '\n' => writer.writeAll("\\n") catch {},
+            '\r' => writer.writeAll("\\r") catch {},
+            '\t' => writer.writeAll("\\t") catch {},
+            else => writer.writeByte(c) catch {},
+        }
+    }
+}
+
 fn printUsage(program: []const u8) void {
     std.debug.print(
         \\zig-april-captions - Real-time speech-to-text
@@ -295,6 +485,13 @@ fn printUsage(program: []const u8) void {
         \\Options:
         \\  -m, --monitor     Capture system audio (YouTube, videos, etc.)
         \\      --mic         Capture from microphone (default)
+        \\  -d, --device NAME Capture from a specific input instead of the default mic
+        \\                    (PulseAudio source name on Linux, device name elsewhere)
+        \\  -r, --record FILE Save the captured audio as a 16-bit mono WAV file
+        \\  -f, --file FILE   Transcribe a 16-bit mono WAV file instead of capturing
+        \\      --vocabulary FILE
+        \\                    Replace misheard terms; one term per line, followed by
+        \\                    tab-separated sound-alikes
         \\  -j, --json        Output JSON lines (for UI integration)
         \\  -h, --help        Show this help message
         \\  -v, --version     Show version
@@ -27,6 +27,29 @@ pub const AudioSource = enum {
     monitor, // Capture system audio output (loopback)
 };
 
+/// Find the capture device called `name`
+fn findDeviceId(name: []const u8, out: *c.ma_device_id) bool {
+    var context: c.ma_context = undefined;
+    if (c.ma_context_init(null, 0, null, &context) != c.MA_SUCCESS) return false;
+    defer _ = c.ma_context_uninit(&context);
+
+    var playback_infos: [*c]c.ma_device_info = null;
+    var playback_count: c.ma_uint32 = 0;
+    var capture_infos: [*c]c.ma_device_info = null;
+    var capture_count: c.ma_uint32 = 0;
+    if (c.ma_context_get_devices(&context, &playback_infos, &playback_count, &capture_infos, &capture_count) != c.MA_SUCCESS) {
+        return false;
+    }
+
+    for (capture_infos[0..capture_count]) |*info| {
+        if (std.mem.eql(u8, std.mem.sliceTo(&info.name, 0), name)) {
+            out.* = info.id;
+            return true;
+        }
+    }
+    return false;
+}
+
 /// Thread-safe ring buffer for audio data
 const RingBuffer = struct {
     data: []i16,
@@ -149,7 +172,7 @@ pub const AudioCapture = struct {
     const Self = @This();
 
     /// Initialize audio capture
-    pub fn init(allocator: std.mem.Allocator, sample_rate: u32, source: AudioSource, verbose: bool) AudioError!Self {
+    pub fn init(allocator: std.mem.Allocator, sample_rate: u32, source: AudioSource, device_name: ?[:0]const u8, verbose: bool) AudioError!Self {
         if (verbose) {
             std.log.info("miniaudio: Initializing audio capture at {d} Hz", .{sample_rate});
         }
@@ -193,6 +216,18 @@ pub const AudioCapture = struct {
             device_config.capture.channels = 2;
         }
 
+        // Loopback records the default output; a chosen device is a microphone
+        var device_id: c.ma_device_id = undefined;
+        if (device_name) |name| {
+            if (source == .monitor) {
+                std.log.warn("miniaudio: Ignoring device '{s}' for system audio capture", .{name});
+            } else if (findDeviceId(name, &device_id)) {
+                device_config.capture.pDeviceID = &device_id;
+            } else {
+                std.log.warn("miniaudio: Device '{s}' not found, using the default", .{name});
+            }
+        }
+
         // Initialize device
         var device: c.ma_device = undefined;
         const result = c.ma_device_init(null, &device_config, &device);
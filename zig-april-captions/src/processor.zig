@@ -3,6 +3,7 @@
 
 const std = @import("std");
 const april = @import("april.zig");
+const Vocabulary = @import("vocabulary.zig").Vocabulary;
 
 /// Silence detection threshold (amplitude)
 /// Lower threshold for system audio which may be quieter
@@ -12,6 +13,15 @@ const SILENCE_THRESHOLD: i16 = 8;
 /// Reduced for faster response
 const SILENCE_FLUSH_SAMPLES: usize = 8000; // ~0.5s at 16kHz
 
+/// A recognized word: a span of the caption text with its timing
+pub const Word = struct {
+    start: usize, // Byte offset into the caption text
+    len: usize,
+    start_ms: usize, // Stream time reported by April
+    end_ms: usize,
+    confidence: f32, // exp() of the mean token log-probability
+};
+
 /// ASR Processor state
 pub const AsrProcessor = struct {
     model: april.Model,
@@ -25,16 +35,21 @@ pub const AsrProcessor = struct {
     // Output buffer for captions
     output_mutex: std.Thread.Mutex = .{},
     current_text: std.ArrayList(u8),
+    current_words: std.ArrayList(Word),
     is_final: bool = false,
     has_new_text: bool = false,
 
     // Error state
     is_errored: bool = false,
 
+    // Terms applied to every result; set before audio is fed
+    vocabulary: ?*const Vocabulary = null,
+
     const Self = @This();
 
-    /// Initialize processor with model path
-    pub fn init(allocator: std.mem.Allocator, model_path: [:0]const u8) !*Self {
+    /// Initialize processor with model path. `realtime` is for live capture;
+    /// files are processed synchronously so no audio is dropped.
+    pub fn init(allocator: std.mem.Allocator, model_path: [:0]const u8, realtime: bool) !*Self {
         // Initialize April API
         april.apiInit();
 
@@ -57,10 +72,11 @@ pub const AsrProcessor = struct {
             .session = undefined,
             .sample_rate = sample_rate,
             .current_text = std.ArrayList(u8).init(allocator),
+            .current_words = std.ArrayList(Word).init(allocator),
         };
 
         // Create session with callback - pass self pointer as userdata
-        self.session = april.createSession(model, resultCallback, @ptrCast(self)) orelse {
+        self.session = april.createSession(model, resultCallback, @ptrCast(self), realtime) orelse {
             april.freeModel(model);
             allocator.destroy(self);
             return error.SessionCreateFailed;
@@ -98,16 +114,32 @@ pub const AsrProcessor = struct {
         }
     }
 
-    /// Get current caption text (thread-safe)
-    pub fn getText(self: *Self, buffer: []u8) struct { len: usize, is_final: bool } {
+    /// Finalize whatever is still pending, e.g. at the end of a file
+    pub fn flush(self: *Self) void {
+        april.flush(self.session);
+        self.has_activity = false;
+        self.silence_samples = 0;
+    }
+
+    /// Get current caption text and its words (thread-safe). Words that
+    /// don't fit in either buffer are dropped.
+    pub fn getText(self: *Self, buffer: []u8, words: []Word) struct { len: usize, is_final: bool, num_words: usize } {
         self.output_mutex.lock();
         defer self.output_mutex.unlock();
 
         const len = @min(buffer.len, self.current_text.items.len);
         @memcpy(buffer[0..len], self.current_text.items[0..len]);
 
+        var num_words: usize = 0;
+        for (self.current_words.items) |word| {
+            if (num_words == words.len or word.start + word.len > len) break;
+            if (word.len == 0) continue;
+            words[num_words] = word;
+            num_words += 1;
+        }
+
         self.has_new_text = false;
-        return .{ .len = len, .is_final = self.is_final };
+        return .{ .len = len, .is_final = self.is_final, .num_words = num_words };
     }
 
     /// Check if there's new text available
@@ -137,9 +169,18 @@ pub const AsrProcessor = struct {
         april.freeSession(self.session);
         april.freeModel(self.model);
         self.current_text.deinit();
+        self.current_words.deinit();
         allocator.destroy(self);
     }
 
+    /// Set the confidence of the word being built. Caller holds output_mutex.
+    fn finishWord(self: *Self, logprob_sum: f32, token_count: usize) void {
+        if (token_count == 0 or self.current_words.items.len == 0) return;
+        const word = &self.current_words.items[self.current_words.items.len - 1];
+        const mean = logprob_sum / @as(f32, @floatFromInt(token_count));
+        word.confidence = @min(@exp(mean), 1.0);
+    }
+
     /// April ASR result callback - called from C
     fn resultCallback(
         userdata: ?*anyopaque,
@@ -157,15 +198,44 @@ pub const AsrProcessor = struct {
                 defer self.output_mutex.unlock();
 
                 self.current_text.clearRetainingCapacity();
+                self.current_words.clearRetainingCapacity();
                 self.is_final = (result_type == .recognition_final);
 
+                // Group tokens into words; a word's confidence is the
+                // geometric mean of its token probabilities
+                var logprob_sum: f32 = 0;
+                var word_tokens: usize = 0;
                 var i: usize = 0;
                 while (i < num_tokens) : (i += 1) {
                     const token = tokens[i];
-                    if (token.token) |ptr| {
-                        const text = std.mem.span(ptr);
-                        self.current_text.appendSlice(text) catch {};
+                    const text = april.getTokenText(token);
+
+                    if (april.startsWord(token) or self.current_words.items.len == 0) {
+                        self.finishWord(logprob_sum, word_tokens);
+                        logprob_sum = 0;
+                        word_tokens = 0;
+                        const leading = text.len - std.mem.trimLeft(u8, text, " ").len;
+                        self.current_words.append(.{
+                            .start = self.current_text.items.len + leading,
+                            .len = 0,
+                            .start_ms = token.time_ms,
+                            .end_ms = token.time_ms,
+                            .confidence = 0,
+                        }) catch {};
                     }
+
+                    self.current_text.appendSlice(text) catch {};
+                    if (self.current_words.items.len > 0) {
+                        const word = &self.current_words.items[self.current_words.items.len - 1];
+                        word.len = self.current_text.items.len -| word.start;
+                        word.end_ms = token.time_ms;
+                    }
+                    logprob_sum += token.logprob;
+                    word_tokens += 1;
+                }
+                self.finishWord(logprob_sum, word_tokens);
+                if (self.vocabulary) |vocabulary| {
+                    vocabulary.apply(&self.current_text, &self.current_words);
                 }
 
                 self.has_new_text = true;
@@ -61,9 +61,10 @@ pub const AudioCapture = struct {
     /// Initialize audio capture
     /// @param sample_rate: Sample rate in Hz (usually 16000 for speech)
     /// @param source: AudioSource.microphone or AudioSource.monitor
+    /// @param device_name: PulseAudio source name overriding the default microphone
     /// @param verbose: Enable verbose logging (unused for PulseAudio)
     /// Reference: LiveCaptions audiocap-pa.c - create_audio_thread_pa()
-    pub fn init(sample_rate: u32, source: AudioSource, verbose: bool) PulseError!Self {
+    pub fn init(sample_rate: u32, source: AudioSource, device_name: ?[:0]const u8, verbose: bool) PulseError!Self {
         _ = verbose; // Unused for PulseAudio
         const format = AudioFormat{ .sample_rate = sample_rate };
         var sample_spec = format.toPaSampleSpec();
@@ -71,9 +72,10 @@ pub const AudioCapture = struct {
         var err: c_int = 0;
 
         // For monitor source, we need to specify the device
-        // @default.monitor captures the default output device
+        // @DEFAULT_MONITOR@ captures the default output device; a chosen
+        // device only replaces the microphone
         const device: ?[*:0]const u8 = switch (source) {
-            .microphone => null, // Default microphone
+            .microphone => if (device_name) |name| name.ptr else null, // Default microphone
             .monitor => "@DEFAULT_MONITOR@", // System audio output
         };
 
@@ -86,7 +88,7 @@ pub const AudioCapture = struct {
             null, // Default server
             "zig-april-captions", // Application name
             c.PA_STREAM_RECORD, // Recording mode
-            device, // Device: null for mic, @DEFAULT_MONITOR@ for system audio
+            device, // Device: chosen source or null for mic, @DEFAULT_MONITOR@ for system audio
             stream_name, // Stream description
             &sample_spec,
             null, // Default channel map
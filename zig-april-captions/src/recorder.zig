@@ -0,0 +1,74 @@
+//! WAV recorder - writes the captured stream to disk for later re-transcription
+//!
+//! Audio is 16-bit mono PCM at the recognizer's sample rate. The RIFF sizes
+//! are patched about once a second so a crashed run still leaves a playable
+//! file.
+
+const std = @import("std");
+
+/// Header is 44 bytes: RIFF chunk, fmt chunk, data chunk header
+const HEADER_SIZE: u32 = 44;
+
+pub const WavRecorder = struct {
+    file: std.fs.File,
+    sample_rate: u32,
+    data_bytes: u32 = 0,
+    unpatched_bytes: u32 = 0,
+
+    const Self = @This();
+
+    /// Create (or truncate) the file at `path` and write a header
+    pub fn init(path: []const u8, sample_rate: u32) !Self {
+        const file = try std.fs.cwd().createFile(path, .{});
+        errdefer file.close();
+
+        var self = Self{ .file = file, .sample_rate = sample_rate };
+        try self.writeHeader();
+        return self;
+    }
+
+    fn writeHeader(self: *Self) !void {
+        var header: [HEADER_SIZE]u8 = undefined;
+        @memcpy(header[0..4], "RIFF");
+        std.mem.writeInt(u32, header[4..8], HEADER_SIZE - 8 + self.data_bytes, .little);
+        @memcpy(header[8..16], "WAVEfmt ");
+        std.mem.writeInt(u32, header[16..20], 16, .little); // fmt chunk size
+        std.mem.writeInt(u16, header[20..22], 1, .little); // PCM
+        std.mem.writeInt(u16, header[22..24], 1, .little); // mono
+        std.mem.writeInt(u32, header[24..28], self.sample_rate, .little);
+        std.mem.writeInt(u32, header[28..32], self.sample_rate * 2, .little); // byte rate
+        std.mem.writeInt(u16, header[32..34], 2, .little); // block align
+        std.mem.writeInt(u16, header[34..36], 16, .little); // bits per sample
+        @memcpy(header[36..40], "data");
+        std.mem.writeInt(u32, header[40..44], self.data_bytes, .little);
+
+        try self.file.pwriteAll(&header, 0);
+    }
+
+    /// Append samples; errors are reported once by the caller and recording stops
+    pub fn write(self: *Self, samples: []const i16) !void {
+        var bytes: [8192]u8 = undefined;
+        var rest = samples;
+        while (rest.len > 0) {
+            const n = @min(rest.len, bytes.len / 2);
+            for (rest[0..n], 0..) |sample, i| {
+                std.mem.writeInt(i16, bytes[i * 2 ..][0..2], sample, .little);
+            }
+            try self.file.pwriteAll(bytes[0 .. n * 2], HEADER_SIZE + self.data_bytes);
+            self.data_bytes += @intCast(n * 2);
+            rest = rest[n..];
+        }
+
+        self.unpatched_bytes += @intCast(samples.len * 2);
+        if (self.unpatched_bytes >= self.sample_rate * 2) {
+            self.unpatched_bytes = 0;
+            try self.writeHeader();
+        }
+    }
+
+    /// Finalize the header and close the file
+    pub fn deinit(self: *Self) void {
+        self.writeHeader() catch {};
+        self.file.close();
+    }
+};
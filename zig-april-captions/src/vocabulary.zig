@@ -0,0 +1,214 @@
+//! Custom vocabulary - jargon, names and acronyms passed with --vocabulary
+//!
+//! April models can't be biased towards extra words, so terms are applied to
+//! every result as it arrives, partials included: runs of up to MAX_SPAN
+//! words that spell a term once case, spacing and punctuation are ignored
+//! ("git hub" -> "GitHub"), or that spell one of its sound-alikes ("jig" ->
+//! "Zig"), are replaced by the term as written.
+//!
+//! File format: one term per line, optionally followed by tab-separated
+//! sound-alikes, e.g. "Zig\tjig\tzigg".
+
+const std = @import("std");
+const Word = @import("processor.zig").Word;
+
+/// Longest run of words matched against one term
+const MAX_SPAN: usize = 3;
+
+/// Longest matching key, in bytes; longer spellings are ignored
+const MAX_KEY: usize = 256;
+
+/// Largest vocabulary file read
+const MAX_FILE_SIZE: usize = 1 << 20;
+
+const Entry = struct {
+    key: []const u8, // Lowercase letters and digits of the spelling
+    term: []const u8, // Slice of the file contents
+};
+
+const Match = struct {
+    span: usize,
+    term: []const u8,
+};
+
+/// Append the lowercase letters and digits of `text` to `buf[len..]`;
+/// null if they don't fit
+fn appendKey(buf: *[MAX_KEY]u8, len: usize, text: []const u8) ?usize {
+    var end = len;
+    for (text) |c| {
+        // Bytes of multi-byte UTF-8 characters are kept as they are
+        if (!std.ascii.isAlphanumeric(c) and c < 0x80) continue;
+        if (end == buf.len) return null;
+        buf[end] = std.ascii.toLower(c);
+        end += 1;
+    }
+    return end;
+}
+
+/// Length of the punctuation before the first letter or digit of `text`
+fn leadingLen(text: []const u8) usize {
+    for (text, 0..) |c, i| {
+        if (std.ascii.isAlphanumeric(c) or c >= 0x80) return i;
+    }
+    return text.len;
+}
+
+/// Length of the punctuation after the last letter or digit of `text`
+fn trailingLen(text: []const u8) usize {
+    var i = text.len;
+    while (i > 0) : (i -= 1) {
+        const c = text[i - 1];
+        if (std.ascii.isAlphanumeric(c) or c >= 0x80) return text.len - i;
+    }
+    return text.len;
+}
+
+pub const Vocabulary = struct {
+    allocator: std.mem.Allocator,
+    data: []u8,
+    entries: std.ArrayList(Entry),
+
+    const Self = @This();
+
+    pub fn load(allocator: std.mem.Allocator, path: []const u8) !Self {
+        const data = try std.fs.cwd().readFileAlloc(allocator, path, MAX_FILE_SIZE);
+        errdefer allocator.free(data);
+        var self = Self{
+            .allocator = allocator,
+            .data = data,
+            .entries = std.ArrayList(Entry).init(allocator),
+        };
+        errdefer self.freeEntries();
+
+        var lines = std.mem.splitScalar(u8, data, '\n');
+        while (lines.next()) |line| {
+            var fields = std.mem.splitScalar(u8, std.mem.trimRight(u8, line, "\r"), '\t');
+            const term = std.mem.trim(u8, fields.first(), " ");
+            if (term.len == 0) continue;
+            try self.add(term, term);
+            while (fields.next()) |variant| {
+                try self.add(variant, term);
+            }
+        }
+        return self;
+    }
+
+    fn add(self: *Self, spelling: []const u8, term: []const u8) !void {
+        var buf: [MAX_KEY]u8 = undefined;
+        const len = appendKey(&buf, 0, spelling) orelse return;
+        if (len == 0) return;
+        const key = try self.allocator.dupe(u8, buf[0..len]);
+        errdefer self.allocator.free(key);
+        try self.entries.append(.{ .key = key, .term = term });
+    }
+
+    fn freeEntries(self: *Self) void {
+        for (self.entries.items) |entry| {
+            self.allocator.free(entry.key);
+        }
+        self.entries.deinit();
+    }
+
+    pub fn deinit(self: *Self) void {
+        self.freeEntries();
+        self.allocator.free(self.data);
+    }
+
+    /// Longest run of words at the start of `words` that spells a term
+    fn find(self: *const Self, text: []const u8, words: []const Word) ?Match {
+        var span = @min(MAX_SPAN, words.len);
+        while (span > 0) : (span -= 1) {
+            var buf: [MAX_KEY]u8 = undefined;
+            var len: usize = 0;
+            const spelled = for (words[0..span]) |word| {
+                const end = appendKey(&buf, len, text[word.start..][0..word.len]) orelse break false;
+                if (end == len) break false;
+                len = end;
+            } else true;
+            if (!spelled) continue;
+            for (self.entries.items) |entry| {
+                if (std.mem.eql(u8, entry.key, buf[0..len])) {
+                    return .{ .span = span, .term = entry.term };
+                }
+            }
+        }
+        return null;
+    }
+
+    /// Replace misrecognized terms in `text`, moving `words` along with it. A
+    /// replaced run becomes one word spanning the run's timing. Left unchanged
+    /// if memory runs out.
+    pub fn apply(self: *const Self, text: *std.ArrayList(u8), words: *std.ArrayList(Word)) void {
+        if (self.entries.items.len == 0 or words.items.len == 0) return;
+
+        var out = std.ArrayList(u8).init(text.allocator);
+        defer out.deinit();
+        var out_words = std.ArrayList(Word).init(words.allocator);
+        defer out_words.deinit();
+
+        var copied: usize = 0; // Bytes of `text` already in `out`
+        var i: usize = 0;
+        while (i < words.items.len) {
+            const first = words.items[i];
+            if (self.find(text.items, words.items[i..])) |match| {
+                const last = words.items[i + match.span - 1];
+                const start = first.start + leadingLen(text.items[first.start..][0..first.len]);
+                const end = last.start + last.len - trailingLen(text.items[last.start..][0..last.len]);
+                out.appendSlice(text.items[copied..start]) catch return;
+                var confidence = first.confidence;
+                for (words.items[i .. i + match.span]) |word| {
+                    confidence = @min(confidence, word.confidence);
+                }
+                out_words.append(.{
+                    .start = out.items.len,
+                    .len = match.term.len,
+                    .start_ms = first.start_ms,
+                    .end_ms = last.end_ms,
+                    .confidence = confidence,
+                }) catch return;
+                out.appendSlice(match.term) catch return;
+                copied = end;
+                i += match.span;
+            } else {
+                out.appendSlice(text.items[copied..first.start]) catch return;
+                var moved = first;
+                moved.start = out.items.len;
+                out_words.append(moved) catch return;
+                out.appendSlice(text.items[first.start..][0..first.len]) catch return;
+                copied = first.start + first.len;
+                i += 1;
+            }
+        }
+        out.appendSlice(text.items[copied..]) catch return;
+
+        std.mem.swap(std.ArrayList(u8), text, &out);
+        std.mem.swap(std.ArrayList(Word), words, &out_words);
+    }
+};
+
+test "vocabulary replaces spelled-out terms and sound-alikes" {
+    const allocator = std.testing.allocator;
+    var dir = std.testing.tmpDir(.{});
+    defer dir.cleanup();
+    try dir.dir.writeFile(.{ .sub_path = "vocabulary.txt", .data = "GitHub\nZig\tjig\n" });
+    const path = try dir.dir.realpathAlloc(allocator, "vocabulary.txt");
+    defer allocator.free(path);
+
+    var vocabulary = try Vocabulary.load(allocator, path);
+    defer vocabulary.deinit();
+
+    var text = std.ArrayList(u8).init(allocator);
+    defer text.deinit();
+    var words = std.ArrayList(Word).init(allocator);
+    defer words.deinit();
+    try text.appendSlice(" PUSH TO GIT HUB, JIG");
+    for ([_][2]usize{ .{ 1, 4 }, .{ 6, 2 }, .{ 9, 3 }, .{ 13, 4 }, .{ 18, 3 } }, 0..) |span, n| {
+        try words.append(.{ .start = span[0], .len = span[1], .start_ms = n * 100, .end_ms = n * 100 + 90, .confidence = 0.9 });
+    }
+
+    vocabulary.apply(&text, &words);
+    try std.testing.expectEqualStrings(" PUSH TO GitHub, Zig", text.items);
+    try std.testing.expectEqual(@as(usize, 4), words.items.len);
+    try std.testing.expectEqualStrings("GitHub", text.items[words.items[2].start..][0..words.items[2].len]);
+    try std.testing.expectEqual(@as(usize, 390), words.items[2].end_ms);
+}
@@ -0,0 +1,88 @@
+//! WAV reader - feeds a recorded file to the recognizer instead of a device
+//!
+//! Only 16-bit mono PCM is accepted, which is what WavRecorder writes. Other
+//! files need converting first, e.g. `ffmpeg -i in.mp3 -ac 1 -ar 16000 out.wav`.
+
+const std = @import("std");
+
+pub const WavReader = struct {
+    file: std.fs.File,
+    sample_rate: u32,
+    total_samples: usize,
+    samples_read: usize = 0,
+
+    const Self = @This();
+
+    /// Open `path` and position it at the start of the sample data
+    pub fn init(path: []const u8) !Self {
+        const file = try std.fs.cwd().openFile(path, .{});
+        errdefer file.close();
+        const reader = file.reader();
+
+        var riff: [12]u8 = undefined;
+        try reader.readNoEof(&riff);
+        if (!std.mem.eql(u8, riff[0..4], "RIFF") or !std.mem.eql(u8, riff[8..12], "WAVE")) {
+            return error.NotWav;
+        }
+
+        // Walk the chunks until "data", checking "fmt " on the way
+        var sample_rate: ?u32 = null;
+        while (true) {
+            var header: [8]u8 = undefined;
+            try reader.readNoEof(&header);
+            const size = std.mem.readInt(u32, header[4..8], .little);
+
+            if (std.mem.eql(u8, header[0..4], "fmt ")) {
+                if (size < 16) return error.InvalidWav;
+                var fmt: [16]u8 = undefined;
+                try reader.readNoEof(&fmt);
+                const format = std.mem.readInt(u16, fmt[0..2], .little);
+                const channels = std.mem.readInt(u16, fmt[2..4], .little);
+                const bits = std.mem.readInt(u16, fmt[14..16], .little);
+                if (format != 1 or channels != 1 or bits != 16) return error.UnsupportedWavFormat;
+                sample_rate = std.mem.readInt(u32, fmt[4..8], .little);
+                try file.seekBy(@intCast(size - 16 + size % 2));
+            } else if (std.mem.eql(u8, header[0..4], "data")) {
+                const rate = sample_rate orelse return error.InvalidWav;
+                // A recording cut short may still carry a zero data size
+                const remaining = (try file.getEndPos()) - (try file.getPos());
+                const data_bytes = if (size == 0) remaining else @min(size, remaining);
+                return Self{
+                    .file = file,
+                    .sample_rate = rate,
+                    .total_samples = @intCast(data_bytes / 2),
+                };
+            } else {
+                try file.seekBy(@intCast(size + size % 2)); // Chunks are word aligned
+            }
+        }
+    }
+
+    /// Fill `buffer` with the next samples; an empty slice means end of file
+    pub fn read(self: *Self, buffer: []i16) ![]i16 {
+        const wanted = @min(buffer.len, self.total_samples - self.samples_read);
+        var bytes: [8192]u8 = undefined;
+        var filled: usize = 0;
+        while (filled < wanted) {
+            const n = @min(wanted - filled, bytes.len / 2);
+            const got = try self.file.reader().readAll(bytes[0 .. n * 2]);
+            const samples = got / 2;
+            for (0..samples) |i| {
+                buffer[filled + i] = std.mem.readInt(i16, bytes[i * 2 ..][0..2], .little);
+            }
+            filled += samples;
+            if (got < n * 2) break;
+        }
+        self.samples_read += filled;
+        return buffer[0..filled];
+    }
+
+    /// Position in the file, in milliseconds
+    pub fn positionMs(self: *const Self) i64 {
+        return @intCast(self.samples_read * 1000 / self.sample_rate);
+    }
+
+    pub fn deinit(self: *Self) void {
+        self.file.close();
+    }
+};